@@ -0,0 +1,386 @@
+//! `ads`: build, extend and prove statements about an MMR persisted in a directory.
+//!
+//! Inputs are newline-delimited records, one entry per line, read from a file or stdin. The store
+//! is a directory with the node hashes in `nodes/` and the entries in `entries/`, both
+//! `FileStore`s. Proofs and commitments are written as JSON, or as BCS with `--format binary`.
+
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use ads_store::file::FileStore;
+use ads_verifier::{Digest, MmrCommitment, MmrInclusionProof, MostRecentNElementsProof};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use merkle_forests::store::StoredMmr;
+use serde::{Deserialize, Serialize};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+type Store = StoredMmr<FileStore<Digest>, FileStore<Vec<u8>>>;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "ads",
+    about = "Authenticated logs on top of Merkle mountain ranges"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Create a store from newline-delimited records
+    Build {
+        store: PathBuf,
+        /// Records to add, stdin if omitted
+        input: Option<PathBuf>,
+    },
+    /// Append newline-delimited records to an existing store
+    Append {
+        store: PathBuf,
+        /// Records to add, stdin if omitted
+        input: Option<PathBuf>,
+    },
+    /// Print the commitment of a store
+    Root {
+        store: PathBuf,
+        #[arg(long, value_enum, default_value_t = RootFormat::Text)]
+        format: RootFormat,
+    },
+    /// Prove the last entries of a store, or a single entry
+    Prove {
+        store: PathBuf,
+        #[command(flatten)]
+        query: Query,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// Where to write the proof, stdout if omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Verify a proof against a store or a commitment
+    Verify {
+        proof: PathBuf,
+        #[command(flatten)]
+        against: Against,
+        /// Format of both the proof and the commitment
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// Print the known-answer test vectors, as JSON
+    Vectors {
+        /// Where to write the vectors, stdout if omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct Query {
+    /// Prove the n most recent entries
+    #[arg(long)]
+    last_n: Option<u64>,
+    /// Prove the entry at this index
+    #[arg(long)]
+    index: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct Against {
+    /// Store to read the commitment from
+    #[arg(long)]
+    store: Option<PathBuf>,
+    /// Commitment written by `ads root --format json`
+    #[arg(long)]
+    commitment: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RootFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    /// BCS
+    Binary,
+}
+
+/// Any proof the tool emits, tagged so that `ads verify` knows which one it reads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Proof {
+    MostRecent(MostRecentNElementsProof),
+    Inclusion(MmrInclusionProof),
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    match cli.command {
+        Command::Build { store, input } => {
+            let mut mmr = open(&store)?;
+            if mmr.num_entries() != 0 {
+                return Err(format!("{} already has entries", store.display()).into());
+            }
+            append(&mut mmr, input.as_deref())
+        }
+        Command::Append { store, input } => {
+            if !store.is_dir() {
+                return Err(format!("no store at {}", store.display()).into());
+            }
+            append(&mut open(&store)?, input.as_deref())
+        }
+        Command::Root { store, format } => {
+            let mmr = open(&store)?;
+            match format {
+                RootFormat::Text => {
+                    println!("entries: {}", mmr.num_entries());
+                    println!(
+                        "commitment: {}",
+                        MmrCommitment::from_digests(&mmr.digests()?)
+                    );
+                    match mmr.standard_root()? {
+                        Some(root) => println!("root: {}", root),
+                        None => println!("root: none"),
+                    }
+                    for peak in mmr.standard_peaks()? {
+                        println!("peak: {}", peak);
+                    }
+                }
+                RootFormat::Json => {
+                    let commitment = MmrCommitment::from_digests(&mmr.digests()?);
+                    println!("{}", serde_json::to_string_pretty(&commitment)?);
+                }
+            }
+            Ok(())
+        }
+        Command::Prove {
+            store,
+            query,
+            format,
+            output,
+        } => {
+            let mmr = open(&store)?;
+            let proof = prove(&mmr, &query)?;
+            let bytes = encode(&proof, format)?;
+            match output {
+                Some(path) => fs::write(path, bytes)?,
+                None => io::stdout().write_all(&bytes)?,
+            }
+            Ok(())
+        }
+        Command::Verify {
+            proof,
+            against,
+            format,
+        } => {
+            let proof: Proof = decode(&fs::read(proof)?, format)?;
+            let commitment = match (against.store, against.commitment) {
+                (Some(store), _) => MmrCommitment::from_digests(&open(&store)?.digests()?),
+                (None, Some(path)) => decode(&fs::read(path)?, format)?,
+                (None, None) => unreachable!("clap requires one of --store and --commitment"),
+            };
+            verify(&commitment, &proof)?;
+            println!("OK");
+            Ok(())
+        }
+        Command::Vectors { output } => {
+            let bytes = encode(&merkle_forests::vectors::generate(), Format::Json)?;
+            match output {
+                Some(path) => fs::write(path, bytes)?,
+                None => io::stdout().write_all(&bytes)?,
+            }
+            Ok(())
+        }
+    }
+}
+
+fn open(dir: &Path) -> Result<Store> {
+    let nodes = FileStore::open(dir.join("nodes"))?;
+    let entries = FileStore::open(dir.join("entries"))?;
+    Ok(StoredMmr::open(nodes, entries)?)
+}
+
+fn append(mmr: &mut Store, input: Option<&Path>) -> Result<()> {
+    let data = match input {
+        Some(path) => fs::read(path)?,
+        None => {
+            let mut data = vec![];
+            io::stdin().read_to_end(&mut data)?;
+            data
+        }
+    };
+    for record in records(&data) {
+        mmr.append(record)?;
+    }
+    mmr.flush()?;
+    Ok(())
+}
+
+// One record per line; the newline terminating the last record is optional
+fn records(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let data = data.strip_suffix(b"\n").unwrap_or(data);
+    data.split(|&b| b == b'\n')
+        .filter(move |_| !data.is_empty())
+}
+
+fn prove(mmr: &Store, query: &Query) -> Result<Proof> {
+    let num_entries = mmr.num_entries();
+    match (query.last_n, query.index) {
+        (Some(n), _) => {
+            if n == 0 || n > num_entries {
+                return Err(format!("cannot prove {} of {} entries", n, num_entries).into());
+            }
+            Ok(Proof::MostRecent(mmr.prove_most_recent_n_elements(n)?))
+        }
+        (None, Some(index)) => {
+            if index >= num_entries {
+                return Err(format!("no entry {} in {} entries", index, num_entries).into());
+            }
+            Ok(Proof::Inclusion(mmr.prove_inclusion(index)?))
+        }
+        (None, None) => unreachable!("clap requires one of --last-n and --index"),
+    }
+}
+
+fn verify(commitment: &MmrCommitment, proof: &Proof) -> Result<()> {
+    match proof {
+        Proof::MostRecent(proof) => commitment.verify_most_recent_n_elements(proof)?,
+        Proof::Inclusion(proof) => commitment.verify_inclusion(proof)?,
+    }
+    Ok(())
+}
+
+fn encode<T: Serialize>(value: &T, format: Format) -> Result<Vec<u8>> {
+    Ok(match format {
+        Format::Json => {
+            let mut bytes = serde_json::to_vec_pretty(value)?;
+            bytes.push(b'\n');
+            bytes
+        }
+        Format::Binary => bcs::to_bytes(value)?,
+    })
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(bytes: &[u8], format: Format) -> Result<T> {
+    Ok(match format {
+        Format::Json => serde_json::from_slice(bytes)?,
+        Format::Binary => bcs::from_bytes(bytes)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ads-cli-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_records() {
+        let split = |data: &'static [u8]| records(data).collect::<Vec<_>>();
+        assert!(split(b"").is_empty());
+        assert_eq!(split(b"a"), vec![b"a"]);
+        assert_eq!(split(b"a\n"), vec![b"a"]);
+        assert_eq!(split(b"a\n\nb\n"), vec![&b"a"[..], b"", b"b"]);
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let dir = temp_dir("store");
+        let input = dir.with_extension("txt");
+        fs::write(&input, b"a\nb\nc\nd\ne\n").unwrap();
+        run(Cli::parse_from([
+            "ads",
+            "build",
+            dir.to_str().unwrap(),
+            input.to_str().unwrap(),
+        ]))
+        .unwrap();
+        fs::write(&input, b"f\ng").unwrap();
+        run(Cli::parse_from([
+            "ads",
+            "append",
+            dir.to_str().unwrap(),
+            input.to_str().unwrap(),
+        ]))
+        .unwrap();
+        assert!(run(Cli::parse_from([
+            "ads",
+            "build",
+            dir.to_str().unwrap(),
+            input.to_str().unwrap()
+        ]))
+        .is_err());
+
+        let mmr = open(&dir).unwrap();
+        assert_eq!(mmr.num_entries(), 7);
+        let commitment = MmrCommitment::from_digests(&mmr.digests().unwrap());
+        for format in [Format::Json, Format::Binary] {
+            for query in [
+                Query {
+                    last_n: Some(3),
+                    index: None,
+                },
+                Query {
+                    last_n: None,
+                    index: Some(1),
+                },
+            ] {
+                let proof = prove(&mmr, &query).unwrap();
+                let decoded: Proof = decode(&encode(&proof, format).unwrap(), format).unwrap();
+                assert_eq!(decoded, proof);
+                verify(&commitment, &decoded).unwrap();
+            }
+        }
+        assert!(prove(
+            &mmr,
+            &Query {
+                last_n: Some(8),
+                index: None
+            }
+        )
+        .is_err());
+        assert!(prove(
+            &mmr,
+            &Query {
+                last_n: None,
+                index: Some(7)
+            }
+        )
+        .is_err());
+
+        let Proof::Inclusion(mut proof) = prove(
+            &mmr,
+            &Query {
+                last_n: None,
+                index: Some(1),
+            },
+        )
+        .unwrap() else {
+            unreachable!()
+        };
+        proof.entry = b"x".to_vec();
+        assert!(verify(&commitment, &Proof::Inclusion(proof)).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_file(&input).unwrap();
+    }
+}
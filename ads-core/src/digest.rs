@@ -0,0 +1,160 @@
+//! Fixed-size node hashes.
+//!
+//! Digests print and parse as lowercase hex, and serialize as hex strings in human-readable
+//! formats (JSON, TOML) and as raw bytes otherwise (BCS), so binary encodings are unchanged.
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::error::ParseDigestError;
+
+pub const DIGEST_LENGTH: usize = 32;
+
+/// A 32-byte hash of a leaf, an internal node or a skip list node.
+///
+/// Digests are compared in constant time so that checking a recomputed root doesn't leak how many
+/// leading bytes matched.
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Digest(pub [u8; DIGEST_LENGTH]);
+
+impl Digest {
+    pub fn new(bytes: [u8; DIGEST_LENGTH]) -> Self {
+        Digest(bytes)
+    }
+
+    /// Returns None if `bytes` isn't exactly `DIGEST_LENGTH` long.
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        Some(Digest(bytes.try_into().ok()?))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; DIGEST_LENGTH] {
+        &self.0
+    }
+}
+
+impl PartialEq for Digest {
+    fn eq(&self, other: &Self) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+    }
+}
+
+impl Eq for Digest {}
+
+impl Hash for Digest {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; DIGEST_LENGTH]> for Digest {
+    fn from(bytes: [u8; DIGEST_LENGTH]) -> Self {
+        Digest(bytes)
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest({})", self)
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&Hex(&self.0), f)
+    }
+}
+
+impl FromStr for Digest {
+    type Err = ParseDigestError;
+
+    /// Parse the hex form printed by `Display`. Upper case digits are accepted too.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.as_bytes();
+        if s.len() != 2 * DIGEST_LENGTH {
+            return Err(ParseDigestError::InvalidLength(s.len()));
+        }
+        let digit = |offset: usize| {
+            (s[offset] as char)
+                .to_digit(16)
+                .map(|d| d as u8)
+                .ok_or(ParseDigestError::InvalidCharacter(offset))
+        };
+        let mut bytes = [0; DIGEST_LENGTH];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = digit(2 * i)? << 4 | digit(2 * i + 1)?;
+        }
+        Ok(Digest(bytes))
+    }
+}
+
+impl Serialize for Digest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            // Same as the derived impl, i.e., the bare bytes in BCS
+            serializer.serialize_newtype_struct("Digest", &self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Digest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(HexVisitor)
+        } else {
+            Ok(Digest(<[u8; DIGEST_LENGTH]>::deserialize(deserializer)?))
+        }
+    }
+}
+
+struct HexVisitor;
+
+impl Visitor<'_> for HexVisitor {
+    type Value = Digest;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} hex digits", 2 * DIGEST_LENGTH)
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Digest, E> {
+        s.parse().map_err(E::custom)
+    }
+}
+
+/// Displays arbitrary bytes, e.g. entries, as lowercase hex.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Hex<'a>(pub &'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,95 @@
+//! Canonical byte encodings of what gets hashed and of what proofs are parsed from.
+//!
+//! The encodings are BCS, written and read by hand so that hot paths and `no_std` verifiers don't
+//! go through serde: every value has exactly one accepted encoding.
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+
+/// Longest BCS sequence length encoding.
+pub const MAX_ULEB128_LENGTH: usize = 5;
+
+/// First byte of the message hashed for a leaf. Together with `NODE_PREFIX`, it keeps a leaf whose
+/// value is the encoding of two children from hashing like an internal node.
+pub const LEAF_PREFIX: u8 = 0x00;
+
+/// First byte of the message hashed for an internal node.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// Length of the message hashed for an internal node.
+pub const ENCODED_CHILDREN_LENGTH: usize = 1 + 2 * (1 + DIGEST_LENGTH);
+
+/// The message hashed for an internal node, i.e., `NODE_PREFIX` followed by the BCS encoding of
+/// its children's hashes: each child prefixed by its length as a ULEB128 byte.
+pub fn encode_children(left: &Digest, right: &Digest) -> [u8; ENCODED_CHILDREN_LENGTH] {
+    let mut encoded = [0; ENCODED_CHILDREN_LENGTH];
+    encoded[0] = NODE_PREFIX;
+    for (chunk, child) in encoded[1..]
+        .chunks_exact_mut(1 + DIGEST_LENGTH)
+        .zip([left, right])
+    {
+        chunk[0] = DIGEST_LENGTH as u8;
+        chunk[1..].copy_from_slice(&child.0);
+    }
+    encoded
+}
+
+/// Read a little-endian `u64` at the start of `bytes`, returning it and the rest.
+pub fn read_u64(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    if bytes.len() < 8 {
+        return None;
+    }
+    let (value, rest) = bytes.split_at(8);
+    Some((u64::from_le_bytes(value.try_into().unwrap()), rest))
+}
+
+/// Encode a BCS sequence length, which must be below 2^31. Returns the buffer and how many of its
+/// bytes the encoding uses.
+pub fn encode_uleb128(value: usize) -> ([u8; MAX_ULEB128_LENGTH], usize) {
+    assert!(value < 1 << 31, "Length {} too large for BCS", value);
+    let mut encoded = [0; MAX_ULEB128_LENGTH];
+    let mut value = value;
+    let mut len = 0;
+    loop {
+        encoded[len] = (value & 0x7f) as u8;
+        value >>= 7;
+        len += 1;
+        if value == 0 {
+            return (encoded, len);
+        }
+        encoded[len - 1] |= 0x80;
+    }
+}
+
+/// Length of the BCS encoding of a sequence length.
+pub fn uleb128_length(value: usize) -> usize {
+    encode_uleb128(value).1
+}
+
+/// Length of the BCS encoding of a byte vector of `len` bytes.
+pub fn bytes_length(len: usize) -> usize {
+    uleb128_length(len) + len
+}
+
+/// Length of the BCS encoding of a vector of `count` digests.
+pub fn digests_length(count: usize) -> usize {
+    uleb128_length(count) + count * DIGEST_LENGTH
+}
+
+/// Read a BCS sequence length at the start of `bytes`, returning it and the rest.
+///
+/// Same rules as BCS: the encoding must be canonical (no trailing zero digits) and the length at
+/// most 2^31 - 1.
+pub fn read_uleb128(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    let mut value: u64 = 0;
+    for (i, &byte) in bytes.iter().enumerate().take(MAX_ULEB128_LENGTH) {
+        let digit = byte & 0x7f;
+        value |= u64::from(digit) << (7 * i);
+        if digit == byte {
+            if i > 0 && digit == 0 {
+                return None;
+            }
+            return (value < 1 << 31).then_some((value as usize, &bytes[i + 1..]));
+        }
+    }
+    None
+}
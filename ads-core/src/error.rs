@@ -0,0 +1,41 @@
+//! Errors shared by the crates of this workspace.
+
+use core::fmt;
+
+use crate::digest::DIGEST_LENGTH;
+
+/// Why a string isn't a digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDigestError {
+    /// The string isn't `2 * DIGEST_LENGTH` characters long
+    InvalidLength(usize),
+    /// The character at this byte offset isn't a hex digit
+    InvalidCharacter(usize),
+}
+
+impl fmt::Display for ParseDigestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseDigestError::InvalidLength(len) => {
+                write!(f, "expected {} hex digits, got {}", 2 * DIGEST_LENGTH, len)
+            }
+            ParseDigestError::InvalidCharacter(offset) => {
+                write!(f, "invalid hex digit at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseDigestError {}
+
+/// Why a string isn't the name of a hash algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownHashAlgo;
+
+impl fmt::Display for UnknownHashAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown hash algorithm")
+    }
+}
+
+impl core::error::Error for UnknownHashAlgo {}
@@ -0,0 +1,297 @@
+//! The hash function and the leaf and internal node hashes built on it.
+//!
+//! A leaf is the Blake2b256 of `LEAF_PREFIX` followed by its value. An internal node is the
+//! Blake2b256 of `NODE_PREFIX` followed by the BCS encoding of its children's hashes (see
+//! `encoding::encode_children`). A structure without any leaf commits to `EMPTY_ROOT`.
+//!
+//! A `NodeHasher` can instead key both with a secret, so that a structure over guessable values
+//! doesn't let anyone without the key test a guess against its digests, or use another `HashAlgo`
+//! in place of Blake2b256, with the same encodings. Poseidon, which works on field elements rather
+//! than bytes, has encodings of its own (see `poseidon`).
+
+use core::fmt;
+use core::str::FromStr;
+
+use blake2::digest::consts::U32;
+use blake2::digest::Mac;
+use blake2::{Blake2b, Blake2bMac, Digest as _};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sha3::{Keccak256, Sha3_256};
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::encoding::{LEAF_PREFIX, NODE_PREFIX};
+use crate::error::UnknownHashAlgo;
+
+/// An incremental hash function producing `Digest`s.
+pub trait Hasher: Default {
+    fn update(&mut self, data: &[u8]);
+
+    fn finalize(self) -> Digest;
+
+    /// Hash a whole message at once.
+    fn digest(data: &[u8]) -> Digest {
+        let mut hasher = Self::default();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+/// Blake2b with a 32-byte output, the hash function of every structure in this workspace.
+#[derive(Debug, Clone, Default)]
+pub struct Blake2b256(Blake2b<U32>);
+
+impl Hasher for Blake2b256 {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Digest {
+        Digest(self.0.finalize().into())
+    }
+}
+
+/// A hash function with a 32-byte output that leaves and nodes can be hashed with. Commitments
+/// and proofs name theirs, so that a verifier can accept structures hashed with any of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum HashAlgo {
+    #[default]
+    Blake2b256,
+    Sha256,
+    Sha3_256,
+    /// Keccak256 as in Ethereum, i.e., SHA-3 before its final padding change
+    Keccak256,
+    /// Poseidon over the BN254 scalar field, cheap to verify in SNARK circuits
+    #[cfg(feature = "poseidon")]
+    Poseidon,
+}
+
+impl HashAlgo {
+    #[cfg(not(feature = "poseidon"))]
+    pub const ALL: &'static [HashAlgo] = &[
+        HashAlgo::Blake2b256,
+        HashAlgo::Sha256,
+        HashAlgo::Sha3_256,
+        HashAlgo::Keccak256,
+    ];
+    #[cfg(feature = "poseidon")]
+    pub const ALL: &'static [HashAlgo] = &[
+        HashAlgo::Blake2b256,
+        HashAlgo::Sha256,
+        HashAlgo::Sha3_256,
+        HashAlgo::Keccak256,
+        HashAlgo::Poseidon,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgo::Blake2b256 => "blake2b256",
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Sha3_256 => "sha3-256",
+            HashAlgo::Keccak256 => "keccak256",
+            #[cfg(feature = "poseidon")]
+            HashAlgo::Poseidon => "poseidon-bn254",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgo {
+    type Err = UnknownHashAlgo;
+
+    fn from_str(s: &str) -> Result<Self, UnknownHashAlgo> {
+        HashAlgo::ALL
+            .iter()
+            .copied()
+            .find(|algo| algo.name() == s)
+            .ok_or(UnknownHashAlgo)
+    }
+}
+
+/// The root of an empty structure, and the digest of a missing tree in an MMR: all zeros, which
+/// is the hash of no known leaf or node, unlike the hash of an empty value.
+pub const EMPTY_ROOT: Digest = Digest([0; DIGEST_LENGTH]);
+
+/// Length of the secret key of keyed hashing.
+pub const KEY_LENGTH: usize = 32;
+
+/// How leaves and internal nodes are hashed: with a `HashAlgo`, Blake2b256 by default, or with
+/// Blake2b256 keyed by a secret (Blake2b's own keyed mode, not HMAC). A structure and the proofs
+/// about it must be hashed the same way, so the key is as much an input of verification as the
+/// commitment.
+///
+/// Every structure defaults to `UNKEYED` and takes a hasher through its `_with_hasher`
+/// constructor: perfect trees, in-memory, lazy and stored MMRs, the peaks-only appender and skip
+/// lists. Peaks are bagged into a conventional-layout root with the same hasher as the nodes below
+/// them.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeHasher {
+    algo: HashAlgo,
+    // Only with Blake2b256
+    key: Option<[u8; KEY_LENGTH]>,
+}
+
+impl NodeHasher {
+    /// Plain Blake2b256, as `hash_leaf` and `hash_children`.
+    pub const UNKEYED: NodeHasher = NodeHasher {
+        algo: HashAlgo::Blake2b256,
+        key: None,
+    };
+
+    /// Hashing with `algo`, unkeyed.
+    pub fn new(algo: HashAlgo) -> Self {
+        NodeHasher { algo, key: None }
+    }
+
+    /// Hashing with Blake2b256 keyed by `key`.
+    pub fn keyed(key: [u8; KEY_LENGTH]) -> Self {
+        NodeHasher {
+            algo: HashAlgo::Blake2b256,
+            key: Some(key),
+        }
+    }
+
+    pub fn algo(&self) -> HashAlgo {
+        self.algo
+    }
+
+    pub fn key(&self) -> Option<&[u8; KEY_LENGTH]> {
+        self.key.as_ref()
+    }
+
+    pub fn is_keyed(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Hash of a leaf, i.e., the (keyed) hash of `LEAF_PREFIX` followed by its value.
+    pub fn hash_leaf(&self, value: &[u8]) -> Digest {
+        self.hash(&[&[LEAF_PREFIX], value])
+    }
+
+    /// Hash of an internal node. The encoding is streamed into the hasher instead of being
+    /// allocated.
+    pub fn hash_children(&self, left: &Digest, right: &Digest) -> Digest {
+        #[cfg(feature = "poseidon")]
+        if self.algo == HashAlgo::Poseidon {
+            return crate::poseidon::hash_children(left, right);
+        }
+        // BCS length prefix (ULEB128), a single byte for 32
+        let prefix = [DIGEST_LENGTH as u8];
+        self.hash(&[&[NODE_PREFIX], &prefix, &left.0, &prefix, &right.0])
+    }
+
+    /// Whether `digest` is one this hasher can output. Any 32 bytes are, except with Poseidon,
+    /// whose digests must encode a field element canonically: `hash_children` reduces its inputs,
+    /// so a digest at or above the modulus would hash like the digest of its remainder.
+    #[cfg_attr(not(feature = "poseidon"), allow(unused_variables))]
+    pub fn is_canonical(&self, digest: &Digest) -> bool {
+        #[cfg(feature = "poseidon")]
+        if self.algo == HashAlgo::Poseidon {
+            return crate::poseidon::to_canonical_field(digest).is_some();
+        }
+        true
+    }
+
+    /// The (keyed) hash of a whole message, e.g., one built by `encoding::encode_children`.
+    pub fn hash_message(&self, message: &[u8]) -> Digest {
+        self.hash(&[message])
+    }
+
+    /// An incremental hasher of a leaf value, e.g., one too large to hold in memory.
+    pub fn leaf_hasher(&self) -> LeafHasher {
+        let mut hasher = self.message_hasher();
+        hasher.update(&[LEAF_PREFIX]);
+        hasher
+    }
+
+    /// An incremental hasher of a whole message, as `hash_message`.
+    pub fn message_hasher(&self) -> LeafHasher {
+        LeafHasher(match (&self.key, self.algo) {
+            (Some(key), _) => LeafHasherInner::Keyed(
+                Blake2bMac::new_from_slice(key).expect("Blake2b takes keys of up to 64 bytes"),
+            ),
+            (None, HashAlgo::Blake2b256) => LeafHasherInner::Unkeyed(Blake2b256::default()),
+            (None, HashAlgo::Sha256) => LeafHasherInner::Sha256(Sha256::default()),
+            (None, HashAlgo::Sha3_256) => LeafHasherInner::Sha3_256(Sha3_256::default()),
+            (None, HashAlgo::Keccak256) => LeafHasherInner::Keccak256(Keccak256::default()),
+            #[cfg(feature = "poseidon")]
+            (None, HashAlgo::Poseidon) => {
+                LeafHasherInner::Poseidon(crate::poseidon::LeafHasher::default())
+            }
+        })
+    }
+
+    fn hash(&self, parts: &[&[u8]]) -> Digest {
+        let mut hasher = self.message_hasher();
+        parts.iter().for_each(|part| hasher.update(part));
+        hasher.finalize()
+    }
+}
+
+/// Hashes a leaf value fed in pieces, as `NodeHasher::hash_leaf` of their concatenation.
+pub struct LeafHasher(LeafHasherInner);
+
+enum LeafHasherInner {
+    Unkeyed(Blake2b256),
+    Keyed(Blake2bMac<U32>),
+    Sha256(Sha256),
+    Sha3_256(Sha3_256),
+    Keccak256(Keccak256),
+    #[cfg(feature = "poseidon")]
+    Poseidon(crate::poseidon::LeafHasher),
+}
+
+impl LeafHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.0 {
+            LeafHasherInner::Unkeyed(hasher) => hasher.update(data),
+            LeafHasherInner::Keyed(mac) => mac.update(data),
+            LeafHasherInner::Sha256(hasher) => hasher.update(data),
+            LeafHasherInner::Sha3_256(hasher) => hasher.update(data),
+            LeafHasherInner::Keccak256(hasher) => hasher.update(data),
+            #[cfg(feature = "poseidon")]
+            LeafHasherInner::Poseidon(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> Digest {
+        match self.0 {
+            LeafHasherInner::Unkeyed(hasher) => hasher.finalize(),
+            LeafHasherInner::Keyed(mac) => Digest(mac.finalize().into_bytes().into()),
+            LeafHasherInner::Sha256(hasher) => Digest(hasher.finalize().into()),
+            LeafHasherInner::Sha3_256(hasher) => Digest(hasher.finalize().into()),
+            LeafHasherInner::Keccak256(hasher) => Digest(hasher.finalize().into()),
+            #[cfg(feature = "poseidon")]
+            LeafHasherInner::Poseidon(hasher) => hasher.finalize(),
+        }
+    }
+}
+
+// Never print the key
+impl fmt::Debug for NodeHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.key {
+            None => write!(f, "NodeHasher::Unkeyed({:?})", self.algo),
+            Some(_) => f.write_str("NodeHasher::Keyed(..)"),
+        }
+    }
+}
+
+/// Hash of a leaf, see `NodeHasher::hash_leaf`.
+pub fn hash_leaf(value: &[u8]) -> Digest {
+    NodeHasher::UNKEYED.hash_leaf(value)
+}
+
+/// Hash of an internal node, see `NodeHasher::hash_children`.
+pub fn hash_children(left: &Digest, right: &Digest) -> Digest {
+    NodeHasher::UNKEYED.hash_children(left, right)
+}
@@ -0,0 +1,24 @@
+//! Building blocks shared by every authenticated data structure of this workspace: the digest
+//! type, the hash function behind it and the canonical encodings of what gets hashed.
+//!
+//! Trees, MMRs and skip lists all commit with these, so their digests print, parse and serialize
+//! the same way. Like ads-verifier, this crate is `no_std`.
+
+#![no_std]
+
+pub mod digest;
+pub mod encoding;
+pub mod error;
+pub mod hash;
+pub mod memory;
+pub mod observer;
+#[cfg(feature = "poseidon")]
+pub mod poseidon;
+pub mod proof;
+
+pub use digest::{Digest, Hex, DIGEST_LENGTH};
+pub use error::{ParseDigestError, UnknownHashAlgo};
+pub use hash::{Blake2b256, HashAlgo, Hasher, NodeHasher, EMPTY_ROOT};
+pub use memory::MemoryUsage;
+pub use observer::AppendObserver;
+pub use proof::Proof;
@@ -0,0 +1,33 @@
+//! Estimates of the heap memory held by the data structures, in one breakdown shared by all of
+//! them so that usage can be compared and summed across structures.
+
+use core::ops::Add;
+
+/// Bytes used by a structure, broken down by what they hold.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryUsage {
+    /// Nodes, including their hashes but not what's counted as values or auxiliary
+    pub nodes: usize,
+    /// Entry payloads, or the values stored in nodes
+    pub values: usize,
+    /// Indexes, finger tables and other bookkeeping next to the nodes and values
+    pub auxiliary: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.nodes + self.values + self.auxiliary
+    }
+}
+
+impl Add for MemoryUsage {
+    type Output = MemoryUsage;
+
+    fn add(self, other: MemoryUsage) -> MemoryUsage {
+        MemoryUsage {
+            nodes: self.nodes + other.nodes,
+            values: self.values + other.values,
+            auxiliary: self.auxiliary + other.auxiliary,
+        }
+    }
+}
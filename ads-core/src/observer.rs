@@ -0,0 +1,17 @@
+//! Hooks into appends, e.g. for replication, write-ahead log shipping or external indexing.
+
+use crate::digest::Digest;
+
+/// Notified of every append to a structure committed to by a `C`, such as an MMR commitment or a
+/// skip list head.
+pub trait AppendObserver<C> {
+    /// `index` counts appends from 0, `leaf_hash` is the hash the new entry is committed as and
+    /// `commitment` the commitment right after the append.
+    fn on_append(&mut self, index: u64, leaf_hash: &Digest, commitment: &C);
+}
+
+impl<C, F: FnMut(u64, &Digest, &C)> AppendObserver<C> for F {
+    fn on_append(&mut self, index: u64, leaf_hash: &Digest, commitment: &C) {
+        self(index, leaf_hash, commitment)
+    }
+}
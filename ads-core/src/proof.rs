@@ -0,0 +1,29 @@
+//! The interface shared by the proofs of every structure of this workspace.
+
+use serde::Serialize;
+
+/// A proof, whatever structure it's about, so that middleware (caches, metrics, transports) can
+/// handle proofs generically.
+///
+/// Proofs serialize with serde; their canonical encoding is BCS, whose length `size_hint` gives
+/// without encoding.
+pub trait Proof: Serialize {
+    /// What the proof is checked against, e.g. a root or an MMR commitment.
+    type Commitment: ?Sized;
+    /// What the proof claims beyond what it carries, e.g. the leaves it proves. `()` for proofs
+    /// carrying everything they prove.
+    type Claim: ?Sized;
+    /// What verification computes on success, e.g. the recomputed root for proofs about a single
+    /// tree, so that it can be logged or fed into an outer proof.
+    type Output;
+    type Error;
+
+    fn verify(
+        &self,
+        commitment: &Self::Commitment,
+        claim: &Self::Claim,
+    ) -> Result<Self::Output, Self::Error>;
+
+    /// Length of the BCS encoding in bytes.
+    fn size_hint(&self) -> usize;
+}
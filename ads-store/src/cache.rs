@@ -0,0 +1,201 @@
+//! An LRU cache in front of any node store.
+//!
+//! Every proof walks down from the peaks, so the upper levels of a structure are read far more
+//! often than any leaf. Keeping recently read nodes in memory, up to a byte budget, serves those
+//! from memory and only hits the backend for the cold lower levels. Writes go through to the
+//! backend and update the cache.
+
+use std::collections::{BTreeMap, HashMap};
+use std::mem::size_of;
+use std::sync::Mutex;
+
+use crate::{BatchOp, NodeStore};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Debug)]
+struct Lru<N> {
+    // pos -> (node, weight, last use)
+    nodes: HashMap<u64, (N, usize, u64)>,
+    // last use -> pos, oldest first
+    order: BTreeMap<u64, u64>,
+    used: usize,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl<N> Lru<N> {
+    fn touch(&mut self, pos: u64) {
+        if let Some((_, _, last_use)) = self.nodes.get_mut(&pos) {
+            self.order.remove(last_use);
+            self.clock += 1;
+            *last_use = self.clock;
+            self.order.insert(self.clock, pos);
+        }
+    }
+
+    fn remove(&mut self, pos: u64) {
+        if let Some((_, weight, last_use)) = self.nodes.remove(&pos) {
+            self.order.remove(&last_use);
+            self.used -= weight;
+        }
+    }
+
+    fn insert(&mut self, pos: u64, node: N, weight: usize, budget: usize) {
+        self.remove(pos);
+        if weight > budget {
+            return;
+        }
+        while self.used + weight > budget {
+            let (_, oldest) = self.order.pop_first().unwrap();
+            let (_, oldest_weight, _) = self.nodes.remove(&oldest).unwrap();
+            self.used -= oldest_weight;
+        }
+        self.clock += 1;
+        self.nodes.insert(pos, (node, weight, self.clock));
+        self.order.insert(self.clock, pos);
+        self.used += weight;
+    }
+}
+
+#[derive(Debug)]
+pub struct CachedStore<S, N> {
+    inner: S,
+    budget: usize,
+    weigh: fn(&N) -> usize,
+    lru: Mutex<Lru<N>>,
+}
+
+impl<S, N> CachedStore<S, N> {
+    /// Cache up to `budget` bytes of nodes, counting `size_of::<N>()` bytes per node.
+    pub fn new(inner: S, budget: usize) -> Self {
+        Self::with_weigher(inner, budget, |_| size_of::<N>())
+    }
+
+    /// Cache up to `budget` bytes of nodes, weighing each node with `weigh` (e.g., to account for
+    /// heap allocations of variable size entries).
+    pub fn with_weigher(inner: S, budget: usize, weigh: fn(&N) -> usize) -> Self {
+        CachedStore {
+            inner,
+            budget,
+            weigh,
+            lru: Mutex::new(Lru {
+                nodes: HashMap::new(),
+                order: BTreeMap::new(),
+                used: 0,
+                clock: 0,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lru.lock().unwrap().stats
+    }
+
+    /// Bytes currently cached.
+    pub fn cached_bytes(&self) -> usize {
+        self.lru.lock().unwrap().used
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: NodeStore<N>, N: Clone> NodeStore<N> for CachedStore<S, N> {
+    type Error = S::Error;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error> {
+        let mut lru = self.lru.lock().unwrap();
+        if let Some((node, _, _)) = lru.nodes.get(&pos) {
+            let node = node.clone();
+            lru.touch(pos);
+            lru.stats.hits += 1;
+            return Ok(Some(node));
+        }
+        lru.stats.misses += 1;
+
+        let node = self.inner.get(pos)?;
+        if let Some(node) = &node {
+            lru.insert(pos, node.clone(), (self.weigh)(node), self.budget);
+        }
+        Ok(node)
+    }
+
+    fn put(&mut self, pos: u64, node: N) -> Result<(), Self::Error> {
+        self.inner.put(pos, node.clone())?;
+        let weight = (self.weigh)(&node);
+        self.lru
+            .get_mut()
+            .unwrap()
+            .insert(pos, node, weight, self.budget);
+        Ok(())
+    }
+
+    fn delete(&mut self, pos: u64) -> Result<(), Self::Error> {
+        self.inner.delete(pos)?;
+        self.lru.get_mut().unwrap().remove(pos);
+        Ok(())
+    }
+
+    fn write_batch(&mut self, batch: Vec<BatchOp<N>>) -> Result<(), Self::Error> {
+        self.inner.write_batch(batch.clone())?;
+        let lru = self.lru.get_mut().unwrap();
+        for op in batch {
+            match op {
+                BatchOp::Put(pos, node) => {
+                    let weight = (self.weigh)(&node);
+                    lru.insert(pos, node, weight, self.budget);
+                }
+                BatchOp::Delete(pos) => lru.remove(pos),
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+
+    #[test]
+    fn test_cached_store() {
+        let mut inner = MemoryStore::new();
+        for pos in 0..10u64 {
+            inner.put(pos, pos).unwrap();
+        }
+        // Room for 4 nodes
+        let mut store = CachedStore::new(inner, 4 * size_of::<u64>());
+
+        for pos in 0..4 {
+            assert_eq!(store.get(pos), Ok(Some(pos)));
+        }
+        assert_eq!(store.stats(), CacheStats { hits: 0, misses: 4 });
+        assert_eq!(store.cached_bytes(), 32);
+
+        // Reading 0 makes 1 the least recently used node, which is evicted for 4
+        store.get(0).unwrap();
+        store.get(4).unwrap();
+        assert_eq!(store.stats(), CacheStats { hits: 1, misses: 5 });
+        store.get(0).unwrap();
+        store.get(1).unwrap();
+        assert_eq!(store.stats(), CacheStats { hits: 2, misses: 6 });
+
+        // Writes go through and keep the cache consistent
+        store.put(0, 100).unwrap();
+        assert_eq!(store.get(0), Ok(Some(100)));
+        store.write_batch(vec![BatchOp::Delete(0)]).unwrap();
+        assert_eq!(store.get(0), Ok(None));
+        assert_eq!(store.into_inner().get(0), Ok(None));
+    }
+}
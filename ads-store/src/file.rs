@@ -0,0 +1,396 @@
+//! An append-only log of nodes in segment files.
+//!
+//! Every write appends a record `pos (u64) || len (u32) || node` to the current segment and the
+//! record's location to the segment's index; nothing is overwritten in place. Deletes append a
+//! tombstone. Once a segment reaches `max_segment_size`, it is synced and a new one is started.
+//! The latest location of every position is kept in memory and rebuilt on open from the indexes
+//! of sealed segments and by rescanning the last segment, whose index may lag behind after a
+//! crash. A record torn by a crash at the end of the log is truncated.
+//!
+//! Besides nodes, the store keeps a small header (e.g., the size and peaks of an MMR), which is
+//! replaced atomically by writing a new file and renaming it over the old one.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{BatchOp, NodeStore};
+
+pub const DEFAULT_MAX_SEGMENT_SIZE: u64 = 64 << 20;
+
+const HEADER_FILE: &str = "HEADER";
+// pos (u64) || len (u32)
+const RECORD_HEADER_SIZE: usize = 12;
+// pos (u64) || offset (u64) || len (u32)
+const INDEX_RECORD_SIZE: usize = 20;
+const TOMBSTONE: u32 = u32::MAX;
+
+#[derive(Debug)]
+pub enum FileStoreError {
+    Io(io::Error),
+    Encoding(bcs::Error),
+}
+
+impl fmt::Display for FileStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileStoreError::Io(e) => write!(f, "I/O error: {}", e),
+            FileStoreError::Encoding(e) => write!(f, "Malformed node: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FileStoreError {}
+
+impl From<io::Error> for FileStoreError {
+    fn from(e: io::Error) -> Self {
+        FileStoreError::Io(e)
+    }
+}
+
+impl From<bcs::Error> for FileStoreError {
+    fn from(e: bcs::Error) -> Self {
+        FileStoreError::Encoding(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Location {
+    segment: usize,
+    // Offset of the node, i.e., right after the record header
+    offset: u64,
+    len: u32,
+}
+
+#[derive(Debug)]
+pub struct FileStore<N> {
+    dir: PathBuf,
+    max_segment_size: u64,
+    locations: HashMap<u64, Location>,
+    // One read handle per segment
+    segments: Vec<Mutex<File>>,
+    // Append handles of the last segment and its index
+    log: File,
+    index: File,
+    log_size: u64,
+    _node: PhantomData<N>,
+}
+
+impl<N> FileStore<N> {
+    /// Open (or create) the log in `dir`.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, FileStoreError> {
+        Self::open_with_segment_size(dir, DEFAULT_MAX_SEGMENT_SIZE)
+    }
+
+    pub fn open_with_segment_size(
+        dir: impl AsRef<Path>,
+        max_segment_size: u64,
+    ) -> Result<Self, FileStoreError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut numbers = vec![];
+        for entry in fs::read_dir(&dir)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            if let Some(number) = name.strip_suffix(".log") {
+                if let Ok(number) = number.parse::<usize>() {
+                    numbers.push(number);
+                }
+            }
+        }
+        numbers.sort();
+        if numbers.is_empty() {
+            numbers.push(0);
+        }
+        // Segments are numbered consecutively from 0
+        if numbers.iter().enumerate().any(|(i, &number)| i != number) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Missing segment").into());
+        }
+
+        let mut locations = HashMap::new();
+        let mut segments = vec![];
+        let last = numbers.len() - 1;
+        for segment in 0..last {
+            load_index(&index_path(&dir, segment), segment, &mut locations)?;
+            segments.push(Mutex::new(File::open(log_path(&dir, segment))?));
+        }
+
+        let (log, index, log_size) = recover_segment(&dir, last, &mut locations)?;
+        segments.push(Mutex::new(File::open(log_path(&dir, last))?));
+
+        Ok(FileStore {
+            dir,
+            max_segment_size,
+            locations,
+            segments,
+            log,
+            index,
+            log_size,
+            _node: PhantomData,
+        })
+    }
+
+    /// Number of segment files.
+    pub fn num_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn header(&self) -> Result<Option<Vec<u8>>, FileStoreError> {
+        match fs::read(self.dir.join(HEADER_FILE)) {
+            Ok(header) => Ok(Some(header)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Atomically replace the header. The new header is durable once this returns.
+    pub fn set_header(&mut self, header: &[u8]) -> Result<(), FileStoreError> {
+        let tmp = self.dir.join(format!("{}.tmp", HEADER_FILE));
+        let mut file = File::create(&tmp)?;
+        file.write_all(header)?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.dir.join(HEADER_FILE))?;
+        File::open(&self.dir)?.sync_all()?;
+        Ok(())
+    }
+
+    fn append(&mut self, records: Vec<(u64, Option<Vec<u8>>)>) -> Result<(), FileStoreError> {
+        if self.log_size >= self.max_segment_size {
+            self.roll_segment()?;
+        }
+        let segment = self.segments.len() - 1;
+
+        let mut log = vec![];
+        let mut index = vec![];
+        let mut new_locations = vec![];
+        for (pos, node) in records {
+            let len = node.as_ref().map_or(TOMBSTONE, |node| node.len() as u32);
+            let offset = self.log_size + (log.len() + RECORD_HEADER_SIZE) as u64;
+            log.extend(pos.to_le_bytes());
+            log.extend(len.to_le_bytes());
+            log.extend(node.unwrap_or_default());
+            index.extend(pos.to_le_bytes());
+            index.extend(offset.to_le_bytes());
+            index.extend(len.to_le_bytes());
+            new_locations.push((pos, offset, len));
+        }
+        // The log goes first so that an index record never points past the log
+        self.log.write_all(&log)?;
+        self.index.write_all(&index)?;
+        self.log_size += log.len() as u64;
+
+        for (pos, offset, len) in new_locations {
+            apply(&mut self.locations, pos, segment, offset, len);
+        }
+        Ok(())
+    }
+
+    // Seal the current segment and start the next one
+    fn roll_segment(&mut self) -> Result<(), FileStoreError> {
+        self.log.sync_data()?;
+        self.index.sync_data()?;
+        let segment = self.segments.len();
+        self.log = append_handle(&log_path(&self.dir, segment))?;
+        self.index = append_handle(&index_path(&self.dir, segment))?;
+        self.segments
+            .push(Mutex::new(File::open(log_path(&self.dir, segment))?));
+        self.log_size = 0;
+        Ok(())
+    }
+}
+
+impl<N: Serialize + DeserializeOwned> NodeStore<N> for FileStore<N> {
+    type Error = FileStoreError;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error> {
+        let Some(location) = self.locations.get(&pos) else {
+            return Ok(None);
+        };
+        let mut bytes = vec![0; location.len as usize];
+        let mut file = self.segments[location.segment].lock().unwrap();
+        file.seek(SeekFrom::Start(location.offset))?;
+        file.read_exact(&mut bytes)?;
+        Ok(Some(bcs::from_bytes(&bytes)?))
+    }
+
+    fn put(&mut self, pos: u64, node: N) -> Result<(), Self::Error> {
+        self.write_batch(vec![BatchOp::Put(pos, node)])
+    }
+
+    fn delete(&mut self, pos: u64) -> Result<(), Self::Error> {
+        self.write_batch(vec![BatchOp::Delete(pos)])
+    }
+
+    fn write_batch(&mut self, batch: Vec<BatchOp<N>>) -> Result<(), Self::Error> {
+        let records = batch
+            .into_iter()
+            .map(|op| match op {
+                BatchOp::Put(pos, node) => Ok((pos, Some(bcs::to_bytes(&node)?))),
+                BatchOp::Delete(pos) => Ok((pos, None)),
+            })
+            .collect::<Result<_, bcs::Error>>()?;
+        self.append(records)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.log.sync_data()?;
+        self.index.sync_data()?;
+        Ok(())
+    }
+}
+
+fn log_path(dir: &Path, segment: usize) -> PathBuf {
+    dir.join(format!("{:08}.log", segment))
+}
+
+fn index_path(dir: &Path, segment: usize) -> PathBuf {
+    dir.join(format!("{:08}.idx", segment))
+}
+
+fn append_handle(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn apply(locations: &mut HashMap<u64, Location>, pos: u64, segment: usize, offset: u64, len: u32) {
+    if len == TOMBSTONE {
+        locations.remove(&pos);
+    } else {
+        locations.insert(
+            pos,
+            Location {
+                segment,
+                offset,
+                len,
+            },
+        );
+    }
+}
+
+// Load the index of a sealed segment
+fn load_index(
+    path: &Path,
+    segment: usize,
+    locations: &mut HashMap<u64, Location>,
+) -> io::Result<()> {
+    let index = fs::read(path)?;
+    for record in index.chunks_exact(INDEX_RECORD_SIZE) {
+        let pos = u64::from_le_bytes(record[0..8].try_into().unwrap());
+        let offset = u64::from_le_bytes(record[8..16].try_into().unwrap());
+        let len = u32::from_le_bytes(record[16..20].try_into().unwrap());
+        apply(locations, pos, segment, offset, len);
+    }
+    Ok(())
+}
+
+// Rebuild the last segment's index from its log, truncating a torn record at its end. Returns the
+// append handles of the log and index, and the size of the log.
+fn recover_segment(
+    dir: &Path,
+    segment: usize,
+    locations: &mut HashMap<u64, Location>,
+) -> io::Result<(File, File, u64)> {
+    let log = match fs::read(log_path(dir, segment)) {
+        Ok(log) => log,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e),
+    };
+
+    let mut index = vec![];
+    let mut end = 0;
+    while end + RECORD_HEADER_SIZE <= log.len() {
+        let pos = u64::from_le_bytes(log[end..end + 8].try_into().unwrap());
+        let len = u32::from_le_bytes(log[end + 8..end + 12].try_into().unwrap());
+        let offset = end + RECORD_HEADER_SIZE;
+        let record_end = offset + if len == TOMBSTONE { 0 } else { len as usize };
+        if record_end > log.len() {
+            break;
+        }
+        index.extend(pos.to_le_bytes());
+        index.extend((offset as u64).to_le_bytes());
+        index.extend(len.to_le_bytes());
+        apply(locations, pos, segment, offset as u64, len);
+        end = record_end;
+    }
+
+    let log_file = append_handle(&log_path(dir, segment))?;
+    log_file.set_len(end as u64)?;
+    fs::write(index_path(dir, segment), &index)?;
+    let index_file = append_handle(&index_path(dir, segment))?;
+    Ok((log_file, index_file, end as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ads-file-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_file_store() {
+        let dir = temp_dir("store");
+        {
+            let mut store = FileStore::<Vec<u8>>::open_with_segment_size(&dir, 64).unwrap();
+            assert_eq!(store.get(0).unwrap(), None);
+            assert_eq!(store.header().unwrap(), None);
+            for pos in 0..20 {
+                store.put(pos, vec![pos as u8; 10]).unwrap();
+            }
+            store
+                .write_batch(vec![BatchOp::Put(3, b"new".to_vec()), BatchOp::Delete(4)])
+                .unwrap();
+            store.set_header(b"peaks").unwrap();
+            store.flush().unwrap();
+            assert!(store.num_segments() > 1);
+        }
+
+        let store = FileStore::<Vec<u8>>::open_with_segment_size(&dir, 64).unwrap();
+        assert_eq!(store.get(0).unwrap(), Some(vec![0; 10]));
+        assert_eq!(store.get(19).unwrap(), Some(vec![19; 10]));
+        assert_eq!(store.get(3).unwrap(), Some(b"new".to_vec()));
+        assert_eq!(store.get(4).unwrap(), None);
+        assert_eq!(store.header().unwrap(), Some(b"peaks".to_vec()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_recovery() {
+        let dir = temp_dir("recovery");
+        let last_log;
+        {
+            let mut store = FileStore::<Vec<u8>>::open(&dir).unwrap();
+            store.put(0, b"a".to_vec()).unwrap();
+            store.put(1, b"b".to_vec()).unwrap();
+            store.flush().unwrap();
+            last_log = log_path(&dir, store.num_segments() - 1);
+        }
+
+        // A torn record at the end of the log and a lost index
+        let mut log = OpenOptions::new().append(true).open(&last_log).unwrap();
+        log.write_all(&2u64.to_le_bytes()).unwrap();
+        log.write_all(&[5, 0]).unwrap();
+        fs::write(index_path(&dir, 0), []).unwrap();
+
+        let mut store = FileStore::<Vec<u8>>::open(&dir).unwrap();
+        assert_eq!(store.get(0).unwrap(), Some(b"a".to_vec()));
+        assert_eq!(store.get(1).unwrap(), Some(b"b".to_vec()));
+        assert_eq!(store.get(2).unwrap(), None);
+
+        // Appends continue after the last complete record
+        store.put(2, b"c".to_vec()).unwrap();
+        drop(store);
+        let store = FileStore::<Vec<u8>>::open(&dir).unwrap();
+        assert_eq!(store.get(2).unwrap(), Some(b"c".to_vec()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+//! Node storage shared by the authenticated data structures.
+//!
+//! Every structure addresses its nodes by a single `u64` position (post-order positions for MMRs,
+//! heights for skip lists), so a store is a map from positions to nodes. Backends only need to
+//! implement `get`, `put` and `delete`; batches and flushing have defaults for stores without
+//! native support.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+pub mod cache;
+#[cfg(feature = "file")]
+pub mod file;
+#[cfg(feature = "rocksdb")]
+pub mod rocksdb;
+#[cfg(feature = "sled")]
+pub mod sled;
+
+/// A single write of a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchOp<N> {
+    Put(u64, N),
+    Delete(u64),
+}
+
+pub trait NodeStore<N> {
+    type Error: std::error::Error;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error>;
+
+    fn put(&mut self, pos: u64, node: N) -> Result<(), Self::Error>;
+
+    fn delete(&mut self, pos: u64) -> Result<(), Self::Error>;
+
+    /// Apply all writes of the batch. Backends with atomic batches should override this so that
+    /// either all or none of the writes are persisted.
+    fn write_batch(&mut self, batch: Vec<BatchOp<N>>) -> Result<(), Self::Error> {
+        for op in batch {
+            match op {
+                BatchOp::Put(pos, node) => self.put(pos, node)?,
+                BatchOp::Delete(pos) => self.delete(pos)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Make all previous writes durable.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// The default store, keeping nodes on the heap. Positions are expected to be dense, as they are
+/// for append-only structures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryStore<N> {
+    nodes: Vec<Option<N>>,
+}
+
+impl<N> MemoryStore<N> {
+    pub fn new() -> Self {
+        MemoryStore { nodes: vec![] }
+    }
+
+    /// Number of positions up to the last one ever written.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<N> Default for MemoryStore<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Clone> NodeStore<N> for MemoryStore<N> {
+    type Error = Infallible;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error> {
+        Ok(self.nodes.get(pos as usize).cloned().flatten())
+    }
+
+    fn put(&mut self, pos: u64, node: N) -> Result<(), Self::Error> {
+        let pos = pos as usize;
+        if pos >= self.nodes.len() {
+            self.nodes.resize_with(pos + 1, || None);
+        }
+        self.nodes[pos] = Some(node);
+        Ok(())
+    }
+
+    fn delete(&mut self, pos: u64) -> Result<(), Self::Error> {
+        if let Some(node) = self.nodes.get_mut(pos as usize) {
+            *node = None;
+        }
+        Ok(())
+    }
+}
+
+/// A sparse store on the heap, for positions far apart, e.g. when several structures share a
+/// store by each using a range of positions.
+impl<N: Clone> NodeStore<N> for BTreeMap<u64, N> {
+    type Error = Infallible;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error> {
+        Ok(BTreeMap::get(self, &pos).cloned())
+    }
+
+    fn put(&mut self, pos: u64, node: N) -> Result<(), Self::Error> {
+        self.insert(pos, node);
+        Ok(())
+    }
+
+    fn delete(&mut self, pos: u64) -> Result<(), Self::Error> {
+        self.remove(&pos);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store() {
+        let mut store = MemoryStore::new();
+        assert!(store.is_empty());
+        assert_eq!(store.get(0), Ok(None));
+
+        store.put(2, "c").unwrap();
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get(1), Ok(None));
+        assert_eq!(store.get(2), Ok(Some("c")));
+
+        store
+            .write_batch(vec![
+                BatchOp::Put(0, "a"),
+                BatchOp::Put(1, "b"),
+                BatchOp::Delete(2),
+            ])
+            .unwrap();
+        assert_eq!(store.get(0), Ok(Some("a")));
+        assert_eq!(store.get(1), Ok(Some("b")));
+        assert_eq!(store.get(2), Ok(None));
+        store.delete(5).unwrap();
+        store.flush().unwrap();
+    }
+
+    #[test]
+    fn test_sparse_store() {
+        let mut store = BTreeMap::new();
+        NodeStore::put(&mut store, 1 << 40, "a").unwrap();
+        assert_eq!(NodeStore::get(&store, 1 << 40), Ok(Some("a")));
+        assert_eq!(NodeStore::get(&store, 0), Ok(None));
+        NodeStore::delete(&mut store, 1 << 40).unwrap();
+        assert!(store.is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+//! A node store on top of RocksDB.
+//!
+//! A database holds three column families: node hashes, entries and a small key-value space for
+//! metadata (e.g., the size of a structure). Positions are big-endian keys, so range reads follow
+//! position order, and values are BCS encoded. Batches are written as one RocksDB write batch
+//! without syncing the WAL; `flush` syncs it.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use ::rocksdb::{
+    ColumnFamilyDescriptor, Direction, IteratorMode, Options, WriteBatch, WriteOptions, DB,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{BatchOp, NodeStore};
+
+pub const NODES_CF: &str = "nodes";
+pub const ENTRIES_CF: &str = "entries";
+pub const METADATA_CF: &str = "metadata";
+
+#[derive(Debug)]
+pub enum RocksStoreError {
+    RocksDb(rocksdb::Error),
+    Encoding(bcs::Error),
+}
+
+impl fmt::Display for RocksStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RocksStoreError::RocksDb(e) => write!(f, "RocksDB error: {}", e),
+            RocksStoreError::Encoding(e) => write!(f, "Malformed node: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RocksStoreError {}
+
+impl From<rocksdb::Error> for RocksStoreError {
+    fn from(e: rocksdb::Error) -> Self {
+        RocksStoreError::RocksDb(e)
+    }
+}
+
+impl From<bcs::Error> for RocksStoreError {
+    fn from(e: bcs::Error) -> Self {
+        RocksStoreError::Encoding(e)
+    }
+}
+
+/// A RocksDB database with the column families used by the stores.
+#[derive(Debug, Clone)]
+pub struct RocksDb {
+    db: Arc<DB>,
+}
+
+impl RocksDb {
+    /// Open (or create) the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RocksStoreError> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let cfs = [NODES_CF, ENTRIES_CF, METADATA_CF].map(|name| {
+            let mut cf_options = Options::default();
+            // Nodes and entries are only ever appended, in key order
+            cf_options.set_level_compaction_dynamic_level_bytes(true);
+            ColumnFamilyDescriptor::new(name, cf_options)
+        });
+        Ok(RocksDb {
+            db: Arc::new(DB::open_cf_descriptors(&options, path, cfs)?),
+        })
+    }
+
+    pub fn nodes<N>(&self) -> RocksStore<N> {
+        RocksStore::new(self.db.clone(), NODES_CF)
+    }
+
+    pub fn entries<N>(&self) -> RocksStore<N> {
+        RocksStore::new(self.db.clone(), ENTRIES_CF)
+    }
+
+    pub fn metadata(&self, key: &str) -> Result<Option<Vec<u8>>, RocksStoreError> {
+        Ok(self
+            .db
+            .get_cf(self.db.cf_handle(METADATA_CF).unwrap(), key)?)
+    }
+
+    pub fn set_metadata(&self, key: &str, value: &[u8]) -> Result<(), RocksStoreError> {
+        Ok(self
+            .db
+            .put_cf(self.db.cf_handle(METADATA_CF).unwrap(), key, value)?)
+    }
+}
+
+/// A node store in one column family of a `RocksDb`.
+#[derive(Debug, Clone)]
+pub struct RocksStore<N> {
+    db: Arc<DB>,
+    cf: &'static str,
+    _node: PhantomData<N>,
+}
+
+impl<N> RocksStore<N> {
+    fn new(db: Arc<DB>, cf: &'static str) -> Self {
+        RocksStore {
+            db,
+            cf,
+            _node: PhantomData,
+        }
+    }
+
+    fn write_options() -> WriteOptions {
+        let mut options = WriteOptions::default();
+        options.set_sync(false);
+        options
+    }
+}
+
+impl<N: DeserializeOwned> RocksStore<N> {
+    /// The nodes stored at positions in `range`, in order. Missing positions are skipped.
+    pub fn range(
+        &self,
+        range: Range<u64>,
+    ) -> impl Iterator<Item = Result<(u64, N), RocksStoreError>> + '_ {
+        let start = range.start.to_be_bytes();
+        self.db
+            .iterator_cf(
+                self.db.cf_handle(self.cf).unwrap(),
+                IteratorMode::From(&start, Direction::Forward),
+            )
+            .map(|item| -> Result<(u64, N), RocksStoreError> {
+                let (key, value) = item?;
+                let pos = u64::from_be_bytes(key.as_ref().try_into().unwrap());
+                Ok((pos, bcs::from_bytes(&value)?))
+            })
+            .take_while(move |item| match item {
+                Ok((pos, _)) => *pos < range.end,
+                Err(_) => true,
+            })
+    }
+}
+
+impl<N: Serialize + DeserializeOwned> NodeStore<N> for RocksStore<N> {
+    type Error = RocksStoreError;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error> {
+        let cf = self.db.cf_handle(self.cf).unwrap();
+        match self.db.get_pinned_cf(cf, pos.to_be_bytes())? {
+            Some(bytes) => Ok(Some(bcs::from_bytes(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&mut self, pos: u64, node: N) -> Result<(), Self::Error> {
+        let cf = self.db.cf_handle(self.cf).unwrap();
+        self.db.put_cf_opt(
+            cf,
+            pos.to_be_bytes(),
+            bcs::to_bytes(&node)?,
+            &Self::write_options(),
+        )?;
+        Ok(())
+    }
+
+    fn delete(&mut self, pos: u64) -> Result<(), Self::Error> {
+        let cf = self.db.cf_handle(self.cf).unwrap();
+        self.db
+            .delete_cf_opt(cf, pos.to_be_bytes(), &Self::write_options())?;
+        Ok(())
+    }
+
+    fn write_batch(&mut self, batch: Vec<BatchOp<N>>) -> Result<(), Self::Error> {
+        let cf = self.db.cf_handle(self.cf).unwrap();
+        let mut rocks_batch = WriteBatch::default();
+        for op in batch {
+            match op {
+                BatchOp::Put(pos, node) => {
+                    rocks_batch.put_cf(cf, pos.to_be_bytes(), bcs::to_bytes(&node)?)
+                }
+                BatchOp::Delete(pos) => rocks_batch.delete_cf(cf, pos.to_be_bytes()),
+            }
+        }
+        self.db.write_opt(rocks_batch, &Self::write_options())?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.db.flush_wal(true)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rocksdb_store() {
+        let dir = std::env::temp_dir().join(format!("ads-rocksdb-{}", std::process::id()));
+        {
+            let db = RocksDb::open(&dir).unwrap();
+            let mut nodes = db.nodes::<Vec<u8>>();
+            nodes
+                .write_batch(vec![
+                    BatchOp::Put(0, b"a".to_vec()),
+                    BatchOp::Put(1, b"b".to_vec()),
+                    BatchOp::Put(256, b"c".to_vec()),
+                    BatchOp::Put(300, b"d".to_vec()),
+                    BatchOp::Delete(1),
+                ])
+                .unwrap();
+            nodes.flush().unwrap();
+            db.set_metadata("size", &[4]).unwrap();
+        }
+
+        let db = RocksDb::open(&dir).unwrap();
+        let nodes = db.nodes::<Vec<u8>>();
+        assert_eq!(nodes.get(0).unwrap(), Some(b"a".to_vec()));
+        assert_eq!(nodes.get(1).unwrap(), None);
+        assert_eq!(db.entries::<Vec<u8>>().get(0).unwrap(), None);
+        assert_eq!(db.metadata("size").unwrap(), Some(vec![4]));
+
+        // Big-endian keys iterate in position order
+        let range: Vec<_> = nodes.range(1..300).map(Result::unwrap).collect();
+        assert_eq!(range, vec![(256, b"c".to_vec())]);
+        drop((nodes, db));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
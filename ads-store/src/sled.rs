@@ -0,0 +1,135 @@
+//! A node store on top of a sled tree.
+//!
+//! Positions are stored as big-endian keys, so that sled iterates them in order, and nodes are BCS
+//! encoded. Batches are applied atomically.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{BatchOp, NodeStore};
+
+// Re-exported to open databases without depending on sled directly
+pub use ::sled::{Config, Db};
+
+#[derive(Debug)]
+pub enum SledStoreError {
+    Sled(sled::Error),
+    Encoding(bcs::Error),
+}
+
+impl fmt::Display for SledStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SledStoreError::Sled(e) => write!(f, "sled error: {}", e),
+            SledStoreError::Encoding(e) => write!(f, "Malformed node: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SledStoreError {}
+
+impl From<sled::Error> for SledStoreError {
+    fn from(e: sled::Error) -> Self {
+        SledStoreError::Sled(e)
+    }
+}
+
+impl From<bcs::Error> for SledStoreError {
+    fn from(e: bcs::Error) -> Self {
+        SledStoreError::Encoding(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SledStore<N> {
+    tree: sled::Tree,
+    _node: PhantomData<N>,
+}
+
+impl<N> SledStore<N> {
+    /// Use the tree with the given name in `db`, creating it if needed. Several stores (e.g., the
+    /// nodes and the entries of an MMR) can share a database under different names.
+    pub fn open(db: &sled::Db, name: &str) -> Result<Self, SledStoreError> {
+        Ok(SledStore {
+            tree: db.open_tree(name)?,
+            _node: PhantomData,
+        })
+    }
+}
+
+impl<N: Serialize + DeserializeOwned> NodeStore<N> for SledStore<N> {
+    type Error = SledStoreError;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error> {
+        match self.tree.get(pos.to_be_bytes())? {
+            Some(bytes) => Ok(Some(bcs::from_bytes(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&mut self, pos: u64, node: N) -> Result<(), Self::Error> {
+        self.tree.insert(pos.to_be_bytes(), bcs::to_bytes(&node)?)?;
+        Ok(())
+    }
+
+    fn delete(&mut self, pos: u64) -> Result<(), Self::Error> {
+        self.tree.remove(pos.to_be_bytes())?;
+        Ok(())
+    }
+
+    fn write_batch(&mut self, batch: Vec<BatchOp<N>>) -> Result<(), Self::Error> {
+        let mut sled_batch = sled::Batch::default();
+        for op in batch {
+            match op {
+                BatchOp::Put(pos, node) => {
+                    sled_batch.insert(&pos.to_be_bytes(), bcs::to_bytes(&node)?)
+                }
+                BatchOp::Delete(pos) => sled_batch.remove(&pos.to_be_bytes()),
+            }
+        }
+        self.tree.apply_batch(sled_batch)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.tree.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sled_store() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let mut store = SledStore::<Vec<u8>>::open(&db, "nodes").unwrap();
+        assert_eq!(store.get(0).unwrap(), None);
+
+        store
+            .write_batch(vec![
+                BatchOp::Put(0, b"a".to_vec()),
+                BatchOp::Put(1, b"b".to_vec()),
+                BatchOp::Put(300, b"c".to_vec()),
+                BatchOp::Delete(1),
+            ])
+            .unwrap();
+        store.flush().unwrap();
+        assert_eq!(store.get(0).unwrap(), Some(b"a".to_vec()));
+        assert_eq!(store.get(1).unwrap(), None);
+
+        // Stores are isolated by name but see each other's writes under the same name
+        let other = SledStore::<Vec<u8>>::open(&db, "entries").unwrap();
+        assert_eq!(other.get(0).unwrap(), None);
+        let same = SledStore::<Vec<u8>>::open(&db, "nodes").unwrap();
+        assert_eq!(same.get(300).unwrap(), Some(b"c".to_vec()));
+
+        // Values that don't decode as the node type are reported
+        store.put(2, vec![0xff]).unwrap();
+        let digests = SledStore::<[u8; 32]>::open(&db, "nodes").unwrap();
+        assert!(matches!(digests.get(2), Err(SledStoreError::Encoding(_))));
+    }
+}
@@ -0,0 +1,94 @@
+//! Witnesses for arkworks' Merkle tree gadget (`ark_crypto_primitives::merkle_tree`, 0.4).
+//!
+//! The types below have the fields of arkworks' `Path` and `PoseidonConfig`, with elements of the
+//! BN254 scalar field, so a circuit builds its own from them without this crate depending on
+//! arkworks. The tree `Config` they fit is:
+//!
+//! - `Leaf = Fr` and `LeafHash` the identity: the leaf given to the gadget is the leaf's hash, as
+//!   in `CircuitInclusionProof` (see `circuit`),
+//! - `LeafDigest = InnerDigest = Fr` with `IdentityDigestConverter`,
+//! - `TwoToOneHash` the Poseidon permutation of `poseidon_config()` on `[0, left, right]`,
+//!   outputting the first element of the state. This is circomlib's `Poseidon(2)`, not arkworks'
+//!   `poseidon::TwoToOneCRH`, whose sponge outputs the second element, so it is a CRH of the
+//!   circuit's own over `ark_crypto_primitives::sponge::poseidon::PoseidonSponge`'s permutation.
+
+use alloc::vec::Vec;
+
+use ads_core::poseidon::{self, Fr};
+
+use crate::circuit::CircuitInclusionProof;
+use crate::error::VerificationError;
+
+/// The fields of arkworks' `merkle_tree::Path`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArkworksPath {
+    pub leaf_sibling_hash: Fr,
+    /// Siblings of the nodes on the path above the leaf level, from the children of the root down
+    pub auth_path: Vec<Fr>,
+    pub leaf_index: usize,
+}
+
+/// What arkworks' gadget checks: the path from `leaf` up to `root`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArkworksWitness {
+    pub leaf: Fr,
+    pub path: ArkworksPath,
+    pub root: Fr,
+}
+
+/// The fields of arkworks' `sponge::poseidon::PoseidonConfig` for the permutation of this
+/// crate's Poseidon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArkworksPoseidonConfig {
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    pub alpha: u64,
+    /// Round constants, one row per round
+    pub ark: Vec<Vec<Fr>>,
+    pub mds: Vec<Vec<Fr>>,
+    pub rate: usize,
+    pub capacity: usize,
+}
+
+pub fn poseidon_config() -> ArkworksPoseidonConfig {
+    ArkworksPoseidonConfig {
+        full_rounds: poseidon::FULL_ROUNDS,
+        partial_rounds: poseidon::PARTIAL_ROUNDS,
+        alpha: poseidon::ALPHA,
+        ark: poseidon::ROUND_CONSTANTS
+            .chunks(poseidon::WIDTH)
+            .map(<[Fr]>::to_vec)
+            .collect(),
+        mds: poseidon::MDS.iter().map(|row| row.to_vec()).collect(),
+        rate: poseidon::WIDTH - 1,
+        capacity: 1,
+    }
+}
+
+impl ArkworksWitness {
+    /// The witness of a circuit inclusion proof. arkworks' trees have at least two leaves, so a
+    /// proof without siblings has none.
+    pub fn from_circuit(proof: &CircuitInclusionProof) -> Result<Self, VerificationError> {
+        let (&leaf_sibling_hash, auth_path) = proof
+            .path_elements
+            .split_first()
+            .ok_or(VerificationError::NotEnoughProofElements)?;
+        let leaf_index = proof
+            .path_indices
+            .iter()
+            .rev()
+            .try_fold(0usize, |index, &bit| {
+                index.checked_mul(2).map(|index| index + usize::from(bit))
+            })
+            .ok_or(VerificationError::NonCanonicalProof)?;
+        Ok(ArkworksWitness {
+            leaf: proof.leaf,
+            path: ArkworksPath {
+                leaf_sibling_hash,
+                auth_path: auth_path.iter().rev().copied().collect(),
+                leaf_index,
+            },
+            root: proof.root,
+        })
+    }
+}
@@ -0,0 +1,144 @@
+//! Auditing a whole log against the commitments published along the way.
+//!
+//! The auditor hashes the entries in as they stream by, keeping only the frontier of the MMR they
+//! make, one peak per tree and so O(log n) digests, and checks each published commitment when the
+//! stream reaches its size.
+
+use core::borrow::Borrow;
+use core::fmt;
+
+use crate::error::VerificationError;
+use crate::frontier::Frontier;
+use crate::hash::NodeHasher;
+use crate::mmr::{CommitmentError, MmrCommitment};
+use crate::version::check_version;
+
+/// The frontier of the MMR of the entries pushed so far.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MmrAuditor {
+    frontier: Frontier,
+    hasher: NodeHasher,
+}
+
+/// A published commitment that the entries don't lead to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditError {
+    /// Position of the commitment among those audited
+    pub commitment: usize,
+    pub error: VerificationError,
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Commitment {} fails the audit: {}",
+            self.commitment, self.error
+        )
+    }
+}
+
+impl core::error::Error for AuditError {}
+
+impl MmrAuditor {
+    /// An auditor of a log from its first entry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An auditor of a log hashed with `hasher`, from its first entry.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        MmrAuditor {
+            frontier: Frontier::empty().with_hash_algo(hasher.algo()),
+            hasher,
+        }
+    }
+
+    /// An auditor of the entries appended to the MMR committed to, which must have one peak per
+    /// tree.
+    pub fn from_commitment(commitment: &MmrCommitment) -> Result<Self, CommitmentError> {
+        Self::from_commitment_with_hasher(commitment, NodeHasher::new(commitment.hash_algo()))
+    }
+
+    /// Same as `from_commitment`, for an MMR hashed with `hasher`.
+    pub fn from_commitment_with_hasher(
+        commitment: &MmrCommitment,
+        hasher: NodeHasher,
+    ) -> Result<Self, CommitmentError> {
+        let commitment = commitment.clone().with_hash_algo(hasher.algo());
+        Ok(MmrAuditor {
+            frontier: Frontier::from_commitment(commitment)?,
+            hasher,
+        })
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.frontier.num_entries()
+    }
+
+    /// Hash in the next entry of the log.
+    pub fn push(&mut self, entry: &[u8]) {
+        self.frontier
+            .push(&self.hasher, self.hasher.hash_leaf(entry));
+    }
+
+    /// The frontier of the entries pushed so far, e.g., to hand over to an appender.
+    pub fn frontier(&self) -> &Frontier {
+        &self.frontier
+    }
+
+    /// The commitment to the entries pushed so far.
+    pub fn commitment(&self) -> MmrCommitment {
+        self.frontier.commitment().clone()
+    }
+
+    /// Check that `commitment` is to exactly the entries pushed so far.
+    pub fn check(&self, commitment: &MmrCommitment) -> Result<(), VerificationError> {
+        check_version(commitment.version)?;
+        commitment.check_hash_algo(&self.hasher, commitment.hash_algo())?;
+        if commitment.num_entries() != self.num_entries() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: commitment.num_entries(),
+                actual: self.num_entries(),
+            });
+        }
+        if self.frontier.commitment().peaks != commitment.peaks {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Check every commitment in `commitments`, by increasing size, against the log of `entries`
+/// hashed with the algorithm of the first one, and return the commitment to the whole log.
+pub fn audit<I, C>(entries: I, commitments: C) -> Result<MmrCommitment, AuditError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+    C: IntoIterator,
+    C::Item: Borrow<MmrCommitment>,
+{
+    let mut auditor = MmrAuditor::new();
+    let mut entries = entries.into_iter();
+    for (position, commitment) in commitments.into_iter().enumerate() {
+        let commitment = commitment.borrow();
+        if position == 0 {
+            // The log is hashed as its commitments say
+            auditor = MmrAuditor::new_with_hasher(NodeHasher::new(commitment.hash_algo()));
+        }
+        while auditor.num_entries() < commitment.num_entries() {
+            let Some(entry) = entries.next() else {
+                break;
+            };
+            auditor.push(entry.as_ref());
+        }
+        auditor.check(commitment).map_err(|error| AuditError {
+            commitment: position,
+            error,
+        })?;
+    }
+    for entry in entries {
+        auditor.push(entry.as_ref());
+    }
+    Ok(auditor.commitment())
+}
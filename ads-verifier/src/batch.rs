@@ -0,0 +1,101 @@
+//! Verification of many independent proofs against a commitment at once.
+//!
+//! Proofs are spread over a rayon thread pool, the global one unless a `Parallelism` says
+//! otherwise. A batch either verifies entirely or reports every proof that failed, identified by
+//! its position in the batch.
+
+use alloc::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{MmrCommitment, MmrInclusionProof, MostRecentNElementsProof};
+use crate::parallel::Parallelism;
+
+/// The proofs of a batch that failed verification, as (index in the batch, error).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchVerificationError {
+    pub failures: Vec<(usize, VerificationError)>,
+}
+
+impl MmrCommitment {
+    pub fn verify_batch_most_recent_n_elements(
+        &self,
+        proofs: &[MostRecentNElementsProof],
+    ) -> Result<(), BatchVerificationError> {
+        self.verify_batch_most_recent_n_elements_with_parallelism(proofs, &Parallelism::new())
+    }
+
+    /// Same as `verify_batch_most_recent_n_elements`, spread as `parallelism` says.
+    pub fn verify_batch_most_recent_n_elements_with_parallelism(
+        &self,
+        proofs: &[MostRecentNElementsProof],
+        parallelism: &Parallelism,
+    ) -> Result<(), BatchVerificationError> {
+        let hasher = NodeHasher::new(self.hash_algo());
+        self.verify_batch_most_recent_n_elements_with_hasher(&hasher, proofs, parallelism)
+    }
+
+    /// Same as `verify_batch_most_recent_n_elements_with_parallelism`, for an MMR hashed with
+    /// `hasher`.
+    pub fn verify_batch_most_recent_n_elements_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proofs: &[MostRecentNElementsProof],
+        parallelism: &Parallelism,
+    ) -> Result<(), BatchVerificationError> {
+        verify_batch(proofs, parallelism, |proof| {
+            self.verify_most_recent_n_elements_with_hasher(hasher, proof)
+        })
+    }
+
+    pub fn verify_batch_inclusion(
+        &self,
+        proofs: &[MmrInclusionProof],
+    ) -> Result<(), BatchVerificationError> {
+        self.verify_batch_inclusion_with_parallelism(proofs, &Parallelism::new())
+    }
+
+    /// Same as `verify_batch_inclusion`, spread as `parallelism` says.
+    pub fn verify_batch_inclusion_with_parallelism(
+        &self,
+        proofs: &[MmrInclusionProof],
+        parallelism: &Parallelism,
+    ) -> Result<(), BatchVerificationError> {
+        let hasher = NodeHasher::new(self.hash_algo());
+        self.verify_batch_inclusion_with_hasher(&hasher, proofs, parallelism)
+    }
+
+    /// Same as `verify_batch_inclusion_with_parallelism`, for an MMR hashed with `hasher`.
+    pub fn verify_batch_inclusion_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proofs: &[MmrInclusionProof],
+        parallelism: &Parallelism,
+    ) -> Result<(), BatchVerificationError> {
+        verify_batch(proofs, parallelism, |proof| {
+            self.verify_inclusion_with_hasher(hasher, proof)
+        })
+    }
+}
+
+fn verify_batch<P: Sync>(
+    proofs: &[P],
+    parallelism: &Parallelism,
+    verify: impl Fn(&P) -> Result<(), VerificationError> + Sync,
+) -> Result<(), BatchVerificationError> {
+    let failures: Vec<(usize, VerificationError)> = parallelism.install(|| {
+        proofs
+            .par_iter()
+            .with_min_len(parallelism.min_len())
+            .enumerate()
+            .filter_map(|(i, proof)| verify(proof).err().map(|e| (i, e)))
+            .collect()
+    });
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(BatchVerificationError { failures })
+    }
+}
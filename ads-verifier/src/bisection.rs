@@ -0,0 +1,191 @@
+//! Interactive bisection to the first entry two parties disagree on.
+//!
+//! Two parties committed to MMRs of the same number of entries with different peaks, e.g., the
+//! claimed outputs of the same computation. A `Bisection`, run by a referee or by either party,
+//! narrows their dispute down to a single leaf in one round per level of the largest tree they
+//! disagree on. Each round, it asks both parties for the children of the node they disagree on
+//! (`query`), checks that each party's children hash to the digest that party claimed for the
+//! node, and moves to the left child if the parties disagree on it, to the right one otherwise.
+//! Since larger trees hold older entries, this ends at the first entry the parties disagree on, and
+//! a fraud-proof game only needs to settle that entry. A party answering with children that don't
+//! hash to its own claim is caught cheating.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+use crate::hash::NodeHasher;
+use crate::mmr::MmrCommitment;
+
+/// A side of a dispute, in the order the commitments were given to `Bisection::new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Party {
+    First,
+    Second,
+}
+
+/// What both parties are asked each round: the children of their node of the given height
+/// covering the entries from `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct BisectionQuery {
+    pub start: u64,
+    pub height: u64,
+}
+
+/// A party's answer to a `BisectionQuery`: the digests of the children of the queried node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Split {
+    pub left: Digest,
+    pub right: Digest,
+}
+
+/// Where a bisection ends: the first entry the parties disagree on, and the leaf digest each party
+/// claims for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disagreement {
+    pub index: u64,
+    pub first: Digest,
+    pub second: Digest,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bisection {
+    hasher: NodeHasher,
+    // The node disputed: its first entry, its height, and its digest as claimed by each party
+    start: u64,
+    height: u64,
+    first: Digest,
+    second: Digest,
+}
+
+impl Bisection {
+    /// Start the bisection of a dispute between the parties committed to `first` and `second`.
+    pub fn new(first: &MmrCommitment, second: &MmrCommitment) -> Result<Self, BisectionError> {
+        Self::new_with_hasher(NodeHasher::new(first.hash_algo()), first, second)
+    }
+
+    /// Same as `new`, for MMRs hashed with `hasher`.
+    pub fn new_with_hasher(
+        hasher: NodeHasher,
+        first: &MmrCommitment,
+        second: &MmrCommitment,
+    ) -> Result<Self, BisectionError> {
+        let num_entries = first.num_entries();
+        if second.num_entries() != num_entries {
+            return Err(BisectionError::SizeMismatch {
+                first: num_entries,
+                second: second.num_entries(),
+            });
+        }
+
+        // The largest tree the parties disagree on holds the first entry they disagree on
+        let tree_index = (0..u64::BITS as u64)
+            .rev()
+            .filter(|tree_index| num_entries & (1 << tree_index) != 0)
+            .find(|&tree_index| first.tree_digest(tree_index) != second.tree_digest(tree_index))
+            .ok_or(BisectionError::NoDispute)?;
+        Ok(Bisection {
+            hasher,
+            // Larger trees come first
+            start: num_entries >> tree_index >> 1 << tree_index << 1,
+            height: tree_index,
+            first: first.tree_digest(tree_index),
+            second: second.tree_digest(tree_index),
+        })
+    }
+
+    /// What to ask both parties next, None once the dispute is narrowed to a single entry.
+    pub fn query(&self) -> Option<BisectionQuery> {
+        (self.height > 0).then_some(BisectionQuery {
+            start: self.start,
+            height: self.height,
+        })
+    }
+
+    /// Number of rounds until the dispute is narrowed to a single entry.
+    pub fn rounds_left(&self) -> u64 {
+        self.height
+    }
+
+    /// Play a round with the parties' answers to `query()`, and return the disagreement if that
+    /// was the last round.
+    pub fn respond(
+        &mut self,
+        first: &Split,
+        second: &Split,
+    ) -> Result<Option<Disagreement>, BisectionError> {
+        if self.height == 0 {
+            return Err(BisectionError::Finished);
+        }
+        if self.hasher.hash_children(&first.left, &first.right) != self.first {
+            return Err(BisectionError::InvalidSplit(Party::First));
+        }
+        if self.hasher.hash_children(&second.left, &second.right) != self.second {
+            return Err(BisectionError::InvalidSplit(Party::Second));
+        }
+
+        // Both splits hash to different digests, so the parties disagree on at least one child
+        self.height -= 1;
+        if first.left != second.left {
+            self.first = first.left;
+            self.second = second.left;
+        } else {
+            self.start += 1 << self.height;
+            self.first = first.right;
+            self.second = second.right;
+        }
+        Ok(self.disagreement())
+    }
+
+    /// The first entry the parties disagree on, once the bisection is over.
+    pub fn disagreement(&self) -> Option<Disagreement> {
+        (self.height == 0).then_some(Disagreement {
+            index: self.start,
+            first: self.first,
+            second: self.second,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BisectionError {
+    /// The commitments are to different numbers of entries
+    SizeMismatch { first: u64, second: u64 },
+    /// The commitments agree on every entry
+    NoDispute,
+    /// The dispute is already narrowed to a single entry
+    Finished,
+    /// The party's children don't hash to its claim
+    InvalidSplit(Party),
+}
+
+impl fmt::Display for BisectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BisectionError::SizeMismatch { first, second } => write!(
+                f,
+                "Can't bisect commitments to {} and {} entries",
+                first, second
+            ),
+            BisectionError::NoDispute => write!(f, "The commitments agree on every entry"),
+            BisectionError::Finished => write!(f, "The bisection is over"),
+            BisectionError::InvalidSplit(Party::First) => {
+                write!(f, "The first party's split doesn't match its claim")
+            }
+            BisectionError::InvalidSplit(Party::Second) => {
+                write!(f, "The second party's split doesn't match its claim")
+            }
+        }
+    }
+}
+
+impl core::error::Error for BisectionError {}
@@ -0,0 +1,120 @@
+//! Interop with Bitcoin's transaction Merkle trees, so that block Merkle roots and SPV proofs can
+//! be computed and checked with this crate.
+//!
+//! Leaves are transaction ids and nodes are the double SHA-256 of their concatenated children,
+//! without any prefix. A level with an odd number of nodes is completed by duplicating its last
+//! node, so trees of any size are a single tree rather than a forest. Digests are in Bitcoin's
+//! internal byte order, which is the reverse of how ids and roots are usually displayed.
+//!
+//! Duplicating the last node makes the root of n transactions with an odd last level also the
+//! root of a list repeating the last ones (CVE-2012-2459), and a 64-byte transaction can pass for
+//! an internal node. A block header commits to neither the number of transactions nor their
+//! sizes, so a proof carries the number of transactions, which the caller must get from a trusted
+//! source like the root.
+
+use alloc::vec::Vec;
+
+use ads_core::HashAlgo;
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+use crate::hash::NodeHasher;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// SHA-256 applied twice.
+pub fn sha256d(data: &[u8]) -> Digest {
+    let sha256 = NodeHasher::new(HashAlgo::Sha256);
+    sha256.hash_message(&sha256.hash_message(data).0)
+}
+
+/// Id of a serialized transaction (without witness data), in internal byte order.
+pub fn txid(raw_transaction: &[u8]) -> Digest {
+    sha256d(raw_transaction)
+}
+
+/// Hash of an internal node of a transaction tree.
+pub fn hash_children(left: &Digest, right: &Digest) -> Digest {
+    let sha256 = NodeHasher::new(HashAlgo::Sha256);
+    let mut hasher = sha256.message_hasher();
+    hasher.update(&left.0);
+    hasher.update(&right.0);
+    sha256.hash_message(&hasher.finalize().0)
+}
+
+/// The nodes of the level above `level`, duplicating its last node if it has an odd number.
+pub fn next_level(level: &[Digest]) -> Vec<Digest> {
+    level
+        .chunks(2)
+        .map(|pair| hash_children(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+        .collect()
+}
+
+/// The Merkle root of a block with transactions `txids`, None for no transaction.
+pub fn merkle_root(txids: &[Digest]) -> Option<Digest> {
+    let mut level = txids.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied()
+}
+
+/// An SPV proof that a transaction is in a block: the sibling of its node at each level, from the
+/// transactions up, as returned by e.g. Electrum's `blockchain.transaction.get_merkle`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct BitcoinMerkleProof {
+    pub version: u8,
+    pub num_transactions: u64,
+    pub tx_index: u64,
+    pub siblings: Vec<Digest>,
+}
+
+impl BitcoinMerkleProof {
+    pub fn new(num_transactions: u64, tx_index: u64, siblings: Vec<Digest>) -> Self {
+        BitcoinMerkleProof {
+            version: PROOF_FORMAT_VERSION,
+            num_transactions,
+            tx_index,
+            siblings,
+        }
+    }
+
+    /// Verify that `txid` is the transaction at `tx_index` of the block with Merkle root `root`.
+    /// Proofs of an unsupported version don't verify either.
+    pub fn verify(&self, root: &Digest, txid: &Digest) -> bool {
+        self.compute_root(txid).as_ref() == Some(root)
+    }
+
+    /// Recompute the Merkle root of the block from `txid`, None if the proof is malformed or of an
+    /// unsupported version. Where the node is the duplicated last one of its level, its sibling
+    /// must be itself.
+    pub fn compute_root(&self, txid: &Digest) -> Option<Digest> {
+        check_version(self.version).ok()?;
+        if self.tx_index >= self.num_transactions {
+            return None;
+        }
+        let mut current = *txid;
+        let mut index = self.tx_index;
+        let mut width = self.num_transactions;
+        let mut siblings = self.siblings.iter();
+        while width > 1 {
+            let sibling = siblings.next()?;
+            current = if index % 2 == 1 {
+                hash_children(sibling, &current)
+            } else if index + 1 == width {
+                if *sibling != current {
+                    return None;
+                }
+                hash_children(&current, &current)
+            } else {
+                hash_children(&current, sibling)
+            };
+            index /= 2;
+            width = width.div_ceil(2);
+        }
+        siblings.next().is_none().then_some(current)
+    }
+}
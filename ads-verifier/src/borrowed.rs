@@ -0,0 +1,180 @@
+//! Borrowed, zero-copy views of proofs.
+//!
+//! Proofs received over the wire are usually BCS-encoded `SuffixProof`s / `InclusionProof`s. The
+//! views below point straight into such a buffer and verify without allocating: proof elements
+//! are borrowed slices and every intermediate hash is a `Digest` on the stack.
+
+use ads_core::encoding::{read_u64, read_uleb128};
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::hash::{hash_children, hash_leaf};
+use crate::version::check_version;
+
+/// A list of digests borrowed from a BCS-encoded `Vec<Digest>`, i.e., a length followed by the
+/// digests back to back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DigestListRef<'a> {
+    encoded: &'a [u8],
+}
+
+impl<'a> DigestListRef<'a> {
+    // Parse a BCS vector of digests at the start of `bytes`, returning it and the rest
+    fn parse(bytes: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        let (len, rest) = read_uleb128(bytes)?;
+        let size = len.checked_mul(DIGEST_LENGTH)?;
+        if rest.len() < size {
+            return None;
+        }
+        let (encoded, rest) = rest.split_at(size);
+        Some((DigestListRef { encoded }, rest))
+    }
+
+    pub fn len(&self) -> usize {
+        self.encoded.len() / DIGEST_LENGTH
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.encoded.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Digest> + 'a {
+        // Chunks are exactly DIGEST_LENGTH long, as checked in `parse`
+        self.encoded
+            .chunks_exact(DIGEST_LENGTH)
+            .map(|chunk| Digest::from_slice(chunk).unwrap())
+    }
+}
+
+/// Borrowed counterpart of `SuffixProof`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SuffixProofRef<'a> {
+    pub version: u8,
+    pub num_suffix_elements: u64,
+    pub proof: DigestListRef<'a>,
+    pub num_leaves: Option<u64>,
+}
+
+impl<'a> SuffixProofRef<'a> {
+    /// View a BCS-encoded `SuffixProof` without copying it.
+    pub fn from_bcs(bytes: &'a [u8]) -> Option<Self> {
+        let (&version, bytes) = bytes.split_first()?;
+        let (num_suffix_elements, bytes) = read_u64(bytes)?;
+        let (proof, rest) = DigestListRef::parse(bytes)?;
+        let num_leaves = match rest.split_first() {
+            Some((0, [])) => None,
+            Some((1, rest)) => match read_u64(rest)? {
+                (num_leaves, []) => Some(num_leaves),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(SuffixProofRef {
+            version,
+            num_suffix_elements,
+            proof,
+            num_leaves,
+        })
+    }
+
+    /// Verify that `suffix_elements` are the last leaves of a perfect tree with `num_leaves` leaves
+    /// and the given root.
+    pub fn verify(&self, root: &Digest, num_leaves: u64, suffix_elements: &[&[u8]]) -> bool {
+        if check_version(self.version).is_err()
+            || self.num_leaves.is_some_and(|n| n != num_leaves)
+            || !num_leaves.is_power_of_two()
+            || suffix_elements.len() as u64 != self.num_suffix_elements
+            || self.num_suffix_elements == 0
+            || self.num_suffix_elements > num_leaves
+        {
+            return false;
+        }
+        let first_suffix_index = num_leaves - self.num_suffix_elements;
+        let mut proof = self.proof.iter();
+        let Some(computed) = subtree_root(
+            0,
+            num_leaves,
+            first_suffix_index,
+            suffix_elements,
+            &mut proof,
+        ) else {
+            return false;
+        };
+        proof.next().is_none() && computed == *root
+    }
+
+    /// Verify that `suffix_elements` are the last leaves of the tree with the given root, taking
+    /// the tree size from the proof.
+    pub fn verify_standalone(&self, root: &Digest, suffix_elements: &[&[u8]]) -> bool {
+        self.num_leaves
+            .is_some_and(|num_leaves| self.verify(root, num_leaves, suffix_elements))
+    }
+}
+
+/// Borrowed counterpart of `InclusionProof`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InclusionProofRef<'a> {
+    pub version: u8,
+    pub leaf_index: u64,
+    pub siblings: DigestListRef<'a>,
+}
+
+impl<'a> InclusionProofRef<'a> {
+    /// View a BCS-encoded `InclusionProof` without copying it.
+    pub fn from_bcs(bytes: &'a [u8]) -> Option<Self> {
+        let (&version, bytes) = bytes.split_first()?;
+        let (leaf_index, bytes) = read_u64(bytes)?;
+        let (siblings, rest) = DigestListRef::parse(bytes)?;
+        if !rest.is_empty() {
+            return None;
+        }
+        Some(InclusionProofRef {
+            version,
+            leaf_index,
+            siblings,
+        })
+    }
+
+    pub fn verify(&self, root: &Digest, num_leaves: u64, leaf: &[u8]) -> bool {
+        if check_version(self.version).is_err()
+            || !num_leaves.is_power_of_two()
+            || self.leaf_index >= num_leaves
+            || self.siblings.len() != num_leaves.trailing_zeros() as usize
+        {
+            return false;
+        }
+        let mut current = hash_leaf(leaf);
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            current = if (self.leaf_index >> level) & 1 == 0 {
+                hash_children(&current, &sibling)
+            } else {
+                hash_children(&sibling, &current)
+            };
+        }
+        current == *root
+    }
+}
+
+// Recompute the root of the subtree covering leaves [start, start + size), consuming the proof's
+// left siblings top-down in the order `collect_proof_nodes` emits them. Recursion depth is the
+// height of the tree, so this needs no heap memory.
+fn subtree_root(
+    start: u64,
+    size: u64,
+    first_suffix_index: u64,
+    suffix_elements: &[&[u8]],
+    proof: &mut impl Iterator<Item = Digest>,
+) -> Option<Digest> {
+    if size == 1 {
+        return Some(hash_leaf(
+            suffix_elements[(start - first_suffix_index) as usize],
+        ));
+    }
+    let mid = start + size / 2;
+    let left = if first_suffix_index >= mid {
+        proof.next()?
+    } else {
+        subtree_root(start, size / 2, first_suffix_index, suffix_elements, proof)?
+    };
+    let right = subtree_root(mid, size / 2, first_suffix_index, suffix_elements, proof)?;
+    Some(hash_children(&left, &right))
+}
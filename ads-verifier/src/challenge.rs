@@ -0,0 +1,109 @@
+//! Spot-check challenges an auditor issues to the operator of a log.
+//!
+//! A challenge asks for the entries at indices derived from the commitment to the log and a nonce
+//! the auditor picks: the samples of a `Sampler` seeded with the commitment's digest followed by
+//! the nonce. Both sides compute them alike, so the challenge is just the nonce, and the operator
+//! can't predict the indices before publishing the commitment as long as the nonce is kept secret
+//! until then. The operator answers with a `ChallengeResponse`: one multi-inclusion proof of the
+//! challenged entries.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{MmrCommitment, MmrMultiInclusionProof};
+use crate::spot::Sampler;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct ChallengeResponse {
+    pub version: u8,
+    pub proof: MmrMultiInclusionProof,
+}
+
+impl ChallengeResponse {
+    pub fn new(proof: MmrMultiInclusionProof) -> Self {
+        ChallengeResponse {
+            version: PROOF_FORMAT_VERSION,
+            proof,
+        }
+    }
+
+    /// The challenged entries, in increasing order of index.
+    pub fn entries(&self) -> &[Vec<u8>] {
+        &self.proof.entries
+    }
+
+    /// Verify that the response proves exactly the `count` entries challenged by `nonce`.
+    pub fn verify(
+        &self,
+        commitment: &MmrCommitment,
+        nonce: &[u8],
+        count: u64,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_hasher(
+            &NodeHasher::new(commitment.hash_algo()),
+            commitment,
+            nonce,
+            count,
+        )
+    }
+
+    /// Same as `verify`, for an MMR hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        commitment: &MmrCommitment,
+        nonce: &[u8],
+        count: u64,
+    ) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        commitment.verify_multi_inclusion_with_hasher(hasher, &self.proof)?;
+        let proven = self
+            .proof
+            .tree_proofs
+            .iter()
+            .flat_map(|(tree_index, proof)| {
+                proof
+                    .leaf_indices
+                    .iter()
+                    .map(|&leaf_index| commitment.entry_index(*tree_index, leaf_index))
+            });
+        let challenged = commitment.challenge_indices(nonce, count);
+        if !proven.eq(challenged.into_iter().map(Some)) {
+            return Err(VerificationError::NotChallenged);
+        }
+        Ok(())
+    }
+}
+
+impl MmrCommitment {
+    /// The indices of the entries challenged by `nonce`: `count` distinct ones, or all of them if
+    /// there are fewer entries, in increasing order.
+    pub fn challenge_indices(&self, nonce: &[u8], count: u64) -> Vec<u64> {
+        let num_entries = self.num_entries();
+        if count >= num_entries {
+            return (0..num_entries).collect();
+        }
+        let mut sampler = self.sampler(nonce);
+        let mut indices = BTreeSet::new();
+        while (indices.len() as u64) < count {
+            indices.insert(sampler.next_index(num_entries));
+        }
+        indices.into_iter().collect()
+    }
+
+    // Samples of this commitment and `nonce`
+    pub(crate) fn sampler(&self, nonce: &[u8]) -> Sampler {
+        let mut seed = self.digest().0.to_vec();
+        seed.extend_from_slice(nonce);
+        Sampler::new(&seed)
+    }
+}
@@ -0,0 +1,113 @@
+//! Proofs about Poseidon trees as circuit witnesses.
+//!
+//! Merkle gadgets (arkworks' `Path`, halo2's and circomlib's Merkle checks) take a path as the
+//! sibling of the node at each level, from the leaf up, and one bit per level saying whether that
+//! node is the right child. `CircuitInclusionProof` is that layout, with every digest as an element
+//! of the BN254 scalar field. `CircuitSuffixProof` is the same for the last leaves of a tree,
+//! whose path bits are those of the index of the first of them.
+//!
+//! Leaves are exported hashed, so a circuit only needs the node hash (circomlib's `Poseidon(2)`);
+//! one that also checks the leaf value must hash it as `ads_core::poseidon::LeafHasher` does.
+
+use alloc::vec::Vec;
+
+use ads_core::poseidon::{self, Fr};
+use ads_core::HashAlgo;
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::proof::{InclusionProof, SuffixProof};
+
+/// An inclusion proof in the layout of Merkle gadgets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitInclusionProof {
+    /// Hash of the proven leaf
+    pub leaf: Fr,
+    /// Sibling of the node at each level, from the leaf level up to the children of the root
+    pub path_elements: Vec<Fr>,
+    /// Whether the node at each level is the right child, i.e., the bits of the leaf index
+    pub path_indices: Vec<bool>,
+    pub root: Fr,
+}
+
+/// A suffix proof in the layout of Merkle gadgets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitSuffixProof {
+    /// Hashes of the proven leaves, in order
+    pub leaves: Vec<Fr>,
+    /// The proof's digests, in the order of `SuffixProof::proof`
+    pub proof: Vec<Fr>,
+    /// Bits of the index of the first proven leaf, from the leaf level up. At each level whose
+    /// bit is set, the first node of the suffix is a right child and takes its left sibling from
+    /// the end of `proof`.
+    pub start_index_bits: Vec<bool>,
+    pub root: Fr,
+}
+
+// A digest that isn't the canonical encoding of a field element can't be a Poseidon hash, and
+// exporting it reduced would make the witness disagree with the proof
+fn to_field(digest: &Digest) -> Result<Fr, VerificationError> {
+    poseidon::to_canonical_field(digest).ok_or(VerificationError::NonCanonicalProof)
+}
+
+// Indices have no bits set past 64 levels
+fn bits(index: u64, depth: usize) -> Vec<bool> {
+    (0..depth)
+        .map(|level| level < 64 && (index >> level) & 1 == 1)
+        .collect()
+}
+
+impl InclusionProof {
+    /// The witness of this proof of `leaf` in a Poseidon tree, whose root it recomputes.
+    pub fn to_circuit(&self, leaf: &[u8]) -> Result<CircuitInclusionProof, VerificationError> {
+        let hasher = NodeHasher::new(HashAlgo::Poseidon);
+        self.to_circuit_from_leaf_hash(&hasher.hash_leaf(leaf))
+    }
+
+    /// Same as `to_circuit`, from the hash of the leaf instead of its value.
+    pub fn to_circuit_from_leaf_hash(
+        &self,
+        leaf_hash: &Digest,
+    ) -> Result<CircuitInclusionProof, VerificationError> {
+        let hasher = NodeHasher::new(HashAlgo::Poseidon);
+        Ok(CircuitInclusionProof {
+            leaf: to_field(leaf_hash)?,
+            path_elements: self
+                .siblings
+                .iter()
+                .map(to_field)
+                .collect::<Result<_, _>>()?,
+            path_indices: bits(self.leaf_index, self.siblings.len()),
+            root: poseidon::to_field(&self.compute_root_from_leaf_hash(&hasher, leaf_hash)),
+        })
+    }
+}
+
+impl SuffixProof {
+    /// The witness of this proof of `suffix_elements` in a Poseidon tree, whose root it
+    /// recomputes. The tree size is taken from the proof.
+    pub fn to_circuit(
+        &self,
+        suffix_elements: &[Vec<u8>],
+    ) -> Result<CircuitSuffixProof, VerificationError> {
+        let num_leaves = self.num_leaves.ok_or(VerificationError::MissingTreeSize)?;
+        if !num_leaves.is_power_of_two() {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        let hasher = NodeHasher::new(HashAlgo::Poseidon);
+        let root = self.compute_root_with_hasher(&hasher, num_leaves, suffix_elements)?;
+        Ok(CircuitSuffixProof {
+            leaves: suffix_elements
+                .iter()
+                .map(|element| poseidon::to_field(&hasher.hash_leaf(element)))
+                .collect(),
+            proof: self.proof.iter().map(to_field).collect::<Result<_, _>>()?,
+            start_index_bits: bits(
+                num_leaves - self.num_suffix_elements,
+                num_leaves.trailing_zeros() as usize,
+            ),
+            root: poseidon::to_field(&root),
+        })
+    }
+}
@@ -0,0 +1,175 @@
+//! Proofs that a log was compacted: rebuilt without its deleted entries, and nothing else changed.
+//!
+//! Compaction drops every entry it lists as deleted along with its tombstone, whose index would
+//! otherwise point at the wrong entry, and keeps the other entries in order. Both logs start with
+//! the entries before the first one dropped, so the proof commits to that prefix with a
+//! consistency proof against the old commitment instead of listing it. Past the prefix, it gives
+//! the leaf hash of each deleted entry and the index of its tombstone, whose leaf hash the verifier
+//! computes from its encoding, and the leaf hashes of the entries kept. Appended to the prefix in
+//! their old order, all of them must give the old commitment, and the kept ones alone the new one.
+//!
+//! The proof is logarithmic in the entries before the first deletion. The kept entries after it
+//! move to other positions, which changes every tree they're in, so their leaf hashes are needed.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::consistency::ConsistencyProof;
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::error::VerificationError;
+use crate::frontier::Frontier;
+use crate::hash::NodeHasher;
+use crate::mmr::MmrCommitment;
+use crate::proof::check_canonical;
+use crate::tombstone::Tombstone;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct CompactionProof {
+    pub version: u8,
+    /// The old log up to its first dropped entry, which the new log starts with too
+    pub prefix: MmrCommitment,
+    /// That the old log extends `prefix`
+    pub consistency: ConsistencyProof,
+    /// Indices in the old log of the deleted entries that were dropped, strictly increasing
+    pub deleted: Vec<u64>,
+    /// Index in the old log of the tombstone of each deleted entry, the first one after it
+    pub tombstones: Vec<u64>,
+    /// Leaf hash of each deleted entry
+    pub deleted_leaf_hashes: Vec<Digest>,
+    /// Leaf hashes of the entries kept after the prefix, in order
+    pub kept_leaf_hashes: Vec<Digest>,
+}
+
+impl CompactionProof {
+    pub fn new(
+        prefix: MmrCommitment,
+        consistency: ConsistencyProof,
+        deleted: Vec<(u64, u64, Digest)>,
+        kept_leaf_hashes: Vec<Digest>,
+    ) -> Self {
+        CompactionProof {
+            version: PROOF_FORMAT_VERSION,
+            prefix,
+            consistency,
+            deleted: deleted.iter().map(|&(index, _, _)| index).collect(),
+            tombstones: deleted.iter().map(|&(_, tombstone, _)| tombstone).collect(),
+            deleted_leaf_hashes: deleted.iter().map(|&(_, _, leaf)| leaf).collect(),
+            kept_leaf_hashes,
+        }
+    }
+
+    /// Verify that `new` commits to the entries of `old` but the deleted ones and their
+    /// tombstones, in the same order.
+    pub fn verify(
+        &self,
+        old: &MmrCommitment,
+        new: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_hasher(&NodeHasher::new(old.hash_algo()), old, new)
+    }
+
+    /// Same as `verify`, for logs hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        old: &MmrCommitment,
+        new: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        // Compaction rehashes nothing, so both logs are hashed the same way
+        old.check_hash_algo(hasher, new.hash_algo())?;
+        check_canonical(
+            hasher,
+            self.deleted_leaf_hashes
+                .iter()
+                .chain(&self.kept_leaf_hashes),
+        )?;
+        if self.tombstones.len() != self.deleted.len()
+            || self.deleted_leaf_hashes.len() != self.deleted.len()
+            || !self.deleted.windows(2).all(|pair| pair[0] < pair[1])
+        {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+
+        // The leaf hash of every dropped entry by its index, and the deleted entry and tombstone
+        // index of each tombstone by its leaf hash
+        let num_entries = old.num_entries();
+        let mut dropped = BTreeMap::<u64, Digest>::new();
+        let mut tombstones = BTreeMap::<[u8; DIGEST_LENGTH], (u64, u64)>::new();
+        for ((&index, &tombstone), &leaf) in self
+            .deleted
+            .iter()
+            .zip(&self.tombstones)
+            .zip(&self.deleted_leaf_hashes)
+        {
+            for i in [index, tombstone] {
+                if i >= num_entries {
+                    return Err(VerificationError::LeafIndexOutOfBounds(i));
+                }
+            }
+            if tombstone <= index {
+                return Err(VerificationError::NotDeleted);
+            }
+            let tombstone_leaf = hasher.hash_leaf(&Tombstone::new(index).encode());
+            tombstones.insert(tombstone_leaf.0, (index, tombstone));
+            if dropped.insert(index, leaf).is_some()
+                || dropped.insert(tombstone, tombstone_leaf).is_some()
+            {
+                return Err(VerificationError::NonCanonicalProof);
+            }
+        }
+
+        // Both logs start with the entries before the first dropped one
+        let prefix_len = dropped.keys().next().copied().unwrap_or(num_entries);
+        if self.prefix.num_entries() != prefix_len {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        old.verify_consistency_with_hasher(hasher, &self.prefix, &self.consistency)?;
+        let mut old_frontier = Frontier::from_commitment(self.prefix.clone())
+            .map_err(VerificationError::InvalidCommitment)?;
+        let mut new_frontier = old_frontier.clone();
+
+        let mut kept = self.kept_leaf_hashes.iter();
+        for i in prefix_len..num_entries {
+            if let Some(leaf) = dropped.get(&i) {
+                old_frontier.push(hasher, *leaf);
+                continue;
+            }
+            let leaf = *kept
+                .next()
+                .ok_or(VerificationError::NotEnoughProofElements)?;
+            // A kept tombstone of a dropped entry must come after the one dropped with it
+            if let Some(&(index, tombstone)) = tombstones.get(&leaf.0) {
+                if index < i && i < tombstone {
+                    return Err(VerificationError::NonCanonicalProof);
+                }
+            }
+            old_frontier.push(hasher, leaf);
+            new_frontier.push(hasher, leaf);
+        }
+        if kept.next().is_some() {
+            return Err(VerificationError::UnusedProofElements);
+        }
+
+        if old_frontier.commitment().peaks != old.peaks {
+            return Err(VerificationError::RootMismatch);
+        }
+        if new_frontier.num_entries() != new.num_entries() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: new.num_entries(),
+                actual: new_frontier.num_entries(),
+            });
+        }
+        if new_frontier.commitment().peaks != new.peaks {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+}
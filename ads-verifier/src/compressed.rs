@@ -0,0 +1,348 @@
+//! Proof bundles and their compressed encoding.
+//!
+//! A `ProofBundle` holds several proofs against the same MMR commitment, e.g., a wide suffix and
+//! the inclusion of a few older entries. Its BCS encoding repeats whatever the proofs share: the
+//! digests of trees and subtrees two proofs both need, their versions and hash algorithm, and
+//! every index and count as a full `u64`.
+//!
+//! The compressed encoding writes each distinct digest once, in a table the proofs refer to by
+//! position, the version and hash algorithm once, integers as LEB128 varints, and sets of trees
+//! as bitmasks (paths within a tree follow the bits of the leaf index, so they need no directions
+//! of their own). Decoding accepts exactly one encoding of each bundle: digests appear in the
+//! table in the order the proofs first use them, and varints have no redundant bytes.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ads_core::HashAlgo;
+use serde::{Deserialize, Serialize};
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::error::VerificationError;
+use crate::mmr::{MmrCommitment, MmrInclusionProof, MostRecentNElementsProof};
+use crate::proof::{InclusionProof, SuffixProof};
+use crate::version::PROOF_FORMAT_VERSION;
+
+/// Proofs against the same MMR commitment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub inclusion_proofs: Vec<MmrInclusionProof>,
+    pub suffix_proofs: Vec<MostRecentNElementsProof>,
+}
+
+/// The compressed encoding of a `ProofBundle`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompressedProofBundle(pub Vec<u8>);
+
+impl MmrCommitment {
+    /// Verify every proof of the bundle.
+    pub fn verify_bundle(&self, bundle: &ProofBundle) -> Result<(), VerificationError> {
+        for proof in &bundle.inclusion_proofs {
+            self.verify_inclusion(proof)?;
+        }
+        for proof in &bundle.suffix_proofs {
+            self.verify_most_recent_n_elements(proof)?;
+        }
+        Ok(())
+    }
+
+    /// Decompress the bundle and verify every proof of it.
+    pub fn verify_compressed_bundle(
+        &self,
+        bundle: &CompressedProofBundle,
+    ) -> Result<(), VerificationError> {
+        self.verify_bundle(&bundle.decompress()?)
+    }
+}
+
+impl ProofBundle {
+    /// The compressed encoding of the bundle. Its proofs must all be of the current format
+    /// version and about an MMR hashed with the same algorithm.
+    pub fn compress(&self) -> Result<CompressedProofBundle, VerificationError> {
+        let algo = self.hash_algo()?;
+        let mut writer = Writer::default();
+
+        writer.varint(self.inclusion_proofs.len() as u64);
+        for proof in &self.inclusion_proofs {
+            current_version(proof.version)?;
+            current_version(proof.proof.version)?;
+            writer.varint(proof.tree_index);
+            writer.varint(proof.proof.leaf_index);
+            writer.blob(&proof.entry);
+            writer.digests(&proof.proof.siblings);
+        }
+
+        writer.varint(self.suffix_proofs.len() as u64);
+        for proof in &self.suffix_proofs {
+            current_version(proof.version)?;
+            proof.check_shape()?;
+            writer.varint(proof.entries.len() as u64);
+            for entry in &proof.entries {
+                writer.blob(entry);
+            }
+            // The shape of the proof makes tree indices strictly increasing and below 64
+            let trees = proof
+                .full_tree_indices
+                .iter()
+                .fold(0u64, |mask, &index| mask | 1 << index);
+            writer.varint(trees);
+            match &proof.partial_tree_proof {
+                None => writer.bytes.push(0),
+                Some((tree_index, suffix_proof)) => {
+                    current_version(suffix_proof.version)?;
+                    // The tree size follows from the tree index
+                    if suffix_proof.num_leaves != tree_size(*tree_index) {
+                        return Err(VerificationError::NonCanonicalProof);
+                    }
+                    writer.bytes.push(1);
+                    writer.varint(*tree_index);
+                    writer.varint(suffix_proof.num_suffix_elements);
+                    writer.digests(&suffix_proof.proof);
+                }
+            }
+        }
+
+        let mut compressed = vec![PROOF_FORMAT_VERSION, algo_tag(algo)];
+        compressed.extend(encode_varint(writer.table.len() as u64));
+        for digest in &writer.table {
+            compressed.extend_from_slice(&digest.0);
+        }
+        compressed.extend(writer.bytes);
+        Ok(CompressedProofBundle(compressed))
+    }
+
+    // The algorithm all proofs agree on, Blake2b256 for an empty bundle
+    fn hash_algo(&self) -> Result<HashAlgo, VerificationError> {
+        let mut algos = self
+            .inclusion_proofs
+            .iter()
+            .map(MmrInclusionProof::hash_algo)
+            .chain(
+                self.suffix_proofs
+                    .iter()
+                    .map(MostRecentNElementsProof::hash_algo),
+            );
+        let Some(first) = algos.next() else {
+            return Ok(HashAlgo::default());
+        };
+        match algos.find(|&algo| algo != first) {
+            Some(other) => Err(VerificationError::HashAlgoMismatch {
+                expected: first,
+                actual: other,
+            }),
+            None => Ok(first),
+        }
+    }
+}
+
+impl CompressedProofBundle {
+    /// The bundle this encodes, whose proofs are well-formed but not verified.
+    pub fn decompress(&self) -> Result<ProofBundle, VerificationError> {
+        let mut reader = Reader {
+            bytes: &self.0,
+            table: Vec::new(),
+            used: 0,
+        };
+        let version = reader.byte()?;
+        current_version(version)?;
+        let algo = algo_from_tag(reader.byte()?)?;
+        let num_digests = reader.count(DIGEST_LENGTH)?;
+        for _ in 0..num_digests {
+            let digest = reader.take(DIGEST_LENGTH)?;
+            reader.table.push(Digest::from_slice(digest).unwrap());
+        }
+
+        let mut bundle = ProofBundle::default();
+        for _ in 0..reader.count(1)? {
+            let tree_index = reader.varint()?;
+            let leaf_index = reader.varint()?;
+            let entry = reader.blob()?;
+            let siblings = reader.digests()?;
+            let proof = MmrInclusionProof {
+                version,
+                entry,
+                tree_index,
+                proof: InclusionProof::new(leaf_index, siblings),
+                hash_algo: None,
+            };
+            bundle.inclusion_proofs.push(proof.with_hash_algo(algo));
+        }
+        for _ in 0..reader.count(1)? {
+            let entries = (0..reader.count(1)?)
+                .map(|_| reader.blob())
+                .collect::<Result<Vec<_>, _>>()?;
+            let trees = reader.varint()?;
+            let full_tree_indices = (0..u64::BITS as u64)
+                .filter(|&index| trees >> index & 1 == 1)
+                .collect();
+            let partial_tree_proof = match reader.byte()? {
+                0 => None,
+                1 => {
+                    let tree_index = reader.varint()?;
+                    let num_suffix_elements = reader.varint()?;
+                    let num_leaves = tree_size(tree_index)
+                        .ok_or(VerificationError::TreeIndexOutOfBounds(tree_index))?;
+                    let proof = reader.digests()?;
+                    Some((
+                        tree_index,
+                        SuffixProof::new(num_leaves, num_suffix_elements, proof),
+                    ))
+                }
+                _ => return Err(VerificationError::NonCanonicalProof),
+            };
+            let proof = MostRecentNElementsProof {
+                version,
+                entries,
+                full_tree_indices,
+                partial_tree_proof,
+                hash_algo: None,
+            }
+            .with_hash_algo(algo);
+            proof.check_shape()?;
+            bundle.suffix_proofs.push(proof);
+        }
+        if !reader.bytes.is_empty() || reader.used != reader.table.len() {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        Ok(bundle)
+    }
+}
+
+// Only proofs of the current version are compressed, so that decompressing gives them back as
+// they were
+fn current_version(version: u8) -> Result<(), VerificationError> {
+    if version != PROOF_FORMAT_VERSION {
+        return Err(VerificationError::UnsupportedVersion(version));
+    }
+    Ok(())
+}
+
+// Number of leaves of the tree at `tree_index`
+fn tree_size(tree_index: u64) -> Option<u64> {
+    u32::try_from(tree_index)
+        .ok()
+        .and_then(|index| 1u64.checked_shl(index))
+}
+
+fn algo_tag(algo: HashAlgo) -> u8 {
+    HashAlgo::ALL.iter().position(|&a| a == algo).unwrap() as u8
+}
+
+fn algo_from_tag(tag: u8) -> Result<HashAlgo, VerificationError> {
+    HashAlgo::ALL
+        .get(tag as usize)
+        .copied()
+        .ok_or(VerificationError::NonCanonicalProof)
+}
+
+fn encode_varint(mut value: u64) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    while value >= 0x80 {
+        encoded.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    encoded.push(value as u8);
+    encoded
+}
+
+// The proofs' part of the encoding, and the digests it refers to in order of first use
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+    table: Vec<Digest>,
+}
+
+impl Writer {
+    fn varint(&mut self, value: u64) {
+        self.bytes.extend(encode_varint(value));
+    }
+
+    fn blob(&mut self, blob: &[u8]) {
+        self.varint(blob.len() as u64);
+        self.bytes.extend_from_slice(blob);
+    }
+
+    fn digests(&mut self, digests: &[Digest]) {
+        self.varint(digests.len() as u64);
+        for digest in digests {
+            let position = match self.table.iter().position(|d| d == digest) {
+                Some(position) => position,
+                None => {
+                    self.table.push(*digest);
+                    self.table.len() - 1
+                }
+            };
+            self.varint(position as u64);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    table: Vec<Digest>,
+    // Digests of the table referred to so far, which are its first ones
+    used: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], VerificationError> {
+        if self.bytes.len() < n {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, VerificationError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, VerificationError> {
+        let mut value = 0u64;
+        for shift in (0..u64::BITS).step_by(7) {
+            let byte = self.byte()?;
+            let digit = u64::from(byte & 0x7f);
+            // No bits past 64, and no trailing zero digit
+            if digit << shift >> shift != digit || (shift > 0 && byte == 0) {
+                return Err(VerificationError::NonCanonicalProof);
+            }
+            value |= digit << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(VerificationError::NonCanonicalProof)
+    }
+
+    // A count of items of at least `min_size` bytes each, checked against the remaining bytes
+    // before anything is allocated for them
+    fn count(&mut self, min_size: usize) -> Result<usize, VerificationError> {
+        usize::try_from(self.varint()?)
+            .ok()
+            .filter(|&count| count.saturating_mul(min_size) <= self.bytes.len())
+            .ok_or(VerificationError::NonCanonicalProof)
+    }
+
+    fn blob(&mut self) -> Result<Vec<u8>, VerificationError> {
+        let len = self.count(1)?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn digests(&mut self) -> Result<Vec<Digest>, VerificationError> {
+        (0..self.count(1)?)
+            .map(|_| {
+                let position = usize::try_from(self.varint()?)
+                    .map_err(|_| VerificationError::NonCanonicalProof)?;
+                // A digest not referred to before is the next one of the table
+                if position > self.used || position >= self.table.len() {
+                    return Err(VerificationError::NonCanonicalProof);
+                }
+                if position == self.used {
+                    self.used += 1;
+                }
+                Ok(self.table[position])
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,270 @@
+//! Proofs that an MMR only appended to an older one.
+//!
+//! The trees of an MMR split its entries into aligned blocks of decreasing size, so each tree of
+//! the older MMR is a subtree of the tree of the newer MMR holding its first entry. A consistency
+//! proof gives, for each older tree, the siblings on the path from its root up to the root of that
+//! newer tree. Together, the older trees cover all older entries, so the newer MMR starts with
+//! exactly those entries.
+//!
+//! A client who also wants the appended entries gets an `AppendedRangeProof` instead: the peaks of
+//! the older MMR are all that's needed to hash them in, so the entries alone show how the older
+//! MMR became the newer one.
+
+use alloc::vec::Vec;
+
+use ads_core::encoding::{digests_length, uleb128_length};
+use ads_core::Proof;
+use serde::{Deserialize, Serialize};
+
+use crate::audit::MmrAuditor;
+use crate::digest::Digest;
+#[cfg(feature = "borsh")]
+use crate::error::invalid_data;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::MmrCommitment;
+use crate::proof::{check_canonical, entries_length, MAX_HEIGHT, VERSION_LENGTH};
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// Proof that an MMR extends an older one.
+///
+/// Deserializing with serde or borsh checks `check_shape`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawConsistencyProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct ConsistencyProof {
+    pub version: u8,
+    // One path per tree of the older MMR, from the oldest (largest) tree to the most recent one.
+    // Siblings are ordered from the older tree's root up to the children of the newer tree's root.
+    pub paths: Vec<Vec<Digest>>,
+}
+
+// `ConsistencyProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawConsistencyProof {
+    version: u8,
+    paths: Vec<Vec<Digest>>,
+}
+
+impl TryFrom<RawConsistencyProof> for ConsistencyProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawConsistencyProof) -> Result<Self, VerificationError> {
+        let proof = ConsistencyProof {
+            version: raw.version,
+            paths: raw.paths,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for ConsistencyProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawConsistencyProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+impl ConsistencyProof {
+    pub fn new(paths: Vec<Vec<Digest>>) -> Self {
+        ConsistencyProof {
+            version: PROOF_FORMAT_VERSION,
+            paths,
+        }
+    }
+
+    /// Check the invariants that hold regardless of the commitments: at most one path per tree
+    /// of a u64 MMR, each shorter than the height of the largest tree.
+    pub fn check_shape(&self) -> Result<(), VerificationError> {
+        if self.paths.len() > MAX_HEIGHT || self.paths.iter().any(|path| path.len() >= MAX_HEIGHT) {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(())
+    }
+}
+
+/// Proof of exactly which entries were appended to an MMR to make a newer one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct AppendedRangeProof {
+    pub version: u8,
+    /// The appended entries, in order
+    pub entries: Vec<Vec<u8>>,
+}
+
+impl AppendedRangeProof {
+    pub fn new(entries: Vec<Vec<u8>>) -> Self {
+        AppendedRangeProof {
+            version: PROOF_FORMAT_VERSION,
+            entries,
+        }
+    }
+}
+
+impl MmrCommitment {
+    /// Verify that this MMR is the `old` one with the entries of `proof` appended.
+    pub fn verify_appended_range(
+        &self,
+        old: &MmrCommitment,
+        proof: &AppendedRangeProof,
+    ) -> Result<(), VerificationError> {
+        check_version(proof.version)?;
+        self.verify_appended_entries(old, &proof.entries)
+    }
+
+    /// Verify that this MMR is the `old` one with `entries` appended.
+    pub fn verify_appended_entries<E: AsRef<[u8]>>(
+        &self,
+        old: &MmrCommitment,
+        entries: &[E],
+    ) -> Result<(), VerificationError> {
+        self.verify_appended_entries_with_hasher(&NodeHasher::new(self.hash_algo()), old, entries)
+    }
+
+    /// Same as `verify_appended_entries`, for MMRs hashed with `hasher`.
+    pub fn verify_appended_entries_with_hasher<E: AsRef<[u8]>>(
+        &self,
+        hasher: &NodeHasher,
+        old: &MmrCommitment,
+        entries: &[E],
+    ) -> Result<(), VerificationError> {
+        check_version(old.version)?;
+        self.check_hash_algo(hasher, old.hash_algo())?;
+        let mut auditor = MmrAuditor::from_commitment_with_hasher(old, *hasher)
+            .map_err(VerificationError::InvalidCommitment)?;
+        for entry in entries {
+            auditor.push(entry.as_ref());
+        }
+        auditor.check(self)
+    }
+
+    /// Verify that this MMR holds the entries of the `old` one, followed by zero or more others.
+    pub fn verify_consistency(
+        &self,
+        old: &MmrCommitment,
+        proof: &ConsistencyProof,
+    ) -> Result<(), VerificationError> {
+        self.verify_consistency_with_hasher(&NodeHasher::new(self.hash_algo()), old, proof)
+    }
+
+    /// Same as `verify_consistency`, for MMRs hashed with `hasher`.
+    pub fn verify_consistency_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        old: &MmrCommitment,
+        proof: &ConsistencyProof,
+    ) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        check_version(old.version)?;
+        check_version(proof.version)?;
+        self.check_hash_algo(hasher, old.hash_algo())?;
+        check_canonical(hasher, proof.paths.iter().flatten())?;
+        let num_entries = self.num_entries();
+        let old_num_entries = old.num_entries();
+        if old_num_entries > num_entries {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: num_entries,
+                actual: old_num_entries,
+            });
+        }
+
+        let old_trees: Vec<u64> = old.tree_indices().collect();
+        let mut paths = proof.paths.iter();
+        let mut first_entry = 0;
+        for &height in old_trees.iter().rev() {
+            let path = paths
+                .next()
+                .ok_or(VerificationError::NotEnoughProofElements)?;
+            // The newer tree holding the older tree's first entry, and where the older tree sits
+            // among its subtrees of the same height
+            let (tree_index, leaf_index) = self.locate(first_entry);
+            let subtree_index = leaf_index >> height;
+            if path.len() as u64 != tree_index - height {
+                return Err(VerificationError::NonCanonicalProof);
+            }
+            let mut current = *old.digest_at(height)?;
+            for (level, sibling) in path.iter().enumerate() {
+                current = if (subtree_index >> level) & 1 == 0 {
+                    hasher.hash_children(&current, sibling)
+                } else {
+                    hasher.hash_children(sibling, &current)
+                };
+            }
+            if *self.digest_at(tree_index)? != current {
+                return Err(VerificationError::RootMismatch);
+            }
+            first_entry += 1 << height;
+        }
+        if paths.next().is_some() {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(())
+    }
+
+    // The tree holding the entry at `index` (less than `num_entries`) and its leaf index there
+    fn locate(&self, index: u64) -> (u64, u64) {
+        // Larger trees hold older entries
+        let mut offset = 0;
+        let tree_indices: Vec<u64> = self.tree_indices().collect();
+        for &tree_index in tree_indices.iter().rev() {
+            if index < offset + (1 << tree_index) {
+                return (tree_index, index - offset);
+            }
+            offset += 1 << tree_index;
+        }
+        unreachable!("Index beyond the committed entries");
+    }
+}
+
+/// Checked against the newer commitment, for the older one.
+impl Proof for ConsistencyProof {
+    type Commitment = MmrCommitment;
+    type Claim = MmrCommitment;
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(
+        &self,
+        commitment: &MmrCommitment,
+        old: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        commitment.verify_consistency(old, self)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH
+            + uleb128_length(self.paths.len())
+            + self
+                .paths
+                .iter()
+                .map(|path| digests_length(path.len()))
+                .sum::<usize>()
+    }
+}
+
+/// Checked against the newer commitment, for the older one.
+impl Proof for AppendedRangeProof {
+    type Commitment = MmrCommitment;
+    type Claim = MmrCommitment;
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(
+        &self,
+        commitment: &MmrCommitment,
+        old: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        commitment.verify_appended_range(old, self)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH + entries_length(&self.entries)
+    }
+}
@@ -0,0 +1,245 @@
+//! Certificate Transparency (RFC 6962) audit paths and consistency proofs, as served by CT logs,
+//! so that a monitor can check a log with this crate.
+//!
+//! CT trees differ from this crate's: leaves are `SHA-256(0x00 || leaf)`, nodes `SHA-256(0x01 ||
+//! left || right)`, and a tree of any size is a single tree whose left subtree holds the largest
+//! power of two of leaves smaller than its size. Proofs are verified with the algorithms of RFC
+//! 9162, section 2.1, and fail with the same `VerificationError`s as this crate's.
+//!
+//! The response types deserialize from the JSON of the log API (`get-sth`, `get-proof-by-hash`,
+//! `get-sth-consistency`), with digests in base64. Tree head signatures are parsed but not checked.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ads_core::HashAlgo;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+fn sha256(parts: &[&[u8]]) -> Digest {
+    let mut hasher = NodeHasher::new(HashAlgo::Sha256).message_hasher();
+    parts.iter().for_each(|part| hasher.update(part));
+    hasher.finalize()
+}
+
+/// Hash of a leaf, given its `MerkleTreeLeaf` structure (`leaf_input` of `get-entries`).
+pub fn leaf_hash(leaf_input: &[u8]) -> Digest {
+    sha256(&[&[LEAF_PREFIX], leaf_input])
+}
+
+/// Hash of an internal node.
+pub fn hash_children(left: &Digest, right: &Digest) -> Digest {
+    sha256(&[&[NODE_PREFIX], &left.0, &right.0])
+}
+
+/// The root of the tree over `leaf_hashes`, the SHA-256 of nothing for no leaf.
+pub fn root(leaf_hashes: &[Digest]) -> Digest {
+    match leaf_hashes.len() {
+        0 => sha256(&[]),
+        1 => leaf_hashes[0],
+        n => {
+            let split = 1 << (usize::BITS - 1 - (n - 1).leading_zeros());
+            hash_children(&root(&leaf_hashes[..split]), &root(&leaf_hashes[split..]))
+        }
+    }
+}
+
+/// A signed tree head (`get-sth`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedTreeHead {
+    pub tree_size: u64,
+    pub timestamp: u64,
+    #[serde(with = "base64_digest")]
+    pub sha256_root_hash: Digest,
+    #[serde(with = "base64_bytes")]
+    pub tree_head_signature: Vec<u8>,
+}
+
+/// The audit path of a leaf (`get-proof-by-hash`), siblings from the leaf up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CtInclusionProof {
+    pub leaf_index: u64,
+    #[serde(with = "base64_digests")]
+    pub audit_path: Vec<Digest>,
+}
+
+/// Proof that a tree extends an older one (`get-sth-consistency`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CtConsistencyProof {
+    #[serde(with = "base64_digests")]
+    pub consistency: Vec<Digest>,
+}
+
+impl CtInclusionProof {
+    /// Verify that the leaf with `leaf_hash` is at `leaf_index` of the tree with `tree_size`
+    /// leaves and the given root.
+    pub fn verify(
+        &self,
+        tree_size: u64,
+        root: &Digest,
+        leaf_hash: &Digest,
+    ) -> Result<(), VerificationError> {
+        if self.leaf_index >= tree_size {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.leaf_index));
+        }
+        let mut index = self.leaf_index;
+        let mut last = tree_size - 1;
+        let mut current = *leaf_hash;
+        for sibling in &self.audit_path {
+            if last == 0 {
+                return Err(VerificationError::UnusedProofElements);
+            }
+            if index & 1 == 1 || index == last {
+                current = hash_children(sibling, &current);
+                // Skip the levels where the node is the last one and has no sibling
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                current = hash_children(&current, sibling);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        if last != 0 {
+            return Err(VerificationError::NotEnoughProofElements);
+        }
+        if current != *root {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl CtConsistencyProof {
+    /// Verify that the tree with `second_size` leaves and root `second_root` starts with the
+    /// leaves of the one with `first_size` leaves and root `first_root`.
+    pub fn verify(
+        &self,
+        first_size: u64,
+        first_root: &Digest,
+        second_size: u64,
+        second_root: &Digest,
+    ) -> Result<(), VerificationError> {
+        if first_size > second_size {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: second_size,
+                actual: first_size,
+            });
+        }
+        if first_size == second_size {
+            if !self.consistency.is_empty() {
+                return Err(VerificationError::UnusedProofElements);
+            }
+            if first_root != second_root {
+                return Err(VerificationError::RootMismatch);
+            }
+            return Ok(());
+        }
+        // Every tree extends the empty one, but logs don't prove it
+        if first_size == 0 {
+            return Err(VerificationError::EmptyProof);
+        }
+
+        // The older tree's root is the first node of the path when it's a subtree of the newer
+        let mut path = self.consistency.iter();
+        let first = if first_size.is_power_of_two() {
+            first_root
+        } else {
+            path.next()
+                .ok_or(VerificationError::NotEnoughProofElements)?
+        };
+        let mut index = first_size - 1;
+        let mut last = second_size - 1;
+        while index & 1 == 1 {
+            index >>= 1;
+            last >>= 1;
+        }
+        let (mut first_hash, mut second_hash) = (*first, *first);
+        for node in path {
+            if last == 0 {
+                return Err(VerificationError::UnusedProofElements);
+            }
+            if index & 1 == 1 || index == last {
+                first_hash = hash_children(node, &first_hash);
+                second_hash = hash_children(node, &second_hash);
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                second_hash = hash_children(&second_hash, node);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        if last != 0 {
+            return Err(VerificationError::NotEnoughProofElements);
+        }
+        if first_hash != *first_root || second_hash != *second_root {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+fn decode<E: serde::de::Error>(encoded: &str) -> Result<Vec<u8>, E> {
+    BASE64_STANDARD.decode(encoded).map_err(E::custom)
+}
+
+fn decode_digest<E: serde::de::Error>(encoded: &str) -> Result<Digest, E> {
+    Digest::from_slice(&decode::<E>(encoded)?).ok_or_else(|| E::custom("not a 32-byte digest"))
+}
+
+mod base64_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        decode(&String::deserialize(deserializer)?)
+    }
+}
+
+mod base64_digest {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(digest: &Digest, serializer: S) -> Result<S::Ok, S::Error> {
+        base64_bytes::serialize(&digest.0, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Digest, D::Error> {
+        decode_digest(&String::deserialize(deserializer)?)
+    }
+}
+
+mod base64_digests {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(digests: &[Digest], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            digests
+                .iter()
+                .map(|digest| BASE64_STANDARD.encode(digest.0)),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Digest>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|encoded| decode_digest(encoded))
+            .collect()
+    }
+}
@@ -0,0 +1,124 @@
+//! Evidence that a log operator published commitments to two different logs.
+//!
+//! An honest operator only ever appends, so any two of its commitments are consistent: the same
+//! size means the same peaks, and an entry committed by one is the entry at the same index in the
+//! other. Evidence is either two commitments to the same number of entries that differ, or two
+//! inclusion proofs of different entries at the same index, each against one of the commitments.
+//! It carries everything needed to check it, so a monitor can hand it to anyone who trusts the
+//! commitments came from the operator (e.g., because they are signed).
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VerificationError;
+use crate::mmr::{MmrCommitment, MmrInclusionProof};
+
+/// Proof that two commitments can't both be to the same append-only log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum EquivocationEvidence {
+    /// Two commitments to the same number of entries, with different peaks
+    SameSize {
+        first: MmrCommitment,
+        second: MmrCommitment,
+    },
+    /// Two commitments, of any sizes, proving different entries at the same index
+    ConflictingEntries {
+        first: MmrCommitment,
+        first_proof: MmrInclusionProof,
+        second: MmrCommitment,
+        second_proof: MmrInclusionProof,
+    },
+}
+
+impl EquivocationEvidence {
+    /// Evidence from two commitments to the same number of entries, None if they are consistent
+    /// or about different sizes or hash algorithms.
+    pub fn from_commitments(first: &MmrCommitment, second: &MmrCommitment) -> Option<Self> {
+        let evidence = EquivocationEvidence::SameSize {
+            first: first.clone(),
+            second: second.clone(),
+        };
+        evidence.verify().is_ok().then_some(evidence)
+    }
+
+    /// Evidence from inclusion proofs against two commitments, None unless both verify and prove
+    /// different entries at the same index.
+    pub fn from_inclusion_proofs(
+        first: &MmrCommitment,
+        first_proof: &MmrInclusionProof,
+        second: &MmrCommitment,
+        second_proof: &MmrInclusionProof,
+    ) -> Option<Self> {
+        let evidence = EquivocationEvidence::ConflictingEntries {
+            first: first.clone(),
+            first_proof: first_proof.clone(),
+            second: second.clone(),
+            second_proof: second_proof.clone(),
+        };
+        evidence.verify().is_ok().then_some(evidence)
+    }
+
+    /// The two conflicting commitments.
+    pub fn commitments(&self) -> (&MmrCommitment, &MmrCommitment) {
+        match self {
+            EquivocationEvidence::SameSize { first, second }
+            | EquivocationEvidence::ConflictingEntries { first, second, .. } => (first, second),
+        }
+    }
+
+    /// Check that the evidence shows the two commitments conflict.
+    pub fn verify(&self) -> Result<(), VerificationError> {
+        let (first, second) = self.commitments();
+        // Logs hashed differently are different logs, not a fork of one
+        if first.hash_algo() != second.hash_algo() {
+            return Err(VerificationError::HashAlgoMismatch {
+                expected: first.hash_algo(),
+                actual: second.hash_algo(),
+            });
+        }
+        match self {
+            EquivocationEvidence::SameSize { .. } => {
+                if first.num_entries() != second.num_entries() {
+                    return Err(VerificationError::WrongNumberOfEntries {
+                        expected: first.num_entries(),
+                        actual: second.num_entries(),
+                    });
+                }
+                if first.digest() == second.digest() {
+                    return Err(VerificationError::NoEquivocation);
+                }
+                Ok(())
+            }
+            EquivocationEvidence::ConflictingEntries {
+                first_proof,
+                second_proof,
+                ..
+            } => {
+                first.verify_inclusion(first_proof)?;
+                second.verify_inclusion(second_proof)?;
+                let first_index = entry_index(first, first_proof)?;
+                if first_index != entry_index(second, second_proof)?
+                    || first_proof.entry == second_proof.entry
+                {
+                    return Err(VerificationError::NoEquivocation);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Index among all entries of the entry an inclusion proof verified against `commitment` proves
+fn entry_index(
+    commitment: &MmrCommitment,
+    proof: &MmrInclusionProof,
+) -> Result<u64, VerificationError> {
+    commitment
+        .entry_index(proof.tree_index, proof.proof.leaf_index)
+        .ok_or(VerificationError::LeafIndexOutOfBounds(
+            proof.proof.leaf_index,
+        ))
+}
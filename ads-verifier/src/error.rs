@@ -0,0 +1,206 @@
+//! Errors reported by proof verification.
+
+use core::fmt;
+
+use ads_core::HashAlgo;
+
+use crate::limits::Limit;
+use crate::mmr::CommitmentError;
+use crate::ordering::LeafOrdering;
+use crate::version::PROOF_FORMAT_VERSION;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerificationError {
+    /// The number of provided entries doesn't match the number the proof covers
+    WrongNumberOfEntries {
+        expected: u64,
+        actual: u64,
+    },
+    /// The proof covers no entries at all
+    EmptyProof,
+    TreeIndexOutOfBounds(u64),
+    MissingTree(u64),
+    /// The proof references the tree at this index more than once
+    DuplicateTreeIndex(u64),
+    /// The number of leaves isn't a power of two (or of the arity of a k-ary tree), so no perfect
+    /// tree has it
+    InvalidTreeSize(u64),
+    /// Nodes can't have this number of children
+    InvalidArity(u64),
+    /// The proof is about a tree of another arity than the commitment's
+    ArityMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    LeafIndexOutOfBounds(u64),
+    NotEnoughProofElements,
+    UnusedProofElements,
+    /// The recomputed root differs from the committed one
+    RootMismatch,
+    /// The proof or commitment was produced with a format this verifier doesn't know
+    UnsupportedVersion(u8),
+    /// The proof or commitment was produced with an older format, which this verifier no longer
+    /// verifies: it must be produced again
+    OutdatedVersion(u8),
+    /// The proof isn't laid out the one way the query and the tree sizes determine
+    NonCanonicalProof,
+    /// The entry at this index doesn't decode as the proof requires
+    MalformedEntry(u64),
+    /// Two structures committing to the same log hold different entries at this index
+    EntryMismatch(u64),
+    /// The proven entries don't show that no entry of the queried range was left out
+    IncompleteRange,
+    /// The body doesn't hash to the digest committed with its header
+    BodyMismatch,
+    /// The proof predates the field saying which tree it's about, so it can only be verified
+    /// against an independently known tree size
+    MissingTreeSize,
+    /// The proof, or the hasher verifying it, isn't for the commitment's hash algorithm
+    HashAlgoMismatch {
+        expected: HashAlgo,
+        actual: HashAlgo,
+    },
+    /// The commitment itself is malformed, e.g., doesn't have one peak per tree
+    InvalidCommitment(CommitmentError),
+    /// The evidence doesn't show the commitments conflict
+    NoEquivocation,
+    /// The commitment doesn't order its entries as the proof requires
+    LeafOrderingMismatch {
+        expected: LeafOrdering,
+        actual: LeafOrdering,
+    },
+    /// The proven entries don't enclose the value, which may be an entry
+    NotAbsent,
+    /// The element isn't the minimum of the queue
+    NotMinimum,
+    /// The entry isn't the top of the stack
+    NotTop,
+    /// The tombstone doesn't reference the proven entry, or isn't after it
+    NotDeleted,
+    /// The proven entries aren't the ones the challenge asks for
+    NotChallenged,
+    /// The proven entry isn't the one the seed selects
+    NotSelected,
+    /// The checkpoint isn't of the proven version, or is later than the time the proof is about
+    NotCheckpointed,
+    /// The proof would take more work to verify than the verifier accepts
+    LimitExceeded {
+        limit: Limit,
+        max: u64,
+        actual: u64,
+    },
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::WrongNumberOfEntries { expected, actual } => write!(
+                f,
+                "Expected {} entries but {} were provided",
+                expected, actual
+            ),
+            VerificationError::EmptyProof => write!(f, "Proof entries cannot be empty"),
+            VerificationError::TreeIndexOutOfBounds(index) => {
+                write!(f, "Tree index {} out of bounds", index)
+            }
+            VerificationError::MissingTree(index) => {
+                write!(f, "Tree at index {} doesn't exist", index)
+            }
+            VerificationError::DuplicateTreeIndex(index) => {
+                write!(f, "Tree at index {} referenced more than once", index)
+            }
+            VerificationError::InvalidTreeSize(num_leaves) => {
+                write!(f, "No perfect tree has {} leaves", num_leaves)
+            }
+            VerificationError::InvalidArity(arity) => {
+                write!(f, "Nodes can't have {} children", arity)
+            }
+            VerificationError::ArityMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "Expected a tree of arity {} but got {}",
+                    expected, actual
+                )
+            }
+            VerificationError::LeafIndexOutOfBounds(index) => {
+                write!(f, "Leaf index {} out of bounds", index)
+            }
+            VerificationError::NotEnoughProofElements => write!(f, "Not enough proof elements"),
+            VerificationError::UnusedProofElements => {
+                write!(f, "Not all proof elements were used")
+            }
+            VerificationError::RootMismatch => {
+                write!(f, "Computed root doesn't match expected root")
+            }
+            VerificationError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported proof format version {}", version)
+            }
+            VerificationError::OutdatedVersion(version) => write!(
+                f,
+                "Proof format version {} predates the current version {}, produce the proof again",
+                version, PROOF_FORMAT_VERSION
+            ),
+            VerificationError::NonCanonicalProof => {
+                write!(f, "Proof doesn't have the canonical shape for its query")
+            }
+            VerificationError::MalformedEntry(index) => write!(f, "Malformed entry {}", index),
+            VerificationError::EntryMismatch(index) => {
+                write!(f, "Entry {} differs between the structures", index)
+            }
+            VerificationError::IncompleteRange => {
+                write!(f, "Proof may omit entries of the queried range")
+            }
+            VerificationError::BodyMismatch => {
+                write!(f, "Body doesn't match the digest committed with its header")
+            }
+            VerificationError::MissingTreeSize => write!(f, "Proof doesn't carry its tree size"),
+            VerificationError::HashAlgoMismatch { expected, actual } => {
+                write!(f, "Expected hashing with {} but got {}", expected, actual)
+            }
+            VerificationError::InvalidCommitment(e) => write!(f, "Invalid commitment: {}", e),
+            VerificationError::NoEquivocation => {
+                write!(f, "Evidence doesn't show conflicting commitments")
+            }
+            VerificationError::LeafOrderingMismatch { expected, actual } => {
+                write!(f, "Expected {} entries but got {}", expected, actual)
+            }
+            VerificationError::NotAbsent => {
+                write!(f, "Proven entries don't enclose the value")
+            }
+            VerificationError::NotMinimum => write!(f, "Element isn't the minimum of the queue"),
+            VerificationError::NotTop => write!(f, "Entry isn't the top of the stack"),
+            VerificationError::NotDeleted => {
+                write!(f, "Tombstone doesn't delete the proven entry")
+            }
+            VerificationError::NotChallenged => {
+                write!(f, "Proven entries aren't the challenged ones")
+            }
+            VerificationError::NotSelected => {
+                write!(f, "Proven entry isn't the one the seed selects")
+            }
+            VerificationError::NotCheckpointed => {
+                write!(
+                    f,
+                    "Checkpoint isn't of the proven version at the given time"
+                )
+            }
+            VerificationError::LimitExceeded { limit, max, actual } => {
+                write!(
+                    f,
+                    "Proof has {} {}, more than the limit of {}",
+                    actual, limit, max
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for VerificationError {}
+
+// The error of a borsh decoding whose invariants don't hold, as checked by the serde one
+#[cfg(feature = "borsh")]
+pub(crate) fn invalid_data(error: impl fmt::Display) -> borsh::io::Error {
+    use alloc::string::ToString;
+
+    borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, error.to_string())
+}
@@ -0,0 +1,143 @@
+//! Interop with the Merkle trees of Solidity contracts, so that roots and proofs match those of
+//! OpenZeppelin's `MerkleProof` and of the JavaScript libraries building trees for it, e.g., for
+//! airdrops.
+//!
+//! Nodes are the keccak256 of their children concatenated as raw 32-byte words (no BCS encoding).
+//! With `PairOrder::Sorted`, the smaller child comes first whatever its position, as in
+//! OpenZeppelin's `MerkleProof.verify`, so a proof is only its sibling digests. With
+//! `PairOrder::Positional`, the left child comes first. A level with an odd number of nodes moves
+//! its last node up unchanged, as merkletreejs does by default.
+//!
+//! Leaves are hashed by the caller: `keccak256` of a value, or `standard_leaf_hash` as
+//! OpenZeppelin's `StandardMerkleTree`.
+
+use alloc::vec::Vec;
+
+use ads_core::HashAlgo;
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+use crate::hash::NodeHasher;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// How the two children of a node are ordered before hashing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum PairOrder {
+    /// Left child first
+    #[default]
+    Positional,
+    /// Smaller digest first, as OpenZeppelin's `MerkleProof`
+    Sorted,
+}
+
+pub fn keccak256(data: &[u8]) -> Digest {
+    NodeHasher::new(HashAlgo::Keccak256).hash_message(data)
+}
+
+/// Leaf hash of OpenZeppelin's `StandardMerkleTree`, i.e., keccak256 twice, of a leaf's ABI
+/// encoding.
+pub fn standard_leaf_hash(abi_encoded: &[u8]) -> Digest {
+    keccak256(&keccak256(abi_encoded).0)
+}
+
+/// Hash of an internal node.
+pub fn hash_children(order: PairOrder, left: &Digest, right: &Digest) -> Digest {
+    let (first, second) = match order {
+        PairOrder::Sorted if right.0 < left.0 => (right, left),
+        _ => (left, right),
+    };
+    let mut hasher = NodeHasher::new(HashAlgo::Keccak256).message_hasher();
+    hasher.update(&first.0);
+    hasher.update(&second.0);
+    hasher.finalize()
+}
+
+/// The nodes of the level above `level`, moving its last node up if it has an odd number.
+pub fn next_level(order: PairOrder, level: &[Digest]) -> Vec<Digest> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_children(order, left, right),
+            [last] => *last,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// The root of the tree over `leaf_hashes`, None for no leaf.
+pub fn merkle_root(order: PairOrder, leaf_hashes: &[Digest]) -> Option<Digest> {
+    let mut level = leaf_hashes.to_vec();
+    while level.len() > 1 {
+        level = next_level(order, &level);
+    }
+    level.first().copied()
+}
+
+/// The root that OpenZeppelin's `MerkleProof.processProof` computes from a leaf and its sorted-pair
+/// proof.
+pub fn process_sorted_proof(leaf_hash: &Digest, siblings: &[Digest]) -> Digest {
+    siblings.iter().fold(*leaf_hash, |current, sibling| {
+        hash_children(PairOrder::Sorted, &current, sibling)
+    })
+}
+
+/// A proof that a leaf is in a tree: the siblings of its node at the levels where it has one, from
+/// the leaves up. With sorted pairs, `siblings` is the proof OpenZeppelin's `MerkleProof` takes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct EthereumMerkleProof {
+    pub version: u8,
+    pub order: PairOrder,
+    pub num_leaves: u64,
+    pub leaf_index: u64,
+    pub siblings: Vec<Digest>,
+}
+
+impl EthereumMerkleProof {
+    pub fn new(order: PairOrder, num_leaves: u64, leaf_index: u64, siblings: Vec<Digest>) -> Self {
+        EthereumMerkleProof {
+            version: PROOF_FORMAT_VERSION,
+            order,
+            num_leaves,
+            leaf_index,
+            siblings,
+        }
+    }
+
+    /// Verify that `leaf_hash` is the leaf at `leaf_index` of the tree with the given root. Proofs
+    /// of an unsupported version don't verify either.
+    pub fn verify(&self, root: &Digest, leaf_hash: &Digest) -> bool {
+        self.compute_root(leaf_hash).as_ref() == Some(root)
+    }
+
+    /// Recompute the root of the tree from `leaf_hash`, None if the proof is malformed or of an
+    /// unsupported version.
+    pub fn compute_root(&self, leaf_hash: &Digest) -> Option<Digest> {
+        check_version(self.version).ok()?;
+        if self.leaf_index >= self.num_leaves {
+            return None;
+        }
+        let mut current = *leaf_hash;
+        let mut index = self.leaf_index;
+        let mut width = self.num_leaves;
+        let mut siblings = self.siblings.iter();
+        while width > 1 {
+            // The last node of an odd level has no sibling and moves up unchanged
+            if index % 2 == 1 {
+                current = hash_children(self.order, siblings.next()?, &current);
+            } else if index + 1 < width {
+                current = hash_children(self.order, &current, siblings.next()?);
+            }
+            index /= 2;
+            width = width.div_ceil(2);
+        }
+        siblings.next().is_none().then_some(current)
+    }
+}
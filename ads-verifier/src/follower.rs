@@ -0,0 +1,60 @@
+//! A client following an MMR as it grows, trusting nothing but the commitment it started from.
+//!
+//! The peaks of an MMR are all that appending needs, so the follower recomputes each commitment
+//! from the previous one and the appended entries (see `AppendedRangeProof`), and only moves on if
+//! the result is the one the prover announced. Entries it isn't interested in can be skipped over
+//! with a consistency proof.
+
+use crate::consistency::ConsistencyProof;
+use crate::error::VerificationError;
+use crate::mmr::MmrCommitment;
+
+/// Holds the commitment to an MMR and advances it along verified appends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MmrFollower {
+    commitment: MmrCommitment,
+}
+
+impl MmrFollower {
+    /// Follow the MMR from `commitment`, typically `MmrCommitment::empty()` or one that is signed.
+    pub fn new(commitment: MmrCommitment) -> Self {
+        MmrFollower { commitment }
+    }
+
+    /// The commitment all verified appends lead to.
+    pub fn commitment(&self) -> &MmrCommitment {
+        &self.commitment
+    }
+
+    pub fn into_commitment(self) -> MmrCommitment {
+        self.commitment
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.commitment.num_entries()
+    }
+
+    /// Verify that appending `entries` to the followed MMR gives the one committed to by `next`,
+    /// and follow it. On error, the follower keeps its commitment.
+    pub fn append<E: AsRef<[u8]>>(
+        &mut self,
+        entries: &[E],
+        next: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        next.verify_appended_entries(&self.commitment, entries)?;
+        self.commitment = next.clone();
+        Ok(())
+    }
+
+    /// Follow the MMR committed to by `next` without seeing the entries appended, given a proof
+    /// that it extends the followed one. On error, the follower keeps its commitment.
+    pub fn skip_to(
+        &mut self,
+        next: &MmrCommitment,
+        proof: &ConsistencyProof,
+    ) -> Result<(), VerificationError> {
+        next.verify_consistency(&self.commitment, proof)?;
+        self.commitment = next.clone();
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+//! Commitments to many named logs at once.
+//!
+//! A forest commits to each of its logs through a record of (name, root, size), where the root is
+//! the log's conventional (bagged) MMR root. The records, ordered by name, are the entries of an
+//! MMR whose own bagged root is the super-root. A `LogProof` shows that a log has a given root and
+//! size within a super-root, and proofs about the log's entries are then checked against that
+//! root as `StandardMerkleProof`s. A `LogEntryProof` combines both, proving an entry of a log
+//! against the super-root alone.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ads_core::encoding::{bytes_length, digests_length, encode_uleb128};
+use ads_core::Proof;
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::proof::VERSION_LENGTH;
+use crate::standard::{leaf_count_from_mmr_size, leaf_index_to_pos, StandardMerkleProof};
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// What the super-root commits to about one log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct LogRecord {
+    pub name: String,
+    /// None while the log is empty
+    pub root: Option<Digest>,
+    pub size: u64,
+}
+
+impl LogRecord {
+    /// The entry committed for this record, i.e., its BCS encoding.
+    pub fn encode(&self) -> Vec<u8> {
+        let (len, len_size) = encode_uleb128(self.name.len());
+        let mut encoded = Vec::with_capacity(len_size + self.name.len() + 1 + 32 + 8);
+        encoded.extend_from_slice(&len[..len_size]);
+        encoded.extend_from_slice(self.name.as_bytes());
+        match &self.root {
+            None => encoded.push(0),
+            Some(root) => {
+                encoded.push(1);
+                encoded.extend_from_slice(&root.0);
+            }
+        }
+        encoded.extend_from_slice(&self.size.to_le_bytes());
+        encoded
+    }
+}
+
+/// Proof that a log has the record's root and size within a super-root.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct LogProof {
+    pub version: u8,
+    pub record: LogRecord,
+    /// Index of the record among the records of the forest, i.e., of the log among all logs in
+    /// name order
+    pub index: u64,
+    pub proof: StandardMerkleProof,
+}
+
+impl LogProof {
+    pub fn new(record: LogRecord, index: u64, proof: StandardMerkleProof) -> Self {
+        LogProof {
+            version: PROOF_FORMAT_VERSION,
+            record,
+            index,
+            proof,
+        }
+    }
+
+    pub fn verify(&self, super_root: &Digest) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        let num_logs = leaf_count_from_mmr_size(self.proof.mmr_size).unwrap_or(0);
+        if self.index >= num_logs {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.index));
+        }
+        let pos = leaf_index_to_pos(self.index);
+        if !self.proof.verify(super_root, pos, &self.record.encode()) {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Checked against the super-root.
+impl Proof for LogProof {
+    type Commitment = Digest;
+    type Claim = ();
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(&self, super_root: &Digest, _: &()) -> Result<(), VerificationError> {
+        LogProof::verify(self, super_root)
+    }
+
+    fn size_hint(&self) -> usize {
+        // The record is committed as its BCS encoding
+        let proof = VERSION_LENGTH + 8 + digests_length(self.proof.proof_items.len());
+        VERSION_LENGTH + self.record.encode().len() + 8 + proof
+    }
+}
+
+/// Proof that an entry is in a log, and that the log's record is in a super-root.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct LogEntryProof {
+    pub version: u8,
+    pub log: LogProof,
+    pub entry: Vec<u8>,
+    /// Index of the entry within its log
+    pub index: u64,
+    /// Against the root in `log.record`
+    pub proof: StandardMerkleProof,
+}
+
+impl LogEntryProof {
+    pub fn new(log: LogProof, entry: Vec<u8>, index: u64, proof: StandardMerkleProof) -> Self {
+        LogEntryProof {
+            version: PROOF_FORMAT_VERSION,
+            log,
+            entry,
+            index,
+            proof,
+        }
+    }
+
+    pub fn verify(&self, super_root: &Digest) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        self.log.verify(super_root)?;
+        let record = &self.log.record;
+        let Some(root) = &record.root else {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.index));
+        };
+        if self.index >= record.size {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.index));
+        }
+        // The proof must be about the log as of its record
+        let num_entries = leaf_count_from_mmr_size(self.proof.mmr_size).unwrap_or(0);
+        if num_entries != record.size {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: record.size,
+                actual: num_entries,
+            });
+        }
+        let pos = leaf_index_to_pos(self.index);
+        if !self.proof.verify(root, pos, &self.entry) {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Checked against the super-root.
+impl Proof for LogEntryProof {
+    type Commitment = Digest;
+    type Claim = ();
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(&self, super_root: &Digest, _: &()) -> Result<(), VerificationError> {
+        LogEntryProof::verify(self, super_root)
+    }
+
+    fn size_hint(&self) -> usize {
+        let proof = VERSION_LENGTH + 8 + digests_length(self.proof.proof_items.len());
+        VERSION_LENGTH + self.log.size_hint() + bytes_length(self.entry.len()) + 8 + proof
+    }
+}
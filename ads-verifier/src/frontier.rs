@@ -0,0 +1,178 @@
+//! The frontier of an MMR: the state appending to it needs, and what appenders hand over.
+//!
+//! Appending an entry only merges trees at the right end of the MMR, so the number of entries and
+//! the peaks are all an appender has to keep, whichever appender appended before it. That's also
+//! exactly what an `MmrCommitment` holds, so a frontier converts to and from one at no cost and
+//! serializes the same way, and its root is the commitment's digest.
+
+use alloc::vec::Vec;
+
+use ads_core::HashAlgo;
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+#[cfg(feature = "borsh")]
+use crate::error::invalid_data;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{CommitmentError, MmrCommitment};
+
+/// The number of entries of an MMR and the peaks of its trees, advanced on append.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct Frontier {
+    commitment: MmrCommitment,
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for Frontier {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        Frontier::from_commitment(MmrCommitment::deserialize_reader(reader)?).map_err(invalid_data)
+    }
+}
+
+impl Frontier {
+    /// The frontier of an MMR without entries, hashed with Blake2b256.
+    pub fn empty() -> Self {
+        Frontier {
+            commitment: MmrCommitment::empty(),
+        }
+    }
+
+    /// The same frontier of an MMR hashed with `algo`.
+    pub fn with_hash_algo(self, algo: HashAlgo) -> Self {
+        Frontier {
+            commitment: self.commitment.with_hash_algo(algo),
+        }
+    }
+
+    /// The frontier of the MMR committed to, which must have one peak per tree.
+    pub fn from_commitment(commitment: MmrCommitment) -> Result<Self, CommitmentError> {
+        let expected = commitment.num_entries().count_ones() as usize;
+        if commitment.peaks.len() != expected {
+            return Err(CommitmentError::WrongNumberOfPeaks {
+                expected,
+                actual: commitment.peaks.len(),
+            });
+        }
+        Ok(Frontier { commitment })
+    }
+
+    /// The frontier of the MMR with these leaf hashes, computed with `hasher`.
+    pub fn from_leaf_hashes(hasher: &NodeHasher, leaves: impl IntoIterator<Item = Digest>) -> Self {
+        let mut frontier = Frontier::empty().with_hash_algo(hasher.algo());
+        for leaf in leaves {
+            frontier.push(hasher, leaf);
+        }
+        frontier
+    }
+
+    /// The commitment to the entries appended so far.
+    pub fn commitment(&self) -> &MmrCommitment {
+        &self.commitment
+    }
+
+    pub fn into_commitment(self) -> MmrCommitment {
+        self.commitment
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.commitment.num_entries()
+    }
+
+    pub fn hash_algo(&self) -> HashAlgo {
+        self.commitment.hash_algo()
+    }
+
+    /// The peaks with the heights of their trees, from the oldest (largest) tree.
+    pub fn peaks(&self) -> impl Iterator<Item = (u64, &Digest)> + '_ {
+        let heights: Vec<u64> = self.commitment.tree_indices().collect();
+        heights.into_iter().rev().zip(&self.commitment.peaks)
+    }
+
+    /// The peaks folded into a single digest, `MmrCommitment::digest`.
+    pub fn root(&self) -> Digest {
+        self.commitment.digest()
+    }
+
+    /// Append `entry`, hashed with the frontier's algorithm.
+    pub fn append(&mut self, entry: &[u8]) {
+        let hasher = NodeHasher::new(self.hash_algo());
+        self.push(&hasher, hasher.hash_leaf(entry));
+    }
+
+    /// Append `entry` to an MMR hashed with `hasher`, e.g., a keyed one.
+    pub fn append_with_hasher(
+        &mut self,
+        hasher: &NodeHasher,
+        entry: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.append_leaf_hash(hasher, hasher.hash_leaf(entry))
+    }
+
+    /// Append an entry by its leaf hash with `hasher`, e.g., one hashed as it was streamed in.
+    pub fn append_leaf_hash(
+        &mut self,
+        hasher: &NodeHasher,
+        leaf: Digest,
+    ) -> Result<(), VerificationError> {
+        self.commitment.check_hash_algo(hasher, self.hash_algo())?;
+        self.push(hasher, leaf);
+        Ok(())
+    }
+
+    // Append without checking the hasher. The new entry completes one tree per trailing one bit of
+    // the number of entries, each merged with the new tree to its right.
+    pub(crate) fn push(&mut self, hasher: &NodeHasher, leaf: Digest) {
+        let mut carry = leaf;
+        for _ in 0..self.commitment.num_entries.trailing_ones() {
+            let left = self.commitment.peaks.pop().expect("One peak per tree");
+            carry = hasher.hash_children(&left, &carry);
+        }
+        self.commitment.peaks.push(carry);
+        self.commitment.num_entries += 1;
+    }
+}
+
+impl Default for Frontier {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl TryFrom<MmrCommitment> for Frontier {
+    type Error = CommitmentError;
+
+    fn try_from(commitment: MmrCommitment) -> Result<Self, CommitmentError> {
+        Self::from_commitment(commitment)
+    }
+}
+
+impl From<Frontier> for MmrCommitment {
+    fn from(frontier: Frontier) -> Self {
+        frontier.commitment
+    }
+}
+
+impl MmrCommitment {
+    // Check that the commitment is to the MMR with these leaf hashes, e.g., ones a proof carries to
+    // relate two commitments
+    pub(crate) fn check_leaf_hashes(
+        &self,
+        hasher: &NodeHasher,
+        leaf_hashes: &[Digest],
+    ) -> Result<(), VerificationError> {
+        if leaf_hashes.len() as u64 != self.num_entries() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: self.num_entries(),
+                actual: leaf_hashes.len() as u64,
+            });
+        }
+        let frontier = Frontier::from_leaf_hashes(hasher, leaf_hashes.iter().copied());
+        if frontier.commitment.peaks != self.peaks {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+//! Proofs that a grow-only set is the union of two others.
+//!
+//! A grow-only set is committed to as an MMR of its elements, sorted (see `ordering`). Replicas
+//! converge by merging: the union of two sets is again sorted, so it's the interleaving of both,
+//! with the elements they share once. A `MergeProof` carries the elements of the union and, for
+//! each, which of the inputs holds it. A verifier checks that they're sorted and recomputes the
+//! three commitments from them, so that the union holds exactly the elements of the inputs.
+
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::MmrCommitment;
+use crate::ordering::LeafOrdering;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// Which of the merged sets holds an element of their union.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum MergeSource {
+    Left,
+    Right,
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct MergeProof {
+    pub version: u8,
+    // The elements of the union, in increasing order
+    pub elements: Vec<Vec<u8>>,
+    // One per element
+    pub sources: Vec<MergeSource>,
+}
+
+impl MergeProof {
+    pub fn new(elements: Vec<Vec<u8>>, sources: Vec<MergeSource>) -> Self {
+        MergeProof {
+            version: PROOF_FORMAT_VERSION,
+            elements,
+            sources,
+        }
+    }
+
+    /// Verify that `merged` commits to the union of the sets committed to by `left` and `right`.
+    pub fn verify(
+        &self,
+        left: &MmrCommitment,
+        right: &MmrCommitment,
+        merged: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_hasher(&NodeHasher::new(merged.hash_algo()), left, right, merged)
+    }
+
+    /// Same as `verify`, for sets hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        left: &MmrCommitment,
+        right: &MmrCommitment,
+        merged: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        if self.sources.len() != self.elements.len() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: self.elements.len() as u64,
+                actual: self.sources.len() as u64,
+            });
+        }
+        for commitment in [left, right, merged] {
+            merged.check_hash_algo(hasher, commitment.hash_algo())?;
+            commitment.check_ordering(LeafOrdering::Sorted)?;
+        }
+        // Strictly increasing, so each element is in the union once
+        if !self.elements.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+
+        let merged_leaves: Vec<_> = self
+            .elements
+            .iter()
+            .map(|element| hasher.hash_leaf(element))
+            .collect();
+        let mut left_leaves = Vec::new();
+        let mut right_leaves = Vec::new();
+        for (leaf, source) in merged_leaves.iter().zip(&self.sources) {
+            if *source != MergeSource::Right {
+                left_leaves.push(*leaf);
+            }
+            if *source != MergeSource::Left {
+                right_leaves.push(*leaf);
+            }
+        }
+        left.check_leaf_hashes(hasher, &left_leaves)?;
+        right.check_leaf_hashes(hasher, &right_leaves)?;
+        merged.check_leaf_hashes(hasher, &merged_leaves)
+    }
+}
@@ -0,0 +1,80 @@
+//! Entries split into a header committed alongside a large body.
+//!
+//! The committed entry is the header followed by the digest of the body, i.e., the BCS encoding of
+//! a `HeaderedEntry`. An inclusion proof of that entry proves the header (a type tag, an author,
+//! ...) without the body, and whoever later fetches the body checks it against the proven digest.
+
+use alloc::vec::Vec;
+
+use ads_core::encoding::{encode_uleb128, read_uleb128};
+use ads_core::{Blake2b256, Hasher};
+use serde::{Deserialize, Serialize};
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::error::VerificationError;
+use crate::mmr::{MmrCommitment, MmrInclusionProof};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct HeaderedEntry {
+    pub header: Vec<u8>,
+    pub body_digest: Digest,
+}
+
+impl HeaderedEntry {
+    pub fn new(header: Vec<u8>, body: &[u8]) -> Self {
+        HeaderedEntry {
+            header,
+            body_digest: Blake2b256::digest(body),
+        }
+    }
+
+    /// The bytes committed in the log.
+    pub fn encode(&self) -> Vec<u8> {
+        let (len, len_size) = encode_uleb128(self.header.len());
+        let mut encoded = Vec::with_capacity(len_size + self.header.len() + DIGEST_LENGTH);
+        encoded.extend_from_slice(&len[..len_size]);
+        encoded.extend_from_slice(&self.header);
+        encoded.extend_from_slice(&self.body_digest.0);
+        encoded
+    }
+
+    /// Inverse of `encode`. Returns None unless `bytes` is exactly an encoded entry.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let (len, rest) = read_uleb128(bytes)?;
+        if rest.len() != len.checked_add(DIGEST_LENGTH)? {
+            return None;
+        }
+        let (header, body_digest) = rest.split_at(len);
+        Some(HeaderedEntry {
+            header: header.to_vec(),
+            body_digest: Digest::from_slice(body_digest)?,
+        })
+    }
+
+    /// Check that `body` is the body committed with this header.
+    pub fn verify_body(&self, body: &[u8]) -> Result<(), VerificationError> {
+        if Blake2b256::digest(body) != self.body_digest {
+            return Err(VerificationError::BodyMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl MmrCommitment {
+    /// Verify an inclusion proof of a headered entry and return its header and body digest.
+    pub fn verify_header(
+        &self,
+        proof: &MmrInclusionProof,
+    ) -> Result<HeaderedEntry, VerificationError> {
+        self.verify_inclusion(proof)?;
+        HeaderedEntry::decode(&proof.entry).ok_or_else(|| {
+            // The proof verified, so its leaf exists
+            let index = self.entry_index(proof.tree_index, proof.proof.leaf_index);
+            VerificationError::MalformedEntry(index.unwrap())
+        })
+    }
+}
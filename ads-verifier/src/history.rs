@@ -0,0 +1,101 @@
+//! Proofs that an entry was in a log at a given time.
+//!
+//! A log publishes checkpoints: now and then, it appends the digest of its commitment (see
+//! `MmrCommitment::digest`), timestamped, to a second log of checkpoints whose timestamps never
+//! decrease (see `timed`). Each checkpointed version of the log is thereby tied to a time. An
+//! `ExistenceProof` shows that an entry was in the log at time `t` in three steps: a checkpoint
+//! timestamped no later than `t` is in the log of checkpoints, the entry is in the version it
+//! commits to, and the current log extends that version.
+
+use serde::{Deserialize, Serialize};
+
+use crate::consistency::ConsistencyProof;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{MmrCommitment, MmrInclusionProof};
+use crate::timed::TimestampedEntry;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct ExistenceProof {
+    pub version: u8,
+    // Of the timestamped digest of `commitment` in the log of checkpoints
+    pub checkpoint: MmrInclusionProof,
+    // The checkpointed version of the log
+    pub commitment: MmrCommitment,
+    // Of the entry in that version
+    pub inclusion: MmrInclusionProof,
+    // From that version to the current log
+    pub consistency: ConsistencyProof,
+}
+
+impl ExistenceProof {
+    pub fn new(
+        checkpoint: MmrInclusionProof,
+        commitment: MmrCommitment,
+        inclusion: MmrInclusionProof,
+        consistency: ConsistencyProof,
+    ) -> Self {
+        ExistenceProof {
+            version: PROOF_FORMAT_VERSION,
+            checkpoint,
+            commitment,
+            inclusion,
+            consistency,
+        }
+    }
+
+    /// The proven entry.
+    pub fn entry(&self) -> &[u8] {
+        &self.inclusion.entry
+    }
+
+    /// Timestamp of the checkpoint the entry is proven in, None if it doesn't decode.
+    pub fn timestamp(&self) -> Option<u64> {
+        Some(TimestampedEntry::decode(&self.checkpoint.entry)?.timestamp)
+    }
+
+    /// Verify that the entry was in the log committed to by `log` at time `time`, given the log
+    /// of its checkpoints, and return its index.
+    pub fn verify(
+        &self,
+        log: &MmrCommitment,
+        checkpoints: &MmrCommitment,
+        time: u64,
+    ) -> Result<u64, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::new(log.hash_algo()), log, checkpoints, time)
+    }
+
+    /// Same as `verify`, for a log hashed with `hasher`. The log of checkpoints is hashed with
+    /// the algorithm of its commitment.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        log: &MmrCommitment,
+        checkpoints: &MmrCommitment,
+        time: u64,
+    ) -> Result<u64, VerificationError> {
+        check_version(self.version)?;
+        checkpoints.verify_inclusion(&self.checkpoint)?;
+        let checkpoint = TimestampedEntry::decode(&self.checkpoint.entry).ok_or_else(|| {
+            let index = checkpoints
+                .entry_index(self.checkpoint.tree_index, self.checkpoint.proof.leaf_index);
+            VerificationError::MalformedEntry(index.unwrap_or_default())
+        })?;
+        if checkpoint.timestamp > time || checkpoint.payload != self.commitment.digest().0 {
+            return Err(VerificationError::NotCheckpointed);
+        }
+
+        self.commitment
+            .verify_inclusion_with_hasher(hasher, &self.inclusion)?;
+        log.verify_consistency_with_hasher(hasher, &self.commitment, &self.consistency)?;
+        let index = self
+            .commitment
+            .entry_index(self.inclusion.tree_index, self.inclusion.proof.leaf_index);
+        Ok(index.expect("The entry was proven in this tree"))
+    }
+}
@@ -0,0 +1,492 @@
+//! Inclusion proofs as ICS-23 messages, the proof format of IBC.
+//!
+//! The types below have the fields of the `cosmos.ics23.v1` protobuf messages, so that they map
+//! one to one onto those of an ICS-23 implementation without this crate depending on protobuf.
+//! Only existence proofs are produced.
+//!
+//! An ICS-23 leaf is `hash(prefix || key || value)` and a node is `hash(prefix || child ||
+//! suffix)`. This crate's leaves hash `0x00 || value`, so the leaf prefix is 0x00 and the key is
+//! empty, and its nodes hash `0x01 || 0x20 || left || 0x20 || right`, i.e., two children of 33
+//! bytes after the node prefix. ICS-23 has operations for SHA-256 and Keccak256 but not
+//! Blake2b256, so only trees hashed with one of those two can be described. Verifiers that also
+//! require a non-empty key reject these proofs.
+//!
+//! Proofs in this format can also be verified, this crate's against its commitments and others'
+//! against their spec and root. Non-existence proofs need trees sorted by key, which this crate
+//! doesn't build, so they are only verified for other producers.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use ads_core::encoding::{LEAF_PREFIX, NODE_PREFIX};
+use ads_core::HashAlgo;
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::MmrCommitment;
+use crate::proof::InclusionProof;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ics23Error {
+    /// The proof uses a hash or length operation this crate doesn't implement
+    UnsupportedOp,
+    /// The proof doesn't follow the spec it's verified against
+    SpecMismatch,
+    /// The proof is about another key or value than the queried ones
+    KeyValueMismatch,
+    /// The neighbors of a non-existence proof don't enclose the key or aren't adjacent leaves
+    NotNeighbors,
+    /// A non-existence proof without any neighbor
+    EmptyProof,
+    /// An existence proof for a non-membership query or the other way around
+    WrongProofType,
+    Verification(VerificationError),
+}
+
+impl fmt::Display for Ics23Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ics23Error::UnsupportedOp => write!(f, "Unsupported hash or length operation"),
+            Ics23Error::SpecMismatch => write!(f, "Proof doesn't follow the proof spec"),
+            Ics23Error::KeyValueMismatch => {
+                write!(
+                    f,
+                    "Proof is about another key or value than the queried ones"
+                )
+            }
+            Ics23Error::NotNeighbors => write!(f, "Proven leaves don't enclose the key"),
+            Ics23Error::EmptyProof => write!(f, "Non-existence proof without neighbors"),
+            Ics23Error::WrongProofType => write!(f, "Wrong kind of proof for the query"),
+            Ics23Error::Verification(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl core::error::Error for Ics23Error {}
+
+impl From<VerificationError> for Ics23Error {
+    fn from(e: VerificationError) -> Self {
+        Ics23Error::Verification(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum HashOp {
+    NoHash = 0,
+    Sha256 = 1,
+    Sha512 = 2,
+    Keccak256 = 3,
+    Ripemd160 = 4,
+    Bitcoin = 5,
+    Sha512_256 = 6,
+    Blake2b512 = 7,
+    Blake2s256 = 8,
+    Blake3 = 9,
+}
+
+impl HashOp {
+    /// The operation of `algo`, None if ICS-23 has none.
+    pub fn of(algo: HashAlgo) -> Option<Self> {
+        match algo {
+            HashAlgo::Sha256 => Some(HashOp::Sha256),
+            HashAlgo::Keccak256 => Some(HashOp::Keccak256),
+            _ => None,
+        }
+    }
+
+    fn algo(self) -> Option<HashAlgo> {
+        match self {
+            HashOp::Sha256 => Some(HashAlgo::Sha256),
+            HashOp::Keccak256 => Some(HashAlgo::Keccak256),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum LengthOp {
+    NoPrefix = 0,
+    VarProto = 1,
+    VarRlp = 2,
+    Fixed32Big = 3,
+    Fixed32Little = 4,
+    Fixed64Big = 5,
+    Fixed64Little = 6,
+    Require32Bytes = 7,
+    Require64Bytes = 8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafOp {
+    pub hash: HashOp,
+    pub prehash_key: HashOp,
+    pub prehash_value: HashOp,
+    pub length: LengthOp,
+    pub prefix: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnerOp {
+    pub hash: HashOp,
+    pub prefix: Vec<u8>,
+    pub suffix: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExistenceProof {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub leaf: LeafOp,
+    /// From the leaf up
+    pub path: Vec<InnerOp>,
+}
+
+/// Proof that no leaf has `key`: the leaves with the closest keys on either side, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonExistenceProof {
+    pub key: Vec<u8>,
+    pub left: Option<ExistenceProof>,
+    pub right: Option<ExistenceProof>,
+}
+
+/// ICS-23's `CommitmentProof`, without its batch and compressed cases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitmentProof {
+    Exist(ExistenceProof),
+    Nonexist(NonExistenceProof),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnerSpec {
+    pub child_order: Vec<i32>,
+    pub child_size: i32,
+    pub min_prefix_length: i32,
+    pub max_prefix_length: i32,
+    pub empty_child: Vec<u8>,
+    pub hash: HashOp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofSpec {
+    pub leaf_spec: LeafOp,
+    pub inner_spec: InnerSpec,
+    pub max_depth: i32,
+    pub min_depth: i32,
+    pub prehash_key_before_comparison: bool,
+}
+
+// BCS length prefix of a digest
+const CHILD_PREFIX: u8 = DIGEST_LENGTH as u8;
+
+fn leaf_op(hash: HashOp) -> LeafOp {
+    LeafOp {
+        hash,
+        prehash_key: HashOp::NoHash,
+        prehash_value: HashOp::NoHash,
+        length: LengthOp::NoPrefix,
+        prefix: vec![LEAF_PREFIX],
+    }
+}
+
+/// The spec of perfect trees hashed with `algo`, None if ICS-23 has no operation for it.
+pub fn proof_spec(algo: HashAlgo) -> Option<ProofSpec> {
+    let hash = HashOp::of(algo)?;
+    Some(ProofSpec {
+        leaf_spec: leaf_op(hash),
+        inner_spec: InnerSpec {
+            child_order: vec![0, 1],
+            child_size: DIGEST_LENGTH as i32 + 1,
+            min_prefix_length: 2,
+            max_prefix_length: 2,
+            empty_child: vec![],
+            hash,
+        },
+        max_depth: 64,
+        min_depth: 0,
+        prehash_key_before_comparison: false,
+    })
+}
+
+impl InclusionProof {
+    /// This proof of `leaf` in a tree hashed with `algo`, None if ICS-23 has no operation for it.
+    pub fn to_ics23(&self, algo: HashAlgo, leaf: &[u8]) -> Option<CommitmentProof> {
+        let hash = HashOp::of(algo)?;
+        let path = self
+            .siblings
+            .iter()
+            .enumerate()
+            .map(|(level, sibling)| {
+                let is_right = level < 64 && (self.leaf_index >> level) & 1 == 1;
+                let mut encoded = vec![CHILD_PREFIX];
+                encoded.extend_from_slice(&sibling.0);
+                if is_right {
+                    let mut prefix = vec![NODE_PREFIX];
+                    prefix.append(&mut encoded);
+                    prefix.push(CHILD_PREFIX);
+                    InnerOp {
+                        hash,
+                        prefix,
+                        suffix: vec![],
+                    }
+                } else {
+                    InnerOp {
+                        hash,
+                        prefix: vec![NODE_PREFIX, CHILD_PREFIX],
+                        suffix: encoded,
+                    }
+                }
+            })
+            .collect();
+        Some(CommitmentProof::Exist(ExistenceProof {
+            key: vec![],
+            value: leaf.to_vec(),
+            leaf: leaf_op(hash),
+            path,
+        }))
+    }
+}
+
+impl ExistenceProof {
+    /// The root the proof commits to, as an ICS-23 verifier computes it. Unlike ICS-23's
+    /// reference implementations, empty keys are accepted, since this crate's proofs have them.
+    pub fn calculate(&self) -> Result<Digest, Ics23Error> {
+        let leaf = &self.leaf;
+        let key = prepare(leaf.prehash_key, leaf.length, &self.key)?;
+        let value = prepare(leaf.prehash_value, leaf.length, &self.value)?;
+        let mut current = hash(leaf.hash, &[&leaf.prefix, &key, &value])?;
+        for inner in &self.path {
+            current = hash(inner.hash, &[&inner.prefix, &current.0, &inner.suffix])?;
+        }
+        Ok(current)
+    }
+
+    /// Verify that the proof follows `spec` and shows that `key` maps to `value` in the tree with
+    /// the given root.
+    pub fn verify(
+        &self,
+        spec: &ProofSpec,
+        root: &Digest,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Ics23Error> {
+        if self.key != key || self.value != value {
+            return Err(Ics23Error::KeyValueMismatch);
+        }
+        self.verify_root(spec, root)
+    }
+
+    fn verify_root(&self, spec: &ProofSpec, root: &Digest) -> Result<(), Ics23Error> {
+        self.check_spec(spec)?;
+        if self.calculate()? != *root {
+            return Err(VerificationError::RootMismatch.into());
+        }
+        Ok(())
+    }
+
+    fn check_spec(&self, spec: &ProofSpec) -> Result<(), Ics23Error> {
+        let (leaf, expected) = (&self.leaf, &spec.leaf_spec);
+        if leaf.hash != expected.hash
+            || leaf.prehash_key != expected.prehash_key
+            || leaf.prehash_value != expected.prehash_value
+            || leaf.length != expected.length
+            || !leaf.prefix.starts_with(&expected.prefix)
+        {
+            return Err(Ics23Error::SpecMismatch);
+        }
+        let depth = self.path.len() as i64;
+        if (spec.min_depth > 0 && depth < spec.min_depth.into())
+            || (spec.max_depth > 0 && depth > spec.max_depth.into())
+        {
+            return Err(Ics23Error::SpecMismatch);
+        }
+        let inner_spec = &spec.inner_spec;
+        let child_size =
+            usize::try_from(inner_spec.child_size).map_err(|_| Ics23Error::SpecMismatch)?;
+        let max_prefix = usize::try_from(inner_spec.max_prefix_length).unwrap_or(0)
+            + inner_spec.child_order.len().saturating_sub(1) * child_size;
+        for inner in &self.path {
+            // Without a leaf prefix, nothing tells an inner node from a leaf
+            if inner.hash != inner_spec.hash
+                || (!expected.prefix.is_empty() && inner.prefix.starts_with(&expected.prefix))
+                || (inner.prefix.len() as i64) < inner_spec.min_prefix_length.into()
+                || inner.prefix.len() > max_prefix
+                || child_size == 0
+                || inner.suffix.len() % child_size != 0
+            {
+                return Err(Ics23Error::SpecMismatch);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NonExistenceProof {
+    /// Verify that the proof follows `spec` and shows that no leaf of the tree with the given root
+    /// has `key`.
+    pub fn verify(&self, spec: &ProofSpec, root: &Digest, key: &[u8]) -> Result<(), Ics23Error> {
+        if self.key != key {
+            return Err(Ics23Error::KeyValueMismatch);
+        }
+        let key = comparable_key(spec, key)?;
+        if let Some(left) = &self.left {
+            left.verify_root(spec, root)?;
+            if comparable_key(spec, &left.key)? >= key {
+                return Err(Ics23Error::NotNeighbors);
+            }
+        }
+        if let Some(right) = &self.right {
+            right.verify_root(spec, root)?;
+            if comparable_key(spec, &right.key)? <= key {
+                return Err(Ics23Error::NotNeighbors);
+            }
+        }
+        let inner_spec = &spec.inner_spec;
+        let last = inner_spec.child_order.len().saturating_sub(1);
+        let all_at = |path: &[InnerOp], position: usize| {
+            path.iter()
+                .all(|inner| branch_position(inner_spec, inner) == Some(position))
+        };
+        let adjacent = match (&self.left, &self.right) {
+            (None, None) => return Err(Ics23Error::EmptyProof),
+            (None, Some(right)) => all_at(&right.path, 0),
+            (Some(left), None) => all_at(&left.path, last),
+            (Some(left), Some(right)) => {
+                // Below the lowest common node, the left leaf is the rightmost of one child and
+                // the right leaf the leftmost of the next
+                let common = left
+                    .path
+                    .iter()
+                    .rev()
+                    .zip(right.path.iter().rev())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                let left_path = &left.path[..left.path.len() - common];
+                let right_path = &right.path[..right.path.len() - common];
+                match (left_path.split_last(), right_path.split_last()) {
+                    (Some((left_top, left_rest)), Some((right_top, right_rest))) => {
+                        branch_position(inner_spec, left_top)
+                            .zip(branch_position(inner_spec, right_top))
+                            .is_some_and(|(l, r)| l + 1 == r)
+                            && all_at(left_rest, last)
+                            && all_at(right_rest, 0)
+                    }
+                    _ => false,
+                }
+            }
+        };
+        if !adjacent {
+            return Err(Ics23Error::NotNeighbors);
+        }
+        Ok(())
+    }
+}
+
+/// Verify that `proof` shows that `key` maps to `value` in the tree with the given root.
+pub fn verify_membership(
+    proof: &CommitmentProof,
+    spec: &ProofSpec,
+    root: &Digest,
+    key: &[u8],
+    value: &[u8],
+) -> Result<(), Ics23Error> {
+    match proof {
+        CommitmentProof::Exist(proof) => proof.verify(spec, root, key, value),
+        CommitmentProof::Nonexist(_) => Err(Ics23Error::WrongProofType),
+    }
+}
+
+/// Verify that `proof` shows that no leaf of the tree with the given root has `key`.
+pub fn verify_non_membership(
+    proof: &CommitmentProof,
+    spec: &ProofSpec,
+    root: &Digest,
+    key: &[u8],
+) -> Result<(), Ics23Error> {
+    match proof {
+        CommitmentProof::Nonexist(proof) => proof.verify(spec, root, key),
+        CommitmentProof::Exist(_) => Err(Ics23Error::WrongProofType),
+    }
+}
+
+impl MmrCommitment {
+    /// Verify that `proof`, exported from one of the MMR's trees, shows that `value` is committed
+    /// to. The tree is the one whose height is the proof's depth.
+    pub fn verify_ics23_membership(
+        &self,
+        proof: &CommitmentProof,
+        value: &[u8],
+    ) -> Result<(), Ics23Error> {
+        let spec = proof_spec(self.hash_algo()).ok_or(Ics23Error::UnsupportedOp)?;
+        let CommitmentProof::Exist(existence) = proof else {
+            return Err(Ics23Error::WrongProofType);
+        };
+        let root = self.digest_at(existence.path.len() as u64)?;
+        existence.verify(&spec, root, &[], value)
+    }
+}
+
+// Position among its siblings of the child an inner op hashes, from how many siblings its prefix
+// and suffix hold
+fn branch_position(spec: &InnerSpec, inner: &InnerOp) -> Option<usize> {
+    let child_size = usize::try_from(spec.child_size).ok()?;
+    let min_prefix = usize::try_from(spec.min_prefix_length).ok()?;
+    let max_prefix = usize::try_from(spec.max_prefix_length).ok()?;
+    let num_children = spec.child_order.len();
+    (0..num_children).find(|&position| {
+        let prefix = inner.prefix.len();
+        (min_prefix + position * child_size..=max_prefix + position * child_size).contains(&prefix)
+            && inner.suffix.len() == (num_children - 1 - position) * child_size
+    })
+}
+
+fn comparable_key(spec: &ProofSpec, key: &[u8]) -> Result<Vec<u8>, Ics23Error> {
+    match spec.leaf_spec.prehash_key {
+        op if spec.prehash_key_before_comparison && op != HashOp::NoHash => {
+            Ok(hash(op, &[key])?.0.to_vec())
+        }
+        _ => Ok(key.to_vec()),
+    }
+}
+
+// The length and data as the leaf op hashes them
+fn prepare(prehash: HashOp, length: LengthOp, data: &[u8]) -> Result<Vec<u8>, Ics23Error> {
+    let data = match prehash {
+        HashOp::NoHash => data.to_vec(),
+        op => hash(op, &[data])?.0.to_vec(),
+    };
+    let mut prepared = match length {
+        LengthOp::NoPrefix => vec![],
+        LengthOp::VarProto => {
+            let mut encoded = vec![];
+            let mut n = data.len();
+            while n >= 0x80 {
+                encoded.push(n as u8 | 0x80);
+                n >>= 7;
+            }
+            encoded.push(n as u8);
+            encoded
+        }
+        LengthOp::Fixed32Big => (data.len() as u32).to_be_bytes().to_vec(),
+        LengthOp::Fixed32Little => (data.len() as u32).to_le_bytes().to_vec(),
+        LengthOp::Fixed64Big => (data.len() as u64).to_be_bytes().to_vec(),
+        LengthOp::Fixed64Little => (data.len() as u64).to_le_bytes().to_vec(),
+        LengthOp::Require32Bytes if data.len() == 32 => vec![],
+        LengthOp::Require64Bytes if data.len() == 64 => vec![],
+        LengthOp::Require32Bytes | LengthOp::Require64Bytes => {
+            return Err(Ics23Error::SpecMismatch)
+        }
+        LengthOp::VarRlp => return Err(Ics23Error::UnsupportedOp),
+    };
+    prepared.extend_from_slice(&data);
+    Ok(prepared)
+}
+
+fn hash(op: HashOp, parts: &[&[u8]]) -> Result<Digest, Ics23Error> {
+    let mut hasher = NodeHasher::new(op.algo().ok_or(Ics23Error::UnsupportedOp)?).message_hasher();
+    parts.iter().for_each(|part| hasher.update(part));
+    Ok(hasher.finalize())
+}
@@ -0,0 +1,408 @@
+//! Proofs about perfect trees whose nodes have k children, e.g., 4, 8 or 16.
+//!
+//! A wider tree has fewer levels, log_k(n) instead of log2(n), but each level of a path carries
+//! the k - 1 other children of its node. That's more digests overall, in fewer hashing rounds,
+//! which is the better trade for verifiers paying per level rather than per byte hashed.
+//!
+//! A node hashes its children the way `NodeHasher::hash_children` hashes two, the node prefix
+//! followed by each child BCS-encoded in order, so that a tree of arity 2 is exactly a
+//! `PerfectMerkleTree`.
+
+use alloc::vec::Vec;
+
+use ads_core::encoding::NODE_PREFIX;
+use serde::{Deserialize, Serialize};
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+#[cfg(feature = "borsh")]
+use crate::error::invalid_data;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::proof::{check_canonical, check_path_length, MAX_HEIGHT};
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// Most children a node can have.
+pub const MAX_ARITY: u64 = 256;
+
+/// The error unless nodes can have `arity` children.
+pub fn check_arity(arity: u64) -> Result<(), VerificationError> {
+    if !(2..=MAX_ARITY).contains(&arity) {
+        return Err(VerificationError::InvalidArity(arity));
+    }
+    Ok(())
+}
+
+/// Number of levels below the root of the perfect tree of the given arity with `num_leaves`
+/// leaves, None unless `num_leaves` is a power of `arity`.
+pub fn tree_height(arity: u64, num_leaves: u64) -> Option<u32> {
+    let mut height = 0;
+    let mut size = 1u64;
+    while size < num_leaves {
+        size = size.checked_mul(arity)?;
+        height += 1;
+    }
+    (size == num_leaves).then_some(height)
+}
+
+/// Hash of a node from the digests of its children, ordered left to right.
+pub fn hash_node(hasher: &NodeHasher, children: &[Digest]) -> Digest {
+    if let [left, right] = children {
+        return hasher.hash_children(left, right);
+    }
+    // BCS length prefix (ULEB128) of each child, a single byte for 32
+    let prefix = [DIGEST_LENGTH as u8];
+    let mut node = hasher.message_hasher();
+    node.update(&[NODE_PREFIX]);
+    for child in children {
+        node.update(&prefix);
+        node.update(child.as_bytes());
+    }
+    node.finalize()
+}
+
+/// Inclusion proof of a single leaf in a perfect k-ary tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawKaryInclusionProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct KaryInclusionProof {
+    pub version: u8,
+    pub arity: u64,
+    pub leaf_index: u64,
+    // The arity - 1 other children of each node on the path, left to right, from the leaf level
+    // up to the children of the root
+    pub siblings: Vec<Digest>,
+}
+
+// `KaryInclusionProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawKaryInclusionProof {
+    version: u8,
+    arity: u64,
+    leaf_index: u64,
+    siblings: Vec<Digest>,
+}
+
+impl TryFrom<RawKaryInclusionProof> for KaryInclusionProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawKaryInclusionProof) -> Result<Self, VerificationError> {
+        let proof = KaryInclusionProof {
+            version: raw.version,
+            arity: raw.arity,
+            leaf_index: raw.leaf_index,
+            siblings: raw.siblings,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for KaryInclusionProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawKaryInclusionProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+impl KaryInclusionProof {
+    pub fn new(arity: u64, leaf_index: u64, siblings: Vec<Digest>) -> Self {
+        KaryInclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            arity,
+            leaf_index,
+            siblings,
+        }
+    }
+
+    /// Number of levels the path climbs.
+    pub fn height(&self) -> usize {
+        self.siblings.len() / (self.arity.max(2) - 1) as usize
+    }
+
+    /// Check the invariants that hold whatever the tree: a supported arity, whole levels of
+    /// siblings, no more levels than a binary tree of u64 leaves has, and a leaf index within the
+    /// tree of that height.
+    pub fn check_shape(&self) -> Result<(), VerificationError> {
+        check_arity(self.arity)?;
+        if !self
+            .siblings
+            .len()
+            .is_multiple_of((self.arity - 1) as usize)
+        {
+            return Err(VerificationError::NotEnoughProofElements);
+        }
+        if self.height() > MAX_HEIGHT {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        let max_leaves = u32::try_from(self.height())
+            .ok()
+            .and_then(|height| self.arity.checked_pow(height));
+        if max_leaves.is_some_and(|max_leaves| self.leaf_index >= max_leaves) {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.leaf_index));
+        }
+        Ok(())
+    }
+
+    /// Recompute the root digest from the leaf value and the sibling path.
+    pub fn compute_root(&self, leaf: &[u8]) -> Digest {
+        self.compute_root_with_hasher(&NodeHasher::UNKEYED, leaf)
+    }
+
+    /// Same as `compute_root`, for a tree hashed with `hasher`.
+    pub fn compute_root_with_hasher(&self, hasher: &NodeHasher, leaf: &[u8]) -> Digest {
+        self.compute_root_from_leaf_hash(hasher, &hasher.hash_leaf(leaf))
+    }
+
+    /// Same as `compute_root_with_hasher`, from the hash of the leaf instead of its value. The
+    /// proof must be of a valid shape, see `check_shape`.
+    pub fn compute_root_from_leaf_hash(&self, hasher: &NodeHasher, leaf_hash: &Digest) -> Digest {
+        let num_siblings = (self.arity - 1) as usize;
+        let mut current = *leaf_hash;
+        let mut index = self.leaf_index;
+        let mut children = Vec::with_capacity(self.arity as usize);
+        for level in self.siblings.chunks(num_siblings) {
+            // The digit of the index at this level is our position among the children
+            let position = (index % self.arity) as usize;
+            children.clear();
+            children.extend_from_slice(&level[..position]);
+            children.push(current);
+            children.extend_from_slice(&level[position..]);
+            current = hash_node(hasher, &children);
+            index /= self.arity;
+        }
+        current
+    }
+
+    /// Verify that `leaf` is in the perfect tree of the given arity with `num_leaves` (a power of
+    /// the arity) leaves and the given root, which is returned.
+    pub fn verify(
+        &self,
+        root: &Digest,
+        arity: u64,
+        num_leaves: u64,
+        leaf: &[u8],
+    ) -> Result<Digest, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::UNKEYED, root, arity, num_leaves, leaf)
+    }
+
+    /// Same as `verify`, for a tree hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        root: &Digest,
+        arity: u64,
+        num_leaves: u64,
+        leaf: &[u8],
+    ) -> Result<Digest, VerificationError> {
+        check_arity(arity)?;
+        let height =
+            tree_height(arity, num_leaves).ok_or(VerificationError::InvalidTreeSize(num_leaves))?;
+        check_version(self.version)?;
+        if self.arity != arity {
+            return Err(VerificationError::ArityMismatch {
+                expected: arity,
+                actual: self.arity,
+            });
+        }
+        self.check_shape()?;
+        if self.leaf_index >= num_leaves {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.leaf_index));
+        }
+        check_path_length(height as usize * (arity - 1) as usize, self.siblings.len())?;
+        check_canonical(hasher, &self.siblings)?;
+        let computed = self.compute_root_with_hasher(hasher, leaf);
+        if computed != *root {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(computed)
+    }
+}
+
+/// Inclusion proof of several leaves of a perfect k-ary tree at once. Each level only carries the
+/// children of the nodes on the paths that the leaves, and the levels below, don't determine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawKaryMultiInclusionProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct KaryMultiInclusionProof {
+    pub version: u8,
+    pub arity: u64,
+    // Strictly increasing
+    pub leaf_indices: Vec<u64>,
+    // Missing nodes level by level from the leaves up, left to right within a level
+    pub siblings: Vec<Digest>,
+}
+
+// `KaryMultiInclusionProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawKaryMultiInclusionProof {
+    version: u8,
+    arity: u64,
+    leaf_indices: Vec<u64>,
+    siblings: Vec<Digest>,
+}
+
+impl TryFrom<RawKaryMultiInclusionProof> for KaryMultiInclusionProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawKaryMultiInclusionProof) -> Result<Self, VerificationError> {
+        let proof = KaryMultiInclusionProof {
+            version: raw.version,
+            arity: raw.arity,
+            leaf_indices: raw.leaf_indices,
+            siblings: raw.siblings,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for KaryMultiInclusionProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawKaryMultiInclusionProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+impl KaryMultiInclusionProof {
+    pub fn new(arity: u64, leaf_indices: Vec<u64>, siblings: Vec<Digest>) -> Self {
+        KaryMultiInclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            arity,
+            leaf_indices,
+            siblings,
+        }
+    }
+
+    /// Check the invariants that hold whatever the tree: a supported arity, leaf indices strictly
+    /// increase, and no more siblings than the paths of the leaves could need.
+    pub fn check_shape(&self) -> Result<(), VerificationError> {
+        check_arity(self.arity)?;
+        if self.leaf_indices.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        let max_siblings = self
+            .leaf_indices
+            .len()
+            .saturating_mul(MAX_HEIGHT)
+            .saturating_mul((self.arity - 1) as usize);
+        if self.siblings.len() > max_siblings {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(())
+    }
+
+    /// Recompute the root of the perfect tree of the given arity with `num_leaves` (a power of
+    /// the arity) leaves from the leaves at `leaf_indices`, given in the same order.
+    pub fn compute_root(
+        &self,
+        arity: u64,
+        num_leaves: u64,
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        self.compute_root_with_hasher(&NodeHasher::UNKEYED, arity, num_leaves, leaves)
+    }
+
+    /// Same as `compute_root`, for a tree hashed with `hasher`.
+    pub fn compute_root_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        arity: u64,
+        num_leaves: u64,
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        check_arity(arity)?;
+        let height =
+            tree_height(arity, num_leaves).ok_or(VerificationError::InvalidTreeSize(num_leaves))?;
+        check_version(self.version)?;
+        if self.arity != arity {
+            return Err(VerificationError::ArityMismatch {
+                expected: arity,
+                actual: self.arity,
+            });
+        }
+        if leaves.len() != self.leaf_indices.len() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: self.leaf_indices.len() as u64,
+                actual: leaves.len() as u64,
+            });
+        }
+        if leaves.is_empty() {
+            return Err(VerificationError::EmptyProof);
+        }
+        if let Some(&index) = self.leaf_indices.iter().find(|&&i| i >= num_leaves) {
+            return Err(VerificationError::LeafIndexOutOfBounds(index));
+        }
+        if self.leaf_indices.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+
+        // Breadth-first: each level is the known nodes of one height, by increasing index
+        let mut level: Vec<(u64, Digest)> = self
+            .leaf_indices
+            .iter()
+            .zip(leaves)
+            .map(|(&index, leaf)| (index, hasher.hash_leaf(leaf)))
+            .collect();
+        let mut siblings = self.siblings.iter();
+        let mut children = Vec::with_capacity(arity as usize);
+        for _ in 0..height {
+            let mut next_level = Vec::with_capacity(level.len());
+            let mut known = level.iter().peekable();
+            while let Some(&&(index, _)) = known.peek() {
+                let parent = index / arity;
+                children.clear();
+                for child in parent * arity..(parent + 1) * arity {
+                    match known.next_if(|(index, _)| *index == child) {
+                        Some((_, hash)) => children.push(*hash),
+                        None => children.push(
+                            *siblings
+                                .next()
+                                .ok_or(VerificationError::NotEnoughProofElements)?,
+                        ),
+                    }
+                }
+                next_level.push((parent, hash_node(hasher, &children)));
+            }
+            level = next_level;
+        }
+        if siblings.next().is_some() {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(level[0].1)
+    }
+
+    /// Verify that `leaves` sit at `leaf_indices` of the perfect tree of the given arity with
+    /// `num_leaves` leaves and the given root, which is returned.
+    pub fn verify(
+        &self,
+        root: &Digest,
+        arity: u64,
+        num_leaves: u64,
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::UNKEYED, root, arity, num_leaves, leaves)
+    }
+
+    /// Same as `verify`, for a tree hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        root: &Digest,
+        arity: u64,
+        num_leaves: u64,
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        check_canonical(hasher, &self.siblings)?;
+        let computed = self.compute_root_with_hasher(hasher, arity, num_leaves, leaves)?;
+        if computed != *root {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(computed)
+    }
+}
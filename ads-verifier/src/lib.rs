@@ -0,0 +1,97 @@
+//! Proof types and verification for the authenticated data structures of this workspace.
+//!
+//! This crate holds everything needed to check a proof against a commitment and nothing needed to
+//! produce one: no trees, no storage, no benchmarks. It is `no_std` (it only needs `alloc`), so it
+//! can be embedded in wallets, enclaves and on-chain programs. Only the `rayon` feature, for
+//! verifying batches of proofs in parallel, needs std.
+
+#![no_std]
+
+extern crate alloc;
+
+#[cfg(feature = "arkworks")]
+pub mod arkworks;
+pub mod audit;
+#[cfg(feature = "rayon")]
+pub mod batch;
+pub mod bisection;
+pub mod bitcoin;
+pub mod borrowed;
+pub mod challenge;
+#[cfg(feature = "poseidon")]
+pub mod circuit;
+pub mod compaction;
+pub mod compressed;
+pub mod consistency;
+pub mod ct;
+pub mod equivocation;
+pub mod error;
+pub mod ethereum;
+pub mod follower;
+pub mod forest;
+pub mod frontier;
+pub mod gset;
+pub mod headers;
+pub mod history;
+pub mod ics23;
+pub mod kary;
+pub mod limits;
+pub mod mmr;
+pub mod ordering;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod proof;
+pub mod queue;
+pub mod selection;
+pub mod spot;
+pub mod stack;
+pub mod standard;
+pub mod streaming;
+pub mod timed;
+pub mod tombstone;
+pub mod version;
+
+// The digest and hash function are shared with the provers
+pub use ads_core::{digest, hash};
+pub use ads_core::{Digest, Hex, NodeHasher, ParseDigestError, Proof, DIGEST_LENGTH, EMPTY_ROOT};
+#[cfg(feature = "arkworks")]
+pub use arkworks::{ArkworksPath, ArkworksWitness};
+pub use audit::{audit, AuditError, MmrAuditor};
+#[cfg(feature = "rayon")]
+pub use batch::BatchVerificationError;
+pub use bisection::{Bisection, BisectionError, BisectionQuery, Disagreement, Party, Split};
+pub use bitcoin::BitcoinMerkleProof;
+pub use challenge::ChallengeResponse;
+#[cfg(feature = "poseidon")]
+pub use circuit::{CircuitInclusionProof, CircuitSuffixProof};
+pub use compaction::CompactionProof;
+pub use compressed::{CompressedProofBundle, ProofBundle};
+pub use consistency::{AppendedRangeProof, ConsistencyProof};
+pub use equivocation::EquivocationEvidence;
+pub use error::VerificationError;
+pub use ethereum::EthereumMerkleProof;
+pub use follower::MmrFollower;
+pub use forest::{LogEntryProof, LogProof, LogRecord};
+pub use frontier::Frontier;
+pub use gset::{MergeProof, MergeSource};
+pub use headers::HeaderedEntry;
+pub use history::ExistenceProof;
+pub use kary::{KaryInclusionProof, KaryMultiInclusionProof};
+pub use limits::{Bounded, Limit, ProofCost, VerificationLimits};
+pub use mmr::{
+    CommitmentError, MmrCommitment, MmrInclusionProof, MmrMultiInclusionProof,
+    MostRecentNElementsProof,
+};
+pub use ordering::{LeafOrdering, NonMembershipProof};
+#[cfg(feature = "rayon")]
+pub use parallel::Parallelism;
+pub use proof::{InclusionProof, MultiInclusionProof, SuffixProof};
+pub use queue::{QueueOperation, QueueTransitionProof};
+pub use selection::SelectionProof;
+pub use spot::{Sampler, SpotCheck, SpotCheckError, SpotCheckReport};
+pub use stack::PopProof;
+pub use standard::StandardMerkleProof;
+pub use streaming::{MostRecentVerifier, SuffixAccumulator};
+pub use timed::{TimeRangeProof, TimestampedEntry};
+pub use tombstone::{DeletionProof, Tombstone};
+pub use version::PROOF_FORMAT_VERSION;
@@ -0,0 +1,221 @@
+//! Bounds on the work verifying an untrusted proof takes.
+//!
+//! Verification hashes every entry a proof carries and every digest on its paths, and the trees it
+//! claims fix how long those paths get, so a client sending a proof chooses how much a verifier
+//! hashes. `VerificationLimits::verify` reads what a proof would cost from its shape alone and
+//! rejects it with `VerificationError::LimitExceeded` before hashing anything.
+
+use core::fmt;
+
+use ads_core::Proof;
+
+use crate::consistency::{AppendedRangeProof, ConsistencyProof};
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::forest::LogProof;
+use crate::mmr::{
+    MmrCommitment, MmrInclusionProof, MmrMultiInclusionProof, MostRecentNElementsProof,
+};
+use crate::proof::{InclusionProof, MultiInclusionProof, SuffixProof};
+use crate::timed::TimeRangeProof;
+
+/// What a `VerificationLimits` bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Digests carried by the proof
+    ProofElements,
+    /// Entries hashed as leaves
+    Entries,
+    /// Leaves of the tree, or entries of the MMR, the proof is about
+    TreeSize,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::ProofElements => write!(f, "proof elements"),
+            Limit::Entries => write!(f, "entries"),
+            Limit::TreeSize => write!(f, "tree size"),
+        }
+    }
+}
+
+/// How much verifying a proof takes, as read from its shape.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProofCost {
+    pub proof_elements: u64,
+    pub entries: u64,
+    pub tree_size: u64,
+}
+
+/// A proof whose verification cost is known before verifying it.
+pub trait Bounded: Proof {
+    /// The cost of verifying this proof against `commitment`, which may claim the tree size.
+    fn cost(&self, commitment: &Self::Commitment) -> ProofCost;
+}
+
+/// The most a verifier accepts to do for a single proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationLimits {
+    pub max_proof_elements: u64,
+    pub max_entries: u64,
+    pub max_tree_size: u64,
+}
+
+impl VerificationLimits {
+    /// No limits, i.e., what verifying without limits does.
+    pub const UNLIMITED: VerificationLimits = VerificationLimits {
+        max_proof_elements: u64::MAX,
+        max_entries: u64::MAX,
+        max_tree_size: u64::MAX,
+    };
+
+    /// Check `cost` against the limits.
+    pub fn check(&self, cost: &ProofCost) -> Result<(), VerificationError> {
+        check_limit(
+            Limit::ProofElements,
+            self.max_proof_elements,
+            cost.proof_elements,
+        )?;
+        check_limit(Limit::Entries, self.max_entries, cost.entries)?;
+        check_limit(Limit::TreeSize, self.max_tree_size, cost.tree_size)
+    }
+
+    /// Verify `proof` as `Proof::verify` does, if its cost is within the limits.
+    pub fn verify<P>(
+        &self,
+        proof: &P,
+        commitment: &P::Commitment,
+        claim: &P::Claim,
+    ) -> Result<P::Output, P::Error>
+    where
+        P: Bounded,
+        P::Error: From<VerificationError>,
+    {
+        self.check(&proof.cost(commitment))?;
+        proof.verify(commitment, claim)
+    }
+}
+
+/// Enough for proofs of a million entries about trees of up to 2^40 leaves.
+impl Default for VerificationLimits {
+    fn default() -> Self {
+        VerificationLimits {
+            max_proof_elements: 1 << 16,
+            max_entries: 1 << 20,
+            max_tree_size: 1 << 40,
+        }
+    }
+}
+
+fn check_limit(limit: Limit, max: u64, actual: u64) -> Result<(), VerificationError> {
+    if actual > max {
+        return Err(VerificationError::LimitExceeded { limit, max, actual });
+    }
+    Ok(())
+}
+
+impl Bounded for SuffixProof {
+    fn cost(&self, _: &Digest) -> ProofCost {
+        ProofCost {
+            proof_elements: self.proof.len() as u64,
+            entries: self.num_suffix_elements,
+            tree_size: self.num_leaves.unwrap_or_default(),
+        }
+    }
+}
+
+impl Bounded for InclusionProof {
+    fn cost(&self, (_, num_leaves): &(Digest, u64)) -> ProofCost {
+        ProofCost {
+            proof_elements: self.siblings.len() as u64,
+            entries: 1,
+            tree_size: *num_leaves,
+        }
+    }
+}
+
+impl Bounded for MultiInclusionProof {
+    fn cost(&self, (_, num_leaves): &(Digest, u64)) -> ProofCost {
+        ProofCost {
+            proof_elements: self.siblings.len() as u64,
+            entries: self.leaf_indices.len() as u64,
+            tree_size: *num_leaves,
+        }
+    }
+}
+
+impl Bounded for MostRecentNElementsProof {
+    fn cost(&self, commitment: &MmrCommitment) -> ProofCost {
+        ProofCost {
+            proof_elements: self
+                .partial_tree_proof
+                .as_ref()
+                .map_or(0, |(_, proof)| proof.proof.len() as u64),
+            entries: self.entries.len() as u64,
+            tree_size: commitment.num_entries(),
+        }
+    }
+}
+
+impl Bounded for MmrInclusionProof {
+    fn cost(&self, commitment: &MmrCommitment) -> ProofCost {
+        ProofCost {
+            proof_elements: self.proof.siblings.len() as u64,
+            entries: 1,
+            tree_size: commitment.num_entries(),
+        }
+    }
+}
+
+impl Bounded for MmrMultiInclusionProof {
+    fn cost(&self, commitment: &MmrCommitment) -> ProofCost {
+        ProofCost {
+            proof_elements: self
+                .tree_proofs
+                .iter()
+                .map(|(_, proof)| proof.siblings.len() as u64)
+                .sum(),
+            entries: self.entries.len() as u64,
+            tree_size: commitment.num_entries(),
+        }
+    }
+}
+
+impl Bounded for ConsistencyProof {
+    fn cost(&self, commitment: &MmrCommitment) -> ProofCost {
+        ProofCost {
+            proof_elements: self.paths.iter().map(|path| path.len() as u64).sum(),
+            entries: 0,
+            tree_size: commitment.num_entries(),
+        }
+    }
+}
+
+impl Bounded for AppendedRangeProof {
+    fn cost(&self, commitment: &MmrCommitment) -> ProofCost {
+        ProofCost {
+            proof_elements: 0,
+            entries: self.entries.len() as u64,
+            tree_size: commitment.num_entries(),
+        }
+    }
+}
+
+impl Bounded for TimeRangeProof {
+    fn cost(&self, commitment: &MmrCommitment) -> ProofCost {
+        self.proof
+            .as_ref()
+            .map_or_else(ProofCost::default, |proof| proof.cost(commitment))
+    }
+}
+
+impl Bounded for LogProof {
+    fn cost(&self, _: &Digest) -> ProofCost {
+        ProofCost {
+            proof_elements: self.proof.proof_items.len() as u64,
+            entries: 1,
+            tree_size: self.proof.mmr_size,
+        }
+    }
+}
@@ -0,0 +1,940 @@
+//! Proofs about a Merkle Mountain Range and the commitment they are checked against.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use ads_core::encoding::{bytes_length, uleb128_length};
+use ads_core::{
+    Blake2b256, HashAlgo, Hasher, NodeHasher, ParseDigestError, Proof, UnknownHashAlgo, EMPTY_ROOT,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+#[cfg(feature = "borsh")]
+use crate::error::invalid_data;
+use crate::error::VerificationError;
+use crate::ordering::LeafOrdering;
+use crate::proof::{
+    check_path_length, entries_length, tree_root, InclusionProof, MultiInclusionProof, SuffixProof,
+    USIZE_LENGTH, VERSION_LENGTH,
+};
+use crate::standard::{mmr_size_from_leaf_count, PeakBagging};
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// What a verifier needs to know about an MMR: its number of entries and the digests of its trees,
+/// the peaks.
+///
+/// Peaks are ordered from the oldest (largest) tree to the most recent one, as in the
+/// conventional layout, and there is one per bit set in `num_entries`. Deserializing with serde or
+/// borsh checks that count. `Display` prints `<num_entries>:<peak>,<peak>,...`, prefixed with
+/// `<hash algorithm>/` unless it's Blake2b256, which `FromStr` parses back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawMmrCommitment")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct MmrCommitment {
+    pub version: u8,
+    pub num_entries: u64,
+    pub peaks: Vec<Digest>,
+    // The hash algorithm of the MMR if not Blake2b256, since version 4
+    pub hash_algo: Option<HashAlgo>,
+    // The order of the entries if not the append order, since version 5
+    pub ordering: Option<LeafOrdering>,
+}
+
+// `MmrCommitment` as encoded, before the number of peaks is checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawMmrCommitment {
+    version: u8,
+    num_entries: u64,
+    peaks: Vec<Digest>,
+    hash_algo: Option<HashAlgo>,
+    ordering: Option<LeafOrdering>,
+}
+
+impl TryFrom<RawMmrCommitment> for MmrCommitment {
+    type Error = CommitmentError;
+
+    fn try_from(raw: RawMmrCommitment) -> Result<Self, CommitmentError> {
+        let mut commitment = MmrCommitment::new(raw.num_entries, raw.peaks)?
+            .with_hash_algo(raw.hash_algo.unwrap_or_default())
+            .with_ordering(raw.ordering.unwrap_or_default());
+        commitment.version = raw.version;
+        Ok(commitment)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for MmrCommitment {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawMmrCommitment::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+/// Why a commitment couldn't be built or parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommitmentError {
+    /// The number of entries calls for a different number of peaks
+    WrongNumberOfPeaks {
+        expected: usize,
+        actual: usize,
+    },
+    /// No `:` between the number of entries and the peaks
+    MissingSeparator,
+    InvalidNumberOfEntries,
+    /// The peak at this position isn't a digest
+    InvalidPeak(usize, ParseDigestError),
+    UnknownHashAlgo(UnknownHashAlgo),
+}
+
+impl fmt::Display for CommitmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitmentError::WrongNumberOfPeaks { expected, actual } => {
+                write!(f, "Expected {} peaks but got {}", expected, actual)
+            }
+            CommitmentError::MissingSeparator => {
+                write!(f, "Missing ':' after the number of entries")
+            }
+            CommitmentError::InvalidNumberOfEntries => write!(f, "Invalid number of entries"),
+            CommitmentError::InvalidPeak(index, e) => write!(f, "Invalid peak {}: {}", index, e),
+            CommitmentError::UnknownHashAlgo(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl core::error::Error for CommitmentError {}
+
+// Indices of the full trees (smallest first), and of the partial tree with the number of its
+// leaves covered
+type ProofShape = (Vec<u64>, Option<(u64, u64)>);
+
+/// The most recent n elements proof contains some full trees and at most one partial tree.
+///
+/// Deserializing with serde or borsh checks the same invariants as `new`, so a decoded proof is
+/// well-formed, though only verification tells whether it matches a commitment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawMostRecentNElementsProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct MostRecentNElementsProof {
+    pub version: u8,
+    pub entries: Vec<Vec<u8>>,
+    // Indices of trees that contain all the elements in the proof
+    pub full_tree_indices: Vec<u64>,
+    // If N is an exact span of some trees, then this is None.
+    pub partial_tree_proof: Option<(u64, SuffixProof)>,
+    // As in `MmrCommitment`, since version 4
+    pub hash_algo: Option<HashAlgo>,
+}
+
+// `MostRecentNElementsProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawMostRecentNElementsProof {
+    version: u8,
+    entries: Vec<Vec<u8>>,
+    full_tree_indices: Vec<u64>,
+    partial_tree_proof: Option<(u64, SuffixProof)>,
+    hash_algo: Option<HashAlgo>,
+}
+
+impl TryFrom<RawMostRecentNElementsProof> for MostRecentNElementsProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawMostRecentNElementsProof) -> Result<Self, VerificationError> {
+        let proof = MostRecentNElementsProof {
+            version: raw.version,
+            entries: raw.entries,
+            full_tree_indices: raw.full_tree_indices,
+            partial_tree_proof: raw.partial_tree_proof,
+            hash_algo: raw.hash_algo,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for MostRecentNElementsProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawMostRecentNElementsProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+/// Inclusion proof of a single entry: the tree holding it plus a path within that tree.
+///
+/// Deserializing with serde or borsh checks `check_shape`, as for `MmrMultiInclusionProof`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawMmrInclusionProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct MmrInclusionProof {
+    pub version: u8,
+    pub entry: Vec<u8>,
+    pub tree_index: u64,
+    pub proof: InclusionProof,
+    // As in `MmrCommitment`, since version 4
+    pub hash_algo: Option<HashAlgo>,
+}
+
+/// Inclusion proof of several entries at once: one multi-proof per tree holding some of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawMmrMultiInclusionProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct MmrMultiInclusionProof {
+    pub version: u8,
+    // In increasing order of index, i.e., in the order of `tree_proofs` and their leaf indices
+    pub entries: Vec<Vec<u8>>,
+    // From the oldest (largest) tree to the most recent one
+    pub tree_proofs: Vec<(u64, MultiInclusionProof)>,
+    // As in `MmrCommitment`, since version 4
+    pub hash_algo: Option<HashAlgo>,
+}
+
+// `MmrInclusionProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawMmrInclusionProof {
+    version: u8,
+    entry: Vec<u8>,
+    tree_index: u64,
+    proof: InclusionProof,
+    hash_algo: Option<HashAlgo>,
+}
+
+impl TryFrom<RawMmrInclusionProof> for MmrInclusionProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawMmrInclusionProof) -> Result<Self, VerificationError> {
+        let proof = MmrInclusionProof {
+            version: raw.version,
+            entry: raw.entry,
+            tree_index: raw.tree_index,
+            proof: raw.proof,
+            hash_algo: raw.hash_algo,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for MmrInclusionProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawMmrInclusionProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+// `MmrMultiInclusionProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawMmrMultiInclusionProof {
+    version: u8,
+    entries: Vec<Vec<u8>>,
+    tree_proofs: Vec<(u64, MultiInclusionProof)>,
+    hash_algo: Option<HashAlgo>,
+}
+
+impl TryFrom<RawMmrMultiInclusionProof> for MmrMultiInclusionProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawMmrMultiInclusionProof) -> Result<Self, VerificationError> {
+        let proof = MmrMultiInclusionProof {
+            version: raw.version,
+            entries: raw.entries,
+            tree_proofs: raw.tree_proofs,
+            hash_algo: raw.hash_algo,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for MmrMultiInclusionProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawMmrMultiInclusionProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+// The `hash_algo` field of a commitment or proof about an MMR hashed with `algo`
+fn hash_algo_field(algo: HashAlgo) -> Option<HashAlgo> {
+    (algo != HashAlgo::Blake2b256).then_some(algo)
+}
+
+impl MmrInclusionProof {
+    /// The same proof about an MMR hashed with `algo`.
+    pub fn with_hash_algo(mut self, algo: HashAlgo) -> Self {
+        self.hash_algo = hash_algo_field(algo);
+        self
+    }
+
+    pub fn hash_algo(&self) -> HashAlgo {
+        self.hash_algo.unwrap_or_default()
+    }
+
+    /// Check the invariants that hold regardless of the commitment: the tree fits a u64 MMR and
+    /// the path is as long as the tree is high, ending at a leaf of that tree.
+    pub fn check_shape(&self) -> Result<(), VerificationError> {
+        check_tree_index(self.tree_index)?;
+        self.proof.check_shape()?;
+        check_path_length(self.tree_index as usize, self.proof.siblings.len())
+    }
+}
+
+impl MmrMultiInclusionProof {
+    /// The same proof about an MMR hashed with `algo`.
+    pub fn with_hash_algo(mut self, algo: HashAlgo) -> Self {
+        self.hash_algo = hash_algo_field(algo);
+        self
+    }
+
+    pub fn hash_algo(&self) -> HashAlgo {
+        self.hash_algo.unwrap_or_default()
+    }
+
+    /// Check the invariants that hold regardless of the commitment: trees fit a u64 MMR and come
+    /// each once from the largest, their leaf indices are within them, and there's one entry per
+    /// leaf index.
+    pub fn check_shape(&self) -> Result<(), VerificationError> {
+        let mut previous = None;
+        let mut num_leaves = 0u64;
+        for (tree_index, tree_proof) in &self.tree_proofs {
+            let tree_index = *tree_index;
+            check_tree_index(tree_index)?;
+            check_tree_order(previous, tree_index, |previous| previous > tree_index)?;
+            previous = Some(tree_index);
+            tree_proof.check_shape()?;
+            // Strictly increasing, so the last index is the largest
+            if let Some(&leaf_index) = tree_proof.leaf_indices.last() {
+                if leaf_index >> tree_index != 0 {
+                    return Err(VerificationError::LeafIndexOutOfBounds(leaf_index));
+                }
+            }
+            num_leaves += tree_proof.leaf_indices.len() as u64;
+        }
+        if num_leaves != self.entries.len() as u64 {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: num_leaves,
+                actual: self.entries.len() as u64,
+            });
+        }
+        Ok(())
+    }
+}
+
+// Trees of an MMR of at most u64::MAX entries
+fn check_tree_index(tree_index: u64) -> Result<(), VerificationError> {
+    if tree_index >= u64::BITS as u64 {
+        return Err(VerificationError::TreeIndexOutOfBounds(tree_index));
+    }
+    Ok(())
+}
+
+// A tree following `previous` in a proof, which `in_order` says is in the proof's order: a repeat
+// is a duplicate, anything else out of order isn't canonical
+fn check_tree_order(
+    previous: Option<u64>,
+    tree_index: u64,
+    in_order: impl Fn(u64) -> bool,
+) -> Result<(), VerificationError> {
+    match previous {
+        Some(previous) if previous == tree_index => {
+            Err(VerificationError::DuplicateTreeIndex(tree_index))
+        }
+        Some(previous) if !in_order(previous) => Err(VerificationError::NonCanonicalProof),
+        _ => Ok(()),
+    }
+}
+
+impl MostRecentNElementsProof {
+    /// A proof of `entries`, the most recent ones of an MMR, from its full trees (smallest first)
+    /// and the suffix of the next tree holding the oldest of them.
+    ///
+    /// Fails if the trees don't cover exactly `entries`, or aren't laid out as a proof by
+    /// `MerkleMountainRange::prove_most_recent_n_elements` would be.
+    pub fn new(
+        entries: Vec<Vec<u8>>,
+        full_tree_indices: Vec<u64>,
+        partial_tree_proof: Option<(u64, SuffixProof)>,
+    ) -> Result<Self, VerificationError> {
+        let proof = MostRecentNElementsProof {
+            version: PROOF_FORMAT_VERSION,
+            entries,
+            full_tree_indices,
+            partial_tree_proof,
+            hash_algo: None,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+
+    /// The same proof about an MMR hashed with `algo`.
+    pub fn with_hash_algo(mut self, algo: HashAlgo) -> Self {
+        self.hash_algo = hash_algo_field(algo);
+        self
+    }
+
+    pub fn hash_algo(&self) -> HashAlgo {
+        self.hash_algo.unwrap_or_default()
+    }
+
+    /// The proven entries, oldest first.
+    pub fn entries(&self) -> &[Vec<u8>] {
+        &self.entries
+    }
+
+    /// Indices of the trees whose leaves are all proven, smallest first.
+    pub fn full_tree_indices(&self) -> &[u64] {
+        &self.full_tree_indices
+    }
+
+    /// The index of the tree holding the oldest proven entries along with their proof, None if
+    /// only full trees are proven.
+    pub fn partial_tree_proof(&self) -> Option<(u64, &SuffixProof)> {
+        self.partial_tree_proof
+            .as_ref()
+            .map(|(index, proof)| (*index, proof))
+    }
+
+    pub fn num_entries(&self) -> usize {
+        self.entries.len()
+    }
+
+    // The invariants that hold regardless of the commitment: tree indices fit a u64 MMR and
+    // increase, the partial tree is larger than the full ones and only partly covered, and the
+    // trees cover exactly the entries
+    pub(crate) fn check_shape(&self) -> Result<(), VerificationError> {
+        if self.entries.is_empty() {
+            return Err(VerificationError::EmptyProof);
+        }
+        let partial_tree_index = self.partial_tree_proof.as_ref().map(|(index, _)| *index);
+        let mut previous = None;
+        for &tree_index in self.full_tree_indices.iter().chain(&partial_tree_index) {
+            check_tree_index(tree_index)?;
+            check_tree_order(previous, tree_index, |previous| previous < tree_index)?;
+            previous = Some(tree_index);
+        }
+        if let Some((_, suffix_proof)) = &self.partial_tree_proof {
+            suffix_proof.check_shape()?;
+        }
+        let mut covered = self
+            .full_tree_indices
+            .iter()
+            .map(|&tree_index| 1 << tree_index)
+            .try_fold(0u64, u64::checked_add);
+        if let Some((tree_index, suffix_proof)) = &self.partial_tree_proof {
+            let num_leaves = 1 << tree_index;
+            if suffix_proof.num_suffix_elements == 0
+                || suffix_proof.num_suffix_elements >= num_leaves
+                || suffix_proof.num_leaves.is_some_and(|n| n != num_leaves)
+            {
+                return Err(VerificationError::NonCanonicalProof);
+            }
+            covered = covered.and_then(|c| c.checked_add(suffix_proof.num_suffix_elements));
+        }
+        match covered {
+            Some(covered) if covered == self.entries.len() as u64 => Ok(()),
+            _ => Err(VerificationError::WrongNumberOfEntries {
+                expected: covered.unwrap_or(u64::MAX),
+                actual: self.entries.len() as u64,
+            }),
+        }
+    }
+}
+
+impl MmrCommitment {
+    /// The commitment to an MMR of `num_entries` entries with the given peaks, oldest first.
+    pub fn new(num_entries: u64, peaks: Vec<Digest>) -> Result<Self, CommitmentError> {
+        let expected = num_entries.count_ones() as usize;
+        if peaks.len() != expected {
+            return Err(CommitmentError::WrongNumberOfPeaks {
+                expected,
+                actual: peaks.len(),
+            });
+        }
+        Ok(MmrCommitment {
+            version: PROOF_FORMAT_VERSION,
+            num_entries,
+            peaks,
+            hash_algo: None,
+            ordering: None,
+        })
+    }
+
+    /// The same commitment to an MMR hashed with `algo`.
+    pub fn with_hash_algo(mut self, algo: HashAlgo) -> Self {
+        self.hash_algo = hash_algo_field(algo);
+        self
+    }
+
+    /// The hash algorithm of the MMR, which proofs are verified with.
+    pub fn hash_algo(&self) -> HashAlgo {
+        self.hash_algo.unwrap_or_default()
+    }
+
+    /// The same commitment to an MMR whose entries are ordered as `ordering` says.
+    pub fn with_ordering(mut self, ordering: LeafOrdering) -> Self {
+        self.ordering = (ordering != LeafOrdering::Append).then_some(ordering);
+        self
+    }
+
+    /// The order of the entries, which non-membership proofs rely on.
+    pub fn ordering(&self) -> LeafOrdering {
+        self.ordering.unwrap_or_default()
+    }
+
+    // Check that the proof and the hasher it's verified with are for this MMR's algorithm
+    pub(crate) fn check_hash_algo(
+        &self,
+        hasher: &NodeHasher,
+        proof_algo: HashAlgo,
+    ) -> Result<(), VerificationError> {
+        let expected = self.hash_algo();
+        for actual in [proof_algo, hasher.algo()] {
+            if actual != expected {
+                return Err(VerificationError::HashAlgoMismatch { expected, actual });
+            }
+        }
+        Ok(())
+    }
+
+    /// The commitment from the digest of each tree: `digests[h]` is the digest of the tree of 2^h
+    /// leaves, None if the MMR has no such tree, exactly as returned by
+    /// `MerkleMountainRange::digests` and `MmrAppender::digests`.
+    pub fn from_digests(digests: &[Option<Digest>]) -> Self {
+        let num_entries = digests
+            .iter()
+            .enumerate()
+            .filter(|(_, digest)| digest.is_some())
+            .map(|(height, _)| 1u64 << height)
+            .sum();
+        MmrCommitment {
+            version: PROOF_FORMAT_VERSION,
+            num_entries,
+            peaks: digests.iter().rev().flatten().copied().collect(),
+            hash_algo: None,
+            ordering: None,
+        }
+    }
+
+    /// The commitment to an MMR without entries, which every MMR is consistent with.
+    pub fn empty() -> Self {
+        Self::from_digests(&[])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_entries == 0
+    }
+
+    /// The digest of each tree, in the format of `from_digests`.
+    pub fn digests(&self) -> Vec<Option<Digest>> {
+        let num_heights = (u64::BITS - self.num_entries.leading_zeros()) as u64;
+        (0..num_heights)
+            .map(|tree_index| self.digest_at(tree_index).ok().copied())
+            .collect()
+    }
+
+    /// Digest of the tree of 2^h leaves, `EMPTY_ROOT` if the MMR has no such tree.
+    pub fn tree_digest(&self, tree_index: u64) -> Digest {
+        self.digest_at(tree_index).copied().unwrap_or(EMPTY_ROOT)
+    }
+
+    /// Root of the MMR in the conventional layout (see `standard`), `EMPTY_ROOT` if it's empty.
+    pub fn standard_root(&self) -> Digest {
+        self.standard_root_with_bagging(&PeakBagging::CKB)
+    }
+
+    /// Same as `standard_root`, with the peaks bagged as `bagging` does.
+    pub fn standard_root_with_bagging(&self, bagging: &PeakBagging) -> Digest {
+        self.standard_root_with_hasher(&NodeHasher::new(self.hash_algo()), bagging)
+    }
+
+    /// Same as `standard_root_with_bagging`, for an MMR hashed with `hasher`, e.g., keyed.
+    pub fn standard_root_with_hasher(&self, hasher: &NodeHasher, bagging: &PeakBagging) -> Digest {
+        bagging
+            .bag_with_hasher(
+                hasher,
+                &self.peaks,
+                mmr_size_from_leaf_count(self.num_entries),
+            )
+            .unwrap_or(EMPTY_ROOT)
+    }
+
+    /// The single digest to publish or sign for the MMR: the Blake2b256 of the number of entries
+    /// (8 bytes, little-endian) followed by the standard root, so that MMRs with the same peaks but
+    /// different sizes commit differently. The name of the hash algorithm follows unless it's
+    /// Blake2b256, then the name of the ordering of the entries unless it's the append order.
+    pub fn digest(&self) -> Digest {
+        let mut hasher = Blake2b256::default();
+        hasher.update(&self.num_entries.to_le_bytes());
+        hasher.update(self.standard_root().as_bytes());
+        if let Some(algo) = self.hash_algo {
+            hasher.update(algo.name().as_bytes());
+        }
+        if let Some(ordering) = self.ordering {
+            hasher.update(ordering.name().as_bytes());
+        }
+        hasher.finalize()
+    }
+
+    /// Number of entries committed to.
+    pub fn num_entries(&self) -> u64 {
+        self.num_entries
+    }
+
+    pub fn verify_most_recent_n_elements(
+        &self,
+        proof: &MostRecentNElementsProof,
+    ) -> Result<(), VerificationError> {
+        self.verify_most_recent_n_elements_with_hasher(&NodeHasher::new(self.hash_algo()), proof)
+    }
+
+    /// Same as `verify_most_recent_n_elements`, for an MMR hashed with `hasher`.
+    pub fn verify_most_recent_n_elements_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proof: &MostRecentNElementsProof,
+    ) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        check_version(proof.version)?;
+        self.check_hash_algo(hasher, proof.hash_algo())?;
+        // Check that provided entries are non-empty
+        if proof.entries.is_empty() {
+            return Err(VerificationError::EmptyProof);
+        }
+
+        let num_suffix_elements = proof.entries.len() as u64;
+        let mut total_leaves_covered = 0;
+
+        // Every referenced tree must exist, and the proof must cover exactly the most recent trees,
+        // so that the same query can't be answered with trees skipped, repeated or split differently
+        let partial_tree_index = proof.partial_tree_proof.as_ref().map(|(index, _)| *index);
+        for &tree_index in proof.full_tree_indices.iter().chain(&partial_tree_index) {
+            self.digest_at(tree_index)?;
+        }
+        let (full_tree_indices, partial) = self.most_recent_shape(num_suffix_elements)?;
+        let proof_partial = proof
+            .partial_tree_proof
+            .as_ref()
+            .map(|(index, suffix_proof)| (*index, suffix_proof.num_suffix_elements));
+        if proof.full_tree_indices != full_tree_indices || proof_partial != partial {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+
+        // First, handle partial tree if present (it contains the oldest elements)
+        let mut entry_offset = 0;
+        if let Some((tree_index, ref suffix_proof)) = proof.partial_tree_proof {
+            let digest = self.digest_at(tree_index)?;
+
+            let partial_elements = suffix_proof.num_suffix_elements;
+            total_leaves_covered += partial_elements;
+
+            // Partial tree gets the first (oldest) elements
+            if partial_elements > num_suffix_elements {
+                return Err(VerificationError::WrongNumberOfEntries {
+                    expected: partial_elements,
+                    actual: num_suffix_elements,
+                });
+            }
+            let tree_entries = &proof.entries[0..partial_elements as usize];
+
+            suffix_proof.verify_with_hasher(hasher, digest, 1 << tree_index, tree_entries)?;
+
+            entry_offset = partial_elements;
+        }
+
+        // Then process full trees from largest index to smallest
+        // (from oldest to most recent in terms of data)
+        for &tree_index in proof.full_tree_indices.iter().rev() {
+            let digest = self.digest_at(tree_index)?;
+
+            let tree_leaves = 1 << tree_index;
+            total_leaves_covered += tree_leaves;
+
+            // Get the entries for this tree
+            let tree_entries_end = entry_offset + tree_leaves;
+            if tree_entries_end > num_suffix_elements {
+                return Err(VerificationError::WrongNumberOfEntries {
+                    expected: tree_entries_end,
+                    actual: num_suffix_elements,
+                });
+            }
+            let tree_entries = &proof.entries[entry_offset as usize..tree_entries_end as usize];
+            entry_offset = tree_entries_end;
+
+            // Reconstruct and verify root for full tree
+            if tree_root(hasher, tree_entries) != *digest {
+                return Err(VerificationError::RootMismatch);
+            }
+        }
+
+        // Check that all entries were accounted for
+        if total_leaves_covered != num_suffix_elements {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: total_leaves_covered,
+                actual: num_suffix_elements,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn verify_inclusion(&self, proof: &MmrInclusionProof) -> Result<(), VerificationError> {
+        self.verify_inclusion_with_hasher(&NodeHasher::new(self.hash_algo()), proof)
+    }
+
+    /// Same as `verify_inclusion`, for an MMR hashed with `hasher`.
+    pub fn verify_inclusion_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proof: &MmrInclusionProof,
+    ) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        check_version(proof.version)?;
+        self.check_hash_algo(hasher, proof.hash_algo())?;
+        let digest = self.digest_at(proof.tree_index)?;
+        proof
+            .proof
+            .verify_with_hasher(hasher, digest, 1 << proof.tree_index, &proof.entry)?;
+        Ok(())
+    }
+
+    /// Verify that every entry of the proof is committed to, at the leaves the proof claims.
+    pub fn verify_multi_inclusion(
+        &self,
+        proof: &MmrMultiInclusionProof,
+    ) -> Result<(), VerificationError> {
+        self.verify_multi_inclusion_with_hasher(&NodeHasher::new(self.hash_algo()), proof)
+    }
+
+    /// Same as `verify_multi_inclusion`, for an MMR hashed with `hasher`.
+    pub fn verify_multi_inclusion_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proof: &MmrMultiInclusionProof,
+    ) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        check_version(proof.version)?;
+        self.check_hash_algo(hasher, proof.hash_algo())?;
+        if proof.tree_proofs.is_empty() {
+            return Err(VerificationError::EmptyProof);
+        }
+        // Trees in order, each at most once, so that entries are in order too
+        if proof
+            .tree_proofs
+            .windows(2)
+            .any(|pair| pair[0].0 <= pair[1].0)
+        {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+
+        let mut entries = proof.entries.as_slice();
+        for (tree_index, tree_proof) in &proof.tree_proofs {
+            let digest = self.digest_at(*tree_index)?;
+            let num_tree_entries = tree_proof.leaf_indices.len().min(entries.len());
+            let (tree_entries, rest) = entries.split_at(num_tree_entries);
+            tree_proof.verify_with_hasher(hasher, digest, 1 << *tree_index, tree_entries)?;
+            entries = rest;
+        }
+        if !entries.is_empty() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: (proof.entries.len() - entries.len()) as u64,
+                actual: proof.entries.len() as u64,
+            });
+        }
+        Ok(())
+    }
+
+    /// Index among all entries of the leaf at `leaf_index` of the tree at `tree_index`, e.g. to
+    /// locate the entries of a verified inclusion proof. None if there's no such leaf.
+    pub fn entry_index(&self, tree_index: u64, leaf_index: u64) -> Option<u64> {
+        self.digest_at(tree_index).ok()?;
+        if leaf_index >= 1 << tree_index {
+            return None;
+        }
+        // Larger trees hold older entries
+        let smaller_or_equal = u64::MAX >> (u64::BITS as u64 - 1 - tree_index);
+        Some((self.num_entries & !smaller_or_equal) + leaf_index)
+    }
+
+    // The heights of the trees, from the smallest (most recent) one
+    pub(crate) fn tree_indices(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..u64::BITS as u64).filter(|&height| self.num_entries >> height & 1 == 1)
+    }
+
+    // The trees a proof of the most recent `n` entries covers
+    pub(crate) fn most_recent_shape(&self, n: u64) -> Result<ProofShape, VerificationError> {
+        let num_entries = self.num_entries();
+        if n > num_entries {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: num_entries,
+                actual: n,
+            });
+        }
+        let mut remaining = n;
+        let mut full_tree_indices = Vec::new();
+        for tree_index in self.tree_indices() {
+            if remaining == 0 {
+                break;
+            }
+            if 1 << tree_index <= remaining {
+                remaining -= 1 << tree_index;
+                full_tree_indices.push(tree_index);
+            } else {
+                return Ok((full_tree_indices, Some((tree_index, remaining))));
+            }
+        }
+        Ok((full_tree_indices, None))
+    }
+
+    // The digest of the tree at `tree_index`, as referenced by a proof
+    pub(crate) fn digest_at(&self, tree_index: u64) -> Result<&Digest, VerificationError> {
+        let num_heights = (u64::BITS - self.num_entries.leading_zeros()) as u64;
+        if tree_index >= num_heights {
+            return Err(VerificationError::TreeIndexOutOfBounds(tree_index));
+        }
+        if self.num_entries >> tree_index & 1 == 0 {
+            return Err(VerificationError::MissingTree(tree_index));
+        }
+        // Peaks of larger trees come first
+        let larger = (self.num_entries >> tree_index >> 1).count_ones() as usize;
+        self.peaks
+            .get(larger)
+            .ok_or(VerificationError::TreeIndexOutOfBounds(tree_index))
+    }
+}
+
+impl fmt::Display for MmrCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(algo) = self.hash_algo {
+            write!(f, "{}/", algo)?;
+        }
+        if let Some(ordering) = self.ordering {
+            write!(f, "{}/", ordering)?;
+        }
+        write!(f, "{}:", self.num_entries)?;
+        for (i, peak) in self.peaks.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", peak)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MmrCommitment {
+    type Err = CommitmentError;
+
+    /// Parse the form printed by `Display`.
+    fn from_str(s: &str) -> Result<Self, CommitmentError> {
+        let (algo, s) = match s.split_once('/') {
+            Some((algo, rest)) if algo != LeafOrdering::Sorted.name() => (
+                algo.parse().map_err(CommitmentError::UnknownHashAlgo)?,
+                rest,
+            ),
+            _ => (HashAlgo::Blake2b256, s),
+        };
+        let (ordering, s) = match s.split_once('/') {
+            Some((ordering, rest)) if ordering == LeafOrdering::Sorted.name() => {
+                (LeafOrdering::Sorted, rest)
+            }
+            _ => (LeafOrdering::Append, s),
+        };
+        let (num_entries, peaks) = s.split_once(':').ok_or(CommitmentError::MissingSeparator)?;
+        let num_entries = num_entries
+            .parse()
+            .map_err(|_| CommitmentError::InvalidNumberOfEntries)?;
+        let peaks = if peaks.is_empty() {
+            Vec::new()
+        } else {
+            peaks
+                .split(',')
+                .enumerate()
+                .map(|(i, peak)| peak.parse().map_err(|e| CommitmentError::InvalidPeak(i, e)))
+                .collect::<Result<_, _>>()?
+        };
+        Ok(MmrCommitment::new(num_entries, peaks)?
+            .with_hash_algo(algo)
+            .with_ordering(ordering))
+    }
+}
+
+impl Proof for MostRecentNElementsProof {
+    type Commitment = MmrCommitment;
+    type Claim = ();
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(&self, commitment: &MmrCommitment, _: &()) -> Result<(), VerificationError> {
+        commitment.verify_most_recent_n_elements(self)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH
+            + entries_length(&self.entries)
+            + uleb128_length(self.full_tree_indices.len())
+            + self.full_tree_indices.len() * USIZE_LENGTH
+            + 1
+            + self
+                .partial_tree_proof
+                .as_ref()
+                .map_or(0, |(_, proof)| USIZE_LENGTH + proof.size_hint())
+            + hash_algo_length(self.hash_algo)
+    }
+}
+
+impl Proof for MmrInclusionProof {
+    type Commitment = MmrCommitment;
+    type Claim = ();
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(&self, commitment: &MmrCommitment, _: &()) -> Result<(), VerificationError> {
+        commitment.verify_inclusion(self)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH
+            + bytes_length(self.entry.len())
+            + USIZE_LENGTH
+            + self.proof.size_hint()
+            + hash_algo_length(self.hash_algo)
+    }
+}
+
+impl Proof for MmrMultiInclusionProof {
+    type Commitment = MmrCommitment;
+    type Claim = ();
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(&self, commitment: &MmrCommitment, _: &()) -> Result<(), VerificationError> {
+        commitment.verify_multi_inclusion(self)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH
+            + entries_length(&self.entries)
+            + uleb128_length(self.tree_proofs.len())
+            + self
+                .tree_proofs
+                .iter()
+                .map(|(_, proof)| USIZE_LENGTH + proof.size_hint())
+                .sum::<usize>()
+            + hash_algo_length(self.hash_algo)
+    }
+}
+
+// BCS length of a `hash_algo` field: the option tag and the variant index
+fn hash_algo_length(algo: Option<HashAlgo>) -> usize {
+    1 + algo.map_or(0, |_| 1)
+}
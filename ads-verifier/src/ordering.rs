@@ -0,0 +1,149 @@
+//! The order of the entries of an MMR, recorded in its commitment.
+//!
+//! Entries are normally in the order they were appended. An MMR built over a set may instead keep
+//! its entries sorted, strictly increasing as bytes, which its commitment records so that
+//! verifiers know they can rely on it. Two adjacent entries then prove that no entry lies between
+//! them, which is how a `NonMembershipProof` shows that a value isn't in the set.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{MmrCommitment, MmrMultiInclusionProof};
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// How the entries of an MMR are ordered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum LeafOrdering {
+    /// In the order they were appended, possibly with duplicates
+    #[default]
+    Append,
+    /// Strictly increasing as bytes, i.e., sorted and without duplicates
+    Sorted,
+}
+
+impl LeafOrdering {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LeafOrdering::Append => "append",
+            LeafOrdering::Sorted => "sorted",
+        }
+    }
+
+    /// Whether `next` may follow `previous`.
+    pub fn allows(&self, previous: &[u8], next: &[u8]) -> bool {
+        match self {
+            LeafOrdering::Append => true,
+            LeafOrdering::Sorted => previous < next,
+        }
+    }
+}
+
+impl fmt::Display for LeafOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Proof that a value isn't an entry of an MMR with sorted entries: the entries right before and
+/// after it, adjacent, or the first or last entry alone if the value is out of their range.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct NonMembershipProof {
+    pub version: u8,
+    // None if the MMR is empty
+    pub neighbours: Option<MmrMultiInclusionProof>,
+}
+
+impl NonMembershipProof {
+    pub fn new(neighbours: Option<MmrMultiInclusionProof>) -> Self {
+        NonMembershipProof {
+            version: PROOF_FORMAT_VERSION,
+            neighbours,
+        }
+    }
+}
+
+impl MmrCommitment {
+    // Check that the entries are ordered as a proof relies on
+    pub(crate) fn check_ordering(&self, expected: LeafOrdering) -> Result<(), VerificationError> {
+        if self.ordering() != expected {
+            return Err(VerificationError::LeafOrderingMismatch {
+                expected,
+                actual: self.ordering(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Verify that `value` isn't an entry of the MMR, whose entries must be sorted.
+    pub fn verify_non_membership(
+        &self,
+        proof: &NonMembershipProof,
+        value: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.verify_non_membership_with_hasher(&NodeHasher::new(self.hash_algo()), proof, value)
+    }
+
+    /// Same as `verify_non_membership`, for an MMR hashed with `hasher`.
+    pub fn verify_non_membership_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proof: &NonMembershipProof,
+        value: &[u8],
+    ) -> Result<(), VerificationError> {
+        check_version(proof.version)?;
+        self.check_ordering(LeafOrdering::Sorted)?;
+        let Some(neighbours) = &proof.neighbours else {
+            return match self.num_entries() {
+                0 => Ok(()),
+                _ => Err(VerificationError::EmptyProof),
+            };
+        };
+        self.verify_multi_inclusion_with_hasher(hasher, neighbours)?;
+
+        let mut indices = neighbours
+            .tree_proofs
+            .iter()
+            .flat_map(|(tree_index, tree_proof)| {
+                tree_proof
+                    .leaf_indices
+                    .iter()
+                    .map(|&leaf_index| self.entry_index(*tree_index, leaf_index))
+            });
+        let entries = &neighbours.entries;
+        let last = self.num_entries() - 1;
+        let enclosed = match (entries.as_slice(), indices.next(), indices.next()) {
+            // Before the first entry or after the last one
+            ([entry], Some(Some(index)), None) => {
+                (index == 0 && value < entry.as_slice())
+                    || (index == last && value > entry.as_slice())
+            }
+            // Between two adjacent entries
+            ([lower, upper], Some(Some(lower_index)), Some(Some(upper_index))) => {
+                upper_index == lower_index + 1
+                    && lower.as_slice() < value
+                    && value < upper.as_slice()
+            }
+            _ => {
+                return Err(VerificationError::WrongNumberOfEntries {
+                    expected: 2,
+                    actual: entries.len() as u64,
+                })
+            }
+        };
+        if !enclosed {
+            return Err(VerificationError::NotAbsent);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+//! How much of the machine parallel operations, such as batch verification, may use.
+//!
+//! By default, parallel work runs on rayon's global pool, which has a thread per core. Servers
+//! sharing their cores between tenants can cap it with a pool of their own, either built here
+//! from a thread count or passed in, and make each task larger so that small batches don't pay
+//! for spreading over threads.
+
+use alloc::sync::Arc;
+
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+#[derive(Debug, Clone)]
+pub struct Parallelism {
+    // None for rayon's global pool
+    pool: Option<Arc<ThreadPool>>,
+    // The fewest items a task handles
+    min_len: usize,
+}
+
+impl Parallelism {
+    /// Rayon's global pool, splitting work down to single items.
+    pub fn new() -> Self {
+        Parallelism {
+            pool: None,
+            min_len: 1,
+        }
+    }
+
+    /// A dedicated pool of `num_threads` threads.
+    pub fn threads(num_threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new().num_threads(num_threads).build()?;
+        Ok(Self::pool(Arc::new(pool)))
+    }
+
+    /// A pool configured by the caller, possibly shared with other work.
+    pub fn pool(pool: Arc<ThreadPool>) -> Self {
+        Parallelism {
+            pool: Some(pool),
+            min_len: 1,
+        }
+    }
+
+    /// Don't split work into tasks of fewer than `min_len` items.
+    pub fn with_min_len(mut self, min_len: usize) -> Self {
+        self.min_len = min_len.max(1);
+        self
+    }
+
+    pub fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    /// Run `op` in the pool, so that the parallel iterators it uses run there too.
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,675 @@
+//! Proofs about a single perfect tree, i.e., one storing 2^n leaves.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use ads_core::encoding::{bytes_length, digests_length, uleb128_length};
+use ads_core::Proof;
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+#[cfg(feature = "borsh")]
+use crate::error::invalid_data;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+// A struct representing a proof of the most recent n elements in a Perfect Merkle Tree.
+//
+// Deserializing with serde or borsh checks `check_shape`, as for every proof about a single tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawSuffixProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct SuffixProof {
+    pub version: u8,
+    pub num_suffix_elements: u64,
+    pub proof: Vec<Digest>,
+    // Number of leaves of the tree, since version 2
+    pub num_leaves: Option<u64>,
+}
+
+// `SuffixProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawSuffixProof {
+    version: u8,
+    num_suffix_elements: u64,
+    proof: Vec<Digest>,
+    num_leaves: Option<u64>,
+}
+
+impl TryFrom<RawSuffixProof> for SuffixProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawSuffixProof) -> Result<Self, VerificationError> {
+        let proof = SuffixProof {
+            version: raw.version,
+            num_suffix_elements: raw.num_suffix_elements,
+            proof: raw.proof,
+            num_leaves: raw.num_leaves,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for SuffixProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawSuffixProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+// Most levels a tree can have, i.e., most siblings on a path of a tree of u64 leaves
+pub(crate) const MAX_HEIGHT: usize = u64::BITS as usize;
+
+// The error for a path of `actual` siblings in a tree of the given height
+pub(crate) fn check_path_length(height: usize, actual: usize) -> Result<(), VerificationError> {
+    if actual < height {
+        return Err(VerificationError::NotEnoughProofElements);
+    }
+    if actual > height {
+        return Err(VerificationError::UnusedProofElements);
+    }
+    Ok(())
+}
+
+impl SuffixProof {
+    pub fn new(num_leaves: u64, num_suffix_elements: u64, proof: Vec<Digest>) -> Self {
+        SuffixProof {
+            version: PROOF_FORMAT_VERSION,
+            num_suffix_elements,
+            proof,
+            num_leaves: Some(num_leaves),
+        }
+    }
+
+    /// Check the invariants that hold whatever the tree: the tree size, if carried, is a power of
+    /// two covering the suffix, and there is at most one left sibling per level.
+    pub fn check_shape(&self) -> Result<(), VerificationError> {
+        let max_siblings = match self.num_leaves {
+            Some(num_leaves) => {
+                if !num_leaves.is_power_of_two() {
+                    return Err(VerificationError::InvalidTreeSize(num_leaves));
+                }
+                if self.num_suffix_elements > num_leaves {
+                    return Err(VerificationError::WrongNumberOfEntries {
+                        expected: num_leaves,
+                        actual: self.num_suffix_elements,
+                    });
+                }
+                num_leaves.trailing_zeros() as usize
+            }
+            None => MAX_HEIGHT,
+        };
+        if self.proof.len() > max_siblings {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(())
+    }
+
+    /// The indices of the proven leaves within their tree, None if the proof predates carrying
+    /// the tree size.
+    pub fn covered_range(&self) -> Option<Range<u64>> {
+        let num_leaves = self.num_leaves?;
+        Some(num_leaves.saturating_sub(self.num_suffix_elements)..num_leaves)
+    }
+
+    /// Verify that `suffix_elements` are the last leaves of the perfect tree with `num_leaves`
+    /// (a power of two) leaves and the given root, which is returned.
+    pub fn verify(
+        &self,
+        root: &Digest,
+        num_leaves: u64,
+        suffix_elements: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::UNKEYED, root, num_leaves, suffix_elements)
+    }
+
+    /// Same as `verify`, for a tree hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        root: &Digest,
+        num_leaves: u64,
+        suffix_elements: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        if self.num_leaves.is_some_and(|n| n != num_leaves) {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        check_canonical(hasher, &self.proof)?;
+        let computed = self.compute_root_with_hasher(hasher, num_leaves, suffix_elements)?;
+        if computed != *root {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(computed)
+    }
+
+    /// Verify that `suffix_elements` are the last leaves of the tree with the given root, taking
+    /// the tree size from the proof.
+    pub fn verify_standalone(
+        &self,
+        root: &Digest,
+        suffix_elements: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        check_version(self.version)?;
+        let num_leaves = self.num_leaves.ok_or(VerificationError::MissingTreeSize)?;
+        if !num_leaves.is_power_of_two() {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        let computed = self.compute_root(num_leaves, suffix_elements)?;
+        if computed != *root {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(computed)
+    }
+
+    /// Recompute the root of the perfect tree with `num_leaves` (a power of two) leaves from its
+    /// last leaves, `suffix_elements`.
+    pub fn compute_root(
+        &self,
+        num_leaves: u64,
+        suffix_elements: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        self.compute_root_with_hasher(&NodeHasher::UNKEYED, num_leaves, suffix_elements)
+    }
+
+    /// Same as `compute_root`, for a tree hashed with `hasher`.
+    pub fn compute_root_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        num_leaves: u64,
+        suffix_elements: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        check_tree_size(num_leaves)?;
+        check_version(self.version)?;
+        if suffix_elements.len() as u64 != self.num_suffix_elements {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: self.num_suffix_elements,
+                actual: suffix_elements.len() as u64,
+            });
+        }
+        if suffix_elements.is_empty() {
+            return Err(VerificationError::EmptyProof);
+        }
+        if self.num_suffix_elements > num_leaves {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: num_leaves,
+                actual: self.num_suffix_elements,
+            });
+        }
+        let first_suffix_index = num_leaves - self.num_suffix_elements;
+
+        // Build up the tree from suffix elements
+        let mut current_hashes: Vec<Digest> = suffix_elements
+            .iter()
+            .map(|e| hasher.hash_leaf(e))
+            .collect();
+        let mut proof_index = self.proof.len();
+        let mut level_start_index = first_suffix_index;
+
+        // Build tree level by level
+        while current_hashes.len() > 1 || level_start_index > 0 {
+            let mut next_level = Vec::new();
+            let mut i = 0;
+
+            // Check if we need a left sibling from proof
+            if level_start_index % 2 == 1 {
+                // Need left sibling from proof
+                if proof_index == 0 {
+                    return Err(VerificationError::NotEnoughProofElements);
+                }
+                proof_index -= 1;
+                let left_sibling = &self.proof[proof_index];
+                let right = &current_hashes[0];
+
+                // Hash them together - match tree construction order
+                next_level.push(hasher.hash_children(left_sibling, right));
+
+                i = 1;
+                level_start_index -= 1;
+            }
+
+            // Pair up remaining elements (note: tree uses reversed order)
+            while i < current_hashes.len() {
+                if i + 1 < current_hashes.len() {
+                    // Pair two elements - match tree construction order
+                    next_level
+                        .push(hasher.hash_children(&current_hashes[i], &current_hashes[i + 1]));
+                    i += 2;
+                } else {
+                    // Odd element, carry forward
+                    next_level.push(current_hashes[i]);
+                    i += 1;
+                }
+            }
+
+            current_hashes = next_level;
+            level_start_index /= 2;
+        }
+
+        if proof_index != 0 {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(current_hashes[0])
+    }
+}
+
+// A struct representing an inclusion proof of a single leaf in a Perfect Merkle Tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawInclusionProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct InclusionProof {
+    pub version: u8,
+    pub leaf_index: u64,
+    // Sibling hashes ordered from the leaf level up to the children of the root
+    pub siblings: Vec<Digest>,
+}
+
+// `InclusionProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawInclusionProof {
+    version: u8,
+    leaf_index: u64,
+    siblings: Vec<Digest>,
+}
+
+impl TryFrom<RawInclusionProof> for InclusionProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawInclusionProof) -> Result<Self, VerificationError> {
+        let proof = InclusionProof {
+            version: raw.version,
+            leaf_index: raw.leaf_index,
+            siblings: raw.siblings,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for InclusionProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawInclusionProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+impl InclusionProof {
+    pub fn new(leaf_index: u64, siblings: Vec<Digest>) -> Self {
+        InclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            leaf_index,
+            siblings,
+        }
+    }
+
+    /// Check the invariants that hold whatever the tree: at most one sibling per level, and a leaf
+    /// index within the tree of that height.
+    pub fn check_shape(&self) -> Result<(), VerificationError> {
+        if self.siblings.len() > MAX_HEIGHT {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        // No bits set at or above the height, which a height of 64 leaves no room for
+        if self
+            .leaf_index
+            .checked_shr(self.siblings.len() as u32)
+            .is_some_and(|above| above != 0)
+        {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.leaf_index));
+        }
+        Ok(())
+    }
+
+    /// Recompute the root digest from the leaf value and the sibling path.
+    pub fn compute_root(&self, leaf: &[u8]) -> Digest {
+        self.compute_root_with_hasher(&NodeHasher::UNKEYED, leaf)
+    }
+
+    /// Same as `compute_root`, for a tree hashed with `hasher`.
+    pub fn compute_root_with_hasher(&self, hasher: &NodeHasher, leaf: &[u8]) -> Digest {
+        self.compute_root_from_leaf_hash(hasher, &hasher.hash_leaf(leaf))
+    }
+
+    /// Same as `compute_root_with_hasher`, from the hash of the leaf instead of its value.
+    pub fn compute_root_from_leaf_hash(&self, hasher: &NodeHasher, leaf_hash: &Digest) -> Digest {
+        let mut current = *leaf_hash;
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            // The bit at `level` tells us whether we are the left (0) or right (1) child, and
+            // indices have no bits set past 64 levels
+            let bit = u32::try_from(level)
+                .ok()
+                .and_then(|level| self.leaf_index.checked_shr(level))
+                .unwrap_or(0);
+            current = if bit & 1 == 0 {
+                hasher.hash_children(&current, sibling)
+            } else {
+                hasher.hash_children(sibling, &current)
+            };
+        }
+        current
+    }
+
+    /// Verify that `leaf` is in the perfect tree with `num_leaves` (a power of two) leaves and the
+    /// given root, which is returned.
+    pub fn verify(
+        &self,
+        root: &Digest,
+        num_leaves: u64,
+        leaf: &[u8],
+    ) -> Result<Digest, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::UNKEYED, root, num_leaves, leaf)
+    }
+
+    /// Same as `verify`, for a tree hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        root: &Digest,
+        num_leaves: u64,
+        leaf: &[u8],
+    ) -> Result<Digest, VerificationError> {
+        check_tree_size(num_leaves)?;
+        check_version(self.version)?;
+        if self.leaf_index >= num_leaves {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.leaf_index));
+        }
+        check_path_length(num_leaves.trailing_zeros() as usize, self.siblings.len())?;
+        check_canonical(hasher, &self.siblings)?;
+        let computed = self.compute_root_with_hasher(hasher, leaf);
+        if computed != *root {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(computed)
+    }
+}
+
+/// Inclusion proof of several leaves of a perfect tree at once. Siblings shared by the paths of
+/// the leaves, and nodes computable from the leaves themselves, are only included once.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawMultiInclusionProof")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+pub struct MultiInclusionProof {
+    pub version: u8,
+    // Strictly increasing
+    pub leaf_indices: Vec<u64>,
+    // Missing nodes level by level from the leaves up, left to right within a level
+    pub siblings: Vec<Digest>,
+}
+
+// `MultiInclusionProof` as encoded, before its invariants are checked
+#[derive(Deserialize)]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshDeserialize))]
+struct RawMultiInclusionProof {
+    version: u8,
+    leaf_indices: Vec<u64>,
+    siblings: Vec<Digest>,
+}
+
+impl TryFrom<RawMultiInclusionProof> for MultiInclusionProof {
+    type Error = VerificationError;
+
+    fn try_from(raw: RawMultiInclusionProof) -> Result<Self, VerificationError> {
+        let proof = MultiInclusionProof {
+            version: raw.version,
+            leaf_indices: raw.leaf_indices,
+            siblings: raw.siblings,
+        };
+        proof.check_shape()?;
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for MultiInclusionProof {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        RawMultiInclusionProof::deserialize_reader(reader)?
+            .try_into()
+            .map_err(invalid_data)
+    }
+}
+
+impl MultiInclusionProof {
+    pub fn new(leaf_indices: Vec<u64>, siblings: Vec<Digest>) -> Self {
+        MultiInclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            leaf_indices,
+            siblings,
+        }
+    }
+
+    /// Check the invariants that hold whatever the tree: leaf indices strictly increase, and no
+    /// more siblings than the paths of the leaves could need.
+    pub fn check_shape(&self) -> Result<(), VerificationError> {
+        if self.leaf_indices.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        let max_siblings = self.leaf_indices.len().saturating_mul(MAX_HEIGHT);
+        if self.siblings.len() > max_siblings {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(())
+    }
+
+    /// Recompute the root of the perfect tree with `num_leaves` (a power of two) leaves from the
+    /// leaves at `leaf_indices`, given in the same order.
+    pub fn compute_root(
+        &self,
+        num_leaves: u64,
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        self.compute_root_with_hasher(&NodeHasher::UNKEYED, num_leaves, leaves)
+    }
+
+    /// Same as `compute_root`, for a tree hashed with `hasher`.
+    pub fn compute_root_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        num_leaves: u64,
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        check_tree_size(num_leaves)?;
+        check_version(self.version)?;
+        if leaves.len() != self.leaf_indices.len() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: self.leaf_indices.len() as u64,
+                actual: leaves.len() as u64,
+            });
+        }
+        if leaves.is_empty() {
+            return Err(VerificationError::EmptyProof);
+        }
+        if let Some(&index) = self.leaf_indices.iter().find(|&&i| i >= num_leaves) {
+            return Err(VerificationError::LeafIndexOutOfBounds(index));
+        }
+        if self.leaf_indices.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+
+        let mut level: Vec<(u64, Digest)> = self
+            .leaf_indices
+            .iter()
+            .zip(leaves)
+            .map(|(&index, leaf)| (index, hasher.hash_leaf(leaf)))
+            .collect();
+        let mut siblings = self.siblings.iter();
+        for _ in 0..num_leaves.trailing_zeros() {
+            let mut next_level = Vec::with_capacity(level.len());
+            let mut i = 0;
+            while i < level.len() {
+                let (index, hash) = level[i];
+                let parent = match level.get(i + 1) {
+                    // Both children are known
+                    Some((next, next_hash)) if index & 1 == 0 && *next == index + 1 => {
+                        i += 1;
+                        hasher.hash_children(&hash, next_hash)
+                    }
+                    _ => {
+                        let sibling = siblings
+                            .next()
+                            .ok_or(VerificationError::NotEnoughProofElements)?;
+                        if index & 1 == 0 {
+                            hasher.hash_children(&hash, sibling)
+                        } else {
+                            hasher.hash_children(sibling, &hash)
+                        }
+                    }
+                };
+                next_level.push((index / 2, parent));
+                i += 1;
+            }
+            level = next_level;
+        }
+        if siblings.next().is_some() {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(level[0].1)
+    }
+
+    /// Verify that `leaves` sit at `leaf_indices` of the perfect tree with `num_leaves` (a power of
+    /// two) leaves and the given root, which is returned.
+    pub fn verify(
+        &self,
+        root: &Digest,
+        num_leaves: u64,
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::UNKEYED, root, num_leaves, leaves)
+    }
+
+    /// Same as `verify`, for a tree hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        root: &Digest,
+        num_leaves: u64,
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        check_canonical(hasher, &self.siblings)?;
+        let computed = self.compute_root_with_hasher(hasher, num_leaves, leaves)?;
+        if computed != *root {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(computed)
+    }
+}
+
+// Digests from a proof must be ones the hasher can output, see `NodeHasher::is_canonical`
+pub(crate) fn check_canonical<'a>(
+    hasher: &NodeHasher,
+    digests: impl IntoIterator<Item = &'a Digest>,
+) -> Result<(), VerificationError> {
+    if !digests
+        .into_iter()
+        .all(|digest| hasher.is_canonical(digest))
+    {
+        return Err(VerificationError::NonCanonicalProof);
+    }
+    Ok(())
+}
+
+// Trees are perfect, so their number of leaves is a power of two
+pub(crate) fn check_tree_size(num_leaves: u64) -> Result<(), VerificationError> {
+    if !num_leaves.is_power_of_two() {
+        return Err(VerificationError::InvalidTreeSize(num_leaves));
+    }
+    Ok(())
+}
+
+// BCS lengths of the fields shared by proofs
+pub(crate) const VERSION_LENGTH: usize = 1;
+pub(crate) const USIZE_LENGTH: usize = 8;
+
+// Length of the BCS encoding of a list of entries
+pub(crate) fn entries_length(entries: &[Vec<u8>]) -> usize {
+    uleb128_length(entries.len())
+        + entries
+            .iter()
+            .map(|entry| bytes_length(entry.len()))
+            .sum::<usize>()
+}
+
+/// Checked against the root of a perfect tree alone, for the suffix of its leaves: the proof
+/// carries the tree size.
+impl Proof for SuffixProof {
+    type Commitment = Digest;
+    type Claim = [Vec<u8>];
+    type Output = Digest;
+    type Error = VerificationError;
+
+    fn verify(
+        &self,
+        root: &Digest,
+        suffix_elements: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        self.verify_standalone(root, suffix_elements)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH
+            + USIZE_LENGTH
+            + digests_length(self.proof.len())
+            + 1
+            + self.num_leaves.map_or(0, |_| USIZE_LENGTH)
+    }
+}
+
+/// Checked against the root and number of leaves of a perfect tree, for the proven leaf.
+impl Proof for InclusionProof {
+    type Commitment = (Digest, u64);
+    type Claim = [u8];
+    type Output = Digest;
+    type Error = VerificationError;
+
+    fn verify(
+        &self,
+        (root, num_leaves): &(Digest, u64),
+        leaf: &[u8],
+    ) -> Result<Digest, VerificationError> {
+        InclusionProof::verify(self, root, *num_leaves, leaf)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH + USIZE_LENGTH + digests_length(self.siblings.len())
+    }
+}
+
+/// Checked against the root and number of leaves of a perfect tree, for the proven leaves.
+impl Proof for MultiInclusionProof {
+    type Commitment = (Digest, u64);
+    type Claim = [Vec<u8>];
+    type Output = Digest;
+    type Error = VerificationError;
+
+    fn verify(
+        &self,
+        (root, num_leaves): &(Digest, u64),
+        leaves: &[Vec<u8>],
+    ) -> Result<Digest, VerificationError> {
+        MultiInclusionProof::verify(self, root, *num_leaves, leaves)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH
+            + uleb128_length(self.leaf_indices.len())
+            + self.leaf_indices.len() * USIZE_LENGTH
+            + digests_length(self.siblings.len())
+    }
+}
+
+// Root of the perfect tree over `leaves`, whose number must be a power of two
+pub(crate) fn tree_root(hasher: &NodeHasher, leaves: &[Vec<u8>]) -> Digest {
+    let mut level: Vec<Digest> = leaves.iter().map(|leaf| hasher.hash_leaf(leaf)).collect();
+    while level.len() > 1 {
+        level = level
+            .chunks_exact(2)
+            .map(|pair| hasher.hash_children(&pair[0], &pair[1]))
+            .collect();
+    }
+    level[0]
+}
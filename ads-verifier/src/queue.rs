@@ -0,0 +1,155 @@
+//! Proofs about a priority queue committed to as a sorted MMR.
+//!
+//! The elements of the queue are the entries of an MMR whose commitment records them as sorted
+//! (see `ordering`), so its minimum is entry 0, which an inclusion proof shows. Elements compare
+//! as bytes and must be distinct, e.g., a big-endian priority followed by a sequence number.
+//!
+//! Popping or pushing an element shifts the elements after it, so a `QueueTransitionProof` carries
+//! the leaf hash of every element before the operation. A verifier recomputes the old commitment
+//! from them, checks the operation against them, and recomputes the new commitment from the leaf
+//! hashes after it.
+
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{MmrCommitment, MmrInclusionProof};
+use crate::ordering::LeafOrdering;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// An operation on a priority queue, with the element it pops or pushes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum QueueOperation {
+    /// Remove the minimum
+    Pop(Vec<u8>),
+    /// Insert an element, which ends up at `position`
+    Push { element: Vec<u8>, position: u64 },
+}
+
+/// Proof that applying an operation to the queue committed to by one commitment gives the queue
+/// committed to by another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct QueueTransitionProof {
+    pub version: u8,
+    pub operation: QueueOperation,
+    // One per element before the operation
+    pub leaf_hashes: Vec<Digest>,
+    // For a push, the elements right before and after the pushed one, those that exist
+    pub neighbours: Vec<Vec<u8>>,
+}
+
+impl QueueTransitionProof {
+    pub fn new(
+        operation: QueueOperation,
+        leaf_hashes: Vec<Digest>,
+        neighbours: Vec<Vec<u8>>,
+    ) -> Self {
+        QueueTransitionProof {
+            version: PROOF_FORMAT_VERSION,
+            operation,
+            leaf_hashes,
+            neighbours,
+        }
+    }
+
+    /// Verify that the operation turns the queue committed to by `old` into that committed to by
+    /// `new`.
+    pub fn verify(
+        &self,
+        old: &MmrCommitment,
+        new: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_hasher(&NodeHasher::new(old.hash_algo()), old, new)
+    }
+
+    /// Same as `verify`, for queues hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        old: &MmrCommitment,
+        new: &MmrCommitment,
+    ) -> Result<(), VerificationError> {
+        check_version(self.version)?;
+        old.check_hash_algo(hasher, new.hash_algo())?;
+        // Only sorted entries put the minimum first
+        for commitment in [old, new] {
+            commitment.check_ordering(LeafOrdering::Sorted)?;
+        }
+        old.check_leaf_hashes(hasher, &self.leaf_hashes)?;
+
+        let mut leaf_hashes = self.leaf_hashes.clone();
+        match &self.operation {
+            QueueOperation::Pop(element) => {
+                if leaf_hashes.is_empty() {
+                    return Err(VerificationError::EmptyProof);
+                }
+                if leaf_hashes.remove(0) != hasher.hash_leaf(element) {
+                    return Err(VerificationError::NotMinimum);
+                }
+            }
+            QueueOperation::Push { element, position } => {
+                let position = *position;
+                if position > leaf_hashes.len() as u64 {
+                    return Err(VerificationError::LeafIndexOutOfBounds(position));
+                }
+                // The neighbours that exist, each checked against its leaf hash
+                let lower = position.checked_sub(1);
+                let upper = (position < leaf_hashes.len() as u64).then_some(position);
+                let indices: Vec<u64> = lower.into_iter().chain(upper).collect();
+                if indices.len() != self.neighbours.len() {
+                    return Err(VerificationError::WrongNumberOfEntries {
+                        expected: indices.len() as u64,
+                        actual: self.neighbours.len() as u64,
+                    });
+                }
+                for (&index, neighbour) in indices.iter().zip(&self.neighbours) {
+                    if hasher.hash_leaf(neighbour) != leaf_hashes[index as usize] {
+                        return Err(VerificationError::RootMismatch);
+                    }
+                    let sorted = if index < position {
+                        neighbour < element
+                    } else {
+                        element < neighbour
+                    };
+                    if !sorted {
+                        return Err(VerificationError::NotAbsent);
+                    }
+                }
+                leaf_hashes.insert(position as usize, hasher.hash_leaf(element));
+            }
+        }
+        new.check_leaf_hashes(hasher, &leaf_hashes)
+    }
+}
+
+impl MmrCommitment {
+    /// Verify that the entry `proof` proves is the minimum of the queue the commitment is to.
+    pub fn verify_min(&self, proof: &MmrInclusionProof) -> Result<(), VerificationError> {
+        self.verify_min_with_hasher(&NodeHasher::new(self.hash_algo()), proof)
+    }
+
+    /// Same as `verify_min`, for a queue hashed with `hasher`.
+    pub fn verify_min_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proof: &MmrInclusionProof,
+    ) -> Result<(), VerificationError> {
+        self.check_ordering(LeafOrdering::Sorted)?;
+        self.verify_inclusion_with_hasher(hasher, proof)?;
+        if self.entry_index(proof.tree_index, proof.proof.leaf_index) != Some(0) {
+            return Err(VerificationError::NotMinimum);
+        }
+        Ok(())
+    }
+}
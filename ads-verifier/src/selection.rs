@@ -0,0 +1,96 @@
+//! Provably random selection of an entry of a committed list.
+//!
+//! The seed, e.g., the output of a randomness beacon, selects the entry at the first index a
+//! `Sampler` draws from the commitment's digest followed by the seed, as for challenges (see
+//! `challenge`). Anyone holding the commitment and the seed recomputes the index, so a
+//! `SelectionProof`, the inclusion proof of the selected entry, shows both what was selected and
+//! that nothing else could have been. The selection is only unpredictable if the seed is unknown
+//! when the commitment is published.
+
+use serde::{Deserialize, Serialize};
+
+use ads_core::Proof;
+
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{MmrCommitment, MmrInclusionProof};
+use crate::proof::VERSION_LENGTH;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct SelectionProof {
+    pub version: u8,
+    pub selected: MmrInclusionProof,
+}
+
+impl SelectionProof {
+    pub fn new(selected: MmrInclusionProof) -> Self {
+        SelectionProof {
+            version: PROOF_FORMAT_VERSION,
+            selected,
+        }
+    }
+
+    /// The selected entry.
+    pub fn entry(&self) -> &[u8] {
+        &self.selected.entry
+    }
+
+    /// Verify that the proven entry is the one `seed` selects from the list committed to, and
+    /// return its index.
+    pub fn verify(
+        &self,
+        commitment: &MmrCommitment,
+        seed: &[u8],
+    ) -> Result<u64, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::new(commitment.hash_algo()), commitment, seed)
+    }
+
+    /// Same as `verify`, for an MMR hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        commitment: &MmrCommitment,
+        seed: &[u8],
+    ) -> Result<u64, VerificationError> {
+        check_version(self.version)?;
+        commitment.verify_inclusion_with_hasher(hasher, &self.selected)?;
+        let index =
+            commitment.entry_index(self.selected.tree_index, self.selected.proof.leaf_index);
+        match commitment.select_index(seed) {
+            Some(selected) if index == Some(selected) => Ok(selected),
+            _ => Err(VerificationError::NotSelected),
+        }
+    }
+}
+
+/// Checked against the commitment to the list, for the seed, returning the selected index.
+impl Proof for SelectionProof {
+    type Commitment = MmrCommitment;
+    type Claim = [u8];
+    type Output = u64;
+    type Error = VerificationError;
+
+    fn verify(&self, commitment: &MmrCommitment, seed: &[u8]) -> Result<u64, VerificationError> {
+        SelectionProof::verify(self, commitment, seed)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH + self.selected.size_hint()
+    }
+}
+
+impl MmrCommitment {
+    /// Index of the entry `seed` selects, None if there are no entries.
+    pub fn select_index(&self, seed: &[u8]) -> Option<u64> {
+        let num_entries = self.num_entries();
+        if num_entries == 0 {
+            return None;
+        }
+        Some(self.sampler(seed).next_index(num_entries))
+    }
+}
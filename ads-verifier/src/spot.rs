@@ -0,0 +1,200 @@
+//! Verifying a random sample of a large batch of proofs.
+//!
+//! Checking every proof of an enormous response, e.g., one inclusion proof per entry of a range,
+//! costs as much as the response is large. Checking `k` proofs drawn uniformly at random instead
+//! catches a batch where at least a fraction `t` of the proofs are invalid with probability at
+//! least `1 - (1 - t)^k`, whatever the size of the batch: 688 samples give 99.9% confidence of
+//! catching 1% of invalid proofs. The samples are drawn from an explicit seed, so that a check can
+//! be replayed, e.g., to settle a dispute with full verification of the same batch.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::fmt;
+
+use ads_core::Proof;
+
+use crate::hash::NodeHasher;
+
+/// Prefix of the messages hashed into samples, apart from leaves of any tree.
+pub const SAMPLE_TAG: &[u8] = b"ads/sample\0";
+
+/// A deterministic stream of pseudo-random indices from a seed: the `i`-th is drawn from the leaf
+/// hash of `SAMPLE_TAG`, then `i` as 8 little-endian bytes, then the seed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sampler {
+    hasher: NodeHasher,
+    seed: Vec<u8>,
+    counter: u64,
+}
+
+impl Sampler {
+    pub fn new(seed: &[u8]) -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED, seed)
+    }
+
+    /// A sampler hashing with `hasher`, which whoever replays the samples must use too.
+    pub fn new_with_hasher(hasher: NodeHasher, seed: &[u8]) -> Self {
+        Sampler {
+            hasher,
+            seed: seed.to_vec(),
+            counter: 0,
+        }
+    }
+
+    /// The next index below `bound`, which must be positive. The bias of reducing 64 bits modulo
+    /// `bound` is below `bound / 2^64`.
+    pub fn next_index(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "Can't sample from an empty range");
+        let mut message = Vec::with_capacity(SAMPLE_TAG.len() + 8 + self.seed.len());
+        message.extend_from_slice(SAMPLE_TAG);
+        message.extend_from_slice(&self.counter.to_le_bytes());
+        message.extend_from_slice(&self.seed);
+        self.counter += 1;
+        let digest = self.hasher.hash_leaf(&message);
+        let value = u64::from_le_bytes(digest.0[..8].try_into().unwrap());
+        value % bound
+    }
+}
+
+/// Verification of `samples` proofs of a batch drawn with replacement from `seed`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotCheck {
+    seed: Vec<u8>,
+    samples: u64,
+}
+
+impl SpotCheck {
+    pub fn new(seed: &[u8], samples: u64) -> Self {
+        SpotCheck {
+            seed: seed.to_vec(),
+            samples,
+        }
+    }
+
+    /// The smallest spot check catching a batch with at least a fraction `tolerance` of invalid
+    /// proofs with probability at least `confidence`. Both must be in (0, 1).
+    pub fn for_confidence(seed: &[u8], tolerance: f64, confidence: f64) -> Self {
+        assert!(
+            tolerance > 0.0 && tolerance < 1.0,
+            "Tolerance must be in (0, 1)"
+        );
+        assert!(
+            confidence > 0.0 && confidence < 1.0,
+            "Confidence must be in (0, 1)"
+        );
+        // The probability of a miss decreases with the samples: double then bisect
+        let caught = |samples| 1.0 - miss_probability(tolerance, samples) >= confidence;
+        let mut high = 1;
+        while !caught(high) {
+            high *= 2;
+        }
+        let mut low = high / 2;
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if caught(mid) {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Self::new(seed, high)
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// The positions of the batch the check verifies, for a batch of `len` proofs, in increasing
+    /// order. Every position if there are fewer proofs than samples.
+    pub fn positions(&self, len: u64) -> Vec<u64> {
+        if len <= self.samples {
+            return (0..len).collect();
+        }
+        let mut sampler = Sampler::new(&self.seed);
+        let positions: BTreeSet<u64> = (0..self.samples).map(|_| sampler.next_index(len)).collect();
+        positions.into_iter().collect()
+    }
+
+    /// Verify the sampled proofs of `proofs` against `commitment`.
+    pub fn check<P>(
+        &self,
+        proofs: &[P],
+        commitment: &P::Commitment,
+    ) -> Result<SpotCheckReport, SpotCheckError<P::Error>>
+    where
+        P: Proof<Claim = ()>,
+    {
+        let positions = self.positions(proofs.len() as u64);
+        for &position in &positions {
+            proofs[position as usize]
+                .verify(commitment, &())
+                .map_err(|error| SpotCheckError { position, error })?;
+        }
+        Ok(SpotCheckReport {
+            total: proofs.len() as u64,
+            checked: positions.len() as u64,
+            samples: self.samples,
+        })
+    }
+}
+
+/// What a passed spot check verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpotCheckReport {
+    /// Proofs in the batch
+    pub total: u64,
+    /// Distinct proofs verified
+    pub checked: u64,
+    /// Proofs drawn, some possibly more than once
+    pub samples: u64,
+}
+
+impl SpotCheckReport {
+    pub fn is_complete(&self) -> bool {
+        self.checked == self.total
+    }
+
+    /// Lower bound on the probability that the check would have failed, had at least a fraction
+    /// `tolerance` of the proofs been invalid. 1 if every proof was verified.
+    pub fn confidence(&self, tolerance: f64) -> f64 {
+        if self.is_complete() {
+            return 1.0;
+        }
+        1.0 - miss_probability(tolerance, self.samples)
+    }
+}
+
+/// An invalid proof found by a spot check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotCheckError<E> {
+    /// Position of the proof in the batch
+    pub position: u64,
+    pub error: E,
+}
+
+impl<E: fmt::Display> fmt::Display for SpotCheckError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Proof {} fails the spot check: {}",
+            self.position, self.error
+        )
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> core::error::Error for SpotCheckError<E> {}
+
+// (1 - tolerance)^samples, by squaring since floats have no `powi` without std
+fn miss_probability(tolerance: f64, samples: u64) -> f64 {
+    let mut base = 1.0 - tolerance;
+    let mut exponent = samples;
+    let mut result = 1.0;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result *= base;
+        }
+        base *= base;
+        exponent >>= 1;
+    }
+    result
+}
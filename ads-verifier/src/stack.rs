@@ -0,0 +1,71 @@
+//! Proofs that a value was popped from a stack committed to as an MMR.
+//!
+//! Pushing appends to the MMR, so the top of the stack is its last entry, and the commitment after
+//! a push follows from the one before it (see `Frontier::append`). Popping the last entry splits
+//! the smallest tree into the trees left of the entry, whose roots are exactly the siblings on the
+//! entry's path. The inclusion proof of the top therefore also yields the commitment after the
+//! pop, in time logarithmic in the size of the stack.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{MmrCommitment, MmrInclusionProof};
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// Proof that a value was the top of a stack: the inclusion proof of the last entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct PopProof {
+    pub version: u8,
+    pub top: MmrInclusionProof,
+}
+
+impl PopProof {
+    pub fn new(top: MmrInclusionProof) -> Self {
+        PopProof {
+            version: PROOF_FORMAT_VERSION,
+            top,
+        }
+    }
+
+    /// The popped value.
+    pub fn value(&self) -> &[u8] {
+        &self.top.entry
+    }
+}
+
+impl MmrCommitment {
+    /// Verify that the proof pops the top of the stack and return the commitment after the pop.
+    pub fn verify_pop(&self, proof: &PopProof) -> Result<MmrCommitment, VerificationError> {
+        self.verify_pop_with_hasher(&NodeHasher::new(self.hash_algo()), proof)
+    }
+
+    /// Same as `verify_pop`, for a stack hashed with `hasher`.
+    pub fn verify_pop_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proof: &PopProof,
+    ) -> Result<MmrCommitment, VerificationError> {
+        check_version(proof.version)?;
+        self.verify_inclusion_with_hasher(hasher, &proof.top)?;
+        let top = &proof.top;
+        if self.entry_index(top.tree_index, top.proof.leaf_index) != self.num_entries.checked_sub(1)
+        {
+            return Err(VerificationError::NotTop);
+        }
+
+        // The last entry is in the smallest tree, whose peak is the last one. Its siblings are the
+        // peaks of the trees left of it, from the smallest.
+        let mut peaks = self.peaks[..self.peaks.len() - 1].to_vec();
+        peaks.extend(top.proof.siblings.iter().rev());
+        let popped = MmrCommitment::new(self.num_entries - 1, peaks)
+            .expect("One peak per tree")
+            .with_hash_algo(self.hash_algo())
+            .with_ordering(self.ordering());
+        Ok(popped)
+    }
+}
@@ -0,0 +1,376 @@
+//! The conventional MMR layout used by Grin, ckb and most other MMR libraries.
+//!
+//! These implementations store all nodes of all trees in one array in post-order, so that a node
+//! is addressed by a single (0-based) position, and the size of the MMR is the number of nodes.
+//! Peaks are ordered from the left (largest, oldest tree) to the right.
+//!
+//! Proofs follow the ckb-merkle-mountain-range layout: the peaks left of the leaf's peak, then the
+//! path from the leaf to its peak, then the peaks right of it bagged into a single item. The root
+//! bags peaks from right to left as `hash_children(right, left)`.
+//!
+//! Peaks are bagged with the same hasher as the nodes below them, so the `_with_hasher` variants
+//! take the `NodeHasher` of a keyed or non-Blake2b MMR.
+//!
+//! Other implementations bag differently, so a `PeakBagging` selects the order and whether the
+//! size of the MMR is committed to. Folding from the left, it's the peaks left of the leaf's peak
+//! that proofs bag into a single item, and those right of it that are carried one by one.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::hash::NodeHasher;
+use crate::proof::InclusionProof;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// Number of nodes in a perfect tree of the given height.
+pub fn tree_size(height: u32) -> u64 {
+    (1 << (height + 1)) - 1
+}
+
+/// Number of nodes in an MMR with `num_leaves` leaves.
+pub fn mmr_size_from_leaf_count(num_leaves: u64) -> u64 {
+    2 * num_leaves - num_leaves.count_ones() as u64
+}
+
+/// Inverse of `mmr_size_from_leaf_count`. Returns None if `mmr_size` is not a valid MMR size.
+pub fn leaf_count_from_mmr_size(mmr_size: u64) -> Option<u64> {
+    let mut remaining = mmr_size;
+    let mut num_leaves = 0;
+    for height in (0..63).rev() {
+        if remaining >= tree_size(height) {
+            remaining -= tree_size(height);
+            num_leaves += 1 << height;
+        }
+    }
+    if remaining == 0 {
+        Some(num_leaves)
+    } else {
+        None
+    }
+}
+
+/// Position of the leaf with the given index.
+pub fn leaf_index_to_pos(index: u64) -> u64 {
+    mmr_size_from_leaf_count(index + 1) - (index + 1).trailing_zeros() as u64 - 1
+}
+
+/// Inverse of `leaf_index_to_pos`. Returns None if `pos` is an internal node.
+pub fn pos_to_leaf_index(pos: u64) -> Option<u64> {
+    // All nodes preceding a leaf form a complete MMR, whose leaves are exactly the earlier leaves
+    let index = leaf_count_from_mmr_size(pos)?;
+    (leaf_index_to_pos(index) == pos).then_some(index)
+}
+
+/// Positions of the peaks of an MMR with `num_leaves` leaves, from left to right.
+pub fn peak_positions(num_leaves: u64) -> Vec<u64> {
+    let mut peaks = vec![];
+    let mut offset = 0;
+    for height in (0..63).rev() {
+        if num_leaves & (1 << height) != 0 {
+            offset += tree_size(height);
+            peaks.push(offset - 1);
+        }
+    }
+    peaks
+}
+
+/// Returns (peak index from the left, index of the first leaf under that peak, height of the
+/// peak) for the peak containing `pos` in an MMR with `num_leaves` leaves.
+pub fn locate_pos(pos: u64, num_leaves: u64) -> Option<(usize, u64, u32)> {
+    let mut start = 0;
+    let mut first_leaf = 0;
+    let mut peak_index = 0;
+    for height in (0..63).rev() {
+        if num_leaves & (1 << height) == 0 {
+            continue;
+        }
+        if pos < start + tree_size(height) {
+            return Some((peak_index, first_leaf, height));
+        }
+        start += tree_size(height);
+        first_leaf += 1 << height;
+        peak_index += 1;
+    }
+    None
+}
+
+/// Height of the node at `pos`, 0 for leaves.
+pub fn pos_height(pos: u64) -> u32 {
+    // Positions are 1-based here: the peak of a tree of height h starting at 1 is 2^(h+1) - 1, all
+    // ones in binary. Any other node is moved into the leftmost tree by subtracting the size of
+    // the trees left of it, which keeps its height
+    let mut pos = pos + 1;
+    while !(pos + 1).is_power_of_two() {
+        let top = 1 << (u64::BITS - 1 - pos.leading_zeros());
+        pos -= top - 1;
+    }
+    u64::BITS - pos.leading_zeros() - 1
+}
+
+/// Position of the parent of the node at `pos`. The parent may not exist yet if `pos` is a peak.
+pub fn parent_pos(pos: u64) -> u64 {
+    let height = pos_height(pos);
+    if pos_height(pos + 1) > height {
+        // Right child, directly followed by its parent
+        pos + 1
+    } else {
+        pos + tree_size(height) + 1
+    }
+}
+
+/// Position of the other child of the parent of the node at `pos`.
+pub fn sibling_pos(pos: u64) -> u64 {
+    let height = pos_height(pos);
+    if pos_height(pos + 1) > height {
+        pos - tree_size(height)
+    } else {
+        pos + tree_size(height)
+    }
+}
+
+/// Positions of the (left, right) children of the node at `pos`. None for leaves.
+pub fn children_pos(pos: u64) -> Option<(u64, u64)> {
+    let height = pos_height(pos);
+    if height == 0 {
+        return None;
+    }
+    let right = pos - 1;
+    Some((right - tree_size(height - 1), right))
+}
+
+/// Bag peaks (ordered left to right) into a single root, folding from the right.
+pub fn bag_peaks(peaks: &[Digest]) -> Option<Digest> {
+    bag_peaks_with_hasher(&NodeHasher::UNKEYED, peaks)
+}
+
+/// Same as `bag_peaks`, for peaks hashed with `hasher`.
+pub fn bag_peaks_with_hasher(hasher: &NodeHasher, peaks: &[Digest]) -> Option<Digest> {
+    let mut peaks = peaks.to_vec();
+    while peaks.len() > 1 {
+        let right = peaks.pop().unwrap();
+        let left = peaks.pop().unwrap();
+        peaks.push(hasher.hash_children(&right, &left));
+    }
+    peaks.pop()
+}
+
+/// The end peaks are folded from when bagged into a single root.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum BaggingOrder {
+    /// From the rightmost peak, as `hash_children(right, left)`, as ckb does
+    #[default]
+    RightToLeft,
+    /// From the leftmost peak, as `hash_children(left, right)`
+    LeftToRight,
+}
+
+/// How peaks are bagged into the root of an MMR, to match the convention of a given deployment.
+/// The default is `bag_peaks`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct PeakBagging {
+    pub order: BaggingOrder,
+    /// Whether the root is `hash_leaf` of the MMR size (8 bytes, little-endian) followed by the
+    /// bagged peaks, rather than the bagged peaks themselves
+    pub with_size: bool,
+}
+
+impl PeakBagging {
+    /// The bagging of `bag_peaks` and ckb-merkle-mountain-range.
+    pub const CKB: PeakBagging = PeakBagging {
+        order: BaggingOrder::RightToLeft,
+        with_size: false,
+    };
+
+    pub const fn new(order: BaggingOrder, with_size: bool) -> Self {
+        PeakBagging { order, with_size }
+    }
+
+    /// The root of an MMR of `mmr_size` nodes with the given peaks, ordered left to right. None
+    /// for an empty MMR.
+    pub fn bag(&self, peaks: &[Digest], mmr_size: u64) -> Option<Digest> {
+        self.bag_with_hasher(&NodeHasher::UNKEYED, peaks, mmr_size)
+    }
+
+    /// Same as `bag`, for peaks hashed with `hasher`, which also hashes the size.
+    pub fn bag_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        peaks: &[Digest],
+        mmr_size: u64,
+    ) -> Option<Digest> {
+        let bagged = self.fold(hasher, peaks)?;
+        if !self.with_size {
+            return Some(bagged);
+        }
+        let mut value = [0; 8 + DIGEST_LENGTH];
+        value[..8].copy_from_slice(&mmr_size.to_le_bytes());
+        value[8..].copy_from_slice(bagged.as_bytes());
+        Some(hasher.hash_leaf(&value))
+    }
+
+    /// The items of a proof that the peak at `peak_index` has the path `siblings` below it: the
+    /// peaks on the side folded first bagged into one item, the others one by one.
+    pub fn proof_items(
+        &self,
+        peaks: &[Digest],
+        peak_index: usize,
+        siblings: Vec<Digest>,
+    ) -> Vec<Digest> {
+        self.proof_items_with_hasher(&NodeHasher::UNKEYED, peaks, peak_index, siblings)
+    }
+
+    /// Same as `proof_items`, for peaks hashed with `hasher`.
+    pub fn proof_items_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        peaks: &[Digest],
+        peak_index: usize,
+        siblings: Vec<Digest>,
+    ) -> Vec<Digest> {
+        let (left, right) = (&peaks[..peak_index], &peaks[peak_index + 1..]);
+        let mut items = vec![];
+        match self.order {
+            BaggingOrder::RightToLeft => {
+                items.extend_from_slice(left);
+                items.extend(siblings);
+                items.extend(self.fold(hasher, right));
+            }
+            BaggingOrder::LeftToRight => {
+                items.extend(self.fold(hasher, left));
+                items.extend(siblings);
+                items.extend_from_slice(right);
+            }
+        }
+        items
+    }
+
+    // The peaks folded in order, without the size
+    fn fold(&self, hasher: &NodeHasher, peaks: &[Digest]) -> Option<Digest> {
+        match self.order {
+            BaggingOrder::RightToLeft => bag_peaks_with_hasher(hasher, peaks),
+            BaggingOrder::LeftToRight => peaks
+                .iter()
+                .copied()
+                .reduce(|left, right| hasher.hash_children(&left, &right)),
+        }
+    }
+}
+
+/// A proof in the conventional (ckb-style) MMR layout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct StandardMerkleProof {
+    pub version: u8,
+    pub mmr_size: u64,
+    pub proof_items: Vec<Digest>,
+}
+
+impl StandardMerkleProof {
+    pub fn new(mmr_size: u64, proof_items: Vec<Digest>) -> Self {
+        StandardMerkleProof {
+            version: PROOF_FORMAT_VERSION,
+            mmr_size,
+            proof_items,
+        }
+    }
+
+    /// Verify that `leaf` sits at position `pos` of the MMR committed to by `root`. Proofs of an
+    /// unsupported version don't verify either.
+    pub fn verify(&self, root: &Digest, pos: u64, leaf: &[u8]) -> bool {
+        self.compute_root(pos, leaf).as_ref() == Some(root)
+    }
+
+    /// Same as `verify`, for a root bagged with `bagging`.
+    pub fn verify_with_bagging(
+        &self,
+        root: &Digest,
+        pos: u64,
+        leaf: &[u8],
+        bagging: &PeakBagging,
+    ) -> bool {
+        self.compute_root_with_bagging(pos, leaf, bagging).as_ref() == Some(root)
+    }
+
+    /// Same as `verify_with_bagging`, for an MMR hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        root: &Digest,
+        pos: u64,
+        leaf: &[u8],
+        bagging: &PeakBagging,
+    ) -> bool {
+        self.compute_root_with_hasher(hasher, pos, leaf, bagging)
+            .as_ref()
+            == Some(root)
+    }
+
+    /// Recompute the bagged root of the MMR from `leaf` at position `pos`, None if the proof is
+    /// malformed or of an unsupported version.
+    pub fn compute_root(&self, pos: u64, leaf: &[u8]) -> Option<Digest> {
+        self.compute_root_with_bagging(pos, leaf, &PeakBagging::CKB)
+    }
+
+    /// Same as `compute_root`, for a proof laid out and a root bagged as `bagging` does.
+    pub fn compute_root_with_bagging(
+        &self,
+        pos: u64,
+        leaf: &[u8],
+        bagging: &PeakBagging,
+    ) -> Option<Digest> {
+        self.compute_root_with_hasher(&NodeHasher::UNKEYED, pos, leaf, bagging)
+    }
+
+    /// Same as `compute_root_with_bagging`, for an MMR hashed with `hasher`.
+    pub fn compute_root_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        pos: u64,
+        leaf: &[u8],
+        bagging: &PeakBagging,
+    ) -> Option<Digest> {
+        check_version(self.version).ok()?;
+        let num_leaves = leaf_count_from_mmr_size(self.mmr_size)?;
+        if pos >= self.mmr_size {
+            return None;
+        }
+        let leaf_index = pos_to_leaf_index(pos)?;
+        let (peak_index, first_leaf, height) = locate_pos(pos, num_leaves)?;
+        let num_peaks = num_leaves.count_ones() as usize;
+
+        // The peaks on the side folded first are either absent or bagged into one item
+        let (num_left, num_right) = match bagging.order {
+            BaggingOrder::RightToLeft => (peak_index, usize::from(peak_index + 1 < num_peaks)),
+            BaggingOrder::LeftToRight => (usize::from(peak_index > 0), num_peaks - peak_index - 1),
+        };
+        let mut items = self.proof_items.iter();
+        let mut peaks: Vec<Digest> = items.by_ref().take(num_left).copied().collect();
+        let siblings: Vec<Digest> = items.by_ref().take(height as usize).copied().collect();
+        if peaks.len() != num_left || siblings.len() != height as usize {
+            return None;
+        }
+        let path = InclusionProof::new(leaf_index - first_leaf, siblings);
+        peaks.push(path.compute_root_with_hasher(hasher, leaf));
+
+        let rest: Vec<Digest> = items.copied().collect();
+        if rest.len() != num_right {
+            return None;
+        }
+        peaks.extend(rest);
+        bagging.bag_with_hasher(hasher, &peaks, self.mmr_size)
+    }
+}
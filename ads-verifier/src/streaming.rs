@@ -0,0 +1,313 @@
+//! Verifying proofs of the most recent entries as the entries stream by.
+//!
+//! `SuffixProof::verify` hashes all of its entries before combining them, so checking a range of
+//! millions of entries needs millions of digests in memory. Here the entries are hashed in one at a
+//! time instead: the subtrees they complete are merged as soon as both children are known, so at
+//! most one digest per level is kept. The left siblings from the proof are only needed once the
+//! last entry is in, which matches the BCS layout of `MostRecentNElementsProof`, where they come
+//! after the entries.
+
+use alloc::vec::Vec;
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::MmrCommitment;
+use crate::proof::{check_canonical, SuffixProof};
+use crate::version::check_version;
+
+/// The root of a perfect tree, hashed from its last leaves as they're pushed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuffixAccumulator {
+    num_leaves: u64,
+    first_index: u64,
+    next_index: u64,
+    // Subtrees completed within the suffix, with their heights, leftmost (lowest) first
+    frontier: Vec<(u64, Digest)>,
+    hasher: NodeHasher,
+}
+
+impl SuffixAccumulator {
+    /// An accumulator of the last `num_suffix_elements` leaves of the perfect tree with
+    /// `num_leaves` (a power of two) leaves.
+    pub fn new(num_leaves: u64, num_suffix_elements: u64) -> Result<Self, VerificationError> {
+        Self::new_with_hasher(NodeHasher::UNKEYED, num_leaves, num_suffix_elements)
+    }
+
+    /// Same as `new`, for a tree hashed with `hasher`.
+    pub fn new_with_hasher(
+        hasher: NodeHasher,
+        num_leaves: u64,
+        num_suffix_elements: u64,
+    ) -> Result<Self, VerificationError> {
+        if !num_leaves.is_power_of_two() {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        if num_suffix_elements == 0 {
+            return Err(VerificationError::EmptyProof);
+        }
+        if num_suffix_elements > num_leaves {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: num_leaves,
+                actual: num_suffix_elements,
+            });
+        }
+        let first_index = num_leaves - num_suffix_elements;
+        Ok(SuffixAccumulator {
+            num_leaves,
+            first_index,
+            next_index: first_index,
+            frontier: Vec::new(),
+            hasher,
+        })
+    }
+
+    /// Number of leaves pushed so far.
+    pub fn num_pushed(&self) -> u64 {
+        self.next_index - self.first_index
+    }
+
+    /// Whether all the leaves of the suffix were pushed.
+    pub fn is_complete(&self) -> bool {
+        self.next_index == self.num_leaves
+    }
+
+    /// Hash in the next leaf.
+    pub fn push(&mut self, entry: &[u8]) -> Result<(), VerificationError> {
+        let leaf_hash = self.hasher.hash_leaf(entry);
+        self.push_leaf_hash(leaf_hash)
+    }
+
+    /// Same as `push`, for a leaf already hashed, e.g., with `LeafHasher` for a large entry.
+    pub fn push_leaf_hash(&mut self, leaf_hash: Digest) -> Result<(), VerificationError> {
+        if self.is_complete() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: self.num_leaves - self.first_index,
+                actual: self.num_pushed() + 1,
+            });
+        }
+        let (mut height, mut current) = (0, leaf_hash);
+        let mut index = self.next_index;
+        // A right child whose left sibling is within the suffix completes their parent
+        while index & 1 == 1 {
+            match self.frontier.last() {
+                Some(&(left_height, left)) if left_height == height => {
+                    self.frontier.pop();
+                    current = self.hasher.hash_children(&left, &current);
+                    height += 1;
+                    index >>= 1;
+                }
+                _ => break,
+            }
+        }
+        self.frontier.push((height, current));
+        self.next_index += 1;
+        Ok(())
+    }
+
+    /// The root of the tree, from the left siblings of the suffix as in `SuffixProof::proof`.
+    pub fn finalize(self, proof: &[Digest]) -> Result<Digest, VerificationError> {
+        if !self.is_complete() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: self.num_leaves - self.first_index,
+                actual: self.num_pushed(),
+            });
+        }
+        check_canonical(&self.hasher, proof)?;
+        let tree_height = self.num_leaves.trailing_zeros() as u64;
+        let mut subtrees = self.frontier.into_iter();
+        let (mut height, mut current) = subtrees.next().expect("The suffix is never empty");
+        let mut index = self.first_index >> height;
+        // The proof holds left siblings from the root down
+        let mut siblings = proof.iter().rev();
+        while height < tree_height {
+            current = if index & 1 == 1 {
+                let left = siblings
+                    .next()
+                    .ok_or(VerificationError::NotEnoughProofElements)?;
+                self.hasher.hash_children(left, &current)
+            } else {
+                // Every subtree was merged with its right sibling, so the next one is it
+                let (_, right) = subtrees.next().expect("The suffix runs to the last leaf");
+                self.hasher.hash_children(&current, &right)
+            };
+            height += 1;
+            index >>= 1;
+        }
+        if siblings.next().is_some() {
+            return Err(VerificationError::UnusedProofElements);
+        }
+        Ok(current)
+    }
+}
+
+/// Checks a proof of the most recent entries of an MMR, with the entries pushed one at a time,
+/// oldest first.
+///
+/// Each full tree is checked as soon as its last entry is in, and the partial tree, which holds
+/// the oldest entries, once its left siblings are given to `finish`.
+#[derive(Debug, Clone)]
+pub struct MostRecentVerifier<'a> {
+    commitment: &'a MmrCommitment,
+    hasher: NodeHasher,
+    full_tree_indices: Vec<u64>,
+    partial_tree_index: Option<u64>,
+    // The trees still to be hashed, with how many of their entries are proven, most recent first
+    pending: Vec<(u64, u64)>,
+    current: Option<(u64, SuffixAccumulator)>,
+    // The partial tree, once all its entries were pushed
+    partial: Option<(u64, SuffixAccumulator)>,
+    num_entries: u64,
+    num_pushed: u64,
+}
+
+impl<'a> MostRecentVerifier<'a> {
+    /// A verifier of the `num_entries` most recent entries of the MMR committed to.
+    pub fn new(commitment: &'a MmrCommitment, num_entries: u64) -> Result<Self, VerificationError> {
+        Self::new_with_hasher(
+            commitment,
+            NodeHasher::new(commitment.hash_algo()),
+            num_entries,
+        )
+    }
+
+    /// Same as `new`, for an MMR hashed with `hasher`.
+    pub fn new_with_hasher(
+        commitment: &'a MmrCommitment,
+        hasher: NodeHasher,
+        num_entries: u64,
+    ) -> Result<Self, VerificationError> {
+        check_version(commitment.version)?;
+        commitment.check_hash_algo(&hasher, commitment.hash_algo())?;
+        if num_entries == 0 {
+            return Err(VerificationError::EmptyProof);
+        }
+        let (full_tree_indices, partial) = commitment.most_recent_shape(num_entries)?;
+        // Full trees come smallest (most recent) first, and are popped from the end
+        let mut pending: Vec<(u64, u64)> = full_tree_indices
+            .iter()
+            .map(|&tree_index| (tree_index, 1 << tree_index))
+            .collect();
+        pending.extend(partial);
+        Ok(MostRecentVerifier {
+            commitment,
+            hasher,
+            full_tree_indices,
+            partial_tree_index: partial.map(|(tree_index, _)| tree_index),
+            pending,
+            current: None,
+            partial: None,
+            num_entries,
+            num_pushed: 0,
+        })
+    }
+
+    /// The full trees the proven entries span, smallest (most recent) first, as in
+    /// `MostRecentNElementsProof::full_tree_indices`.
+    pub fn full_tree_indices(&self) -> &[u64] {
+        &self.full_tree_indices
+    }
+
+    /// The tree holding the oldest entries when they don't span whole trees, which `finish` needs
+    /// the suffix proof of.
+    pub fn partial_tree_index(&self) -> Option<u64> {
+        self.partial_tree_index
+    }
+
+    /// The number of entries to push.
+    pub fn num_entries(&self) -> u64 {
+        self.num_entries
+    }
+
+    /// The number of entries pushed so far.
+    pub fn num_pushed(&self) -> u64 {
+        self.num_pushed
+    }
+
+    /// Hash in the next entry.
+    pub fn push(&mut self, entry: &[u8]) -> Result<(), VerificationError> {
+        let leaf_hash = self.hasher.hash_leaf(entry);
+        self.push_leaf_hash(leaf_hash)
+    }
+
+    /// Same as `push`, for an entry already hashed as a leaf.
+    pub fn push_leaf_hash(&mut self, leaf_hash: Digest) -> Result<(), VerificationError> {
+        if self.current.is_none() {
+            let (tree_index, num_suffix_elements) =
+                self.pending
+                    .pop()
+                    .ok_or(VerificationError::WrongNumberOfEntries {
+                        expected: self.num_entries,
+                        actual: self.num_pushed + 1,
+                    })?;
+            let accumulator = SuffixAccumulator::new_with_hasher(
+                self.hasher,
+                1 << tree_index,
+                num_suffix_elements,
+            )?;
+            self.current = Some((tree_index, accumulator));
+        }
+        let (tree_index, accumulator) = self.current.as_mut().unwrap();
+        accumulator.push_leaf_hash(leaf_hash)?;
+        self.num_pushed += 1;
+        if accumulator.is_complete() {
+            let tree_index = *tree_index;
+            let accumulator = self.current.take().unwrap().1;
+            if accumulator.num_pushed() < 1 << tree_index {
+                self.partial = Some((tree_index, accumulator));
+            } else if accumulator.finalize(&[])? != *self.commitment.digest_at(tree_index)? {
+                return Err(VerificationError::RootMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the partial tree against `partial_tree_proof`, which must be given exactly when the
+    /// entries don't span whole trees, once all entries were pushed.
+    pub fn finish(self, partial_tree_proof: Option<&SuffixProof>) -> Result<(), VerificationError> {
+        if self.num_pushed != self.num_entries {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: self.num_entries,
+                actual: self.num_pushed,
+            });
+        }
+        match (self.partial, partial_tree_proof) {
+            (None, None) => Ok(()),
+            (Some((tree_index, accumulator)), Some(proof)) => {
+                check_version(proof.version)?;
+                if proof.num_suffix_elements != accumulator.num_pushed()
+                    || proof.num_leaves.is_some_and(|n| n != 1 << tree_index)
+                {
+                    return Err(VerificationError::NonCanonicalProof);
+                }
+                if accumulator.finalize(&proof.proof)? != *self.commitment.digest_at(tree_index)? {
+                    return Err(VerificationError::RootMismatch);
+                }
+                Ok(())
+            }
+            _ => Err(VerificationError::NonCanonicalProof),
+        }
+    }
+}
+
+impl MmrCommitment {
+    /// Verify that `entries` are the most recent `num_entries` entries of this MMR, hashing them in
+    /// one at a time as the iterator yields them. `partial_tree_proof` is the suffix proof of
+    /// the tree holding the oldest of them, if they don't span whole trees.
+    pub fn verify_most_recent_streaming<I>(
+        &self,
+        num_entries: u64,
+        entries: I,
+        partial_tree_proof: Option<&SuffixProof>,
+    ) -> Result<(), VerificationError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut verifier = MostRecentVerifier::new(self, num_entries)?;
+        for entry in entries {
+            verifier.push(entry.as_ref())?;
+        }
+        verifier.finish(partial_tree_proof)
+    }
+}
@@ -0,0 +1,180 @@
+//! Entries carrying a timestamp, and proofs of every entry within a time range.
+//!
+//! A timestamped entry is committed as its timestamp (8 bytes, little-endian) followed by its
+//! payload, and timestamps never decrease along the log. Entries within [t1, t2] are therefore
+//! contiguous, and a range proof proves them together with the entry right before and right after
+//! them (when they exist): the boundary entries fall outside the range, which shows that nothing
+//! in it was omitted.
+
+use alloc::vec::Vec;
+
+use ads_core::Proof;
+use serde::{Deserialize, Serialize};
+
+use crate::error::VerificationError;
+use crate::mmr::{MmrCommitment, MmrMultiInclusionProof};
+use crate::proof::VERSION_LENGTH;
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+const TIMESTAMP_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct TimestampedEntry {
+    pub timestamp: u64,
+    pub payload: Vec<u8>,
+}
+
+impl TimestampedEntry {
+    pub fn new(timestamp: u64, payload: Vec<u8>) -> Self {
+        TimestampedEntry { timestamp, payload }
+    }
+
+    /// The bytes committed in the log.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(TIMESTAMP_LENGTH + self.payload.len());
+        encoded.extend_from_slice(&self.timestamp.to_le_bytes());
+        encoded.extend_from_slice(&self.payload);
+        encoded
+    }
+
+    /// Inverse of `encode`. Returns None if `bytes` is too short to hold a timestamp.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < TIMESTAMP_LENGTH {
+            return None;
+        }
+        let (timestamp, payload) = bytes.split_at(TIMESTAMP_LENGTH);
+        Some(TimestampedEntry {
+            timestamp: u64::from_le_bytes(timestamp.try_into().unwrap()),
+            payload: payload.to_vec(),
+        })
+    }
+}
+
+/// Proof of all entries with a timestamp in [start, end], inclusive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct TimeRangeProof {
+    pub version: u8,
+    pub start: u64,
+    pub end: u64,
+    // The entries in range plus the boundary entries, None only for an empty log
+    pub proof: Option<MmrMultiInclusionProof>,
+}
+
+impl TimeRangeProof {
+    pub fn new(start: u64, end: u64, proof: Option<MmrMultiInclusionProof>) -> Self {
+        TimeRangeProof {
+            version: PROOF_FORMAT_VERSION,
+            start,
+            end,
+            proof,
+        }
+    }
+}
+
+impl MmrCommitment {
+    /// Verify that the proof holds every entry of the log with a timestamp in [start, end], and
+    /// return those entries in log order.
+    pub fn verify_time_range(
+        &self,
+        proof: &TimeRangeProof,
+    ) -> Result<Vec<TimestampedEntry>, VerificationError> {
+        check_version(proof.version)?;
+        let num_entries = self.num_entries();
+        let Some(inclusion) = &proof.proof else {
+            // Only an empty log has no entries to bound the range with
+            return if num_entries == 0 {
+                check_version(self.version)?;
+                Ok(Vec::new())
+            } else {
+                Err(VerificationError::IncompleteRange)
+            };
+        };
+        self.verify_multi_inclusion(inclusion)?;
+
+        // The proven entries must be consecutive in the log
+        let mut indices = inclusion
+            .tree_proofs
+            .iter()
+            .flat_map(|(tree_index, tree_proof)| {
+                tree_proof
+                    .leaf_indices
+                    .iter()
+                    .map(|&leaf_index| self.entry_index(*tree_index, leaf_index))
+            });
+        let first = indices.next().flatten().unwrap();
+        let mut last = first;
+        for index in indices {
+            if index != Some(last + 1) {
+                return Err(VerificationError::IncompleteRange);
+            }
+            last += 1;
+        }
+
+        let entries = inclusion
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, bytes)| {
+                TimestampedEntry::decode(bytes)
+                    .ok_or(VerificationError::MalformedEntry(first + i as u64))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Timestamps going backwards
+        if let Some(i) = entries
+            .windows(2)
+            .position(|pair| pair[0].timestamp > pair[1].timestamp)
+        {
+            return Err(VerificationError::MalformedEntry(first + i as u64 + 1));
+        }
+
+        // Nothing before or after the proven entries can be in range
+        let complete_before = first == 0 || entries[0].timestamp < proof.start;
+        let complete_after =
+            last == num_entries - 1 || entries[entries.len() - 1].timestamp > proof.end;
+        if !complete_before || !complete_after {
+            return Err(VerificationError::IncompleteRange);
+        }
+        // Only the first entry may precede the range and only the last one may follow it
+        if entries[1..]
+            .iter()
+            .any(|entry| entry.timestamp < proof.start)
+            || entries[..entries.len() - 1]
+                .iter()
+                .any(|entry| entry.timestamp > proof.end)
+        {
+            return Err(VerificationError::NonCanonicalProof);
+        }
+        Ok(entries
+            .into_iter()
+            .filter(|entry| (proof.start..=proof.end).contains(&entry.timestamp))
+            .collect())
+    }
+}
+
+/// Verification yields the entries in range, see `MmrCommitment::verify_time_range`.
+impl Proof for TimeRangeProof {
+    type Commitment = MmrCommitment;
+    type Claim = ();
+    type Output = Vec<TimestampedEntry>;
+    type Error = VerificationError;
+
+    fn verify(
+        &self,
+        commitment: &MmrCommitment,
+        _: &(),
+    ) -> Result<Vec<TimestampedEntry>, VerificationError> {
+        commitment.verify_time_range(self)
+    }
+
+    fn size_hint(&self) -> usize {
+        VERSION_LENGTH + 2 * TIMESTAMP_LENGTH + 1 + self.proof.as_ref().map_or(0, Proof::size_hint)
+    }
+}
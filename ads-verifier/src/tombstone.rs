@@ -0,0 +1,107 @@
+//! Logical deletion of entries of an append-only log.
+//!
+//! An entry can't be removed without changing every commitment after it, so it's deleted by
+//! appending a tombstone: a record that references its index. Readers treat tombstoned entries as
+//! redacted, and a `DeletionProof` shows that an entry was deleted as of a commitment with the
+//! inclusion proofs of the entry and of its tombstone. Logs with tombstones must not append other
+//! entries that start with `TOMBSTONE_TAG`.
+
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::VerificationError;
+use crate::hash::NodeHasher;
+use crate::mmr::{MmrCommitment, MmrInclusionProof};
+use crate::version::{check_version, PROOF_FORMAT_VERSION};
+
+/// Prefix of the encoding of tombstones, which no other entry of a log with tombstones starts with.
+pub const TOMBSTONE_TAG: &[u8] = b"ads/tombstone\0";
+
+/// Record deleting the entry at `index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Tombstone {
+    pub index: u64,
+}
+
+impl Tombstone {
+    pub fn new(index: u64) -> Self {
+        Tombstone { index }
+    }
+
+    /// The bytes committed in the log: the tag followed by the index, little endian.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(TOMBSTONE_TAG.len() + 8);
+        encoded.extend_from_slice(TOMBSTONE_TAG);
+        encoded.extend_from_slice(&self.index.to_le_bytes());
+        encoded
+    }
+
+    /// Inverse of `encode`. Returns None unless `bytes` is exactly an encoded tombstone.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let index = bytes.strip_prefix(TOMBSTONE_TAG)?;
+        Some(Tombstone {
+            index: u64::from_le_bytes(index.try_into().ok()?),
+        })
+    }
+}
+
+/// Proof that an entry was deleted as of a commitment: its inclusion and that of a later tombstone
+/// referencing it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct DeletionProof {
+    pub version: u8,
+    pub entry: MmrInclusionProof,
+    pub tombstone: MmrInclusionProof,
+}
+
+impl DeletionProof {
+    pub fn new(entry: MmrInclusionProof, tombstone: MmrInclusionProof) -> Self {
+        DeletionProof {
+            version: PROOF_FORMAT_VERSION,
+            entry,
+            tombstone,
+        }
+    }
+}
+
+impl MmrCommitment {
+    /// Verify that an entry was deleted and return its index.
+    pub fn verify_deletion(&self, proof: &DeletionProof) -> Result<u64, VerificationError> {
+        self.verify_deletion_with_hasher(&NodeHasher::new(self.hash_algo()), proof)
+    }
+
+    /// Same as `verify_deletion`, for an MMR hashed with `hasher`.
+    pub fn verify_deletion_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        proof: &DeletionProof,
+    ) -> Result<u64, VerificationError> {
+        check_version(proof.version)?;
+        self.verify_inclusion_with_hasher(hasher, &proof.entry)?;
+        self.verify_inclusion_with_hasher(hasher, &proof.tombstone)?;
+
+        // Both proofs verified, so their leaves exist
+        let index_of = |proof: &MmrInclusionProof| {
+            self.entry_index(proof.tree_index, proof.proof.leaf_index)
+                .unwrap()
+        };
+        let index = index_of(&proof.entry);
+        let tombstone_index = index_of(&proof.tombstone);
+        let tombstone = Tombstone::decode(&proof.tombstone.entry)
+            .ok_or(VerificationError::MalformedEntry(tombstone_index))?;
+        // A tombstone can only reference an entry appended before it
+        if tombstone.index != index || tombstone_index <= index {
+            return Err(VerificationError::NotDeleted);
+        }
+        Ok(index)
+    }
+}
@@ -0,0 +1,33 @@
+//! Versioning of serialized proofs and commitments.
+//!
+//! Every proof and commitment carries the version of the format it was produced with as its first
+//! field. A version fixes both the encoding and the hashing (e.g., domain separation), and a
+//! verifier only implements `PROOF_FORMAT_VERSION`: newer proofs are rejected with
+//! `VerificationError::UnsupportedVersion` and older ones with `VerificationError::OutdatedVersion`,
+//! instead of being verified as if they were current and failing with a misleading root mismatch.
+//! Older proofs have to be produced again.
+//!
+//! Proofs are encoded with BCS, which isn't self-describing, so a field added by a later version
+//! changes the encoding and older encodings don't decode as the new struct. New fields are
+//! appended at the end of a struct, which keeps the version in front, where a decoder reading it
+//! first (as `merkle_forests::stream` does) can report the version error before decoding fails.
+
+use crate::error::VerificationError;
+
+/// The version of proofs and commitments produced by this crate.
+///
+/// Version 2 added the tree size to `SuffixProof`. Version 3 replaced the per-height digests of
+/// `MmrCommitment` with its number of entries and peaks, so older commitments need converting with
+/// `MmrCommitment::from_digests`. Version 4 added the hash algorithm to `MmrCommitment` and the
+/// proofs about an MMR. Version 5 added the ordering of the entries to `MmrCommitment`.
+pub const PROOF_FORMAT_VERSION: u8 = 5;
+
+/// Check that a proof or commitment of the given version can be verified by this crate, i.e.,
+/// that it's of the current version.
+pub fn check_version(version: u8) -> Result<(), VerificationError> {
+    match version {
+        PROOF_FORMAT_VERSION => Ok(()),
+        1..PROOF_FORMAT_VERSION => Err(VerificationError::OutdatedVersion(version)),
+        _ => Err(VerificationError::UnsupportedVersion(version)),
+    }
+}
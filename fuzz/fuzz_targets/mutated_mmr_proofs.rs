@@ -0,0 +1,152 @@
+//! Honest MMR proofs, mutated in structured ways, verified against the MMR's commitment.
+//!
+//! Verification must never panic, and whatever it accepts must be true of the MMR: the entries of a
+//! most recent n elements proof are the last entries, and the entry of an inclusion proof sits
+//! where the proof says it does.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use ads_verifier::standard::pos_to_leaf_index;
+use ads_verifier::{Digest, MmrCommitment, MmrInclusionProof, MostRecentNElementsProof};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use merkle_forests::MerkleMountainRange;
+
+// 8 + 4 + 1, so proofs mix full and partial trees
+const NUM_ENTRIES: usize = 13;
+
+#[derive(Debug, Arbitrary)]
+enum Mutation {
+    Version(u8),
+    Entry { index: u8, byte: u8 },
+    DropEntry(u8),
+    DuplicateEntry(u8),
+    TreeIndex { index: u8, value: u8 },
+    DropTreeIndex(u8),
+    PushTreeIndex(u8),
+    PartialTree(Option<u8>),
+    SuffixCount(u8),
+    Digest { index: u8, byte: u8 },
+    DropDigest(u8),
+    PushDigest([u8; 32]),
+    LeafIndex(u16),
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    num_recent: u8,
+    index: u8,
+    mutations: Vec<Mutation>,
+}
+
+fn fixture() -> &'static (MerkleMountainRange, MmrCommitment) {
+    static FIXTURE: OnceLock<(MerkleMountainRange, MmrCommitment)> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let entries: Vec<Vec<u8>> = (0..NUM_ENTRIES)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let commitment = mmr.commitment();
+        (mmr, commitment)
+    })
+}
+
+fn at<T>(items: &mut [T], index: u8) -> Option<&mut T> {
+    let len = items.len();
+    (len > 0).then(|| &mut items[index as usize % len])
+}
+
+fn mutate_digests(digests: &mut Vec<Digest>, mutation: &Mutation) {
+    match *mutation {
+        Mutation::Digest { index, byte } => {
+            if let Some(digest) = at(digests, index) {
+                digest.0[byte as usize % 32] ^= 1 << (byte % 8);
+            }
+        }
+        Mutation::DropDigest(index) if !digests.is_empty() => {
+            digests.remove(index as usize % digests.len());
+        }
+        Mutation::PushDigest(bytes) => digests.push(Digest(bytes)),
+        _ => {}
+    }
+}
+
+fn mutate_recent(proof: &mut MostRecentNElementsProof, mutation: &Mutation) {
+    match *mutation {
+        Mutation::Version(version) => proof.version = version,
+        Mutation::Entry { index, byte } => {
+            if let Some(entry) = at(&mut proof.entries, index) {
+                entry.push(byte);
+            }
+        }
+        Mutation::DropEntry(index) if !proof.entries.is_empty() => {
+            proof.entries.remove(index as usize % proof.entries.len());
+        }
+        Mutation::DuplicateEntry(index) => {
+            if let Some(entry) = at(&mut proof.entries, index).cloned() {
+                proof.entries.push(entry);
+            }
+        }
+        Mutation::TreeIndex { index, value } => {
+            if let Some(tree_index) = at(&mut proof.full_tree_indices, index) {
+                *tree_index = value as u64;
+            }
+        }
+        Mutation::DropTreeIndex(index) if !proof.full_tree_indices.is_empty() => {
+            let len = proof.full_tree_indices.len();
+            proof.full_tree_indices.remove(index as usize % len);
+        }
+        Mutation::PushTreeIndex(value) => proof.full_tree_indices.push(value as u64),
+        Mutation::PartialTree(None) => proof.partial_tree_proof = None,
+        Mutation::PartialTree(Some(value)) => {
+            if let Some((tree_index, _)) = proof.partial_tree_proof.as_mut() {
+                *tree_index = value as u64;
+            }
+        }
+        Mutation::SuffixCount(count) => {
+            if let Some((_, suffix_proof)) = proof.partial_tree_proof.as_mut() {
+                suffix_proof.num_suffix_elements = count as u64;
+            }
+        }
+        _ => {
+            if let Some((_, suffix_proof)) = proof.partial_tree_proof.as_mut() {
+                mutate_digests(&mut suffix_proof.proof, mutation);
+            }
+        }
+    }
+}
+
+fn mutate_inclusion(proof: &mut MmrInclusionProof, mutation: &Mutation) {
+    match *mutation {
+        Mutation::Version(version) => proof.version = version,
+        Mutation::Entry { byte, .. } => proof.entry.push(byte),
+        Mutation::TreeIndex { value, .. } => proof.tree_index = value as u64,
+        Mutation::LeafIndex(leaf_index) => proof.proof.leaf_index = leaf_index as u64,
+        _ => mutate_digests(&mut proof.proof.siblings, mutation),
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let (mmr, commitment) = fixture();
+
+    let num_recent = input.num_recent as u64 % NUM_ENTRIES as u64 + 1;
+    let mut recent = mmr.prove_most_recent_n_elements(num_recent);
+    let mut inclusion = mmr.prove_inclusion(input.index as u64 % NUM_ENTRIES as u64);
+    for mutation in &input.mutations {
+        mutate_recent(&mut recent, mutation);
+        mutate_inclusion(&mut inclusion, mutation);
+    }
+
+    if commitment.verify_most_recent_n_elements(&recent).is_ok() {
+        let start = NUM_ENTRIES - recent.entries.len();
+        assert_eq!(recent.entries, mmr.entries.to_vecs(start..NUM_ENTRIES));
+    }
+
+    if commitment.verify_inclusion(&inclusion).is_ok() {
+        let pos = mmr.standard_pos_of(inclusion.tree_index as usize, inclusion.proof.leaf_index);
+        let index = pos_to_leaf_index(pos).unwrap() as usize;
+        assert_eq!(inclusion.entry, mmr.entries.get(index));
+    }
+});
@@ -0,0 +1,68 @@
+//! Arbitrary bytes decoded as BCS proofs, both owned and borrowed, then verified against a tree.
+//!
+//! Decoding and verification must never panic, both decoders must accept exactly the same inputs,
+//! and a decoded proof may only verify if it is the honest proof for its query.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use ads_verifier::borrowed::{InclusionProofRef, SuffixProofRef};
+use ads_verifier::{Digest, InclusionProof, SuffixProof};
+use libfuzzer_sys::fuzz_target;
+use merkle_forests::PerfectMerkleTree;
+
+const NUM_LEAVES: u64 = 16;
+
+struct Fixture {
+    leaves: Vec<Vec<u8>>,
+    tree: PerfectMerkleTree,
+    root: Digest,
+}
+
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let leaves: Vec<Vec<u8>> = (0..NUM_LEAVES)
+            .map(|i| format!("leaf{}", i).into_bytes())
+            .collect();
+        let tree = PerfectMerkleTree::new(leaves.iter().map(|leaf| leaf.as_slice()).collect());
+        let root = tree.root().hash;
+        Fixture { leaves, tree, root }
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let fixture = fixture();
+
+    let suffix = bcs::from_bytes::<SuffixProof>(data).ok();
+    let suffix_ref = SuffixProofRef::from_bcs(data);
+    assert_eq!(suffix.is_some(), suffix_ref.is_some());
+    if let (Some(proof), Some(proof_ref)) = (suffix, suffix_ref) {
+        let n = proof.num_suffix_elements.min(NUM_LEAVES);
+        let elements = &fixture.leaves[(NUM_LEAVES - n) as usize..];
+        let element_refs: Vec<&[u8]> = elements.iter().map(|e| e.as_slice()).collect();
+        let accepted = proof.verify(&fixture.root, NUM_LEAVES, elements).is_ok();
+        assert_eq!(
+            accepted,
+            proof_ref.verify(&fixture.root, NUM_LEAVES, &element_refs)
+        );
+        if accepted {
+            let honest = fixture.tree.prove_most_recent_n_elements(n);
+            assert_eq!(bcs::to_bytes(&honest).unwrap(), data);
+        }
+    }
+
+    let inclusion = bcs::from_bytes::<InclusionProof>(data).ok();
+    let inclusion_ref = InclusionProofRef::from_bcs(data);
+    assert_eq!(inclusion.is_some(), inclusion_ref.is_some());
+    if let (Some(proof), Some(proof_ref)) = (inclusion, inclusion_ref) {
+        let leaf = &fixture.leaves[(proof.leaf_index % NUM_LEAVES) as usize];
+        let accepted = proof.verify(&fixture.root, NUM_LEAVES, leaf).is_ok();
+        assert_eq!(accepted, proof_ref.verify(&fixture.root, NUM_LEAVES, leaf));
+        if accepted {
+            let honest = fixture.tree.prove_inclusion(proof.leaf_index);
+            assert_eq!(bcs::to_bytes(&honest).unwrap(), data);
+        }
+    }
+});
@@ -1,5 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use merkle_forests::MerkleMountainRange;
+use merkle_forests::hashing::HashBackend;
+use merkle_forests::{MerkleMountainRange, PerfectMerkleTree};
 
 fn bench_merkle_tree_creation(c: &mut Criterion) {
     let lengths = vec![100, 1000, 10000];
@@ -31,12 +32,36 @@ fn bench_merkle_tree_add_entry(c: &mut Criterion) {
 
         c.bench_function(format!("merkle_tree_add_entry_{}", length).as_str(), |b| {
             b.iter(|| {
-                black_box(merkle_tree.add_entry(b"newblock"));
+                merkle_tree.add_entry(black_box(b"newblock"));
             })
         });
     }
 }
 
-criterion_group!(benches, bench_merkle_tree_add_entry);
+fn bench_hash_backends(c: &mut Criterion) {
+    let strings: Vec<String> = (1..=2u32.pow(16)).map(|i| format!("block{}", i)).collect();
+    let data_blocks: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+
+    for backend in [HashBackend::Scalar, HashBackend::MultiBuffer] {
+        c.bench_function(
+            format!("perfect_tree_creation_{:?}", backend).as_str(),
+            |b| {
+                b.iter(|| {
+                    black_box(PerfectMerkleTree::new_with_backend(
+                        data_blocks.clone(),
+                        backend,
+                    ));
+                })
+            },
+        );
+    }
+}
+
+criterion_group!(
+    benches,
+    bench_merkle_tree_creation,
+    bench_merkle_tree_add_entry,
+    bench_hash_backends
+);
 
 criterion_main!(benches);
@@ -0,0 +1,167 @@
+//! Checkpointing the root of an MMR into a skip list.
+//!
+//! An `AnchoredLog` folds the bagged root of its MMR into a skip list every so often, so that the
+//! skip list's head commits to the whole history of roots: checkpoint `h` sits at height `h`. An
+//! `AnchorProof` shows that an entry is in the root checkpointed at some height, and that this root
+//! is in the skip list with a given head.
+
+use ads_core::encoding::{bytes_length, digests_length, uleb128_length};
+use ads_core::Proof;
+use serde::Serialize;
+pub use skip_lists::{HeadCommitment, Node, SkipList};
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::standard::{leaf_count_from_mmr_size, leaf_index_to_pos, StandardMerkleProof};
+use crate::MerkleMountainRange;
+
+/// Proof that an entry is in the MMR root checkpointed at `checkpoint.height`, and that the
+/// checkpoint is in a skip list.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnchorProof {
+    pub entry: Vec<u8>,
+    pub index: u64,
+    /// Against `checkpoint.value`
+    pub proof: StandardMerkleProof,
+    pub checkpoint: Node<Digest>,
+    /// From the skip list's head down to the checkpoint, as returned by `get_inclusion_proof`
+    pub path: Vec<Node<Digest>>,
+}
+
+impl AnchorProof {
+    /// Verify against the head of the skip list.
+    pub fn verify(&self, head: &HeadCommitment) -> Result<(), VerificationError> {
+        if !skip_lists::verify_inclusion_proof(head, &self.checkpoint, &self.path) {
+            return Err(VerificationError::RootMismatch);
+        }
+        let num_entries = leaf_count_from_mmr_size(self.proof.mmr_size).unwrap_or(0);
+        if self.index >= num_entries {
+            return Err(VerificationError::LeafIndexOutOfBounds(self.index));
+        }
+        let pos = leaf_index_to_pos(self.index);
+        if !self.proof.verify(&self.checkpoint.value, pos, &self.entry) {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl Proof for AnchorProof {
+    type Commitment = HeadCommitment;
+    type Claim = ();
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(&self, head: &HeadCommitment, _: &()) -> Result<(), VerificationError> {
+        AnchorProof::verify(self, head)
+    }
+
+    fn size_hint(&self) -> usize {
+        let proof = 1 + 8 + digests_length(self.proof.proof_items.len());
+        bytes_length(self.entry.len())
+            + 8
+            + proof
+            + self.checkpoint.encoded_length()
+            + uleb128_length(self.path.len())
+            + self.path.iter().map(Node::encoded_length).sum::<usize>()
+    }
+}
+
+/// An MMR whose roots are checkpointed into a skip list.
+#[derive(Debug)]
+pub struct AnchoredLog {
+    mmr: MerkleMountainRange,
+    checkpoints: SkipList<Digest>,
+    // Number of entries at each checkpoint
+    checkpoint_sizes: Vec<u64>,
+    // Checkpoint automatically every this many entries, if set
+    interval: Option<u64>,
+}
+
+impl AnchoredLog {
+    /// A log checkpointed every `interval` entries, or only on `checkpoint` if None.
+    pub fn new(interval: Option<u64>) -> Self {
+        assert_ne!(interval, Some(0), "Checkpoint interval must be positive");
+        AnchoredLog {
+            mmr: MerkleMountainRange::new(vec![]),
+            checkpoints: SkipList::new(),
+            checkpoint_sizes: vec![],
+            interval,
+        }
+    }
+
+    pub fn append(&mut self, entry: &[u8]) {
+        self.mmr.add_entry(entry);
+        if let Some(interval) = self.interval {
+            if self.mmr.num_entries().is_multiple_of(interval) {
+                self.checkpoint();
+            }
+        }
+    }
+
+    /// Fold the current root into the skip list and return the checkpoint's height, None if the
+    /// log is empty. A root already checkpointed isn't added again.
+    pub fn checkpoint(&mut self) -> Option<u64> {
+        let num_entries = self.mmr.num_entries();
+        if self.checkpoint_sizes.last() == Some(&num_entries) {
+            return Some(self.checkpoint_sizes.len() as u64);
+        }
+        self.checkpoints.add(self.mmr.standard_root()?);
+        self.checkpoint_sizes.push(num_entries);
+        Some(self.checkpoint_sizes.len() as u64)
+    }
+
+    pub fn mmr(&self) -> &MerkleMountainRange {
+        &self.mmr
+    }
+
+    pub fn checkpoints(&self) -> &SkipList<Digest> {
+        &self.checkpoints
+    }
+
+    /// Head of the skip list of checkpoints, which anchor proofs are checked against.
+    pub fn head(&self) -> Option<HeadCommitment> {
+        self.checkpoints.head()
+    }
+
+    /// Number of entries the root checkpointed at `height` covers.
+    pub fn checkpoint_size(&self, height: u64) -> Option<u64> {
+        let i = height.checked_sub(1)?;
+        self.checkpoint_sizes.get(i as usize).copied()
+    }
+
+    /// Height of the first checkpoint covering the entry at `index`, None if none does yet.
+    pub fn first_checkpoint_of(&self, index: u64) -> Option<u64> {
+        let i = self.checkpoint_sizes.partition_point(|&size| size <= index);
+        (i < self.checkpoint_sizes.len()).then_some(i as u64 + 1)
+    }
+
+    /// Prove the entry at `index` against the root checkpointed at `height`, which must cover it.
+    /// Proving against an older root than the current one rebuilds the MMR as it was then.
+    pub fn prove_anchored(&self, index: u64, height: u64) -> AnchorProof {
+        let size = self
+            .checkpoint_size(height)
+            .unwrap_or_else(|| panic!("No checkpoint at height {}", height));
+        assert!(
+            index < size,
+            "Entry {} isn't covered by checkpoint {}",
+            index,
+            height
+        );
+        let proof = if size == self.mmr.num_entries() {
+            self.mmr.prove_inclusion_standard(index)
+        } else {
+            let entries = (0..size as usize)
+                .map(|i| self.mmr.entries.get(i))
+                .collect();
+            MerkleMountainRange::new(entries).prove_inclusion_standard(index)
+        };
+        AnchorProof {
+            entry: self.mmr.entries.get(index as usize).to_vec(),
+            index,
+            proof,
+            checkpoint: self.checkpoints.nodes[height as usize - 1].clone(),
+            path: self.checkpoints.get_inclusion_proof(height),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! A peaks-only MMR for nodes that only ingest entries and publish commitments.
+//!
+//! Appending only ever touches the peaks, so it suffices to keep one digest per tree (at most
+//! log2(n) + 1 of them) and the number of entries. No entries or interior nodes are retained,
+//! hence no proofs can be served. Nor are values needed once hashed, so a large one can be
+//! streamed in without ever being held in memory.
+
+use std::io::{self, Read};
+
+use ads_core::NodeHasher;
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::frontier::Frontier;
+use crate::standard::{bag_peaks_with_hasher, mmr_size_from_leaf_count, PeakBagging};
+use crate::stream::hash_leaf_from_reader_with_hasher;
+use crate::{MerkleMountainRange, MmrCommitment};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MmrAppender {
+    num_entries: u64,
+    // peaks[i] is the digest of the tree with 2^i leaves, mirroring MerkleMountainRange::trees
+    peaks: Vec<Option<Digest>>,
+    hasher: NodeHasher,
+}
+
+impl MmrAppender {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// An appender hashing leaves and nodes with `hasher`, as `MerkleMountainRange::new_with_hasher`.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        MmrAppender {
+            num_entries: 0,
+            peaks: vec![None],
+            hasher,
+        }
+    }
+
+    /// Start from the current state of a full MMR.
+    pub fn from_mmr(mmr: &MerkleMountainRange) -> Self {
+        MmrAppender {
+            num_entries: mmr.num_entries(),
+            peaks: mmr.digests(),
+            hasher: *mmr.hasher(),
+        }
+    }
+
+    /// Resume from the frontier another appender handed over, hashing with `hasher`.
+    pub fn from_frontier(
+        frontier: &Frontier,
+        hasher: NodeHasher,
+    ) -> Result<Self, VerificationError> {
+        if hasher.algo() != frontier.hash_algo() {
+            return Err(VerificationError::HashAlgoMismatch {
+                expected: frontier.hash_algo(),
+                actual: hasher.algo(),
+            });
+        }
+        let mut peaks = frontier.commitment().digests();
+        // Room for the carry out of the largest tree, as `new_with_hasher` starts with
+        peaks.push(None);
+        Ok(MmrAppender {
+            num_entries: frontier.num_entries(),
+            peaks,
+            hasher,
+        })
+    }
+
+    /// Resume from a number of entries and the matching peaks, in the format of `digests`.
+    pub fn from_peaks(num_entries: u64, peaks: Vec<Option<Digest>>) -> Self {
+        MmrAppender {
+            num_entries,
+            peaks,
+            hasher: NodeHasher::UNKEYED,
+        }
+    }
+
+    pub fn append(&mut self, entry: &[u8]) {
+        self.append_leaf_hash(self.hasher.hash_leaf(entry));
+    }
+
+    /// Append the entry `reader` yields, hashed as it's read, and return its leaf hash. On error,
+    /// nothing is appended.
+    pub fn append_reader<R: Read>(&mut self, reader: R) -> io::Result<Digest> {
+        let leaf = hash_leaf_from_reader_with_hasher(&self.hasher, reader)?;
+        self.append_leaf_hash(leaf);
+        Ok(leaf)
+    }
+
+    /// Same as `append_reader`, for an asynchronous stream.
+    #[cfg(feature = "async")]
+    pub async fn append_async_reader<R: futures_io::AsyncRead + Unpin>(
+        &mut self,
+        reader: R,
+    ) -> io::Result<Digest> {
+        let leaf = crate::stream::hash_leaf_from_async_reader(&self.hasher, reader).await?;
+        self.append_leaf_hash(leaf);
+        Ok(leaf)
+    }
+
+    /// Append an entry by its leaf hash, `hash_leaf` of its value with the appender's hasher.
+    pub fn append_leaf_hash(&mut self, leaf: Digest) {
+        self.num_entries += 1;
+
+        // Same carry propagation as MerkleMountainRange::add_entry
+        let mut carry = leaf;
+        for peak in self.peaks.iter_mut() {
+            if let Some(left) = peak.take() {
+                carry = self.hasher.hash_children(&left, &carry);
+            } else {
+                *peak = Some(carry);
+                break;
+            }
+        }
+
+        if self.peaks.last().unwrap().is_some() {
+            self.peaks.push(None);
+        }
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.num_entries
+    }
+
+    /// The commitment in the same format as `MerkleMountainRange::digests`.
+    pub fn digests(&self) -> Vec<Option<Digest>> {
+        self.peaks.clone()
+    }
+
+    /// The commitment proofs about the full MMR are verified against.
+    pub fn commitment(&self) -> MmrCommitment {
+        MmrCommitment::from_digests(&self.digests()).with_hash_algo(self.hasher.algo())
+    }
+
+    /// The state to hand over to another appender, e.g., on another node.
+    pub fn frontier(&self) -> Frontier {
+        Frontier::from_commitment(self.commitment()).expect("One peak per tree")
+    }
+
+    /// Peak digests ordered left to right, as in the conventional layout.
+    pub fn standard_peaks(&self) -> Vec<Digest> {
+        self.peaks.iter().rev().flatten().copied().collect()
+    }
+
+    pub fn standard_root(&self) -> Option<Digest> {
+        bag_peaks_with_hasher(&self.hasher, &self.standard_peaks())
+    }
+
+    /// Same as `standard_root`, with the peaks bagged as `bagging` does.
+    pub fn standard_root_with_bagging(&self, bagging: &PeakBagging) -> Option<Digest> {
+        bagging.bag_with_hasher(
+            &self.hasher,
+            &self.standard_peaks(),
+            mmr_size_from_leaf_count(self.num_entries),
+        )
+    }
+}
+
+impl Default for MmrAppender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Only the peaks are kept, so entries can stream in from anywhere in memory logarithmic in their
+/// number.
+impl<T: AsRef<[u8]>> FromIterator<T> for MmrAppender {
+    fn from_iter<I: IntoIterator<Item = T>>(entries: I) -> Self {
+        let mut appender = MmrAppender::new();
+        appender.extend(entries);
+        appender
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for MmrAppender {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, entries: I) {
+        for entry in entries {
+            self.append(entry.as_ref());
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Verification of many independent proofs at once, against the commitment of an MMR as in
+//! `ads_verifier::batch`.
+
+pub use ads_verifier::batch::BatchVerificationError;
+
+use crate::parallel::Parallelism;
+use crate::{MerkleMountainRange, MmrInclusionProof, MostRecentNElementsProof};
+
+impl MerkleMountainRange {
+    pub fn verify_batch_most_recent_n_elements(
+        &self,
+        proofs: &[MostRecentNElementsProof],
+    ) -> Result<(), BatchVerificationError> {
+        self.verify_batch_most_recent_n_elements_with_parallelism(proofs, &Parallelism::new())
+    }
+
+    /// Same as `verify_batch_most_recent_n_elements`, spread as `parallelism` says.
+    pub fn verify_batch_most_recent_n_elements_with_parallelism(
+        &self,
+        proofs: &[MostRecentNElementsProof],
+        parallelism: &Parallelism,
+    ) -> Result<(), BatchVerificationError> {
+        self.commitment()
+            .verify_batch_most_recent_n_elements_with_hasher(&self.hasher, proofs, parallelism)
+    }
+
+    pub fn verify_batch_inclusion(
+        &self,
+        proofs: &[MmrInclusionProof],
+    ) -> Result<(), BatchVerificationError> {
+        self.verify_batch_inclusion_with_parallelism(proofs, &Parallelism::new())
+    }
+
+    /// Same as `verify_batch_inclusion`, spread as `parallelism` says.
+    pub fn verify_batch_inclusion_with_parallelism(
+        &self,
+        proofs: &[MmrInclusionProof],
+        parallelism: &Parallelism,
+    ) -> Result<(), BatchVerificationError> {
+        self.commitment()
+            .verify_batch_inclusion_with_hasher(&self.hasher, proofs, parallelism)
+    }
+}
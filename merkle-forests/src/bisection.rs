@@ -0,0 +1,29 @@
+//! Answering the rounds of a bisection from an MMR.
+
+pub use ads_verifier::bisection::{
+    Bisection, BisectionError, BisectionQuery, Disagreement, Party, Split,
+};
+
+use crate::MerkleMountainRange;
+
+impl MerkleMountainRange {
+    /// The children of the node of `query.height` covering the entries from `query.start`. None if
+    /// there's no such node, e.g., for a query meant for an MMR of another size.
+    pub fn split(&self, query: &BisectionQuery) -> Option<Split> {
+        if query.height == 0 || query.start >= self.num_entries() {
+            return None;
+        }
+        let (tree_index, leaf_index) = self.locate(query.start);
+        let aligned = leaf_index.trailing_zeros() as u64 >= query.height;
+        if tree_index as u64 >= query.height && aligned {
+            let tree = self.trees[tree_index].as_ref()?;
+            let child = leaf_index >> (query.height - 1);
+            Some(Split {
+                left: tree.node_at(query.height - 1, child).hash,
+                right: tree.node_at(query.height - 1, child + 1).hash,
+            })
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Bitcoin transaction Merkle trees (see `ads_verifier::bitcoin`) and their SPV proofs.
+
+pub use ads_verifier::bitcoin::{
+    hash_children, merkle_root, next_level, sha256d, txid, BitcoinMerkleProof,
+};
+
+use crate::digest::Digest;
+
+/// The transaction tree of a block, kept level by level so that proofs don't rehash it.
+#[derive(Debug, Clone)]
+pub struct BitcoinMerkleTree {
+    // From the transaction ids up to the root, each level without the duplicated node
+    levels: Vec<Vec<Digest>>,
+}
+
+impl BitcoinMerkleTree {
+    pub fn new(txids: Vec<Digest>) -> Self {
+        assert!(!txids.is_empty(), "A block has at least one transaction");
+        let mut levels = vec![txids];
+        while levels.last().unwrap().len() > 1 {
+            levels.push(next_level(levels.last().unwrap()));
+        }
+        BitcoinMerkleTree { levels }
+    }
+
+    /// The tree of the serialized transactions `transactions`.
+    pub fn from_transactions(transactions: &[&[u8]]) -> Self {
+        Self::new(transactions.iter().map(|tx| txid(tx)).collect())
+    }
+
+    pub fn root(&self) -> Digest {
+        self.levels.last().unwrap()[0]
+    }
+
+    pub fn num_transactions(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    /// Prove that the transaction at `tx_index` is in the block.
+    pub fn prove(&self, tx_index: u64) -> BitcoinMerkleProof {
+        assert!(tx_index < self.num_transactions());
+        let mut index = tx_index as usize;
+        let siblings = self.levels[..self.levels.len() - 1]
+            .iter()
+            .map(|level| {
+                // The last node of an odd level is paired with itself
+                let sibling = level.get(index ^ 1).unwrap_or(&level[index]);
+                index /= 2;
+                *sibling
+            })
+            .collect();
+        BitcoinMerkleProof::new(self.num_transactions(), tx_index, siblings)
+    }
+}
@@ -0,0 +1,92 @@
+//! The root of a perfect tree, computed as its leaves stream in.
+//!
+//! Building a `PerfectMerkleTree` keeps every node, which is wasted when only the root is needed.
+//! Leaves arrive left to right, so every subtree that's complete is final: the builder keeps one
+//! digest per complete subtree of the leaves so far, at most log2(n) + 1 of them, in a `Frontier`.
+//! Once all 2^h leaves are in, that's a single digest, the root.
+
+use std::io::{self, Read};
+
+use ads_core::NodeHasher;
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::frontier::Frontier;
+use crate::stream::hash_leaf_from_reader_with_hasher;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerfectTreeBuilder {
+    frontier: Frontier,
+    hasher: NodeHasher,
+}
+
+impl PerfectTreeBuilder {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// A builder hashing leaves and nodes with `hasher`, as `PerfectMerkleTree::new_with_hasher`.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        PerfectTreeBuilder {
+            frontier: Frontier::empty().with_hash_algo(hasher.algo()),
+            hasher,
+        }
+    }
+
+    pub fn append(&mut self, value: &[u8]) {
+        self.append_leaf_hash(self.hasher.hash_leaf(value));
+    }
+
+    /// Append the value `reader` yields, hashed as it's read, and return its leaf hash. On error,
+    /// nothing is appended.
+    pub fn append_reader<R: Read>(&mut self, reader: R) -> io::Result<Digest> {
+        let leaf = hash_leaf_from_reader_with_hasher(&self.hasher, reader)?;
+        self.append_leaf_hash(leaf);
+        Ok(leaf)
+    }
+
+    /// Append a leaf by its hash, `hash_leaf` of its value with the builder's hasher.
+    pub fn append_leaf_hash(&mut self, leaf: Digest) {
+        self.frontier
+            .append_leaf_hash(&self.hasher, leaf)
+            .expect("The frontier is hashed with the builder's algorithm");
+    }
+
+    pub fn num_leaves(&self) -> u64 {
+        self.frontier.num_entries()
+    }
+
+    /// The digests of the complete subtrees so far, the largest first.
+    pub fn frontier(&self) -> &Frontier {
+        &self.frontier
+    }
+
+    /// The root of the tree, the same as `PerfectMerkleTree::root` of the same leaves, or
+    /// `InvalidTreeSize` unless the number of leaves is a power of two.
+    pub fn finish(self) -> Result<Digest, VerificationError> {
+        let num_leaves = self.num_leaves();
+        if !num_leaves.is_power_of_two() {
+            return Err(VerificationError::InvalidTreeSize(num_leaves));
+        }
+        let (_, root) = self
+            .frontier
+            .peaks()
+            .next()
+            .expect("A power of two leaves make a single tree");
+        Ok(*root)
+    }
+}
+
+impl Default for PerfectTreeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Extend<&'a [u8]> for PerfectTreeBuilder {
+    fn extend<I: IntoIterator<Item = &'a [u8]>>(&mut self, values: I) {
+        for value in values {
+            self.append(value);
+        }
+    }
+}
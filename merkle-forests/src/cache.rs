@@ -0,0 +1,198 @@
+//! Memoizing proofs of an MMR between appends.
+//!
+//! A `ProofCache` holds proofs valid against one commitment, by query. Moving it to the
+//! commitment after some appends only drops the proofs that changed: an inclusion proof only
+//! depends on the tree holding its entry, which survives the appends unless they merge it into a
+//! larger one, while proofs of the most recent entries and of consistency with an older size are
+//! about the latest entries, so they all change. `CachedMmr` serves proofs of an MMR through a
+//! cache kept in step with its appends.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::provider::{ProofProvider, ProviderError};
+use crate::{
+    ConsistencyProof, MerkleMountainRange, MmrCommitment, MmrInclusionProof,
+    MostRecentNElementsProof,
+};
+
+/// What a proof is about, as requested from a `ProofProvider`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProofQuery {
+    Inclusion(u64),
+    Suffix(u64),
+    Consistency(u64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CachedProof {
+    Inclusion(MmrInclusionProof),
+    Suffix(MostRecentNElementsProof),
+    Consistency(ConsistencyProof),
+}
+
+#[derive(Debug, Clone)]
+pub struct ProofCache {
+    // What the cached proofs verify against
+    commitment: MmrCommitment,
+    proofs: HashMap<ProofQuery, CachedProof>,
+    hits: u64,
+    misses: u64,
+}
+
+impl ProofCache {
+    /// An empty cache of proofs against `commitment`.
+    pub fn new(commitment: MmrCommitment) -> Self {
+        ProofCache {
+            commitment,
+            proofs: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn commitment(&self) -> &MmrCommitment {
+        &self.commitment
+    }
+
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Number of lookups that found a proof.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Number of lookups that didn't.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// The cached proof answering `query` against `commitment()`, if any.
+    pub fn get(&mut self, query: &ProofQuery) -> Option<&CachedProof> {
+        let proof = self.proofs.get(query);
+        match proof {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        proof
+    }
+
+    /// Cache `proof`, which must answer `query` against `commitment()`.
+    pub fn insert(&mut self, query: ProofQuery, proof: CachedProof) {
+        self.proofs.insert(query, proof);
+    }
+
+    /// Move to `commitment`, keeping the proofs that are still valid against it. Only a commitment
+    /// to more entries of the same MMR keeps any.
+    pub fn advance(&mut self, commitment: MmrCommitment) {
+        let old = self.commitment.num_entries();
+        let new = commitment.num_entries();
+        if new <= old {
+            self.proofs.clear();
+        } else {
+            self.proofs.retain(|query, proof| match (query, proof) {
+                // The tree of height h holding the entry survives while no bit from h up changes
+                (ProofQuery::Inclusion(_), CachedProof::Inclusion(proof)) => {
+                    old >> proof.tree_index == new >> proof.tree_index
+                }
+                _ => false,
+            });
+        }
+        self.commitment = commitment;
+    }
+
+    pub fn clear(&mut self) {
+        self.proofs.clear();
+    }
+}
+
+/// An MMR whose proofs are cached until appends change them.
+#[derive(Debug)]
+pub struct CachedMmr {
+    mmr: MerkleMountainRange,
+    cache: Mutex<ProofCache>,
+}
+
+impl CachedMmr {
+    pub fn new(mmr: MerkleMountainRange) -> Self {
+        let cache = Mutex::new(ProofCache::new(mmr.commitment()));
+        CachedMmr { mmr, cache }
+    }
+
+    pub fn mmr(&self) -> &MerkleMountainRange {
+        &self.mmr
+    }
+
+    pub fn into_mmr(self) -> MerkleMountainRange {
+        self.mmr
+    }
+
+    pub fn append(&mut self, entry: &[u8]) {
+        self.mmr.add_entry(entry);
+        let commitment = self.mmr.commitment();
+        self.cache().advance(commitment);
+    }
+
+    pub fn cache(&self) -> MutexGuard<'_, ProofCache> {
+        // Nothing panics while holding the lock, so it can't be poisoned
+        self.cache.lock().unwrap()
+    }
+
+    fn cached(&self, query: &ProofQuery) -> Option<CachedProof> {
+        self.cache().get(query).cloned()
+    }
+}
+
+/// Proofs from the cache, computed and cached on a miss.
+impl ProofProvider for CachedMmr {
+    type Error = ProviderError;
+
+    async fn get_commitment(&self) -> Result<MmrCommitment, ProviderError> {
+        Ok(self.cache().commitment().clone())
+    }
+
+    async fn prove_inclusion(&self, index: u64) -> Result<MmrInclusionProof, ProviderError> {
+        let query = ProofQuery::Inclusion(index);
+        if let Some(CachedProof::Inclusion(proof)) = self.cached(&query) {
+            return Ok(proof);
+        }
+        let proof = ProofProvider::prove_inclusion(&self.mmr, index).await?;
+        let cached = CachedProof::Inclusion(proof.clone());
+        self.cache().insert(query, cached);
+        Ok(proof)
+    }
+
+    async fn prove_suffix(
+        &self,
+        num_entries: u64,
+    ) -> Result<MostRecentNElementsProof, ProviderError> {
+        let query = ProofQuery::Suffix(num_entries);
+        if let Some(CachedProof::Suffix(proof)) = self.cached(&query) {
+            return Ok(proof);
+        }
+        let proof = self.mmr.prove_suffix(num_entries).await?;
+        let cached = CachedProof::Suffix(proof.clone());
+        self.cache().insert(query, cached);
+        Ok(proof)
+    }
+
+    async fn prove_consistency(
+        &self,
+        old_num_entries: u64,
+    ) -> Result<ConsistencyProof, ProviderError> {
+        let query = ProofQuery::Consistency(old_num_entries);
+        if let Some(CachedProof::Consistency(proof)) = self.cached(&query) {
+            return Ok(proof);
+        }
+        let proof = ProofProvider::prove_consistency(&self.mmr, old_num_entries).await?;
+        let cached = CachedProof::Consistency(proof.clone());
+        self.cache().insert(query, cached);
+        Ok(proof)
+    }
+}
@@ -0,0 +1,17 @@
+//! Answering auditors' spot-check challenges.
+
+pub use ads_verifier::challenge::ChallengeResponse;
+
+use crate::MerkleMountainRange;
+
+impl MerkleMountainRange {
+    /// Prove the `count` entries challenged by `nonce`, see `MmrCommitment::challenge_indices`.
+    /// None if the MMR is empty.
+    pub fn respond_to_challenge(&self, nonce: &[u8], count: u64) -> Option<ChallengeResponse> {
+        let indices = self.commitment().challenge_indices(nonce, count);
+        if indices.is_empty() {
+            return None;
+        }
+        Some(ChallengeResponse::new(self.prove_multi_inclusion(&indices)))
+    }
+}
@@ -0,0 +1,62 @@
+//! Compacting an MMR: rebuilding it without its deleted entries, with a proof relating the two.
+
+pub use ads_verifier::compaction::CompactionProof;
+
+use crate::hashing::HashBackend;
+use crate::tombstone::Tombstone;
+use crate::MerkleMountainRange;
+
+impl MerkleMountainRange {
+    /// The MMR of the entries but the deleted ones and their tombstones, in the same order and
+    /// hashed the same way, and the proof that its commitment follows from the current one.
+    pub fn compact(&self) -> (MerkleMountainRange, CompactionProof) {
+        let num_entries = self.entries.len();
+        let mut dropped = vec![false; num_entries];
+        let mut deleted = vec![];
+        let mut leaf_hashes = vec![];
+        for (i, entry) in self.entries.iter().enumerate() {
+            // As verifiers do, a tombstone only deletes an earlier entry, and only the first counts
+            if let Some(tombstone) = Tombstone::decode(entry) {
+                let index = tombstone.index as usize;
+                if index < i && !dropped[index] {
+                    dropped[index] = true;
+                    dropped[i] = true;
+                    deleted.push((tombstone.index, i as u64, leaf_hashes[index]));
+                }
+            }
+            leaf_hashes.push(self.hasher.hash_leaf(entry));
+        }
+        deleted.sort_unstable_by_key(|&(index, _, _)| index);
+
+        // Both start with the entries before the first dropped one, the others are listed
+        let prefix_len = dropped.iter().position(|d| *d).unwrap_or(num_entries);
+        let kept_leaf_hashes = leaf_hashes
+            .into_iter()
+            .zip(&dropped)
+            .skip(prefix_len)
+            .filter(|(_, dropped)| !**dropped)
+            .map(|(leaf, _)| leaf)
+            .collect();
+        let proof = CompactionProof::new(
+            self.commitment_at(prefix_len as u64),
+            self.prove_consistency(prefix_len as u64),
+            deleted,
+            kept_leaf_hashes,
+        );
+
+        let kept = self
+            .entries
+            .iter()
+            .zip(&dropped)
+            .filter(|(_, dropped)| !**dropped)
+            .map(|(entry, _)| entry)
+            .collect();
+        let mut compacted = Self::new_with_hasher(kept, HashBackend::detect(), self.hasher);
+        compacted.ordering = self.ordering;
+        if self.content_index.is_some() {
+            compacted.enable_content_index();
+        }
+
+        (compacted, proof)
+    }
+}
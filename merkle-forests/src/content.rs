@@ -0,0 +1,210 @@
+//! Lookup of entries by content.
+//!
+//! Clients usually know the record they care about, or its leaf hash, not its index. An MMR can
+//! maintain an index from the leaf hash of each entry, as its own hasher computes it, to the
+//! indices it was appended at, so that finding and proving an entry doesn't scan the whole log.
+//! Conventional positions (see `standard`) follow from indices in constant time. The index is
+//! optional: it costs a hash map entry per distinct entry, and lookups fall back to a scan without
+//! it.
+
+use std::collections::HashMap;
+use std::mem::size_of;
+
+use ads_core::NodeHasher;
+
+use crate::digest::Digest;
+use crate::memory::MemoryUsage;
+use crate::standard::leaf_index_to_pos;
+use crate::values::LeafValues;
+use crate::{MerkleMountainRange, MmrInclusionProof, MmrMultiInclusionProof};
+
+/// Indices of the entries of an MMR, keyed by their leaf hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentIndex {
+    indices: HashMap<Digest, Vec<u64>>,
+    hasher: NodeHasher,
+}
+
+impl ContentIndex {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// An index keyed by the leaf hashes of `hasher`, the one of the MMR.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        ContentIndex {
+            indices: HashMap::new(),
+            hasher,
+        }
+    }
+
+    /// Index every entry of `entries`, hashed with `hasher`.
+    pub fn from_entries(hasher: NodeHasher, entries: &LeafValues) -> Self {
+        let mut index = ContentIndex::new_with_hasher(hasher);
+        for (i, entry) in entries.iter().enumerate() {
+            index.insert(entry, i as u64);
+        }
+        index
+    }
+
+    /// Record that `entry` was appended at `index`. Indices must be inserted in increasing order.
+    pub fn insert(&mut self, entry: &[u8], index: u64) {
+        self.insert_leaf_hash(self.hasher.hash_leaf(entry), index);
+    }
+
+    /// Same as `insert`, for an entry known by its leaf hash.
+    pub fn insert_leaf_hash(&mut self, leaf_hash: Digest, index: u64) {
+        self.indices.entry(leaf_hash).or_default().push(index);
+    }
+
+    /// Forget that the entry with the given leaf hash is at `index`, e.g., once it's pruned.
+    pub fn remove(&mut self, leaf_hash: &Digest, index: u64) {
+        if let Some(indices) = self.indices.get_mut(leaf_hash) {
+            indices.retain(|&i| i != index);
+            if indices.is_empty() {
+                self.indices.remove(leaf_hash);
+            }
+        }
+    }
+
+    /// Indices at which an entry with the given leaf hash was appended, in increasing order.
+    pub fn get(&self, leaf_hash: &Digest) -> &[u64] {
+        self.indices.get(leaf_hash).map_or(&[], |indices| indices)
+    }
+
+    /// How the leaf hashes the index is keyed by are computed.
+    pub fn hasher(&self) -> &NodeHasher {
+        &self.hasher
+    }
+
+    /// Indices of every entry appended more than once, ordered by first occurrence.
+    pub fn duplicates(&self) -> Vec<&[u64]> {
+        let mut duplicates: Vec<&[u64]> = self
+            .indices
+            .values()
+            .filter(|indices| indices.len() > 1)
+            .map(|indices| indices.as_slice())
+            .collect();
+        duplicates.sort_unstable_by_key(|indices| indices[0]);
+        duplicates
+    }
+
+    /// Number of distinct entries.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn memory_usage(&self) -> MemoryUsage {
+        // A HashMap allocates a control byte next to each bucket
+        let buckets = self.indices.capacity() * (size_of::<(Digest, Vec<u64>)>() + 1);
+        let indices: usize = self
+            .indices
+            .values()
+            .map(|indices| indices.capacity() * size_of::<u64>())
+            .sum();
+        MemoryUsage {
+            auxiliary: buckets + indices,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for ContentIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MerkleMountainRange {
+    /// Build the content index of the current entries and keep it up to date on appends.
+    pub fn enable_content_index(&mut self) {
+        if self.content_index.is_none() {
+            self.content_index = Some(ContentIndex::from_entries(self.hasher, &self.entries));
+        }
+    }
+
+    /// Drop the content index; lookups scan the entries from then on.
+    pub fn disable_content_index(&mut self) {
+        self.content_index = None;
+    }
+
+    /// Every index at which `value` was appended, in increasing order.
+    pub fn indices_of(&self, value: &[u8]) -> Vec<u64> {
+        match &self.content_index {
+            Some(index) => index.get(&self.hasher.hash_leaf(value)).to_vec(),
+            None => self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| *entry == value)
+                .map(|(i, _)| i as u64)
+                .collect(),
+        }
+    }
+
+    /// Every index at which an entry with the given leaf hash, as the MMR's hasher computes it,
+    /// was appended, in increasing order.
+    pub fn indices_of_leaf_hash(&self, leaf_hash: &Digest) -> Vec<u64> {
+        match &self.content_index {
+            Some(index) => index.get(leaf_hash).to_vec(),
+            None => self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| self.hasher.hash_leaf(entry) == *leaf_hash)
+                .map(|(i, _)| i as u64)
+                .collect(),
+        }
+    }
+
+    /// Conventional positions of every entry with the given leaf hash, in increasing order.
+    pub fn positions_of_leaf_hash(&self, leaf_hash: &Digest) -> Vec<u64> {
+        self.indices_of_leaf_hash(leaf_hash)
+            .into_iter()
+            .map(leaf_index_to_pos)
+            .collect()
+    }
+
+    /// An inclusion proof of the first entry with the given leaf hash, None if there's none.
+    pub fn prove_inclusion_of_leaf_hash(&self, leaf_hash: &Digest) -> Option<MmrInclusionProof> {
+        let index = *self.indices_of_leaf_hash(leaf_hash).first()?;
+        Some(self.prove_inclusion(index))
+    }
+
+    /// An inclusion proof of the first occurrence of `value`, None if it was never appended.
+    pub fn prove_inclusion_of(&self, value: &[u8]) -> Option<MmrInclusionProof> {
+        let index = *self.indices_of(value).first()?;
+        Some(self.prove_inclusion(index))
+    }
+
+    /// One proof of every occurrence of `value`, None if it was never appended.
+    pub fn prove_occurrences_of(&self, value: &[u8]) -> Option<MmrMultiInclusionProof> {
+        let indices = self.indices_of(value);
+        (!indices.is_empty()).then(|| self.prove_multi_inclusion(&indices))
+    }
+
+    /// Indices of every entry appended more than once, ordered by first occurrence.
+    pub fn duplicate_indices(&self) -> Vec<Vec<u64>> {
+        let scanned;
+        let index = match &self.content_index {
+            Some(index) => index,
+            None => {
+                scanned = ContentIndex::from_entries(self.hasher, &self.entries);
+                &scanned
+            }
+        };
+        index.duplicates().into_iter().map(<[_]>::to_vec).collect()
+    }
+
+    /// Inclusion proofs of every occurrence of `value`, in increasing order of index.
+    pub fn prove_all_inclusions_of(&self, value: &[u8]) -> Vec<MmrInclusionProof> {
+        self.indices_of(value)
+            .into_iter()
+            .map(|index| self.prove_inclusion(index))
+            .collect()
+    }
+}
@@ -0,0 +1,100 @@
+//! Logs split into epochs, each its own MMR, whose roots are chained into a skip list.
+//!
+//! Sealing an epoch folds the bagged root of its MMR into the skip list and starts a new, empty
+//! MMR: epoch `e` is sealed at height `e`, counting from 1, so the skip list's head commits to
+//! every sealed epoch. Proofs of entries of sealed epochs are `AnchorProof`s, whose checkpoint is
+//! the epoch's root. Since an epoch never changes once sealed, its MMR can be retired, e.g., past a
+//! retention period, without affecting the head or proofs about other epochs.
+
+use crate::anchor::{AnchorProof, HeadCommitment, SkipList};
+use crate::digest::Digest;
+use crate::MerkleMountainRange;
+
+#[derive(Debug)]
+pub struct EpochedLog {
+    // The MMR of each sealed epoch, None once retired
+    sealed: Vec<Option<MerkleMountainRange>>,
+    roots: SkipList<Digest>,
+    current: MerkleMountainRange,
+    // Seal automatically every this many entries, if set
+    epoch_length: Option<u64>,
+}
+
+impl EpochedLog {
+    /// A log sealing an epoch every `epoch_length` entries, or only on `seal` if None.
+    pub fn new(epoch_length: Option<u64>) -> Self {
+        assert_ne!(epoch_length, Some(0), "Epoch length must be positive");
+        EpochedLog {
+            sealed: vec![],
+            roots: SkipList::new(),
+            current: MerkleMountainRange::new(vec![]),
+            epoch_length,
+        }
+    }
+
+    pub fn append(&mut self, entry: &[u8]) {
+        self.current.add_entry(entry);
+        if Some(self.current.num_entries()) == self.epoch_length {
+            self.seal();
+        }
+    }
+
+    /// Seal the current epoch and return its number, None if it has no entries.
+    pub fn seal(&mut self) -> Option<u64> {
+        let root = self.current.standard_root()?;
+        self.roots.add(root);
+        let sealed = std::mem::replace(&mut self.current, MerkleMountainRange::new(vec![]));
+        self.sealed.push(Some(sealed));
+        Some(self.sealed.len() as u64)
+    }
+
+    /// Number of the epoch entries are appended to.
+    pub fn current_epoch(&self) -> u64 {
+        self.sealed.len() as u64 + 1
+    }
+
+    pub fn current(&self) -> &MerkleMountainRange {
+        &self.current
+    }
+
+    /// The MMR of a sealed epoch, None if it isn't sealed or was retired.
+    pub fn epoch(&self, epoch: u64) -> Option<&MerkleMountainRange> {
+        self.sealed.get(epoch.checked_sub(1)? as usize)?.as_ref()
+    }
+
+    /// Drop the entries of a sealed epoch, and return whether it held any. Its root stays chained.
+    pub fn retire(&mut self, epoch: u64) -> bool {
+        let Some(i) = epoch.checked_sub(1) else {
+            return false;
+        };
+        self.sealed
+            .get_mut(i as usize)
+            .is_some_and(|sealed| sealed.take().is_some())
+    }
+
+    /// Head of the skip list of sealed roots, which proofs are checked against. None until an
+    /// epoch is sealed.
+    pub fn head(&self) -> Option<HeadCommitment> {
+        self.roots.head()
+    }
+
+    pub fn roots(&self) -> &SkipList<Digest> {
+        &self.roots
+    }
+
+    /// Prove the entry at `index` of a sealed epoch against the head, None if the epoch isn't
+    /// sealed, was retired, or has no such entry.
+    pub fn prove(&self, epoch: u64, index: u64) -> Option<AnchorProof> {
+        let mmr = self.epoch(epoch)?;
+        if index >= mmr.num_entries() {
+            return None;
+        }
+        Some(AnchorProof {
+            entry: mmr.entries.get(index as usize).to_vec(),
+            index,
+            proof: mmr.prove_inclusion_standard(index),
+            checkpoint: self.roots.nodes[epoch as usize - 1].clone(),
+            path: self.roots.get_inclusion_proof(epoch),
+        })
+    }
+}
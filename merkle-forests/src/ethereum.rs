@@ -0,0 +1,59 @@
+//! Merkle trees as Solidity contracts verify them (see `ads_verifier::ethereum`) and their proofs.
+
+pub use ads_verifier::ethereum::{
+    hash_children, keccak256, merkle_root, next_level, process_sorted_proof, standard_leaf_hash,
+    EthereumMerkleProof, PairOrder,
+};
+
+use crate::digest::Digest;
+
+/// A tree over leaf hashes, kept level by level so that proofs don't rehash it.
+#[derive(Debug, Clone)]
+pub struct EthereumMerkleTree {
+    order: PairOrder,
+    // From the leaves up to the root
+    levels: Vec<Vec<Digest>>,
+}
+
+impl EthereumMerkleTree {
+    pub fn new(order: PairOrder, leaf_hashes: Vec<Digest>) -> Self {
+        assert!(!leaf_hashes.is_empty());
+        let mut levels = vec![leaf_hashes];
+        while levels.last().unwrap().len() > 1 {
+            levels.push(next_level(order, levels.last().unwrap()));
+        }
+        EthereumMerkleTree { order, levels }
+    }
+
+    /// The tree whose leaves are the keccak256 of `values`.
+    pub fn from_values(order: PairOrder, values: &[&[u8]]) -> Self {
+        Self::new(order, values.iter().map(|value| keccak256(value)).collect())
+    }
+
+    pub fn order(&self) -> PairOrder {
+        self.order
+    }
+
+    pub fn root(&self) -> Digest {
+        self.levels.last().unwrap()[0]
+    }
+
+    pub fn num_leaves(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    /// Prove the leaf at `leaf_index`.
+    pub fn prove(&self, leaf_index: u64) -> EthereumMerkleProof {
+        assert!(leaf_index < self.num_leaves());
+        let mut index = leaf_index as usize;
+        let siblings = self.levels[..self.levels.len() - 1]
+            .iter()
+            .filter_map(|level| {
+                let sibling = level.get(index ^ 1).copied();
+                index /= 2;
+                sibling
+            })
+            .collect();
+        EthereumMerkleProof::new(self.order, self.num_leaves(), leaf_index, siblings)
+    }
+}
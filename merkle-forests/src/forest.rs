@@ -0,0 +1,370 @@
+//! Many named logs kept in one pair of stores, committed to by a single super-root.
+//!
+//! Each log is a `StoredMmr` confined to its own range of positions in the shared stores: log `i`
+//! (counting from 1) uses positions `[i << 40, (i + 1) << 40)`. Range 0 of the entry store holds
+//! the directory, i.e., the name of log `i` at position `i - 1`, so a forest can be reopened from
+//! its stores alone. Since positions are far apart, the stores must be sparse: the file, sled and
+//! RocksDB stores are, and `BTreeMap` serves as a sparse store on the heap.
+//!
+//! Logs that are independent MMRs, e.g., shards held by different processes, are committed to by
+//! the same super-root through a `SuperRoot` over their current state.
+//!
+//! See `ads_verifier::forest` for what the super-root commits to.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ads_store::{BatchOp, NodeStore};
+pub use ads_verifier::forest::{LogEntryProof, LogProof, LogRecord};
+
+use crate::digest::Digest;
+use crate::standard::StandardMerkleProof;
+use crate::store::{count_entries, StoreError, StoredMmr};
+use crate::MerkleMountainRange;
+
+// Each log gets 2^40 positions, i.e., logs of up to 2^39 entries
+const LOG_REGION_BITS: u32 = 40;
+const MAX_LOGS: u64 = (1 << (u64::BITS - LOG_REGION_BITS)) - 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForestError<E> {
+    UnknownLog(String),
+    DuplicateLog(String),
+    /// The directory entry of the log with this id isn't a name
+    CorruptDirectory(u64),
+    TooManyLogs,
+    Store(StoreError<E>),
+}
+
+impl<E: fmt::Display> fmt::Display for ForestError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ForestError::UnknownLog(name) => write!(f, "No log named {}", name),
+            ForestError::DuplicateLog(name) => write!(f, "A log named {} already exists", name),
+            ForestError::CorruptDirectory(id) => write!(f, "Directory entry {} is corrupt", id),
+            ForestError::TooManyLogs => write!(f, "The forest can't hold more logs"),
+            ForestError::Store(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for ForestError<E> {}
+
+impl<E> From<StoreError<E>> for ForestError<E> {
+    fn from(e: StoreError<E>) -> Self {
+        ForestError::Store(e)
+    }
+}
+
+/// Named logs sharing a node store and an entry store.
+#[derive(Debug)]
+pub struct Forest<S = BTreeMap<u64, Digest>, E = BTreeMap<u64, Vec<u8>>> {
+    nodes: S,
+    entries: E,
+    // Log ids by name
+    logs: BTreeMap<String, u64>,
+}
+
+impl Forest {
+    /// An empty forest on the heap.
+    pub fn in_memory() -> Self {
+        Forest {
+            nodes: BTreeMap::new(),
+            entries: BTreeMap::new(),
+            logs: BTreeMap::new(),
+        }
+    }
+}
+
+impl<S, E> Forest<S, E>
+where
+    S: NodeStore<Digest>,
+    E: NodeStore<Vec<u8>, Error = S::Error>,
+{
+    /// Open the forest kept in the given stores, empty ones for a new forest.
+    pub fn open(nodes: S, entries: E) -> Result<Self, ForestError<S::Error>> {
+        let directory = Region::new(&entries, 0);
+        let num_logs = count_entries(&directory).map_err(StoreError::Backend)?;
+        let mut logs = BTreeMap::new();
+        for id in 1..=num_logs {
+            let name = directory
+                .get(id - 1)
+                .map_err(StoreError::Backend)?
+                .ok_or(StoreError::MissingEntry(id - 1))?;
+            let name = String::from_utf8(name).map_err(|_| ForestError::CorruptDirectory(id))?;
+            logs.insert(name, id);
+        }
+        Ok(Forest {
+            nodes,
+            entries,
+            logs,
+        })
+    }
+
+    /// Add an empty log.
+    pub fn create_log(&mut self, name: &str) -> Result<(), ForestError<S::Error>> {
+        if self.logs.contains_key(name) {
+            return Err(ForestError::DuplicateLog(name.to_string()));
+        }
+        let id = self.logs.len() as u64 + 1;
+        if id > MAX_LOGS {
+            return Err(ForestError::TooManyLogs);
+        }
+        Region::new(&mut self.entries, 0)
+            .put(id - 1, name.as_bytes().to_vec())
+            .map_err(StoreError::Backend)?;
+        self.logs.insert(name.to_string(), id);
+        Ok(())
+    }
+
+    /// Names of the logs, in the order their records are committed in.
+    pub fn log_names(&self) -> impl Iterator<Item = &str> {
+        self.logs.keys().map(|name| name.as_str())
+    }
+
+    pub fn append(&mut self, name: &str, entry: &[u8]) -> Result<(), ForestError<S::Error>> {
+        let id = self.id(name)?;
+        let mut log = StoredMmr::open(
+            Region::new(&mut self.nodes, id),
+            Region::new(&mut self.entries, id),
+        )?;
+        log.append(entry)?;
+        Ok(())
+    }
+
+    pub fn num_entries(&self, name: &str) -> Result<u64, ForestError<S::Error>> {
+        Ok(self.log(name)?.num_entries())
+    }
+
+    /// The record the super-root commits to for a log.
+    pub fn record(&self, name: &str) -> Result<LogRecord, ForestError<S::Error>> {
+        let log = self.log(name)?;
+        Ok(LogRecord {
+            name: name.to_string(),
+            root: log.standard_root()?,
+            size: log.num_entries(),
+        })
+    }
+
+    /// The super-root, None for a forest without logs.
+    pub fn super_root(&self) -> Result<Option<Digest>, ForestError<S::Error>> {
+        Ok(self.records_mmr()?.standard_root())
+    }
+
+    /// Prove the record of a log against the super-root.
+    pub fn prove_log(&self, name: &str) -> Result<LogProof, ForestError<S::Error>> {
+        let index = self.log_names().position(|n| n == name);
+        let index = index.ok_or_else(|| ForestError::UnknownLog(name.to_string()))? as u64;
+        let records = self.records_mmr()?;
+        Ok(LogProof::new(
+            self.record(name)?,
+            index,
+            records.prove_inclusion_standard(index),
+        ))
+    }
+
+    /// Prove an entry of a log against the root in the log's record.
+    pub fn prove_inclusion(
+        &self,
+        name: &str,
+        index: u64,
+    ) -> Result<StandardMerkleProof, ForestError<S::Error>> {
+        Ok(self.log(name)?.prove_inclusion_standard(index)?)
+    }
+
+    /// Prove an entry of a log against the super-root.
+    pub fn prove_entry(
+        &self,
+        name: &str,
+        index: u64,
+    ) -> Result<LogEntryProof, ForestError<S::Error>> {
+        let log = self.log(name)?;
+        let entry = log.entry(index)?;
+        Ok(LogEntryProof::new(
+            self.prove_log(name)?,
+            entry,
+            index,
+            log.prove_inclusion_standard(index)?,
+        ))
+    }
+
+    /// Make all changes so far durable.
+    pub fn flush(&mut self) -> Result<(), ForestError<S::Error>> {
+        self.nodes.flush().map_err(StoreError::Backend)?;
+        self.entries.flush().map_err(StoreError::Backend)?;
+        Ok(())
+    }
+
+    /// Consume the forest, returning its stores.
+    pub fn into_stores(self) -> (S, E) {
+        (self.nodes, self.entries)
+    }
+
+    fn id(&self, name: &str) -> Result<u64, ForestError<S::Error>> {
+        self.logs
+            .get(name)
+            .copied()
+            .ok_or_else(|| ForestError::UnknownLog(name.to_string()))
+    }
+
+    // A read-only view of a log
+    fn log(&self, name: &str) -> Result<ReadOnlyLog<'_, S, E>, ForestError<S::Error>> {
+        let id = self.id(name)?;
+        Ok(StoredMmr::open(
+            Region::new(&self.nodes, id),
+            Region::new(&self.entries, id),
+        )?)
+    }
+
+    // The MMR whose entries are the encoded records, in name order
+    fn records_mmr(&self) -> Result<MerkleMountainRange, ForestError<S::Error>> {
+        let records = self
+            .log_names()
+            .map(|name| Ok(self.record(name)?.encode()))
+            .collect::<Result<Vec<_>, ForestError<S::Error>>>()?;
+        Ok(MerkleMountainRange::new(
+            records.iter().map(|record| record.as_slice()).collect(),
+        ))
+    }
+}
+
+/// The super-root of independent logs, as a forest holding the same logs would commit to them.
+/// Logs must be hashed with the default hasher, which their proofs are checked with.
+#[derive(Debug)]
+pub struct SuperRoot<'a> {
+    logs: BTreeMap<String, &'a MerkleMountainRange>,
+    // The MMR whose entries are the encoded records, in name order
+    records: MerkleMountainRange,
+}
+
+impl<'a> SuperRoot<'a> {
+    /// Commit to the current state of `logs`, by name. Names must be distinct.
+    pub fn new<'n>(logs: impl IntoIterator<Item = (&'n str, &'a MerkleMountainRange)>) -> Self {
+        let mut by_name = BTreeMap::new();
+        for (name, log) in logs {
+            let duplicate = by_name.insert(name.to_string(), log).is_some();
+            assert!(!duplicate, "A log named {} already exists", name);
+        }
+        let records: Vec<Vec<u8>> = by_name
+            .iter()
+            .map(|(name, log)| record_of(name, log).encode())
+            .collect();
+        SuperRoot {
+            logs: by_name,
+            records: MerkleMountainRange::new(records.iter().map(Vec::as_slice).collect()),
+        }
+    }
+
+    /// The super-root, None without logs.
+    pub fn root(&self) -> Option<Digest> {
+        self.records.standard_root()
+    }
+
+    pub fn log_names(&self) -> impl Iterator<Item = &str> {
+        self.logs.keys().map(|name| name.as_str())
+    }
+
+    /// The record the super-root commits to for a log, None if there's no such log.
+    pub fn record(&self, name: &str) -> Option<LogRecord> {
+        self.logs.get(name).map(|log| record_of(name, log))
+    }
+
+    /// Prove the record of a log against the super-root.
+    pub fn prove_log(&self, name: &str) -> Option<LogProof> {
+        let index = self.logs.keys().position(|n| n == name)? as u64;
+        Some(LogProof::new(
+            self.record(name)?,
+            index,
+            self.records.prove_inclusion_standard(index),
+        ))
+    }
+
+    /// Prove the entry at `index` of a log against the super-root, None if there's no such entry.
+    pub fn prove_entry(&self, name: &str, index: u64) -> Option<LogEntryProof> {
+        let log = self.logs.get(name)?;
+        if index >= log.num_entries() {
+            return None;
+        }
+        Some(LogEntryProof::new(
+            self.prove_log(name)?,
+            log.entries.get(index as usize).to_vec(),
+            index,
+            log.prove_inclusion_standard(index),
+        ))
+    }
+}
+
+fn record_of(name: &str, log: &MerkleMountainRange) -> LogRecord {
+    LogRecord {
+        name: name.to_string(),
+        root: log.standard_root(),
+        size: log.num_entries(),
+    }
+}
+
+type ReadOnlyLog<'a, S, E> = StoredMmr<Region<&'a S>, Region<&'a E>>;
+
+// The positions of one log (or of the directory) within a shared store, through a shared borrow
+// for reads or an exclusive one for appends
+struct Region<R> {
+    store: R,
+    offset: u64,
+}
+
+impl<R> Region<R> {
+    fn new(store: R, id: u64) -> Self {
+        Region {
+            store,
+            offset: id << LOG_REGION_BITS,
+        }
+    }
+}
+
+impl<N, S: NodeStore<N>> NodeStore<N> for Region<&S> {
+    type Error = S::Error;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error> {
+        self.store.get(self.offset + pos)
+    }
+
+    fn put(&mut self, _pos: u64, _node: N) -> Result<(), Self::Error> {
+        unreachable!("Logs are only read through shared borrows")
+    }
+
+    fn delete(&mut self, _pos: u64) -> Result<(), Self::Error> {
+        unreachable!("Logs are only read through shared borrows")
+    }
+}
+
+impl<N, S: NodeStore<N>> NodeStore<N> for Region<&mut S> {
+    type Error = S::Error;
+
+    fn get(&self, pos: u64) -> Result<Option<N>, Self::Error> {
+        self.store.get(self.offset + pos)
+    }
+
+    fn put(&mut self, pos: u64, node: N) -> Result<(), Self::Error> {
+        self.store.put(self.offset + pos, node)
+    }
+
+    fn delete(&mut self, pos: u64) -> Result<(), Self::Error> {
+        self.store.delete(self.offset + pos)
+    }
+
+    fn write_batch(&mut self, batch: Vec<BatchOp<N>>) -> Result<(), Self::Error> {
+        let offset = self.offset;
+        self.store.write_batch(
+            batch
+                .into_iter()
+                .map(|op| match op {
+                    BatchOp::Put(pos, node) => BatchOp::Put(offset + pos, node),
+                    BatchOp::Delete(pos) => BatchOp::Delete(offset + pos),
+                })
+                .collect(),
+        )
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.store.flush()
+    }
+}
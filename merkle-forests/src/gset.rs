@@ -0,0 +1,143 @@
+//! Grow-only sets, replicated and merged with proofs of their union.
+//!
+//! Each replica keeps its elements in a sorted MMR, so that membership and non-membership can be
+//! proven against its commitment. Inserting rebuilds the MMR, so replicas are best grown by
+//! merging batches.
+
+pub use ads_verifier::gset::{MergeProof, MergeSource};
+
+use crate::hashing::HashBackend;
+use crate::ordering::{LeafOrdering, NonMembershipProof};
+use crate::{MerkleMountainRange, MmrCommitment, MmrInclusionProof, NodeHasher};
+
+#[derive(Debug)]
+pub struct GrowOnlySet {
+    mmr: MerkleMountainRange,
+}
+
+impl GrowOnlySet {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// An empty set hashed with `hasher`. Only sets hashed the same way can be merged.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        Self::from_elements_with_hasher(vec![], hasher)
+    }
+
+    /// The set of `elements`, in any order and possibly repeated.
+    pub fn from_elements(elements: Vec<&[u8]>) -> Self {
+        Self::from_elements_with_hasher(elements, NodeHasher::UNKEYED)
+    }
+
+    pub fn from_elements_with_hasher(elements: Vec<&[u8]>, hasher: NodeHasher) -> Self {
+        GrowOnlySet {
+            mmr: MerkleMountainRange::new_with_ordering_and_hasher(
+                elements,
+                LeafOrdering::Sorted,
+                HashBackend::detect(),
+                hasher,
+            ),
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.mmr.num_entries()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, element: &[u8]) -> bool {
+        self.position(element).is_ok()
+    }
+
+    /// The elements, in increasing order.
+    pub fn elements(&self) -> impl Iterator<Item = &[u8]> {
+        self.mmr.entries.iter()
+    }
+
+    pub fn mmr(&self) -> &MerkleMountainRange {
+        &self.mmr
+    }
+
+    pub fn commitment(&self) -> MmrCommitment {
+        self.mmr.commitment()
+    }
+
+    /// Add `element` and return whether it wasn't in the set yet.
+    pub fn insert(&mut self, element: &[u8]) -> bool {
+        let Err(position) = self.position(element) else {
+            return false;
+        };
+        let mut elements: Vec<&[u8]> = self.elements().collect();
+        elements.insert(position, element);
+        let mmr = Self::from_elements_with_hasher(elements, self.mmr.hasher).mmr;
+        self.mmr = mmr;
+        true
+    }
+
+    pub fn prove_membership(&self, element: &[u8]) -> Option<MmrInclusionProof> {
+        let index = self.position(element).ok()?;
+        Some(self.mmr.prove_inclusion(index as u64))
+    }
+
+    pub fn prove_non_membership(&self, element: &[u8]) -> Option<NonMembershipProof> {
+        self.mmr.prove_non_membership(element)
+    }
+
+    /// The union of both sets, and the proof that its commitment is to the union of theirs.
+    pub fn merge(&self, other: &GrowOnlySet) -> (GrowOnlySet, MergeProof) {
+        assert_eq!(
+            self.mmr.hasher, other.mmr.hasher,
+            "Only sets hashed the same way can be merged"
+        );
+        let mut left = self.elements().peekable();
+        let mut right = other.elements().peekable();
+        let mut elements = Vec::new();
+        let mut sources = Vec::new();
+        loop {
+            let (element, source) = match (left.peek(), right.peek()) {
+                (None, None) => break,
+                (Some(_), None) => (left.next().unwrap(), MergeSource::Left),
+                (None, Some(_)) => (right.next().unwrap(), MergeSource::Right),
+                (Some(l), Some(r)) => match l.cmp(r) {
+                    std::cmp::Ordering::Less => (left.next().unwrap(), MergeSource::Left),
+                    std::cmp::Ordering::Greater => (right.next().unwrap(), MergeSource::Right),
+                    std::cmp::Ordering::Equal => {
+                        right.next();
+                        (left.next().unwrap(), MergeSource::Both)
+                    }
+                },
+            };
+            elements.push(element);
+            sources.push(source);
+        }
+
+        let merged = Self::from_elements_with_hasher(elements.clone(), self.mmr.hasher);
+        let elements = elements.into_iter().map(<[u8]>::to_vec).collect();
+        (merged, MergeProof::new(elements, sources))
+    }
+
+    // Index of `element` if it's in the set, or where it would be inserted
+    fn position(&self, element: &[u8]) -> Result<usize, usize> {
+        let mut low = 0;
+        let mut high = self.mmr.entries.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.mmr.entries.get(mid).cmp(element) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Equal => return Ok(mid),
+                std::cmp::Ordering::Greater => high = mid,
+            }
+        }
+        Err(low)
+    }
+}
+
+impl Default for GrowOnlySet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,108 @@
+//! Hashing backends for batches of leaves and internal nodes.
+//!
+//! Building a tree hashes the leaves and then every level as one batch of independent messages,
+//! so instead of hashing them one by one we can feed them to a multi-buffer Blake2b implementation
+//! that hashes several messages at once in SIMD lanes (4-way AVX2 on x86-64). Both backends
+//! compute the exact same digests as `hash_leaf` and `hash_children`, or as a keyed `NodeHasher`.
+
+use ads_core::encoding::{encode_children, LEAF_PREFIX};
+use ads_core::{HashAlgo, NodeHasher};
+use fastcrypto::hash::{Blake2b256, HashFunction};
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HashBackend {
+    /// Hash one message at a time
+    Scalar,
+    /// Hash several messages in parallel SIMD lanes
+    MultiBuffer,
+}
+
+impl HashBackend {
+    /// Pick the multi-buffer backend if the CPU supports hashing more than one message at a time.
+    pub fn detect() -> Self {
+        if blake2b_simd::many::degree() > 1 {
+            HashBackend::MultiBuffer
+        } else {
+            HashBackend::Scalar
+        }
+    }
+
+    /// Hash a batch of leaf values, returning the leaves' hashes in order.
+    pub fn hash_leaves_batch(&self, values: &[&[u8]]) -> Vec<Digest> {
+        self.hash_leaves_batch_with_hasher(&NodeHasher::UNKEYED, values)
+    }
+
+    /// Same as `hash_leaves_batch`, with the leaf hash of `hasher`.
+    pub fn hash_leaves_batch_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        values: &[&[u8]],
+    ) -> Vec<Digest> {
+        let inputs: Vec<_> = values
+            .iter()
+            .map(|value| [&[LEAF_PREFIX], *value].concat())
+            .collect();
+        self.hash_batch(hasher, &inputs)
+    }
+
+    /// Hash a batch of (left, right) children, returning their parents' hashes in order.
+    pub fn hash_children_batch(&self, pairs: &[(Digest, Digest)]) -> Vec<Digest> {
+        self.hash_children_batch_with_hasher(&NodeHasher::UNKEYED, pairs)
+    }
+
+    /// Same as `hash_children_batch`, with the node hash of `hasher`.
+    pub fn hash_children_batch_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        pairs: &[(Digest, Digest)],
+    ) -> Vec<Digest> {
+        // Only byte hashes hash a node as the message of its encoding (not Poseidon)
+        if hasher.algo() != HashAlgo::Blake2b256 {
+            return pairs
+                .iter()
+                .map(|(left, right)| hasher.hash_children(left, right))
+                .collect();
+        }
+        let inputs: Vec<_> = pairs
+            .iter()
+            .map(|(left, right)| encode_children(left, right))
+            .collect();
+        self.hash_batch(hasher, &inputs)
+    }
+
+    fn hash_batch<T: AsRef<[u8]>>(&self, hasher: &NodeHasher, inputs: &[T]) -> Vec<Digest> {
+        match self {
+            // The multi-buffer implementation only computes Blake2b
+            _ if hasher.algo() != HashAlgo::Blake2b256 => Self::hash_each(hasher, inputs),
+            HashBackend::Scalar if hasher.is_keyed() => Self::hash_each(hasher, inputs),
+            HashBackend::Scalar => inputs
+                .iter()
+                .map(|input| Digest(Blake2b256::digest(input).digest))
+                .collect(),
+            HashBackend::MultiBuffer => {
+                let mut params = blake2b_simd::Params::new();
+                params.hash_length(DIGEST_LENGTH);
+                if let Some(key) = hasher.key() {
+                    params.key(key);
+                }
+                let mut jobs: Vec<_> = inputs
+                    .iter()
+                    .map(|input| blake2b_simd::many::HashManyJob::new(&params, input.as_ref()))
+                    .collect();
+                blake2b_simd::many::hash_many(jobs.iter_mut());
+                jobs.iter()
+                    .map(|job| Digest::from_slice(job.to_hash().as_bytes()).unwrap())
+                    .collect()
+            }
+        }
+    }
+
+    fn hash_each<T: AsRef<[u8]>>(hasher: &NodeHasher, inputs: &[T]) -> Vec<Digest> {
+        inputs
+            .iter()
+            .map(|input| hasher.hash_message(input.as_ref()))
+            .collect()
+    }
+}
@@ -0,0 +1,66 @@
+//! Logs of (header, body) entries whose headers can be proven without their bodies.
+//!
+//! See `ads_verifier::headers` for what gets committed.
+
+pub use ads_verifier::headers::HeaderedEntry;
+
+use crate::error::VerificationError;
+use crate::values::LeafValues;
+use crate::{MerkleMountainRange, MmrInclusionProof};
+
+/// An MMR of headered entries, with the bodies kept next to it.
+#[derive(Debug)]
+pub struct HeaderedLog {
+    mmr: MerkleMountainRange,
+    bodies: LeafValues,
+}
+
+impl Default for HeaderedLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeaderedLog {
+    pub fn new() -> Self {
+        HeaderedLog {
+            mmr: MerkleMountainRange::new(vec![]),
+            bodies: LeafValues::new(),
+        }
+    }
+
+    pub fn append(&mut self, header: &[u8], body: &[u8]) {
+        let entry = HeaderedEntry::new(header.to_vec(), body);
+        self.mmr.add_entry(&entry.encode());
+        self.bodies.push(body);
+    }
+
+    pub fn num_entries(&self) -> usize {
+        self.bodies.len()
+    }
+
+    /// The underlying MMR, e.g. for its commitment.
+    pub fn mmr(&self) -> &MerkleMountainRange {
+        &self.mmr
+    }
+
+    pub fn header(&self, index: u64) -> HeaderedEntry {
+        HeaderedEntry::decode(self.mmr.entries.get(index as usize)).unwrap()
+    }
+
+    pub fn body(&self, index: u64) -> &[u8] {
+        self.bodies.get(index as usize)
+    }
+
+    /// Prove the header of an entry. The proof doesn't include the body.
+    pub fn prove_header(&self, index: u64) -> MmrInclusionProof {
+        self.mmr.prove_inclusion(index)
+    }
+
+    pub fn try_verify_header(
+        &self,
+        proof: &MmrInclusionProof,
+    ) -> Result<HeaderedEntry, VerificationError> {
+        self.mmr.commitment().verify_header(proof)
+    }
+}
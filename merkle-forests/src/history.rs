@@ -0,0 +1,90 @@
+//! Logs keeping every checkpointed version, to prove what they held at any past time.
+//!
+//! A `CheckpointedLog` is an append-only `VersionedMmr` along with a `TimestampedLog` of
+//! checkpoints: checkpointing at time `t` commits a version and appends its digest, timestamped
+//! `t`. An entry existed at time `t` if it's in the latest version checkpointed by then, which
+//! `prove_existence` shows against the current commitments of both logs (see `ExistenceProof`).
+
+pub use ads_verifier::history::ExistenceProof;
+
+use ads_core::NodeHasher;
+
+use crate::persistent::VersionedMmr;
+use crate::timed::{TimestampError, TimestampedLog};
+use crate::MmrCommitment;
+
+#[derive(Debug)]
+pub struct CheckpointedLog {
+    log: VersionedMmr,
+    // The digest of version `v` is the `v`-th entry
+    checkpoints: TimestampedLog,
+}
+
+impl Default for CheckpointedLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CheckpointedLog {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// A log hashed with `hasher`. The log of checkpoints is unkeyed, so that anyone can check it.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        CheckpointedLog {
+            log: VersionedMmr::new_with_hasher(hasher),
+            checkpoints: TimestampedLog::new(),
+        }
+    }
+
+    pub fn append(&mut self, entry: &[u8]) {
+        self.log.append(entry);
+    }
+
+    /// Commit the entries appended so far as of `timestamp`, and return the number of the new
+    /// version. Rejected if `timestamp` precedes the last checkpoint's.
+    pub fn checkpoint(&mut self, timestamp: u64) -> Result<u64, TimestampError> {
+        let digest = self.log.current().commitment().digest();
+        self.checkpoints.append(timestamp, &digest.0)?;
+        Ok(self.log.commit())
+    }
+
+    pub fn log(&self) -> &VersionedMmr {
+        &self.log
+    }
+
+    pub fn checkpoints(&self) -> &TimestampedLog {
+        &self.checkpoints
+    }
+
+    /// The commitment to every entry appended so far, checkpointed or not.
+    pub fn commitment(&self) -> MmrCommitment {
+        self.log.current().commitment()
+    }
+
+    pub fn checkpoints_commitment(&self) -> MmrCommitment {
+        self.checkpoints.mmr().commitment()
+    }
+
+    /// The version that was the latest at `time`, None if nothing was checkpointed by then.
+    pub fn version_at(&self, time: u64) -> Option<u64> {
+        self.checkpoints.latest_at(time)
+    }
+
+    /// Prove that the entry at `index` existed at `time`, against `commitment()` and
+    /// `checkpoints_commitment()`. None if it wasn't checkpointed by then.
+    pub fn prove_existence(&self, index: u64, time: u64) -> Option<ExistenceProof> {
+        let version = self.version_at(time)?;
+        let mmr = self.log.version(version)?;
+        let inclusion = mmr.prove_inclusion(index)?;
+        let consistency = self.log.current().prove_consistency(mmr.num_entries())?;
+        Some(ExistenceProof::new(
+            self.checkpoints.mmr().prove_inclusion(version),
+            mmr.commitment(),
+            inclusion,
+            consistency,
+        ))
+    }
+}
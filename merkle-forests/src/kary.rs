@@ -0,0 +1,130 @@
+//! Perfect trees whose nodes have k children, and the inclusion proofs they produce.
+//!
+//! Levels are kept as flat vectors of digests, the leaves first, which is all a proof needs: the
+//! siblings of a node at a level are the other digests of its group of `arity` consecutive ones.
+
+pub use ads_verifier::kary::{
+    check_arity, hash_node, tree_height, KaryInclusionProof, KaryMultiInclusionProof, MAX_ARITY,
+};
+
+use ads_core::NodeHasher;
+
+use crate::digest::Digest;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KaryMerkleTree {
+    arity: u64,
+    // levels[0] holds the leaf hashes, and the last level the root alone
+    levels: Vec<Vec<Digest>>,
+    hasher: NodeHasher,
+}
+
+impl KaryMerkleTree {
+    /// Build the tree of the given arity over `values`, whose number must be a power of it.
+    pub fn new(arity: u64, values: Vec<&[u8]>) -> Self {
+        Self::new_with_hasher(arity, values, NodeHasher::UNKEYED)
+    }
+
+    /// Build the tree with its leaves and nodes hashed by `hasher`, e.g., keyed.
+    pub fn new_with_hasher(arity: u64, values: Vec<&[u8]>, hasher: NodeHasher) -> Self {
+        let leaves = values.iter().map(|value| hasher.hash_leaf(value)).collect();
+        Self::from_leaf_hashes_with_hasher(arity, leaves, hasher)
+    }
+
+    /// Build the tree from the hashes of its leaves, computed with `hasher`.
+    pub fn from_leaf_hashes_with_hasher(
+        arity: u64,
+        leaves: Vec<Digest>,
+        hasher: NodeHasher,
+    ) -> Self {
+        if let Err(e) = check_arity(arity) {
+            panic!("{}", e);
+        }
+        assert!(
+            tree_height(arity, leaves.len() as u64).is_some(),
+            "Not a perfect tree! {} leaves isn't a power of {}",
+            leaves.len(),
+            arity
+        );
+
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(arity as usize)
+                .map(|children| hash_node(&hasher, children))
+                .collect();
+            levels.push(level);
+        }
+        KaryMerkleTree {
+            arity,
+            levels,
+            hasher,
+        }
+    }
+
+    pub fn arity(&self) -> u64 {
+        self.arity
+    }
+
+    pub fn root(&self) -> Digest {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// How the leaves and nodes are hashed, which proofs about the tree are verified with.
+    pub fn hasher(&self) -> &NodeHasher {
+        &self.hasher
+    }
+
+    pub fn num_leaves(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    /// Number of levels below the root.
+    pub fn height(&self) -> u32 {
+        self.levels.len() as u32 - 1
+    }
+
+    pub fn prove_inclusion(&self, index: u64) -> KaryInclusionProof {
+        assert!(index < self.num_leaves(), "Index {} out of bounds", index);
+        let arity = self.arity as usize;
+        let mut siblings = Vec::with_capacity(self.height() as usize * (arity - 1));
+        let mut position = index as usize;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let first = position - position % arity;
+            siblings.extend(
+                (first..first + arity)
+                    .filter(|&i| i != position)
+                    .map(|i| level[i]),
+            );
+            position /= arity;
+        }
+        KaryInclusionProof::new(self.arity, index, siblings)
+    }
+
+    /// Prove the leaves at `leaf_indices`, strictly increasing, at once.
+    pub fn prove_multi_inclusion(&self, leaf_indices: &[u64]) -> KaryMultiInclusionProof {
+        assert!(!leaf_indices.is_empty());
+        assert!(leaf_indices.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(*leaf_indices.last().unwrap() < self.num_leaves());
+
+        // Same traversal as the verifier, recording each child it can't compute
+        let mut siblings = vec![];
+        let mut known = leaf_indices.to_vec();
+        for level in &self.levels[..self.levels.len() - 1] {
+            let mut parents: Vec<u64> = known.iter().map(|index| index / self.arity).collect();
+            parents.dedup();
+            for &parent in &parents {
+                let children = parent * self.arity..(parent + 1) * self.arity;
+                siblings.extend(
+                    children
+                        .filter(|child| known.binary_search(child).is_err())
+                        .map(|child| level[child as usize]),
+                );
+            }
+            known = parents;
+        }
+        KaryMultiInclusionProof::new(self.arity, leaf_indices.to_vec(), siblings)
+    }
+}
@@ -0,0 +1,128 @@
+//! An MMR that defers hashing until a digest or proof is requested.
+//!
+//! Appending only records the entry. The first time the trees are needed, the missing ones are
+//! built in batches: trees hashed by an earlier request are reused as subtrees, and only the parts
+//! covering new entries are hashed. The result is memoized until the next append.
+
+use ads_core::NodeHasher;
+
+use crate::digest::Digest;
+use crate::hashing::HashBackend;
+use crate::observer::Observers;
+use crate::ordering::LeafOrdering;
+use crate::{MerkleMountainRange, PerfectMerkleTree};
+
+#[derive(Debug)]
+pub struct LazyMerkleMountainRange {
+    // Holds every entry, but its trees only cover the first num_hashed of them
+    pub(crate) mmr: MerkleMountainRange,
+    num_hashed: usize,
+    backend: HashBackend,
+}
+
+impl LazyMerkleMountainRange {
+    pub fn new(entries: Vec<&[u8]>) -> Self {
+        Self::new_with_backend(entries, HashBackend::detect())
+    }
+
+    pub fn new_with_backend(entries: Vec<&[u8]>, backend: HashBackend) -> Self {
+        Self::new_with_hasher(entries, backend, NodeHasher::UNKEYED)
+    }
+
+    /// Same as `MerkleMountainRange::new_with_hasher`, with the hashing deferred.
+    pub fn new_with_hasher(entries: Vec<&[u8]>, backend: HashBackend, hasher: NodeHasher) -> Self {
+        LazyMerkleMountainRange {
+            mmr: MerkleMountainRange {
+                entries: entries.into_iter().collect(),
+                trees: vec![None],
+                content_index: None,
+                observers: Observers::default(),
+                hasher,
+                ordering: LeafOrdering::Append,
+            },
+            num_hashed: 0,
+            backend,
+        }
+    }
+
+    pub fn add_entry(&mut self, entry: &[u8]) {
+        self.mmr.entries.push(entry);
+    }
+
+    pub fn num_entries(&self) -> usize {
+        self.mmr.entries.len()
+    }
+
+    /// Whether all trees are up to date, i.e. the next request won't hash anything.
+    pub fn is_hashed(&self) -> bool {
+        self.num_hashed == self.mmr.entries.len()
+    }
+
+    /// The fully hashed MMR, to prove or verify against.
+    pub fn mmr(&mut self) -> &MerkleMountainRange {
+        self.hash_pending();
+        &self.mmr
+    }
+
+    pub fn digests(&mut self) -> Vec<Option<Digest>> {
+        self.mmr().digests()
+    }
+
+    pub fn into_mmr(mut self) -> MerkleMountainRange {
+        self.hash_pending();
+        self.mmr
+    }
+
+    fn hash_pending(&mut self) {
+        if self.is_hashed() {
+            return;
+        }
+
+        let mut hashed: Vec<(usize, PerfectMerkleTree)> = vec![];
+        let mut start = 0;
+        for tree in self.mmr.trees.iter_mut().rev().flat_map(|tree| tree.take()) {
+            let num_leaves = tree.num_leaves() as usize;
+            hashed.push((start, tree));
+            start += num_leaves;
+        }
+
+        // Same layout as MerkleMountainRange::new_with_backend
+        let num_entries = self.mmr.entries.len();
+        let num_heights = (usize::BITS - num_entries.leading_zeros()) as usize;
+        let mut trees: Vec<Option<PerfectMerkleTree>> = (0..=num_heights).map(|_| None).collect();
+        let mut offset = 0;
+        for height in (0..num_heights).rev() {
+            if num_entries & (1 << height) != 0 {
+                trees[height] = Some(self.build(offset, 1 << height, &mut hashed));
+                offset += 1 << height;
+            }
+        }
+
+        self.mmr.trees = trees;
+        self.num_hashed = num_entries;
+    }
+
+    // Build the tree over entries [start, start + num_leaves). Trees only ever grow by merging, so
+    // every previously hashed tree in that range is one of its aligned subtrees.
+    fn build(
+        &self,
+        start: usize,
+        num_leaves: usize,
+        hashed: &mut Vec<(usize, PerfectMerkleTree)>,
+    ) -> PerfectMerkleTree {
+        let end = start + num_leaves;
+        if let Some(i) = hashed
+            .iter()
+            .position(|(s, t)| *s == start && t.num_leaves() == num_leaves as u64)
+        {
+            return hashed.swap_remove(i).1;
+        }
+        if !hashed.iter().any(|(s, _)| (start..end).contains(s)) {
+            let values: Vec<&[u8]> = (start..end).map(|i| self.mmr.entries.get(i)).collect();
+            return PerfectMerkleTree::new_with_hasher(values, self.backend, self.mmr.hasher);
+        }
+        let left = self.build(start, num_leaves / 2, hashed);
+        let right = self.build(start + num_leaves / 2, num_leaves / 2, hashed);
+        PerfectMerkleTree::merge(left, right)
+    }
+}
@@ -1,8 +1,136 @@
+//! Perfect Merkle trees and Merkle Mountain Ranges, built in memory or over a node store, and the
+//! proofs they produce.
+//!
+//! Everything that builds trees or produces proofs sits behind the `prover` feature, on by
+//! default. Without it, the crate only re-exports the proof types and verification of
+//! ads-verifier, under the same paths.
+
+#[cfg(feature = "prover")]
+pub mod anchor;
+#[cfg(feature = "prover")]
+pub mod appender;
+#[cfg(feature = "prover")]
+pub mod batch;
+#[cfg(feature = "prover")]
+pub mod bisection;
+#[cfg(feature = "prover")]
+pub mod bitcoin;
+#[cfg(feature = "prover")]
+pub mod builder;
+#[cfg(feature = "prover")]
+pub mod cache;
+#[cfg(feature = "prover")]
+pub mod challenge;
+#[cfg(feature = "prover")]
+pub mod compaction;
+#[cfg(feature = "prover")]
+pub mod content;
+#[cfg(feature = "prover")]
+pub mod epoch;
+#[cfg(feature = "prover")]
+pub mod ethereum;
+#[cfg(feature = "prover")]
+pub mod forest;
+#[cfg(feature = "prover")]
+pub mod gset;
+#[cfg(feature = "prover")]
+pub mod hashing;
+#[cfg(feature = "prover")]
+pub mod headers;
+#[cfg(feature = "prover")]
+pub mod history;
+#[cfg(feature = "prover")]
+pub mod kary;
+#[cfg(feature = "prover")]
+pub mod lazy;
+#[cfg(feature = "prover")]
+pub mod lockstep;
+#[cfg(feature = "prover")]
+pub mod memory;
+#[cfg(feature = "prover")]
+pub mod observer;
+#[cfg(feature = "prover")]
+pub mod ordering;
+#[cfg(feature = "prover")]
+pub mod ots;
+#[cfg(feature = "prover")]
+pub mod persistent;
+#[cfg(feature = "prover")]
+pub mod planner;
+#[cfg(feature = "prover")]
+pub mod provider;
+#[cfg(feature = "prover")]
+pub mod queue;
+#[cfg(feature = "prover")]
+pub mod selection;
+#[cfg(feature = "prover")]
+pub mod snapshot;
+#[cfg(feature = "prover")]
+pub mod stack;
+#[cfg(feature = "prover")]
+pub mod standard;
+#[cfg(feature = "prover")]
+pub mod store;
+#[cfg(feature = "prover")]
+pub mod stream;
+#[cfg(feature = "prover")]
+pub mod sync;
+#[cfg(feature = "prover")]
 mod test;
-
-use fastcrypto::hash::{Blake2b256, HashFunction};
-use serde::Serialize;
-
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "prover")]
+pub mod timed;
+#[cfg(feature = "prover")]
+pub mod tombstone;
+#[cfg(feature = "prover")]
+pub mod values;
+#[cfg(feature = "vectors")]
+pub mod vectors;
+#[cfg(feature = "wire")]
+pub mod wire;
+
+// The modules above extending these with proof generation
+#[cfg(not(feature = "prover"))]
+pub use ads_verifier::{
+    bisection, bitcoin, challenge, compaction, ethereum, forest, gset, headers, history, kary,
+    ordering, queue, selection, stack, standard, timed, tombstone,
+};
+
+pub use ads_core::digest;
+#[cfg(feature = "arkworks")]
+pub use ads_verifier::arkworks;
+#[cfg(feature = "poseidon")]
+pub use ads_verifier::circuit;
+#[cfg(feature = "prover")]
+pub use ads_verifier::parallel;
+pub use ads_verifier::{
+    audit, borrowed, compressed, ct, equivocation, error, frontier, ics23, limits, spot, streaming,
+};
+pub use ads_verifier::{
+    AppendedRangeProof, CommitmentError, ConsistencyProof, InclusionProof, MmrCommitment,
+    MmrFollower, MmrInclusionProof, MmrMultiInclusionProof, MostRecentNElementsProof,
+    MultiInclusionProof, NodeHasher, SuffixProof, EMPTY_ROOT, PROOF_FORMAT_VERSION,
+};
+
+#[cfg(feature = "prover")]
+use ads_core::hash::{hash_children, hash_leaf};
+#[cfg(feature = "prover")]
+use content::ContentIndex;
+#[cfg(feature = "prover")]
+use digest::{Digest, Hex};
+#[cfg(feature = "prover")]
+use error::VerificationError;
+#[cfg(feature = "prover")]
+use hashing::HashBackend;
+#[cfg(feature = "prover")]
+use observer::Observers;
+#[cfg(feature = "prover")]
+use ordering::LeafOrdering;
+#[cfg(feature = "prover")]
+use values::LeafValues;
+
+#[cfg(feature = "prover")]
 #[derive(Debug, Clone, PartialEq)]
 pub enum NodeType {
     Internal,
@@ -10,51 +138,49 @@ pub enum NodeType {
 }
 
 // A struct representing a Merkle Tree Node
+#[cfg(feature = "prover")]
 #[derive(Debug, Clone)]
 pub struct MerkleNode {
-    pub hash: Vec<u8>,
+    pub hash: Digest,
     pub node_type: NodeType,
-    pub value: Option<Vec<u8>>, // None for internal nodes, Some for leaf nodes
-    pub left: Option<Box<MerkleNode>>, // None for leaf nodes, Some for internal nodes
-    pub right: Option<Box<MerkleNode>>, // Same as above
-    pub height: usize,
-}
-
-#[derive(Serialize)]
-struct HashPair {
-    left: Vec<u8>,
-    right: Vec<u8>,
+    pub left: Option<usize>, // Index into the tree's node arena. None for leaf nodes
+    pub right: Option<usize>, // Same as above
+    pub height: u64,
 }
 
+#[cfg(feature = "prover")]
 impl MerkleNode {
-    fn new_leaf(value: Vec<u8>) -> Self {
-        // assert!(value.len() == 32);
+    // Leaves only keep the hash of their value. The value itself is stored by the owner of the data.
+    fn new_leaf(hash: Digest) -> Self {
         MerkleNode {
-            hash: value.clone(),
+            hash,
             node_type: NodeType::Leaf,
-            value: Some(value),
             left: None,
             right: None,
             height: 0,
         }
     }
 
-    fn from_children(left: MerkleNode, right: MerkleNode) -> Self {
-        assert!(left.height == right.height);
-        let height = left.height + 1;
-        let bytes = bcs::to_bytes(&HashPair {
-            left: left.hash.clone(),
-            right: right.hash.clone(),
-        })
-        .unwrap();
-        let hash = Blake2b256::digest(&bytes).to_vec();
+    fn from_children(hasher: &NodeHasher, nodes: &[MerkleNode], left: usize, right: usize) -> Self {
+        let hash = hasher.hash_children(&nodes[left].hash, &nodes[right].hash);
+        Self::from_children_with_hash(nodes, left, right, hash)
+    }
+
+    // Same as `from_children` for a hash that was already computed, e.g., as part of a batch
+    fn from_children_with_hash(
+        nodes: &[MerkleNode],
+        left: usize,
+        right: usize,
+        hash: Digest,
+    ) -> Self {
+        assert!(nodes[left].height == nodes[right].height);
+        let height = nodes[left].height + 1;
 
         MerkleNode {
             hash,
             node_type: NodeType::Internal,
-            value: None,
-            left: Some(Box::new(left)),
-            right: Some(Box::new(right)),
+            left: Some(left),
+            right: Some(right),
             height,
         }
     }
@@ -62,85 +188,172 @@ impl MerkleNode {
 
 /// A struct representing a Perfect Binary Merkle Tree, i.e., one storing 2^n leaves.
 /// This is storing the entire tree in heap memory for the PoC. We'd want to optimize this in practice.
+///
+/// Nodes live in a single arena and refer to their children by index, which avoids one heap
+/// allocation per node. The root is always the last node of the arena.
+#[cfg(feature = "prover")]
 #[derive(Debug)]
 pub struct PerfectMerkleTree {
-    pub root: MerkleNode,
+    pub nodes: Vec<MerkleNode>,
+    hasher: NodeHasher,
 }
 
+#[cfg(feature = "prover")]
 impl PerfectMerkleTree {
     pub fn new(data_blocks: Vec<&[u8]>) -> Self {
-        let mut nodes = data_blocks
-            .iter()
-            .map(|&data| MerkleNode::new_leaf(data.to_vec()))
+        Self::new_with_backend(data_blocks, HashBackend::detect())
+    }
+
+    /// Build the tree hashing each level as one batch with the given backend.
+    pub fn new_with_backend(data_blocks: Vec<&[u8]>, backend: HashBackend) -> Self {
+        Self::new_with_hasher(data_blocks, backend, NodeHasher::UNKEYED)
+    }
+
+    /// Build the tree with its leaves and nodes hashed by `hasher`, e.g., keyed.
+    pub fn new_with_hasher(
+        data_blocks: Vec<&[u8]>,
+        backend: HashBackend,
+        hasher: NodeHasher,
+    ) -> Self {
+        let leaves = backend.hash_leaves_batch_with_hasher(&hasher, &data_blocks);
+        Self::from_leaf_hashes_with_hasher(leaves, backend, hasher)
+    }
+
+    /// Build the tree from the hashes of its leaves, e.g., when the values themselves aren't at hand.
+    pub fn from_leaf_hashes(leaves: Vec<Digest>) -> Self {
+        Self::from_leaf_hashes_with_backend(leaves, HashBackend::detect())
+    }
+
+    pub fn from_leaf_hashes_with_backend(leaves: Vec<Digest>, backend: HashBackend) -> Self {
+        Self::from_leaf_hashes_with_hasher(leaves, backend, NodeHasher::UNKEYED)
+    }
+
+    /// Build the tree from leaf hashes computed with `hasher`, which hashes its nodes too.
+    pub fn from_leaf_hashes_with_hasher(
+        leaves: Vec<Digest>,
+        backend: HashBackend,
+        hasher: NodeHasher,
+    ) -> Self {
+        let mut nodes = leaves
+            .into_iter()
+            .map(MerkleNode::new_leaf)
             .collect::<Vec<_>>();
+        let mut level = (0..nodes.len()).collect::<Vec<_>>();
 
-        while nodes.len() > 1 {
-            if nodes.len() % 2 != 0 {
+        while level.len() > 1 {
+            if level.len() % 2 != 0 {
                 // Throw an error
                 panic!(
                     "Not a perfect binary tree! Odd number of nodes at some level ({})",
-                    nodes.len()
+                    level.len()
                 );
             }
 
-            // Note: Do we actually need to clone the nodes?
-            nodes = nodes
+            let pairs: Vec<(Digest, Digest)> = level
+                .chunks(2)
+                .map(|chunk| (nodes[chunk[0]].hash, nodes[chunk[1]].hash))
+                .collect();
+            let hashes = backend.hash_children_batch_with_hasher(&hasher, &pairs);
+
+            level = level
                 .chunks(2)
-                .map(|chunk| MerkleNode::from_children(chunk[0].clone(), chunk[1].clone()))
+                .zip(hashes)
+                .map(|(chunk, hash)| {
+                    let node =
+                        MerkleNode::from_children_with_hash(&nodes, chunk[0], chunk[1], hash);
+                    nodes.push(node);
+                    nodes.len() - 1
+                })
                 .collect();
         }
 
+        PerfectMerkleTree { nodes, hasher }
+    }
+
+    fn leaf(hasher: NodeHasher, value: &[u8]) -> Self {
         PerfectMerkleTree {
-            root: nodes.into_iter().next().unwrap(),
+            nodes: vec![MerkleNode::new_leaf(hasher.hash_leaf(value))],
+            hasher,
         }
     }
 
-    fn height(&self) -> usize {
-        self.root.height
+    // Join two trees of equal height under a new root, `left` holding the earlier leaves
+    fn merge(mut left: PerfectMerkleTree, right: PerfectMerkleTree) -> Self {
+        assert!(
+            left.hasher == right.hasher,
+            "Trees hashed differently can't be merged"
+        );
+        let offset = left.nodes.len();
+        let left_root = offset - 1;
+        left.nodes.extend(right.nodes.into_iter().map(|mut node| {
+            node.left = node.left.map(|i| i + offset);
+            node.right = node.right.map(|i| i + offset);
+            node
+        }));
+        let root =
+            MerkleNode::from_children(&left.hasher, &left.nodes, left_root, left.nodes.len() - 1);
+        left.nodes.push(root);
+        left
+    }
+
+    pub fn root(&self) -> &MerkleNode {
+        self.nodes.last().unwrap()
+    }
+
+    /// How the leaves and nodes are hashed, which proofs about the tree are verified with.
+    pub fn hasher(&self) -> &NodeHasher {
+        &self.hasher
+    }
+
+    fn left(&self, node: &MerkleNode) -> Option<&MerkleNode> {
+        node.left.map(|i| &self.nodes[i])
     }
 
-    pub fn num_leaves(&self) -> usize {
-        2usize.pow(self.height() as u32)
+    fn right(&self, node: &MerkleNode) -> Option<&MerkleNode> {
+        node.right.map(|i| &self.nodes[i])
+    }
+
+    fn height(&self) -> u64 {
+        self.root().height
+    }
+
+    pub fn num_leaves(&self) -> u64 {
+        1u64.checked_shl(self.height() as u32)
+            .expect("Trees have fewer than 2^64 leaves")
     }
 
     pub fn pretty_print(&self) {
-        let mut stack = vec![(0, &self.root, "root".to_string())];
+        let mut stack = vec![(0, self.root(), "root".to_string())];
         while let Some((indent, node, label)) = stack.pop() {
-            // println!("{}{}{}", " ".repeat(indent), hex_string(&node.hash), node.value.as_ref().map_or("".to_string(), |v| format!(" ({})", String::from_utf8_lossy(v))));
-            // Include the label, hash and value in the output
+            // Include the label and hash in the output
             println!(
                 "{}[{}] {}{}",
                 " ".repeat(indent),
                 label,
-                hex_string(&node.hash),
-                node.value.as_ref().map_or("".to_string(), |v| format!(
-                    " ({})",
-                    String::from_utf8_lossy(v)
-                ))
+                node.hash,
+                if node.node_type == NodeType::Leaf {
+                    " (leaf)"
+                } else {
+                    ""
+                }
             );
-            if let Some(right) = &node.right {
+            if let Some(right) = self.right(node) {
                 stack.push((indent + 2, right, "right".to_string()));
             }
-            if let Some(left) = &node.left {
+            if let Some(left) = self.left(node) {
                 stack.push((indent + 2, left, "left".to_string()));
             }
         }
     }
 
-    fn digest(&self) -> &[u8] {
-        &self.root.hash
+    fn digest(&self) -> Digest {
+        self.root().hash
     }
 }
 
-// A struct representing a proof of the most recent n elements in a Perfect Merkle Tree.
-#[derive(Debug, Clone)]
-pub struct SuffixProof {
-    pub num_suffix_elements: usize,
-    pub proof: Vec<Vec<u8>>,
-}
-
+#[cfg(feature = "prover")]
 impl PerfectMerkleTree {
-    pub fn prove_most_recent_n_elements(&self, num_suffix_elements: usize) -> SuffixProof {
+    pub fn prove_most_recent_n_elements(&self, num_suffix_elements: u64) -> SuffixProof {
         assert!(num_suffix_elements > 0);
         assert!(num_suffix_elements <= self.num_leaves());
 
@@ -150,7 +363,7 @@ impl PerfectMerkleTree {
 
         // Recursively collect proof nodes
         self.collect_proof_nodes(
-            &self.root,
+            self.root(),
             0,
             num_leaves,
             first_suffix_index,
@@ -158,20 +371,17 @@ impl PerfectMerkleTree {
             &mut proof_nodes,
         );
 
-        SuffixProof {
-            num_suffix_elements,
-            proof: proof_nodes,
-        }
+        SuffixProof::new(num_leaves, num_suffix_elements, proof_nodes)
     }
 
     fn collect_proof_nodes(
         &self,
         node: &MerkleNode,
-        subtree_start: usize,
-        subtree_size: usize,
-        first_suffix_index: usize,
-        suffix_size: usize,
-        proof_nodes: &mut Vec<Vec<u8>>,
+        subtree_start: u64,
+        subtree_size: u64,
+        first_suffix_index: u64,
+        suffix_size: u64,
+        proof_nodes: &mut Vec<Digest>,
     ) {
         if subtree_size == 1 {
             // This is a leaf
@@ -184,10 +394,10 @@ impl PerfectMerkleTree {
         if first_suffix_index >= mid {
             // Suffix is entirely in right subtree (which contains later elements)
             // Add left subtree to proof
-            if let Some(left) = &node.left {
-                proof_nodes.push(left.hash.clone());
+            if let Some(left) = self.left(node) {
+                proof_nodes.push(left.hash);
             }
-            if let Some(right) = &node.right {
+            if let Some(right) = self.right(node) {
                 self.collect_proof_nodes(
                     right,
                     mid,
@@ -200,10 +410,10 @@ impl PerfectMerkleTree {
         } else if first_suffix_index + suffix_size <= mid {
             // Suffix is entirely in left subtree (which contains earlier elements)
             // Add right subtree to proof
-            if let Some(right) = &node.right {
-                proof_nodes.push(right.hash.clone());
+            if let Some(right) = self.right(node) {
+                proof_nodes.push(right.hash);
             }
-            if let Some(left) = &node.left {
+            if let Some(left) = self.left(node) {
                 self.collect_proof_nodes(
                     left,
                     subtree_start,
@@ -215,7 +425,7 @@ impl PerfectMerkleTree {
             }
         } else {
             // Suffix spans both subtrees
-            if let Some(left) = &node.left {
+            if let Some(left) = self.left(node) {
                 self.collect_proof_nodes(
                     left,
                     subtree_start,
@@ -225,7 +435,7 @@ impl PerfectMerkleTree {
                     proof_nodes,
                 );
             }
-            if let Some(right) = &node.right {
+            if let Some(right) = self.right(node) {
                 self.collect_proof_nodes(
                     right,
                     mid,
@@ -239,73 +449,124 @@ impl PerfectMerkleTree {
     }
 
     pub fn verify_suffix_proof(&self, suffix_elements: &[Vec<u8>], proof: &SuffixProof) {
-        assert_eq!(suffix_elements.len(), proof.num_suffix_elements);
-
-        let num_leaves = self.num_leaves();
-        let first_suffix_index = num_leaves - proof.num_suffix_elements;
+        if let Err(e) = self.try_verify_suffix_proof(suffix_elements, proof) {
+            panic!("{}", e);
+        }
+    }
 
-        // Build up the tree from suffix elements
-        let mut current_hashes = suffix_elements.to_vec();
-        let mut proof_index = proof.proof.len();
-        let mut level_start_index = first_suffix_index;
-        let mut level_size = proof.num_suffix_elements;
+    pub fn try_verify_suffix_proof(
+        &self,
+        suffix_elements: &[Vec<u8>],
+        proof: &SuffixProof,
+    ) -> Result<(), VerificationError> {
+        proof.verify(&self.digest(), self.num_leaves(), suffix_elements)?;
+        Ok(())
+    }
+}
 
-        // Build tree level by level
-        while current_hashes.len() > 1 || level_start_index > 0 {
-            let mut next_level = Vec::new();
-            let mut i = 0;
+#[cfg(feature = "prover")]
+impl PerfectMerkleTree {
+    pub fn prove_inclusion(&self, leaf_index: u64) -> InclusionProof {
+        assert!(leaf_index < self.num_leaves());
+
+        // Walk down from the root, recording the sibling of every node on the path
+        let mut siblings = Vec::with_capacity(self.height() as usize);
+        let mut node = self.root();
+        for level in (0..self.height()).rev() {
+            let (Some(left), Some(right)) = (self.left(node), self.right(node)) else {
+                panic!("Internal node without children");
+            };
+            if (leaf_index >> level) & 1 == 0 {
+                siblings.push(right.hash);
+                node = left;
+            } else {
+                siblings.push(left.hash);
+                node = right;
+            }
+        }
+        siblings.reverse();
 
-            // Check if we need a left sibling from proof
-            if level_start_index % 2 == 1 {
-                // Need left sibling from proof
-                assert!(proof_index > 0, "Not enough proof elements");
-                proof_index -= 1;
-                let left_sibling = &proof.proof[proof_index];
-                let right = &current_hashes[0];
-
-                // Hash them together - match tree construction order
-                let bytes = bcs::to_bytes(&HashPair {
-                    left: left_sibling.clone(), // Left sibling
-                    right: right.clone(),       // Right child (our suffix)
-                })
-                .unwrap();
-                next_level.push(Blake2b256::digest(&bytes).to_vec());
+        InclusionProof::new(leaf_index, siblings)
+    }
 
-                i = 1;
-                level_start_index -= 1;
-            }
+    /// The witness of the leaf at `leaf_index` for arkworks' Merkle tree gadget. The tree must be
+    /// hashed with Poseidon and have at least two leaves.
+    #[cfg(feature = "arkworks")]
+    pub fn arkworks_witness(&self, leaf_index: u64) -> arkworks::ArkworksWitness {
+        assert_eq!(self.hasher.algo(), ads_core::HashAlgo::Poseidon);
+        let leaf_hash = self.node_at(0, leaf_index).hash;
+        let proof = self
+            .prove_inclusion(leaf_index)
+            .to_circuit_from_leaf_hash(&leaf_hash)
+            .expect("Poseidon digests are field elements");
+        arkworks::ArkworksWitness::from_circuit(&proof).expect("Tree has at least two leaves")
+    }
 
-            // Pair up remaining elements (note: tree uses reversed order)
-            while i < current_hashes.len() {
-                if i + 1 < current_hashes.len() {
-                    // Pair two elements - match tree construction order
-                    let bytes = bcs::to_bytes(&HashPair {
-                        left: current_hashes[i].clone(),      // Left child
-                        right: current_hashes[i + 1].clone(), // Right child
-                    })
-                    .unwrap();
-                    next_level.push(Blake2b256::digest(&bytes).to_vec());
-                    i += 2;
-                } else {
-                    // Odd element, carry forward
-                    next_level.push(current_hashes[i].clone());
+    /// Prove the leaves at `leaf_indices` (strictly increasing) at once.
+    pub fn prove_multi_inclusion(&self, leaf_indices: &[u64]) -> MultiInclusionProof {
+        assert!(!leaf_indices.is_empty());
+        assert!(leaf_indices.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(*leaf_indices.last().unwrap() < self.num_leaves());
+
+        // Same traversal as the verifier, recording each node it can't compute
+        let mut siblings = vec![];
+        let mut level = leaf_indices.to_vec();
+        for height in 0..self.height() {
+            let mut next_level = Vec::with_capacity(level.len());
+            let mut i = 0;
+            while i < level.len() {
+                let index = level[i];
+                if index & 1 == 0 && level.get(i + 1) == Some(&(index + 1)) {
                     i += 1;
+                } else {
+                    siblings.push(self.node_at(height, index ^ 1).hash);
                 }
+                next_level.push(index / 2);
+                i += 1;
             }
+            level = next_level;
+        }
+
+        MultiInclusionProof::new(leaf_indices.to_vec(), siblings)
+    }
 
-            current_hashes = next_level;
-            level_start_index /= 2;
-            level_size = (level_size + 1) / 2;
+    // Siblings on the path from the node at `index` among the nodes of the given height up to the
+    // children of the root, bottom-up
+    fn subtree_path(&self, height: u64, index: u64) -> Vec<Digest> {
+        (height..self.height())
+            .map(|level| self.node_at(level, (index >> (level - height)) ^ 1).hash)
+            .collect()
+    }
+
+    // The node at `index` (from the left) among the nodes of the given height
+    fn node_at(&self, height: u64, index: u64) -> &MerkleNode {
+        let mut node = self.root();
+        for level in (height..self.height()).rev() {
+            let (Some(left), Some(right)) = (self.left(node), self.right(node)) else {
+                panic!("Internal node without children");
+            };
+            node = if (index >> (level - height)) & 1 == 0 {
+                left
+            } else {
+                right
+            };
         }
+        node
+    }
 
-        assert_eq!(proof_index, 0, "Not all proof elements were used");
-        assert_eq!(current_hashes.len(), 1, "Should have exactly one root hash");
+    pub fn verify_inclusion_proof(&self, leaf: &[u8], proof: &InclusionProof) {
+        if let Err(e) = self.try_verify_inclusion_proof(leaf, proof) {
+            panic!("{}", e);
+        }
+    }
 
-        // Check that the computed root matches the actual root
-        assert_eq!(
-            current_hashes[0], self.root.hash,
-            "Computed root doesn't match expected root"
-        );
+    pub fn try_verify_inclusion_proof(
+        &self,
+        leaf: &[u8],
+        proof: &InclusionProof,
+    ) -> Result<(), VerificationError> {
+        proof.verify(&self.digest(), self.num_leaves(), leaf)?;
+        Ok(())
     }
 }
 
@@ -317,36 +578,95 @@ impl PerfectMerkleTree {
  * then we will have three PerfectMerkleTrees of leaves 2^0, 2^2 and 2^7 respectively.
  * In particular, trees[0] has 1 leaf, trees[2] has 4 leaves and trees[7] has 128 leaves.
  */
+#[cfg(feature = "prover")]
 #[derive(Debug)]
 pub struct MerkleMountainRange {
-    pub entries: Vec<Vec<u8>>,
+    pub entries: LeafValues,
     pub trees: Vec<Option<PerfectMerkleTree>>,
+    /// Indices of the entries by content, if enabled (see `content`)
+    pub content_index: Option<ContentIndex>,
+    pub(crate) observers: Observers,
+    pub(crate) hasher: NodeHasher,
+    pub(crate) ordering: LeafOrdering,
 }
 
+#[cfg(feature = "prover")]
 impl MerkleMountainRange {
     pub fn new(entries: Vec<&[u8]>) -> Self {
-        let mut mmr = MerkleMountainRange {
-            entries: vec![],
-            trees: vec![None],
-        };
+        Self::new_with_backend(entries, HashBackend::detect())
+    }
 
-        for entry in entries {
-            mmr.add_entry(entry);
+    /// Build the MMR in one go, which lets every tree be hashed level by level in batches.
+    /// The result is identical to adding the entries one at a time.
+    pub fn new_with_backend(entries: Vec<&[u8]>, backend: HashBackend) -> Self {
+        Self::new_with_hasher(entries, backend, NodeHasher::UNKEYED)
+    }
+
+    /// Build the MMR with its leaves and nodes hashed by `hasher`, e.g., keyed so that its
+    /// commitments can't be linked to guessable entries. Entries appended later are hashed the same
+    /// way, and proofs must be verified with the same hasher.
+    pub fn new_with_hasher(entries: Vec<&[u8]>, backend: HashBackend, hasher: NodeHasher) -> Self {
+        let num_entries = entries.len();
+        let num_heights = (usize::BITS - num_entries.leading_zeros()) as usize;
+        let mut trees: Vec<Option<PerfectMerkleTree>> = (0..=num_heights).map(|_| None).collect();
+
+        // Larger trees hold older entries
+        let mut offset = 0;
+        for height in (0..num_heights).rev() {
+            if num_entries & (1 << height) != 0 {
+                let tree_entries = entries[offset..offset + (1 << height)].to_vec();
+                trees[height] = Some(PerfectMerkleTree::new_with_hasher(
+                    tree_entries,
+                    backend,
+                    hasher,
+                ));
+                offset += 1 << height;
+            }
         }
 
-        mmr
+        MerkleMountainRange {
+            entries: entries.into_iter().collect(),
+            trees,
+            content_index: None,
+            observers: Observers::default(),
+            hasher,
+            ordering: LeafOrdering::Append,
+        }
+    }
+
+    /// How the leaves and nodes are hashed, which proofs about the MMR are verified with.
+    pub fn hasher(&self) -> &NodeHasher {
+        &self.hasher
     }
 
+    /// The leaf hash of every entry, in order.
+    pub fn leaf_hashes(&self) -> Vec<Digest> {
+        self.entries
+            .iter()
+            .map(|entry| self.hasher.hash_leaf(entry))
+            .collect()
+    }
+
+    /// Append `entry`, which must be greater than the last entry if the MMR is sorted.
     pub fn add_entry(&mut self, entry: &[u8]) {
-        self.entries.push(entry.to_vec());
+        if let Some(last) = self.entries.len().checked_sub(1) {
+            assert!(
+                self.ordering.allows(self.entries.get(last), entry),
+                "Entries of a sorted MMR must be strictly increasing"
+            );
+        }
+        if let Some(index) = &mut self.content_index {
+            index.insert(entry, self.entries.len() as u64);
+        }
+        self.entries.push(entry);
 
-        let mut i = MerkleNode::new_leaf(entry.to_vec());
+        let mut i = PerfectMerkleTree::leaf(self.hasher, entry);
         for tree in self.trees.iter_mut() {
             if let Some(t) = tree.take() {
-                i = MerkleNode::from_children(t.root, i);
+                i = PerfectMerkleTree::merge(t, i);
             } else {
-                // Make i the root of current tree
-                *tree = Some(PerfectMerkleTree { root: i });
+                // Make i the current tree
+                *tree = Some(i);
                 break;
             };
         }
@@ -354,15 +674,13 @@ impl MerkleMountainRange {
         if self.trees.last().unwrap().is_some() {
             self.trees.push(None);
         }
+        self.notify_observers(entry);
     }
 
     pub fn pretty_print(&self) {
         println!(
             "Entries: {:?}",
-            self.entries
-                .iter()
-                .map(|e| hex_string(e))
-                .collect::<Vec<_>>()
+            self.entries.iter().map(Hex).collect::<Vec<_>>()
         );
         for (i, tree) in self.trees.iter().enumerate() {
             println!("Tree {}", i);
@@ -374,45 +692,120 @@ impl MerkleMountainRange {
         }
     }
 
-    fn digests(&self) -> Vec<Vec<u8>> {
-        let mut digests = vec![];
-        for tree in &self.trees {
-            if let Some(tree) = tree {
-                digests.push(tree.digest().to_vec());
-            } else {
-                digests.push(vec![]);
+    /// The digest of every tree, None where `trees` has no tree.
+    pub fn digests(&self) -> Vec<Option<Digest>> {
+        self.trees
+            .iter()
+            .map(|tree| tree.as_ref().map(|tree| tree.digest()))
+            .collect()
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// The commitment proofs about this MMR are verified against.
+    pub fn commitment(&self) -> MmrCommitment {
+        MmrCommitment::from_digests(&self.digests())
+            .with_hash_algo(self.hasher.algo())
+            .with_ordering(self.ordering)
+    }
+}
+
+/// Entries are folded into the trees as they arrive, `COLLECT_CHUNK_SIZE` at a time so that each
+/// chunk is hashed in batches as by `new`. The MMR keeps every entry and node: to hold only the
+/// peaks in memory, collect into an `MmrAppender` or append to a `StoredMmr` on disk.
+#[cfg(feature = "prover")]
+impl<T: AsRef<[u8]>> FromIterator<T> for MerkleMountainRange {
+    fn from_iter<I: IntoIterator<Item = T>>(entries: I) -> Self {
+        let backend = HashBackend::detect();
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        let mut entries = entries.into_iter();
+        let mut chunk = Vec::with_capacity(COLLECT_CHUNK_SIZE);
+        loop {
+            chunk.extend(entries.by_ref().take(COLLECT_CHUNK_SIZE));
+            if chunk.is_empty() {
+                return mmr;
+            }
+            mmr.append_chunk(chunk.iter().map(AsRef::as_ref).collect(), backend);
+            chunk.clear();
+        }
+    }
+}
+
+/// Number of entries `from_iter` hashes at once. Only the last chunk can be smaller, so every
+/// chunk starts at a multiple of its largest tree.
+#[cfg(feature = "prover")]
+const COLLECT_CHUNK_SIZE: usize = 1024;
+
+#[cfg(feature = "prover")]
+impl MerkleMountainRange {
+    // Append a tree per power of two in the number of entries, largest first, each hashed in
+    // batches, merging it with the trees it completes. The MMR must hold no tree smaller than the
+    // largest, which is the case when its number of entries is a multiple of that tree's.
+    fn append_chunk(&mut self, entries: Vec<&[u8]>, backend: HashBackend) {
+        let mut offset = 0;
+        for height in (0..usize::BITS as usize).rev() {
+            if entries.len() & (1 << height) == 0 {
+                continue;
+            }
+            let tree_entries = &entries[offset..offset + (1 << height)];
+            offset += 1 << height;
+            for entry in tree_entries {
+                self.entries.push(entry);
+            }
+            let mut tree =
+                PerfectMerkleTree::new_with_hasher(tree_entries.to_vec(), backend, self.hasher);
+            for level in height.. {
+                if self.trees.len() <= level {
+                    self.trees.resize_with(level + 1, || None);
+                }
+                match self.trees[level].take() {
+                    Some(older) => tree = PerfectMerkleTree::merge(older, tree),
+                    None => {
+                        self.trees[level] = Some(tree);
+                        break;
+                    }
+                }
             }
         }
-        digests
+        if self.trees.last().unwrap().is_some() {
+            self.trees.push(None);
+        }
     }
 }
 
-/// The most recent n elements proof contains some full trees and at most one partial tree.
-pub struct MostRecentNElementsProof {
-    pub entries: Vec<Vec<u8>>,
-    // Indices of trees that contain all the elements in the proof
-    pub full_tree_indices: Vec<usize>,
-    // If N is an exact span of some trees, then this is None.
-    pub partial_tree_proof: Option<(usize, SuffixProof)>,
+/// Entries are added one at a time, as by `add_entry`.
+#[cfg(feature = "prover")]
+impl<T: AsRef<[u8]>> Extend<T> for MerkleMountainRange {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, entries: I) {
+        for entry in entries {
+            self.add_entry(entry.as_ref());
+        }
+    }
 }
 
+#[cfg(feature = "prover")]
 impl MerkleMountainRange {
     pub fn prove_most_recent_n_elements(
         &self,
-        num_suffix_elements: usize,
+        num_suffix_elements: u64,
     ) -> MostRecentNElementsProof {
-        assert!(num_suffix_elements <= self.entries.len());
+        assert!(num_suffix_elements <= self.num_entries());
 
         // Take the LAST num_suffix_elements from entries (most recent)
-        let start_index = self.entries.len() - num_suffix_elements;
-        let suffix_entries = self.entries[start_index..].to_vec();
+        let start_index = self.entries.len() - num_suffix_elements as usize;
+        let suffix_entries = self.entries.to_vecs(start_index..self.entries.len());
 
         let mut remaining_elements = num_suffix_elements;
         let mut proof = MostRecentNElementsProof {
+            version: PROOF_FORMAT_VERSION,
             entries: suffix_entries,
             full_tree_indices: vec![],
             partial_tree_proof: None,
-        };
+            hash_algo: None,
+        }
+        .with_hash_algo(self.hasher.algo());
 
         // Iterate trees from smallest to largest (they contain most recent to oldest)
         for (tree_index, tree) in self.trees.iter().enumerate() {
@@ -422,11 +815,11 @@ impl MerkleMountainRange {
             if let Some(tree) = tree {
                 if tree.num_leaves() <= remaining_elements {
                     remaining_elements -= tree.num_leaves();
-                    proof.full_tree_indices.push(tree_index);
+                    proof.full_tree_indices.push(tree_index as u64);
                 } else {
                     // Need partial proof from this tree
                     proof.partial_tree_proof = Some((
-                        tree_index,
+                        tree_index as u64,
                         tree.prove_most_recent_n_elements(remaining_elements),
                     ));
                     return proof;
@@ -438,102 +831,179 @@ impl MerkleMountainRange {
     }
 
     pub fn verify_most_recent_n_elements(&self, proof: &MostRecentNElementsProof) {
-        // Check that provided entries are non-empty
-        assert!(!proof.entries.is_empty(), "Proof entries cannot be empty");
-
-        let num_suffix_elements = proof.entries.len();
-        let mut total_leaves_covered = 0;
+        if let Err(e) = self.try_verify_most_recent_n_elements(proof) {
+            panic!("{}", e);
+        }
+    }
 
-        // First, handle partial tree if present (it contains the oldest elements)
-        let mut entry_offset = 0;
-        if let Some((tree_index, ref suffix_proof)) = proof.partial_tree_proof {
-            assert!(
-                tree_index < self.trees.len(),
-                "Tree index {} out of bounds",
-                tree_index
-            );
+    pub fn try_verify_most_recent_n_elements(
+        &self,
+        proof: &MostRecentNElementsProof,
+    ) -> Result<(), VerificationError> {
+        self.commitment()
+            .verify_most_recent_n_elements_with_hasher(&self.hasher, proof)
+    }
+}
 
-            let Some(tree) = &self.trees[tree_index] else {
-                panic!("Tree at index {} doesn't exist", tree_index);
-            };
+#[cfg(feature = "prover")]
+impl MerkleMountainRange {
+    /// Returns (tree index, leaf index within that tree) for the entry at `index`.
+    fn locate(&self, index: u64) -> (usize, u64) {
+        assert!(index < self.num_entries(), "Index {} out of bounds", index);
 
-            let partial_elements = suffix_proof.num_suffix_elements;
-            total_leaves_covered += partial_elements;
+        // Larger trees hold older entries, so walk from the largest tree down
+        let mut offset = 0;
+        for (tree_index, tree) in self.trees.iter().enumerate().rev() {
+            if let Some(tree) = tree {
+                if index < offset + tree.num_leaves() {
+                    return (tree_index, index - offset);
+                }
+                offset += tree.num_leaves();
+            }
+        }
+        unreachable!("Entries and trees are out of sync");
+    }
 
-            // Partial tree gets the first (oldest) elements
-            assert!(
-                partial_elements <= proof.entries.len(),
-                "Partial tree entries out of bounds"
-            );
-            let tree_entries = &proof.entries[0..partial_elements];
+    pub fn prove_inclusion(&self, index: u64) -> MmrInclusionProof {
+        let (tree_index, leaf_index) = self.locate(index);
+        let tree = self.trees[tree_index].as_ref().unwrap();
+        MmrInclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            entry: self.entries.get(index as usize).to_vec(),
+            tree_index: tree_index as u64,
+            proof: tree.prove_inclusion(leaf_index),
+            hash_algo: None,
+        }
+        .with_hash_algo(self.hasher.algo())
+    }
 
-            tree.verify_suffix_proof(tree_entries, suffix_proof);
+    /// Prove the entries at `indices` (strictly increasing) at once.
+    pub fn prove_multi_inclusion(&self, indices: &[u64]) -> MmrMultiInclusionProof {
+        assert!(!indices.is_empty());
+        assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Indices are increasing, so their trees come from the oldest to the most recent
+        let mut by_tree: Vec<(usize, Vec<u64>)> = vec![];
+        for &index in indices {
+            let (tree_index, leaf_index) = self.locate(index);
+            match by_tree.last_mut() {
+                Some((last, leaf_indices)) if *last == tree_index => leaf_indices.push(leaf_index),
+                _ => by_tree.push((tree_index, vec![leaf_index])),
+            }
+        }
 
-            entry_offset = partial_elements;
+        MmrMultiInclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            entries: indices
+                .iter()
+                .map(|&index| self.entries.get(index as usize).to_vec())
+                .collect(),
+            tree_proofs: by_tree
+                .into_iter()
+                .map(|(tree_index, leaf_indices)| {
+                    let tree = self.trees[tree_index].as_ref().unwrap();
+                    (tree_index as u64, tree.prove_multi_inclusion(&leaf_indices))
+                })
+                .collect(),
+            hash_algo: None,
         }
+        .with_hash_algo(self.hasher.algo())
+    }
 
-        // Then process full trees from largest index to smallest
-        // (from oldest to most recent in terms of data)
-        for &tree_index in proof.full_tree_indices.iter().rev() {
-            assert!(
-                tree_index < self.trees.len(),
-                "Tree index {} out of bounds",
-                tree_index
-            );
+    /// Prove that this MMR extends its first `old_num_entries` entries, i.e., the MMR it was when
+    /// it held that many.
+    pub fn prove_consistency(&self, old_num_entries: u64) -> ConsistencyProof {
+        assert!(old_num_entries <= self.num_entries());
 
-            let Some(tree) = &self.trees[tree_index] else {
-                panic!("Tree at index {} doesn't exist", tree_index);
-            };
+        prove_consistency_with(old_num_entries, |first_entry, height| {
+            let (tree_index, leaf_index) = self.locate(first_entry);
+            let tree = self.trees[tree_index].as_ref().unwrap();
+            Some(tree.subtree_path(height, leaf_index >> height))
+        })
+        .expect("Every entry of the older MMR is in this one")
+    }
 
-            let tree_leaves = tree.num_leaves();
-            total_leaves_covered += tree_leaves;
+    /// The commitment to the MMR this was when it held its first `num_entries` entries.
+    pub fn commitment_at(&self, num_entries: u64) -> MmrCommitment {
+        assert!(num_entries <= self.num_entries());
 
-            // Get the entries for this tree
-            let tree_entries_end = entry_offset + tree_leaves;
-            assert!(
-                tree_entries_end <= proof.entries.len(),
-                "Tree entries out of bounds"
-            );
-            let tree_entries = &proof.entries[entry_offset..tree_entries_end];
-            entry_offset = tree_entries_end;
+        // Each tree of the older MMR is a subtree of the tree holding its first entry
+        let mut peaks = vec![];
+        let mut first_entry = 0;
+        for height in (0..u64::BITS as u64).rev() {
+            if num_entries & (1 << height) == 0 {
+                continue;
+            }
+            let (tree_index, leaf_index) = self.locate(first_entry);
+            let tree = self.trees[tree_index].as_ref().unwrap();
+            peaks.push(tree.node_at(height, leaf_index >> height).hash);
+            first_entry += 1 << height;
+        }
+        MmrCommitment::new(num_entries, peaks)
+            .expect("One peak per tree")
+            .with_hash_algo(self.hasher.algo())
+            .with_ordering(self.ordering)
+    }
 
-            // Reconstruct and verify root for full tree
-            let tree_entries_refs: Vec<&[u8]> = tree_entries.iter().map(|e| e.as_slice()).collect();
+    /// Prove which entries were appended to the MMR of the first `old_num_entries` entries to make
+    /// the one of the first `new_num_entries`, see `commitment_at`.
+    pub fn prove_appended_range(
+        &self,
+        old_num_entries: u64,
+        new_num_entries: u64,
+    ) -> AppendedRangeProof {
+        assert!(old_num_entries <= new_num_entries && new_num_entries <= self.num_entries());
+        AppendedRangeProof::new(
+            self.entries
+                .to_vecs(old_num_entries as usize..new_num_entries as usize),
+        )
+    }
 
-            let reconstructed = PerfectMerkleTree::new(tree_entries_refs);
-            assert_eq!(
-                reconstructed.digest(),
-                tree.digest(),
-                "Reconstructed tree digest doesn't match expected"
-            );
+    pub fn verify_inclusion(&self, proof: &MmrInclusionProof) {
+        if let Err(e) = self.try_verify_inclusion(proof) {
+            panic!("{}", e);
         }
+    }
 
-        // Check that all entries were accounted for
-        assert_eq!(
-            total_leaves_covered, num_suffix_elements,
-            "Not all entries were accounted for"
-        );
+    pub fn try_verify_inclusion(&self, proof: &MmrInclusionProof) -> Result<(), VerificationError> {
+        self.commitment()
+            .verify_inclusion_with_hasher(&self.hasher, proof)
+    }
+
+    pub fn try_verify_multi_inclusion(
+        &self,
+        proof: &MmrMultiInclusionProof,
+    ) -> Result<(), VerificationError> {
+        self.commitment()
+            .verify_multi_inclusion_with_hasher(&self.hasher, proof)
     }
 }
 
 ////// Helper functions
 
-// Print hash in hex format
-fn hex_string(bytes: &[u8]) -> String {
-    bytes
-        .iter()
-        .map(|byte| format!("{:02x}", byte))
-        .collect::<String>()
+// The consistency proof of an MMR with the one of its first `old_num_entries` entries. Each tree of
+// the older MMR, from the largest, is a subtree of the tree holding its first entry, and
+// `subtree_path(first_entry, height)` gives the siblings (bottom-up) from that subtree to the root
+// of its tree, None if they can't be found.
+#[cfg(feature = "prover")]
+pub(crate) fn prove_consistency_with(
+    old_num_entries: u64,
+    mut subtree_path: impl FnMut(u64, u64) -> Option<Vec<Digest>>,
+) -> Option<ConsistencyProof> {
+    let mut paths = vec![];
+    let mut first_entry = 0;
+    for height in (0..u64::BITS as u64).rev() {
+        if old_num_entries & (1 << height) == 0 {
+            continue;
+        }
+        paths.push(subtree_path(first_entry, height)?);
+        first_entry += 1 << height;
+    }
+    Some(ConsistencyProof::new(paths))
 }
 
-// ceil(log_2) + 1
-fn num_trees(n: usize) -> usize {
-    let x = (n as f64).log2().ceil() as usize;
-
-    // if n is a power of 2, then return x + 1 else return x
-    if n == 2usize.pow(x as u32) {
-        x + 2
-    } else {
-        x + 1
-    }
+// Length of `trees` for n entries: one slot per bit of n, plus the one a carry would fill
+#[cfg(all(test, feature = "prover"))]
+fn num_trees(n: u64) -> usize {
+    (u64::BITS - n.leading_zeros()) as usize + 1
 }
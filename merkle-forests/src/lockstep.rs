@@ -0,0 +1,147 @@
+//! An MMR and a skip list kept in lockstep over the same entries.
+//!
+//! A `LockstepLog` appends every entry to its MMR and the entry's leaf hash to its skip list in the
+//! same call, so the two always describe the same log: the skip list node at height `i + 1` holds
+//! the leaf hash of MMR entry `i`. A `CrossProof` shows this for one entry against both
+//! commitments at once, e.g., the latest entry after each append, which lets clients move from one
+//! structure to the other while checking that they never diverge.
+
+use ads_core::Proof;
+use serde::{Deserialize, Serialize};
+pub use skip_lists::{HeadCommitment, SkipList, SkipListProof};
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::hashing::HashBackend;
+use crate::{MerkleMountainRange, MmrCommitment, MmrInclusionProof, NodeHasher};
+
+/// The commitments of both structures to the same log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DualCommitment {
+    pub mmr: MmrCommitment,
+    pub skip_list: HeadCommitment,
+}
+
+/// Proof that an entry is at the same index in both structures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossProof {
+    pub mmr: MmrInclusionProof,
+    /// The skip list node holding the entry's leaf hash
+    pub skip_list: SkipListProof<Digest>,
+}
+
+impl CrossProof {
+    /// Verify against both commitments and return the index of the entry.
+    pub fn verify(&self, commitment: &DualCommitment) -> Result<u64, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::new(commitment.mmr.hash_algo()), commitment)
+    }
+
+    /// Same as `verify`, for a log hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        commitment: &DualCommitment,
+    ) -> Result<u64, VerificationError> {
+        let DualCommitment { mmr, skip_list } = commitment;
+        // Nodes are at heights 1 to the number of entries
+        if skip_list.height != mmr.num_entries() {
+            return Err(VerificationError::WrongNumberOfEntries {
+                expected: mmr.num_entries(),
+                actual: skip_list.height,
+            });
+        }
+        mmr.verify_inclusion_with_hasher(hasher, &self.mmr)?;
+        self.skip_list.verify(skip_list, &())?;
+
+        // The MMR proof verified, so its leaf exists
+        let index = mmr
+            .entry_index(self.mmr.tree_index, self.mmr.proof.leaf_index)
+            .unwrap();
+        let node = &self.skip_list.node;
+        if node.height != index + 1 || node.value != hasher.hash_leaf(&self.mmr.entry) {
+            return Err(VerificationError::EntryMismatch(index));
+        }
+        Ok(index)
+    }
+}
+
+impl Proof for CrossProof {
+    type Commitment = DualCommitment;
+    type Claim = ();
+    type Output = u64;
+    type Error = VerificationError;
+
+    fn verify(&self, commitment: &DualCommitment, _: &()) -> Result<u64, VerificationError> {
+        CrossProof::verify(self, commitment)
+    }
+
+    fn size_hint(&self) -> usize {
+        self.mmr.size_hint() + self.skip_list.size_hint()
+    }
+}
+
+/// An MMR and a skip list appended to together.
+#[derive(Debug)]
+pub struct LockstepLog {
+    mmr: MerkleMountainRange,
+    skip_list: SkipList<Digest>,
+}
+
+impl LockstepLog {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// A log whose MMR hashes with `hasher`, which also computes the leaf hashes in the skip list.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        LockstepLog {
+            mmr: MerkleMountainRange::new_with_hasher(vec![], HashBackend::detect(), hasher),
+            skip_list: SkipList::new(),
+        }
+    }
+
+    pub fn append(&mut self, entry: &[u8]) {
+        let leaf_hash = self.mmr.hasher.hash_leaf(entry);
+        self.mmr.add_entry(entry);
+        self.skip_list.add(leaf_hash);
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.mmr.num_entries()
+    }
+
+    pub fn mmr(&self) -> &MerkleMountainRange {
+        &self.mmr
+    }
+
+    pub fn skip_list(&self) -> &SkipList<Digest> {
+        &self.skip_list
+    }
+
+    pub fn commitment(&self) -> DualCommitment {
+        DualCommitment {
+            mmr: self.mmr.commitment(),
+            skip_list: self.skip_list.commitment(),
+        }
+    }
+
+    /// Prove that the entry at `index` is the same in both structures.
+    pub fn prove_cross(&self, index: u64) -> CrossProof {
+        CrossProof {
+            mmr: self.mmr.prove_inclusion(index),
+            skip_list: self.skip_list.prove(index + 1),
+        }
+    }
+
+    /// Prove the latest entry, binding the commitments after an append. None if the log is empty.
+    pub fn prove_latest(&self) -> Option<CrossProof> {
+        let index = self.num_entries().checked_sub(1)?;
+        Some(self.prove_cross(index))
+    }
+}
+
+impl Default for LockstepLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
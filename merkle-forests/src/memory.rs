@@ -0,0 +1,44 @@
+//! Estimates of the heap memory held by the data structures.
+//!
+//! Sizes are computed from allocated capacities rather than lengths, so they reflect what the
+//! allocator actually handed out (modulo its own bookkeeping).
+
+use std::mem::size_of;
+use std::ops::Add;
+
+pub use ads_core::MemoryUsage;
+
+use crate::content::ContentIndex;
+use crate::lazy::LazyMerkleMountainRange;
+use crate::{MerkleMountainRange, MerkleNode, PerfectMerkleTree};
+
+impl PerfectMerkleTree {
+    pub fn memory_usage(&self) -> MemoryUsage {
+        // Hashes are stored inline in the nodes, and leaves only hold the hashes of their values
+        MemoryUsage {
+            nodes: self.nodes.capacity() * size_of::<MerkleNode>(),
+            ..Default::default()
+        }
+    }
+}
+
+impl MerkleMountainRange {
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let trees = MemoryUsage {
+            auxiliary: self.trees.capacity() * size_of::<Option<PerfectMerkleTree>>(),
+            ..Default::default()
+        };
+        self.trees
+            .iter()
+            .flatten()
+            .map(PerfectMerkleTree::memory_usage)
+            .chain(self.content_index.as_ref().map(ContentIndex::memory_usage))
+            .fold(trees + self.entries.memory_usage(), Add::add)
+    }
+}
+
+impl LazyMerkleMountainRange {
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.mmr.memory_usage()
+    }
+}
@@ -0,0 +1,54 @@
+//! Observers notified of every entry added to an MMR.
+//!
+//! Each append reports the entry's index, its leaf hash and the MMR's commitment after the append.
+//! Computing the commitment costs a few digest copies per append, and is skipped while no observer
+//! is registered.
+
+use std::fmt;
+
+pub use ads_core::AppendObserver;
+
+use crate::{MerkleMountainRange, MmrCommitment};
+
+type BoxedObserver = Box<dyn AppendObserver<MmrCommitment> + Send + Sync>;
+
+// The observers of an MMR, which only shows their number when debugging
+#[derive(Default)]
+pub(crate) struct Observers(Vec<BoxedObserver>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} observers", self.0.len())
+    }
+}
+
+// Observers are only ever called through `&mut`, so an MMR shared across `catch_unwind` can't
+// observe one that panicked halfway
+impl std::panic::RefUnwindSafe for Observers {}
+
+impl MerkleMountainRange {
+    /// Call `observer` after every append from now on.
+    pub fn add_observer(
+        &mut self,
+        observer: impl AppendObserver<MmrCommitment> + Send + Sync + 'static,
+    ) {
+        self.observers.0.push(Box::new(observer));
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.0.clear();
+    }
+
+    // Report the entry that was just appended
+    pub(crate) fn notify_observers(&mut self, entry: &[u8]) {
+        if self.observers.0.is_empty() {
+            return;
+        }
+        let index = self.entries.len() as u64 - 1;
+        let leaf_hash = self.hasher.hash_leaf(entry);
+        let commitment = self.commitment();
+        for observer in &mut self.observers.0 {
+            observer.on_append(index, &leaf_hash, &commitment);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! MMRs over a set, with sorted entries that non-membership can be proven against.
+//!
+//! The ordering is chosen when the MMR is built and recorded in its commitment. A sorted MMR only
+//! accepts appends greater than its last entry, so sets are best built in one go, from their
+//! elements in any order.
+
+pub use ads_verifier::ordering::{LeafOrdering, NonMembershipProof};
+
+use crate::hashing::HashBackend;
+use crate::{MerkleMountainRange, NodeHasher};
+
+impl MerkleMountainRange {
+    /// Build the MMR with its entries ordered as `ordering` says: as given, or sorted with
+    /// duplicates removed.
+    pub fn new_with_ordering(entries: Vec<&[u8]>, ordering: LeafOrdering) -> Self {
+        Self::new_with_ordering_and_hasher(
+            entries,
+            ordering,
+            HashBackend::detect(),
+            NodeHasher::UNKEYED,
+        )
+    }
+
+    /// Same as `new_with_ordering`, hashing with `hasher`, see `new_with_hasher`.
+    pub fn new_with_ordering_and_hasher(
+        mut entries: Vec<&[u8]>,
+        ordering: LeafOrdering,
+        backend: HashBackend,
+        hasher: NodeHasher,
+    ) -> Self {
+        if ordering == LeafOrdering::Sorted {
+            entries.sort_unstable();
+            entries.dedup();
+        }
+        let mut mmr = Self::new_with_hasher(entries, backend, hasher);
+        mmr.ordering = ordering;
+        mmr
+    }
+
+    /// How the entries are ordered, as recorded in the commitment.
+    pub fn ordering(&self) -> LeafOrdering {
+        self.ordering
+    }
+
+    /// Prove that `value` isn't an entry, None if it is. The MMR must be sorted.
+    pub fn prove_non_membership(&self, value: &[u8]) -> Option<NonMembershipProof> {
+        assert_eq!(
+            self.ordering,
+            LeafOrdering::Sorted,
+            "Non-membership can only be proven in a sorted MMR"
+        );
+        // Index of the first entry greater than `value`
+        let mut low = 0;
+        let mut high = self.entries.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.entries.get(mid).cmp(value) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Equal => return None,
+                std::cmp::Ordering::Greater => high = mid,
+            }
+        }
+        let neighbours: Vec<u64> = (low.saturating_sub(1)..(low + 1).min(self.entries.len()))
+            .map(|index| index as u64)
+            .collect();
+        Some(NonMembershipProof::new(
+            (!neighbours.is_empty()).then(|| self.prove_multi_inclusion(&neighbours)),
+        ))
+    }
+}
@@ -0,0 +1,171 @@
+//! Export of inclusion proofs as OpenTimestamps-style operation chains.
+//!
+//! An OTS timestamp is a chain of operations (append, prepend, hash) that takes a message to a
+//! digest which is itself attested somewhere (e.g., in a Bitcoin block). An MMR inclusion proof
+//! maps onto such a chain directly: the entry is hashed into its leaf, then every level of the path
+//! prepends/appends the sibling (with the prefixes used by `hash_children`) and hashes. Stamping a
+//! peak digest with OTS and prefixing its timestamp with the chain of an entry yields a timestamp
+//! for that entry.
+//!
+//! Note that OTS clients only understand a fixed set of hash operations (SHA-256, Keccak-256, ...),
+//! so only proofs about MMRs hashed with `HashAlgo::Sha256` or `HashAlgo::Keccak256` can be
+//! exported. Blake2b256, the default, has no OTS op. Neither can proofs about keyed MMRs: their
+//! hashes take a secret that no OTS op does.
+
+use ads_core::encoding::{LEAF_PREFIX, NODE_PREFIX};
+use ads_core::{HashAlgo, NodeHasher};
+use fastcrypto::hash::{HashFunction, Keccak256, Sha256};
+
+use crate::digest::DIGEST_LENGTH;
+use crate::MmrInclusionProof;
+
+// Op tags as defined by the OpenTimestamps serialization format
+const TAG_APPEND: u8 = 0xf0;
+const TAG_PREPEND: u8 = 0xf1;
+const TAG_SHA256: u8 = 0x08;
+const TAG_KECCAK256: u8 = 0x67;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OtsOp {
+    Append(Vec<u8>),
+    Prepend(Vec<u8>),
+    Sha256,
+    Keccak256,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OtsError {
+    // The proof's hash function has no OTS op
+    UnsupportedHashAlgo(HashAlgo),
+    KeyedHasher,
+    HashAlgoMismatch {
+        expected: HashAlgo,
+        actual: HashAlgo,
+    },
+}
+
+impl OtsOp {
+    pub fn apply(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            OtsOp::Append(suffix) => [msg, suffix.as_slice()].concat(),
+            OtsOp::Prepend(prefix) => [prefix.as_slice(), msg].concat(),
+            OtsOp::Sha256 => Sha256::digest(msg).to_vec(),
+            OtsOp::Keccak256 => Keccak256::digest(msg).to_vec(),
+        }
+    }
+
+    fn serialize_into(&self, out: &mut Vec<u8>) {
+        match self {
+            OtsOp::Append(arg) | OtsOp::Prepend(arg) => {
+                out.push(if let OtsOp::Append(_) = self {
+                    TAG_APPEND
+                } else {
+                    TAG_PREPEND
+                });
+                out.extend(uleb128(arg.len()));
+                out.extend(arg);
+            }
+            OtsOp::Sha256 => out.push(TAG_SHA256),
+            OtsOp::Keccak256 => out.push(TAG_KECCAK256),
+        }
+    }
+}
+
+/// A linear chain of operations taking `msg` to a digest (an MMR peak for exported proofs).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtsChain {
+    pub msg: Vec<u8>,
+    pub ops: Vec<OtsOp>,
+}
+
+impl OtsChain {
+    /// Apply all operations to the message, returning the resulting digest.
+    pub fn evaluate(&self) -> Vec<u8> {
+        self.ops
+            .iter()
+            .fold(self.msg.clone(), |msg, op| op.apply(&msg))
+    }
+
+    /// Serialize the operations in the OTS binary format (without the attestation).
+    pub fn serialize_ops(&self) -> Vec<u8> {
+        let mut out = vec![];
+        for op in &self.ops {
+            op.serialize_into(&mut out);
+        }
+        out
+    }
+
+    /// Prefix an OTS timestamp of the final digest (e.g., the timestamp of an MMR peak as returned
+    /// by a calendar server) with this chain, yielding a timestamp for `msg`.
+    pub fn prepend_to_timestamp(&self, digest_timestamp: &[u8]) -> Vec<u8> {
+        let mut out = self.serialize_ops();
+        out.extend(digest_timestamp);
+        out
+    }
+}
+
+impl OtsChain {
+    /// Express an inclusion proof as an operation chain from the entry to the digest of its tree.
+    /// Fails if the MMR is hashed with a function OTS has no op for.
+    pub fn from_inclusion_proof(proof: &MmrInclusionProof) -> Result<Self, OtsError> {
+        Self::from_inclusion_proof_with_hasher(&NodeHasher::new(proof.hash_algo()), proof)
+    }
+
+    /// As `from_inclusion_proof`, for a proof about an MMR hashed by `hasher`. Fails if `hasher` is
+    /// keyed or doesn't use the proof's algorithm.
+    pub fn from_inclusion_proof_with_hasher(
+        hasher: &NodeHasher,
+        proof: &MmrInclusionProof,
+    ) -> Result<Self, OtsError> {
+        if hasher.is_keyed() {
+            return Err(OtsError::KeyedHasher);
+        }
+        if hasher.algo() != proof.hash_algo() {
+            return Err(OtsError::HashAlgoMismatch {
+                expected: proof.hash_algo(),
+                actual: hasher.algo(),
+            });
+        }
+        let hash_op = match proof.hash_algo() {
+            HashAlgo::Sha256 => OtsOp::Sha256,
+            HashAlgo::Keccak256 => OtsOp::Keccak256,
+            algo => return Err(OtsError::UnsupportedHashAlgo(algo)),
+        };
+        // Mirrors `hash_leaf`: LEAF_PREFIX || entry
+        let mut ops = vec![OtsOp::Prepend(vec![LEAF_PREFIX]), hash_op.clone()];
+        let digest_prefix = uleb128(DIGEST_LENGTH);
+        for (level, sibling) in proof.proof.siblings.iter().enumerate() {
+            // Mirrors `hash_children`: NODE_PREFIX || uleb(32) || left || uleb(32) || right
+            let encoded_sibling = [digest_prefix.as_slice(), &sibling.0].concat();
+            if (proof.proof.leaf_index >> level) & 1 == 0 {
+                ops.push(OtsOp::Prepend(
+                    [vec![NODE_PREFIX], digest_prefix.clone()].concat(),
+                ));
+                ops.push(OtsOp::Append(encoded_sibling));
+            } else {
+                ops.push(OtsOp::Prepend(
+                    [vec![NODE_PREFIX], encoded_sibling, digest_prefix.clone()].concat(),
+                ));
+            }
+            ops.push(hash_op.clone());
+        }
+        Ok(OtsChain {
+            msg: proof.entry.clone(),
+            ops,
+        })
+    }
+}
+
+// Unsigned LEB128, used both by BCS for length prefixes and by OTS for varuints
+fn uleb128(mut n: usize) -> Vec<u8> {
+    let mut out = vec![];
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
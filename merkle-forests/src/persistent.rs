@@ -0,0 +1,534 @@
+//! MMRs whose versions share their nodes.
+//!
+//! `MerkleMountainRange` merges trees by moving their nodes into one arena, so an older version
+//! can only be kept by copying everything. Here nodes are immutable and linked by `Arc`: appending
+//! only creates the new leaf and the parents it completes, O(log n) nodes, and every other node is
+//! shared with the version before. Cloning a `PersistentMmr` is cloning its peaks, so a writer can
+//! hand out versions to concurrent readers, each proving against its own commitment, while it
+//! keeps appending. Entries can also be updated in place, copying only the nodes on the path to
+//! the entry, so that the version before keeps its root; the log is then no longer append-only,
+//! and consistency proofs between the two don't hold.
+//!
+//! A `VersionedMmr` keeps the versions it commits, numbered from 0, so that entries can be proven
+//! as of any of them, against the commitment published then, until they're pruned. Committing
+//! writes the nodes the version created to a `NodeStore`, each at a position of its own, in one
+//! batch. Pruning deletes the positions of the nodes no retained version reaches, and drops them
+//! from memory.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use ads_core::NodeHasher;
+use ads_store::{BatchOp, MemoryStore, NodeStore};
+
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::{
+    prove_consistency_with, ConsistencyProof, InclusionProof, MmrCommitment, MmrInclusionProof,
+    PROOF_FORMAT_VERSION,
+};
+
+#[derive(Debug)]
+enum Node {
+    Leaf {
+        hash: Digest,
+        entry: Vec<u8>,
+    },
+    Internal {
+        hash: Digest,
+        left: Arc<Node>,
+        right: Arc<Node>,
+    },
+}
+
+impl Node {
+    fn hash(&self) -> &Digest {
+        match self {
+            Node::Leaf { hash, .. } | Node::Internal { hash, .. } => hash,
+        }
+    }
+}
+
+/// A node of a `VersionedMmr` as written to its store, with the positions of its children.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoredNode {
+    Leaf { hash: Digest, entry: Vec<u8> },
+    Internal { hash: Digest, left: u64, right: u64 },
+}
+
+impl StoredNode {
+    pub fn hash(&self) -> &Digest {
+        match self {
+            StoredNode::Leaf { hash, .. } | StoredNode::Internal { hash, .. } => hash,
+        }
+    }
+
+    /// Bytes of its fields: the hash, and the entry of a leaf or the positions of the children.
+    pub fn size(&self) -> usize {
+        match self {
+            StoredNode::Leaf { entry, .. } => DIGEST_LENGTH + entry.len(),
+            StoredNode::Internal { .. } => DIGEST_LENGTH + 2 * std::mem::size_of::<u64>(),
+        }
+    }
+}
+
+/// A version of an MMR. Appending to it leaves its clones as they were.
+#[derive(Debug, Clone)]
+pub struct PersistentMmr {
+    // (height, root) of each tree, the largest (oldest) first
+    peaks: Vec<(u64, Arc<Node>)>,
+    num_entries: u64,
+    hasher: NodeHasher,
+}
+
+impl PersistentMmr {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// An empty MMR hashed with `hasher`, which proofs must be verified with.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        PersistentMmr {
+            peaks: vec![],
+            num_entries: 0,
+            hasher,
+        }
+    }
+
+    pub fn hasher(&self) -> &NodeHasher {
+        &self.hasher
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.num_entries
+    }
+
+    pub fn append(&mut self, entry: &[u8]) {
+        let mut height = 0;
+        let mut node = Arc::new(Node::Leaf {
+            hash: self.hasher.hash_leaf(entry),
+            entry: entry.to_vec(),
+        });
+        while self
+            .peaks
+            .last()
+            .is_some_and(|(last_height, _)| *last_height == height)
+        {
+            let (_, left) = self.peaks.pop().unwrap();
+            node = Arc::new(Node::Internal {
+                hash: self.hasher.hash_children(left.hash(), node.hash()),
+                left,
+                right: node,
+            });
+            height += 1;
+        }
+        self.peaks.push((height, node));
+        self.num_entries += 1;
+    }
+
+    /// Replace the entry at `index` and return the previous one, None if there's no such entry.
+    /// Only the nodes on its path are copied: clones taken before keep the previous entry.
+    pub fn update(&mut self, index: u64, entry: &[u8]) -> Option<Vec<u8>> {
+        let (position, leaf_index) = self.locate(index)?;
+        let (height, root) = &self.peaks[position];
+        let (root, previous) = self.replace(root, *height, leaf_index, entry);
+        self.peaks[position].1 = root;
+        Some(previous)
+    }
+
+    pub fn entry(&self, index: u64) -> Option<&[u8]> {
+        let (_, _, _, leaf) = self.path(index, 0)?;
+        match leaf {
+            Node::Leaf { entry, .. } => Some(entry),
+            Node::Internal { .. } => unreachable!("Paths end at leaves"),
+        }
+    }
+
+    pub fn commitment(&self) -> MmrCommitment {
+        let peaks = self.peaks.iter().map(|(_, peak)| *peak.hash()).collect();
+        MmrCommitment::new(self.num_entries, peaks)
+            .expect("One peak per tree")
+            .with_hash_algo(self.hasher.algo())
+    }
+
+    /// Prove the entry at `index` against `commitment()`, None if there's no such entry.
+    pub fn prove_inclusion(&self, index: u64) -> Option<MmrInclusionProof> {
+        let (tree_index, leaf_index, siblings, leaf) = self.path(index, 0)?;
+        let Node::Leaf { entry, .. } = leaf else {
+            unreachable!("Paths end at leaves");
+        };
+        let proof = MmrInclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            entry: entry.clone(),
+            tree_index,
+            proof: InclusionProof::new(leaf_index, siblings),
+            hash_algo: None,
+        };
+        Some(proof.with_hash_algo(self.hasher.algo()))
+    }
+
+    /// Prove that `commitment()` extends the commitment to this MMR's first `old_num_entries`
+    /// entries, None if it has fewer entries. Since updates rewrite entries, this only holds if
+    /// none of those entries was updated since.
+    pub fn prove_consistency(&self, old_num_entries: u64) -> Option<ConsistencyProof> {
+        if old_num_entries > self.num_entries {
+            return None;
+        }
+        prove_consistency_with(old_num_entries, |first_entry, height| {
+            let (_, _, siblings, _) = self.path(first_entry, height)?;
+            Some(siblings)
+        })
+    }
+
+    /// Number of nodes of this version that `other` shares, i.e., that storing both doesn't
+    /// duplicate.
+    pub fn shared_nodes(&self, other: &PersistentMmr) -> u64 {
+        let mut theirs = HashSet::new();
+        reach(other.roots(), &mut theirs, |_| ());
+
+        // Below a shared node, everything is shared
+        let mut shared = 0;
+        let mut stack: Vec<(u64, &Arc<Node>)> = self.peaks.iter().map(|(h, p)| (*h, p)).collect();
+        while let Some((height, node)) = stack.pop() {
+            if theirs.contains(&Arc::as_ptr(node)) {
+                shared += (2 << height) - 1;
+            } else if let Node::Internal { left, right, .. } = node.as_ref() {
+                stack.extend([(height - 1, left), (height - 1, right)]);
+            }
+        }
+        shared
+    }
+
+    fn roots(&self) -> Vec<Arc<Node>> {
+        self.peaks.iter().map(|(_, peak)| peak.clone()).collect()
+    }
+
+    // The position among the peaks of the tree holding the entry at `index`, and its leaf index
+    fn locate(&self, index: u64) -> Option<(usize, u64)> {
+        // Larger trees hold older entries
+        let mut leaf_index = index;
+        for (position, (height, _)) in self.peaks.iter().enumerate() {
+            if leaf_index < 1 << height {
+                return Some((position, leaf_index));
+            }
+            leaf_index -= 1 << height;
+        }
+        None
+    }
+
+    // The tree index and leaf index of the entry at `index`, the siblings (bottom-up) of the
+    // subtree of the given height holding it, and the root of that subtree: its leaf at height 0
+    fn path(&self, index: u64, height: u64) -> Option<(u64, u64, Vec<Digest>, &Node)> {
+        let (position, leaf_index) = self.locate(index)?;
+        let (tree_height, root) = &self.peaks[position];
+        let tree_height = *tree_height;
+
+        let mut siblings = Vec::with_capacity(tree_height.saturating_sub(height) as usize);
+        let mut node = root.as_ref();
+        for level in (height..tree_height).rev() {
+            let Node::Internal { left, right, .. } = node else {
+                unreachable!("Trees are perfect");
+            };
+            if (leaf_index >> level) & 1 == 0 {
+                siblings.push(*right.hash());
+                node = left;
+            } else {
+                siblings.push(*left.hash());
+                node = right;
+            }
+        }
+        siblings.reverse();
+        Some((tree_height, leaf_index, siblings, node))
+    }
+
+    // A copy of the subtree of the given height at `node` with the leaf at `leaf_index` replaced,
+    // sharing every node off its path, and the replaced entry
+    fn replace(
+        &self,
+        node: &Node,
+        height: u64,
+        leaf_index: u64,
+        entry: &[u8],
+    ) -> (Arc<Node>, Vec<u8>) {
+        match node {
+            Node::Leaf {
+                entry: previous, ..
+            } => {
+                let leaf = Node::Leaf {
+                    hash: self.hasher.hash_leaf(entry),
+                    entry: entry.to_vec(),
+                };
+                (Arc::new(leaf), previous.clone())
+            }
+            Node::Internal { left, right, .. } => {
+                let (left, right, previous) = if (leaf_index >> (height - 1)) & 1 == 0 {
+                    let (left, previous) = self.replace(left, height - 1, leaf_index, entry);
+                    (left, right.clone(), previous)
+                } else {
+                    let (right, previous) = self.replace(right, height - 1, leaf_index, entry);
+                    (left.clone(), right, previous)
+                };
+                let node = Node::Internal {
+                    hash: self.hasher.hash_children(left.hash(), right.hash()),
+                    left,
+                    right,
+                };
+                (Arc::new(node), previous)
+            }
+        }
+    }
+}
+
+impl Default for PersistentMmr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: AsRef<[u8]>> FromIterator<T> for PersistentMmr {
+    fn from_iter<I: IntoIterator<Item = T>>(entries: I) -> Self {
+        let mut mmr = PersistentMmr::new();
+        mmr.extend(entries);
+        mmr
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for PersistentMmr {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, entries: I) {
+        for entry in entries {
+            self.append(entry.as_ref());
+        }
+    }
+}
+
+/// An MMR keeping every version committed so far, and their nodes in a store.
+#[derive(Debug, Clone)]
+pub struct VersionedMmr<S = MemoryStore<StoredNode>> {
+    current: PersistentMmr,
+    // None once pruned
+    versions: Vec<Option<PersistentMmr>>,
+    store: S,
+    // Position in the store of every node of a committed version, by its address. The versions
+    // hold these nodes, so no address is reused while it's here.
+    positions: HashMap<usize, u64>,
+    next_pos: u64,
+}
+
+impl VersionedMmr {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// A log hashed with `hasher`, keeping its nodes on the heap.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        Self::new_with_store(MemoryStore::new(), hasher)
+    }
+}
+
+impl<S: NodeStore<StoredNode, Error = Infallible>> VersionedMmr<S> {
+    /// Commit the entries appended so far as a new version, and return its number.
+    pub fn commit(&mut self) -> u64 {
+        match self.try_commit() {
+            Ok(version) => version,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Drop every committed version but those in `retained`, see `try_prune`.
+    pub fn prune(&mut self, retained: &BTreeSet<u64>) -> usize {
+        match self.try_prune(retained) {
+            Ok(reclaimed) => reclaimed,
+            Err(e) => match e {},
+        }
+    }
+}
+
+impl<S: NodeStore<StoredNode>> VersionedMmr<S> {
+    /// A log hashed with `hasher`, writing the nodes of the versions it commits to `store`, which
+    /// must be empty.
+    pub fn new_with_store(store: S, hasher: NodeHasher) -> Self {
+        VersionedMmr {
+            current: PersistentMmr::new_with_hasher(hasher),
+            versions: vec![],
+            store,
+            positions: HashMap::new(),
+            next_pos: 0,
+        }
+    }
+
+    /// Append `entry` to the next version.
+    pub fn append(&mut self, entry: &[u8]) {
+        self.current.append(entry);
+    }
+
+    /// Replace the entry at `index` in the next version, see `PersistentMmr::update`.
+    pub fn update(&mut self, index: u64, entry: &[u8]) -> Option<Vec<u8>> {
+        self.current.update(index, entry)
+    }
+
+    /// Commit the entries appended so far as a new version, and return its number. The nodes
+    /// created since the last commit are written to the store in one batch; if it fails, nothing
+    /// is committed.
+    pub fn try_commit(&mut self) -> Result<u64, S::Error> {
+        let mut batch = vec![];
+        let mut added = vec![];
+        for root in self.current.roots() {
+            self.persist(&root, &mut batch, &mut added);
+        }
+        if let Err(e) = self.store.write_batch(batch) {
+            for key in &added {
+                self.positions.remove(key);
+            }
+            self.next_pos -= added.len() as u64;
+            return Err(e);
+        }
+        self.versions.push(Some(self.current.clone()));
+        Ok(self.versions.len() as u64 - 1)
+    }
+
+    /// The entries appended so far, committed or not.
+    pub fn current(&self) -> &PersistentMmr {
+        &self.current
+    }
+
+    /// Number of versions committed, pruned ones included.
+    pub fn num_versions(&self) -> u64 {
+        self.versions.len() as u64
+    }
+
+    /// A committed version, None if there's no such version or it was pruned.
+    pub fn version(&self, version: u64) -> Option<&PersistentMmr> {
+        self.versions.get(usize::try_from(version).ok()?)?.as_ref()
+    }
+
+    pub fn commitment_at_version(&self, version: u64) -> Option<MmrCommitment> {
+        Some(self.version(version)?.commitment())
+    }
+
+    /// Prove the entry at `index` as of `version`, against `commitment_at_version(version)`.
+    /// None if there's no such version, or the entry was appended after it.
+    pub fn prove_inclusion_at_version(
+        &self,
+        index: u64,
+        version: u64,
+    ) -> Option<MmrInclusionProof> {
+        self.version(version)?.prove_inclusion(index)
+    }
+
+    /// The store the nodes of the committed versions are written to.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Drop every committed version but those in `retained`, delete the nodes only dropped
+    /// versions reached from the store in one batch, and return the bytes they took there (see
+    /// `StoredNode::size`). In memory, they're freed when their last `Arc` goes, i.e., unless
+    /// clones of the dropped versions are held elsewhere. Nodes the retained versions or the
+    /// uncommitted entries still reach are kept: appends keep every node reachable from the latest
+    /// entries, so only the paths that updates replaced are ever deleted. If the batch fails,
+    /// nothing is dropped.
+    pub fn try_prune(&mut self, retained: &BTreeSet<u64>) -> Result<usize, S::Error> {
+        let is_dropped = |version: usize| !retained.contains(&(version as u64));
+        let mut kept = HashSet::new();
+        let mut stack = self.current.roots();
+        for (version, mmr) in self.versions.iter().enumerate() {
+            if let Some(mmr) = mmr.as_ref().filter(|_| !is_dropped(version)) {
+                stack.extend(mmr.roots());
+            }
+        }
+        reach(stack, &mut kept, |_| ());
+
+        let mut deleted = vec![];
+        let mut reclaimed = 0;
+        for (version, mmr) in self.versions.iter().enumerate() {
+            if let Some(mmr) = mmr.as_ref().filter(|_| is_dropped(version)) {
+                reach(mmr.roots(), &mut kept, |node| {
+                    let key = Arc::as_ptr(node) as usize;
+                    let pos = self.positions[&key];
+                    reclaimed += self.stored_node(node).size();
+                    deleted.push((key, pos));
+                });
+            }
+        }
+        self.store.write_batch(
+            deleted
+                .iter()
+                .map(|&(_, pos)| BatchOp::Delete(pos))
+                .collect(),
+        )?;
+
+        for (key, _) in deleted {
+            self.positions.remove(&key);
+        }
+        for (version, mmr) in self.versions.iter_mut().enumerate() {
+            if is_dropped(version) {
+                mmr.take();
+            }
+        }
+        Ok(reclaimed)
+    }
+
+    // Give `node` and every node below it without one a position, post-order so that children
+    // have theirs first, and add their writes to `batch`. Returns the position of `node`.
+    fn persist(
+        &mut self,
+        node: &Arc<Node>,
+        batch: &mut Vec<BatchOp<StoredNode>>,
+        added: &mut Vec<usize>,
+    ) -> u64 {
+        let key = Arc::as_ptr(node) as usize;
+        if let Some(pos) = self.positions.get(&key) {
+            return *pos;
+        }
+        if let Node::Internal { left, right, .. } = node.as_ref() {
+            self.persist(left, batch, added);
+            self.persist(right, batch, added);
+        }
+        let stored = self.stored_node(node);
+        let pos = self.next_pos;
+        self.next_pos += 1;
+        self.positions.insert(key, pos);
+        added.push(key);
+        batch.push(BatchOp::Put(pos, stored));
+        pos
+    }
+
+    // `node` as written to the store, its children having positions already
+    fn stored_node(&self, node: &Arc<Node>) -> StoredNode {
+        let position = |child: &Arc<Node>| self.positions[&(Arc::as_ptr(child) as usize)];
+        match node.as_ref() {
+            Node::Leaf { hash, entry } => StoredNode::Leaf {
+                hash: *hash,
+                entry: entry.clone(),
+            },
+            Node::Internal { hash, left, right } => StoredNode::Internal {
+                hash: *hash,
+                left: position(left),
+                right: position(right),
+            },
+        }
+    }
+}
+
+impl Default for VersionedMmr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Visit every node reachable from `stack` that isn't in `seen` yet, adding it
+fn reach(
+    mut stack: Vec<Arc<Node>>,
+    seen: &mut HashSet<*const Node>,
+    mut visit: impl FnMut(&Arc<Node>),
+) {
+    while let Some(node) = stack.pop() {
+        if !seen.insert(Arc::as_ptr(&node)) {
+            continue;
+        }
+        visit(&node);
+        if let Node::Internal { left, right, .. } = node.as_ref() {
+            stack.extend([left.clone(), right.clone()]);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Answering queries about entries of an MMR with the smallest proof.
+//!
+//! The same entries can be proven in several ways: one inclusion proof each, a multi-inclusion
+//! proof sharing their siblings, or a multi-inclusion proof of the whole subtrees holding them,
+//! which carries more entries but fewer siblings, down to none once whole trees are sent. For the
+//! most recent entries, there's also the dedicated suffix proof. Which is smallest depends on how
+//! the entries are spread and how large they are, so the planner builds each candidate and keeps
+//! the one whose encoding is shortest, as given by `size_hint`.
+
+use std::ops::Range;
+
+use ads_core::encoding::uleb128_length;
+use ads_core::Proof;
+use serde::{Deserialize, Serialize};
+
+use crate::error::VerificationError;
+use crate::{
+    MerkleMountainRange, MmrCommitment, MmrInclusionProof, MmrMultiInclusionProof,
+    MostRecentNElementsProof, NodeHasher,
+};
+
+/// Entries a client asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Indices(Vec<u64>),
+    Range(Range<u64>),
+    // The most recent this many entries
+    Suffix(u64),
+}
+
+impl Query {
+    /// The indices of the entries queried from an MMR of `num_entries` entries, increasing.
+    pub fn indices(&self, num_entries: u64) -> Vec<u64> {
+        match self {
+            Query::Indices(indices) => {
+                let mut indices = indices.clone();
+                indices.sort_unstable();
+                indices.dedup();
+                indices
+            }
+            Query::Range(range) => range.clone().collect(),
+            Query::Suffix(n) => (num_entries.saturating_sub(*n)..num_entries).collect(),
+        }
+    }
+}
+
+/// How a query is answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    // One inclusion proof per entry
+    Single,
+    // One multi-inclusion proof of the entries
+    Multi,
+    // One multi-inclusion proof of every entry of the aligned subtrees of this height holding the
+    // entries, or of their trees if smaller
+    Subtrees(u64),
+    // A proof of the most recent entries
+    Suffix,
+}
+
+/// The chosen strategy and the length of the encoding of its proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Plan {
+    pub strategy: Strategy,
+    pub cost: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PlannedProof {
+    Single(Vec<MmrInclusionProof>),
+    Multi(MmrMultiInclusionProof),
+    Suffix(MostRecentNElementsProof),
+}
+
+impl PlannedProof {
+    /// The proven entries, which may be more than queried.
+    pub fn entries(&self) -> Vec<&[u8]> {
+        match self {
+            PlannedProof::Single(proofs) => proofs.iter().map(|p| p.entry.as_slice()).collect(),
+            PlannedProof::Multi(proof) => proof.entries.iter().map(Vec::as_slice).collect(),
+            PlannedProof::Suffix(proof) => proof.entries().iter().map(Vec::as_slice).collect(),
+        }
+    }
+
+    /// Verify the proof against `commitment` and return the indices of its entries, in the order
+    /// of `entries`, so that a client can check that they cover its query.
+    pub fn verify(&self, commitment: &MmrCommitment) -> Result<Vec<u64>, VerificationError> {
+        self.verify_with_hasher(&NodeHasher::new(commitment.hash_algo()), commitment)
+    }
+
+    /// Same as `verify`, for an MMR hashed with `hasher`.
+    pub fn verify_with_hasher(
+        &self,
+        hasher: &NodeHasher,
+        commitment: &MmrCommitment,
+    ) -> Result<Vec<u64>, VerificationError> {
+        let index = |tree_index, leaf_index| {
+            commitment
+                .entry_index(tree_index, leaf_index)
+                .ok_or(VerificationError::TreeIndexOutOfBounds(tree_index))
+        };
+        match self {
+            PlannedProof::Single(proofs) => proofs
+                .iter()
+                .map(|proof| {
+                    commitment.verify_inclusion_with_hasher(hasher, proof)?;
+                    index(proof.tree_index, proof.proof.leaf_index)
+                })
+                .collect(),
+            PlannedProof::Multi(proof) => {
+                commitment.verify_multi_inclusion_with_hasher(hasher, proof)?;
+                proof
+                    .tree_proofs
+                    .iter()
+                    .flat_map(|(tree_index, tree_proof)| {
+                        tree_proof
+                            .leaf_indices
+                            .iter()
+                            .map(|&leaf_index| index(*tree_index, leaf_index))
+                    })
+                    .collect()
+            }
+            PlannedProof::Suffix(proof) => {
+                commitment.verify_most_recent_n_elements_with_hasher(hasher, proof)?;
+                let num_entries = commitment.num_entries();
+                Ok((num_entries - proof.entries().len() as u64..num_entries).collect())
+            }
+        }
+    }
+}
+
+/// Checked against an MMR commitment, returning the indices of the proven entries.
+impl Proof for PlannedProof {
+    type Commitment = MmrCommitment;
+    type Claim = ();
+    type Output = Vec<u64>;
+    type Error = VerificationError;
+
+    fn verify(&self, commitment: &MmrCommitment, _: &()) -> Result<Vec<u64>, VerificationError> {
+        PlannedProof::verify(self, commitment)
+    }
+
+    fn size_hint(&self) -> usize {
+        // The variant index, then the proofs
+        1 + match self {
+            PlannedProof::Single(proofs) => {
+                uleb128_length(proofs.len()) + proofs.iter().map(Proof::size_hint).sum::<usize>()
+            }
+            PlannedProof::Multi(proof) => proof.size_hint(),
+            PlannedProof::Suffix(proof) => proof.size_hint(),
+        }
+    }
+}
+
+impl MerkleMountainRange {
+    /// Answer `query` with the smallest of the proofs of its entries, returned with the plan that
+    /// produced it. None if the query is empty or asks for entries the MMR doesn't have.
+    pub fn prove_query(&self, query: &Query) -> Option<(Plan, PlannedProof)> {
+        let num_entries = self.num_entries();
+        let indices = query.indices(num_entries);
+        let too_long = matches!(query, Query::Suffix(n) if *n > num_entries);
+        if too_long || indices.is_empty() || *indices.last()? >= num_entries {
+            return None;
+        }
+
+        let mut candidates = vec![];
+        if let Query::Suffix(n) = query {
+            let proof = self.prove_most_recent_n_elements(*n);
+            candidates.push((Strategy::Suffix, PlannedProof::Suffix(proof)));
+        }
+        let proofs = indices.iter().map(|&i| self.prove_inclusion(i)).collect();
+        candidates.push((Strategy::Single, PlannedProof::Single(proofs)));
+        let proof = self.prove_multi_inclusion(&indices);
+        candidates.push((Strategy::Multi, PlannedProof::Multi(proof)));
+
+        // Each height widens the subtrees until they're the whole trees holding the entries
+        let tree_heights: Vec<u64> = indices.iter().map(|&i| self.locate(i).0 as u64).collect();
+        let max_height = tree_heights.iter().copied().max()?;
+        for height in 1..=max_height {
+            // Trees start at multiples of their size, so subtrees are aligned among all entries
+            let mut subtrees: Vec<(u64, u64)> = indices
+                .iter()
+                .zip(&tree_heights)
+                .map(|(&i, &tree_height)| {
+                    let height = height.min(tree_height);
+                    (i >> height << height, 1 << height)
+                })
+                .collect();
+            subtrees.dedup();
+            let covered: Vec<u64> = subtrees
+                .into_iter()
+                .flat_map(|(start, len)| start..start + len)
+                .collect();
+            let proof = self.prove_multi_inclusion(&covered);
+            candidates.push((Strategy::Subtrees(height), PlannedProof::Multi(proof)));
+        }
+
+        candidates
+            .into_iter()
+            .map(|(strategy, proof)| {
+                let cost = proof.size_hint();
+                (Plan { strategy, cost }, proof)
+            })
+            .min_by_key(|(plan, _)| plan.cost)
+    }
+}
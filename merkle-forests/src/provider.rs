@@ -0,0 +1,105 @@
+//! Proof generation behind a trait, so that clients can be written once against a prover and run
+//! with the MMR in their own process or, later, with one behind an RPC.
+//!
+//! Methods return futures, as a remote prover answers over the network; those of the local
+//! implementation below are ready as soon as they are polled.
+
+use std::fmt;
+use std::future::Future;
+
+use crate::{
+    ConsistencyProof, MerkleMountainRange, MmrCommitment, MmrInclusionProof,
+    MostRecentNElementsProof,
+};
+
+/// Something that serves commitments to an MMR and proofs against them.
+pub trait ProofProvider {
+    type Error;
+
+    /// The commitment to the MMR as it currently is, which the proofs below verify against.
+    fn get_commitment(&self) -> impl Future<Output = Result<MmrCommitment, Self::Error>> + Send;
+
+    /// Prove the entry at `index`.
+    fn prove_inclusion(
+        &self,
+        index: u64,
+    ) -> impl Future<Output = Result<MmrInclusionProof, Self::Error>> + Send;
+
+    /// Prove the `num_entries` most recent entries.
+    fn prove_suffix(
+        &self,
+        num_entries: u64,
+    ) -> impl Future<Output = Result<MostRecentNElementsProof, Self::Error>> + Send;
+
+    /// Prove that the MMR extends the one it was with `old_num_entries` entries.
+    fn prove_consistency(
+        &self,
+        old_num_entries: u64,
+    ) -> impl Future<Output = Result<ConsistencyProof, Self::Error>> + Send;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    /// No entry at this index
+    IndexOutOfBounds(u64),
+    /// The MMR holds fewer entries than this
+    TooManyEntries(u64),
+    /// A proof of the most recent entries must cover at least one
+    EmptySuffix,
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::IndexOutOfBounds(index) => write!(f, "No entry at index {}", index),
+            ProviderError::TooManyEntries(num_entries) => {
+                write!(f, "The MMR holds fewer than {} entries", num_entries)
+            }
+            ProviderError::EmptySuffix => write!(f, "Can't prove the 0 most recent entries"),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// Proofs straight from an MMR in memory.
+impl ProofProvider for MerkleMountainRange {
+    type Error = ProviderError;
+
+    async fn get_commitment(&self) -> Result<MmrCommitment, ProviderError> {
+        Ok(self.commitment())
+    }
+
+    async fn prove_inclusion(&self, index: u64) -> Result<MmrInclusionProof, ProviderError> {
+        if index >= self.num_entries() {
+            return Err(ProviderError::IndexOutOfBounds(index));
+        }
+        Ok(MerkleMountainRange::prove_inclusion(self, index))
+    }
+
+    async fn prove_suffix(
+        &self,
+        num_entries: u64,
+    ) -> Result<MostRecentNElementsProof, ProviderError> {
+        if num_entries == 0 {
+            return Err(ProviderError::EmptySuffix);
+        }
+        if num_entries > self.num_entries() {
+            return Err(ProviderError::TooManyEntries(num_entries));
+        }
+        Ok(self.prove_most_recent_n_elements(num_entries))
+    }
+
+    async fn prove_consistency(
+        &self,
+        old_num_entries: u64,
+    ) -> Result<ConsistencyProof, ProviderError> {
+        if old_num_entries > self.num_entries() {
+            return Err(ProviderError::TooManyEntries(old_num_entries));
+        }
+        Ok(MerkleMountainRange::prove_consistency(
+            self,
+            old_num_entries,
+        ))
+    }
+}
@@ -0,0 +1,113 @@
+//! A priority queue committed to as a sorted MMR, with proofs of its minimum and of each operation.
+//!
+//! Every operation rebuilds the MMR, in time linear in the number of elements, and returns the
+//! proof that takes the previous commitment to the new one.
+
+pub use ads_verifier::queue::{QueueOperation, QueueTransitionProof};
+
+use crate::hashing::HashBackend;
+use crate::ordering::LeafOrdering;
+use crate::{MerkleMountainRange, MmrCommitment, MmrInclusionProof, NodeHasher};
+
+#[derive(Debug)]
+pub struct PriorityQueue {
+    // Sorted, so the minimum is entry 0
+    mmr: MerkleMountainRange,
+}
+
+impl PriorityQueue {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// An empty queue committed to with `hasher`, which proofs must be verified with.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        Self::from_elements_with_hasher(vec![], hasher)
+    }
+
+    /// A queue of `elements`, in any order. Duplicates are queued once.
+    pub fn from_elements(elements: Vec<&[u8]>) -> Self {
+        Self::from_elements_with_hasher(elements, NodeHasher::UNKEYED)
+    }
+
+    pub fn from_elements_with_hasher(elements: Vec<&[u8]>, hasher: NodeHasher) -> Self {
+        PriorityQueue {
+            mmr: MerkleMountainRange::new_with_ordering_and_hasher(
+                elements,
+                LeafOrdering::Sorted,
+                HashBackend::detect(),
+                hasher,
+            ),
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.mmr.num_entries()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The minimum, None if the queue is empty.
+    pub fn peek(&self) -> Option<&[u8]> {
+        (!self.is_empty()).then(|| self.mmr.entries.get(0))
+    }
+
+    pub fn mmr(&self) -> &MerkleMountainRange {
+        &self.mmr
+    }
+
+    pub fn commitment(&self) -> MmrCommitment {
+        self.mmr.commitment()
+    }
+
+    /// Proof that `peek` is the minimum, see `MmrCommitment::verify_min`.
+    pub fn prove_min(&self) -> Option<MmrInclusionProof> {
+        (!self.is_empty()).then(|| self.mmr.prove_inclusion(0))
+    }
+
+    /// Insert `element` and return the proof of the transition, None if it's already queued.
+    pub fn push(&mut self, element: &[u8]) -> Option<QueueTransitionProof> {
+        let mut elements: Vec<&[u8]> = self.mmr.entries.iter().collect();
+        let position = match elements.binary_search(&element) {
+            Ok(_) => return None,
+            Err(position) => position,
+        };
+        let neighbours = elements[position.saturating_sub(1)..(position + 1).min(elements.len())]
+            .iter()
+            .map(|neighbour| neighbour.to_vec())
+            .collect();
+        let operation = QueueOperation::Push {
+            element: element.to_vec(),
+            position: position as u64,
+        };
+        let proof = QueueTransitionProof::new(operation, self.mmr.leaf_hashes(), neighbours);
+
+        elements.insert(position, element);
+        let mmr = Self::from_elements_with_hasher(elements, self.mmr.hasher).mmr;
+        self.mmr = mmr;
+        Some(proof)
+    }
+
+    /// Remove the minimum and return it with the proof of the transition, None if the queue is
+    /// empty.
+    pub fn pop(&mut self) -> Option<(Vec<u8>, QueueTransitionProof)> {
+        let min = self.peek()?.to_vec();
+        let proof = QueueTransitionProof::new(
+            QueueOperation::Pop(min.clone()),
+            self.mmr.leaf_hashes(),
+            vec![],
+        );
+        let elements = self.mmr.entries.iter().skip(1).collect();
+        let mmr = Self::from_elements_with_hasher(elements, self.mmr.hasher).mmr;
+        self.mmr = mmr;
+        Some((min, proof))
+    }
+}
+
+impl Default for PriorityQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,14 @@
+//! Selecting entries at random, with proofs of the selection.
+
+pub use ads_verifier::selection::SelectionProof;
+
+use crate::MerkleMountainRange;
+
+impl MerkleMountainRange {
+    /// Prove the entry `seed` selects, see `MmrCommitment::select_index`. None if the MMR is
+    /// empty.
+    pub fn select(&self, seed: &[u8]) -> Option<SelectionProof> {
+        let index = self.commitment().select_index(seed)?;
+        Some(SelectionProof::new(self.prove_inclusion(index)))
+    }
+}
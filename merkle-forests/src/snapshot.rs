@@ -0,0 +1,186 @@
+//! Streaming snapshots for backups.
+//!
+//! A full snapshot holds the entries followed by the digests of the trees. Interior nodes are not
+//! written out: importing recomputes them from the entries as they are read, so the whole snapshot
+//! never needs to be in memory, and then checks the recomputed digests against the recorded ones.
+//! A pruned snapshot holds only the number of entries and the peaks, as kept by `MmrAppender`.
+//!
+//! Layout: magic (4 bytes) || version (1) || kind (1) || number of entries (u64 LE), then for a
+//! full snapshot every entry as uleb128(len) || bytes, and finally one digest per tree, largest
+//! tree first.
+
+use std::fmt;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use crate::appender::MmrAppender;
+use crate::digest::{Digest, DIGEST_LENGTH};
+use crate::MerkleMountainRange;
+
+const MAGIC: &[u8; 4] = b"MMRS";
+const VERSION: u8 = 1;
+const KIND_FULL: u8 = 0;
+const KIND_PEAKS: u8 = 1;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// Not a snapshot, or a snapshot of the other kind
+    Malformed,
+    UnsupportedVersion(u8),
+    /// The digests recomputed from the entries differ from the recorded ones
+    DigestMismatch,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "I/O error: {}", e),
+            SnapshotError::Malformed => write!(f, "Malformed snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported snapshot version {}", version)
+            }
+            SnapshotError::DigestMismatch => {
+                write!(f, "Snapshot digests don't match its entries")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl MerkleMountainRange {
+    pub fn export_snapshot<W: Write>(&self, writer: W) -> Result<(), SnapshotError> {
+        let mut writer = BufWriter::new(writer);
+        write_preamble(&mut writer, KIND_FULL, self.num_entries())?;
+        for entry in self.entries.iter() {
+            write_uleb128(&mut writer, entry.len())?;
+            writer.write_all(entry)?;
+        }
+        write_peaks(&mut writer, self.digests())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn import_snapshot<R: Read>(reader: R) -> Result<Self, SnapshotError> {
+        let mut reader = BufReader::new(reader);
+        let num_entries = read_preamble(&mut reader, KIND_FULL)?;
+
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        let mut entry = vec![];
+        for _ in 0..num_entries {
+            let len = read_uleb128(&mut reader)?;
+            entry.resize(len, 0);
+            reader.read_exact(&mut entry)?;
+            mmr.add_entry(&entry);
+        }
+
+        let recorded = read_peaks(&mut reader, num_entries)?;
+        if recorded != mmr.digests() {
+            return Err(SnapshotError::DigestMismatch);
+        }
+        expect_end(&mut reader)?;
+        Ok(mmr)
+    }
+}
+
+impl MmrAppender {
+    pub fn export_snapshot<W: Write>(&self, writer: W) -> Result<(), SnapshotError> {
+        let mut writer = BufWriter::new(writer);
+        write_preamble(&mut writer, KIND_PEAKS, self.num_entries())?;
+        write_peaks(&mut writer, self.digests())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Import a pruned snapshot. There is nothing to recompute the peaks from, so callers should
+    /// check the resulting root against a trusted one.
+    pub fn import_snapshot<R: Read>(reader: R) -> Result<Self, SnapshotError> {
+        let mut reader = BufReader::new(reader);
+        let num_entries = read_preamble(&mut reader, KIND_PEAKS)?;
+        let peaks = read_peaks(&mut reader, num_entries)?;
+        expect_end(&mut reader)?;
+        Ok(MmrAppender::from_peaks(num_entries, peaks))
+    }
+}
+
+fn write_preamble<W: Write>(writer: &mut W, kind: u8, num_entries: u64) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION, kind])?;
+    writer.write_all(&num_entries.to_le_bytes())
+}
+
+fn read_preamble<R: Read>(reader: &mut R, kind: u8) -> Result<u64, SnapshotError> {
+    let mut preamble = [0; 14];
+    reader.read_exact(&mut preamble)?;
+    if &preamble[..4] != MAGIC {
+        return Err(SnapshotError::Malformed);
+    }
+    if preamble[4] != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(preamble[4]));
+    }
+    if preamble[5] != kind {
+        return Err(SnapshotError::Malformed);
+    }
+    Ok(u64::from_le_bytes(preamble[6..].try_into().unwrap()))
+}
+
+// Digests of the trees that exist, largest first
+fn write_peaks<W: Write>(writer: &mut W, digests: Vec<Option<Digest>>) -> io::Result<()> {
+    for digest in digests.iter().rev().flatten() {
+        writer.write_all(digest.as_bytes())?;
+    }
+    Ok(())
+}
+
+// Read the digests written by `write_peaks`, in the layout of `MerkleMountainRange::digests`
+fn read_peaks<R: Read>(
+    reader: &mut R,
+    num_entries: u64,
+) -> Result<Vec<Option<Digest>>, SnapshotError> {
+    let num_heights = (u64::BITS - num_entries.leading_zeros()) as usize;
+    let mut digests = vec![None; num_heights + 1];
+    for height in (0..num_heights).rev() {
+        if num_entries & (1 << height) != 0 {
+            let mut digest = [0; DIGEST_LENGTH];
+            reader.read_exact(&mut digest)?;
+            digests[height] = Some(Digest(digest));
+        }
+    }
+    Ok(digests)
+}
+
+fn expect_end<R: Read>(reader: &mut R) -> Result<(), SnapshotError> {
+    if reader.read(&mut [0])? != 0 {
+        return Err(SnapshotError::Malformed);
+    }
+    Ok(())
+}
+
+fn write_uleb128<W: Write>(writer: &mut W, mut n: usize) -> io::Result<()> {
+    while n >= 0x80 {
+        writer.write_all(&[(n & 0x7f) as u8 | 0x80])?;
+        n >>= 7;
+    }
+    writer.write_all(&[n as u8])
+}
+
+fn read_uleb128<R: Read>(reader: &mut R) -> Result<usize, SnapshotError> {
+    let mut value: usize = 0;
+    for i in 0..10 {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as usize)
+            .checked_shl(7 * i)
+            .ok_or(SnapshotError::Malformed)?;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(SnapshotError::Malformed)
+}
@@ -0,0 +1,108 @@
+//! A stack committed to as an MMR, popping with proofs.
+//!
+//! The stack keeps the frontier of the MMR at every depth, so that pushing and popping take time
+//! logarithmic in its size instead of rebuilding the MMR: the proof of the top comes from the
+//! frontier below it, whose last peaks are the top's siblings.
+
+pub use ads_verifier::stack::PopProof;
+
+use ads_core::NodeHasher;
+
+use crate::digest::Digest;
+use crate::frontier::Frontier;
+use crate::{InclusionProof, MmrCommitment, MmrInclusionProof, PROOF_FORMAT_VERSION};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticatedStack {
+    values: Vec<Vec<u8>>,
+    // frontiers[i] is the frontier of the first i values
+    frontiers: Vec<Frontier>,
+    hasher: NodeHasher,
+}
+
+impl AuthenticatedStack {
+    pub fn new() -> Self {
+        Self::new_with_hasher(NodeHasher::UNKEYED)
+    }
+
+    /// An empty stack hashed with `hasher`, which pops must be verified with.
+    pub fn new_with_hasher(hasher: NodeHasher) -> Self {
+        AuthenticatedStack {
+            values: vec![],
+            frontiers: vec![Frontier::empty().with_hash_algo(hasher.algo())],
+            hasher,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.values.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn peek(&self) -> Option<&[u8]> {
+        self.values.last().map(Vec::as_slice)
+    }
+
+    pub fn commitment(&self) -> MmrCommitment {
+        self.frontier().commitment().clone()
+    }
+
+    /// Push `value` and return the new commitment.
+    pub fn push(&mut self, value: &[u8]) -> MmrCommitment {
+        let mut frontier = self.frontier().clone();
+        frontier
+            .append_with_hasher(&self.hasher, value)
+            .expect("The frontier is hashed with the stack's algorithm");
+        self.frontiers.push(frontier);
+        self.values.push(value.to_vec());
+        self.commitment()
+    }
+
+    /// Proof of the top, from which `MmrCommitment::verify_pop` computes the commitment after a
+    /// pop. None if the stack is empty.
+    pub fn prove_top(&self) -> Option<PopProof> {
+        let top = self.values.last()?;
+        let num_entries = self.len();
+        let height = num_entries.trailing_zeros() as usize;
+        // Below the top, the trees of heights up to `height - 1` are the top's siblings
+        let below = &self.frontiers[self.frontiers.len() - 2];
+        let mut siblings: Vec<Digest> = below
+            .peaks()
+            .filter(|(tree_height, _)| (*tree_height as usize) < height)
+            .map(|(_, peak)| *peak)
+            .collect();
+        siblings.reverse();
+        let proof = MmrInclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            entry: top.clone(),
+            tree_index: height as u64,
+            proof: InclusionProof::new((1 << height) - 1, siblings),
+            hash_algo: None,
+        }
+        .with_hash_algo(self.hasher.algo());
+        Some(PopProof::new(proof))
+    }
+
+    /// Pop the top and return it with the proof of the pop, None if the stack is empty.
+    pub fn pop(&mut self) -> Option<(Vec<u8>, PopProof)> {
+        let proof = self.prove_top()?;
+        self.frontiers.pop();
+        let value = self.values.pop()?;
+        Some((value, proof))
+    }
+
+    fn frontier(&self) -> &Frontier {
+        self.frontiers
+            .last()
+            .expect("The empty frontier is never popped")
+    }
+}
+
+impl Default for AuthenticatedStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
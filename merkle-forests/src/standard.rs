@@ -0,0 +1,94 @@
+//! Interop with the conventional MMR layout used by Grin, ckb and most other MMR libraries.
+//!
+//! These implementations store all nodes of all trees in one array in post-order, so that a node
+//! is addressed by a single (0-based) position, and the size of the MMR is the number of nodes.
+//! Peaks are ordered from the left (largest, oldest tree) to the right. Our forest keeps trees in
+//! `trees[height]` instead, but the trees themselves are identical, so converting is only a matter
+//! of position arithmetic.
+//!
+//! Proofs follow the ckb-merkle-mountain-range layout: the peaks left of the leaf's peak, then the
+//! path from the leaf to its peak, then the peaks right of it bagged into a single item. The root
+//! bags peaks from right to left as `hash_children(right, left)`. Node hashes themselves are
+//! unchanged, so proofs only verify against implementations configured with the same merge
+//! function (Grin additionally commits positions into its hashes). Roots and proofs bagged another
+//! way are produced by the `_with_bagging` variants, see `PeakBagging`. Peaks are bagged with the
+//! MMR's own hasher, so proofs of a keyed MMR verify with `StandardMerkleProof::verify_with_hasher`.
+
+pub use ads_verifier::standard::{
+    bag_peaks, bag_peaks_with_hasher, children_pos, leaf_count_from_mmr_size, leaf_index_to_pos,
+    locate_pos, mmr_size_from_leaf_count, parent_pos, peak_positions, pos_height,
+    pos_to_leaf_index, sibling_pos, tree_size, BaggingOrder, PeakBagging, StandardMerkleProof,
+};
+
+use crate::digest::Digest;
+use crate::MerkleMountainRange;
+
+impl MerkleMountainRange {
+    /// Number of nodes in the conventional layout.
+    pub fn mmr_size(&self) -> u64 {
+        mmr_size_from_leaf_count(self.entries.len() as u64)
+    }
+
+    /// Peak digests ordered left to right, as in the conventional layout.
+    pub fn standard_peaks(&self) -> Vec<Digest> {
+        self.trees
+            .iter()
+            .rev()
+            .flatten()
+            .map(|tree| tree.digest())
+            .collect()
+    }
+
+    /// Root obtained by bagging the peaks. None for an empty MMR.
+    pub fn standard_root(&self) -> Option<Digest> {
+        bag_peaks_with_hasher(&self.hasher, &self.standard_peaks())
+    }
+
+    /// Same as `standard_root`, with the peaks bagged as `bagging` does.
+    pub fn standard_root_with_bagging(&self, bagging: &PeakBagging) -> Option<Digest> {
+        bagging.bag_with_hasher(&self.hasher, &self.standard_peaks(), self.mmr_size())
+    }
+
+    /// Convert (tree index in `self.trees`, leaf index within that tree) to a conventional position.
+    pub fn standard_pos_of(&self, tree_index: usize, leaf_index: u64) -> u64 {
+        let preceding: u64 = self.trees[tree_index + 1..]
+            .iter()
+            .flatten()
+            .map(|tree| tree.num_leaves())
+            .sum();
+        leaf_index_to_pos(preceding + leaf_index)
+    }
+
+    /// Convert a conventional leaf position to (tree index in `self.trees`, leaf index within that
+    /// tree). Returns None for internal nodes or positions outside the MMR.
+    pub fn locate_standard_pos(&self, pos: u64) -> Option<(usize, u64)> {
+        let index = pos_to_leaf_index(pos)?;
+        if index >= self.num_entries() {
+            return None;
+        }
+        Some(self.locate(index))
+    }
+
+    pub fn prove_inclusion_standard(&self, index: u64) -> StandardMerkleProof {
+        self.prove_inclusion_standard_with_bagging(index, &PeakBagging::CKB)
+    }
+
+    /// Same as `prove_inclusion_standard`, for a root bagged as `bagging` does.
+    pub fn prove_inclusion_standard_with_bagging(
+        &self,
+        index: u64,
+        bagging: &PeakBagging,
+    ) -> StandardMerkleProof {
+        let proof = self.prove_inclusion(index);
+        let peaks = self.standard_peaks();
+        // Our trees are indexed by height, so the peak index counts the larger trees
+        let peak_index = self.trees[proof.tree_index as usize + 1..]
+            .iter()
+            .filter(|tree| tree.is_some())
+            .count();
+
+        let proof_items =
+            bagging.proof_items_with_hasher(&self.hasher, &peaks, peak_index, proof.proof.siblings);
+        StandardMerkleProof::new(self.mmr_size(), proof_items)
+    }
+}
@@ -0,0 +1,619 @@
+//! MMRs kept in a `NodeStore` instead of on the heap.
+//!
+//! Node hashes are stored at their conventional post-order positions (see `standard`) and entries
+//! at their index, in a second store. Appending writes the new leaf and every parent it completes
+//! in one batch, and proofs only fetch the nodes they use, so the MMR never needs to be resident
+//! in memory.
+//!
+//! The entry is written after its nodes and acts as the commit marker of an append: entries are
+//! always a prefix of the nodes, and nodes left over by an interrupted append are overwritten by
+//! the next one.
+//!
+//! Perfect trees are kept the same way by `StoredMerkleTree`, and skip lists by
+//! `skip_lists::store::StoredSkipList`. All of them default to a `MemoryStore`, i.e., the heap.
+
+use std::fmt;
+
+use ads_store::{BatchOp, MemoryStore, NodeStore};
+use ads_verifier::PROOF_FORMAT_VERSION;
+
+use crate::digest::Digest;
+use crate::hashing::HashBackend;
+use crate::standard::{
+    bag_peaks_with_hasher, children_pos, leaf_index_to_pos, locate_pos, mmr_size_from_leaf_count,
+    parent_pos, peak_positions, sibling_pos, tree_size, PeakBagging, StandardMerkleProof,
+};
+use crate::values::LeafValues;
+use crate::{
+    InclusionProof, MerkleMountainRange, MerkleNode, MmrInclusionProof, MostRecentNElementsProof,
+    NodeHasher, PerfectMerkleTree, SuffixProof,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreError<E> {
+    /// The backend failed
+    Backend(E),
+    /// A node that must exist for the current size isn't in the store
+    MissingNode(u64),
+    /// An entry that must exist for the current size isn't in the store
+    MissingEntry(u64),
+    /// A stored node doesn't match the nodes or entry it is computed from
+    CorruptNode(u64),
+    /// An entry or leaf past the end was asked for, or more recent entries than there are
+    IndexOutOfBounds(u64),
+}
+
+impl<E: fmt::Display> fmt::Display for StoreError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Backend(e) => write!(f, "Storage backend error: {}", e),
+            StoreError::MissingNode(pos) => write!(f, "Node at position {} is missing", pos),
+            StoreError::MissingEntry(index) => write!(f, "Entry {} is missing", index),
+            StoreError::CorruptNode(pos) => write!(f, "Node at position {} is corrupt", pos),
+            StoreError::IndexOutOfBounds(index) => write!(f, "Index {} out of bounds", index),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for StoreError<E> {}
+
+/// An MMR whose node hashes and entries live in node stores. Both stores must report errors of
+/// the same type.
+#[derive(Debug)]
+pub struct StoredMmr<S = MemoryStore<Digest>, E = MemoryStore<Vec<u8>>> {
+    nodes: S,
+    entries: E,
+    num_entries: u64,
+    hasher: NodeHasher,
+}
+
+impl StoredMmr {
+    /// An empty MMR on the heap.
+    pub fn in_memory() -> Self {
+        StoredMmr::new(MemoryStore::new(), MemoryStore::new())
+    }
+}
+
+impl<S, E> StoredMmr<S, E>
+where
+    S: NodeStore<Digest>,
+    E: NodeStore<Vec<u8>, Error = S::Error>,
+{
+    /// An empty MMR on top of empty stores.
+    pub fn new(nodes: S, entries: E) -> Self {
+        Self::new_with_hasher(nodes, entries, NodeHasher::UNKEYED)
+    }
+
+    /// An empty MMR on top of empty stores, with its leaves and nodes hashed by `hasher` as by
+    /// `MerkleMountainRange::new_with_hasher`.
+    pub fn new_with_hasher(nodes: S, entries: E, hasher: NodeHasher) -> Self {
+        StoredMmr {
+            nodes,
+            entries,
+            num_entries: 0,
+            hasher,
+        }
+    }
+
+    /// Resume from stores written by a previous `StoredMmr`. The peaks are checked against their
+    /// children (and the last entry), so that a store that was tampered with or lost writes is
+    /// detected up front.
+    pub fn open(nodes: S, entries: E) -> Result<Self, StoreError<S::Error>> {
+        Self::open_with_hasher(nodes, entries, NodeHasher::UNKEYED)
+    }
+
+    /// Same as `open`, for stores written with `hasher`. Opening with a different hasher than the
+    /// stores were written with reports the first peak as corrupt.
+    pub fn open_with_hasher(
+        nodes: S,
+        entries: E,
+        hasher: NodeHasher,
+    ) -> Result<Self, StoreError<S::Error>> {
+        let num_entries = count_entries(&entries).map_err(StoreError::Backend)?;
+        let mmr = StoredMmr {
+            nodes,
+            entries,
+            num_entries,
+            hasher,
+        };
+        if num_entries == 0 {
+            return Ok(mmr);
+        }
+
+        let mut first_leaf = 0;
+        for pos in peak_positions(num_entries) {
+            let (_, _, height) = locate_pos(pos, num_entries).unwrap();
+            let peak = mmr.node(pos)?;
+            let expected = if height == 0 {
+                hasher.hash_leaf(&mmr.entry(first_leaf)?)
+            } else {
+                let (left, right) = children_pos(pos).unwrap();
+                hasher.hash_children(&mmr.node(left)?, &mmr.node(right)?)
+            };
+            if peak != expected {
+                return Err(StoreError::CorruptNode(pos));
+            }
+            first_leaf += 1 << height;
+        }
+
+        // The last append must have been completed, i.e., its leaf commits to the entry
+        let last = num_entries - 1;
+        if mmr.node(leaf_index_to_pos(last))? != hasher.hash_leaf(&mmr.entry(last)?) {
+            return Err(StoreError::CorruptNode(leaf_index_to_pos(last)));
+        }
+        Ok(mmr)
+    }
+
+    /// Write an in-memory MMR to empty stores, keeping its hasher.
+    pub fn from_mmr(
+        mmr: &MerkleMountainRange,
+        mut nodes: S,
+        mut entries: E,
+    ) -> Result<Self, StoreError<S::Error>> {
+        let mut pos = 0;
+        for tree in mmr.trees.iter().rev().flatten() {
+            tree.write_to(&mut nodes, pos)
+                .map_err(StoreError::Backend)?;
+            pos += tree_size(tree.height() as u32);
+        }
+        let batch = mmr
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| BatchOp::Put(i as u64, entry.to_vec()))
+            .collect();
+        entries.write_batch(batch).map_err(StoreError::Backend)?;
+        Ok(StoredMmr {
+            nodes,
+            entries,
+            num_entries: mmr.entries.len() as u64,
+            hasher: mmr.hasher,
+        })
+    }
+
+    /// Read the whole MMR back into memory, for the operations only in-memory MMRs support. Every
+    /// tree is rebuilt from its leaves and checked against its stored peak.
+    pub fn to_mmr(&self) -> Result<MerkleMountainRange, StoreError<S::Error>> {
+        let mut mmr =
+            MerkleMountainRange::new_with_hasher(vec![], HashBackend::detect(), self.hasher);
+        let num_heights = (u64::BITS - self.num_entries.leading_zeros()) as usize;
+        mmr.trees = (0..=num_heights).map(|_| None).collect();
+        let mut pos = 0;
+        for height in (0..num_heights).rev() {
+            if self.num_entries & (1 << height) != 0 {
+                let tree =
+                    PerfectMerkleTree::read_from(&self.nodes, pos, height as u32, self.hasher)?;
+                pos += tree_size(height as u32);
+                mmr.trees[height] = Some(tree);
+            }
+        }
+        let entries = (0..self.num_entries)
+            .map(|index| self.entry(index))
+            .collect::<Result<Vec<_>, _>>()?;
+        mmr.entries = entries.iter().map(Vec::as_slice).collect::<LeafValues>();
+        Ok(mmr)
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.num_entries
+    }
+
+    /// How the leaves and nodes are hashed, which proofs about the MMR are verified with.
+    pub fn hasher(&self) -> &NodeHasher {
+        &self.hasher
+    }
+
+    /// Number of nodes in the conventional layout.
+    pub fn mmr_size(&self) -> u64 {
+        mmr_size_from_leaf_count(self.num_entries)
+    }
+
+    pub fn append(&mut self, entry: &[u8]) -> Result<(), StoreError<S::Error>> {
+        let mut pos = leaf_index_to_pos(self.num_entries);
+        let mut hash = self.hasher.hash_leaf(entry);
+        let mut batch = vec![BatchOp::Put(pos, hash)];
+
+        // The new leaf completes one parent per trailing one of the old number of entries, and
+        // each of those has an existing left sibling
+        for height in 0..self.num_entries.trailing_ones() {
+            let left = self.node(pos - tree_size(height))?;
+            hash = self.hasher.hash_children(&left, &hash);
+            pos += 1;
+            batch.push(BatchOp::Put(pos, hash));
+        }
+
+        self.nodes.write_batch(batch).map_err(StoreError::Backend)?;
+        self.entries
+            .put(self.num_entries, entry.to_vec())
+            .map_err(StoreError::Backend)?;
+        self.num_entries += 1;
+        Ok(())
+    }
+
+    /// Append the entries as the iterator yields them, holding none in memory past its append.
+    /// Stops at the first error, after appending the entries before it.
+    pub fn append_all<I>(&mut self, entries: I) -> Result<(), StoreError<S::Error>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        for entry in entries {
+            self.append(entry.as_ref())?;
+        }
+        Ok(())
+    }
+
+    pub fn entry(&self, index: u64) -> Result<Vec<u8>, StoreError<S::Error>> {
+        if index >= self.num_entries {
+            return Err(StoreError::IndexOutOfBounds(index));
+        }
+        self.entries
+            .get(index)
+            .map_err(StoreError::Backend)?
+            .ok_or(StoreError::MissingEntry(index))
+    }
+
+    /// Peak digests ordered left to right, as in the conventional layout.
+    pub fn standard_peaks(&self) -> Result<Vec<Digest>, StoreError<S::Error>> {
+        peak_positions(self.num_entries)
+            .into_iter()
+            .map(|pos| self.node(pos))
+            .collect()
+    }
+
+    /// Root obtained by bagging the peaks. None for an empty MMR.
+    pub fn standard_root(&self) -> Result<Option<Digest>, StoreError<S::Error>> {
+        Ok(bag_peaks_with_hasher(&self.hasher, &self.standard_peaks()?))
+    }
+
+    /// Same as `standard_root`, with the peaks bagged as `bagging` does.
+    pub fn standard_root_with_bagging(
+        &self,
+        bagging: &PeakBagging,
+    ) -> Result<Option<Digest>, StoreError<S::Error>> {
+        Ok(bagging.bag_with_hasher(&self.hasher, &self.standard_peaks()?, self.mmr_size()))
+    }
+
+    /// The commitment in the same format as `MerkleMountainRange::digests`.
+    pub fn digests(&self) -> Result<Vec<Option<Digest>>, StoreError<S::Error>> {
+        let num_heights = (u64::BITS - self.num_entries.leading_zeros()) as usize;
+        let mut digests = vec![None; num_heights + 1];
+        let mut peaks = self.standard_peaks()?.into_iter();
+        for (height, digest) in digests.iter_mut().enumerate().rev() {
+            if self.num_entries & (1 << height) != 0 {
+                *digest = peaks.next();
+            }
+        }
+        Ok(digests)
+    }
+
+    /// Same proof as `MerkleMountainRange::prove_inclusion`, fetching only the path's nodes.
+    pub fn prove_inclusion(&self, index: u64) -> Result<MmrInclusionProof, StoreError<S::Error>> {
+        let (_, first_leaf, height) = self.locate(index)?;
+        Ok(MmrInclusionProof {
+            version: PROOF_FORMAT_VERSION,
+            entry: self.entry(index)?,
+            tree_index: height.into(),
+            proof: InclusionProof::new(index - first_leaf, self.path(index)?),
+            hash_algo: None,
+        }
+        .with_hash_algo(self.hasher.algo()))
+    }
+
+    /// Same proof as `MerkleMountainRange::prove_most_recent_n_elements`, fetching only the entries
+    /// and the left siblings the partial tree's proof needs.
+    pub fn prove_most_recent_n_elements(
+        &self,
+        num_suffix_elements: u64,
+    ) -> Result<MostRecentNElementsProof, StoreError<S::Error>> {
+        if num_suffix_elements > self.num_entries {
+            return Err(StoreError::IndexOutOfBounds(num_suffix_elements));
+        }
+        let start = self.num_entries - num_suffix_elements;
+        let mut proof = MostRecentNElementsProof {
+            version: PROOF_FORMAT_VERSION,
+            entries: (start..self.num_entries)
+                .map(|index| self.entry(index))
+                .collect::<Result<_, _>>()?,
+            full_tree_indices: vec![],
+            partial_tree_proof: None,
+            hash_algo: None,
+        }
+        .with_hash_algo(self.hasher.algo());
+
+        // Trees from smallest to largest, i.e., from the most recent entries to the oldest
+        let mut remaining = num_suffix_elements;
+        for height in 0..u64::BITS {
+            if remaining == 0 {
+                break;
+            }
+            if self.num_entries & (1 << height) == 0 {
+                continue;
+            }
+            if 1 << height <= remaining {
+                remaining -= 1 << height;
+                proof.full_tree_indices.push(height.into());
+            } else {
+                let suffix_proof = self.suffix_proof(height, remaining)?;
+                proof.partial_tree_proof = Some((height.into(), suffix_proof));
+                break;
+            }
+        }
+        Ok(proof)
+    }
+
+    // Proof of the last `num_suffix_elements` leaves of the tree of the given height: the left
+    // siblings of the path to the first of them, top-down as `PerfectMerkleTree` collects them
+    fn suffix_proof(
+        &self,
+        height: u32,
+        num_suffix_elements: u64,
+    ) -> Result<SuffixProof, StoreError<S::Error>> {
+        // Smaller trees hold the most recent entries
+        let newer = self.num_entries & ((1 << height) - 1);
+        let first_leaf = self.num_entries - newer - (1 << height);
+        let first_suffix_index = (1 << height) - num_suffix_elements;
+        let root_pos = leaf_index_to_pos(first_leaf) + tree_size(height) - 1;
+        let proof = suffix_siblings(&self.nodes, root_pos, height, first_suffix_index)?;
+        Ok(SuffixProof::new(1 << height, num_suffix_elements, proof))
+    }
+
+    /// Same proof as `MerkleMountainRange::prove_inclusion_standard`, fetching only the peaks and
+    /// the path's nodes.
+    pub fn prove_inclusion_standard(
+        &self,
+        index: u64,
+    ) -> Result<StandardMerkleProof, StoreError<S::Error>> {
+        self.prove_inclusion_standard_with_bagging(index, &PeakBagging::CKB)
+    }
+
+    /// Same as `prove_inclusion_standard_with_bagging` of `MerkleMountainRange`.
+    pub fn prove_inclusion_standard_with_bagging(
+        &self,
+        index: u64,
+        bagging: &PeakBagging,
+    ) -> Result<StandardMerkleProof, StoreError<S::Error>> {
+        let (peak_index, _, _) = self.locate(index)?;
+        let peaks = self.standard_peaks()?;
+        let proof_items =
+            bagging.proof_items_with_hasher(&self.hasher, &peaks, peak_index, self.path(index)?);
+        Ok(StandardMerkleProof::new(self.mmr_size(), proof_items))
+    }
+
+    // (peak index from the left, first leaf under that peak, height of the peak) for an entry
+    fn locate(&self, index: u64) -> Result<(usize, u64, u32), StoreError<S::Error>> {
+        if index >= self.num_entries {
+            return Err(StoreError::IndexOutOfBounds(index));
+        }
+        Ok(locate_pos(leaf_index_to_pos(index), self.num_entries).unwrap())
+    }
+
+    // Siblings of the path from the entry's leaf to its peak, bottom-up
+    fn path(&self, index: u64) -> Result<Vec<Digest>, StoreError<S::Error>> {
+        let (_, _, height) = self.locate(index)?;
+        path_siblings(&self.nodes, leaf_index_to_pos(index), height)
+    }
+
+    fn node(&self, pos: u64) -> Result<Digest, StoreError<S::Error>> {
+        get_node(&self.nodes, pos)
+    }
+
+    /// Make all appends so far durable.
+    pub fn flush(&mut self) -> Result<(), StoreError<S::Error>> {
+        self.nodes.flush().map_err(StoreError::Backend)?;
+        self.entries.flush().map_err(StoreError::Backend)
+    }
+
+    /// Consume the MMR, returning its stores.
+    pub fn into_stores(self) -> (S, E) {
+        (self.nodes, self.entries)
+    }
+}
+
+// Entries are stored at 0, 1, ..., so the first missing index is found with a galloping search
+pub(crate) fn count_entries<E: NodeStore<Vec<u8>>>(entries: &E) -> Result<u64, E::Error> {
+    let mut low = 0;
+    let mut high = 1;
+    while entries.get(high - 1)?.is_some() {
+        low = high;
+        high *= 2;
+    }
+    // Entries [0, low) exist, entry high - 1 doesn't
+    let mut high = high - 1;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if entries.get(mid)?.is_some() {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+fn get_node<S: NodeStore<Digest>>(nodes: &S, pos: u64) -> Result<Digest, StoreError<S::Error>> {
+    nodes
+        .get(pos)
+        .map_err(StoreError::Backend)?
+        .ok_or(StoreError::MissingNode(pos))
+}
+
+// Siblings of the path from the leaf at `pos` up `height` levels, bottom-up
+fn path_siblings<S: NodeStore<Digest>>(
+    nodes: &S,
+    mut pos: u64,
+    height: u32,
+) -> Result<Vec<Digest>, StoreError<S::Error>> {
+    let mut siblings = Vec::with_capacity(height as usize);
+    for _ in 0..height {
+        siblings.push(get_node(nodes, sibling_pos(pos))?);
+        pos = parent_pos(pos);
+    }
+    Ok(siblings)
+}
+
+// The left siblings of the path from the root at `root_pos` to the leaf at `first_suffix_index`,
+// top-down as `PerfectMerkleTree` collects them for a suffix proof
+fn suffix_siblings<S: NodeStore<Digest>>(
+    nodes: &S,
+    mut pos: u64,
+    height: u32,
+    first_suffix_index: u64,
+) -> Result<Vec<Digest>, StoreError<S::Error>> {
+    let mut siblings = vec![];
+    for level in (0..height).rev() {
+        let (left, right) = children_pos(pos).unwrap();
+        if (first_suffix_index >> level) & 1 == 1 {
+            siblings.push(get_node(nodes, left)?);
+            pos = right;
+        } else {
+            pos = left;
+        }
+    }
+    Ok(siblings)
+}
+
+/// A perfect Merkle tree whose node hashes live in a node store, in post-order from position 0 as
+/// `PerfectMerkleTree::write_to` lays them out. Proofs only fetch the nodes they use.
+#[derive(Debug)]
+pub struct StoredMerkleTree<S = MemoryStore<Digest>> {
+    nodes: S,
+    height: u32,
+    hasher: NodeHasher,
+}
+
+impl StoredMerkleTree {
+    /// A copy of `tree` on the heap.
+    pub fn in_memory(tree: &PerfectMerkleTree) -> Self {
+        match StoredMerkleTree::from_tree(tree, MemoryStore::new()) {
+            Ok(stored) => stored,
+            Err(StoreError::Backend(e)) => match e {},
+            Err(_) => unreachable!("Writing a tree only fails in the backend"),
+        }
+    }
+}
+
+impl<S: NodeStore<Digest>> StoredMerkleTree<S> {
+    /// Write an in-memory tree to an empty store, keeping its hasher.
+    pub fn from_tree(tree: &PerfectMerkleTree, mut nodes: S) -> Result<Self, StoreError<S::Error>> {
+        tree.write_to(&mut nodes, 0).map_err(StoreError::Backend)?;
+        Ok(StoredMerkleTree {
+            nodes,
+            height: tree.height() as u32,
+            hasher: *tree.hasher(),
+        })
+    }
+
+    /// Open the tree of `2^height` leaves written to `nodes` by `from_tree`. Only the root is
+    /// fetched, and it's trusted, e.g., because it's checked against a published one.
+    pub fn open(nodes: S, height: u32, hasher: NodeHasher) -> Result<Self, StoreError<S::Error>> {
+        let tree = StoredMerkleTree {
+            nodes,
+            height,
+            hasher,
+        };
+        tree.root()?;
+        Ok(tree)
+    }
+
+    pub fn num_leaves(&self) -> u64 {
+        1 << self.height
+    }
+
+    /// How the leaves and nodes are hashed, which proofs about the tree are verified with.
+    pub fn hasher(&self) -> &NodeHasher {
+        &self.hasher
+    }
+
+    pub fn root(&self) -> Result<Digest, StoreError<S::Error>> {
+        get_node(&self.nodes, tree_size(self.height) - 1)
+    }
+
+    /// Same proof as `PerfectMerkleTree::prove_inclusion`, fetching only the path's nodes.
+    pub fn prove_inclusion(&self, leaf_index: u64) -> Result<InclusionProof, StoreError<S::Error>> {
+        if leaf_index >= self.num_leaves() {
+            return Err(StoreError::IndexOutOfBounds(leaf_index));
+        }
+        // Within the first tree, post-order positions are the same as in an MMR
+        let siblings = path_siblings(&self.nodes, leaf_index_to_pos(leaf_index), self.height)?;
+        Ok(InclusionProof::new(leaf_index, siblings))
+    }
+
+    /// Same proof as `PerfectMerkleTree::prove_most_recent_n_elements`, fetching only the left
+    /// siblings of the path to the first suffix leaf.
+    pub fn prove_most_recent_n_elements(
+        &self,
+        num_suffix_elements: u64,
+    ) -> Result<SuffixProof, StoreError<S::Error>> {
+        if num_suffix_elements == 0 || num_suffix_elements > self.num_leaves() {
+            return Err(StoreError::IndexOutOfBounds(num_suffix_elements));
+        }
+        let first_suffix_index = self.num_leaves() - num_suffix_elements;
+        let root_pos = tree_size(self.height) - 1;
+        let proof = suffix_siblings(&self.nodes, root_pos, self.height, first_suffix_index)?;
+        Ok(SuffixProof::new(
+            self.num_leaves(),
+            num_suffix_elements,
+            proof,
+        ))
+    }
+
+    /// Read the whole tree back into memory, checked against the stored root.
+    pub fn to_tree(&self) -> Result<PerfectMerkleTree, StoreError<S::Error>> {
+        PerfectMerkleTree::read_from(&self.nodes, 0, self.height, self.hasher)
+    }
+
+    /// Consume the tree, returning its store.
+    pub fn into_store(self) -> S {
+        self.nodes
+    }
+}
+
+impl PerfectMerkleTree {
+    /// Write the node hashes to `store` in post-order, starting at position `first_pos`.
+    pub fn write_to<S: NodeStore<Digest>>(
+        &self,
+        store: &mut S,
+        first_pos: u64,
+    ) -> Result<(), S::Error> {
+        let mut hashes = Vec::with_capacity(self.nodes.len());
+        self.collect_post_order(self.root(), &mut hashes);
+        let batch = hashes
+            .into_iter()
+            .zip(first_pos..)
+            .map(|(hash, pos)| BatchOp::Put(pos, hash))
+            .collect();
+        store.write_batch(batch)
+    }
+
+    /// Read back the tree of `2^height` leaves hashed with `hasher` that `write_to` wrote at
+    /// `first_pos`. The tree is rebuilt from its leaves, and its root checked against the stored
+    /// one.
+    pub fn read_from<S: NodeStore<Digest>>(
+        store: &S,
+        first_pos: u64,
+        height: u32,
+        hasher: NodeHasher,
+    ) -> Result<Self, StoreError<S::Error>> {
+        let leaves = (0..1u64 << height)
+            .map(|index| get_node(store, first_pos + leaf_index_to_pos(index)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let tree =
+            PerfectMerkleTree::from_leaf_hashes_with_hasher(leaves, HashBackend::detect(), hasher);
+        let root_pos = first_pos + tree_size(height) - 1;
+        if get_node(store, root_pos)? != tree.root().hash {
+            return Err(StoreError::CorruptNode(root_pos));
+        }
+        Ok(tree)
+    }
+
+    fn collect_post_order(&self, node: &MerkleNode, hashes: &mut Vec<Digest>) {
+        if let Some(left) = self.left(node) {
+            self.collect_post_order(left, hashes);
+        }
+        if let Some(right) = self.right(node) {
+            self.collect_post_order(right, hashes);
+        }
+        hashes.push(node.hash);
+    }
+}
@@ -0,0 +1,254 @@
+//! Hashing leaves whose values are streamed instead of held in memory.
+//!
+//! A leaf is the Blake2b256 of `LEAF_PREFIX` followed by its value, so a large value can be hashed
+//! chunk by chunk as it's read and the digest is the same as `hash_leaf` of the whole value. Only
+//! one chunk is buffered at a time, however large the value.
+//!
+//! The same goes for proofs of the most recent entries: `verify_most_recent_from_reader` checks a
+//! BCS-encoded `MostRecentNElementsProof` as it's read, keeping one chunk and one digest per tree
+//! level in memory, so ranges of millions of entries verify on small machines.
+
+use std::fmt;
+use std::io::{self, Read};
+
+use ads_core::encoding::{read_u64, read_uleb128, MAX_ULEB128_LENGTH};
+use ads_core::{HashAlgo, NodeHasher, DIGEST_LENGTH};
+use ads_verifier::limits::{ProofCost, VerificationLimits};
+use ads_verifier::streaming::MostRecentVerifier;
+
+use crate::digest::Digest;
+use crate::error::VerificationError;
+use crate::{MmrCommitment, SuffixProof};
+
+/// Bytes read from the stream at a time.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The leaf hash of everything `reader` yields until its end, equal to `hash_leaf` of it.
+pub fn hash_leaf_from_reader<R: Read>(reader: R) -> io::Result<Digest> {
+    hash_leaf_from_reader_with_hasher(&NodeHasher::UNKEYED, reader)
+}
+
+/// Same as `hash_leaf_from_reader`, with the leaf hash of `hasher`.
+pub fn hash_leaf_from_reader_with_hasher<R: Read>(
+    hasher: &NodeHasher,
+    mut reader: R,
+) -> io::Result<Digest> {
+    let mut hasher = hasher.leaf_hasher();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => return Ok(hasher.finalize()),
+            Ok(n) => hasher.update(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Same as `hash_leaf_from_reader_with_hasher`, for an asynchronous stream.
+#[cfg(feature = "async")]
+pub async fn hash_leaf_from_async_reader<R: futures_io::AsyncRead + Unpin>(
+    hasher: &NodeHasher,
+    mut reader: R,
+) -> io::Result<Digest> {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    let mut hasher = hasher.leaf_hasher();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        match poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut chunk)).await {
+            Ok(0) => return Ok(hasher.finalize()),
+            Ok(n) => hasher.update(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Longest encoding of what follows the entries in a `MostRecentNElementsProof`: the full tree
+/// indices and the partial tree's left siblings, at most one of each per height, and the algorithm.
+pub const MAX_PROOF_TAIL_LENGTH: usize = 4096;
+
+/// Why a streamed proof doesn't verify.
+#[derive(Debug)]
+pub enum StreamVerificationError {
+    Io(io::Error),
+    /// The stream isn't the BCS encoding of a `MostRecentNElementsProof`
+    Malformed,
+    Verification(VerificationError),
+}
+
+impl fmt::Display for StreamVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamVerificationError::Io(e) => write!(f, "I/O error: {}", e),
+            StreamVerificationError::Malformed => write!(f, "Malformed proof"),
+            StreamVerificationError::Verification(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for StreamVerificationError {}
+
+impl From<io::Error> for StreamVerificationError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => StreamVerificationError::Malformed,
+            _ => StreamVerificationError::Io(e),
+        }
+    }
+}
+
+impl From<VerificationError> for StreamVerificationError {
+    fn from(e: VerificationError) -> Self {
+        StreamVerificationError::Verification(e)
+    }
+}
+
+/// Verify the BCS-encoded `MostRecentNElementsProof` that `reader` yields against `commitment`,
+/// hashing each entry chunk by chunk as it's read. Returns the number of entries proven.
+pub fn verify_most_recent_from_reader<R: Read>(
+    commitment: &MmrCommitment,
+    reader: R,
+) -> Result<u64, StreamVerificationError> {
+    verify_most_recent_from_reader_with_limits(commitment, reader, &VerificationLimits::UNLIMITED)
+}
+
+/// Same as `verify_most_recent_from_reader`, rejecting the proof before hashing any entry if the
+/// number of entries it announces or the size of the MMR exceeds `limits`.
+pub fn verify_most_recent_from_reader_with_limits<R: Read>(
+    commitment: &MmrCommitment,
+    mut reader: R,
+    limits: &VerificationLimits,
+) -> Result<u64, StreamVerificationError> {
+    let hasher = NodeHasher::new(commitment.hash_algo());
+    let [version] = read_array(&mut reader)?;
+    ads_verifier::version::check_version(version)?;
+    let num_entries = read_length(&mut reader)? as u64;
+    // The left siblings come after the entries, and are bounded by `MAX_PROOF_TAIL_LENGTH`
+    limits.check(&ProofCost {
+        proof_elements: 0,
+        entries: num_entries,
+        tree_size: commitment.num_entries(),
+    })?;
+    let mut verifier = MostRecentVerifier::new_with_hasher(commitment, hasher, num_entries)?;
+
+    let mut chunk = vec![0; CHUNK_SIZE];
+    for _ in 0..num_entries {
+        let mut remaining = read_length(&mut reader)?;
+        let mut leaf_hasher = hasher.leaf_hasher();
+        while remaining > 0 {
+            let n = remaining.min(CHUNK_SIZE);
+            reader.read_exact(&mut chunk[..n])?;
+            leaf_hasher.update(&chunk[..n]);
+            remaining -= n;
+        }
+        verifier.push_leaf_hash(leaf_hasher.finalize())?;
+    }
+
+    // The rest is small, and parsed as a whole
+    let mut tail = Vec::new();
+    reader
+        .take(MAX_PROOF_TAIL_LENGTH as u64 + 1)
+        .read_to_end(&mut tail)?;
+    if tail.len() > MAX_PROOF_TAIL_LENGTH {
+        return Err(StreamVerificationError::Malformed);
+    }
+    let (full_tree_indices, partial_tree_proof, hash_algo) =
+        parse_tail(&tail).ok_or(StreamVerificationError::Malformed)?;
+    if full_tree_indices != verifier.full_tree_indices() {
+        return Err(VerificationError::NonCanonicalProof.into());
+    }
+    let expected = commitment.hash_algo();
+    let actual = hash_algo.unwrap_or_default();
+    if actual != expected {
+        return Err(VerificationError::HashAlgoMismatch { expected, actual }.into());
+    }
+    let partial = match partial_tree_proof {
+        Some((tree_index, proof)) => {
+            // The verifier knows which tree the partial one is, from the commitment
+            if verifier.partial_tree_index() != Some(tree_index) {
+                return Err(VerificationError::NonCanonicalProof.into());
+            }
+            Some(proof)
+        }
+        None => None,
+    };
+    verifier.finish(partial.as_ref())?;
+    Ok(num_entries)
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+// A BCS sequence length, read a byte at a time so that nothing past it is consumed
+fn read_length<R: Read>(reader: &mut R) -> Result<usize, StreamVerificationError> {
+    let mut encoded = [0; MAX_ULEB128_LENGTH];
+    for length in 1..=MAX_ULEB128_LENGTH {
+        let [byte] = read_array(reader)?;
+        encoded[length - 1] = byte;
+        if byte & 0x80 == 0 {
+            return read_uleb128(&encoded[..length])
+                .map(|(value, _)| value)
+                .ok_or(StreamVerificationError::Malformed);
+        }
+    }
+    Err(StreamVerificationError::Malformed)
+}
+
+type ProofTail = (Vec<u64>, Option<(u64, SuffixProof)>, Option<HashAlgo>);
+
+// The fields of a `MostRecentNElementsProof` after its entries
+fn parse_tail(bytes: &[u8]) -> Option<ProofTail> {
+    let (count, mut bytes) = read_uleb128(bytes)?;
+    let mut full_tree_indices = Vec::new();
+    for _ in 0..count {
+        let (tree_index, rest) = read_u64(bytes)?;
+        full_tree_indices.push(tree_index);
+        bytes = rest;
+    }
+    let (partial_tree_proof, bytes) = match bytes.split_first()? {
+        (0, rest) => (None, rest),
+        (1, rest) => {
+            let (tree_index, rest) = read_u64(rest)?;
+            let (&version, rest) = rest.split_first()?;
+            let (num_suffix_elements, rest) = read_u64(rest)?;
+            let (count, mut rest) = read_uleb128(rest)?;
+            let mut proof = Vec::new();
+            for _ in 0..count {
+                let (digest, more) = rest.split_at_checked(DIGEST_LENGTH)?;
+                proof.push(Digest::from_slice(digest)?);
+                rest = more;
+            }
+            let (num_leaves, rest) = match rest.split_first()? {
+                (0, rest) => (None, rest),
+                (1, rest) => {
+                    let (num_leaves, rest) = read_u64(rest)?;
+                    (Some(num_leaves), rest)
+                }
+                _ => return None,
+            };
+            let proof = SuffixProof {
+                version,
+                num_suffix_elements,
+                proof,
+                num_leaves,
+            };
+            (Some((tree_index, proof)), rest)
+        }
+        _ => return None,
+    };
+    let hash_algo = match bytes.split_first() {
+        Some((0, [])) => None,
+        Some((1, rest)) => match read_uleb128(rest)? {
+            (variant, []) => Some(*HashAlgo::ALL.get(variant)?),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((full_tree_indices, partial_tree_proof, hash_algo))
+}
@@ -0,0 +1,113 @@
+//! Catching up with a remote MMR from its commitment.
+//!
+//! An MMR that only appended to the local one differs from it by its most recent entries, and the
+//! local peaks are all that's needed to hash them in. So the plan is just the range of entries to
+//! fetch, no interior node, and applying it checks that the fetched entries lead exactly to the
+//! remote commitment before adding any of them.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::error::VerificationError;
+use crate::{MerkleMountainRange, MmrCommitment, MmrFollower};
+
+/// What to fetch to bring the local MMR to a remote commitment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncPlan {
+    /// Indices of the entries the local MMR lacks, empty if it's up to date
+    pub missing: Range<u64>,
+    pub target: MmrCommitment,
+}
+
+impl SyncPlan {
+    pub fn is_up_to_date(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncError {
+    /// The remote MMR holds fewer entries than the local one, so it can't extend it
+    RemoteBehind { local: u64, remote: u64 },
+    /// The remote MMR has as many entries as the local one but different peaks
+    Diverged,
+    /// The fetched entries don't extend the local MMR to the target
+    Verification(VerificationError),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::RemoteBehind { local, remote } => write!(
+                f,
+                "The remote MMR holds {} entries, fewer than the {} held locally",
+                remote, local
+            ),
+            SyncError::Diverged => write!(f, "The remote MMR holds different entries"),
+            SyncError::Verification(e) => write!(f, "Fetched entries don't verify: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+impl From<VerificationError> for SyncError {
+    fn from(e: VerificationError) -> Self {
+        SyncError::Verification(e)
+    }
+}
+
+impl MerkleMountainRange {
+    /// The entries to fetch to catch up with the MMR committed to by `remote`.
+    pub fn plan_sync(&self, remote: &MmrCommitment) -> Result<SyncPlan, SyncError> {
+        let local = self.num_entries();
+        let remote_num_entries = remote.num_entries();
+        if remote_num_entries < local {
+            return Err(SyncError::RemoteBehind {
+                local,
+                remote: remote_num_entries,
+            });
+        }
+        if remote_num_entries == local && *remote != self.commitment() {
+            return Err(SyncError::Diverged);
+        }
+        Ok(SyncPlan {
+            missing: local..remote_num_entries,
+            target: remote.clone(),
+        })
+    }
+
+    /// Append the `entries` fetched for `plan`, after checking they lead to its target. On error,
+    /// nothing is appended.
+    pub fn apply_sync<E: AsRef<[u8]>>(
+        &mut self,
+        plan: &SyncPlan,
+        entries: &[E],
+    ) -> Result<(), SyncError> {
+        if plan.missing.start != self.num_entries() {
+            return Err(SyncError::Verification(
+                VerificationError::WrongNumberOfEntries {
+                    expected: plan.missing.start,
+                    actual: self.num_entries(),
+                },
+            ));
+        }
+        let num_missing = plan.missing.end - plan.missing.start;
+        if entries.len() as u64 != num_missing {
+            return Err(SyncError::Verification(
+                VerificationError::WrongNumberOfEntries {
+                    expected: num_missing,
+                    actual: entries.len() as u64,
+                },
+            ));
+        }
+        if plan.is_up_to_date() {
+            return Ok(());
+        }
+        MmrFollower::new(self.commitment()).append(entries, &plan.target)?;
+        for entry in entries {
+            self.add_entry(entry.as_ref());
+        }
+        Ok(())
+    }
+}
@@ -1,13 +1,56 @@
 // Write tests for the Merkle Tree and Merkle Forest
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::useless_vec)]
 mod tests {
-    use crate::hex_string;
+    use crate::anchor::AnchoredLog;
+    use crate::appender::MmrAppender;
+    use crate::audit::{audit, AuditError, MmrAuditor};
+    use crate::batch::BatchVerificationError;
+    use crate::bitcoin::{merkle_root, BitcoinMerkleTree};
+    use crate::borrowed::{InclusionProofRef, SuffixProofRef};
+    use crate::digest::Digest;
+    use crate::error::VerificationError;
+    use crate::ethereum::{keccak256, process_sorted_proof, EthereumMerkleTree, PairOrder};
+    use crate::forest::{Forest, ForestError, LogRecord};
+    use crate::hash_leaf;
+    use crate::hashing::HashBackend;
+    use crate::headers::{HeaderedEntry, HeaderedLog};
+    use crate::lazy::LazyMerkleMountainRange;
+    use crate::memory::MemoryUsage;
     use crate::num_trees;
+    use crate::ots::{OtsChain, OtsError, OtsOp};
+    use crate::provider::{ProofProvider, ProviderError};
+    use crate::snapshot::SnapshotError;
+    use crate::standard::{
+        children_pos, leaf_count_from_mmr_size, leaf_index_to_pos, mmr_size_from_leaf_count,
+        parent_pos, peak_positions, pos_height, pos_to_leaf_index, sibling_pos, BaggingOrder,
+        PeakBagging,
+    };
+    use crate::store::{StoreError, StoredMerkleTree, StoredMmr};
+    use crate::stream::{hash_leaf_from_reader, CHUNK_SIZE};
+    use crate::sync::SyncError;
+    use crate::timed::{TimeRangeProof, TimestampError, TimestampedEntry, TimestampedLog};
+    use crate::values::LeafValues;
+    use crate::CommitmentError;
+    use crate::ConsistencyProof;
+    use crate::InclusionProof;
     use crate::MerkleMountainRange;
+    use crate::MerkleNode;
+    use crate::MmrCommitment;
+    use crate::MmrFollower;
+    use crate::MostRecentNElementsProof;
+    use crate::NodeHasher;
     use crate::PerfectMerkleTree;
+    use crate::SuffixProof;
+    use crate::EMPTY_ROOT;
+    use crate::PROOF_FORMAT_VERSION;
+    use ads_core::HashAlgo;
+    use ads_core::{ParseDigestError, Proof};
+    use ads_store::cache::CachedStore;
+    use ads_store::{MemoryStore, NodeStore};
 
     const MERKLE_8_DIGEST: &str =
-        "85718f77efd6444907af1d47bbf32d3ebffb616f70df03f6649770aba142d689";
+        "fc7f8c273a9342ab427f3d9122ba809c97a15b8c3458cd80b1f26b0f6e9af562";
 
     #[test]
     fn test_perfect_merkle_tree() {
@@ -17,11 +60,22 @@ mod tests {
 
         let merkle_tree = PerfectMerkleTree::new(data_blocks);
         merkle_tree.pretty_print();
-        assert_eq!(hex_string(merkle_tree.digest()), MERKLE_8_DIGEST);
+        assert_eq!(merkle_tree.digest().to_string(), MERKLE_8_DIGEST);
 
         let data_blocks_1 = b"block1";
         let merkle_tree_1 = PerfectMerkleTree::new(vec![data_blocks_1]);
-        assert_eq!(b"block1", merkle_tree_1.digest());
+        assert_eq!(merkle_tree_1.digest(), hash_leaf(b"block1"));
+
+        // A leaf holding the encoding of two children doesn't hash like their parent
+        let children = [
+            &[0x20][..],
+            &hash_leaf(b"block1").0,
+            &[0x20],
+            &hash_leaf(b"block2").0,
+        ]
+        .concat();
+        let merkle_tree_2 = PerfectMerkleTree::new(vec![b"block1", b"block2"]);
+        assert_ne!(merkle_tree_2.digest(), hash_leaf(&children));
     }
 
     #[test]
@@ -128,7 +182,11 @@ mod tests {
         assert_eq!(merkle_forest_inc.trees.last().unwrap().is_none(), true);
         assert_eq!(merkle_forest_inc.trees[3].is_some(), true);
         assert_eq!(
-            hex_string(merkle_forest_inc.trees[3].as_ref().unwrap().digest()),
+            merkle_forest_inc.trees[3]
+                .as_ref()
+                .unwrap()
+                .digest()
+                .to_string(),
             MERKLE_8_DIGEST
         );
     }
@@ -140,10 +198,7 @@ mod tests {
 
         let data_blocks: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
         let mut merkle_forest = MerkleMountainRange::new(data_blocks);
-        assert_eq!(
-            merkle_forest.trees.len(),
-            num_trees(num_values.try_into().unwrap())
-        );
+        assert_eq!(merkle_forest.trees.len(), num_trees(num_values.into()));
         assert_eq!(
             merkle_forest.trees.iter().filter(|&x| x.is_some()).count(),
             20
@@ -152,7 +207,7 @@ mod tests {
         merkle_forest.add_entry(b"newblock");
         assert_eq!(
             merkle_forest.trees.len(),
-            num_trees((num_values + 1).try_into().unwrap())
+            num_trees((num_values + 1).into())
         );
         assert_eq!(
             merkle_forest.trees.iter().filter(|&x| x.is_some()).count(),
@@ -162,6 +217,14 @@ mod tests {
         assert_eq!(merkle_forest.trees[20].is_some(), true);
     }
 
+    #[test]
+    fn test_num_trees_beyond_32_bits() {
+        assert_eq!(num_trees(1), 2);
+        assert_eq!(num_trees((1 << 32) - 1), 33);
+        assert_eq!(num_trees(1 << 32), 34);
+        assert_eq!(num_trees(u64::MAX), 65);
+    }
+
     #[test]
     fn test_suffix_proof_perfect_tree() {
         // Test with a tree of 8 elements
@@ -374,4 +437,5292 @@ mod tests {
 
         // Skip sizes that require partial trees due to API mismatch
     }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        let data_blocks: Vec<&[u8]> = vec![
+            b"block1", b"block2", b"block3", b"block4", b"block5", b"block6", b"block7", b"block8",
+        ];
+        let tree = PerfectMerkleTree::new(data_blocks.clone());
+        for (i, block) in data_blocks.iter().enumerate() {
+            let proof = tree.prove_inclusion(i as u64);
+            assert_eq!(proof.siblings.len(), 3);
+            tree.verify_inclusion_proof(block, &proof);
+        }
+
+        let proof = tree.prove_inclusion(2);
+        let result = std::panic::catch_unwind(|| {
+            tree.verify_inclusion_proof(b"block4", &proof);
+        });
+        assert!(
+            result.is_err(),
+            "Expected verification to fail with wrong leaf"
+        );
+
+        let strings: Vec<String> = (1..=13).map(|i| format!("block{}", i)).collect();
+        let mmr = MerkleMountainRange::new(strings.iter().map(|s| s.as_bytes()).collect());
+        for (i, entry) in strings.iter().enumerate() {
+            let proof = mmr.prove_inclusion(i as u64);
+            assert_eq!(proof.entry, entry.as_bytes());
+            mmr.verify_inclusion(&proof);
+        }
+        // 13 = 8 + 4 + 1, so the entry at index 9 lives in the 4-leaf tree
+        assert_eq!(mmr.prove_inclusion(9).tree_index, 2);
+        assert_eq!(mmr.prove_inclusion(9).proof.leaf_index, 1);
+    }
+
+    #[test]
+    fn test_ots_chain_export() {
+        let strings: Vec<String> = (1..=7).map(|i| format!("block{}", i)).collect();
+        for (algo, hash_op) in [
+            (HashAlgo::Sha256, OtsOp::Sha256),
+            (HashAlgo::Keccak256, OtsOp::Keccak256),
+        ] {
+            let mmr = MerkleMountainRange::new_with_hasher(
+                strings.iter().map(|s| s.as_bytes()).collect(),
+                HashBackend::detect(),
+                NodeHasher::new(algo),
+            );
+            for i in 0..strings.len() {
+                let proof = mmr.prove_inclusion(i as u64);
+                let chain = OtsChain::from_inclusion_proof(&proof).unwrap();
+                assert_eq!(chain.ops[1], hash_op);
+                let peak = mmr.trees[proof.tree_index as usize]
+                    .as_ref()
+                    .unwrap()
+                    .digest();
+                assert_eq!(chain.evaluate(), peak.0);
+            }
+        }
+
+        // Blake2b256 has no OTS op, so the chain isn't built at all
+        let mmr = MerkleMountainRange::new(strings.iter().map(|s| s.as_bytes()).collect());
+        assert_eq!(
+            OtsChain::from_inclusion_proof(&mmr.prove_inclusion(0)),
+            Err(OtsError::UnsupportedHashAlgo(HashAlgo::Blake2b256))
+        );
+
+        // Nor for keyed MMRs, whose proofs look like plain Blake2b256 ones, nor with a hasher
+        // that isn't the proof's
+        let hasher = NodeHasher::keyed([7; 32]);
+        let mmr = MerkleMountainRange::new_with_hasher(
+            strings.iter().map(|s| s.as_bytes()).collect(),
+            HashBackend::detect(),
+            hasher,
+        );
+        assert_eq!(
+            OtsChain::from_inclusion_proof_with_hasher(&hasher, &mmr.prove_inclusion(0)),
+            Err(OtsError::KeyedHasher)
+        );
+        let proof = mmr.prove_inclusion(0).with_hash_algo(HashAlgo::Sha256);
+        assert_eq!(
+            OtsChain::from_inclusion_proof_with_hasher(&NodeHasher::UNKEYED, &proof),
+            Err(OtsError::HashAlgoMismatch {
+                expected: HashAlgo::Sha256,
+                actual: HashAlgo::Blake2b256,
+            })
+        );
+
+        let chain = OtsChain {
+            msg: b"hello".to_vec(),
+            ops: vec![
+                OtsOp::Prepend(vec![0x01]),
+                OtsOp::Append(vec![0x02, 0x03]),
+                OtsOp::Sha256,
+            ],
+        };
+        assert_eq!(
+            chain.serialize_ops(),
+            vec![0xf1, 0x01, 0x01, 0xf0, 0x02, 0x02, 0x03, 0x08]
+        );
+        assert_eq!(
+            chain.prepend_to_timestamp(&[0x00]),
+            vec![0xf1, 0x01, 0x01, 0xf0, 0x02, 0x02, 0x03, 0x08, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_standard_positions() {
+        // Leaf positions of the conventional layout: 0 1 3 4 7 8 10 11 15 ...
+        let leaf_positions: Vec<u64> = (0..9).map(leaf_index_to_pos).collect();
+        assert_eq!(leaf_positions, vec![0, 1, 3, 4, 7, 8, 10, 11, 15]);
+        for (index, &pos) in leaf_positions.iter().enumerate() {
+            assert_eq!(pos_to_leaf_index(pos), Some(index as u64));
+        }
+        assert_eq!(pos_to_leaf_index(2), None);
+        assert_eq!(pos_to_leaf_index(6), None);
+        assert_eq!(pos_to_leaf_index(14), None);
+
+        assert_eq!(mmr_size_from_leaf_count(7), 11);
+        assert_eq!(mmr_size_from_leaf_count(8), 15);
+        assert_eq!(leaf_count_from_mmr_size(11), Some(7));
+        assert_eq!(leaf_count_from_mmr_size(12), None);
+        assert_eq!(peak_positions(7), vec![6, 9, 10]);
+        assert_eq!(peak_positions(11), vec![14, 17, 18]);
+    }
+
+    #[test]
+    fn test_standard_navigation() {
+        let heights: Vec<u32> = (0..15).map(pos_height).collect();
+        assert_eq!(heights, vec![0, 0, 1, 0, 0, 1, 2, 0, 0, 1, 0, 0, 1, 2, 3]);
+        assert_eq!(parent_pos(0), 2);
+        assert_eq!(parent_pos(1), 2);
+        assert_eq!(parent_pos(5), 6);
+        assert_eq!(parent_pos(6), 14);
+        assert_eq!(sibling_pos(2), 5);
+        assert_eq!(sibling_pos(5), 2);
+        assert_eq!(sibling_pos(16), 15);
+        assert_eq!(children_pos(6), Some((2, 5)));
+        assert_eq!(children_pos(14), Some((6, 13)));
+        assert_eq!(children_pos(3), None);
+
+        // Every node below the single peak of 64 leaves is a child of its parent, next to its
+        // sibling
+        let mmr_size = mmr_size_from_leaf_count(64);
+        for pos in 0..mmr_size - 1 {
+            let (left, right) = children_pos(parent_pos(pos)).unwrap();
+            assert!(parent_pos(pos) < mmr_size);
+            assert!(
+                (left, right) == (pos, sibling_pos(pos))
+                    || (left, right) == (sibling_pos(pos), pos)
+            );
+            assert_eq!(pos_height(parent_pos(pos)), pos_height(pos) + 1);
+        }
+        for index in 0..64 {
+            assert_eq!(pos_height(leaf_index_to_pos(index)), 0);
+        }
+    }
+
+    #[test]
+    fn test_standard_proofs() {
+        let strings: Vec<String> = (1..=11).map(|i| format!("block{}", i)).collect();
+        let mmr = MerkleMountainRange::new(strings.iter().map(|s| s.as_bytes()).collect());
+        let root = mmr.standard_root().unwrap();
+        assert_eq!(mmr.mmr_size(), 19);
+
+        for (index, entry) in strings.iter().enumerate() {
+            let (tree_index, leaf_index) = mmr.locate(index as u64);
+            let pos = mmr.standard_pos_of(tree_index, leaf_index);
+            assert_eq!(pos, leaf_index_to_pos(index as u64));
+            assert_eq!(mmr.locate_standard_pos(pos), Some((tree_index, leaf_index)));
+
+            let proof = mmr.prove_inclusion_standard(index as u64);
+            assert!(proof.verify(&root, pos, entry.as_bytes()));
+            assert!(!proof.verify(&root, pos, b"wrong"));
+            assert!(!proof.verify(&root, pos + 1, entry.as_bytes()));
+        }
+        assert_eq!(mmr.locate_standard_pos(2), None);
+        assert_eq!(mmr.locate_standard_pos(19), None);
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_borsh_roundtrip() {
+        use crate::{MmrInclusionProof, MostRecentNElementsProof};
+
+        let strings: Vec<String> = (1..=7).map(|i| format!("block{}", i)).collect();
+        let mmr = MerkleMountainRange::new(strings.iter().map(|s| s.as_bytes()).collect());
+
+        let proof = mmr.prove_most_recent_n_elements(5);
+        let bytes = borsh::to_vec(&proof).unwrap();
+        let decoded: MostRecentNElementsProof = borsh::from_slice(&bytes).unwrap();
+        mmr.verify_most_recent_n_elements(&decoded);
+
+        let proof = mmr.prove_inclusion(3);
+        let bytes = borsh::to_vec(&proof).unwrap();
+        let decoded: MmrInclusionProof = borsh::from_slice(&bytes).unwrap();
+        mmr.verify_inclusion(&decoded);
+
+        // Decoding checks what the serde decoding checks
+        let mut malformed = proof.clone();
+        malformed.proof.siblings.push(Digest::default());
+        let bytes = borsh::to_vec(&malformed).unwrap();
+        assert!(borsh::from_slice::<MmrInclusionProof>(&bytes).is_err());
+
+        let mut commitment = mmr.commitment();
+        commitment.peaks.pop();
+        let bytes = borsh::to_vec(&commitment).unwrap();
+        assert!(borsh::from_slice::<MmrCommitment>(&bytes).is_err());
+        assert!(borsh::from_slice::<ads_verifier::Frontier>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_node_arena() {
+        let strings: Vec<String> = (1..=8).map(|i| format!("block{}", i)).collect();
+        let blocks: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let tree = PerfectMerkleTree::new(blocks.clone());
+        assert_eq!(tree.nodes.len(), 15);
+        assert_eq!(tree.root().height, 3);
+
+        // Trees grown by merging in the MMR have the same shape, laid out differently
+        let mmr = MerkleMountainRange::new(blocks);
+        let merged = mmr.trees[3].as_ref().unwrap();
+        assert_eq!(merged.nodes.len(), 15);
+        assert_eq!(merged.digest(), tree.digest());
+        for node in &merged.nodes {
+            match (node.left, node.right) {
+                (Some(left), Some(right)) => {
+                    assert_eq!(merged.nodes[left].height + 1, node.height);
+                    assert_eq!(merged.nodes[right].height + 1, node.height);
+                }
+                (None, None) => assert_eq!(node.height, 0),
+                _ => panic!("Node with a single child"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_backends() {
+        let strings: Vec<String> = (1..=1000).map(|i| format!("block{}", i)).collect();
+        let blocks: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+
+        let scalar = MerkleMountainRange::new_with_backend(blocks.clone(), HashBackend::Scalar);
+        let simd = MerkleMountainRange::new_with_backend(blocks.clone(), HashBackend::MultiBuffer);
+        assert_eq!(scalar.digests(), simd.digests());
+
+        // Building in one go matches adding entries one at a time
+        let mut incremental = MerkleMountainRange::new(vec![]);
+        for block in &blocks {
+            incremental.add_entry(block);
+        }
+        assert_eq!(scalar.digests(), incremental.digests());
+        assert_eq!(scalar.trees.len(), incremental.trees.len());
+
+        let tree =
+            PerfectMerkleTree::new_with_backend(blocks[..512].to_vec(), HashBackend::MultiBuffer);
+        assert_eq!(tree.digest(), scalar.trees[9].as_ref().unwrap().digest());
+    }
+
+    #[test]
+    fn test_leaf_values() {
+        let mut values = LeafValues::new();
+        assert!(values.is_empty());
+        values.push(b"block1");
+        values.push(b"");
+        values.push(b"block33");
+        assert_eq!(values.len(), 3);
+        assert_eq!(values.get(0), b"block1");
+        assert_eq!(values.get(1), b"");
+        assert_eq!(values.get(2), b"block33");
+        assert_eq!(
+            values.to_vecs(1..3),
+            vec![b"".to_vec(), b"block33".to_vec()]
+        );
+
+        let mmr = MerkleMountainRange::new(vec![b"block1", b"block2", b"block3"]);
+        assert_eq!(
+            mmr.entries.iter().collect::<Vec<_>>(),
+            vec![b"block1", b"block2", b"block3"]
+        );
+    }
+
+    #[test]
+    fn test_build_from_leaf_hashes() {
+        let data_blocks: Vec<&[u8]> = vec![
+            b"block1", b"block2", b"block3", b"block4", b"block5", b"block6", b"block7", b"block8",
+        ];
+        let leaves: Vec<Digest> = data_blocks.iter().map(|b| hash_leaf(b)).collect();
+        let tree = PerfectMerkleTree::from_leaf_hashes(leaves);
+        assert_eq!(tree.digest().to_string(), MERKLE_8_DIGEST);
+        assert_eq!(tree.digest(), PerfectMerkleTree::new(data_blocks).digest());
+    }
+
+    #[test]
+    fn test_borrowed_proofs() {
+        let strings: Vec<String> = (1..=16).map(|i| format!("block{}", i)).collect();
+        let blocks: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let tree = PerfectMerkleTree::new(blocks.clone());
+
+        for n in 1..=16 {
+            let bytes = bcs::to_bytes(&tree.prove_most_recent_n_elements(n)).unwrap();
+            let proof = SuffixProofRef::from_bcs(&bytes).unwrap();
+            assert_eq!(proof.num_suffix_elements, n);
+            assert!(proof.verify(&tree.digest(), 16, &blocks[16 - n as usize..]));
+            let mut tampered = blocks[16 - n as usize..].to_vec();
+            tampered[0] = b"tampered";
+            assert!(!proof.verify(&tree.digest(), 16, &tampered));
+        }
+
+        for (i, block) in blocks.iter().enumerate() {
+            let bytes = bcs::to_bytes(&tree.prove_inclusion(i as u64)).unwrap();
+            let proof = InclusionProofRef::from_bcs(&bytes).unwrap();
+            assert!(proof.verify(&tree.digest(), 16, block));
+            assert!(!proof.verify(&tree.digest(), 16, b"wrong"));
+            assert!(!proof.verify(&tree.digest(), 8, block));
+        }
+
+        // Truncated and malformed buffers are rejected
+        let bytes = bcs::to_bytes(&tree.prove_inclusion(3)).unwrap();
+        assert!(InclusionProofRef::from_bcs(&bytes[..bytes.len() - 1]).is_none());
+        let mut overlong = bytes.clone();
+        overlong[9] |= 0x80;
+        overlong.insert(10, 0);
+        assert!(bcs::from_bytes::<InclusionProof>(&overlong).is_err());
+        assert!(InclusionProofRef::from_bcs(&overlong).is_none());
+        let mut bytes = bytes;
+        bytes[9] = 0x7f;
+        assert!(InclusionProofRef::from_bcs(&bytes).is_none());
+    }
+
+    #[test]
+    fn test_peaks_only_appender() {
+        let mut appender = MmrAppender::new();
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        assert_eq!(appender.digests(), mmr.digests());
+        assert_eq!(appender.standard_root(), None);
+
+        for i in 1..=100 {
+            let entry = format!("block{}", i);
+            appender.append(entry.as_bytes());
+            mmr.add_entry(entry.as_bytes());
+            assert_eq!(appender.num_entries(), i);
+            assert_eq!(appender.digests(), mmr.digests());
+            assert_eq!(appender.standard_root(), mmr.standard_root());
+        }
+
+        // Resuming from a full MMR yields the same commitments
+        let mut resumed = MmrAppender::from_mmr(&mmr);
+        resumed.append(b"block101");
+        appender.append(b"block101");
+        assert_eq!(resumed, appender);
+    }
+
+    #[test]
+    fn test_try_verify() {
+        let mmr = MerkleMountainRange::new(vec![
+            b"block1", b"block2", b"block3", b"block4", b"block5", b"block6", b"block7",
+        ]);
+        let proof = mmr.prove_most_recent_n_elements(5);
+        assert_eq!(mmr.try_verify_most_recent_n_elements(&proof), Ok(()));
+
+        let mut tampered = proof.clone();
+        tampered.entries[0] = b"tampered".to_vec();
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&tampered),
+            Err(VerificationError::RootMismatch)
+        );
+
+        let mut tampered = proof.clone();
+        tampered.full_tree_indices[0] = 10;
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&tampered),
+            Err(VerificationError::TreeIndexOutOfBounds(10))
+        );
+
+        // Missing trees past the largest one aren't part of the commitment
+        let mut tampered = proof;
+        tampered.full_tree_indices[0] = 3;
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&tampered),
+            Err(VerificationError::TreeIndexOutOfBounds(3))
+        );
+
+        let mut proof = mmr.prove_inclusion(2);
+        proof.proof.siblings.pop();
+        assert_eq!(
+            mmr.try_verify_inclusion(&proof),
+            Err(VerificationError::NotEnoughProofElements)
+        );
+    }
+
+    #[test]
+    fn test_batch_verification() {
+        let strings: Vec<String> = (1..=100).map(|i| format!("block{}", i)).collect();
+        let mmr = MerkleMountainRange::new(strings.iter().map(|s| s.as_bytes()).collect());
+
+        let mut proofs: Vec<_> = (1..=100)
+            .map(|n| mmr.prove_most_recent_n_elements(n))
+            .collect();
+        assert_eq!(mmr.verify_batch_most_recent_n_elements(&proofs), Ok(()));
+
+        proofs[10].entries[0] = b"tampered".to_vec();
+        proofs[42].entries.pop();
+        let Err(BatchVerificationError { failures }) =
+            mmr.verify_batch_most_recent_n_elements(&proofs)
+        else {
+            panic!("Expected batch verification to fail");
+        };
+        assert_eq!(
+            failures.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![10, 42]
+        );
+        assert_eq!(failures[0].1, VerificationError::RootMismatch);
+
+        let mut proofs: Vec<_> = (0..100).map(|i| mmr.prove_inclusion(i)).collect();
+        assert_eq!(mmr.verify_batch_inclusion(&proofs), Ok(()));
+        proofs[99].entry = b"tampered".to_vec();
+        assert_eq!(
+            mmr.verify_batch_inclusion(&proofs),
+            Err(BatchVerificationError {
+                failures: vec![(99, VerificationError::RootMismatch)]
+            })
+        );
+
+        // A verifier only needs the commitment, hashed with whichever algorithm it names
+        let mmr = MerkleMountainRange::new_with_hasher(
+            strings.iter().map(|s| s.as_bytes()).collect(),
+            HashBackend::detect(),
+            NodeHasher::new(HashAlgo::Sha256),
+        );
+        let commitment = mmr.commitment();
+        let mut proofs: Vec<_> = (0..100).map(|i| mmr.prove_inclusion(i)).collect();
+        assert_eq!(commitment.verify_batch_inclusion(&proofs), Ok(()));
+        let suffixes: Vec<_> = (1..=10)
+            .map(|n| mmr.prove_most_recent_n_elements(n))
+            .collect();
+        assert_eq!(
+            commitment.verify_batch_most_recent_n_elements(&suffixes),
+            Ok(())
+        );
+        proofs[7].entry = b"tampered".to_vec();
+        assert_eq!(
+            commitment.verify_batch_inclusion(&proofs),
+            Err(BatchVerificationError {
+                failures: vec![(7, VerificationError::RootMismatch)]
+            })
+        );
+    }
+
+    #[test]
+    fn test_lazy_mmr() {
+        let strings: Vec<String> = (1..=100).map(|i| format!("block{}", i)).collect();
+        let mut lazy = LazyMerkleMountainRange::new(vec![]);
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        for (i, s) in strings.iter().enumerate() {
+            lazy.add_entry(s.as_bytes());
+            mmr.add_entry(s.as_bytes());
+            assert!(!lazy.is_hashed());
+            // Only request the trees now and then
+            if i % 7 == 3 || i.is_power_of_two() {
+                assert_eq!(lazy.digests(), mmr.digests());
+                assert!(lazy.is_hashed());
+            }
+        }
+        assert_eq!(lazy.num_entries(), 100);
+
+        let lazy = lazy.into_mmr();
+        assert_eq!(lazy.digests(), mmr.digests());
+        for i in [0, 37, 99] {
+            mmr.verify_inclusion(&lazy.prove_inclusion(i));
+        }
+        mmr.verify_most_recent_n_elements(&lazy.prove_most_recent_n_elements(60));
+    }
+
+    #[test]
+    fn test_keyed_lazy_and_standard_mmr() {
+        let hasher = NodeHasher::keyed([9; 32]);
+        let strings: Vec<String> = (1..=45).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mmr =
+            MerkleMountainRange::new_with_hasher(entries.clone(), HashBackend::detect(), hasher);
+        let mut lazy =
+            LazyMerkleMountainRange::new_with_hasher(vec![], HashBackend::detect(), hasher);
+        for entry in &entries {
+            lazy.add_entry(entry);
+        }
+        assert_eq!(lazy.digests(), mmr.digests());
+        assert_eq!(lazy.mmr().hasher(), &hasher);
+
+        // Peaks are bagged with the MMR's own hasher, as the commitment does
+        let plain = MerkleMountainRange::new(entries.clone());
+        let root = mmr.standard_root().unwrap();
+        assert_ne!(Some(root), plain.standard_root());
+        let bagging = PeakBagging::new(BaggingOrder::LeftToRight, true);
+        let bagged = mmr.standard_root_with_bagging(&bagging).unwrap();
+        assert_eq!(
+            mmr.commitment()
+                .standard_root_with_hasher(&hasher, &bagging),
+            bagged
+        );
+        for index in [0, 31, 44] {
+            let pos = leaf_index_to_pos(index);
+            let proof = mmr.prove_inclusion_standard(index);
+            assert!(proof.verify_with_hasher(
+                &hasher,
+                &root,
+                pos,
+                entries[index as usize],
+                &PeakBagging::CKB
+            ));
+            assert!(!proof.verify(&root, pos, entries[index as usize]));
+            let proof = mmr.prove_inclusion_standard_with_bagging(index, &bagging);
+            assert!(proof.verify_with_hasher(
+                &hasher,
+                &bagged,
+                pos,
+                entries[index as usize],
+                &bagging
+            ));
+        }
+
+        let stored = StoredMmr::from_mmr(&mmr, MemoryStore::new(), MemoryStore::new()).unwrap();
+        assert_eq!(stored.standard_root(), Ok(Some(root)));
+        assert_eq!(
+            stored.prove_inclusion_standard_with_bagging(7, &bagging),
+            Ok(mmr.prove_inclusion_standard_with_bagging(7, &bagging))
+        );
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let tree = PerfectMerkleTree::new(vec![b"block1", b"block2", b"block3", b"block4"]);
+        let usage = tree.memory_usage();
+        assert!(usage.nodes >= 7 * std::mem::size_of::<MerkleNode>());
+        assert_eq!(usage.values, 0);
+
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        let empty = mmr.memory_usage();
+        assert_eq!(empty.nodes, 0);
+        assert_eq!(empty.values, 0);
+
+        let mut previous = empty;
+        for i in 0..100 {
+            mmr.add_entry(format!("block{:03}", i).as_bytes());
+            let usage = mmr.memory_usage();
+            assert!(usage.values >= 8 * (i + 1));
+            assert!(usage.nodes > previous.nodes);
+            assert_eq!(usage.total(), usage.nodes + usage.values + usage.auxiliary);
+            previous = usage;
+        }
+
+        let trees = mmr.trees.iter().flatten().map(|t| t.memory_usage());
+        assert_eq!(
+            trees.fold(MemoryUsage::default(), |a, b| a + b).nodes,
+            previous.nodes
+        );
+    }
+
+    #[test]
+    fn test_stored_mmr() {
+        let mut stored = StoredMmr::in_memory();
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        assert_eq!(stored.digests(), Ok(mmr.digests()));
+        assert_eq!(stored.standard_root(), Ok(None));
+
+        for i in 1..=100 {
+            let entry = format!("block{}", i);
+            stored.append(entry.as_bytes()).unwrap();
+            mmr.add_entry(entry.as_bytes());
+            assert_eq!(stored.num_entries(), i);
+            assert_eq!(stored.mmr_size(), mmr.mmr_size());
+            assert_eq!(stored.digests(), Ok(mmr.digests()));
+            assert_eq!(stored.standard_root(), Ok(mmr.standard_root()));
+        }
+
+        for index in [0, 1, 63, 64, 95, 99] {
+            let proof = stored.prove_inclusion(index).unwrap();
+            assert_eq!(proof.entry, format!("block{}", index + 1).into_bytes());
+            mmr.verify_inclusion(&proof);
+            assert_eq!(
+                stored.prove_inclusion_standard(index),
+                Ok(mmr.prove_inclusion_standard(index))
+            );
+        }
+
+        for n in [0, 1, 3, 4, 5, 36, 37, 50, 99, 100] {
+            assert_eq!(
+                stored.prove_most_recent_n_elements(n),
+                Ok(mmr.prove_most_recent_n_elements(n))
+            );
+        }
+
+        // Asking past the end is an error rather than a panic
+        assert_eq!(stored.entry(100), Err(StoreError::IndexOutOfBounds(100)));
+        assert_eq!(
+            stored.prove_inclusion(100).unwrap_err(),
+            StoreError::IndexOutOfBounds(100)
+        );
+        assert_eq!(
+            stored.prove_inclusion_standard(u64::MAX).unwrap_err(),
+            StoreError::IndexOutOfBounds(u64::MAX)
+        );
+        assert_eq!(
+            stored.prove_most_recent_n_elements(101).unwrap_err(),
+            StoreError::IndexOutOfBounds(101)
+        );
+
+        // Reading the store back gives the same MMR
+        let loaded = stored.to_mmr().unwrap();
+        assert_eq!(loaded.digests(), mmr.digests());
+        assert_eq!(loaded.entries, mmr.entries);
+        loaded.verify_inclusion(&mmr.prove_inclusion(42));
+
+        // Writing out an in-memory MMR gives the same store
+        let copy = StoredMmr::from_mmr(&mmr, MemoryStore::new(), MemoryStore::new()).unwrap();
+        let (nodes, entries) = stored.into_stores();
+        let (copy_nodes, copy_entries) = copy.into_stores();
+        assert_eq!(nodes, copy_nodes);
+        assert_eq!(entries, copy_entries);
+    }
+
+    #[test]
+    fn test_keyed_stored_mmr() {
+        let hasher = NodeHasher::keyed([7; 32]);
+        let mut stored = StoredMmr::new_with_hasher(MemoryStore::new(), MemoryStore::new(), hasher);
+        let mut mmr = MerkleMountainRange::new_with_hasher(vec![], HashBackend::detect(), hasher);
+        for i in 1..=21 {
+            let entry = format!("block{}", i);
+            stored.append(entry.as_bytes()).unwrap();
+            mmr.add_entry(entry.as_bytes());
+        }
+        assert_eq!(stored.digests(), Ok(mmr.digests()));
+        for index in [0, 7, 20] {
+            assert_eq!(
+                stored.prove_inclusion(index),
+                Ok(mmr.prove_inclusion(index))
+            );
+        }
+        for n in [1, 5, 21] {
+            assert_eq!(
+                stored.prove_most_recent_n_elements(n),
+                Ok(mmr.prove_most_recent_n_elements(n))
+            );
+        }
+
+        // The stores only reopen with the hasher they were written with
+        let (nodes, entries) = stored.into_stores();
+        assert!(matches!(
+            StoredMmr::open(nodes.clone(), entries.clone()),
+            Err(StoreError::CorruptNode(_))
+        ));
+        let reopened = StoredMmr::open_with_hasher(nodes, entries, hasher).unwrap();
+        assert_eq!(reopened.to_mmr().unwrap().digests(), mmr.digests());
+    }
+
+    #[test]
+    fn test_stored_merkle_tree() {
+        let blocks: Vec<String> = (1..=16).map(|i| format!("block{}", i)).collect();
+        let tree = PerfectMerkleTree::new(blocks.iter().map(|s| s.as_bytes()).collect());
+        let stored = StoredMerkleTree::in_memory(&tree);
+        assert_eq!(stored.num_leaves(), 16);
+        assert_eq!(stored.root(), Ok(tree.root().hash));
+        for index in [0, 5, 15] {
+            assert_eq!(
+                stored.prove_inclusion(index),
+                Ok(tree.prove_inclusion(index))
+            );
+        }
+        for n in [1, 3, 8, 16] {
+            assert_eq!(
+                stored.prove_most_recent_n_elements(n),
+                Ok(tree.prove_most_recent_n_elements(n))
+            );
+        }
+        assert_eq!(
+            stored.prove_inclusion(16).unwrap_err(),
+            StoreError::IndexOutOfBounds(16)
+        );
+        assert_eq!(
+            stored.prove_most_recent_n_elements(0).unwrap_err(),
+            StoreError::IndexOutOfBounds(0)
+        );
+        let loaded = stored.to_tree().unwrap();
+        assert_eq!(loaded.root().hash, tree.root().hash);
+        assert_eq!(loaded.prove_inclusion(9), tree.prove_inclusion(9));
+
+        // A tampered leaf no longer hashes to the stored root
+        let mut nodes = stored.into_store();
+        nodes.put(3, Default::default()).unwrap();
+        let reopened = StoredMerkleTree::open(nodes, 4, NodeHasher::UNKEYED).unwrap();
+        assert_eq!(reopened.to_tree().unwrap_err(), StoreError::CorruptNode(30));
+    }
+
+    #[test]
+    fn test_reopen_stored_mmr() {
+        let mut stored = StoredMmr::in_memory();
+        for i in 1..=11 {
+            stored.append(format!("block{}", i).as_bytes()).unwrap();
+        }
+        let root = stored.standard_root().unwrap();
+        let (nodes, entries) = stored.into_stores();
+
+        let stored = StoredMmr::open(nodes.clone(), entries.clone()).unwrap();
+        assert_eq!(stored.num_entries(), 11);
+        assert_eq!(stored.standard_root(), Ok(root));
+
+        // An append interrupted before writing its entry is rolled back
+        let mut partial = nodes.clone();
+        partial.put(19, Default::default()).unwrap();
+        let mut stored = StoredMmr::open(partial, entries.clone()).unwrap();
+        assert_eq!(stored.num_entries(), 11);
+        stored.append(b"block12").unwrap();
+        let mmr = MerkleMountainRange::new(
+            (1..=12)
+                .map(|i| format!("block{}", i))
+                .collect::<Vec<_>>()
+                .iter()
+                .map(|s| s.as_bytes())
+                .collect(),
+        );
+        assert_eq!(stored.digests(), Ok(mmr.digests()));
+
+        // Tampered peaks and leaves are detected
+        let mut tampered = nodes.clone();
+        tampered.put(14, Default::default()).unwrap();
+        assert_eq!(
+            StoredMmr::open(tampered, entries.clone()).unwrap_err(),
+            StoreError::CorruptNode(14)
+        );
+        let mut tampered = entries.clone();
+        tampered.put(10, b"tampered".to_vec()).unwrap();
+        assert_eq!(
+            StoredMmr::open(nodes.clone(), tampered).unwrap_err(),
+            StoreError::CorruptNode(18)
+        );
+        let mut lost = nodes;
+        lost.delete(17).unwrap();
+        assert_eq!(
+            StoredMmr::open(lost, entries).unwrap_err(),
+            StoreError::MissingNode(17)
+        );
+    }
+
+    #[cfg(feature = "sled")]
+    #[test]
+    fn test_sled_stored_mmr() {
+        use ads_store::sled::{Config, SledStore};
+
+        let dir = std::env::temp_dir().join(format!("mmr-sled-{}", std::process::id()));
+        let open = || {
+            let db = Config::new().path(&dir).open().unwrap();
+            let nodes = SledStore::open(&db, "nodes").unwrap();
+            let entries = SledStore::open(&db, "entries").unwrap();
+            StoredMmr::open(nodes, entries).unwrap()
+        };
+
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        {
+            let mut stored = open();
+            for i in 1..=20 {
+                let entry = format!("block{}", i);
+                stored.append(entry.as_bytes()).unwrap();
+                mmr.add_entry(entry.as_bytes());
+            }
+            stored.flush().unwrap();
+        }
+
+        let stored = open();
+        assert_eq!(stored.num_entries(), 20);
+        assert_eq!(stored.digests().unwrap(), mmr.digests());
+        mmr.verify_inclusion(&stored.prove_inclusion(13).unwrap());
+        drop(stored);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "file")]
+    #[test]
+    fn test_file_stored_mmr() {
+        use ads_store::file::FileStore;
+
+        let dir = std::env::temp_dir().join(format!("mmr-file-{}", std::process::id()));
+        let open = || {
+            let nodes = FileStore::open(dir.join("nodes")).unwrap();
+            let entries = FileStore::open(dir.join("entries")).unwrap();
+            StoredMmr::open(nodes, entries).unwrap()
+        };
+
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        {
+            let mut stored = open();
+            for i in 1..=20 {
+                let entry = format!("block{}", i);
+                stored.append(entry.as_bytes()).unwrap();
+                mmr.add_entry(entry.as_bytes());
+            }
+            stored.flush().unwrap();
+
+            // Publish the peaks next to the log
+            let (mut nodes, entries) = stored.into_stores();
+            let peaks = bcs::to_bytes(&mmr.standard_peaks()).unwrap();
+            nodes.set_header(&peaks).unwrap();
+            drop((nodes, entries));
+        }
+
+        let stored = open();
+        assert_eq!(stored.num_entries(), 20);
+        assert_eq!(stored.digests().unwrap(), mmr.digests());
+        let (nodes, _) = stored.into_stores();
+        let peaks: Vec<Digest> = bcs::from_bytes(&nodes.header().unwrap().unwrap()).unwrap();
+        assert_eq!(peaks, mmr.standard_peaks());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cached_stored_mmr() {
+        let nodes = CachedStore::new(MemoryStore::new(), 64 * std::mem::size_of::<Digest>());
+        let mut stored = StoredMmr::new(nodes, MemoryStore::new());
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        for i in 1..=1000 {
+            let entry = format!("block{}", i);
+            stored.append(entry.as_bytes()).unwrap();
+            mmr.add_entry(entry.as_bytes());
+        }
+
+        for index in (0..1000).step_by(7) {
+            mmr.verify_inclusion(&stored.prove_inclusion(index).unwrap());
+        }
+        let (nodes, _) = stored.into_stores();
+        // The upper levels of every path are served from the cache
+        let stats = nodes.stats();
+        assert!(stats.hits > stats.misses);
+        assert!(nodes.cached_bytes() <= 64 * std::mem::size_of::<Digest>());
+    }
+
+    #[test]
+    fn test_snapshots() {
+        for n in [0usize, 1, 13, 100] {
+            let strings: Vec<String> = (1..=n).map(|i| format!("block{}", i)).collect();
+            let mmr = MerkleMountainRange::new(strings.iter().map(|s| s.as_bytes()).collect());
+
+            let mut snapshot = vec![];
+            mmr.export_snapshot(&mut snapshot).unwrap();
+            let imported = MerkleMountainRange::import_snapshot(snapshot.as_slice()).unwrap();
+            assert_eq!(imported.digests(), mmr.digests());
+            assert_eq!(imported.entries, mmr.entries);
+
+            let appender = MmrAppender::from_mmr(&mmr);
+            let mut pruned = vec![];
+            appender.export_snapshot(&mut pruned).unwrap();
+            assert_eq!(pruned.len(), 14 + 32 * n.count_ones() as usize);
+            assert_eq!(
+                MmrAppender::import_snapshot(pruned.as_slice()).unwrap(),
+                appender
+            );
+        }
+
+        let mmr = MerkleMountainRange::new(vec![b"block1", b"block2", b"block3"]);
+        let mut snapshot = vec![];
+        mmr.export_snapshot(&mut snapshot).unwrap();
+
+        // A corrupted entry no longer matches the recorded digests
+        let mut corrupted = snapshot.clone();
+        corrupted[16] ^= 1;
+        assert!(matches!(
+            MerkleMountainRange::import_snapshot(corrupted.as_slice()),
+            Err(SnapshotError::DigestMismatch)
+        ));
+        // Truncated, extended and mislabeled snapshots are rejected
+        assert!(matches!(
+            MerkleMountainRange::import_snapshot(&snapshot[..snapshot.len() - 1]),
+            Err(SnapshotError::Io(_))
+        ));
+        let mut extended = snapshot.clone();
+        extended.push(0);
+        assert!(matches!(
+            MerkleMountainRange::import_snapshot(extended.as_slice()),
+            Err(SnapshotError::Malformed)
+        ));
+        assert!(matches!(
+            MmrAppender::import_snapshot(snapshot.as_slice()),
+            Err(SnapshotError::Malformed)
+        ));
+        snapshot[4] = 2;
+        assert!(matches!(
+            MerkleMountainRange::import_snapshot(snapshot.as_slice()),
+            Err(SnapshotError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_verify_against_commitment() {
+        let strings: Vec<String> = (0..13).map(|i| format!("entry{}", i)).collect();
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        let mut appender = MmrAppender::new();
+        for s in &strings {
+            mmr.add_entry(s.as_bytes());
+            appender.append(s.as_bytes());
+        }
+
+        // The appender alone is enough to verify proofs made by the full MMR
+        let commitment = appender.commitment();
+        assert_eq!(commitment, MmrCommitment::from_digests(&mmr.digests()));
+        assert_eq!(commitment.num_entries(), strings.len() as u64);
+        for n in 1..=strings.len() as u64 {
+            let proof = mmr.prove_most_recent_n_elements(n);
+            assert_eq!(commitment.verify_most_recent_n_elements(&proof), Ok(()));
+        }
+        for i in 0..strings.len() as u64 {
+            let proof = mmr.prove_inclusion(i);
+            assert_eq!(commitment.verify_inclusion(&proof), Ok(()));
+        }
+
+        let mut proof = mmr.prove_inclusion(3);
+        proof.entry = b"forged".to_vec();
+        assert_eq!(
+            commitment.verify_inclusion(&proof),
+            Err(VerificationError::RootMismatch)
+        );
+        proof.tree_index = 1;
+        assert_eq!(
+            commitment.verify_inclusion(&proof),
+            Err(VerificationError::MissingTree(1))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_proof_version() {
+        let strings: Vec<String> = (0..6).map(|i| format!("entry{}", i)).collect();
+        let mmr = MerkleMountainRange::new(strings.iter().map(|s| s.as_bytes()).collect());
+        let next = PROOF_FORMAT_VERSION + 1;
+
+        let mut proof = mmr.prove_inclusion(1);
+        assert_eq!(proof.version, PROOF_FORMAT_VERSION);
+        proof.version = next;
+        assert_eq!(
+            mmr.try_verify_inclusion(&proof),
+            Err(VerificationError::UnsupportedVersion(next))
+        );
+        // Older versions aren't verified as if they were current
+        proof.version = PROOF_FORMAT_VERSION - 1;
+        assert_eq!(
+            mmr.try_verify_inclusion(&proof),
+            Err(VerificationError::OutdatedVersion(PROOF_FORMAT_VERSION - 1))
+        );
+        // The nested path is versioned too
+        proof.version = PROOF_FORMAT_VERSION;
+        proof.proof.version = 0;
+        assert_eq!(
+            mmr.try_verify_inclusion(&proof),
+            Err(VerificationError::UnsupportedVersion(0))
+        );
+        proof.proof.version = 1;
+        assert_eq!(
+            mmr.try_verify_inclusion(&proof),
+            Err(VerificationError::OutdatedVersion(1))
+        );
+
+        let mut proof = mmr.prove_most_recent_n_elements(3);
+        proof.version = next;
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&proof),
+            Err(VerificationError::UnsupportedVersion(next))
+        );
+
+        let mut commitment = mmr.commitment();
+        commitment.version = next;
+        assert_eq!(
+            commitment.verify_inclusion(&mmr.prove_inclusion(1)),
+            Err(VerificationError::UnsupportedVersion(next))
+        );
+
+        let root = mmr.standard_root().unwrap();
+        let mut proof = mmr.prove_inclusion_standard(4);
+        assert!(proof.verify(&root, leaf_index_to_pos(4), b"entry4"));
+        proof.version = next;
+        assert!(!proof.verify(&root, leaf_index_to_pos(4), b"entry4"));
+
+        // Borrowed views read the version from the encoding
+        let tree = mmr.trees[2].as_ref().unwrap();
+        let mut bytes = bcs::to_bytes(&tree.prove_inclusion(0)).unwrap();
+        assert!(InclusionProofRef::from_bcs(&bytes)
+            .unwrap()
+            .verify(&tree.digest(), 4, b"entry0"));
+        bytes[0] = next;
+        let proof = InclusionProofRef::from_bcs(&bytes).unwrap();
+        assert_eq!(proof.version, next);
+        assert!(!proof.verify(&tree.digest(), 4, b"entry0"));
+    }
+
+    #[test]
+    fn test_non_canonical_proofs() {
+        let strings: Vec<String> = (0..7).map(|i| format!("entry{}", i)).collect();
+        let mmr = MerkleMountainRange::new(strings.iter().map(|s| s.as_bytes()).collect());
+        let entries = |range: std::ops::Range<usize>| -> Vec<Vec<u8>> {
+            strings[range]
+                .iter()
+                .map(|s| s.as_bytes().to_vec())
+                .collect()
+        };
+
+        // The 4 oldest entries can't pass for the 4 most recent ones by skipping newer trees
+        let mut skipping = mmr.prove_most_recent_n_elements(4);
+        skipping.entries = entries(0..4);
+        skipping.full_tree_indices = vec![2];
+        skipping.partial_tree_proof = None;
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&skipping),
+            Err(VerificationError::NonCanonicalProof)
+        );
+
+        // A full tree can't be proven as a partial one covering all of its leaves
+        let mut split = mmr.prove_most_recent_n_elements(3);
+        assert_eq!(split.full_tree_indices, vec![0, 1]);
+        let tree = mmr.trees[1].as_ref().unwrap();
+        split.full_tree_indices = vec![0];
+        split.partial_tree_proof = Some((1, tree.prove_most_recent_n_elements(2)));
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&split),
+            Err(VerificationError::NonCanonicalProof)
+        );
+
+        // Nor can trees be listed twice or out of order
+        let mut repeated = mmr.prove_most_recent_n_elements(7);
+        repeated.full_tree_indices = vec![0, 0, 1, 2];
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&repeated),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        let mut reordered = mmr.prove_most_recent_n_elements(7);
+        reordered.full_tree_indices.reverse();
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&reordered),
+            Err(VerificationError::NonCanonicalProof)
+        );
+
+        let mut too_many = mmr.prove_most_recent_n_elements(7);
+        too_many.entries.push(b"entry7".to_vec());
+        assert_eq!(
+            mmr.try_verify_most_recent_n_elements(&too_many),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 7,
+                actual: 8
+            })
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    proptest::proptest! {
+        #[test]
+        fn test_generated_proofs(
+            (mmr, recent) in crate::testing::most_recent_proof(40),
+            (invalid_mmr, invalid_recent) in crate::testing::invalid_most_recent_proof(40),
+            (inclusion_mmr, inclusion) in crate::testing::inclusion_proof(40),
+            (invalid_inclusion_mmr, invalid_inclusion) in crate::testing::invalid_inclusion_proof(40),
+        ) {
+            proptest::prop_assert!(mmr.try_verify_most_recent_n_elements(&recent).is_ok());
+            proptest::prop_assert!(invalid_mmr
+                .try_verify_most_recent_n_elements(&invalid_recent)
+                .is_err());
+            proptest::prop_assert!(inclusion_mmr.try_verify_inclusion(&inclusion).is_ok());
+            proptest::prop_assert!(invalid_inclusion_mmr
+                .try_verify_inclusion(&invalid_inclusion)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_digest_encodings() {
+        let digest: Digest = MERKLE_8_DIGEST.parse().unwrap();
+        assert_eq!(digest.to_string(), MERKLE_8_DIGEST);
+        assert_eq!(MERKLE_8_DIGEST.to_uppercase().parse(), Ok(digest));
+        assert_eq!(
+            "00".parse::<Digest>(),
+            Err(ParseDigestError::InvalidLength(2))
+        );
+        let mut invalid = MERKLE_8_DIGEST.to_string();
+        invalid.replace_range(5..6, "g");
+        assert_eq!(
+            invalid.parse::<Digest>(),
+            Err(ParseDigestError::InvalidCharacter(5))
+        );
+
+        // Hex in human-readable formats, bare bytes in binary ones
+        let json = serde_json::to_string(&digest).unwrap();
+        assert_eq!(json, format!("\"{}\"", MERKLE_8_DIGEST));
+        assert_eq!(serde_json::from_str::<Digest>(&json).unwrap(), digest);
+        assert!(serde_json::from_str::<Digest>("\"00\"").is_err());
+        let bytes = bcs::to_bytes(&digest).unwrap();
+        assert_eq!(bytes, digest.0);
+        assert_eq!(bcs::from_bytes::<Digest>(&bytes).unwrap(), digest);
+    }
+
+    #[test]
+    fn test_content_index() {
+        let entries: Vec<&[u8]> = vec![b"a", b"b", b"a", b"c", b"b", b"a", b"d"];
+        let mut mmr = MerkleMountainRange::new(entries);
+        assert_eq!(mmr.indices_of(b"a"), vec![0, 2, 5]);
+
+        mmr.enable_content_index();
+        assert_eq!(mmr.content_index.as_ref().unwrap().len(), 4);
+        mmr.add_entry(b"b");
+        mmr.add_entry(b"e");
+        assert_eq!(mmr.indices_of(b"a"), vec![0, 2, 5]);
+        assert_eq!(mmr.indices_of(b"b"), vec![1, 4, 7]);
+        assert_eq!(mmr.indices_of(b"e"), vec![8]);
+        assert!(mmr.indices_of(b"f").is_empty());
+
+        // The index agrees with a scan
+        let indexed = mmr.memory_usage();
+        mmr.disable_content_index();
+        assert!(mmr.memory_usage().auxiliary < indexed.auxiliary);
+        assert_eq!(mmr.indices_of(b"b"), vec![1, 4, 7]);
+        mmr.enable_content_index();
+
+        let proof = mmr.prove_inclusion_of(b"b").unwrap();
+        assert_eq!(proof, mmr.prove_inclusion(1));
+        assert!(mmr.prove_inclusion_of(b"f").is_none());
+        let proofs = mmr.prove_all_inclusions_of(b"a");
+        assert_eq!(proofs.len(), 3);
+        for proof in &proofs {
+            assert_eq!(proof.entry, b"a");
+            mmr.try_verify_inclusion(proof).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_multi_inclusion_proofs() {
+        // Trees of 8, 4 and 1 entries
+        let entries: Vec<Vec<u8>> = (0..13)
+            .map(|i| format!("entry{}", i % 5).into_bytes())
+            .collect();
+        let mut mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let commitment = mmr.commitment();
+
+        for indices in [
+            vec![0],
+            vec![0, 1],
+            vec![1, 2, 7],
+            vec![3, 8, 12],
+            (0..13).collect(),
+        ] {
+            let proof = mmr.prove_multi_inclusion(&indices);
+            assert_eq!(
+                proof.entries,
+                indices
+                    .iter()
+                    .map(|&i| entries[i as usize].clone())
+                    .collect::<Vec<_>>()
+            );
+            commitment.verify_multi_inclusion(&proof).unwrap();
+            let located: Vec<u64> = proof
+                .tree_proofs
+                .iter()
+                .flat_map(|(tree_index, tree_proof)| {
+                    tree_proof
+                        .leaf_indices
+                        .iter()
+                        .map(|&leaf_index| commitment.entry_index(*tree_index, leaf_index).unwrap())
+                })
+                .collect();
+            assert_eq!(located, indices);
+        }
+        // Siblings shared by both paths are only included once
+        assert_eq!(
+            mmr.prove_multi_inclusion(&[0, 1]).tree_proofs[0]
+                .1
+                .siblings
+                .len(),
+            2
+        );
+        assert!(mmr
+            .prove_multi_inclusion(&(0..8).collect::<Vec<_>>())
+            .tree_proofs[0]
+            .1
+            .siblings
+            .is_empty());
+
+        let proof = mmr.prove_multi_inclusion(&[1, 2, 9]);
+        let mut tampered = proof.clone();
+        tampered.entries[1] = b"entry9".to_vec();
+        assert_eq!(
+            commitment.verify_multi_inclusion(&tampered),
+            Err(VerificationError::RootMismatch)
+        );
+        let mut tampered = proof.clone();
+        tampered.entries.pop();
+        assert!(commitment.verify_multi_inclusion(&tampered).is_err());
+        let mut tampered = proof.clone();
+        tampered.tree_proofs[0].1.leaf_indices = vec![2, 1];
+        assert_eq!(
+            commitment.verify_multi_inclusion(&tampered),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        let mut tampered = proof.clone();
+        tampered.tree_proofs.reverse();
+        assert_eq!(
+            commitment.verify_multi_inclusion(&tampered),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        let mut tampered = proof.clone();
+        tampered.tree_proofs[1].1.siblings.push(Digest::default());
+        assert_eq!(
+            commitment.verify_multi_inclusion(&tampered),
+            Err(VerificationError::UnusedProofElements)
+        );
+
+        // Every occurrence of an entry at once
+        assert_eq!(mmr.duplicate_indices()[0], vec![0, 5, 10]);
+        mmr.enable_content_index();
+        assert_eq!(mmr.duplicate_indices().len(), 5);
+        assert_eq!(mmr.duplicate_indices()[2], vec![2, 7, 12]);
+        let proof = mmr.prove_occurrences_of(b"entry2").unwrap();
+        assert_eq!(proof.entries.len(), 3);
+        mmr.try_verify_multi_inclusion(&proof).unwrap();
+        assert!(mmr.prove_occurrences_of(b"entry5").is_none());
+    }
+
+    #[test]
+    fn test_time_range_proofs() {
+        let mut log = TimestampedLog::new();
+        let empty = log.prove_time_range(0, 10);
+        assert_eq!(log.try_verify_time_range(&empty), Ok(vec![]));
+
+        let timestamps = [10, 20, 20, 30, 40, 40, 40, 50, 60, 70, 80];
+        for (i, &timestamp) in timestamps.iter().enumerate() {
+            log.append(timestamp, format!("event{}", i).as_bytes())
+                .unwrap();
+        }
+        assert_eq!(
+            log.append(79, b"late"),
+            Err(TimestampError::NonMonotonic {
+                last: 80,
+                timestamp: 79
+            })
+        );
+        assert_eq!(log.num_entries(), timestamps.len() as u64);
+
+        for (start, end) in [
+            (0, 100),
+            (20, 40),
+            (21, 39),
+            (40, 40),
+            (0, 5),
+            (90, 100),
+            (45, 45),
+            (10, 10),
+            (80, 80),
+        ] {
+            let proof = log.prove_time_range(start, end);
+            let expected: Vec<TimestampedEntry> = (0..timestamps.len() as u64)
+                .map(|i| log.entry(i))
+                .filter(|entry| (start..=end).contains(&entry.timestamp))
+                .collect();
+            assert_eq!(log.try_verify_time_range(&proof), Ok(expected));
+        }
+
+        // Dropping a boundary, or an entry in range, must be noticed
+        let commitment = log.mmr().commitment();
+        let proof = log.prove_time_range(30, 50);
+        let indices: Vec<u64> = (3..=7).collect();
+        let mut truncated = proof.clone();
+        truncated.proof = Some(log.mmr().prove_multi_inclusion(&indices));
+        assert_eq!(
+            commitment.verify_time_range(&truncated),
+            Err(VerificationError::IncompleteRange)
+        );
+        let gapped: Vec<u64> = vec![2, 3, 5, 6, 7, 8];
+        truncated.proof = Some(log.mmr().prove_multi_inclusion(&gapped));
+        assert_eq!(
+            commitment.verify_time_range(&truncated),
+            Err(VerificationError::IncompleteRange)
+        );
+        let mut none = proof.clone();
+        none.proof = None;
+        assert_eq!(
+            commitment.verify_time_range(&none),
+            Err(VerificationError::IncompleteRange)
+        );
+        // Extra entries beyond the boundaries
+        let mut padded = proof.clone();
+        padded.proof = Some(
+            log.mmr()
+                .prove_multi_inclusion(&(1..=9).collect::<Vec<_>>()),
+        );
+        assert_eq!(
+            commitment.verify_time_range(&padded),
+            Err(VerificationError::NonCanonicalProof)
+        );
+
+        // Entries that aren't timestamped
+        let raw = MerkleMountainRange::new(vec![b"short", b"entry one", b"entry two"]);
+        let mut proof =
+            TimeRangeProof::new(0, u64::MAX, Some(raw.prove_multi_inclusion(&[0, 1, 2])));
+        assert_eq!(
+            raw.commitment().verify_time_range(&proof),
+            Err(VerificationError::MalformedEntry(0))
+        );
+        proof.version = PROOF_FORMAT_VERSION + 1;
+        assert_eq!(
+            raw.commitment().verify_time_range(&proof),
+            Err(VerificationError::UnsupportedVersion(
+                PROOF_FORMAT_VERSION + 1
+            ))
+        );
+    }
+
+    #[test]
+    fn test_headered_entries() {
+        let mut log = HeaderedLog::new();
+        let bodies: Vec<Vec<u8>> = (0..5).map(|i| vec![i as u8; 1000 * (i + 1)]).collect();
+        for (i, body) in bodies.iter().enumerate() {
+            log.append(format!("type=blob;author={}", i).as_bytes(), body);
+        }
+        assert_eq!(log.num_entries(), 5);
+
+        for (i, body) in (0..).zip(&bodies) {
+            let proof = log.prove_header(i);
+            // The proof only carries the header and the body's digest
+            assert!(bcs::to_bytes(&proof).unwrap().len() < 200);
+            let entry = log.try_verify_header(&proof).unwrap();
+            assert_eq!(entry.header, format!("type=blob;author={}", i).into_bytes());
+            assert_eq!(entry, log.header(i));
+            entry.verify_body(log.body(i)).unwrap();
+            assert_eq!(
+                entry.verify_body(&body[1..]),
+                Err(VerificationError::BodyMismatch)
+            );
+        }
+
+        // The committed entry is the BCS encoding of the header and digest
+        let entry = HeaderedEntry::new(vec![7; 200], b"body");
+        assert_eq!(entry.encode(), bcs::to_bytes(&entry).unwrap());
+        assert_eq!(HeaderedEntry::decode(&entry.encode()), Some(entry.clone()));
+        let mut encoded = entry.encode();
+        encoded.push(0);
+        assert_eq!(HeaderedEntry::decode(&encoded), None);
+        assert_eq!(HeaderedEntry::decode(&encoded[..encoded.len() - 2]), None);
+
+        // Plain entries aren't headered
+        let mmr = MerkleMountainRange::new(vec![b"a", b"b", b"c"]);
+        assert_eq!(
+            mmr.commitment().verify_header(&mmr.prove_inclusion(2)),
+            Err(VerificationError::MalformedEntry(2))
+        );
+    }
+
+    #[test]
+    fn test_forest() {
+        let mut forest = Forest::in_memory();
+        assert_eq!(forest.super_root().unwrap(), None);
+        for name in ["payments", "audit", "builds"] {
+            forest.create_log(name).unwrap();
+        }
+        assert_eq!(
+            forest.create_log("audit"),
+            Err(ForestError::DuplicateLog("audit".to_string()))
+        );
+        assert_eq!(
+            forest.log_names().collect::<Vec<_>>(),
+            vec!["audit", "builds", "payments"]
+        );
+
+        let mut logs = vec![
+            ("audit", MerkleMountainRange::new(vec![])),
+            ("payments", MerkleMountainRange::new(vec![])),
+        ];
+        for i in 0..13 {
+            let (name, mmr) = &mut logs[i % 2];
+            let entry = format!("{}-{}", name, i);
+            forest.append(name, entry.as_bytes()).unwrap();
+            mmr.add_entry(entry.as_bytes());
+        }
+        assert_eq!(
+            forest.append("missing", b"x"),
+            Err(ForestError::UnknownLog("missing".to_string()))
+        );
+
+        // Each record holds the root the log would have on its own
+        for (name, mmr) in &logs {
+            let record = forest.record(name).unwrap();
+            assert_eq!(record.root, mmr.standard_root());
+            assert_eq!(record.size, mmr.entries.len() as u64);
+            assert_eq!(record.encode(), bcs::to_bytes(&record).unwrap());
+        }
+        assert_eq!(forest.record("builds").unwrap().root, None);
+
+        let super_root = forest.super_root().unwrap().unwrap();
+        for (name, mmr) in &logs {
+            let log_proof = forest.prove_log(name).unwrap();
+            log_proof.verify(&super_root).unwrap();
+            let root = log_proof.record.root.unwrap();
+            for index in 0..mmr.num_entries() {
+                let proof = forest.prove_inclusion(name, index).unwrap();
+                assert_eq!(proof, mmr.prove_inclusion_standard(index));
+                let entry = format!("{}-{}", name, index * 2 + (*name == "payments") as u64);
+                assert!(proof.verify(&root, leaf_index_to_pos(index), entry.as_bytes()));
+            }
+        }
+        forest
+            .prove_log("builds")
+            .unwrap()
+            .verify(&super_root)
+            .unwrap();
+
+        // A log can't claim another record, nor another position
+        let mut log_proof = forest.prove_log("audit").unwrap();
+        log_proof.record.size += 1;
+        assert_eq!(
+            log_proof.verify(&super_root),
+            Err(VerificationError::RootMismatch)
+        );
+        let mut log_proof = forest.prove_log("audit").unwrap();
+        log_proof.index = 1;
+        assert_eq!(
+            log_proof.verify(&super_root),
+            Err(VerificationError::RootMismatch)
+        );
+        log_proof.index = 3;
+        assert_eq!(
+            log_proof.verify(&super_root),
+            Err(VerificationError::LeafIndexOutOfBounds(3))
+        );
+        let forged = LogRecord {
+            name: "audit".to_string(),
+            root: None,
+            size: 0,
+        };
+        let mut log_proof = forest.prove_log("audit").unwrap();
+        log_proof.record = forged;
+        assert!(log_proof.verify(&super_root).is_err());
+
+        // Appending to one log changes the super-root but not the other logs' records
+        let builds = forest.record("builds").unwrap();
+        forest.append("payments", b"payments-13").unwrap();
+        assert_ne!(forest.super_root().unwrap().unwrap(), super_root);
+        assert_eq!(forest.record("builds").unwrap(), builds);
+
+        // The directory and the logs are recovered from the stores alone
+        let super_root = forest.super_root().unwrap();
+        let (nodes, entries) = forest.into_stores();
+        let forest = Forest::open(nodes, entries).unwrap();
+        assert_eq!(forest.super_root().unwrap(), super_root);
+        assert_eq!(forest.num_entries("payments").unwrap(), 7);
+    }
+
+    #[test]
+    fn test_anchored_log() {
+        let mut log = AnchoredLog::new(Some(4));
+        assert_eq!(log.checkpoint(), None);
+        assert_eq!(log.head(), None);
+        for i in 0..30 {
+            log.append(format!("entry{}", i).as_bytes());
+        }
+        // Every 4 entries, plus one on demand (and only once) for the last 2
+        assert_eq!(log.checkpoints().nodes.len(), 7);
+        assert_eq!(log.checkpoint(), Some(8));
+        assert_eq!(log.checkpoint(), Some(8));
+        assert_eq!(log.checkpoint_size(8), Some(30));
+        assert_eq!(log.checkpoint_size(9), None);
+        assert_eq!(log.first_checkpoint_of(3), Some(1));
+        assert_eq!(log.first_checkpoint_of(4), Some(2));
+        assert_eq!(log.first_checkpoint_of(29), Some(8));
+        assert_eq!(log.first_checkpoint_of(30), None);
+
+        // Each checkpoint holds the root of the log as it was then
+        let mmr = MerkleMountainRange::new(
+            (0..12)
+                .map(|i| format!("entry{}", i).into_bytes())
+                .collect::<Vec<_>>()
+                .iter()
+                .map(|entry| entry.as_slice())
+                .collect(),
+        );
+        assert_eq!(
+            log.checkpoints().nodes[2].value,
+            mmr.standard_root().unwrap()
+        );
+
+        let head = log.head().unwrap();
+        for index in [0, 5, 11, 29] {
+            for height in log.first_checkpoint_of(index).unwrap()..=8 {
+                let proof = log.prove_anchored(index, height);
+                assert_eq!(proof.entry, format!("entry{}", index).into_bytes());
+                proof.verify(&head).unwrap();
+            }
+        }
+
+        // Neither half of the proof can be swapped
+        let proof = log.prove_anchored(5, 3);
+        let mut tampered = proof.clone();
+        tampered.entry = b"entry6".to_vec();
+        assert_eq!(tampered.verify(&head), Err(VerificationError::RootMismatch));
+        let mut tampered = proof.clone();
+        tampered.checkpoint = log.checkpoints().nodes[3].clone();
+        assert_eq!(tampered.verify(&head), Err(VerificationError::RootMismatch));
+        let mut tampered = proof.clone();
+        tampered.proof = log.prove_anchored(5, 4).proof;
+        assert_eq!(tampered.verify(&head), Err(VerificationError::RootMismatch));
+        let mut tampered = proof.clone();
+        tampered.checkpoint.value = log.checkpoints().nodes[3].value;
+        tampered.proof = log.prove_anchored(5, 4).proof;
+        assert_eq!(tampered.verify(&head), Err(VerificationError::RootMismatch));
+        let older_head = log.checkpoints().nodes[5].head_commitment();
+        assert_eq!(
+            proof.verify(&older_head),
+            Err(VerificationError::RootMismatch)
+        );
+        let mut tampered = proof;
+        tampered.index = 40;
+        assert_eq!(
+            tampered.verify(&head),
+            Err(VerificationError::LeafIndexOutOfBounds(40))
+        );
+    }
+
+    // A proof verifies through the trait and its BCS encoding has the size it hints
+    fn check_proof<P: Proof<Error = VerificationError>>(
+        proof: &P,
+        commitment: &P::Commitment,
+        claim: &P::Claim,
+    ) -> P::Output {
+        assert_eq!(proof.size_hint(), bcs::to_bytes(proof).unwrap().len());
+        proof.verify(commitment, claim).unwrap()
+    }
+
+    #[test]
+    fn test_proof_trait() {
+        let entries: Vec<Vec<u8>> = (0..300).map(|i| vec![i as u8; i % 7]).collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let commitment = mmr.commitment();
+        for n in [1, 4, 44, 300] {
+            check_proof(&mmr.prove_most_recent_n_elements(n), &commitment, &());
+        }
+        check_proof(&mmr.prove_inclusion(17), &commitment, &());
+        check_proof(
+            &mmr.prove_multi_inclusion(&[0, 1, 150, 299]),
+            &commitment,
+            &(),
+        );
+        assert_eq!(
+            Proof::verify(&mmr.prove_inclusion(17), &MmrCommitment::empty(), &()),
+            Err(VerificationError::TreeIndexOutOfBounds(8))
+        );
+
+        let tree = PerfectMerkleTree::new(entries[..256].iter().map(|e| e.as_slice()).collect());
+        let root = (tree.root().hash, 256);
+        assert_eq!(
+            check_proof(&tree.prove_inclusion(3), &root, &entries[3]),
+            root.0
+        );
+        check_proof(
+            &tree.prove_most_recent_n_elements(5),
+            &root.0,
+            &entries[251..256],
+        );
+        check_proof(
+            &tree.prove_multi_inclusion(&[3, 4, 200]),
+            &root,
+            &[entries[3].clone(), entries[4].clone(), entries[200].clone()],
+        );
+
+        let mut log = TimestampedLog::new();
+        for i in 0..20 {
+            log.append(i * 10, b"event").unwrap();
+        }
+        let in_range = check_proof(&log.prove_time_range(35, 120), &log.mmr().commitment(), &());
+        assert_eq!(in_range.len(), 9);
+
+        let mut forest = Forest::in_memory();
+        forest.create_log("a").unwrap();
+        forest.create_log("bb").unwrap();
+        forest.append("bb", b"entry").unwrap();
+        let super_root = forest.super_root().unwrap().unwrap();
+        check_proof(&forest.prove_log("a").unwrap(), &super_root, &());
+        check_proof(&forest.prove_log("bb").unwrap(), &super_root, &());
+
+        let mut anchored = AnchoredLog::new(Some(3));
+        for entry in &entries[..50] {
+            anchored.append(entry);
+        }
+        let head = anchored.head().unwrap();
+        check_proof(&anchored.prove_anchored(20, 9), &head, &());
+    }
+
+    #[test]
+    fn test_observers() {
+        use std::sync::mpsc;
+
+        let mut mmr = MerkleMountainRange::new(vec![b"a", b"b"]);
+        let (sender, receiver) = mpsc::channel();
+        let sender = std::sync::Mutex::new(sender);
+        mmr.add_observer(
+            move |index, leaf_hash: &Digest, commitment: &MmrCommitment| {
+                let message = (index, *leaf_hash, commitment.clone());
+                sender.lock().unwrap().send(message).unwrap();
+            },
+        );
+        // A replica fed by the observer stays in sync
+        let mut replica = MerkleMountainRange::new(vec![b"a", b"b"]);
+        for i in 0..10 {
+            let entry = format!("entry{}", i);
+            mmr.add_entry(entry.as_bytes());
+            let (index, leaf_hash, commitment) = receiver.try_recv().unwrap();
+            assert_eq!(index, i + 2);
+            assert_eq!(leaf_hash, hash_leaf(entry.as_bytes()));
+            replica.add_entry(entry.as_bytes());
+            assert_eq!(commitment, replica.commitment());
+        }
+        mmr.clear_observers();
+        mmr.add_entry(b"unobserved");
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_chained_verification() {
+        // Trees of 4 entries, whose roots are the leaves of an outer tree
+        let entries: Vec<Vec<u8>> = (0..32)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let inner: Vec<PerfectMerkleTree> = entries
+            .chunks(4)
+            .map(|chunk| PerfectMerkleTree::new(chunk.iter().map(|e| e.as_slice()).collect()))
+            .collect();
+        let inner_roots: Vec<Digest> = inner.iter().map(|tree| tree.digest()).collect();
+        let outer =
+            PerfectMerkleTree::new(inner_roots.iter().map(|root| root.0.as_slice()).collect());
+
+        // Verifying the inner proof yields the root to prove in the outer tree
+        let inner_proof = inner[5].prove_inclusion(2);
+        let inner_root = inner_proof
+            .verify(&inner_roots[5], 4, &entries[22])
+            .unwrap();
+        let outer_root = outer
+            .prove_inclusion(5)
+            .verify(&outer.digest(), 8, &inner_root.0)
+            .unwrap();
+        assert_eq!(outer_root, outer.digest());
+
+        let suffix = inner[5].prove_most_recent_n_elements(3);
+        assert_eq!(suffix.compute_root(4, &entries[21..24]), Ok(inner_roots[5]));
+        assert_eq!(
+            suffix.verify(&inner_roots[5], 4, &entries[21..24]),
+            Ok(inner_roots[5])
+        );
+        assert_ne!(suffix.compute_root(4, &entries[20..23]), Ok(inner_roots[5]));
+
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let standard = mmr.prove_inclusion_standard(9);
+        let pos = leaf_index_to_pos(9);
+        assert_eq!(standard.compute_root(pos, &entries[9]), mmr.standard_root());
+        assert_ne!(standard.compute_root(pos, &entries[8]), mmr.standard_root());
+    }
+
+    #[test]
+    fn test_self_describing_suffix_proof() {
+        let strings: Vec<String> = (0..16).map(|i| format!("block{}", i)).collect();
+        let blocks: Vec<Vec<u8>> = strings.iter().map(|s| s.as_bytes().to_vec()).collect();
+        let tree = PerfectMerkleTree::new(blocks.iter().map(|b| b.as_slice()).collect());
+
+        // The proof alone says which leaves of which tree it covers
+        let proof = tree.prove_most_recent_n_elements(5);
+        assert_eq!(proof.num_leaves, Some(16));
+        assert_eq!(proof.covered_range(), Some(11..16));
+        let bytes = bcs::to_bytes(&proof).unwrap();
+        let decoded: SuffixProof = bcs::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            decoded.verify_standalone(&tree.digest(), &blocks[11..]),
+            Ok(tree.digest())
+        );
+        assert_eq!(
+            decoded.verify_standalone(&tree.digest(), &blocks[10..15]),
+            Err(VerificationError::RootMismatch)
+        );
+        let borrowed: Vec<&[u8]> = blocks[11..].iter().map(|b| b.as_slice()).collect();
+        let proof_ref = SuffixProofRef::from_bcs(&bytes).unwrap();
+        assert_eq!(proof_ref.num_leaves, Some(16));
+        assert!(proof_ref.verify_standalone(&tree.digest(), &borrowed));
+
+        // A size contradicting the verifier's is rejected
+        assert_eq!(
+            proof.verify(&tree.digest(), 32, &blocks[11..]),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        assert!(!proof_ref.verify(&tree.digest(), 32, &borrowed));
+        let mut resized = proof.clone();
+        resized.num_leaves = Some(12);
+        assert_eq!(
+            resized.verify_standalone(&tree.digest(), &blocks[11..]),
+            Err(VerificationError::NonCanonicalProof)
+        );
+
+        // Proofs without the tree size need it from the verifier
+        let mut sizeless = proof.clone();
+        sizeless.num_leaves = None;
+        assert_eq!(sizeless.covered_range(), None);
+        assert_eq!(
+            sizeless.verify_standalone(&tree.digest(), &blocks[11..]),
+            Err(VerificationError::MissingTreeSize)
+        );
+        assert_eq!(
+            sizeless.verify(&tree.digest(), 16, &blocks[11..]),
+            Ok(tree.digest())
+        );
+        let sizeless_bytes = bcs::to_bytes(&sizeless).unwrap();
+        let sizeless_ref = SuffixProofRef::from_bcs(&sizeless_bytes).unwrap();
+        assert_eq!(sizeless_ref.num_leaves, None);
+        assert!(sizeless_ref.verify(&tree.digest(), 16, &borrowed));
+        assert!(!sizeless_ref.verify_standalone(&tree.digest(), &borrowed));
+
+        // Proofs predating the tree size are outdated, and their encoding is one byte short
+        let mut old = sizeless;
+        old.version = 1;
+        assert_eq!(
+            old.verify(&tree.digest(), 16, &blocks[11..]),
+            Err(VerificationError::OutdatedVersion(1))
+        );
+        let mut old_bytes = bcs::to_bytes(&old).unwrap();
+        old_bytes.pop();
+        assert_eq!(SuffixProofRef::from_bcs(&old_bytes), None);
+        assert!(bcs::from_bytes::<SuffixProof>(&old_bytes).is_err());
+    }
+
+    #[test]
+    fn test_most_recent_proof_constructor() {
+        let entries: Vec<Vec<u8>> = (0..13)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let honest = mmr.prove_most_recent_n_elements(7);
+        assert_eq!(honest.num_entries(), 7);
+        assert_eq!(honest.entries(), &entries[6..]);
+        assert_eq!(honest.full_tree_indices(), &[0, 2]);
+        let (tree_index, suffix_proof) = honest.partial_tree_proof().unwrap();
+        assert_eq!((tree_index, suffix_proof.num_suffix_elements), (3, 2));
+
+        let built = MostRecentNElementsProof::new(
+            entries[6..].to_vec(),
+            vec![0, 2],
+            Some((3, suffix_proof.clone())),
+        )
+        .unwrap();
+        assert_eq!(built, honest);
+        mmr.verify_most_recent_n_elements(&built);
+
+        let new = |entries: &[Vec<u8>], full: Vec<u64>, partial: Option<(u64, SuffixProof)>| {
+            MostRecentNElementsProof::new(entries.to_vec(), full, partial)
+        };
+        assert_eq!(new(&[], vec![], None), Err(VerificationError::EmptyProof));
+        assert_eq!(
+            new(&entries[8..], vec![2, 0], None),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        assert_eq!(
+            new(&entries[..5], vec![0, 2], Some((2, suffix_proof.clone()))),
+            Err(VerificationError::DuplicateTreeIndex(2))
+        );
+        assert_eq!(
+            new(&entries[7..], vec![0, 2], Some((3, suffix_proof.clone()))),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 7,
+                actual: 6
+            })
+        );
+        assert_eq!(
+            new(&entries[..1], vec![64], None),
+            Err(VerificationError::TreeIndexOutOfBounds(64))
+        );
+        // A suffix proof about a tree of another size
+        let other = PerfectMerkleTree::new(entries[..4].iter().map(|e| e.as_slice()).collect());
+        assert_eq!(
+            new(
+                &entries[..2],
+                vec![],
+                Some((3, other.prove_most_recent_n_elements(2)))
+            ),
+            Err(VerificationError::NonCanonicalProof)
+        );
+
+        // Only well-formed proofs decode
+        let bytes = bcs::to_bytes(&honest).unwrap();
+        assert_eq!(
+            bcs::from_bytes::<MostRecentNElementsProof>(&bytes).unwrap(),
+            honest
+        );
+        let mut reordered = honest.clone();
+        reordered.full_tree_indices.reverse();
+        let bytes = bcs::to_bytes(&reordered).unwrap();
+        assert!(bcs::from_bytes::<MostRecentNElementsProof>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_consistency_proof() {
+        let entries: Vec<Vec<u8>> = (0..40)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let commitments: Vec<MmrCommitment> = (0..=entries.len())
+            .map(|n| {
+                MerkleMountainRange::new(entries[..n].iter().map(|e| e.as_slice()).collect())
+                    .commitment()
+            })
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        for old in 0..=entries.len() {
+            let proof = mmr.prove_consistency(old as u64);
+            assert_eq!(
+                commitments[40].verify_consistency(&commitments[old], &proof),
+                Ok(())
+            );
+            assert_eq!(
+                Proof::size_hint(&proof),
+                bcs::to_bytes(&proof).unwrap().len()
+            );
+        }
+
+        // The same sizes with different entries
+        let mut other = entries.clone();
+        other[5] = b"tampered".to_vec();
+        let tampered = MerkleMountainRange::new(other[..13].iter().map(|e| e.as_slice()).collect());
+        let proof = mmr.prove_consistency(13);
+        assert_eq!(
+            commitments[40].verify_consistency(&tampered.commitment(), &proof),
+            Err(VerificationError::RootMismatch)
+        );
+
+        // A proof for another older size, or misshapen
+        assert_eq!(
+            commitments[40].verify_consistency(&commitments[13], &mmr.prove_consistency(14)),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        let mut truncated = proof.clone();
+        truncated.paths.pop();
+        assert_eq!(
+            commitments[40].verify_consistency(&commitments[13], &truncated),
+            Err(VerificationError::NotEnoughProofElements)
+        );
+        let mut extended = proof.clone();
+        extended.paths.push(vec![]);
+        assert_eq!(
+            commitments[40].verify_consistency(&commitments[13], &extended),
+            Err(VerificationError::UnusedProofElements)
+        );
+        assert_eq!(
+            commitments[13].verify_consistency(&commitments[40], &ConsistencyProof::new(vec![])),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 13,
+                actual: 40
+            })
+        );
+    }
+
+    // Poll a future that is ready right away, as those of local providers are
+    fn now<F: std::future::Future>(future: F) -> F::Output {
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        match std::pin::pin!(future).poll(&mut context) {
+            std::task::Poll::Ready(output) => output,
+            std::task::Poll::Pending => panic!("Local providers never wait"),
+        }
+    }
+
+    // A client written against the trait alone
+    fn check_provider<P: ProofProvider>(provider: &P, old: &MmrCommitment, index: u64)
+    where
+        P::Error: std::fmt::Debug,
+    {
+        let commitment = now(provider.get_commitment()).unwrap();
+        let proof = now(provider.prove_inclusion(index)).unwrap();
+        assert_eq!(commitment.verify_inclusion(&proof), Ok(()));
+        let proof = now(provider.prove_suffix(3)).unwrap();
+        assert_eq!(commitment.verify_most_recent_n_elements(&proof), Ok(()));
+        let proof = now(provider.prove_consistency(old.num_entries())).unwrap();
+        assert_eq!(commitment.verify_consistency(old, &proof), Ok(()));
+    }
+
+    #[test]
+    fn test_proof_provider() {
+        let entries: Vec<Vec<u8>> = (0..21)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mut mmr = MerkleMountainRange::new(entries[..6].iter().map(|e| e.as_slice()).collect());
+        let old = mmr.commitment();
+        for entry in &entries[6..] {
+            mmr.add_entry(entry);
+        }
+        check_provider(&mmr, &old, 4);
+
+        assert_eq!(
+            now(ProofProvider::prove_inclusion(&mmr, 21)),
+            Err(ProviderError::IndexOutOfBounds(21))
+        );
+        assert_eq!(now(mmr.prove_suffix(0)), Err(ProviderError::EmptySuffix));
+        assert_eq!(
+            now(mmr.prove_suffix(22)),
+            Err(ProviderError::TooManyEntries(22))
+        );
+        assert_eq!(
+            now(ProofProvider::prove_consistency(&mmr, 22)),
+            Err(ProviderError::TooManyEntries(22))
+        );
+    }
+
+    #[cfg(feature = "vectors")]
+    #[test]
+    fn test_vectors() {
+        use crate::standard::StandardMerkleProof;
+        use crate::vectors::{generate, ProofKind, TestVectors};
+        use crate::{hash_children, MmrInclusionProof, MmrMultiInclusionProof};
+
+        fn unhex(hex: &str) -> Vec<u8> {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect()
+        }
+
+        let vectors = generate();
+        assert_eq!(vectors.version, PROOF_FORMAT_VERSION);
+        assert_eq!(vectors.empty_root, MmrCommitment::empty().standard_root());
+        for vector in &vectors.leaf_hashes {
+            assert_eq!(hash_leaf(&unhex(&vector.value)), vector.digest);
+        }
+        for vector in &vectors.node_hashes {
+            assert_eq!(hash_children(&vector.left, &vector.right), vector.digest);
+        }
+
+        // Every proof decodes from its bytes and verifies, as another implementation would check
+        for mmr in &vectors.mmrs {
+            let entries: Vec<Vec<u8>> = mmr.entries.iter().map(|e| unhex(e)).collect();
+            let commitment: MmrCommitment = bcs::from_bytes(&unhex(&mmr.commitment_bcs)).unwrap();
+            assert_eq!(commitment, mmr.commitment);
+            for proof in &mmr.proofs {
+                let bytes = unhex(&proof.proof_bcs);
+                let query = proof.query[0] as usize;
+                match proof.kind {
+                    ProofKind::Inclusion => {
+                        let proof: MmrInclusionProof = bcs::from_bytes(&bytes).unwrap();
+                        assert_eq!(proof.entry, entries[query]);
+                        assert_eq!(commitment.verify_inclusion(&proof), Ok(()));
+                    }
+                    ProofKind::MultiInclusion => {
+                        let proof: MmrMultiInclusionProof = bcs::from_bytes(&bytes).unwrap();
+                        assert_eq!(commitment.verify_multi_inclusion(&proof), Ok(()));
+                    }
+                    ProofKind::MostRecent => {
+                        let proof: MostRecentNElementsProof = bcs::from_bytes(&bytes).unwrap();
+                        assert_eq!(proof.entries(), &entries[entries.len() - query..]);
+                        assert_eq!(commitment.verify_most_recent_n_elements(&proof), Ok(()));
+                    }
+                    ProofKind::Consistency => {
+                        let proof: ConsistencyProof = bcs::from_bytes(&bytes).unwrap();
+                        let old = MerkleMountainRange::new(
+                            entries[..query].iter().map(|e| e.as_slice()).collect(),
+                        );
+                        assert_eq!(
+                            commitment.verify_consistency(&old.commitment(), &proof),
+                            Ok(())
+                        );
+                    }
+                    ProofKind::StandardInclusion => {
+                        let proof: StandardMerkleProof = bcs::from_bytes(&bytes).unwrap();
+                        let pos = leaf_index_to_pos(query as u64);
+                        assert!(proof.verify(&mmr.standard_root, pos, &entries[query]));
+                    }
+                }
+            }
+        }
+
+        // Deterministic, and the JSON export reads back
+        assert_eq!(generate(), vectors);
+        let json = serde_json::to_string(&vectors).unwrap();
+        assert_eq!(serde_json::from_str::<TestVectors>(&json).unwrap(), vectors);
+    }
+
+    #[test]
+    fn test_empty_commitments() {
+        // Every way to hold an empty MMR commits to the same
+        let empty = MmrCommitment::empty();
+        assert!(empty.is_empty());
+        assert_eq!(empty.num_entries(), 0);
+        assert_eq!(empty.standard_root(), EMPTY_ROOT);
+        assert_eq!(empty.tree_digest(0), EMPTY_ROOT);
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        assert_eq!(mmr.commitment(), empty);
+        assert_eq!(MmrAppender::new().commitment(), empty);
+        let stored = StoredMmr::new(MemoryStore::new(), MemoryStore::new());
+        assert_eq!(
+            MmrCommitment::from_digests(&stored.digests().unwrap()),
+            empty
+        );
+        assert_ne!(EMPTY_ROOT, hash_leaf(b""));
+
+        // Every MMR extends the empty one, including the empty one itself
+        assert_eq!(
+            empty.verify_consistency(&empty, &mmr.prove_consistency(0)),
+            Ok(())
+        );
+        for i in 0..5 {
+            mmr.add_entry(format!("entry{}", i).as_bytes());
+            let commitment = mmr.commitment();
+            assert!(!commitment.is_empty());
+            assert_eq!(Some(commitment.standard_root()), mmr.standard_root());
+            assert_eq!(
+                commitment.verify_consistency(&empty, &mmr.prove_consistency(0)),
+                Ok(())
+            );
+        }
+        assert_eq!(mmr.commitment().tree_digest(1), EMPTY_ROOT);
+        assert_eq!(
+            mmr.commitment()
+                .verify_consistency(&empty, &ConsistencyProof::new(vec![vec![]])),
+            Err(VerificationError::UnusedProofElements)
+        );
+    }
+
+    #[test]
+    fn test_commitment_encoding() {
+        let entries: Vec<Vec<u8>> = (0..13)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let commitment = mmr.commitment();
+        assert_eq!(commitment.num_entries, 13);
+        assert_eq!(commitment.peaks, mmr.standard_peaks());
+        assert_eq!(
+            MmrCommitment::from_digests(&commitment.digests()),
+            commitment
+        );
+        assert_eq!(
+            MmrCommitment::new(13, mmr.standard_peaks()),
+            Ok(commitment.clone())
+        );
+        assert_eq!(
+            MmrCommitment::new(12, mmr.standard_peaks()),
+            Err(CommitmentError::WrongNumberOfPeaks {
+                expected: 2,
+                actual: 3
+            })
+        );
+
+        // The digest binds the size along with the peaks
+        assert_ne!(commitment.digest(), commitment.standard_root());
+        let mut resized = commitment.clone();
+        resized.num_entries = 15;
+        assert_ne!(resized.digest(), commitment.digest());
+        assert_ne!(
+            MmrCommitment::empty().digest(),
+            MmrCommitment::empty().standard_root()
+        );
+
+        // Display and parse
+        let printed = commitment.to_string();
+        assert!(printed.starts_with("13:"));
+        assert_eq!(printed.parse::<MmrCommitment>(), Ok(commitment.clone()));
+        let empty = MmrCommitment::empty();
+        assert_eq!(empty.to_string(), "0:");
+        assert_eq!("0:".parse::<MmrCommitment>(), Ok(empty));
+        assert_eq!(
+            "13".parse::<MmrCommitment>(),
+            Err(CommitmentError::MissingSeparator)
+        );
+        assert_eq!(
+            "x:".parse::<MmrCommitment>(),
+            Err(CommitmentError::InvalidNumberOfEntries)
+        );
+        assert_eq!(
+            "1:00".parse::<MmrCommitment>(),
+            Err(CommitmentError::InvalidPeak(
+                0,
+                ParseDigestError::InvalidLength(2)
+            ))
+        );
+        assert_eq!(
+            printed.replacen("13:", "12:", 1).parse::<MmrCommitment>(),
+            Err(CommitmentError::WrongNumberOfPeaks {
+                expected: 2,
+                actual: 3
+            })
+        );
+
+        // Serde round-trips, and rejects a count of peaks that doesn't match
+        let json = serde_json::to_string(&commitment).unwrap();
+        assert_eq!(
+            serde_json::from_str::<MmrCommitment>(&json).unwrap(),
+            commitment
+        );
+        let bytes = bcs::to_bytes(&resized).unwrap();
+        assert!(bcs::from_bytes::<MmrCommitment>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_follower() {
+        let entries: Vec<Vec<u8>> = (0..20)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mut follower = MmrFollower::new(MmrCommitment::empty());
+        let mut mmr = MerkleMountainRange::new(vec![]);
+        for batch in [
+            &entries[0..1],
+            &entries[1..7],
+            &entries[7..8],
+            &entries[8..13],
+        ] {
+            for entry in batch {
+                mmr.add_entry(entry);
+            }
+            follower.append(batch, &mmr.commitment()).unwrap();
+            assert_eq!(follower.commitment(), &mmr.commitment());
+        }
+
+        // Wrong entries, too many entries, and an old commitment are rejected without advancing
+        let next = MerkleMountainRange::new(entries[..15].iter().map(|e| e.as_slice()).collect());
+        assert_eq!(
+            follower.append(&entries[12..14], &next.commitment()),
+            Err(VerificationError::RootMismatch)
+        );
+        assert_eq!(
+            follower.append(&entries[13..16], &next.commitment()),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 15,
+                actual: 16
+            })
+        );
+        assert!(follower
+            .append(&Vec::<Vec<u8>>::new(), &MmrCommitment::empty())
+            .is_err());
+        assert_eq!(follower.num_entries(), 13);
+
+        follower
+            .append(&entries[13..15], &next.commitment())
+            .unwrap();
+
+        // Skip the remaining entries with a consistency proof
+        let full = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        assert!(follower
+            .skip_to(&full.commitment(), &full.prove_consistency(14))
+            .is_err());
+        assert_eq!(follower.num_entries(), 15);
+        follower
+            .skip_to(&full.commitment(), &full.prove_consistency(15))
+            .unwrap();
+        assert_eq!(follower.into_commitment(), full.commitment());
+    }
+
+    #[test]
+    fn test_sync() {
+        let entries: Vec<Vec<u8>> = (0..21)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let remote = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let mut local =
+            MerkleMountainRange::new(entries[..9].iter().map(|e| e.as_slice()).collect());
+
+        let plan = local.plan_sync(&remote.commitment()).unwrap();
+        assert_eq!(plan.missing, 9..21);
+
+        // Wrong or missing entries are rejected and leave the local MMR as it was
+        let mut tampered = entries[9..].to_vec();
+        tampered[3] = b"tampered".to_vec();
+        assert_eq!(
+            local.apply_sync(&plan, &tampered),
+            Err(SyncError::Verification(VerificationError::RootMismatch))
+        );
+        assert!(local.apply_sync(&plan, &entries[10..]).is_err());
+        assert_eq!(local.entries.len(), 9);
+
+        local.apply_sync(&plan, &entries[9..]).unwrap();
+        assert_eq!(local.commitment(), remote.commitment());
+        assert!(local
+            .plan_sync(&remote.commitment())
+            .unwrap()
+            .is_up_to_date());
+
+        let behind = MerkleMountainRange::new(entries[..5].iter().map(|e| e.as_slice()).collect());
+        assert_eq!(
+            local.plan_sync(&behind.commitment()),
+            Err(SyncError::RemoteBehind {
+                local: 21,
+                remote: 5
+            })
+        );
+        let mut diverged = entries.clone();
+        diverged[0] = b"other".to_vec();
+        let diverged = MerkleMountainRange::new(diverged.iter().map(|e| e.as_slice()).collect());
+        assert_eq!(
+            local.plan_sync(&diverged.commitment()),
+            Err(SyncError::Diverged)
+        );
+    }
+
+    #[test]
+    fn test_appended_range_proof() {
+        let entries: Vec<Vec<u8>> = (0..23)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        for (old, new) in [
+            (0, 0),
+            (0, 23),
+            (5, 12),
+            (8, 16),
+            (13, 14),
+            (7, 23),
+            (23, 23),
+        ] {
+            let prefix = MerkleMountainRange::new(
+                entries[..new as usize]
+                    .iter()
+                    .map(|e| e.as_slice())
+                    .collect(),
+            );
+            let new_commitment = mmr.commitment_at(new);
+            assert_eq!(new_commitment, prefix.commitment());
+
+            let old_commitment = mmr.commitment_at(old);
+            let proof = mmr.prove_appended_range(old, new);
+            assert_eq!(proof.entries, entries[old as usize..new as usize].to_vec());
+            new_commitment
+                .verify_appended_range(&old_commitment, &proof)
+                .unwrap();
+            assert_eq!(proof.size_hint(), bcs::to_bytes(&proof).unwrap().len());
+        }
+
+        let old = mmr.commitment_at(5);
+        let new = mmr.commitment_at(12);
+        let mut proof = mmr.prove_appended_range(5, 12);
+        proof.entries.swap(0, 1);
+        assert_eq!(
+            new.verify_appended_range(&old, &proof),
+            Err(VerificationError::RootMismatch)
+        );
+        proof.entries.pop();
+        assert_eq!(
+            new.verify_appended_range(&old, &proof),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 12,
+                actual: 11
+            })
+        );
+        // The older MMR must be the one the entries were appended to
+        let proof = mmr.prove_appended_range(5, 12);
+        assert_eq!(
+            new.verify_appended_range(&mmr.commitment_at(6), &proof),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 12,
+                actual: 13
+            })
+        );
+    }
+
+    #[test]
+    fn test_audit() {
+        let entries: Vec<Vec<u8>> = (0..40)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let published: Vec<MmrCommitment> = [0, 1, 7, 7, 16, 29, 33]
+            .iter()
+            .map(|&n| mmr.commitment_at(n))
+            .collect();
+        assert_eq!(audit(&entries, &published), Ok(mmr.commitment()));
+        assert_eq!(
+            audit(&entries, Vec::<MmrCommitment>::new()),
+            Ok(mmr.commitment())
+        );
+
+        // A commitment to other entries
+        let mut tampered = entries.clone();
+        tampered[20] = b"tampered".to_vec();
+        assert_eq!(
+            audit(&tampered, &published),
+            Err(AuditError {
+                commitment: 5,
+                error: VerificationError::RootMismatch
+            })
+        );
+        // Commitments out of order, or beyond the log
+        let mut reordered = published.clone();
+        reordered.swap(3, 4);
+        assert_eq!(audit(&entries, &reordered).unwrap_err().commitment, 4);
+        assert_eq!(
+            audit(&entries[..30], &published),
+            Err(AuditError {
+                commitment: 6,
+                error: VerificationError::WrongNumberOfEntries {
+                    expected: 33,
+                    actual: 30
+                }
+            })
+        );
+
+        // Resuming from a commitment
+        let mut auditor = MmrAuditor::from_commitment(&published[4]).unwrap();
+        for entry in &entries[16..29] {
+            auditor.push(entry);
+        }
+        auditor.check(&published[5]).unwrap();
+        assert_eq!(auditor.commitment(), published[5]);
+
+        // Not from a commitment missing a peak
+        let mut truncated = published[4].clone();
+        truncated.peaks.pop();
+        assert!(matches!(
+            MmrAuditor::from_commitment(&truncated),
+            Err(CommitmentError::WrongNumberOfPeaks { .. })
+        ));
+        assert!(matches!(
+            published[5].verify_appended_entries(&truncated, &entries[16..29]),
+            Err(VerificationError::InvalidCommitment(_))
+        ));
+    }
+
+    #[test]
+    fn test_streamed_leaves() {
+        let blob: Vec<u8> = (0..3 * CHUNK_SIZE + 17).map(|i| i as u8).collect();
+        assert_eq!(
+            hash_leaf_from_reader(blob.as_slice()).unwrap(),
+            hash_leaf(&blob)
+        );
+        assert_eq!(hash_leaf_from_reader(&[][..]).unwrap(), hash_leaf(b""));
+
+        let mut streamed = MmrAppender::new();
+        let mut buffered = MmrAppender::new();
+        for entry in [&b"block1"[..], &blob, b"block3"] {
+            let leaf = streamed.append_reader(entry).unwrap();
+            assert_eq!(leaf, hash_leaf(entry));
+            buffered.append(entry);
+        }
+        assert_eq!(streamed, buffered);
+
+        struct Failing;
+        impl std::io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+        }
+        assert!(streamed.append_reader(Failing).is_err());
+        assert_eq!(streamed, buffered);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_streamed_leaves_async() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        let blob: Vec<u8> = (0..2 * CHUNK_SIZE + 5).map(|i| i as u8).collect();
+        let mut appender = MmrAppender::new();
+        let mut future = Box::pin(appender.append_async_reader(blob.as_slice()));
+        let Poll::Ready(leaf) = future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        else {
+            panic!("Reading from a slice never blocks");
+        };
+        drop(future);
+        assert_eq!(leaf.unwrap(), hash_leaf(&blob));
+        assert_eq!(appender.num_entries(), 1);
+    }
+
+    #[test]
+    fn test_keyed_hashing() {
+        let hasher = NodeHasher::keyed([7; 32]);
+        let other = NodeHasher::keyed([8; 32]);
+        assert_ne!(hasher.hash_leaf(b"block1"), hash_leaf(b"block1"));
+        assert_ne!(hasher.hash_leaf(b"block1"), other.hash_leaf(b"block1"));
+        assert_eq!(format!("{:?}", hasher), "NodeHasher::Keyed(..)");
+
+        let strings: Vec<String> = (1..=13).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let scalar =
+            MerkleMountainRange::new_with_hasher(entries.clone(), HashBackend::Scalar, hasher);
+        let simd =
+            MerkleMountainRange::new_with_hasher(entries.clone(), HashBackend::MultiBuffer, hasher);
+        let mut appended =
+            MerkleMountainRange::new_with_hasher(vec![], HashBackend::Scalar, hasher);
+        let mut appender = MmrAppender::new_with_hasher(hasher);
+        for entry in &entries {
+            appended.add_entry(entry);
+            appender.append(entry);
+        }
+        let commitment = scalar.commitment();
+        assert_eq!(simd.commitment(), commitment);
+        assert_eq!(appended.commitment(), commitment);
+        assert_eq!(appender.commitment(), commitment);
+        assert_ne!(
+            MerkleMountainRange::new(entries.clone()).commitment(),
+            commitment
+        );
+
+        let inclusion = scalar.prove_inclusion(5);
+        assert!(commitment
+            .verify_inclusion_with_hasher(&hasher, &inclusion)
+            .is_ok());
+        assert_eq!(
+            commitment.verify_inclusion(&inclusion),
+            Err(VerificationError::RootMismatch)
+        );
+        assert_eq!(
+            commitment.verify_inclusion_with_hasher(&other, &inclusion),
+            Err(VerificationError::RootMismatch)
+        );
+
+        let recent = scalar.prove_most_recent_n_elements(7);
+        assert!(commitment
+            .verify_most_recent_n_elements_with_hasher(&hasher, &recent)
+            .is_ok());
+        assert!(commitment.verify_most_recent_n_elements(&recent).is_err());
+
+        let multi = scalar.prove_multi_inclusion(&[0, 9, 12]);
+        assert!(commitment
+            .verify_multi_inclusion_with_hasher(&hasher, &multi)
+            .is_ok());
+        assert!(commitment.verify_multi_inclusion(&multi).is_err());
+
+        let old = MerkleMountainRange::new_with_hasher(
+            entries[..6].to_vec(),
+            HashBackend::Scalar,
+            hasher,
+        )
+        .commitment();
+        let consistency = scalar.prove_consistency(6);
+        assert!(commitment
+            .verify_consistency_with_hasher(&hasher, &old, &consistency)
+            .is_ok());
+        assert!(commitment.verify_consistency(&old, &consistency).is_err());
+        assert!(commitment
+            .verify_appended_entries_with_hasher(&hasher, &old, &entries[6..])
+            .is_ok());
+        assert!(commitment
+            .verify_appended_entries(&old, &entries[6..])
+            .is_err());
+
+        let blob = vec![3; CHUNK_SIZE + 1];
+        let mut streamed = MmrAppender::new_with_hasher(hasher);
+        assert_eq!(
+            streamed.append_reader(blob.as_slice()).unwrap(),
+            hasher.hash_leaf(&blob)
+        );
+    }
+
+    #[test]
+    fn test_hash_algos() {
+        assert_eq!(
+            NodeHasher::new(HashAlgo::Sha256)
+                .hash_message(b"abc")
+                .to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            NodeHasher::new(HashAlgo::Keccak256)
+                .hash_message(b"")
+                .to_string(),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        let sha256 = NodeHasher::new(HashAlgo::Sha256);
+        assert_eq!(sha256.hash_leaf(b"abc"), sha256.hash_message(b"\x00abc"));
+
+        let strings: Vec<String> = (1..=11).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mut commitments = vec![];
+        for &algo in HashAlgo::ALL {
+            assert_eq!(algo.to_string().parse(), Ok(algo));
+            let hasher = NodeHasher::new(algo);
+            let mmr = MerkleMountainRange::new_with_hasher(
+                entries.clone(),
+                HashBackend::detect(),
+                hasher,
+            );
+            let mut appended =
+                MerkleMountainRange::new_with_hasher(vec![], HashBackend::Scalar, hasher);
+            let mut appender = MmrAppender::new_with_hasher(hasher);
+            for entry in &entries {
+                appended.add_entry(entry);
+                appender.append(entry);
+            }
+            let commitment = mmr.commitment();
+            assert_eq!(commitment.hash_algo(), algo);
+            assert_eq!(appended.commitment(), commitment);
+            assert_eq!(appender.commitment(), commitment);
+            assert_eq!(commitment.to_string().parse(), Ok(commitment.clone()));
+            let encoded = bcs::to_bytes(&commitment).unwrap();
+            assert_eq!(
+                bcs::from_bytes::<MmrCommitment>(&encoded).unwrap(),
+                commitment
+            );
+
+            // The commitment says how to verify
+            let inclusion = mmr.prove_inclusion(4);
+            assert!(commitment.verify_inclusion(&inclusion).is_ok());
+            assert_eq!(
+                inclusion.size_hint(),
+                bcs::to_bytes(&inclusion).unwrap().len()
+            );
+            let recent = mmr.prove_most_recent_n_elements(6);
+            assert!(commitment.verify_most_recent_n_elements(&recent).is_ok());
+            assert_eq!(recent.size_hint(), bcs::to_bytes(&recent).unwrap().len());
+            let multi = mmr.prove_multi_inclusion(&[1, 8, 10]);
+            assert!(commitment.verify_multi_inclusion(&multi).is_ok());
+            assert_eq!(multi.size_hint(), bcs::to_bytes(&multi).unwrap().len());
+            let old = mmr.commitment_at(5);
+            assert!(commitment
+                .verify_consistency(&old, &mmr.prove_consistency(5))
+                .is_ok());
+            assert!(commitment
+                .verify_appended_entries(&old, &entries[5..])
+                .is_ok());
+            assert_eq!(audit(&entries, [&old, &commitment]), Ok(commitment.clone()));
+
+            // A proof claiming another algorithm is rejected before any hashing
+            let other = HashAlgo::ALL[(HashAlgo::ALL.iter().position(|&a| a == algo).unwrap() + 1)
+                % HashAlgo::ALL.len()];
+            assert_eq!(
+                commitment.verify_inclusion(&inclusion.clone().with_hash_algo(other)),
+                Err(VerificationError::HashAlgoMismatch {
+                    expected: algo,
+                    actual: other
+                })
+            );
+            assert_eq!(
+                commitment.verify_inclusion_with_hasher(&NodeHasher::new(other), &inclusion),
+                Err(VerificationError::HashAlgoMismatch {
+                    expected: algo,
+                    actual: other
+                })
+            );
+            commitments.push(commitment);
+        }
+        for (i, a) in commitments.iter().enumerate() {
+            for b in &commitments[i + 1..] {
+                assert_ne!(a.peaks, b.peaks);
+                assert_ne!(a.digest(), b.digest());
+            }
+        }
+
+        // Blake2b256 is the default and isn't spelled out
+        let commitment = &commitments[0];
+        assert_eq!(commitment.hash_algo, None);
+        assert!(!commitment.to_string().contains('/'));
+        let json = serde_json::to_value(commitment).unwrap();
+        let mut older = json.clone();
+        older.as_object_mut().unwrap().remove("hash_algo");
+        assert_eq!(
+            serde_json::from_value::<MmrCommitment>(older).unwrap(),
+            *commitment
+        );
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_poseidon() {
+        let hasher = NodeHasher::new(HashAlgo::Poseidon);
+        let element = |n: u8| {
+            let mut digest = [0; 32];
+            digest[31] = n;
+            Digest(digest)
+        };
+        // circomlib's poseidon([1, 2])
+        assert_eq!(
+            hasher.hash_children(&element(1), &element(2)).to_string(),
+            "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+        );
+
+        // Values are hashed the same however they're split, and a leaf never collides with the
+        // node of the same elements
+        let value: Vec<u8> = (0..100).collect();
+        let mut leaf_hasher = hasher.leaf_hasher();
+        for piece in value.chunks(7) {
+            leaf_hasher.update(piece);
+        }
+        assert_eq!(leaf_hasher.finalize(), hasher.hash_leaf(&value));
+        assert_ne!(hasher.hash_leaf(&value), hasher.hash_leaf(&value[..99]));
+        assert_ne!(hasher.hash_leaf(&[]), hasher.hash_leaf(&[0]));
+        assert_ne!(
+            hasher.hash_leaf(&[1]),
+            hasher.hash_children(&element(1), &element(1))
+        );
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_circuit_witnesses() {
+        use ads_core::poseidon::{self, Fr};
+        use ads_verifier::circuit::CircuitInclusionProof;
+
+        let hasher = NodeHasher::new(HashAlgo::Poseidon);
+        let strings: Vec<String> = (0..16).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let tree = PerfectMerkleTree::new_with_hasher(entries.clone(), HashBackend::Scalar, hasher);
+        let root = poseidon::to_field(&tree.root().hash);
+        let node = |left: Fr, right: Fr| {
+            poseidon::to_field(&poseidon::hash_children(
+                &poseidon::to_digest(left),
+                &poseidon::to_digest(right),
+            ))
+        };
+
+        // What a Merkle gadget checks
+        let check = |witness: &CircuitInclusionProof| {
+            let mut current = witness.leaf;
+            for (&sibling, &is_right) in witness.path_elements.iter().zip(&witness.path_indices) {
+                current = if is_right {
+                    node(sibling, current)
+                } else {
+                    node(current, sibling)
+                };
+            }
+            current
+        };
+        for index in [0, 5, 10, 15] {
+            let witness = tree
+                .prove_inclusion(index)
+                .to_circuit(entries[index as usize])
+                .unwrap();
+            assert_eq!(witness.root, root);
+            assert_eq!(witness.path_indices.len(), 4);
+            assert_eq!(
+                witness.path_indices,
+                (0..4)
+                    .map(|level| (index >> level) & 1 == 1)
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(check(&witness), root);
+        }
+
+        let suffix: Vec<Vec<u8>> = entries[11..].iter().map(|e| e.to_vec()).collect();
+        let witness = tree
+            .prove_most_recent_n_elements(5)
+            .to_circuit(&suffix)
+            .unwrap();
+        assert_eq!(witness.root, root);
+        assert_eq!(witness.leaves.len(), 5);
+        assert_eq!(witness.start_index_bits, vec![true, true, false, true]);
+
+        // A digest that no field element encodes is rejected rather than reduced
+        let mut proof = tree.prove_inclusion(3);
+        proof.siblings[1] = Digest([0xff; 32]);
+        assert_eq!(
+            proof.to_circuit(entries[3]),
+            Err(VerificationError::NonCanonicalProof)
+        );
+
+        // A sibling plus the modulus hashes like the sibling, but verification rejects it
+        let modulus: Digest = "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001"
+            .parse()
+            .unwrap();
+        let mut proof = tree.prove_inclusion(3);
+        let sibling = proof.siblings[1];
+        let mut carry = 0;
+        for i in (0..32).rev() {
+            let sum = sibling.0[i] as u16 + modulus.0[i] as u16 + carry;
+            proof.siblings[1].0[i] = sum as u8;
+            carry = sum >> 8;
+        }
+        assert_eq!(carry, 0);
+        assert_eq!(
+            proof.compute_root_with_hasher(&hasher, entries[3]),
+            tree.root().hash
+        );
+        assert_eq!(
+            proof.verify_with_hasher(&hasher, &tree.root().hash, 16, entries[3]),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        proof.siblings[1] = sibling;
+        proof
+            .verify_with_hasher(&hasher, &tree.root().hash, 16, entries[3])
+            .unwrap();
+    }
+
+    #[cfg(feature = "arkworks")]
+    #[test]
+    fn test_arkworks_witnesses() {
+        use crate::arkworks::{poseidon_config, ArkworksWitness};
+        use ads_core::poseidon::{self, Fr};
+
+        let config = poseidon_config();
+        assert_eq!(config.ark.len(), config.full_rounds + config.partial_rounds);
+        assert!(config.ark.iter().all(|row| row.len() == 3));
+        assert_eq!((config.rate, config.capacity, config.alpha), (2, 1, 5));
+
+        let hasher = NodeHasher::new(HashAlgo::Poseidon);
+        let strings: Vec<String> = (0..8).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let tree = PerfectMerkleTree::new_with_hasher(entries.clone(), HashBackend::Scalar, hasher);
+        let node = |left: Fr, right: Fr| {
+            poseidon::to_field(&poseidon::hash_children(
+                &poseidon::to_digest(left),
+                &poseidon::to_digest(right),
+            ))
+        };
+
+        // What arkworks' `Path::verify` computes, with the identity leaf hash
+        let check = |witness: &ArkworksWitness| {
+            let path = &witness.path;
+            let mut current = if path.leaf_index & 1 == 0 {
+                node(witness.leaf, path.leaf_sibling_hash)
+            } else {
+                node(path.leaf_sibling_hash, witness.leaf)
+            };
+            for (level, &sibling) in path.auth_path.iter().rev().enumerate() {
+                current = if (path.leaf_index >> (level + 1)) & 1 == 0 {
+                    node(current, sibling)
+                } else {
+                    node(sibling, current)
+                };
+            }
+            current
+        };
+        for index in 0..8 {
+            let witness = tree.arkworks_witness(index);
+            assert_eq!(witness.path.leaf_index, index as usize);
+            assert_eq!(witness.path.auth_path.len(), 2);
+            assert_eq!(
+                witness.leaf,
+                poseidon::to_field(&hasher.hash_leaf(entries[index as usize]))
+            );
+            assert_eq!(witness.root, poseidon::to_field(&tree.root().hash));
+            assert_eq!(check(&witness), witness.root);
+        }
+    }
+
+    #[test]
+    fn test_bitcoin_merkle_trees() {
+        // Block 100000, with ids and root in the usual reversed display order
+        let displayed = |hex: &str| {
+            let mut digest: Digest = hex.parse().unwrap();
+            digest.0.reverse();
+            digest
+        };
+        let txids: Vec<Digest> = [
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+        ]
+        .into_iter()
+        .map(displayed)
+        .collect();
+        let tree = BitcoinMerkleTree::new(txids.clone());
+        let root = displayed("f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766");
+        assert_eq!(tree.root(), root);
+        for (index, txid) in txids.iter().enumerate() {
+            let proof = tree.prove(index as u64);
+            assert_eq!(proof.siblings.len(), 2);
+            assert!(proof.verify(&root, txid));
+            assert!(!proof.verify(&root, &txids[(index + 1) % 4]));
+        }
+
+        // Odd levels duplicate their last node, so a tree of 3 has the root of the tree of 4
+        // repeating the last transaction, and proofs say which one they are about
+        assert_eq!(
+            merkle_root(&txids[..3]),
+            merkle_root(&[&txids[..3], &txids[2..3]].concat())
+        );
+        assert_eq!(merkle_root(&txids[..1]), Some(txids[0]));
+        assert_eq!(merkle_root(&[]), None);
+        for n in 1..=7 {
+            let tree = BitcoinMerkleTree::new(txids.iter().cycle().take(n).copied().collect());
+            for index in 0..n as u64 {
+                let proof = tree.prove(index);
+                assert!(proof.verify(&tree.root(), &txids[index as usize % 4]));
+            }
+        }
+        let tree = BitcoinMerkleTree::new(txids[..3].to_vec());
+        let mut proof = tree.prove(2);
+        proof.num_transactions = 4;
+        proof.tx_index = 3;
+        assert!(proof.verify(&tree.root(), &txids[2]));
+        proof.num_transactions = 3;
+        assert!(!proof.verify(&tree.root(), &txids[2]));
+        let mut proof = tree.prove(2);
+        proof.siblings[0] = txids[3];
+        assert!(!proof.verify(&tree.root(), &txids[2]));
+    }
+
+    #[test]
+    fn test_ethereum_merkle_trees() {
+        assert_eq!(
+            keccak256(b"").to_string(),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        let values: Vec<Vec<u8>> = (0..5).map(|i| format!("claim{}", i).into_bytes()).collect();
+        let leaves: Vec<Digest> = values.iter().map(|value| keccak256(value)).collect();
+
+        // Nodes hash the raw concatenation, sorted or not
+        let pair = [leaves[0].0, leaves[1].0].concat();
+        let swapped = [leaves[1].0, leaves[0].0].concat();
+        let positional = EthereumMerkleTree::new(PairOrder::Positional, leaves[..2].to_vec());
+        let sorted = EthereumMerkleTree::new(PairOrder::Sorted, leaves[..2].to_vec());
+        assert_eq!(positional.root(), keccak256(&pair));
+        assert_eq!(
+            sorted.root(),
+            keccak256(if leaves[0].0 < leaves[1].0 {
+                &pair
+            } else {
+                &swapped
+            })
+        );
+
+        for order in [PairOrder::Positional, PairOrder::Sorted] {
+            let tree = EthereumMerkleTree::new(order, leaves.clone());
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = tree.prove(index as u64);
+                // The last leaf of 5 moves up twice without a sibling
+                assert_eq!(proof.siblings.len(), if index == 4 { 1 } else { 3 });
+                assert!(proof.verify(&tree.root(), leaf));
+                assert!(!proof.verify(&tree.root(), &leaves[(index + 1) % 5]));
+                if order == PairOrder::Sorted {
+                    assert_eq!(process_sorted_proof(leaf, &proof.siblings), tree.root());
+                }
+            }
+        }
+        let tree = EthereumMerkleTree::new(PairOrder::Positional, leaves.clone());
+        let mut proof = tree.prove(1);
+        proof.leaf_index = 0;
+        assert!(!proof.verify(&tree.root(), &leaves[1]));
+        proof.leaf_index = 5;
+        assert!(proof.compute_root(&leaves[1]).is_none());
+    }
+
+    #[test]
+    fn test_ics23_export() {
+        use crate::ics23::{proof_spec, CommitmentProof, HashOp};
+
+        let strings: Vec<String> = (0..8).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        for algo in [HashAlgo::Sha256, HashAlgo::Keccak256] {
+            let tree = PerfectMerkleTree::new_with_hasher(
+                entries.clone(),
+                HashBackend::Scalar,
+                NodeHasher::new(algo),
+            );
+            let spec = proof_spec(algo).unwrap();
+            assert_eq!(spec.inner_spec.hash, HashOp::of(algo).unwrap());
+            for (index, entry) in entries.iter().enumerate() {
+                let Some(CommitmentProof::Exist(proof)) =
+                    tree.prove_inclusion(index as u64).to_ics23(algo, entry)
+                else {
+                    panic!("{} has an ICS-23 operation", algo);
+                };
+                assert_eq!(proof.calculate(), Ok(tree.root().hash));
+                // Inner ops are laid out as the spec says
+                for inner in &proof.path {
+                    let child_size = spec.inner_spec.child_size as usize;
+                    assert!(
+                        (inner.prefix.len(), inner.suffix.len()) == (2, child_size)
+                            || (inner.prefix.len(), inner.suffix.len()) == (2 + child_size, 0)
+                    );
+                    assert_ne!(inner.prefix[0], proof.leaf.prefix[0]);
+                }
+            }
+        }
+
+        // ICS-23 has no Blake2b256
+        let tree = PerfectMerkleTree::new(entries.clone());
+        assert!(proof_spec(HashAlgo::Blake2b256).is_none());
+        assert!(tree
+            .prove_inclusion(0)
+            .to_ics23(HashAlgo::Blake2b256, entries[0])
+            .is_none());
+    }
+
+    #[test]
+    fn test_ics23_verification() {
+        use crate::ics23::HashOp::{NoHash, Sha256};
+        use crate::ics23::{
+            verify_membership, verify_non_membership, CommitmentProof, ExistenceProof, Ics23Error,
+            InnerOp, InnerSpec, LeafOp, LengthOp, NonExistenceProof, ProofSpec,
+        };
+
+        // This crate's proofs against its commitments
+        let strings: Vec<String> = (0..11).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mmr = MerkleMountainRange::new_with_hasher(
+            entries.clone(),
+            HashBackend::Scalar,
+            NodeHasher::new(HashAlgo::Sha256),
+        );
+        let commitment = mmr.commitment();
+        for (index, entry) in entries.iter().enumerate() {
+            let proof = mmr.prove_inclusion(index as u64);
+            let exported = proof.proof.to_ics23(HashAlgo::Sha256, entry).unwrap();
+            assert_eq!(commitment.verify_ics23_membership(&exported, entry), Ok(()));
+            assert_eq!(
+                commitment.verify_ics23_membership(&exported, b"other"),
+                Err(Ics23Error::KeyValueMismatch)
+            );
+        }
+        let exported = MerkleMountainRange::new(entries.clone())
+            .prove_inclusion(0)
+            .proof
+            .to_ics23(HashAlgo::Sha256, entries[0])
+            .unwrap();
+        assert_eq!(
+            MerkleMountainRange::new(entries.clone())
+                .commitment()
+                .verify_ics23_membership(&exported, entries[0]),
+            Err(Ics23Error::UnsupportedOp)
+        );
+
+        // Another producer's tree sorted by key, with prefixed leaves and nodes
+        let spec = ProofSpec {
+            leaf_spec: LeafOp {
+                hash: Sha256,
+                prehash_key: NoHash,
+                prehash_value: Sha256,
+                length: LengthOp::VarProto,
+                prefix: vec![0],
+            },
+            inner_spec: InnerSpec {
+                child_order: vec![0, 1],
+                child_size: 32,
+                min_prefix_length: 1,
+                max_prefix_length: 1,
+                empty_child: vec![],
+                hash: Sha256,
+            },
+            max_depth: 0,
+            min_depth: 0,
+            prehash_key_before_comparison: false,
+        };
+        let keys: [&[u8]; 4] = [b"a", b"c", b"e", b"g"];
+        let leaf = |key: &[u8]| ExistenceProof {
+            key: key.to_vec(),
+            value: [b"value-", key].concat(),
+            leaf: spec.leaf_spec.clone(),
+            path: vec![],
+        };
+        let leaves: Vec<Digest> = keys
+            .iter()
+            .map(|key| leaf(key).calculate().unwrap())
+            .collect();
+        let sha256 = NodeHasher::new(HashAlgo::Sha256);
+        let node = |left: &Digest, right: &Digest| {
+            sha256.hash_message(&[&[1][..], &left.0, &right.0].concat())
+        };
+        let level = [node(&leaves[0], &leaves[1]), node(&leaves[2], &leaves[3])];
+        let root = node(&level[0], &level[1]);
+        let step = |sibling: &Digest, is_right: bool| InnerOp {
+            hash: Sha256,
+            prefix: if is_right {
+                [&[1][..], &sibling.0].concat()
+            } else {
+                vec![1]
+            },
+            suffix: if is_right { vec![] } else { sibling.0.to_vec() },
+        };
+        let prove = |index: usize| ExistenceProof {
+            path: vec![
+                step(&leaves[index ^ 1], index & 1 == 1),
+                step(&level[(index >> 1) ^ 1], index & 2 == 2),
+            ],
+            ..leaf(keys[index])
+        };
+        for (index, key) in keys.iter().enumerate() {
+            let proof = CommitmentProof::Exist(prove(index));
+            let value = [b"value-", *key].concat();
+            assert_eq!(verify_membership(&proof, &spec, &root, key, &value), Ok(()));
+            assert_eq!(
+                verify_non_membership(&proof, &spec, &root, key),
+                Err(Ics23Error::WrongProofType)
+            );
+        }
+
+        let absent = |key: &[u8], left: Option<usize>, right: Option<usize>| {
+            let proof = CommitmentProof::Nonexist(NonExistenceProof {
+                key: key.to_vec(),
+                left: left.map(prove),
+                right: right.map(prove),
+            });
+            verify_non_membership(&proof, &spec, &root, key)
+        };
+        assert_eq!(absent(b"d", Some(1), Some(2)), Ok(()));
+        assert_eq!(absent(b"b", Some(0), Some(1)), Ok(()));
+        assert_eq!(absent(b"0", None, Some(0)), Ok(()));
+        assert_eq!(absent(b"h", Some(3), None), Ok(()));
+        assert_eq!(
+            absent(b"b", Some(0), Some(2)),
+            Err(Ics23Error::NotNeighbors)
+        );
+        assert_eq!(absent(b"0", None, Some(1)), Err(Ics23Error::NotNeighbors));
+        assert_eq!(absent(b"h", Some(2), None), Err(Ics23Error::NotNeighbors));
+        assert_eq!(
+            absent(b"c", Some(0), Some(2)),
+            Err(Ics23Error::NotNeighbors)
+        );
+        assert_eq!(absent(b"d", None, None), Err(Ics23Error::EmptyProof));
+
+        // A node passed off as a leaf, or a tampered path, doesn't verify
+        let mut proof = prove(1);
+        proof.path[0].prefix = vec![0];
+        assert_eq!(
+            proof.verify(&spec, &root, b"c", b"value-c"),
+            Err(Ics23Error::SpecMismatch)
+        );
+        let mut proof = prove(1);
+        proof.path[1].suffix[0] ^= 1;
+        assert_eq!(
+            proof.verify(&spec, &root, b"c", b"value-c"),
+            Err(Ics23Error::Verification(VerificationError::RootMismatch))
+        );
+    }
+
+    #[test]
+    fn test_ct_proofs() {
+        use crate::ct::{leaf_hash, root, CtConsistencyProof, CtInclusionProof, SignedTreeHead};
+
+        // The definitions of RFC 6962, section 2.1
+        fn split(n: usize) -> usize {
+            1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+        }
+        fn path(m: usize, leaves: &[Digest]) -> Vec<Digest> {
+            let n = leaves.len();
+            if n == 1 {
+                return vec![];
+            }
+            let k = split(n);
+            if m < k {
+                [path(m, &leaves[..k]), vec![root(&leaves[k..])]].concat()
+            } else {
+                [path(m - k, &leaves[k..]), vec![root(&leaves[..k])]].concat()
+            }
+        }
+        fn subproof(m: usize, leaves: &[Digest], complete: bool) -> Vec<Digest> {
+            let n = leaves.len();
+            if m == n {
+                return if complete { vec![] } else { vec![root(leaves)] };
+            }
+            let k = split(n);
+            if m <= k {
+                [
+                    subproof(m, &leaves[..k], complete),
+                    vec![root(&leaves[k..])],
+                ]
+                .concat()
+            } else {
+                [
+                    subproof(m - k, &leaves[k..], false),
+                    vec![root(&leaves[..k])],
+                ]
+                .concat()
+            }
+        }
+
+        // The test vectors of the reference implementation
+        let inputs: [&[u8]; 8] = [
+            b"",
+            b"\x00",
+            b"\x10",
+            b"\x20\x21",
+            b"\x30\x31",
+            b"\x40\x41\x42\x43",
+            b"\x50\x51\x52\x53\x54\x55\x56\x57",
+            b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f",
+        ];
+        let leaves: Vec<Digest> = inputs.iter().map(|input| leaf_hash(input)).collect();
+        assert_eq!(
+            root(&[]).to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            root(&leaves).to_string(),
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"
+        );
+
+        for n in 1..=leaves.len() {
+            let tree_root = root(&leaves[..n]);
+            for m in 0..n {
+                let proof = CtInclusionProof {
+                    leaf_index: m as u64,
+                    audit_path: path(m, &leaves[..n]),
+                };
+                assert_eq!(proof.verify(n as u64, &tree_root, &leaves[m]), Ok(()));
+                assert_eq!(
+                    proof.verify(n as u64, &tree_root, &leaves[(m + 1) % n]),
+                    if n == 1 {
+                        Ok(())
+                    } else {
+                        Err(VerificationError::RootMismatch)
+                    }
+                );
+                if !proof.audit_path.is_empty() {
+                    let mut short = proof.clone();
+                    short.audit_path.pop();
+                    assert!(short.verify(n as u64, &tree_root, &leaves[m]).is_err());
+                }
+            }
+            for m in 1..=n {
+                let proof = CtConsistencyProof {
+                    consistency: subproof(m, &leaves[..n], true),
+                };
+                let old_root = root(&leaves[..m]);
+                assert_eq!(
+                    proof.verify(m as u64, &old_root, n as u64, &tree_root),
+                    Ok(())
+                );
+                if m < n {
+                    assert_eq!(
+                        proof.verify(m as u64, &tree_root, n as u64, &tree_root),
+                        Err(VerificationError::RootMismatch)
+                    );
+                    let mut long = proof.clone();
+                    long.consistency.push(tree_root);
+                    assert!(long
+                        .verify(m as u64, &old_root, n as u64, &tree_root)
+                        .is_err());
+                }
+            }
+        }
+
+        // Responses of the log API
+        let sth: SignedTreeHead = serde_json::from_str(
+            r#"{"tree_size":8,"timestamp":1700000000000,
+                "sha256_root_hash":"XcnaeacGWamtVZy3Ad7ZoqudgjqtL0lgz+Nw7/RgQyg=",
+                "tree_head_signature":"BAMARzBF"}"#,
+        )
+        .unwrap();
+        assert_eq!(sth.sha256_root_hash, root(&leaves));
+        let proof = CtInclusionProof {
+            leaf_index: 5,
+            audit_path: path(5, &leaves),
+        };
+        let json = serde_json::to_string(&proof).unwrap();
+        let parsed: CtInclusionProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed.verify(sth.tree_size, &sth.sha256_root_hash, &leaves[5]),
+            Ok(())
+        );
+        assert!(serde_json::from_str::<CtConsistencyProof>(r#"{"consistency":["AAAA"]}"#).is_err());
+    }
+
+    #[cfg(feature = "wire")]
+    #[test]
+    fn test_wire_protocol() {
+        use crate::wire::{
+            decode_frame, encode_frame, read_frame, respond, write_frame, Frame, Request, Response,
+            WireError, FRAME_HEADER_LENGTH, MAX_FRAME_LENGTH,
+        };
+
+        let strings: Vec<String> = (0..11).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mmr = MerkleMountainRange::new(entries.clone());
+        let old = mmr.commitment_at(6);
+
+        // A client pipelining requests over a stream, and the server answering them in order
+        let requests = [
+            Request::GetRoot,
+            Request::ProveInclusion { index: 4 },
+            Request::ProveSuffix { num_entries: 3 },
+            Request::ProveConsistency { old_num_entries: 6 },
+            Request::ProveInclusion { index: 11 },
+            Request::VerifyAck {
+                num_entries: 11,
+                verified: true,
+            },
+        ];
+        let mut stream = vec![];
+        for (id, request) in requests.iter().enumerate() {
+            write_frame(&mut stream, id as u64 + 100, request).unwrap();
+        }
+        let mut incoming = stream.as_slice();
+        let mut answers = vec![];
+        for _ in &requests {
+            let frame: Frame<Request> = read_frame(&mut incoming).unwrap();
+            answers.extend(encode_frame(frame.id, &now(respond(&mmr, frame.message))));
+        }
+        assert!(incoming.is_empty());
+
+        let mut responses = vec![];
+        let mut buffer = answers.as_slice();
+        while let Some((frame, used)) = decode_frame::<Response>(buffer).unwrap() {
+            responses.push(frame);
+            buffer = &buffer[used..];
+        }
+        assert!(buffer.is_empty());
+        assert_eq!(
+            responses.iter().map(|frame| frame.id).collect::<Vec<_>>(),
+            (100..106).collect::<Vec<_>>()
+        );
+        let Response::Root(commitment) = &responses[0].message else {
+            panic!("Expected the commitment");
+        };
+        assert_eq!(*commitment, mmr.commitment());
+        let Response::Inclusion(proof) = &responses[1].message else {
+            panic!("Expected an inclusion proof");
+        };
+        assert!(commitment.verify_inclusion(proof).is_ok());
+        let Response::Suffix(proof) = &responses[2].message else {
+            panic!("Expected a suffix proof");
+        };
+        assert!(commitment.verify_most_recent_n_elements(proof).is_ok());
+        let Response::Consistency(proof) = &responses[3].message else {
+            panic!("Expected a consistency proof");
+        };
+        assert!(commitment.verify_consistency(&old, proof).is_ok());
+        assert_eq!(
+            responses[4].message,
+            Response::Error("No entry at index 11".to_string())
+        );
+        assert_eq!(responses[5].message, Response::Ack);
+
+        // Partial, oversized and foreign frames
+        let frame = encode_frame(1, &Request::GetRoot);
+        for end in 0..frame.len() {
+            assert!(decode_frame::<Request>(&frame[..end]).unwrap().is_none());
+        }
+        let mut oversized = frame.clone();
+        oversized[..FRAME_HEADER_LENGTH].copy_from_slice(&(MAX_FRAME_LENGTH + 1).to_be_bytes());
+        assert!(matches!(
+            decode_frame::<Request>(&oversized),
+            Err(WireError::FrameTooLarge(_))
+        ));
+        let mut future = frame.clone();
+        future[FRAME_HEADER_LENGTH] = 2;
+        assert!(matches!(
+            decode_frame::<Request>(&future),
+            Err(WireError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            decode_frame::<Response>(&frame),
+            Err(WireError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_compressed_bundles() {
+        use crate::compressed::{CompressedProofBundle, ProofBundle};
+
+        let strings: Vec<String> = (0..1000).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mmr = MerkleMountainRange::new(entries.clone());
+        let commitment = mmr.commitment();
+        let bundle = ProofBundle {
+            inclusion_proofs: [3, 4, 5, 6, 700].map(|i| mmr.prove_inclusion(i)).to_vec(),
+            suffix_proofs: vec![
+                mmr.prove_most_recent_n_elements(700),
+                mmr.prove_most_recent_n_elements(2),
+            ],
+        };
+        assert_eq!(commitment.verify_bundle(&bundle), Ok(()));
+
+        let compressed = bundle.compress().unwrap();
+        assert_eq!(compressed.decompress(), Ok(bundle.clone()));
+        assert_eq!(commitment.verify_compressed_bundle(&compressed), Ok(()));
+        // Siblings shared by neighbors are written once, and indices take a byte or two
+        let encoded = bcs::to_bytes(&bundle).unwrap();
+        assert!(compressed.0.len() < encoded.len());
+        let inclusions = ProofBundle {
+            inclusion_proofs: bundle.inclusion_proofs.clone(),
+            suffix_proofs: vec![],
+        };
+        let siblings: usize = inclusions
+            .inclusion_proofs
+            .iter()
+            .map(|proof| proof.proof.siblings.len())
+            .sum();
+        assert!(inclusions.compress().unwrap().0.len() < siblings * 32);
+
+        let empty = ProofBundle::default().compress().unwrap();
+        assert_eq!(empty.decompress(), Ok(ProofBundle::default()));
+
+        // Proofs about MMRs hashed differently don't go in the same bundle
+        let sha256 = MerkleMountainRange::new_with_hasher(
+            entries[..10].to_vec(),
+            HashBackend::Scalar,
+            NodeHasher::new(HashAlgo::Sha256),
+        );
+        let mut mixed = bundle.clone();
+        mixed.inclusion_proofs.push(sha256.prove_inclusion(1));
+        assert_eq!(
+            mixed.compress(),
+            Err(VerificationError::HashAlgoMismatch {
+                expected: HashAlgo::Blake2b256,
+                actual: HashAlgo::Sha256
+            })
+        );
+        let compressed_sha256 = ProofBundle {
+            inclusion_proofs: vec![sha256.prove_inclusion(1)],
+            suffix_proofs: vec![sha256.prove_most_recent_n_elements(3)],
+        }
+        .compress()
+        .unwrap();
+        assert_eq!(
+            sha256
+                .commitment()
+                .verify_compressed_bundle(&compressed_sha256),
+            Ok(())
+        );
+
+        // Any change either fails to decode or to verify, and a truncated bundle never decodes
+        let mut tampered = compressed.clone();
+        tampered.0[10] ^= 1;
+        assert_eq!(
+            commitment.verify_compressed_bundle(&tampered),
+            Err(VerificationError::RootMismatch)
+        );
+        let mut trailing = compressed.clone();
+        trailing.0.push(0);
+        assert_eq!(
+            trailing.decompress(),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        for end in 0..compressed.0.len() {
+            assert!(CompressedProofBundle(compressed.0[..end].to_vec())
+                .decompress()
+                .is_err());
+        }
+        // Varints without redundant bytes
+        let mut overlong = empty.0.clone();
+        overlong[2] = 0x80;
+        overlong.insert(3, 0);
+        assert_eq!(
+            CompressedProofBundle(overlong).decompress(),
+            Err(VerificationError::NonCanonicalProof)
+        );
+    }
+
+    #[test]
+    fn test_streaming_verification() {
+        use crate::stream::{verify_most_recent_from_reader, StreamVerificationError};
+        use crate::streaming::{MostRecentVerifier, SuffixAccumulator};
+
+        // Suffixes of a single tree, against the buffered verification
+        let strings: Vec<String> = (0..16).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let tree = PerfectMerkleTree::new(entries.clone());
+        for n in 1..=16 {
+            let proof = tree.prove_most_recent_n_elements(n);
+            let mut accumulator = SuffixAccumulator::new(16, n).unwrap();
+            for entry in &entries[16 - n as usize..] {
+                accumulator.push(entry).unwrap();
+            }
+            assert!(accumulator.is_complete());
+            assert!(accumulator.push(b"extra").is_err());
+            assert_eq!(accumulator.finalize(&proof.proof), Ok(tree.root().hash));
+        }
+        let mut short = SuffixAccumulator::new(16, 3).unwrap();
+        short.push(entries[13]).unwrap();
+        assert!(short.finalize(&[]).is_err());
+        assert!(SuffixAccumulator::new(12, 3).is_err());
+
+        let strings: Vec<String> = (0..1000).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mmr = MerkleMountainRange::new(entries.clone());
+        let commitment = mmr.commitment();
+        for n in [1, 8, 104, 105, 488, 999, 1000] {
+            let proof = mmr.prove_most_recent_n_elements(n);
+            let partial = proof.partial_tree_proof().map(|(_, proof)| proof);
+            assert_eq!(
+                commitment.verify_most_recent_streaming(n, &proof.entries, partial),
+                Ok(())
+            );
+            let verifier = MostRecentVerifier::new(&commitment, n).unwrap();
+            assert_eq!(verifier.full_tree_indices(), proof.full_tree_indices());
+            assert_eq!(
+                verifier.partial_tree_index(),
+                proof.partial_tree_proof().map(|(index, _)| index)
+            );
+            let encoded = bcs::to_bytes(&proof).unwrap();
+            assert_eq!(
+                verify_most_recent_from_reader(&commitment, encoded.as_slice()).unwrap(),
+                n
+            );
+        }
+
+        // A wrong entry fails as soon as its tree is complete, before the rest is read
+        let proof = mmr.prove_most_recent_n_elements(105);
+        let mut verifier = MostRecentVerifier::new(&commitment, 105).unwrap();
+        let mut pushed = proof.entries.iter();
+        for entry in pushed.by_ref().take(40) {
+            verifier.push(entry).unwrap();
+        }
+        verifier.push(b"forged").unwrap();
+        let mut result = Ok(());
+        for entry in pushed.by_ref().skip(1).take(63) {
+            result = result.and_then(|_| verifier.push(entry));
+        }
+        assert_eq!(result, Err(VerificationError::RootMismatch));
+
+        let partial = proof.partial_tree_proof().map(|(_, proof)| proof);
+        assert!(commitment
+            .verify_most_recent_streaming(105, &proof.entries[1..], partial)
+            .is_err());
+        assert!(commitment
+            .verify_most_recent_streaming(104, &proof.entries[1..], partial)
+            .is_err());
+        assert_eq!(
+            commitment.verify_most_recent_streaming(1001, &entries, None),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 1000,
+                actual: 1001
+            })
+        );
+
+        // Streams that are truncated, tampered with or carry trailing bytes. Dropping the hash
+        // algorithm, as proofs before version 4 don't have it, doesn't verify either.
+        let encoded = bcs::to_bytes(&proof).unwrap();
+        for end in [0, 1, 2, 50, encoded.len() - 2, encoded.len() - 1] {
+            assert!(matches!(
+                verify_most_recent_from_reader(&commitment, &encoded[..end]),
+                Err(StreamVerificationError::Malformed)
+            ));
+        }
+        let mut outdated = encoded.clone();
+        outdated[0] = 3;
+        assert!(matches!(
+            verify_most_recent_from_reader(&commitment, outdated.as_slice()),
+            Err(StreamVerificationError::Verification(
+                VerificationError::OutdatedVersion(3)
+            ))
+        ));
+        let mut tampered = encoded.clone();
+        tampered[10] ^= 1;
+        assert!(matches!(
+            verify_most_recent_from_reader(&commitment, tampered.as_slice()),
+            Err(StreamVerificationError::Verification(
+                VerificationError::RootMismatch
+            ))
+        ));
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(matches!(
+            verify_most_recent_from_reader(&commitment, trailing.as_slice()),
+            Err(StreamVerificationError::Malformed)
+        ));
+
+        // Large entries are read a chunk at a time
+        let blob: Vec<u8> = (0..3 * CHUNK_SIZE + 17).map(|i| i as u8).collect();
+        let mut blobs = entries[..5].to_vec();
+        blobs.push(blob.as_slice());
+        let mmr = MerkleMountainRange::new(blobs);
+        let encoded = bcs::to_bytes(&mmr.prove_most_recent_n_elements(3)).unwrap();
+        assert_eq!(
+            verify_most_recent_from_reader(&mmr.commitment(), encoded.as_slice()).unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_verification_limits() {
+        use crate::limits::{Bounded, Limit, ProofCost, VerificationLimits};
+        use crate::stream::{verify_most_recent_from_reader_with_limits, StreamVerificationError};
+
+        let strings: Vec<String> = (0..1000).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mmr = MerkleMountainRange::new(entries.clone());
+        let commitment = mmr.commitment();
+        let suffix = mmr.prove_most_recent_n_elements(105);
+        let inclusion = mmr.prove_inclusion(3);
+        let consistency = mmr.prove_consistency(600);
+        assert_eq!(
+            suffix.cost(&commitment),
+            ProofCost {
+                proof_elements: 7,
+                entries: 105,
+                tree_size: 1000
+            }
+        );
+        assert_eq!(inclusion.cost(&commitment).proof_elements, 9);
+
+        let limits = VerificationLimits::default();
+        assert_eq!(limits.verify(&suffix, &commitment, &()), Ok(()));
+        assert_eq!(limits.verify(&inclusion, &commitment, &()), Ok(()));
+        assert_eq!(
+            limits.verify(&consistency, &commitment, &mmr.commitment_at(600)),
+            Ok(())
+        );
+
+        // Checked before any hashing, so a forged proof over the limit fails on the limit
+        let tight = VerificationLimits {
+            max_proof_elements: 8,
+            max_entries: 100,
+            max_tree_size: 1000,
+        };
+        let mut forged = suffix.clone();
+        forged.entries[0].push(0);
+        assert_eq!(
+            tight.verify(&forged, &commitment, &()),
+            Err(VerificationError::LimitExceeded {
+                limit: Limit::Entries,
+                max: 100,
+                actual: 105
+            })
+        );
+        assert_eq!(
+            tight.verify(&inclusion, &commitment, &()),
+            Err(VerificationError::LimitExceeded {
+                limit: Limit::ProofElements,
+                max: 8,
+                actual: 9
+            })
+        );
+        let bigger = MerkleMountainRange::new(
+            strings
+                .iter()
+                .chain(&strings)
+                .map(|s| s.as_bytes())
+                .collect(),
+        );
+        assert_eq!(
+            tight.verify(
+                &bigger.prove_most_recent_n_elements(1),
+                &bigger.commitment(),
+                &()
+            ),
+            Err(VerificationError::LimitExceeded {
+                limit: Limit::TreeSize,
+                max: 1000,
+                actual: 2000
+            })
+        );
+
+        // Single trees, with the size from the commitment or from the proof
+        let tree = PerfectMerkleTree::new(entries[..16].to_vec());
+        let root = tree.root().hash;
+        let leaf_proof = tree.prove_inclusion(5);
+        assert_eq!(tight.verify(&leaf_proof, &(root, 16), entries[5]), Ok(root));
+        let tree_suffix = tree.prove_most_recent_n_elements(3);
+        let suffix_entries: Vec<Vec<u8>> = entries[13..16].iter().map(|e| e.to_vec()).collect();
+        assert_eq!(
+            tight.verify(&tree_suffix, &root, suffix_entries.as_slice()),
+            Ok(root)
+        );
+        let small = VerificationLimits {
+            max_tree_size: 8,
+            ..VerificationLimits::UNLIMITED
+        };
+        assert!(matches!(
+            small.verify(&tree_suffix, &root, suffix_entries.as_slice()),
+            Err(VerificationError::LimitExceeded {
+                limit: Limit::TreeSize,
+                ..
+            })
+        ));
+
+        // A stream announcing more entries than allowed is rejected before they're read
+        let encoded = bcs::to_bytes(&suffix).unwrap();
+        assert!(matches!(
+            verify_most_recent_from_reader_with_limits(&commitment, &encoded[..2], &tight),
+            Err(StreamVerificationError::Verification(
+                VerificationError::LimitExceeded {
+                    limit: Limit::Entries,
+                    ..
+                }
+            ))
+        ));
+        assert_eq!(
+            verify_most_recent_from_reader_with_limits(
+                &commitment,
+                encoded.as_slice(),
+                &VerificationLimits::default()
+            )
+            .unwrap(),
+            105
+        );
+    }
+
+    #[test]
+    fn test_hardened_deserialization() {
+        use crate::{MmrInclusionProof, MmrMultiInclusionProof, MultiInclusionProof};
+
+        fn decode<T: serde::de::DeserializeOwned>(value: &impl serde::Serialize) -> Option<T> {
+            bcs::from_bytes(&bcs::to_bytes(value).unwrap()).ok()
+        }
+
+        let strings: Vec<String> = (0..13).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mmr = MerkleMountainRange::new(entries.clone());
+        let inclusion = mmr.prove_inclusion(5);
+        let multi = mmr.prove_multi_inclusion(&[1, 2, 9, 12]);
+        let suffix = mmr.prove_most_recent_n_elements(7);
+        let consistency = mmr.prove_consistency(6);
+        assert_eq!(decode(&inclusion), Some(inclusion.clone()));
+        assert_eq!(decode(&multi), Some(multi.clone()));
+        assert_eq!(decode(&suffix), Some(suffix.clone()));
+        assert_eq!(decode(&consistency), Some(consistency.clone()));
+
+        // Paths that don't fit the tree they claim
+        let mut long = inclusion.clone();
+        long.proof.siblings.push(EMPTY_ROOT);
+        assert_eq!(
+            long.check_shape(),
+            Err(VerificationError::UnusedProofElements)
+        );
+        assert_eq!(decode::<MmrInclusionProof>(&long), None);
+        let mut outside = inclusion.clone();
+        outside.proof.leaf_index = 8;
+        assert_eq!(
+            outside.check_shape(),
+            Err(VerificationError::LeafIndexOutOfBounds(8))
+        );
+        assert_eq!(decode::<InclusionProof>(&outside.proof), None);
+        let mut beyond = inclusion.clone();
+        beyond.tree_index = 64;
+        assert_eq!(
+            beyond.check_shape(),
+            Err(VerificationError::TreeIndexOutOfBounds(64))
+        );
+        let endless = InclusionProof::new(0, vec![EMPTY_ROOT; 65]);
+        assert_eq!(decode::<InclusionProof>(&endless), None);
+
+        // Trees repeated or out of order, and leaves or entries that don't match up
+        let mut repeated = multi.clone();
+        repeated
+            .tree_proofs
+            .insert(0, repeated.tree_proofs[0].clone());
+        assert_eq!(
+            repeated.check_shape(),
+            Err(VerificationError::DuplicateTreeIndex(3))
+        );
+        assert_eq!(decode::<MmrMultiInclusionProof>(&repeated), None);
+        let mut reordered = multi.clone();
+        reordered.tree_proofs.reverse();
+        assert_eq!(
+            reordered.check_shape(),
+            Err(VerificationError::NonCanonicalProof)
+        );
+        let mut extra = multi.clone();
+        extra.entries.pop();
+        assert_eq!(
+            extra.check_shape(),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 4,
+                actual: 3
+            })
+        );
+        let mut unsorted = multi.tree_proofs[0].1.clone();
+        unsorted.leaf_indices.swap(0, 1);
+        assert_eq!(decode::<MultiInclusionProof>(&unsorted), None);
+        let mut past = multi.clone();
+        past.tree_proofs[0].1.leaf_indices[1] = 8;
+        assert_eq!(
+            past.check_shape(),
+            Err(VerificationError::LeafIndexOutOfBounds(8))
+        );
+
+        let mut duplicate = suffix.clone();
+        duplicate.full_tree_indices = vec![2, 2];
+        assert_eq!(decode::<MostRecentNElementsProof>(&duplicate), None);
+        let mut odd = suffix.partial_tree_proof().unwrap().1.clone();
+        odd.num_leaves = Some(6);
+        assert_eq!(
+            odd.check_shape(),
+            Err(VerificationError::InvalidTreeSize(6))
+        );
+        assert_eq!(decode::<SuffixProof>(&odd), None);
+        let mut overfull = odd.clone();
+        overfull.num_leaves = Some(8);
+        overfull.num_suffix_elements = 9;
+        assert_eq!(
+            overfull.check_shape(),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 8,
+                actual: 9
+            })
+        );
+        let mut long_consistency = consistency.clone();
+        long_consistency.paths[0] = vec![EMPTY_ROOT; 64];
+        assert_eq!(decode::<ConsistencyProof>(&long_consistency), None);
+
+        // Verification reports a tree size no perfect tree has instead of asserting it
+        let tree = PerfectMerkleTree::new(entries[..8].to_vec());
+        assert_eq!(
+            tree.prove_inclusion(1)
+                .verify(&tree.root().hash, 6, entries[1]),
+            Err(VerificationError::InvalidTreeSize(6))
+        );
+    }
+
+    #[test]
+    fn test_equivocation_evidence() {
+        use crate::equivocation::EquivocationEvidence;
+
+        let strings: Vec<String> = (0..13).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let honest = MerkleMountainRange::new(entries.clone());
+        // The same log with entry 5 rewritten, shown to some clients only
+        let mut forged_entries = entries.clone();
+        forged_entries[5] = &b"forged"[..];
+        let forked = MerkleMountainRange::new(forged_entries);
+
+        // Forks of the same size
+        let evidence =
+            EquivocationEvidence::from_commitments(&honest.commitment(), &forked.commitment())
+                .unwrap();
+        assert_eq!(evidence.verify(), Ok(()));
+        assert_eq!(
+            evidence.commitments(),
+            (&honest.commitment(), &forked.commitment())
+        );
+        let bytes = bcs::to_bytes(&evidence).unwrap();
+        assert_eq!(
+            bcs::from_bytes::<EquivocationEvidence>(&bytes).unwrap(),
+            evidence
+        );
+        assert_eq!(
+            EquivocationEvidence::from_commitments(&honest.commitment(), &honest.commitment()),
+            None
+        );
+        // Only commitments of the same size are compared
+        assert_eq!(
+            EquivocationEvidence::from_commitments(
+                &honest.commitment_at(6),
+                &forked.commitment_at(5)
+            ),
+            None
+        );
+        assert!(EquivocationEvidence::from_commitments(
+            &honest.commitment_at(6),
+            &forked.commitment_at(6)
+        )
+        .is_some());
+
+        // Forks of different sizes, shown by the entry they disagree on
+        let old = honest.commitment_at(8);
+        let old_proof = honest.prove_inclusion(5);
+        let new = forked.commitment();
+        let new_proof = forked.prove_inclusion(5);
+        let evidence =
+            EquivocationEvidence::from_inclusion_proofs(&old, &old_proof, &new, &new_proof)
+                .unwrap();
+        assert_eq!(evidence.verify(), Ok(()));
+
+        // Proofs of the same entry, of different indices, or that don't verify, show nothing
+        assert_eq!(
+            EquivocationEvidence::from_inclusion_proofs(
+                &old,
+                &old_proof,
+                &honest.commitment(),
+                &honest.prove_inclusion(5)
+            ),
+            None
+        );
+        let other_index = EquivocationEvidence::ConflictingEntries {
+            first: old.clone(),
+            first_proof: old_proof.clone(),
+            second: new.clone(),
+            second_proof: forked.prove_inclusion(4),
+        };
+        assert_eq!(other_index.verify(), Err(VerificationError::NoEquivocation));
+        let mut tampered = old_proof.clone();
+        tampered.entry = b"forged".to_vec();
+        let unproven = EquivocationEvidence::ConflictingEntries {
+            first: old.clone(),
+            first_proof: tampered,
+            second: new.clone(),
+            second_proof: new_proof.clone(),
+        };
+        assert_eq!(unproven.verify(), Err(VerificationError::RootMismatch));
+
+        // Logs hashed differently aren't forks of each other
+        let sha256 = MerkleMountainRange::new_with_hasher(
+            entries.clone(),
+            HashBackend::Scalar,
+            NodeHasher::new(HashAlgo::Sha256),
+        );
+        let different = EquivocationEvidence::SameSize {
+            first: honest.commitment(),
+            second: sha256.commitment(),
+        };
+        assert!(matches!(
+            different.verify(),
+            Err(VerificationError::HashAlgoMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_frontier() {
+        use crate::frontier::Frontier;
+
+        let strings: Vec<String> = (0..37).map(|i| format!("entry{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+
+        let mut frontier = Frontier::empty();
+        assert_eq!(frontier.root(), MmrCommitment::empty().digest());
+        for n in 1..=entries.len() {
+            frontier.append(entries[n - 1]);
+            let mmr = MerkleMountainRange::new(entries[..n].to_vec());
+            assert_eq!(frontier.commitment(), &mmr.commitment());
+            assert_eq!(frontier.root(), mmr.commitment().digest());
+            assert_eq!(frontier.peaks().count(), n.count_ones() as usize);
+        }
+        let heights: Vec<u64> = frontier.peaks().map(|(height, _)| height).collect();
+        assert_eq!(heights, vec![5, 2, 0]);
+
+        // Serialized exactly as the commitment
+        let bytes = bcs::to_bytes(&frontier).unwrap();
+        assert_eq!(bytes, bcs::to_bytes(frontier.commitment()).unwrap());
+        assert_eq!(bcs::from_bytes::<Frontier>(&bytes).unwrap(), frontier);
+
+        // Handed over between appenders midway
+        let mut first = MmrAppender::new();
+        for entry in &entries[..20] {
+            first.append(entry);
+        }
+        let mut second =
+            MmrAppender::from_frontier(&first.frontier(), NodeHasher::UNKEYED).unwrap();
+        for entry in &entries[20..] {
+            second.append(entry);
+        }
+        assert_eq!(second.frontier(), frontier);
+        assert_eq!(
+            Frontier::try_from(MerkleMountainRange::new(entries.clone()).commitment()).unwrap(),
+            frontier
+        );
+
+        // Keyed or other hashers must match the frontier's algorithm
+        let hasher = NodeHasher::new(HashAlgo::Sha256);
+        assert!(matches!(
+            frontier.append_with_hasher(&hasher, b"more"),
+            Err(VerificationError::HashAlgoMismatch { .. })
+        ));
+        assert!(MmrAppender::from_frontier(&frontier, hasher).is_err());
+        let mut sha256 = Frontier::empty().with_hash_algo(HashAlgo::Sha256);
+        for entry in &entries {
+            sha256.append_with_hasher(&hasher, entry).unwrap();
+        }
+        let mmr =
+            MerkleMountainRange::new_with_hasher(entries.clone(), HashBackend::Scalar, hasher);
+        assert_eq!(sha256.commitment(), &mmr.commitment());
+    }
+
+    #[test]
+    fn test_perfect_tree_builder() {
+        use crate::builder::PerfectTreeBuilder;
+
+        let strings: Vec<String> = (0..64).map(|i| format!("leaf{}", i)).collect();
+        let values: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        for height in 0..=6 {
+            let leaves = &values[..1 << height];
+            let mut builder = PerfectTreeBuilder::new();
+            builder.extend(leaves.iter().copied());
+            assert_eq!(builder.frontier().peaks().count(), 1);
+            let tree = PerfectMerkleTree::new(leaves.to_vec());
+            assert_eq!(builder.finish(), Ok(tree.root().hash));
+        }
+
+        // Streamed values and keyed hashers give the same roots
+        let hasher = NodeHasher::keyed([7; 32]);
+        let mut builder = PerfectTreeBuilder::new_with_hasher(hasher);
+        for value in &values[..16] {
+            builder.append_reader(*value).unwrap();
+        }
+        let tree =
+            PerfectMerkleTree::new_with_hasher(values[..16].to_vec(), HashBackend::Scalar, hasher);
+        assert_eq!(builder.finish(), Ok(tree.root().hash));
+
+        // Only a power of two leaves make a perfect tree
+        let mut builder = PerfectTreeBuilder::new();
+        assert_eq!(
+            builder.clone().finish(),
+            Err(VerificationError::InvalidTreeSize(0))
+        );
+        builder.extend(values[..12].iter().copied());
+        assert_eq!(builder.num_leaves(), 12);
+        assert_eq!(
+            builder.finish(),
+            Err(VerificationError::InvalidTreeSize(12))
+        );
+    }
+
+    #[test]
+    fn test_peak_bagging() {
+        use crate::hash_children;
+
+        assert_eq!(PeakBagging::default(), PeakBagging::CKB);
+        let baggings = [
+            PeakBagging::CKB,
+            PeakBagging::new(BaggingOrder::RightToLeft, true),
+            PeakBagging::new(BaggingOrder::LeftToRight, false),
+            PeakBagging::new(BaggingOrder::LeftToRight, true),
+        ];
+
+        // Seven entries make three peaks
+        let strings: Vec<String> = (0..45).map(|i| format!("block{}", i)).collect();
+        let entries: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let mmr = MerkleMountainRange::new(entries[..7].to_vec());
+        let peaks = mmr.standard_peaks();
+        let left_to_right = hash_children(&hash_children(&peaks[0], &peaks[1]), &peaks[2]);
+        assert_eq!(
+            mmr.standard_root_with_bagging(&baggings[2]),
+            Some(left_to_right)
+        );
+        let mut sized = 11u64.to_le_bytes().to_vec();
+        sized.extend_from_slice(left_to_right.as_bytes());
+        assert_eq!(
+            mmr.standard_root_with_bagging(&baggings[3]),
+            Some(hash_leaf(&sized))
+        );
+        assert_eq!(
+            mmr.standard_root_with_bagging(&PeakBagging::CKB),
+            mmr.standard_root()
+        );
+
+        let mut stored = StoredMmr::in_memory();
+        let mut appender = MmrAppender::new();
+        for n in 1..=entries.len() {
+            stored.append(entries[n - 1]).unwrap();
+            appender.append(entries[n - 1]);
+            let mmr = MerkleMountainRange::new(entries[..n].to_vec());
+            for bagging in &baggings {
+                let root = mmr.standard_root_with_bagging(bagging).unwrap();
+                assert_eq!(stored.standard_root_with_bagging(bagging), Ok(Some(root)));
+                assert_eq!(appender.standard_root_with_bagging(bagging), Some(root));
+                assert_eq!(mmr.commitment().standard_root_with_bagging(bagging), root);
+
+                for index in 0..n as u64 {
+                    let proof = mmr.prove_inclusion_standard_with_bagging(index, bagging);
+                    assert_eq!(
+                        stored.prove_inclusion_standard_with_bagging(index, bagging),
+                        Ok(proof.clone())
+                    );
+                    let pos = leaf_index_to_pos(index);
+                    let leaf = entries[index as usize];
+                    assert!(proof.verify_with_bagging(&root, pos, leaf, bagging));
+                    assert!(!proof.verify_with_bagging(&root, pos, b"forged", bagging));
+                    // Other baggings commit differently once there are several peaks or the
+                    // size is included
+                    for other in baggings.iter().filter(|other| *other != bagging) {
+                        if n.count_ones() > 1 || bagging.with_size != other.with_size {
+                            assert!(!proof.verify_with_bagging(&root, pos, leaf, other));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_kary_tree() {
+        use crate::kary::{tree_height, KaryInclusionProof, KaryMerkleTree};
+
+        let strings: Vec<String> = (0..256).map(|i| format!("leaf{}", i)).collect();
+        let values: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+
+        // Arity 2 is the binary tree
+        let binary = KaryMerkleTree::new(2, values[..64].to_vec());
+        assert_eq!(
+            binary.root(),
+            PerfectMerkleTree::new(values[..64].to_vec()).root().hash
+        );
+
+        // Wider nodes are domain separated from leaves like binary ones
+        let mut message = vec![ads_core::encoding::NODE_PREFIX];
+        for value in &values[..4] {
+            message.push(32);
+            message.extend(NodeHasher::UNKEYED.hash_leaf(value).0);
+        }
+        assert_eq!(
+            KaryMerkleTree::new(4, values[..4].to_vec()).root(),
+            NodeHasher::UNKEYED.hash_message(&message)
+        );
+
+        for (arity, num_leaves) in [(2, 32), (4, 1), (4, 64), (8, 64), (16, 256)] {
+            let tree = KaryMerkleTree::new(arity, values[..num_leaves].to_vec());
+            assert_eq!(tree.num_leaves(), num_leaves as u64);
+            assert_eq!(Some(tree.height()), tree_height(arity, num_leaves as u64));
+            for index in [0, num_leaves / 3, num_leaves - 1] {
+                let proof = tree.prove_inclusion(index as u64);
+                assert_eq!(
+                    proof.siblings.len(),
+                    tree.height() as usize * (arity as usize - 1)
+                );
+                let leaf = values[index];
+                assert_eq!(
+                    proof.verify(&tree.root(), arity, num_leaves as u64, leaf),
+                    Ok(tree.root())
+                );
+                assert_eq!(
+                    proof.verify(&tree.root(), arity, num_leaves as u64, b"forged"),
+                    Err(VerificationError::RootMismatch)
+                );
+                let bytes = bcs::to_bytes(&proof).unwrap();
+                assert_eq!(
+                    bcs::from_bytes::<KaryInclusionProof>(&bytes).unwrap(),
+                    proof
+                );
+            }
+        }
+
+        // Wide trees have short paths, of as many levels as the tree
+        let tree = KaryMerkleTree::new(16, values.clone());
+        assert_eq!(tree.height(), 2);
+        let proof = tree.prove_inclusion(200);
+        assert_eq!(proof.height(), 2);
+
+        // Proofs only verify for their arity and tree size
+        assert_eq!(
+            proof.verify(&tree.root(), 4, 256, values[200]),
+            Err(VerificationError::ArityMismatch {
+                expected: 4,
+                actual: 16
+            })
+        );
+        assert_eq!(
+            proof.verify(&tree.root(), 16, 128, values[200]),
+            Err(VerificationError::InvalidTreeSize(128))
+        );
+        assert_eq!(
+            proof.verify(&tree.root(), 16, 16, values[200]),
+            Err(VerificationError::LeafIndexOutOfBounds(200))
+        );
+        assert_eq!(
+            proof.verify(&tree.root(), 1, 1, values[200]),
+            Err(VerificationError::InvalidArity(1))
+        );
+        let mut short = proof.clone();
+        short.siblings.truncate(15);
+        short.leaf_index = 8;
+        assert_eq!(
+            short.verify(&tree.root(), 16, 256, values[8]),
+            Err(VerificationError::NotEnoughProofElements)
+        );
+
+        // Malformed proofs don't deserialize
+        let mut partial_level = proof.clone();
+        partial_level.siblings.pop();
+        let bytes = bcs::to_bytes(&partial_level).unwrap();
+        assert!(bcs::from_bytes::<KaryInclusionProof>(&bytes).is_err());
+        let unsupported = KaryInclusionProof::new(0, 0, vec![]);
+        let bytes = bcs::to_bytes(&unsupported).unwrap();
+        assert!(bcs::from_bytes::<KaryInclusionProof>(&bytes).is_err());
+
+        // Keyed trees verify with their hasher only
+        let hasher = NodeHasher::keyed([3; 32]);
+        let keyed = KaryMerkleTree::new_with_hasher(8, values[..64].to_vec(), hasher);
+        let proof = keyed.prove_inclusion(9);
+        assert!(proof
+            .verify_with_hasher(&hasher, &keyed.root(), 8, 64, values[9])
+            .is_ok());
+        assert!(proof.verify(&keyed.root(), 8, 64, values[9]).is_err());
+    }
+
+    #[test]
+    fn test_kary_multi_inclusion() {
+        use crate::kary::{KaryMerkleTree, KaryMultiInclusionProof};
+
+        let strings: Vec<String> = (0..256).map(|i| format!("leaf{}", i)).collect();
+        let values: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let queries: [&[u64]; 5] = [
+            &[0],
+            &[0, 1, 2, 3],
+            &[5, 6, 60],
+            &[0, 17, 100, 255],
+            &[3, 200],
+        ];
+        for arity in [2, 4, 16] {
+            let tree = KaryMerkleTree::new(arity, values.clone());
+            for &indices in &queries {
+                let proof = tree.prove_multi_inclusion(indices);
+                let leaves: Vec<Vec<u8>> = indices
+                    .iter()
+                    .map(|&i| values[i as usize].to_vec())
+                    .collect();
+                assert_eq!(
+                    proof.verify(&tree.root(), arity, 256, &leaves),
+                    Ok(tree.root())
+                );
+
+                // Never more than the separate paths, and fewer once they share nodes
+                let separate: usize = indices
+                    .iter()
+                    .map(|&i| tree.prove_inclusion(i).siblings.len())
+                    .sum();
+                assert!(proof.siblings.len() <= separate);
+                if indices.len() > 1 {
+                    assert!(proof.siblings.len() < separate);
+                }
+
+                let bytes = bcs::to_bytes(&proof).unwrap();
+                assert_eq!(
+                    bcs::from_bytes::<KaryMultiInclusionProof>(&bytes).unwrap(),
+                    proof
+                );
+
+                let mut forged = leaves.clone();
+                forged[0] = b"forged".to_vec();
+                assert_eq!(
+                    proof.verify(&tree.root(), arity, 256, &forged),
+                    Err(VerificationError::RootMismatch)
+                );
+            }
+        }
+
+        // A whole group of siblings needs none of them
+        let tree = KaryMerkleTree::new(16, values.clone());
+        let group: Vec<u64> = (16..32).collect();
+        assert_eq!(tree.prove_multi_inclusion(&group).siblings.len(), 15);
+
+        let proof = tree.prove_multi_inclusion(&[5, 6, 60]);
+        let leaves: Vec<Vec<u8>> = [5, 6, 60].iter().map(|&i| values[i].to_vec()).collect();
+        assert_eq!(
+            proof.verify(&tree.root(), 16, 256, &leaves[..2]),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 3,
+                actual: 2
+            })
+        );
+        let mut short = proof.clone();
+        short.siblings.pop();
+        assert_eq!(
+            short.verify(&tree.root(), 16, 256, &leaves),
+            Err(VerificationError::NotEnoughProofElements)
+        );
+        let mut long = proof.clone();
+        long.siblings.push(tree.root());
+        assert_eq!(
+            long.verify(&tree.root(), 16, 256, &leaves),
+            Err(VerificationError::UnusedProofElements)
+        );
+        let mut unsorted = proof.clone();
+        unsorted.leaf_indices.swap(0, 1);
+        let bytes = bcs::to_bytes(&unsorted).unwrap();
+        assert!(bcs::from_bytes::<KaryMultiInclusionProof>(&bytes).is_err());
+        assert_eq!(
+            proof.verify(&tree.root(), 4, 256, &leaves),
+            Err(VerificationError::ArityMismatch {
+                expected: 4,
+                actual: 16
+            })
+        );
+    }
+
+    #[test]
+    fn test_leaf_ordering() {
+        use crate::ordering::{LeafOrdering, NonMembershipProof};
+
+        let strings: Vec<String> = [13, 2, 40, 7, 2, 29, 11, 35, 18, 2, 24]
+            .iter()
+            .map(|i| format!("key{:02}", i))
+            .collect();
+        let values: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        let set = MerkleMountainRange::new_with_ordering(values.clone(), LeafOrdering::Sorted);
+        assert_eq!(set.num_entries(), 9);
+        assert!(set
+            .entries
+            .iter()
+            .zip(set.entries.iter().skip(1))
+            .all(|(a, b)| a < b));
+
+        // The ordering is part of the commitment, and of what gets signed
+        let commitment = set.commitment();
+        assert_eq!(commitment.ordering(), LeafOrdering::Sorted);
+        let unordered = commitment.clone().with_ordering(LeafOrdering::Append);
+        assert_ne!(commitment.digest(), unordered.digest());
+        assert_eq!(commitment.to_string().parse(), Ok(commitment.clone()));
+        let sha256 = commitment.clone().with_hash_algo(HashAlgo::Sha256);
+        assert_eq!(sha256.to_string().parse(), Ok(sha256));
+        let bytes = bcs::to_bytes(&commitment).unwrap();
+        assert_eq!(
+            bcs::from_bytes::<MmrCommitment>(&bytes).unwrap(),
+            commitment
+        );
+        assert_eq!(
+            MerkleMountainRange::new(values.clone())
+                .commitment()
+                .ordering(),
+            LeafOrdering::Append
+        );
+
+        // Before, between and after the entries
+        for absent in ["a", "key00", "key03", "key12", "key30", "key99", "z"] {
+            let proof = set.prove_non_membership(absent.as_bytes()).unwrap();
+            assert_eq!(
+                commitment.verify_non_membership(&proof, absent.as_bytes()),
+                Ok(())
+            );
+            let bytes = bcs::to_bytes(&proof).unwrap();
+            assert_eq!(
+                bcs::from_bytes::<NonMembershipProof>(&bytes).unwrap(),
+                proof
+            );
+        }
+        for present in &values {
+            assert!(set.prove_non_membership(present).is_none());
+        }
+
+        // A proof for one gap doesn't cover the entries around it, nor other gaps
+        let proof = set.prove_non_membership(b"key12").unwrap();
+        for value in ["key11", "key13", "key30"] {
+            assert_eq!(
+                commitment.verify_non_membership(&proof, value.as_bytes()),
+                Err(VerificationError::NotAbsent)
+            );
+        }
+        // Nor does a proof of entries that aren't adjacent
+        let mut skipping = proof.clone();
+        skipping.neighbours = Some(set.prove_multi_inclusion(&[2, 4]));
+        assert_eq!(
+            commitment.verify_non_membership(&skipping, b"key14"),
+            Err(VerificationError::NotAbsent)
+        );
+        assert_eq!(
+            unordered.verify_non_membership(&proof, b"key12"),
+            Err(VerificationError::LeafOrderingMismatch {
+                expected: LeafOrdering::Sorted,
+                actual: LeafOrdering::Append
+            })
+        );
+
+        // An empty set has no entries to prove
+        let empty = MerkleMountainRange::new_with_ordering(vec![], LeafOrdering::Sorted);
+        let proof = empty.prove_non_membership(b"anything").unwrap();
+        assert_eq!(
+            empty
+                .commitment()
+                .verify_non_membership(&proof, b"anything"),
+            Ok(())
+        );
+        assert_eq!(
+            commitment.verify_non_membership(&proof, b"anything"),
+            Err(VerificationError::EmptyProof)
+        );
+
+        // Appends must keep the entries sorted
+        let mut grown = set;
+        grown.add_entry(b"key50");
+        assert_eq!(grown.commitment().ordering(), LeafOrdering::Sorted);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            grown.add_entry(b"key45");
+        }));
+        assert!(result.is_err(), "Expected an unsorted append to fail");
+    }
+
+    #[test]
+    fn test_content_index_by_leaf_hash() {
+        use crate::standard::leaf_index_to_pos;
+
+        let hasher = NodeHasher::keyed([7; 32]);
+        let entries: Vec<&[u8]> = vec![b"a", b"b", b"a", b"c"];
+        let mut mmr = MerkleMountainRange::new_with_hasher(entries, HashBackend::Scalar, hasher);
+        let leaf_hash = hasher.hash_leaf(b"a");
+        assert_eq!(mmr.indices_of_leaf_hash(&leaf_hash), vec![0, 2]);
+
+        // Keyed by the MMR's own leaf hashes, not the unkeyed ones
+        mmr.enable_content_index();
+        mmr.add_entry(b"a");
+        assert_eq!(mmr.indices_of_leaf_hash(&leaf_hash), vec![0, 2, 4]);
+        assert!(mmr.indices_of_leaf_hash(&hash_leaf(b"a")).is_empty());
+        assert_eq!(mmr.indices_of(b"a"), vec![0, 2, 4]);
+        assert_eq!(
+            mmr.positions_of_leaf_hash(&leaf_hash),
+            vec![
+                leaf_index_to_pos(0),
+                leaf_index_to_pos(2),
+                leaf_index_to_pos(4)
+            ]
+        );
+
+        let proof = mmr.prove_inclusion_of_leaf_hash(&leaf_hash).unwrap();
+        assert_eq!(proof, mmr.prove_inclusion(0));
+        assert!(mmr
+            .prove_inclusion_of_leaf_hash(&hasher.hash_leaf(b"d"))
+            .is_none());
+
+        // Removing an index, as pruning would, keeps the others
+        let index = mmr.content_index.as_mut().unwrap();
+        index.remove(&leaf_hash, 2);
+        assert_eq!(index.get(&leaf_hash), &[0, 4]);
+        index.remove(&hasher.hash_leaf(b"c"), 3);
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_tombstones() {
+        use crate::tombstone::Tombstone;
+
+        let entries: Vec<&[u8]> = vec![b"alice", b"bob", b"carol"];
+        let mut mmr = MerkleMountainRange::new(entries);
+        assert!(!mmr.is_deleted(1));
+        assert!(mmr.prove_deletion(1).is_none());
+
+        let tombstone = mmr.delete(1);
+        assert_eq!(tombstone, 3);
+        mmr.add_entry(b"dave");
+        mmr.enable_content_index();
+        assert_eq!(mmr.tombstone_of(1), Some(3));
+        assert!(!mmr.is_deleted(0));
+        assert_eq!(
+            Tombstone::decode(&Tombstone::new(1).encode()),
+            Some(Tombstone::new(1))
+        );
+        assert!(Tombstone::decode(b"bob").is_none());
+
+        let commitment = mmr.commitment();
+        let proof = mmr.prove_deletion(1).unwrap();
+        assert_eq!(proof.entry.entry, b"bob");
+        assert_eq!(commitment.verify_deletion(&proof), Ok(1));
+
+        // The tombstone must reference the proven entry
+        let mut other = proof.clone();
+        other.entry = mmr.prove_inclusion(0);
+        assert_eq!(
+            commitment.verify_deletion(&other),
+            Err(VerificationError::NotDeleted)
+        );
+        other.tombstone = mmr.prove_inclusion(4);
+        assert_eq!(
+            commitment.verify_deletion(&other),
+            Err(VerificationError::MalformedEntry(4))
+        );
+
+        // Tombstones can't be deleted
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            mmr.delete(tombstone);
+        }));
+        assert!(result.is_err(), "Expected deleting a tombstone to fail");
+    }
+
+    #[test]
+    fn test_compaction() {
+        use crate::tombstone::Tombstone;
+
+        let entries: Vec<&[u8]> = vec![b"a", b"b", b"c", b"d", b"e"];
+        let mut mmr = MerkleMountainRange::new(entries);
+        mmr.delete(1);
+        mmr.add_entry(b"f");
+        mmr.delete(3);
+        let old = mmr.commitment();
+
+        let (compacted, proof) = mmr.compact();
+        let kept: Vec<&[u8]> = compacted.entries.iter().collect();
+        assert_eq!(kept, vec![b"a" as &[u8], b"c", b"e", b"f"]);
+        assert_eq!(proof.deleted, vec![1, 3]);
+        assert_eq!(proof.tombstones, vec![5, 7]);
+        // Only the entries kept after the first dropped one are listed
+        assert_eq!(proof.prefix.num_entries(), 1);
+        let leaf_hashes = mmr.leaf_hashes();
+        assert_eq!(
+            proof.kept_leaf_hashes,
+            vec![leaf_hashes[2], leaf_hashes[4], leaf_hashes[6]]
+        );
+        let new = compacted.commitment();
+        proof.verify(&old, &new).unwrap();
+
+        // Nothing deleted, nothing dropped
+        let (same, proof) = compacted.compact();
+        assert_eq!(same.commitment(), new);
+        proof.verify(&new, &new).unwrap();
+
+        // Dropping an entry that wasn't deleted, or keeping a deleted one, doesn't verify
+        let (_, valid) = mmr.compact();
+        let mut proof = valid.clone();
+        proof.tombstones[0] = 1;
+        assert_eq!(proof.verify(&old, &new), Err(VerificationError::NotDeleted));
+        let mut proof = valid.clone();
+        proof.deleted[1] = 2;
+        proof.deleted_leaf_hashes[1] = leaf_hashes[2];
+        assert_eq!(
+            proof.verify(&old, &new),
+            Err(VerificationError::RootMismatch)
+        );
+        let mut proof = valid.clone();
+        proof.deleted.pop();
+        proof.tombstones.pop();
+        proof.deleted_leaf_hashes.pop();
+        proof.kept_leaf_hashes = [2, 3, 4, 6, 7].map(|i| leaf_hashes[i]).to_vec();
+        assert_eq!(
+            proof.verify(&old, &new),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 4,
+                actual: 6
+            })
+        );
+        let mut proof = valid.clone();
+        proof.kept_leaf_hashes.swap(0, 2);
+        assert_eq!(
+            proof.verify(&old, &new),
+            Err(VerificationError::RootMismatch)
+        );
+        let mut proof = valid;
+        proof.prefix = mmr.commitment_at(2);
+        proof.consistency = mmr.prove_consistency(2);
+        assert_eq!(
+            proof.verify(&old, &new),
+            Err(VerificationError::NonCanonicalProof)
+        );
+
+        // Of two tombstones of an entry, the first is dropped with it
+        let mut mmr = MerkleMountainRange::new(vec![b"a" as &[u8], b"b"]);
+        let tombstone = Tombstone::new(0).encode();
+        mmr.add_entry(&tombstone);
+        mmr.add_entry(&tombstone);
+        let old = mmr.commitment();
+        let (compacted, mut proof) = mmr.compact();
+        assert_eq!(proof.tombstones, vec![2]);
+        let new = compacted.commitment();
+        proof.verify(&old, &new).unwrap();
+        let leaf_hashes = mmr.leaf_hashes();
+        proof.tombstones = vec![3];
+        proof.kept_leaf_hashes = vec![leaf_hashes[1], leaf_hashes[2]];
+        assert_eq!(
+            proof.verify(&old, &new),
+            Err(VerificationError::NonCanonicalProof)
+        );
+    }
+
+    #[test]
+    fn test_lockstep_log() {
+        use crate::lockstep::LockstepLog;
+
+        let mut log = LockstepLog::new();
+        assert!(log.prove_latest().is_none());
+        for i in 0..11 {
+            log.append(format!("entry{}", i).as_bytes());
+            // Every append binds the two commitments
+            let commitment = log.commitment();
+            let proof = log.prove_latest().unwrap();
+            assert_eq!(proof.verify(&commitment), Ok(i));
+            assert_eq!(proof.size_hint(), bcs::to_bytes(&proof).unwrap().len());
+        }
+        let commitment = log.commitment();
+        assert_eq!(commitment.skip_list.height, 11);
+        assert_eq!(log.prove_cross(3).verify(&commitment), Ok(3));
+
+        // Proofs of different entries don't bind
+        let mut proof = log.prove_cross(3);
+        proof.skip_list = log.prove_cross(4).skip_list;
+        assert_eq!(
+            proof.verify(&commitment),
+            Err(VerificationError::EntryMismatch(3))
+        );
+
+        // Nor do commitments to logs of different sizes
+        let mut stale = commitment.clone();
+        stale.skip_list = log.skip_list().nodes[9].head_commitment();
+        assert_eq!(
+            log.prove_cross(3).verify(&stale),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 11,
+                actual: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_priority_queue() {
+        use crate::queue::{PriorityQueue, QueueOperation};
+
+        let mut queue = PriorityQueue::from_elements(vec![b"5", b"3", b"8"]);
+        assert_eq!(queue.peek(), Some(b"3" as &[u8]));
+        let commitment = queue.commitment();
+        commitment.verify_min(&queue.prove_min().unwrap()).unwrap();
+
+        // Pushes anywhere, each proven from the previous commitment
+        let mut old = commitment;
+        for element in [b"4", b"1", b"9"] {
+            let proof = queue.push(element).unwrap();
+            let new = queue.commitment();
+            proof.verify(&old, &new).unwrap();
+            old = new;
+        }
+        assert!(queue.push(b"4").is_none());
+        assert_eq!(queue.len(), 6);
+
+        let (min, proof) = queue.pop().unwrap();
+        assert_eq!(min, b"1");
+        let new = queue.commitment();
+        proof.verify(&old, &new).unwrap();
+        assert_eq!(queue.peek(), Some(b"3" as &[u8]));
+
+        // Popping anything but the minimum, or pushing out of order, doesn't verify
+        let mut forged = proof.clone();
+        forged.operation = QueueOperation::Pop(b"3".to_vec());
+        assert_eq!(
+            forged.verify(&old, &new),
+            Err(VerificationError::NotMinimum)
+        );
+        let mut other = PriorityQueue::from_elements(vec![b"3", b"5"]);
+        let before = other.commitment();
+        let mut forged = other.push(b"4").unwrap();
+        forged.operation = QueueOperation::Push {
+            element: b"6".to_vec(),
+            position: 1,
+        };
+        assert_eq!(
+            forged.verify(&before, &other.commitment()),
+            Err(VerificationError::NotAbsent)
+        );
+        assert_eq!(
+            new.verify_min(&queue.mmr().prove_inclusion(1)),
+            Err(VerificationError::NotMinimum)
+        );
+        while queue.pop().is_some() {}
+        assert!(queue.prove_min().is_none());
+    }
+
+    #[test]
+    fn test_authenticated_stack() {
+        use crate::stack::AuthenticatedStack;
+
+        let mut stack = AuthenticatedStack::new();
+        assert!(stack.pop().is_none());
+        let mut commitments = vec![stack.commitment()];
+        for i in 0..13 {
+            let value = format!("value{}", i);
+            commitments.push(stack.push(value.as_bytes()));
+        }
+        // The same commitments as an MMR of the values
+        let values: Vec<String> = (0..13).map(|i| format!("value{}", i)).collect();
+        let mmr = MerkleMountainRange::new(values.iter().map(|v| v.as_bytes()).collect());
+        assert_eq!(stack.commitment(), mmr.commitment());
+        assert_eq!(stack.prove_top().unwrap().top, mmr.prove_inclusion(12));
+
+        // Every pop yields the commitment before the matching push
+        for i in (0..13).rev() {
+            let commitment = stack.commitment();
+            let (value, proof) = stack.pop().unwrap();
+            assert_eq!(value, format!("value{}", i).into_bytes());
+            assert_eq!(proof.value(), value.as_slice());
+            let popped = commitment.verify_pop(&proof).unwrap();
+            assert_eq!(popped, commitments[i]);
+            assert_eq!(popped, stack.commitment());
+        }
+        assert!(stack.is_empty());
+
+        // Only the top can be popped
+        let proof = crate::stack::PopProof::new(mmr.prove_inclusion(11));
+        assert_eq!(
+            mmr.commitment().verify_pop(&proof),
+            Err(VerificationError::NotTop)
+        );
+    }
+
+    #[test]
+    fn test_grow_only_set_merge() {
+        use crate::gset::{GrowOnlySet, MergeSource};
+
+        let mut left = GrowOnlySet::from_elements(vec![b"d", b"a", b"c"]);
+        let right = GrowOnlySet::from_elements(vec![b"b", b"c", b"e", b"e"]);
+        assert!(left.insert(b"f"));
+        assert!(!left.insert(b"a"));
+        assert_eq!(right.len(), 3);
+
+        let (merged, proof) = left.merge(&right);
+        let elements: Vec<&[u8]> = merged.elements().collect();
+        assert_eq!(elements, vec![b"a" as &[u8], b"b", b"c", b"d", b"e", b"f"]);
+        assert_eq!(proof.sources[2], MergeSource::Both);
+        proof
+            .verify(
+                &left.commitment(),
+                &right.commitment(),
+                &merged.commitment(),
+            )
+            .unwrap();
+
+        // Merging converges whichever replica merges
+        let (other, _) = right.merge(&left);
+        assert_eq!(other.commitment(), merged.commitment());
+        let proof_of_b = merged.prove_membership(b"b").unwrap();
+        merged.commitment().verify_inclusion(&proof_of_b).unwrap();
+        assert!(merged.prove_non_membership(b"b").is_none());
+
+        // The union must hold exactly the elements of both
+        let smaller = GrowOnlySet::from_elements(vec![b"a", b"b", b"c", b"d", b"e"]);
+        assert!(proof
+            .verify(
+                &left.commitment(),
+                &right.commitment(),
+                &smaller.commitment()
+            )
+            .is_err());
+        let mut forged = proof.clone();
+        forged.sources[0] = MergeSource::Right;
+        assert_eq!(
+            forged.verify(
+                &left.commitment(),
+                &right.commitment(),
+                &merged.commitment()
+            ),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 4,
+                actual: 3
+            })
+        );
+        forged = proof.clone();
+        forged.elements.swap(0, 1);
+        assert_eq!(
+            forged.verify(
+                &left.commitment(),
+                &right.commitment(),
+                &merged.commitment()
+            ),
+            Err(VerificationError::NonCanonicalProof)
+        );
+    }
+
+    #[test]
+    fn test_super_root() {
+        use crate::forest::SuperRoot;
+
+        let shards: Vec<MerkleMountainRange> = (0..3)
+            .map(|shard| {
+                let entries: Vec<String> =
+                    (0..5 + shard).map(|i| format!("{}-{}", shard, i)).collect();
+                MerkleMountainRange::new(entries.iter().map(|e| e.as_bytes()).collect())
+            })
+            .collect();
+        let names = ["shard-b", "shard-a", "shard-c"];
+        let super_root = SuperRoot::new(names.into_iter().zip(&shards));
+        let root = super_root.root().unwrap();
+        assert_eq!(
+            super_root.log_names().collect::<Vec<_>>(),
+            vec!["shard-a", "shard-b", "shard-c"]
+        );
+
+        // The same super-root as a forest of the same logs
+        let mut forest = Forest::in_memory();
+        for (name, shard) in names.iter().zip(&shards) {
+            forest.create_log(name).unwrap();
+            for entry in shard.entries.iter() {
+                forest.append(name, entry).unwrap();
+            }
+        }
+        assert_eq!(forest.super_root().unwrap(), Some(root));
+        assert_eq!(
+            forest.prove_entry("shard-c", 6).unwrap(),
+            super_root.prove_entry("shard-c", 6).unwrap()
+        );
+
+        let proof = super_root.prove_entry("shard-a", 3).unwrap();
+        assert_eq!(proof.entry, b"1-3");
+        proof.verify(&root).unwrap();
+        assert_eq!(proof.size_hint(), bcs::to_bytes(&proof).unwrap().len());
+        assert!(super_root.prove_entry("shard-a", 6).is_none());
+        assert!(super_root.prove_entry("shard-d", 0).is_none());
+
+        // The entry proof must be about the log as recorded
+        let mut forged = proof.clone();
+        forged.proof = shards[0].prove_inclusion_standard(3);
+        assert_eq!(
+            forged.verify(&root),
+            Err(VerificationError::WrongNumberOfEntries {
+                expected: 6,
+                actual: 5
+            })
+        );
+        forged = proof.clone();
+        forged.entry = b"0-3".to_vec();
+        assert_eq!(forged.verify(&root), Err(VerificationError::RootMismatch));
+    }
+
+    #[test]
+    fn test_epoched_log() {
+        use crate::epoch::EpochedLog;
+
+        let mut log = EpochedLog::new(Some(5));
+        assert_eq!(log.seal(), None);
+        for i in 0..23 {
+            log.append(format!("entry{}", i).as_bytes());
+        }
+        assert_eq!(log.current_epoch(), 5);
+        assert_eq!(log.current().num_entries(), 3);
+        assert_eq!(log.seal(), Some(5));
+        assert_eq!(log.current().num_entries(), 0);
+        let head = log.head().unwrap();
+        assert_eq!(head.height, 5);
+
+        // An entry of an old epoch, against the current head
+        let proof = log.prove(2, 3).unwrap();
+        assert_eq!(proof.entry, b"entry8");
+        assert_eq!(proof.checkpoint.height, 2);
+        proof.verify(&head).unwrap();
+        assert!(log.prove(5, 3).is_none());
+        assert!(log.prove(6, 0).is_none());
+
+        // Retiring an epoch keeps the others provable under the same head
+        assert!(log.retire(2));
+        assert!(!log.retire(2));
+        assert!(log.prove(2, 3).is_none());
+        log.append(b"entry23");
+        log.prove(1, 0).unwrap().verify(&head).unwrap();
+        assert_eq!(log.head(), Some(head));
+    }
+
+    #[test]
+    fn test_proof_cache() {
+        use crate::cache::{CachedMmr, ProofQuery};
+
+        let entries: Vec<Vec<u8>> = (0..11)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries[..6].iter().map(|e| e.as_slice()).collect());
+        let old = mmr.commitment();
+        let mut cached = CachedMmr::new(mmr);
+        for entry in &entries[6..] {
+            cached.append(entry);
+        }
+        check_provider(&cached, &old, 4);
+        assert_eq!(cached.cache().len(), 3);
+
+        // Identical requests are served from the cache
+        let proof = now(cached.prove_suffix(3)).unwrap();
+        assert_eq!(proof, cached.mmr().prove_most_recent_n_elements(3));
+        now(ProofProvider::prove_inclusion(&cached, 9)).unwrap();
+        assert_eq!(cached.cache().hits(), 1);
+        assert_eq!(cached.cache().misses(), 4);
+        assert_eq!(
+            now(ProofProvider::prove_inclusion(&cached, 11)),
+            Err(ProviderError::IndexOutOfBounds(11))
+        );
+
+        // 11 entries are trees of 8, 2 and 1. Appending merges the trees of 2 and 1, so only the
+        // proof in the tree of 8 is kept.
+        cached.append(b"entry11");
+        let mut cache = cached.cache();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&ProofQuery::Inclusion(4)).is_some());
+        drop(cache);
+        check_provider(&cached, &old, 4);
+        let commitment = cached.mmr().commitment();
+        for index in [4, 9] {
+            let proof = now(ProofProvider::prove_inclusion(&cached, index)).unwrap();
+            assert_eq!(proof, cached.mmr().prove_inclusion(index));
+            commitment.verify_inclusion(&proof).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_query_planner() {
+        use crate::planner::{Query, Strategy};
+
+        // Small entries in the tree of 16, large ones in the trees of 4 and 2
+        let entries: Vec<Vec<u8>> = (0..22)
+            .map(|i| vec![i as u8; if i < 16 { 4 } else { 40 }])
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let commitment = mmr.commitment();
+        let check = |query: Query| {
+            let (plan, proof) = mmr.prove_query(&query).unwrap();
+            assert_eq!(plan.cost, proof.size_hint());
+            let indices = proof.verify(&commitment).unwrap();
+            for (index, entry) in indices.iter().zip(proof.entries()) {
+                assert_eq!(entry, entries[*index as usize].as_slice());
+            }
+            for index in query.indices(mmr.num_entries()) {
+                assert!(indices.contains(&index));
+            }
+            plan.strategy
+        };
+
+        // A lone entry needs no more than its own path
+        assert_eq!(check(Query::Indices(vec![17])), Strategy::Single);
+        // Neighbours share their siblings
+        assert_eq!(check(Query::Indices(vec![17, 16, 18, 17])), Strategy::Multi);
+        // The most recent 6 are the two smaller trees
+        assert_eq!(check(Query::Suffix(6)), Strategy::Suffix);
+        // Every other entry of a tree: sending the tree whole saves a sibling per entry
+        let evens: Vec<u64> = (0..16).step_by(2).collect();
+        assert_eq!(check(Query::Indices(evens.clone())), Strategy::Subtrees(1));
+        let (plan, proof) = mmr.prove_query(&Query::Indices(evens.clone())).unwrap();
+        assert_eq!(proof.entries().len(), 16);
+        assert!(plan.cost < mmr.prove_multi_inclusion(&evens).size_hint());
+        // Small entries are cheaper than the siblings they replace
+        assert_eq!(check(Query::Indices(vec![5])), Strategy::Subtrees(2));
+
+        assert!(mmr.prove_query(&Query::Indices(vec![])).is_none());
+        assert!(mmr.prove_query(&Query::Range(20..23)).is_none());
+        assert!(mmr.prove_query(&Query::Suffix(23)).is_none());
+    }
+
+    #[test]
+    fn test_spot_check() {
+        use crate::spot::{Sampler, SpotCheck};
+
+        let entries: Vec<Vec<u8>> = (0..3000)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let commitment = mmr.commitment();
+        let mut proofs: Vec<_> = (0..3000).map(|i| mmr.prove_inclusion(i)).collect();
+
+        // Samples are replayable from the seed
+        let mut sampler = Sampler::new(b"seed");
+        let first: Vec<u64> = (0..10).map(|_| sampler.next_index(3000)).collect();
+        let mut replayed = Sampler::new(b"seed");
+        assert!(first.iter().all(|&i| i == replayed.next_index(3000)));
+        assert_ne!(Sampler::new(b"other").next_index(3000), first[0]);
+
+        let check = SpotCheck::for_confidence(b"seed", 0.01, 0.999);
+        assert_eq!(check.samples(), 688);
+        let report = check.check(&proofs, &commitment).unwrap();
+        assert_eq!(report.total, 3000);
+        assert!(report.checked <= 688 && !report.is_complete());
+        assert!(report.confidence(0.01) >= 0.999);
+        assert!(report.confidence(0.001) < 0.5);
+
+        // A batch with a tenth of its proofs invalid is caught
+        for proof in proofs.iter_mut().step_by(10) {
+            proof.entry = b"forged".to_vec();
+        }
+        let error = check.check(&proofs, &commitment).unwrap_err();
+        assert_eq!(error.position % 10, 0);
+        assert_eq!(error.error, VerificationError::RootMismatch);
+
+        // Small batches are verified whole
+        let report = check.check(&proofs[1..10], &commitment).unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.confidence(0.001), 1.0);
+    }
+
+    #[test]
+    fn test_challenges() {
+        use crate::challenge::ChallengeResponse;
+
+        let entries: Vec<Vec<u8>> = (0..100)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mut mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let commitment = mmr.commitment();
+
+        // Deterministic for a commitment and nonce, and different across nonces
+        let indices = commitment.challenge_indices(b"nonce", 10);
+        assert_eq!(indices.len(), 10);
+        assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(indices, commitment.challenge_indices(b"nonce", 10));
+        assert_ne!(indices, commitment.challenge_indices(b"other", 10));
+        assert_eq!(commitment.challenge_indices(b"nonce", 200).len(), 100);
+
+        let response = mmr.respond_to_challenge(b"nonce", 10).unwrap();
+        response.verify(&commitment, b"nonce", 10).unwrap();
+        for (index, entry) in indices.iter().zip(response.entries()) {
+            assert_eq!(entry, &entries[*index as usize]);
+        }
+        // Answering another challenge, or proving other entries, fails
+        assert_eq!(
+            response.verify(&commitment, b"other", 10),
+            Err(VerificationError::NotChallenged)
+        );
+        let dodging = ChallengeResponse::new(mmr.prove_multi_inclusion(&indices[1..]));
+        assert_eq!(
+            dodging.verify(&commitment, b"nonce", 10),
+            Err(VerificationError::NotChallenged)
+        );
+
+        // The same nonce challenges other entries once the log grows
+        mmr.add_entry(b"entry100");
+        let grown = mmr.commitment();
+        assert_ne!(grown.challenge_indices(b"nonce", 10), indices);
+        assert!(response.verify(&grown, b"nonce", 10).is_err());
+        assert!(MerkleMountainRange::new(vec![])
+            .respond_to_challenge(b"nonce", 10)
+            .is_none());
+    }
+
+    #[test]
+    fn test_random_selection() {
+        use crate::selection::SelectionProof;
+
+        let entries: Vec<Vec<u8>> = (0..50)
+            .map(|i| format!("ticket{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let commitment = mmr.commitment();
+
+        let proof = mmr.select(b"beacon round 7").unwrap();
+        let index = proof.verify(&commitment, b"beacon round 7").unwrap();
+        assert_eq!(commitment.select_index(b"beacon round 7"), Some(index));
+        assert_eq!(proof.entry(), entries[index as usize].as_slice());
+        assert_eq!(proof.size_hint(), bcs::to_bytes(&proof).unwrap().len());
+
+        // Seeds spread the selection over the list
+        let selected: std::collections::BTreeSet<u64> = (0..100u32)
+            .filter_map(|seed| commitment.select_index(&seed.to_le_bytes()))
+            .collect();
+        assert!(selected.len() > 30);
+
+        // Another entry, or another seed, doesn't pass
+        let other = (index + 1) % 50;
+        let forged = SelectionProof::new(mmr.prove_inclusion(other));
+        assert_eq!(
+            forged.verify(&commitment, b"beacon round 7"),
+            Err(VerificationError::NotSelected)
+        );
+        let seed = (0..100u32)
+            .map(|seed| seed.to_le_bytes())
+            .find(|seed| commitment.select_index(seed) == Some(other))
+            .unwrap();
+        assert_eq!(forged.verify(&commitment, &seed), Ok(other));
+        assert_eq!(
+            proof.verify(&commitment, &seed),
+            Err(VerificationError::NotSelected)
+        );
+        assert!(MerkleMountainRange::new(vec![]).select(b"seed").is_none());
+    }
+
+    #[test]
+    fn test_parallelism() {
+        use crate::parallel::Parallelism;
+
+        let entries: Vec<Vec<u8>> = (0..64)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+        let mut proofs: Vec<_> = (0..64).map(|i| mmr.prove_inclusion(i)).collect();
+
+        let parallelism = Parallelism::threads(2).unwrap().with_min_len(16);
+        assert_eq!(parallelism.install(rayon::current_num_threads), 2);
+        assert_eq!(
+            mmr.verify_batch_inclusion_with_parallelism(&proofs, &parallelism),
+            Ok(())
+        );
+
+        // Failures are reported the same way whatever the pool
+        proofs[3].entry = b"tampered".to_vec();
+        proofs[40].entry = b"tampered".to_vec();
+        let expected = Err(BatchVerificationError {
+            failures: vec![
+                (3, VerificationError::RootMismatch),
+                (40, VerificationError::RootMismatch),
+            ],
+        });
+        assert_eq!(
+            mmr.verify_batch_inclusion_with_parallelism(&proofs, &parallelism),
+            expected
+        );
+        let single = Parallelism::threads(1).unwrap().with_min_len(0);
+        assert_eq!(single.min_len(), 1);
+        assert_eq!(
+            mmr.verify_batch_inclusion_with_parallelism(&proofs, &single),
+            expected
+        );
+        assert_eq!(mmr.verify_batch_inclusion(&proofs), expected);
+
+        let suffixes: Vec<_> = (1..=8)
+            .map(|n| mmr.prove_most_recent_n_elements(n))
+            .collect();
+        assert_eq!(
+            mmr.verify_batch_most_recent_n_elements_with_parallelism(&suffixes, &parallelism),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_build_from_iterator() {
+        let entries = || (0..77u32).map(|i| format!("entry{}", i).into_bytes());
+        let owned: Vec<Vec<u8>> = entries().collect();
+        let expected = MerkleMountainRange::new(owned.iter().map(|e| e.as_slice()).collect());
+
+        // Owned entries, produced on the fly
+        let mmr = MerkleMountainRange::from_iter(entries());
+        assert_eq!(mmr.commitment(), expected.commitment());
+        assert_eq!(mmr.prove_inclusion(40), expected.prove_inclusion(40));
+        let mut extended: MerkleMountainRange = owned[..30].iter().collect();
+        extended.extend(&owned[30..]);
+        assert_eq!(extended.commitment(), expected.commitment());
+
+        // Across chunks, the last one partial
+        let many: Vec<Vec<u8>> = (0..2 * 1024 + 77u32)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mmr: MerkleMountainRange = many.iter().collect();
+        let expected_many = MerkleMountainRange::new(many.iter().map(|e| e.as_slice()).collect());
+        assert_eq!(mmr.commitment(), expected_many.commitment());
+        assert_eq!(mmr.trees.len(), expected_many.trees.len());
+        assert_eq!(
+            mmr.prove_inclusion(1500),
+            expected_many.prove_inclusion(1500)
+        );
+        assert_eq!(
+            mmr.prove_inclusion(2100),
+            expected_many.prove_inclusion(2100)
+        );
+
+        // Only the peaks
+        let appender: MmrAppender = entries().collect();
+        assert_eq!(appender.commitment(), expected.commitment());
+
+        // Into stores
+        let mut stored = StoredMmr::in_memory();
+        stored.append_all(entries()).unwrap();
+        assert_eq!(stored.num_entries(), 77);
+        assert_eq!(stored.digests().unwrap(), expected.digests());
+    }
+
+    #[test]
+    fn test_persistent_versions() {
+        use crate::persistent::PersistentMmr;
+
+        let entries: Vec<Vec<u8>> = (0..11)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mut mmr: PersistentMmr = entries[..8].iter().collect();
+        let v8 = mmr.clone();
+        mmr.extend(&entries[8..]);
+
+        // Each version proves against its own commitment, as the arena-based MMR would
+        for (version, num_entries) in [(&v8, 8), (&mmr, 11)] {
+            let expected = MerkleMountainRange::from_iter(&entries[..num_entries]);
+            let commitment = version.commitment();
+            assert_eq!(commitment, expected.commitment());
+            for index in 0..num_entries as u64 {
+                let proof = version.prove_inclusion(index).unwrap();
+                assert_eq!(proof, expected.prove_inclusion(index));
+                commitment.verify_inclusion(&proof).unwrap();
+            }
+            assert!(version.prove_inclusion(num_entries as u64).is_none());
+        }
+        assert_eq!(v8.entry(9), None);
+        assert_eq!(mmr.entry(9), Some(entries[9].as_slice()));
+
+        // The tree of 8 is shared, only the trees of 2 and 1 are new
+        assert_eq!(mmr.shared_nodes(&v8), 15);
+        assert_eq!(v8.shared_nodes(&mmr), 15);
+        assert_eq!(mmr.shared_nodes(&PersistentMmr::new()), 0);
+
+        // Readers keep their version while the writer appends
+        let reader = std::thread::spawn(move || v8.prove_inclusion(3).unwrap());
+        mmr.append(b"entry11");
+        let proof = reader.join().unwrap();
+        assert_eq!(proof.entry, entries[3]);
+        // The tree of 8 outlives 12 entries too
+        mmr.commitment().verify_inclusion(&proof).unwrap();
+    }
+
+    #[test]
+    fn test_versioned_mmr() {
+        use crate::persistent::VersionedMmr;
+
+        let mut mmr = VersionedMmr::new();
+        let mut commitments = vec![];
+        for i in 0..20 {
+            mmr.append(format!("entry{}", i).as_bytes());
+            if i % 5 == 4 {
+                let version = mmr.commit();
+                assert_eq!(version, i / 5);
+                commitments.push(mmr.current().commitment());
+            }
+        }
+        mmr.append(b"uncommitted");
+        assert_eq!(mmr.num_versions(), 4);
+        assert_eq!(mmr.current().num_entries(), 21);
+
+        // What the log held at each version, against the commitment of that version
+        for (version, commitment) in commitments.iter().enumerate() {
+            let version = version as u64;
+            assert_eq!(
+                mmr.commitment_at_version(version).as_ref(),
+                Some(commitment)
+            );
+            assert_eq!(
+                mmr.version(version).unwrap().num_entries(),
+                5 * (version + 1)
+            );
+            let proof = mmr.prove_inclusion_at_version(3, version).unwrap();
+            assert_eq!(proof.entry, b"entry3");
+            commitment.verify_inclusion(&proof).unwrap();
+            assert!(mmr
+                .prove_inclusion_at_version(5 * (version + 1), version)
+                .is_none());
+        }
+        // 5 entries are trees of 4 and 1, and entry 4 is in a larger tree at every later version
+        let old = mmr.prove_inclusion_at_version(4, 0).unwrap();
+        assert!(commitments[3].verify_inclusion(&old).is_err());
+        assert!(mmr.prove_inclusion_at_version(0, 4).is_none());
+        assert!(mmr.commitment_at_version(4).is_none());
+    }
+
+    #[test]
+    fn test_copy_on_write_updates() {
+        use crate::persistent::{PersistentMmr, VersionedMmr};
+
+        let entries: Vec<Vec<u8>> = (0..11)
+            .map(|i| format!("entry{}", i).into_bytes())
+            .collect();
+        let mut mmr: PersistentMmr = entries.iter().collect();
+        let before = mmr.clone();
+        let old_proof = before.prove_inclusion(9).unwrap();
+
+        assert_eq!(mmr.update(9, b"updated"), Some(entries[9].clone()));
+        assert_eq!(mmr.update(11, b"updated"), None);
+
+        // Both versions keep their root, and prove their own entry with the same siblings
+        let old = before.commitment();
+        let new = mmr.commitment();
+        assert_eq!(old, MerkleMountainRange::from_iter(&entries).commitment());
+        assert_ne!(old, new);
+        assert_eq!(old.peaks[0], new.peaks[0]);
+        let new_proof = mmr.prove_inclusion(9).unwrap();
+        assert_eq!(new_proof.entry, b"updated");
+        assert_eq!(new_proof.proof.siblings, old_proof.proof.siblings);
+        new.verify_inclusion(&new_proof).unwrap();
+        old.verify_inclusion(&old_proof).unwrap();
+        assert!(new.verify_inclusion(&old_proof).is_err());
+        assert_eq!(before.entry(9), Some(entries[9].as_slice()));
+
+        // Of the 19 nodes of trees of 8, 2 and 1, only the leaf and root of the tree of 2 are new
+        assert_eq!(mmr.shared_nodes(&before), 17);
+        let mut expected = entries.clone();
+        expected[9] = b"updated".to_vec();
+        assert_eq!(new, MerkleMountainRange::from_iter(&expected).commitment());
+
+        // Committed versions keep their root across updates
+        let mut versioned = VersionedMmr::new();
+        entries.iter().for_each(|entry| versioned.append(entry));
+        versioned.commit();
+        versioned.update(9, b"updated");
+        versioned.commit();
+        assert_eq!(versioned.commitment_at_version(0), Some(old));
+        assert_eq!(versioned.commitment_at_version(1), Some(new));
+    }
+
+    #[test]
+    fn test_version_pruning() {
+        use std::collections::{BTreeMap, BTreeSet};
+
+        use crate::persistent::{StoredNode, VersionedMmr};
+
+        let mut mmr = VersionedMmr::new_with_store(BTreeMap::new(), NodeHasher::UNKEYED);
+        for i in 0..8 {
+            mmr.append(format!("entry{}", i).as_bytes());
+        }
+        mmr.commit();
+        assert_eq!(mmr.store().len(), 15);
+        mmr.update(3, b"updated3");
+        mmr.commit();
+        mmr.update(5, b"updated5");
+        mmr.commit();
+        // Each update writes the leaf and its 3 ancestors
+        assert_eq!(mmr.store().len(), 23);
+        let commitment = mmr.commitment_at_version(2).unwrap();
+        let root = mmr.store().values().last().unwrap();
+        assert_eq!(root.hash(), &commitment.peaks[0]);
+
+        // Version 2 shares the left half of version 1, and every node off the paths of entries 3
+        // and 5 with version 0. The other 8 nodes are deleted: both replaced leaves, the 5 nodes
+        // above them in version 0 and the root of version 1.
+        let retained: BTreeSet<u64> = [2].into();
+        let leaf = StoredNode::Leaf {
+            hash: hash_leaf(b"entry3"),
+            entry: b"entry3".to_vec(),
+        };
+        let internal_size = mmr.store().values().last().unwrap().size();
+        assert_eq!(mmr.prune(&retained), 2 * leaf.size() + 6 * internal_size);
+        assert_eq!(mmr.store().len(), 15);
+        assert_eq!(mmr.num_versions(), 3);
+        assert!(mmr.version(0).is_none() && mmr.version(1).is_none());
+        assert_eq!(mmr.commitment_at_version(2).as_ref(), Some(&commitment));
+        let proof = mmr.prove_inclusion_at_version(3, 2).unwrap();
+        assert_eq!(proof.entry, b"updated3");
+        commitment.verify_inclusion(&proof).unwrap();
+        assert_eq!(mmr.prune(&retained), 0);
+
+        // The uncommitted entries still reach every node of version 2
+        assert_eq!(mmr.prune(&BTreeSet::new()), 0);
+        assert!(mmr.version(2).is_none());
+        assert_eq!(mmr.current().commitment(), commitment);
+    }
+
+    #[test]
+    fn test_existence_at_time() {
+        use crate::history::CheckpointedLog;
+
+        let mut log = CheckpointedLog::new();
+        for i in 0..3u8 {
+            log.append(&[i]);
+        }
+        assert_eq!(log.checkpoint(10), Ok(0));
+        for i in 3..7u8 {
+            log.append(&[i]);
+        }
+        assert_eq!(log.checkpoint(20), Ok(1));
+        log.append(&[7]);
+        assert!(log.checkpoint(15).is_err());
+        assert_eq!(log.checkpoint(30), Ok(2));
+        log.append(&[8]);
+
+        let commitment = log.commitment();
+        let checkpoints = log.checkpoints_commitment();
+        assert_eq!(log.version_at(5), None);
+        assert_eq!(log.version_at(25), Some(1));
+
+        // Entry 5 was appended between the first two checkpoints
+        assert!(log.prove_existence(5, 15).is_none());
+        let proof = log.prove_existence(5, 25).unwrap();
+        assert_eq!(proof.entry(), &[5]);
+        assert_eq!(proof.timestamp(), Some(20));
+        assert_eq!(proof.verify(&commitment, &checkpoints, 25), Ok(5));
+        assert_eq!(proof.verify(&commitment, &checkpoints, 20), Ok(5));
+        assert_eq!(
+            proof.verify(&commitment, &checkpoints, 19),
+            Err(VerificationError::NotCheckpointed)
+        );
+        // Entries appended since the last checkpoint aren't proven to exist yet
+        assert!(log.prove_existence(8, 100).is_none());
+        let proof = log.prove_existence(0, 100).unwrap();
+        assert_eq!(proof.verify(&commitment, &checkpoints, 100), Ok(0));
+
+        // The checkpoint must be of the version the entry is proven in
+        let mut forged = log.prove_existence(5, 25).unwrap();
+        forged.checkpoint = log.prove_existence(0, 10).unwrap().checkpoint;
+        assert_eq!(
+            forged.verify(&commitment, &checkpoints, 25),
+            Err(VerificationError::NotCheckpointed)
+        );
+        // The version must be an ancestor of the current log
+        let mut other = CheckpointedLog::new();
+        for i in 0..9u8 {
+            other.append(&[i + 1]);
+        }
+        assert!(proof
+            .verify(&other.commitment(), &checkpoints, 100)
+            .is_err());
+        assert!(proof
+            .verify(&commitment, &other.checkpoints_commitment(), 100)
+            .is_err());
+    }
+
+    #[test]
+    fn test_bisection() {
+        use crate::bisection::{Bisection, BisectionError, BisectionQuery, Party};
+
+        let honest: MerkleMountainRange = (0..13u8).map(|i| [i]).collect();
+        // Disagrees on entries 5 and 9, in the trees of 8 and 4 entries
+        let dishonest: MerkleMountainRange = (0..13u8)
+            .map(|i| if i == 5 || i == 9 { [i + 100] } else { [i] })
+            .collect();
+
+        let mut bisection = Bisection::new(&honest.commitment(), &dishonest.commitment()).unwrap();
+        assert_eq!(bisection.rounds_left(), 3);
+        let mut rounds = 0;
+        let disagreement = loop {
+            let query = bisection.query().unwrap();
+            let (first, second) = (
+                honest.split(&query).unwrap(),
+                dishonest.split(&query).unwrap(),
+            );
+            rounds += 1;
+            if let Some(disagreement) = bisection.respond(&first, &second).unwrap() {
+                break disagreement;
+            }
+        };
+        assert_eq!(rounds, 3);
+        assert_eq!(disagreement.index, 5);
+        assert_eq!(disagreement.first, honest.hasher().hash_leaf(&[5]));
+        assert_eq!(disagreement.second, dishonest.hasher().hash_leaf(&[105]));
+        assert_eq!(bisection.query(), None);
+        let split = honest
+            .split(&BisectionQuery {
+                start: 0,
+                height: 1,
+            })
+            .unwrap();
+        assert_eq!(
+            bisection.respond(&split, &split),
+            Err(BisectionError::Finished)
+        );
+
+        // A party answering with children of another node is caught
+        let mut bisection = Bisection::new(&honest.commitment(), &dishonest.commitment()).unwrap();
+        let query = bisection.query().unwrap();
+        let wrong = BisectionQuery {
+            start: 0,
+            height: 2,
+        };
+        assert_eq!(
+            bisection.respond(
+                &honest.split(&query).unwrap(),
+                &dishonest.split(&wrong).unwrap()
+            ),
+            Err(BisectionError::InvalidSplit(Party::Second))
+        );
+
+        // Disagreeing on a tree of one entry needs no round
+        let mut last: MerkleMountainRange = (0..12u8).map(|i| [i]).collect();
+        last.add_entry(&[0]);
+        let bisection = Bisection::new(&honest.commitment(), &last.commitment()).unwrap();
+        assert_eq!(bisection.disagreement().unwrap().index, 12);
+
+        assert_eq!(
+            Bisection::new(&honest.commitment(), &honest.commitment()),
+            Err(BisectionError::NoDispute)
+        );
+        assert_eq!(
+            Bisection::new(&honest.commitment(), &last.commitment_at(12)),
+            Err(BisectionError::SizeMismatch {
+                first: 13,
+                second: 12
+            })
+        );
+        assert!(honest
+            .split(&BisectionQuery {
+                start: 8,
+                height: 3
+            })
+            .is_none());
+        assert!(honest
+            .split(&BisectionQuery {
+                start: 2,
+                height: 2
+            })
+            .is_none());
+    }
 }
@@ -0,0 +1,134 @@
+//! Proptest strategies for trees, MMRs and proofs, so that crates built on top of this one can
+//! property-test their integrations without writing their own generators.
+//!
+//! Valid proofs verify against the MMR they are generated with. Invalid proofs are valid ones with
+//! a single tampering (an altered entry or sibling, a removed entry, an unknown version), so each
+//! fails verification against that MMR.
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use crate::digest::Digest;
+use crate::{
+    MerkleMountainRange, MmrInclusionProof, MostRecentNElementsProof, PerfectMerkleTree,
+    PROOF_FORMAT_VERSION,
+};
+
+// Entries are short so that generated MMRs stay cheap to build
+const MAX_ENTRY_LENGTH: usize = 16;
+
+/// An arbitrary digest.
+pub fn digest() -> impl Strategy<Value = Digest> {
+    any::<[u8; 32]>().prop_map(Digest)
+}
+
+/// A single entry of up to 16 bytes.
+pub fn entry() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..=MAX_ENTRY_LENGTH)
+}
+
+/// Up to `max_entries` entries.
+pub fn entries(max_entries: usize) -> impl Strategy<Value = Vec<Vec<u8>>> {
+    prop::collection::vec(entry(), 0..=max_entries)
+}
+
+/// A perfect tree of height at most `max_height`, along with its leaves.
+pub fn perfect_tree(max_height: u32) -> impl Strategy<Value = (Vec<Vec<u8>>, PerfectMerkleTree)> {
+    (0..=max_height)
+        .prop_flat_map(|height| prop::collection::vec(entry(), 1 << height))
+        .prop_map(|leaves| {
+            let tree = PerfectMerkleTree::new(leaves.iter().map(|l| l.as_slice()).collect());
+            (leaves, tree)
+        })
+}
+
+/// An MMR of up to `max_entries` entries.
+pub fn mmr(max_entries: usize) -> impl Strategy<Value = MerkleMountainRange> {
+    entries(max_entries).prop_map(|entries| build(&entries))
+}
+
+/// A non-empty MMR with a valid proof of some of its most recent entries.
+pub fn most_recent_proof(
+    max_entries: usize,
+) -> impl Strategy<Value = (MerkleMountainRange, MostRecentNElementsProof)> {
+    non_empty_mmr_with_index(max_entries).prop_map(|(mmr, index)| {
+        let proof = mmr.prove_most_recent_n_elements(index + 1);
+        (mmr, proof)
+    })
+}
+
+/// A non-empty MMR with a valid inclusion proof of one of its entries.
+pub fn inclusion_proof(
+    max_entries: usize,
+) -> impl Strategy<Value = (MerkleMountainRange, MmrInclusionProof)> {
+    non_empty_mmr_with_index(max_entries).prop_map(|(mmr, index)| {
+        let proof = mmr.prove_inclusion(index);
+        (mmr, proof)
+    })
+}
+
+/// A non-empty MMR with a proof of its most recent entries that fails verification.
+pub fn invalid_most_recent_proof(
+    max_entries: usize,
+) -> impl Strategy<Value = (MerkleMountainRange, MostRecentNElementsProof)> {
+    (most_recent_proof(max_entries), tampering()).prop_map(|((mmr, mut proof), tampering)| {
+        match tampering {
+            Tampering::Entry(index) => index.get_mut(&mut proof.entries).push(0),
+            Tampering::Structure(index) => {
+                proof.entries.remove(index.index(proof.entries.len()));
+            }
+            Tampering::Version => proof.version = PROOF_FORMAT_VERSION + 1,
+        }
+        (mmr, proof)
+    })
+}
+
+/// A non-empty MMR with an inclusion proof that fails verification.
+pub fn invalid_inclusion_proof(
+    max_entries: usize,
+) -> impl Strategy<Value = (MerkleMountainRange, MmrInclusionProof)> {
+    (inclusion_proof(max_entries), tampering()).prop_map(|((mmr, mut proof), tampering)| {
+        let siblings = &mut proof.proof.siblings;
+        match tampering {
+            Tampering::Structure(index) if !siblings.is_empty() => {
+                index.get_mut(siblings).0[0] ^= 1;
+            }
+            Tampering::Version => proof.version = PROOF_FORMAT_VERSION + 1,
+            _ => proof.entry.push(0),
+        }
+        (mmr, proof)
+    })
+}
+
+#[derive(Debug, Clone)]
+enum Tampering {
+    /// Alter an entry
+    Entry(Index),
+    /// Remove an entry, or corrupt a sibling of an inclusion path
+    Structure(Index),
+    /// Claim a format version verifiers don't know
+    Version,
+}
+
+fn tampering() -> impl Strategy<Value = Tampering> {
+    prop_oneof![
+        any::<Index>().prop_map(Tampering::Entry),
+        any::<Index>().prop_map(Tampering::Structure),
+        Just(Tampering::Version),
+    ]
+}
+
+// A non-empty MMR and the index of one of its entries
+fn non_empty_mmr_with_index(
+    max_entries: usize,
+) -> impl Strategy<Value = (MerkleMountainRange, u64)> {
+    (
+        prop::collection::vec(entry(), 1..=max_entries.max(1)),
+        any::<Index>(),
+    )
+        .prop_map(|(entries, index)| (build(&entries), index.index(entries.len()) as u64))
+}
+
+fn build(entries: &[Vec<u8>]) -> MerkleMountainRange {
+    MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect())
+}
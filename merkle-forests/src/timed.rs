@@ -0,0 +1,108 @@
+//! Logs of timestamped entries, proven by time range rather than by index.
+//!
+//! See `ads_verifier::timed` for the entry encoding and what a range proof shows.
+
+use std::fmt;
+
+pub use ads_verifier::timed::{TimeRangeProof, TimestampedEntry};
+
+use crate::error::VerificationError;
+use crate::MerkleMountainRange;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimestampError {
+    /// Appending would make timestamps decrease
+    NonMonotonic { last: u64, timestamp: u64 },
+}
+
+impl fmt::Display for TimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimestampError::NonMonotonic { last, timestamp } => write!(
+                f,
+                "Timestamp {} precedes the last timestamp {}",
+                timestamp, last
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TimestampError {}
+
+/// An MMR of timestamped entries whose timestamps never decrease.
+#[derive(Debug)]
+pub struct TimestampedLog {
+    mmr: MerkleMountainRange,
+    // Timestamp of every entry, to find the range boundaries without decoding entries
+    timestamps: Vec<u64>,
+}
+
+impl Default for TimestampedLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimestampedLog {
+    pub fn new() -> Self {
+        TimestampedLog {
+            mmr: MerkleMountainRange::new(vec![]),
+            timestamps: vec![],
+        }
+    }
+
+    /// Append an entry, rejecting it if its timestamp precedes the last one. Equal timestamps are
+    /// allowed.
+    pub fn append(&mut self, timestamp: u64, payload: &[u8]) -> Result<(), TimestampError> {
+        if let Some(&last) = self.timestamps.last() {
+            if timestamp < last {
+                return Err(TimestampError::NonMonotonic { last, timestamp });
+            }
+        }
+        let entry = TimestampedEntry::new(timestamp, payload.to_vec());
+        self.mmr.add_entry(&entry.encode());
+        self.timestamps.push(timestamp);
+        Ok(())
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.timestamps.len() as u64
+    }
+
+    /// The underlying MMR, e.g. for its commitment.
+    pub fn mmr(&self) -> &MerkleMountainRange {
+        &self.mmr
+    }
+
+    pub fn entry(&self, index: u64) -> TimestampedEntry {
+        TimestampedEntry::decode(self.mmr.entries.get(index as usize)).unwrap()
+    }
+
+    /// Index of the last entry timestamped no later than `time`, None if there's none.
+    pub fn latest_at(&self, time: u64) -> Option<u64> {
+        let after = self.timestamps.partition_point(|&t| t <= time);
+        Some(after.checked_sub(1)? as u64)
+    }
+
+    /// Prove every entry with a timestamp in [start, end], along with the entries bounding them.
+    pub fn prove_time_range(&self, start: u64, end: u64) -> TimeRangeProof {
+        assert!(start <= end, "Empty time range [{}, {}]", start, end);
+        if self.timestamps.is_empty() {
+            return TimeRangeProof::new(start, end, None);
+        }
+        // Entries in range are [first, last)
+        let first = self.timestamps.partition_point(|&t| t < start);
+        let last = self.timestamps.partition_point(|&t| t <= end);
+        let bounded_first = first.saturating_sub(1);
+        let bounded_last = last.min(self.timestamps.len() - 1);
+        let indices: Vec<u64> = (bounded_first as u64..=bounded_last as u64).collect();
+        TimeRangeProof::new(start, end, Some(self.mmr.prove_multi_inclusion(&indices)))
+    }
+
+    pub fn try_verify_time_range(
+        &self,
+        proof: &TimeRangeProof,
+    ) -> Result<Vec<TimestampedEntry>, VerificationError> {
+        self.mmr.commitment().verify_time_range(proof)
+    }
+}
@@ -0,0 +1,46 @@
+//! Deleting entries by appending tombstones, and proving they were deleted.
+//!
+//! Deleted entries stay in the MMR, as its commitments depend on them. Finding the tombstone of an
+//! entry looks up its encoding, which the content index answers without a scan when enabled.
+
+pub use ads_verifier::tombstone::{DeletionProof, Tombstone, TOMBSTONE_TAG};
+
+use crate::MerkleMountainRange;
+
+impl MerkleMountainRange {
+    /// Delete the entry at `index` by appending a tombstone referencing it, and return the index
+    /// of the tombstone. The entry must exist, and be neither a tombstone nor already deleted.
+    pub fn delete(&mut self, index: u64) -> u64 {
+        assert!(index < self.num_entries(), "Index {} out of bounds", index);
+        assert!(
+            Tombstone::decode(self.entries.get(index as usize)).is_none(),
+            "Tombstones can't be deleted"
+        );
+        assert!(
+            !self.is_deleted(index),
+            "Entry {} is already deleted",
+            index
+        );
+        self.add_entry(&Tombstone::new(index).encode());
+        self.num_entries() - 1
+    }
+
+    /// Index of the tombstone of the entry at `index`, None if it isn't deleted.
+    pub fn tombstone_of(&self, index: u64) -> Option<u64> {
+        let tombstone = Tombstone::new(index).encode();
+        self.indices_of(&tombstone).first().copied()
+    }
+
+    pub fn is_deleted(&self, index: u64) -> bool {
+        self.tombstone_of(index).is_some()
+    }
+
+    /// Prove that the entry at `index` was deleted as of the current commitment, None if it isn't.
+    pub fn prove_deletion(&self, index: u64) -> Option<DeletionProof> {
+        let tombstone = self.tombstone_of(index)?;
+        Some(DeletionProof::new(
+            self.prove_inclusion(index),
+            self.prove_inclusion(tombstone),
+        ))
+    }
+}
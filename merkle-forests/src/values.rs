@@ -0,0 +1,77 @@
+//! Contiguous storage for leaf payloads.
+//!
+//! Trees only hold hashes; the payloads themselves are kept once, back to back in a single
+//! buffer, and addressed by offsets. This avoids a heap allocation per entry and lets readers
+//! borrow entries without copying.
+
+use std::mem::size_of;
+use std::ops::Range;
+
+use crate::memory::MemoryUsage;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeafValues {
+    data: Vec<u8>,
+    // offsets[i]..offsets[i + 1] is the range of the i-th value in data
+    offsets: Vec<usize>,
+}
+
+impl LeafValues {
+    pub fn new() -> Self {
+        LeafValues {
+            data: vec![],
+            offsets: vec![0],
+        }
+    }
+
+    pub fn push(&mut self, value: &[u8]) {
+        self.data.extend_from_slice(value);
+        self.offsets.push(self.data.len());
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> &[u8] {
+        assert!(index < self.len(), "Index {} out of bounds", index);
+        &self.data[self.offsets[index]..self.offsets[index + 1]]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    /// Copy the values in the given range of indices.
+    pub fn to_vecs(&self, range: Range<usize>) -> Vec<Vec<u8>> {
+        range.map(|i| self.get(i).to_vec()).collect()
+    }
+
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            values: self.data.capacity(),
+            auxiliary: self.offsets.capacity() * size_of::<usize>(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for LeafValues {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> FromIterator<&'a [u8]> for LeafValues {
+    fn from_iter<I: IntoIterator<Item = &'a [u8]>>(iter: I) -> Self {
+        let mut values = LeafValues::new();
+        for value in iter {
+            values.push(value);
+        }
+        values
+    }
+}
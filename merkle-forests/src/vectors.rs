@@ -0,0 +1,189 @@
+//! Known-answer test vectors, for checking other implementations (Solidity, Move, JS, ...) against
+//! this one byte for byte.
+//!
+//! The vectors cover the two hash functions and the root of empty structures, then MMRs of a few
+//! sizes with their commitments and a selection of proofs of every kind, all in the BCS encoding
+//! verifiers receive over the wire. Byte strings are hex, as are digests in the JSON encoding of
+//! `MmrCommitment`. `ads vectors` exports them as JSON.
+//!
+//! The vectors are derived from the code, so a change in them is a change in the format: bump
+//! `PROOF_FORMAT_VERSION`, which they record.
+
+use serde::{Deserialize, Serialize};
+
+use crate::digest::{Digest, Hex};
+use crate::{
+    hash_children, hash_leaf, MerkleMountainRange, MmrCommitment, EMPTY_ROOT, PROOF_FORMAT_VERSION,
+};
+
+// Sizes covering a single tree, several trees, and trees of consecutive and distant heights
+const MMR_SIZES: [u64; 9] = [1, 2, 3, 4, 7, 8, 13, 16, 33];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub version: u8,
+    /// Root of any structure without leaves
+    pub empty_root: Digest,
+    pub leaf_hashes: Vec<LeafHashVector>,
+    pub node_hashes: Vec<NodeHashVector>,
+    pub mmrs: Vec<MmrVector>,
+}
+
+/// `hash_leaf(value) == digest`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeafHashVector {
+    pub value: String,
+    pub digest: Digest,
+}
+
+/// `hash_children(left, right) == digest`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeHashVector {
+    pub left: Digest,
+    pub right: Digest,
+    pub digest: Digest,
+}
+
+/// An MMR holding `entries` in order, and proofs about it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MmrVector {
+    pub entries: Vec<String>,
+    pub commitment: MmrCommitment,
+    pub commitment_bcs: String,
+    /// Root of the conventional layout, see `standard`
+    pub standard_root: Digest,
+    pub proofs: Vec<ProofVector>,
+}
+
+/// A proof answering `query`, which verifies against the commitment of its MMR.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofVector {
+    pub kind: ProofKind,
+    pub query: Vec<u64>,
+    pub proof_bcs: String,
+}
+
+/// What a proof proves, and so what its query is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofKind {
+    /// `MmrInclusionProof` of the entry at index `query[0]`
+    Inclusion,
+    /// `MmrMultiInclusionProof` of the entries at the indices in `query`
+    MultiInclusion,
+    /// `MostRecentNElementsProof` of the `query[0]` most recent entries
+    MostRecent,
+    /// `ConsistencyProof` with the MMR of the first `query[0]` entries
+    Consistency,
+    /// `StandardMerkleProof` of the entry at index `query[0]`, against the standard root
+    StandardInclusion,
+}
+
+/// The vectors of the current format.
+pub fn generate() -> TestVectors {
+    let values: [&[u8]; 3] = [b"", b"a", &[0, 1, 2, 0xff]];
+    let leaf_hashes = values
+        .iter()
+        .map(|value| LeafHashVector {
+            value: hex(value),
+            digest: hash_leaf(value),
+        })
+        .collect();
+    let node_hashes = [
+        (EMPTY_ROOT, EMPTY_ROOT),
+        (hash_leaf(b"a"), hash_leaf(b"b")),
+        (hash_leaf(b"b"), hash_leaf(b"a")),
+    ]
+    .iter()
+    .map(|(left, right)| NodeHashVector {
+        left: *left,
+        right: *right,
+        digest: hash_children(left, right),
+    })
+    .collect();
+
+    TestVectors {
+        version: PROOF_FORMAT_VERSION,
+        empty_root: EMPTY_ROOT,
+        leaf_hashes,
+        node_hashes,
+        mmrs: MMR_SIZES.iter().map(|&size| mmr_vector(size)).collect(),
+    }
+}
+
+fn mmr_vector(size: u64) -> MmrVector {
+    let entries: Vec<Vec<u8>> = (0..size)
+        .map(|i| format!("entry{}", i).into_bytes())
+        .collect();
+    let mmr = MerkleMountainRange::new(entries.iter().map(|e| e.as_slice()).collect());
+    let commitment = mmr.commitment();
+    let last = size - 1;
+
+    let mut proofs = vec![];
+    let mut push = |kind, query: Vec<u64>, proof_bcs| {
+        proofs.push(ProofVector {
+            kind,
+            query,
+            proof_bcs,
+        })
+    };
+    for index in dedup([0, size / 2, last]) {
+        push(
+            ProofKind::Inclusion,
+            vec![index],
+            bcs_hex(&mmr.prove_inclusion(index)),
+        );
+        push(
+            ProofKind::StandardInclusion,
+            vec![index],
+            bcs_hex(&mmr.prove_inclusion_standard(index)),
+        );
+    }
+    let indices = dedup([0, size / 2, size / 2 + 1, last])
+        .into_iter()
+        .filter(|&index| index < size)
+        .collect::<Vec<_>>();
+    push(
+        ProofKind::MultiInclusion,
+        indices.clone(),
+        bcs_hex(&mmr.prove_multi_inclusion(&indices)),
+    );
+    for n in dedup([1, size / 2, size]).into_iter().filter(|&n| n > 0) {
+        push(
+            ProofKind::MostRecent,
+            vec![n],
+            bcs_hex(&mmr.prove_most_recent_n_elements(n)),
+        );
+    }
+    for old in dedup([0, size / 2, size]) {
+        push(
+            ProofKind::Consistency,
+            vec![old],
+            bcs_hex(&mmr.prove_consistency(old)),
+        );
+    }
+
+    MmrVector {
+        entries: entries.iter().map(|entry| hex(entry)).collect(),
+        commitment_bcs: bcs_hex(&commitment),
+        commitment,
+        standard_root: mmr.standard_root().unwrap(),
+        proofs,
+    }
+}
+
+// The distinct values, in increasing order
+fn dedup<const N: usize>(mut values: [u64; N]) -> Vec<u64> {
+    values.sort();
+    let mut values = values.to_vec();
+    values.dedup();
+    values
+}
+
+fn hex(bytes: &[u8]) -> String {
+    Hex(bytes).to_string()
+}
+
+fn bcs_hex<T: Serialize>(value: &T) -> String {
+    hex(&bcs::to_bytes(value).expect("Proofs always serialize"))
+}
@@ -0,0 +1,205 @@
+//! A framed binary protocol for exchanging commitments and proofs, e.g. over TCP or QUIC streams.
+//!
+//! Every message travels in a frame: its length as a big-endian `u32`, then the BCS encoding of
+//! the protocol version, a request id, and the message. A response carries the id of the request
+//! it answers, so that clients can pipeline requests. Frames larger than `MAX_FRAME_LENGTH` are
+//! rejected before anything is allocated for them.
+//!
+//! A server answers requests from any `ProofProvider` with `respond`.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{ConsistencyProof, MmrCommitment, MmrInclusionProof, MostRecentNElementsProof};
+
+/// Version of the protocol, sent in every frame.
+pub const WIRE_VERSION: u8 = 1;
+
+/// Bytes of the length that starts every frame.
+pub const FRAME_HEADER_LENGTH: usize = 4;
+
+/// Largest accepted frame, without its header.
+pub const MAX_FRAME_LENGTH: u32 = 16 << 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Request {
+    /// The current commitment
+    GetRoot,
+    ProveInclusion {
+        index: u64,
+    },
+    ProveSuffix {
+        num_entries: u64,
+    },
+    ProveConsistency {
+        old_num_entries: u64,
+    },
+    /// Tells the server whether the proofs against the commitment to `num_entries` entries
+    /// verified, e.g., so that it can monitor its clients
+    VerifyAck {
+        num_entries: u64,
+        verified: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Response {
+    Root(MmrCommitment),
+    Inclusion(MmrInclusionProof),
+    Suffix(MostRecentNElementsProof),
+    Consistency(ConsistencyProof),
+    /// Answer to `VerifyAck`
+    Ack,
+    /// The request couldn't be served
+    Error(String),
+}
+
+/// A message with the id of the request it is or answers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame<T> {
+    pub id: u64,
+    pub message: T,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u8,
+    id: u64,
+    message: T,
+}
+
+#[derive(Debug)]
+pub enum WireError {
+    /// The frame is longer than `MAX_FRAME_LENGTH`
+    FrameTooLarge(u32),
+    /// The peer speaks another version of the protocol
+    UnsupportedVersion(u8),
+    /// The frame doesn't hold a message of the expected type
+    Malformed(bcs::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::FrameTooLarge(length) => write!(f, "Frame of {} bytes is too large", length),
+            WireError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported protocol version {}", version)
+            }
+            WireError::Malformed(e) => write!(f, "Malformed message: {}", e),
+            WireError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+impl From<io::Error> for WireError {
+    fn from(e: io::Error) -> Self {
+        WireError::Io(e)
+    }
+}
+
+/// The frame of `message` with `id`, header included.
+pub fn encode_frame<T: Serialize>(id: u64, message: &T) -> Vec<u8> {
+    let body = bcs::to_bytes(&Envelope {
+        version: WIRE_VERSION,
+        id,
+        message,
+    })
+    .expect("Messages always serialize");
+    let length = u32::try_from(body.len())
+        .ok()
+        .filter(|&length| length <= MAX_FRAME_LENGTH)
+        .expect("Message larger than a frame");
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LENGTH + body.len());
+    frame.extend_from_slice(&length.to_be_bytes());
+    frame.extend_from_slice(&body);
+    frame
+}
+
+/// Decode the frame at the start of `buffer`, returning it and how many bytes it took. None if
+/// the buffer doesn't hold a whole frame yet.
+pub fn decode_frame<T: DeserializeOwned>(
+    buffer: &[u8],
+) -> Result<Option<(Frame<T>, usize)>, WireError> {
+    let Some(header) = buffer.first_chunk::<FRAME_HEADER_LENGTH>() else {
+        return Ok(None);
+    };
+    let length = frame_length(*header)?;
+    let Some(body) = buffer[FRAME_HEADER_LENGTH..].get(..length) else {
+        return Ok(None);
+    };
+    Ok(Some((decode_body(body)?, FRAME_HEADER_LENGTH + length)))
+}
+
+/// Write the frame of `message` with `id`.
+pub fn write_frame<T: Serialize, W: Write>(
+    mut writer: W,
+    id: u64,
+    message: &T,
+) -> Result<(), WireError> {
+    writer.write_all(&encode_frame(id, message))?;
+    Ok(())
+}
+
+/// Read the next frame, blocking until it's whole.
+pub fn read_frame<T: DeserializeOwned, R: Read>(mut reader: R) -> Result<Frame<T>, WireError> {
+    let mut header = [0; FRAME_HEADER_LENGTH];
+    reader.read_exact(&mut header)?;
+    let mut body = vec![0; frame_length(header)?];
+    reader.read_exact(&mut body)?;
+    decode_body(&body)
+}
+
+fn frame_length(header: [u8; FRAME_HEADER_LENGTH]) -> Result<usize, WireError> {
+    let length = u32::from_be_bytes(header);
+    if length > MAX_FRAME_LENGTH {
+        return Err(WireError::FrameTooLarge(length));
+    }
+    Ok(length as usize)
+}
+
+fn decode_body<T: DeserializeOwned>(body: &[u8]) -> Result<Frame<T>, WireError> {
+    // The version comes first, so that it can be checked before the rest is parsed
+    match body.first() {
+        Some(&WIRE_VERSION) => {}
+        Some(&version) => return Err(WireError::UnsupportedVersion(version)),
+        None => return Err(WireError::Malformed(bcs::Error::Eof)),
+    }
+    let envelope: Envelope<T> = bcs::from_bytes(body).map_err(WireError::Malformed)?;
+    Ok(Frame {
+        id: envelope.id,
+        message: envelope.message,
+    })
+}
+
+/// The answer of `provider` to `request`. Acknowledgements are only answered; a server that
+/// tracks them handles `VerifyAck` before calling this.
+#[cfg(feature = "prover")]
+pub async fn respond<P>(provider: &P, request: Request) -> Response
+where
+    P: crate::provider::ProofProvider,
+    P::Error: fmt::Display,
+{
+    let response = match request {
+        Request::GetRoot => provider.get_commitment().await.map(Response::Root),
+        Request::ProveInclusion { index } => provider
+            .prove_inclusion(index)
+            .await
+            .map(Response::Inclusion),
+        Request::ProveSuffix { num_entries } => provider
+            .prove_suffix(num_entries)
+            .await
+            .map(Response::Suffix),
+        Request::ProveConsistency { old_num_entries } => provider
+            .prove_consistency(old_num_entries)
+            .await
+            .map(Response::Consistency),
+        Request::VerifyAck { .. } => Ok(Response::Ack),
+    };
+    response.unwrap_or_else(|e| Response::Error(e.to_string()))
+}
@@ -0,0 +1,1086 @@
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, fmt::{self, Display}, mem::size_of};
+use ads_core::{AppendObserver, NodeHasher, Proof, DIGEST_LENGTH, EMPTY_ROOT};
+use ads_core::encoding::uleb128_length;
+use ads_verifier::VerificationError;
+use serde::{Deserialize, Serialize};
+use bcs::to_bytes;
+use ads_store::{BatchOp, NodeStore};
+
+pub use ads_core::MemoryUsage;
+
+pub mod store;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+const DEFAULT_BASE: u64 = 10;
+
+/// Length of the salts of value-hiding nodes
+pub const SALT_LENGTH: usize = 32;
+
+pub use ads_core::Digest;
+
+/// A node in a skip list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node<T> {
+    /// Value
+    pub value: T,
+    /// Height of current node
+    pub height: u64,
+    /// A list of previous nodes & their heights. Useful for short inclusion proofs.
+    /// We store at most log_b(h) fingers
+    pub fingers: HashMap<u64, Digest>,
+    /// Salt of the value, for nodes of a value-hiding skip list. Such nodes commit to
+    /// H(salt || value) instead of the value itself.
+    pub salt: Option<[u8; SALT_LENGTH]>,
+}
+
+/// A node on the path of a proof through a value-hiding skip list: only the commitment to its
+/// value is revealed, not the value or its salt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlindedNode {
+    pub height: u64,
+    pub value_commitment: Digest,
+    pub fingers: HashMap<u64, Digest>,
+}
+
+/// Which previous heights each node keeps a finger to.
+///
+/// Any schedule works with the same proofs and verification, which only follow the fingers stored
+/// in nodes. Every node of a skip list must be built with the same schedule though, see
+/// `SkipList::read_from_with_schedule`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FingerSchedule {
+    /// Fingers as computed by `calculate_finger_indices` with this base
+    Base(u64),
+    /// The fingers of `base`, plus those of `recent_base` reaching back at most `recent_heights`
+    /// heights. With a small recent base, proofs of recent nodes get shorter at the cost of a few
+    /// more fingers per node, while old nodes are still reached through the sparser fingers.
+    Hybrid { recent_base: u64, recent_heights: u64, base: u64 },
+}
+
+impl Default for FingerSchedule {
+    fn default() -> Self {
+        FingerSchedule::Base(DEFAULT_BASE)
+    }
+}
+
+impl FingerSchedule {
+    /// Indices of the fingers of the node at `height`, in decreasing order
+    pub fn finger_indices(&self, height: u64) -> Vec<u64> {
+        match *self {
+            FingerSchedule::Base(base) => calculate_finger_indices(height, base),
+            FingerSchedule::Hybrid { recent_base, recent_heights, base } => {
+                assert!(recent_base >= 2, "Base must be at least 2");
+                let mut fingers = calculate_finger_indices(height, base);
+                let x = height - 1;
+                let mut y = 1;
+                while x >= y && y <= recent_heights {
+                    let z = (x / y) * y;
+                    if !fingers.contains(&z) {
+                        fingers.push(z);
+                    }
+                    y *= recent_base;
+                }
+                fingers.sort_unstable_by(|a, b| b.cmp(a));
+                fingers
+            }
+        }
+    }
+}
+
+/// Proof that a node is in a skip list, see `SkipList::prove`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkipListProof<T> {
+    pub node: Node<T>,
+    /// From the head down to the node, as returned by `get_inclusion_proof`
+    pub path: Vec<Node<T> >,
+}
+
+impl<T: Copy + Serialize> Proof for SkipListProof<T> {
+    type Commitment = HeadCommitment;
+    type Claim = ();
+    type Output = ();
+    type Error = VerificationError;
+
+    fn verify(&self, head: &HeadCommitment, _: &()) -> Result<(), VerificationError> {
+        if !verify_inclusion_proof(head, &self.node, &self.path) {
+            return Err(VerificationError::RootMismatch);
+        }
+        Ok(())
+    }
+
+    fn size_hint(&self) -> usize {
+        self.node.encoded_length()
+            + uleb128_length(self.path.len())
+            + self.path.iter().map(Node::encoded_length).sum::<usize>()
+    }
+}
+
+/// What clients pin about a skip list, e.g. to sign or gossip it: the height and digest of its
+/// latest node. Inclusion proofs are verified against it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeadCommitment {
+    pub height: u64,
+    pub digest: Digest,
+}
+
+impl HeadCommitment {
+    /// The commitment to an empty skip list. Heights start at 1, so no node is included in it.
+    pub const EMPTY: HeadCommitment = HeadCommitment { height: 0, digest: EMPTY_ROOT };
+
+    pub fn is_empty(&self) -> bool {
+        self.height == 0
+    }
+}
+
+impl Display for HeadCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.height, self.digest)
+    }
+}
+
+#[derive(Debug)]
+pub struct SkipList<T> {
+    pub nodes: Vec<Node<T> >,
+    /// Whether new nodes get a random salt, see `SkipList::new_hiding`
+    pub hiding: bool,
+    /// Fingers of new nodes
+    pub schedule: FingerSchedule,
+    /// How nodes are hashed, see `SkipList::with_hasher`
+    pub hasher: NodeHasher,
+    observers: Observers,
+}
+
+type BoxedObserver = Box<dyn AppendObserver<HeadCommitment> + Send + Sync>;
+
+// Only the number of observers shows when debugging
+#[derive(Default)]
+struct Observers(Vec<BoxedObserver>);
+
+// Observers are only ever called through `&mut`, so a skip list shared across `catch_unwind` can't
+// observe one that panicked halfway
+impl std::panic::RefUnwindSafe for Observers {}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} observers", self.0.len())
+    }
+}
+
+impl<T> Node<T> where T: Copy + Serialize {
+    /// The first node in a skip list
+    pub fn first(val: T) -> Node<T> {
+        Node {
+            value: val,
+            height: 1,
+            fingers: HashMap::<u64, Digest>::new(),
+            salt: None,
+        }
+    }
+
+    pub fn digest(&self) -> Digest {
+        self.digest_with_hasher(&NodeHasher::UNKEYED)
+    }
+
+    /// Same as `digest`, for a skip list hashed with `hasher`
+    pub fn digest_with_hasher(&self, hasher: &NodeHasher) -> Digest {
+        match self.salt {
+            Some(_) => node_digest(hasher, &self.value_commitment_with_hasher(hasher).0, self.height, &self.fingers),
+            None => node_digest(hasher, &to_bytes(&self.value).unwrap(), self.height, &self.fingers),
+        }
+    }
+
+    /// H(salt || value), what a salted node commits to in place of its value. Unsalted nodes have
+    /// no salt to mix in, so this is just the hash of the value.
+    pub fn value_commitment(&self) -> Digest {
+        self.value_commitment_with_hasher(&NodeHasher::UNKEYED)
+    }
+
+    /// Same as `value_commitment`, for a skip list hashed with `hasher`
+    pub fn value_commitment_with_hasher(&self, hasher: &NodeHasher) -> Digest {
+        let mut message = hasher.message_hasher();
+        if let Some(salt) = &self.salt {
+            message.update(salt);
+        }
+        message.update(&to_bytes(&self.value).unwrap());
+        message.finalize()
+    }
+
+    /// Length of the BCS encoding of the node
+    pub fn encoded_length(&self) -> usize {
+        let fingers = uleb128_length(self.fingers.len()) + self.fingers.len() * (8 + DIGEST_LENGTH);
+        let salt = 1 + self.salt.map_or(0, |salt| salt.len());
+        to_bytes(&self.value).unwrap().len() + 8 + fingers + salt
+    }
+
+    /// The commitment to a skip list whose latest node is this one
+    pub fn head_commitment(&self) -> HeadCommitment {
+        self.head_commitment_with_hasher(&NodeHasher::UNKEYED)
+    }
+
+    /// Same as `head_commitment`, for a skip list hashed with `hasher`
+    pub fn head_commitment_with_hasher(&self, hasher: &NodeHasher) -> HeadCommitment {
+        HeadCommitment { height: self.height, digest: self.digest_with_hasher(hasher) }
+    }
+
+    /// The node without its value and salt, as it appears on the path of a proof.
+    pub fn blind(&self) -> BlindedNode {
+        self.blind_with_hasher(&NodeHasher::UNKEYED)
+    }
+
+    /// Same as `blind`, for a skip list hashed with `hasher`
+    pub fn blind_with_hasher(&self, hasher: &NodeHasher) -> BlindedNode {
+        BlindedNode {
+            height: self.height,
+            value_commitment: self.value_commitment_with_hasher(hasher),
+            fingers: self.fingers.clone(),
+        }
+    }
+
+    /// Heap bytes held by the fingers. A HashMap allocates a control byte next to each bucket.
+    pub fn fingers_memory_usage(&self) -> usize {
+        self.fingers.capacity() * (size_of::<(u64, Digest)>() + 1)
+    }
+
+    /// Calculate the next node given the latest node & new value
+    pub fn next(&self, new_value: T) -> Node<T> {
+        self.next_with_schedule(new_value, &FingerSchedule::default())
+    }
+
+    /// Same as `next`, for a skip list with the given finger schedule
+    pub fn next_with_schedule(&self, new_value: T, schedule: &FingerSchedule) -> Node<T> {
+        self.next_with_hasher(new_value, schedule, &NodeHasher::UNKEYED)
+    }
+
+    /// Same as `next_with_schedule`, for a skip list hashed with `hasher`
+    pub fn next_with_hasher(&self, new_value: T, schedule: &FingerSchedule, hasher: &NodeHasher) -> Node<T> {
+        Node {
+            value: new_value,
+            height: self.height + 1,
+            fingers: self.next_fingers(schedule, hasher),
+            salt: None,
+        }
+    }
+
+    /// Calculates the next fingers using the current ones
+    fn next_fingers(&self, schedule: &FingerSchedule, hasher: &NodeHasher) -> HashMap<u64, Digest> {
+        let next_height = self.height + 1;
+        let finger_indices = schedule.finger_indices(next_height);
+        let mut new_h = HashMap::new();
+        let old_h = &self.fingers;
+        for idx in finger_indices {
+            match old_h.get(&idx) {
+                Some(val) => {
+                    new_h.insert(idx, *val);
+                },
+                None => {
+                    if idx == self.height {
+                        new_h.insert(idx, self.digest_with_hasher(hasher));
+                    } else {
+                        panic!("Unexpected idx {}", idx)
+                    }
+                }
+            }
+        }
+        new_h
+    }
+}
+
+impl BlindedNode {
+    /// Same as the digest of the node it was blinded from
+    pub fn digest(&self) -> Digest {
+        self.digest_with_hasher(&NodeHasher::UNKEYED)
+    }
+
+    /// Same as `digest`, for a skip list hashed with `hasher`
+    pub fn digest_with_hasher(&self, hasher: &NodeHasher) -> Digest {
+        node_digest(hasher, &self.value_commitment.0, self.height, &self.fingers)
+    }
+}
+
+// A random salt for a node of a value-hiding skip list
+pub(crate) fn fresh_salt() -> [u8; SALT_LENGTH] {
+    let mut salt = [0; SALT_LENGTH];
+    getrandom::fill(&mut salt).expect("Failed to draw a salt");
+    salt
+}
+
+// Hash of the value (or its commitment), height and fingers, plain Blake2b256 unless the skip list
+// is hashed otherwise
+fn node_digest(hasher: &NodeHasher, value: &[u8], height: u64, fingers: &HashMap<u64, Digest>) -> Digest {
+    let mut hasher = hasher.message_hasher();
+    hasher.update(value);
+    hasher.update(&height.to_le_bytes());
+    // Iterate over fingers in increasing order of indices
+    let mut finger_indices: Vec<u64> = fingers.keys().cloned().collect();
+    finger_indices.sort();
+    for idx in finger_indices {
+        let digest = fingers.get(&idx).expect("Finger not found");
+        hasher.update(&idx.to_le_bytes());
+        hasher.update(&digest.0);
+    }
+    hasher.finalize()
+}
+
+/// Returns indices of fingers for the given height.
+/// Fingers are nothing but greatest indices at different heights.
+/// 
+/// Algo: For a skip list of height h, the finger indices are calculated as follows:
+/// For each base y in [1, base, base^2, ...], calculate z = ((h - 1) / y) * y.
+/// The works because (h - 1) is guaranteed to be the greatest index at some height. 
+/// And other greatest indices can be derived by replacing the last digit(s) in (height - 1) with zeroes.
+pub fn calculate_finger_indices(height: u64, base: u64) -> Vec<u64> {
+    let mut fingers = Vec::new();
+    let x = height - 1;
+    let mut y = 1;
+    while x >= y {
+        let z = (x / y) * y;
+        if !fingers.contains(&z) { // To omit duplicates
+            fingers.push(z);
+        }
+        y *= base;
+    }
+    fingers
+}
+
+/// Check that `node` is in the skip list committed to by `head`, given the path returned by
+/// `get_inclusion_proof(node.height)`.
+pub fn verify_inclusion_proof<T: Copy + Serialize>(head: &HeadCommitment, node: &Node<T>, proof: &[Node<T>]) -> bool {
+    verify_inclusion_proof_with_hasher(&NodeHasher::UNKEYED, head, node, proof)
+}
+
+/// Same as `verify_inclusion_proof`, for a skip list hashed with `hasher`.
+pub fn verify_inclusion_proof_with_hasher<T: Copy + Serialize>(hasher: &NodeHasher, head: &HeadCommitment, node: &Node<T>, proof: &[Node<T>]) -> bool {
+    // The path starts at the head, or is empty when proving the head itself
+    if proof.first().map_or(node.height, |path_node| path_node.height) != head.height {
+        return false;
+    }
+    let mut expected = head.digest;
+    for path_node in proof {
+        if path_node.digest_with_hasher(hasher) != expected || path_node.height <= node.height {
+            return false;
+        }
+        // Same finger as the one followed by get_inclusion_proof
+        let Some(finger) = path_node.fingers.keys().filter(|&&finger| finger >= node.height).min() else {
+            return false;
+        };
+        expected = path_node.fingers[finger];
+    }
+    node.digest_with_hasher(hasher) == expected
+}
+
+/// Check that all of `nodes` are in the skip list committed to by `head`, given the proof returned
+/// by `get_inclusion_proofs` for their heights.
+pub fn verify_inclusion_proofs<T: Copy + Serialize>(head: &HeadCommitment, nodes: &[Node<T>], proof: &[Node<T>]) -> bool {
+    verify_inclusion_proofs_with_hasher(&NodeHasher::UNKEYED, head, nodes, proof)
+}
+
+/// Same as `verify_inclusion_proofs`, for a skip list hashed with `hasher`.
+pub fn verify_inclusion_proofs_with_hasher<T: Copy + Serialize>(hasher: &NodeHasher, head: &HeadCommitment, nodes: &[Node<T>], proof: &[Node<T>]) -> bool {
+    // Every node by height, with its digest computed once
+    let mut by_height = BTreeMap::new();
+    for node in proof.iter().chain(nodes) {
+        let digest = node.digest_with_hasher(hasher);
+        match by_height.insert(node.height, (node, digest)) {
+            Some((_, other)) if other != digest => return false,
+            _ => {}
+        }
+    }
+    let Some((_, &(top, top_digest))) = by_height.last_key_value() else {
+        return false;
+    };
+    if top.height != head.height || top_digest != head.digest {
+        return false;
+    }
+    nodes.iter().all(|node| {
+        let mut current = top;
+        while current.height > node.height {
+            // Same finger as the one followed by get_inclusion_proof
+            let Some(finger) = current.fingers.keys().filter(|&&finger| finger >= node.height).min() else {
+                return false;
+            };
+            match by_height.get(finger) {
+                Some((next, digest)) if *digest == current.fingers[finger] => current = next,
+                _ => return false,
+            }
+        }
+        current.height == node.height
+    })
+}
+
+/// Same as `verify_inclusion_proof` for a value-hiding skip list, given the path returned by
+/// `get_hiding_inclusion_proof(node.height)`. `node` carries its salt.
+pub fn verify_hiding_inclusion_proof<T: Copy + Serialize>(head: &HeadCommitment, node: &Node<T>, proof: &[BlindedNode]) -> bool {
+    verify_hiding_inclusion_proof_with_hasher(&NodeHasher::UNKEYED, head, node, proof)
+}
+
+/// Same as `verify_hiding_inclusion_proof`, for a skip list hashed with `hasher`.
+pub fn verify_hiding_inclusion_proof_with_hasher<T: Copy + Serialize>(hasher: &NodeHasher, head: &HeadCommitment, node: &Node<T>, proof: &[BlindedNode]) -> bool {
+    if proof.first().map_or(node.height, |path_node| path_node.height) != head.height {
+        return false;
+    }
+    let mut expected = head.digest;
+    for path_node in proof {
+        if path_node.digest_with_hasher(hasher) != expected || path_node.height <= node.height {
+            return false;
+        }
+        let Some(finger) = path_node.fingers.keys().filter(|&&finger| finger >= node.height).min() else {
+            return false;
+        };
+        expected = path_node.fingers[finger];
+    }
+    node.salt.is_some() && node.digest_with_hasher(hasher) == expected
+}
+
+impl<T: Copy + Serialize + Display> Default for SkipList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Serialize + Display> SkipList<T> {
+    pub fn new() -> SkipList<T> {
+        SkipList {
+            nodes: Vec::new(),
+            hiding: false,
+            schedule: FingerSchedule::default(),
+            hasher: NodeHasher::UNKEYED,
+            observers: Observers::default(),
+        }
+    }
+
+    /// An empty skip list whose nodes get fingers according to `schedule`.
+    pub fn with_schedule(schedule: FingerSchedule) -> SkipList<T> {
+        SkipList {
+            nodes: Vec::new(),
+            hiding: false,
+            schedule,
+            hasher: NodeHasher::UNKEYED,
+            observers: Observers::default(),
+        }
+    }
+
+    /// An empty skip list whose nodes and value commitments are hashed with `hasher` instead of
+    /// plain Blake2b256, e.g. keyed. Proofs are verified with the `_with_hasher` functions.
+    pub fn with_hasher(hasher: NodeHasher) -> SkipList<T> {
+        SkipList {
+            nodes: Vec::new(),
+            hiding: false,
+            schedule: FingerSchedule::default(),
+            hasher,
+            observers: Observers::default(),
+        }
+    }
+
+    /// A skip list whose nodes commit to H(salt || value) with a fresh random salt per node, so
+    /// that publishing the head digest doesn't expose values to dictionary attacks. Salts are
+    /// only revealed for the nodes being proven.
+    pub fn new_hiding() -> SkipList<T> {
+        SkipList {
+            nodes: Vec::new(),
+            hiding: true,
+            schedule: FingerSchedule::default(),
+            hasher: NodeHasher::UNKEYED,
+            observers: Observers::default(),
+        }
+    }
+
+    // Add a new value to the skip list.
+    pub fn add(&mut self, value: T) {
+        let salt = self.hiding.then(fresh_salt);
+        self.push(value, salt);
+    }
+
+    /// Add a value with the given salt to a value-hiding skip list, e.g. to derive salts from a
+    /// secret instead of drawing them at random.
+    pub fn add_with_salt(&mut self, value: T, salt: [u8; SALT_LENGTH]) {
+        assert!(self.hiding, "Only value-hiding skip lists take salts");
+        self.push(value, Some(salt));
+    }
+
+    fn push(&mut self, value: T, salt: Option<[u8; SALT_LENGTH]>) {
+        let mut new_node = match self.nodes.last() {
+            Some(node) => {
+                node.next_with_hasher(value, &self.schedule, &self.hasher)
+            },
+            None => { // nodes.len() == 0
+                Node::<T>::first(value)
+            }
+        };
+        new_node.salt = salt;
+        if !self.observers.0.is_empty() {
+            let head = new_node.head_commitment_with_hasher(&self.hasher);
+            let leaf_hash = new_node.value_commitment_with_hasher(&self.hasher);
+            for observer in &mut self.observers.0 {
+                observer.on_append(new_node.height - 1, &leaf_hash, &head);
+            }
+        }
+        self.nodes.push(new_node);
+    }
+
+    /// Call `observer` for every node added from now on, with index `height - 1`, the node's value
+    /// commitment as leaf hash and the new head.
+    pub fn add_observer(&mut self, observer: impl AppendObserver<HeadCommitment> + Send + Sync + 'static) {
+        self.observers.0.push(Box::new(observer));
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.0.clear();
+    }
+
+    /// Commitment to the current state, which inclusion proofs are checked against. None while
+    /// the skip list is empty.
+    pub fn head(&self) -> Option<HeadCommitment> {
+        self.nodes.last().map(|node| node.head_commitment_with_hasher(&self.hasher))
+    }
+
+    /// The head commitment, `HeadCommitment::EMPTY` for an empty list.
+    pub fn commitment(&self) -> HeadCommitment {
+        self.head().unwrap_or(HeadCommitment::EMPTY)
+    }
+
+    /// Get an inclusion proof for the node at height h w.r.t the latest head
+    pub fn get_inclusion_proof(&self, h: u64) -> Vec<Node<T> > {
+        self.get_path_heights(h)
+            .into_iter()
+            .map(|height| self.nodes[height as usize - 1].clone()) // -1 because height is 1-indexed
+            .collect()
+    }
+
+    /// Proof of the node at height h w.r.t the latest head, carrying the node and its path
+    pub fn prove(&self, h: u64) -> SkipListProof<T> {
+        SkipListProof {
+            node: self.nodes[h as usize - 1].clone(),
+            path: self.get_inclusion_proof(h),
+        }
+    }
+
+    /// Get a single proof for the nodes at all the given heights w.r.t the latest head. The paths
+    /// to the heights share most of their nodes, which are included once, from the head down.
+    /// Nodes at the given heights aren't included, as with `get_inclusion_proof`.
+    pub fn get_inclusion_proofs(&self, heights: &[u64]) -> Vec<Node<T> > {
+        let mut path_heights = BTreeSet::new();
+        for &h in heights {
+            path_heights.extend(self.get_path_heights(h));
+        }
+        for h in heights {
+            path_heights.remove(h);
+        }
+        path_heights
+            .into_iter()
+            .rev()
+            .map(|h| self.nodes[h as usize - 1].clone())
+            .collect()
+    }
+
+    // Heights of the nodes on the path from the head down to height h, excluding h
+    fn get_path_heights(&self, h: u64) -> Vec<u64> {
+        assert!(h <= self.nodes.len() as u64);
+
+        let mut heights = Vec::new();
+        let mut cur_node = self.nodes.last().expect("One node must exist");
+        while cur_node.height > h {
+            heights.push(cur_node.height);
+
+            let closest_finger = cur_node
+                .fingers
+                .keys()
+                .filter(|&&finger| finger >= h)
+                .min_by_key(|&&finger| finger - h)
+                .expect("At least one finger must be found");
+
+            cur_node = &self.nodes[*closest_finger as usize - 1]; // -1 because height is 1-indexed
+        }
+
+        if cur_node.height < h {
+            panic!("Should not happen")
+        }
+
+        heights
+    }
+
+    /// Get an inclusion proof for the node at height h that doesn't reveal the values (or salts)
+    /// of the nodes on the path. The node itself, salt included, is `self.nodes[h - 1]`.
+    pub fn get_hiding_inclusion_proof(&self, h: u64) -> Vec<BlindedNode> {
+        self.get_inclusion_proof(h).iter().map(|node| node.blind_with_hasher(&self.hasher)).collect()
+    }
+
+    /// Memory used by the nodes. Values are stored inline in the nodes, so they are split out of
+    /// the nodes' size.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let values = self.nodes.len() * size_of::<T>();
+        MemoryUsage {
+            nodes: self.nodes.capacity() * size_of::<Node<T>>() - values,
+            values,
+            auxiliary: self.nodes.iter().map(|node| node.fingers_memory_usage()).sum(),
+        }
+    }
+
+    /// Write all nodes to a node store, at their heights.
+    pub fn write_to<S: NodeStore<Node<T>>>(&self, store: &mut S) -> Result<(), S::Error> {
+        let batch = self.nodes
+            .iter()
+            .map(|node| BatchOp::Put(node.height, node.clone()))
+            .collect();
+        store.write_batch(batch)
+    }
+
+    /// Read back the nodes written by `write_to`, i.e., heights 1, 2, ... up to the first missing one.
+    pub fn read_from<S: NodeStore<Node<T>>>(store: &S) -> Result<SkipList<T>, S::Error> {
+        Self::read_from_with_schedule(store, FingerSchedule::default())
+    }
+
+    /// Same as `read_from` for a skip list built with another finger schedule. The schedule isn't
+    /// stored, and adding to a skip list with the wrong one panics. Neither is the hasher: set
+    /// `hasher` on the result if the skip list wasn't hashed with plain Blake2b256.
+    pub fn read_from_with_schedule<S: NodeStore<Node<T>>>(store: &S, schedule: FingerSchedule) -> Result<SkipList<T>, S::Error> {
+        let mut nodes = Vec::new();
+        while let Some(node) = store.get(nodes.len() as u64 + 1)? {
+            nodes.push(node);
+        }
+        // Only value-hiding skip lists have salted nodes
+        let hiding = nodes.first().is_some_and(|node| node.salt.is_some());
+        Ok(SkipList { nodes, hiding, schedule, hasher: NodeHasher::UNKEYED, observers: Observers::default() })
+    }
+
+    /// Print finger indices w/o the digests
+    pub fn short_print(&self) {
+        for (i, node) in self.nodes.iter().enumerate() {
+            println!("Node {}: Value: {}, Height: {}", i, node.value, node.height);
+            if node.fingers.is_empty() {
+                println!("  Fingers: None");
+            } else {
+                let mut finger_indices: Vec<u64> = node.fingers.keys().cloned().collect();
+                finger_indices.sort();
+                println!("  Fingers: {:?}", finger_indices);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_cast)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_calculate_finger_indices() {
+        assert_eq!(calculate_finger_indices(2, 10), vec![1]);
+
+        assert_eq!(calculate_finger_indices(10001, 10), vec![10000]);
+        assert_eq!(calculate_finger_indices(10000, 10), vec![9999, 9990, 9900, 9000]);
+
+        assert_eq!(calculate_finger_indices(5346, 10), vec![5345, 5340, 5300, 5000]);
+        assert_eq!(calculate_finger_indices(5340, 10), vec![5339, 5330, 5300, 5000]);
+        assert_eq!(calculate_finger_indices(5300, 10), vec![5299, 5290, 5200, 5000]);
+        assert_eq!(calculate_finger_indices(5000, 10), vec![4999, 4990, 4900, 4000]);
+
+        assert_eq!(calculate_finger_indices(5341, 10), vec![5340, 5300, 5000]);
+        assert_eq!(calculate_finger_indices(15, 2), vec![14, 12, 8]);
+    }
+
+    // pub fn kostas_pruning() {
+    //     assert_eq!(calculate_finger_indices(5346, 10), vec![5345, 5340]);
+    //     assert_eq!(calculate_finger_indices(5340, 10), vec![5339, 5330, 5300]);
+    //     assert_eq!(calculate_finger_indices(5300, 10), vec![5299, 5290, 5200, 5000]);
+    //     assert_eq!(calculate_finger_indices(5000, 10), vec![4999, 4990, 4900, 4000]);
+    // }
+
+    #[test]
+    pub fn test_skip_list_add() {
+        let mut skip_list = SkipList::<u64>::new();
+        let num_elements = 250;
+        for i in 0..num_elements {
+            skip_list.add(i);
+        }
+        // println!("Skip List: {:?}", skip_list.nodes);
+        skip_list.short_print();
+
+        assert_eq!(skip_list.nodes.len(), num_elements as usize);
+        // Check the values
+        for i in 0..num_elements {
+            assert_eq!(skip_list.nodes[i as usize].value, i);
+            assert_eq!(skip_list.nodes[i as usize].height, (i + 1) as u64);
+        }
+
+        // Elements with zero fingers
+        let first_fingers = &skip_list.nodes[0].fingers;
+        assert!(first_fingers.is_empty(), "First node should have no fingers");
+
+
+        // Elements with one finger
+        let mut prev_digest = skip_list.nodes[0].digest().0;
+        for i in 1..11 {
+            let node = &skip_list.nodes[i as usize];
+            let fingers = &node.fingers;
+            assert_eq!(fingers.len(), 1, "Node at index {} should have one finger", i);
+            assert!(fingers.contains_key(&(i as u64)), "Node at index {} should have a finger at index {}", i, i);
+            assert_eq!(fingers.get(&(i as u64)).unwrap().0, prev_digest, "Finger at index {} should point to previous node's digest", i);
+            prev_digest = node.digest().0; // Update the digest for the next iteration
+        }
+
+        // Check the fingers of node at index 12
+        let node_12 = &skip_list.nodes[12];
+        let fingers_12 = &node_12.fingers;
+        assert_eq!(fingers_12.len(), 2, "Node at index 12 should have two fingers");
+        assert!(fingers_12.contains_key(&12), "Node at index 12 should have a finger at index 11");
+        assert!(fingers_12.contains_key(&10), "Node at index 12 should have a finger at index 10");
+
+        // Check the fingers of node at index 200
+        let node_200 = &skip_list.nodes[200];
+        let fingers_200 = &node_200.fingers;
+        assert_eq!(fingers_200.len(), 1, "Node at index 200 should have one finger");
+        assert!(fingers_200.contains_key(&200), "Node at index 200 should have a finger at index 200");
+    }
+
+    #[test]
+    pub fn test_skip_list_inclusion() {
+        let mut skip_list = SkipList::<u64>::new();
+        for i in 1..1000 {
+            skip_list.add(i);
+        }
+
+        let proof = skip_list.get_inclusion_proof(345);
+        for node in &proof {
+            println!("Node Height: {}, Value: {}", node.height, node.value);
+        }
+        let head = skip_list.head().unwrap();
+        assert!(verify_inclusion_proof(&head, &skip_list.nodes[344], &proof));
+        assert!(!verify_inclusion_proof(&head, &skip_list.nodes[343], &proof));
+        assert!(!verify_inclusion_proof(&skip_list.nodes[500].head_commitment(), &skip_list.nodes[344], &proof));
+        assert_eq!(head.height, 999);
+        assert_eq!(head.to_string(), format!("999:{}", head.digest));
+        assert_eq!(bcs::from_bytes::<HeadCommitment>(&to_bytes(&head).unwrap()).unwrap(), head);
+        let wrong_height = HeadCommitment { height: 998, ..head };
+        assert!(!verify_inclusion_proof(&wrong_height, &skip_list.nodes[344], &proof));
+        let mut tampered = proof.clone();
+        tampered[1].value += 1;
+        assert!(!verify_inclusion_proof(&head, &skip_list.nodes[344], &tampered));
+        // The head itself needs no path
+        assert!(verify_inclusion_proof(&head, skip_list.nodes.last().unwrap(), &[]));
+
+        // The same through the Proof trait
+        let proof = skip_list.prove(345);
+        assert_eq!(Proof::verify(&proof, &head, &()), Ok(()));
+        assert_eq!(proof.size_hint(), to_bytes(&proof).unwrap().len());
+        let older = skip_list.nodes[997].head_commitment();
+        assert_eq!(Proof::verify(&proof, &older, &()), Err(VerificationError::RootMismatch));
+        let mut hiding = SkipList::<u64>::new_hiding();
+        hiding.add(1);
+        assert_eq!(hiding.prove(1).size_hint(), to_bytes(&hiding.prove(1)).unwrap().len());
+    }
+
+    #[test]
+    pub fn test_digest_hex() {
+        let mut skip_list = SkipList::<u64>::new();
+        skip_list.add(1);
+        let digest = skip_list.nodes[0].digest();
+        assert_eq!(digest.to_string().len(), 64);
+        assert_eq!(digest.to_string().parse::<Digest>(), Ok(digest));
+        assert!("xyz".parse::<Digest>().is_err());
+    }
+
+    #[test]
+    pub fn test_memory_usage() {
+        let mut skip_list = SkipList::<u64>::new();
+        assert_eq!(skip_list.memory_usage(), MemoryUsage::default());
+
+        for i in 1..1000 {
+            skip_list.add(i);
+        }
+        let usage = skip_list.memory_usage();
+        assert_eq!(usage.values, 999 * 8);
+        assert!(usage.nodes >= 999 * (size_of::<Node<u64>>() - 8));
+        // Every node but the first has at least one finger
+        assert!(usage.auxiliary >= 998 * (size_of::<(u64, Digest)>() + 1));
+        assert_eq!(usage.total(), usage.nodes + usage.values + usage.auxiliary);
+    }
+
+    #[test]
+    pub fn test_skip_list_store() {
+        let mut skip_list = SkipList::<u64>::new();
+        for i in 1..200 {
+            skip_list.add(i);
+        }
+        let mut store = ads_store::MemoryStore::new();
+        skip_list.write_to(&mut store).unwrap();
+        assert_eq!(store.len(), 200); // Position 0 is unused as heights start at 1
+
+        let mut restored = SkipList::<u64>::read_from(&store).unwrap();
+        assert_eq!(restored.nodes.len(), 199);
+        for (a, b) in restored.nodes.iter().zip(&skip_list.nodes) {
+            assert_eq!(a.digest().0, b.digest().0);
+        }
+
+        // The restored list keeps growing the same way
+        restored.add(200);
+        skip_list.add(200);
+        assert_eq!(restored.nodes.last().unwrap().digest().0, skip_list.nodes.last().unwrap().digest().0);
+    }
+
+    #[test]
+    pub fn test_batched_inclusion() {
+        let mut skip_list = SkipList::<u64>::new();
+        for i in 1..1000 {
+            skip_list.add(i);
+        }
+        let head = skip_list.head().unwrap();
+        let heights = [345, 346, 350, 400, 999];
+        let nodes: Vec<_> = heights.iter().map(|&h| skip_list.nodes[h as usize - 1].clone()).collect();
+        let proof = skip_list.get_inclusion_proofs(&heights);
+        assert!(verify_inclusion_proofs(&head, &nodes, &proof));
+
+        // Shared nodes are included once, and proven heights not at all
+        let separate: usize = heights.iter().map(|&h| skip_list.get_inclusion_proof(h).len()).sum();
+        assert!(proof.len() < separate);
+        assert!(proof.windows(2).all(|pair| pair[0].height > pair[1].height));
+        assert!(proof.iter().all(|node| !heights.contains(&node.height)));
+
+        // A single height gives the single proof
+        let single = skip_list.get_inclusion_proofs(&[345]);
+        let expected = skip_list.get_inclusion_proof(345);
+        assert_eq!(single.iter().map(Node::digest).collect::<Vec<_>>(), expected.iter().map(Node::digest).collect::<Vec<_>>());
+
+        let mut wrong = nodes.clone();
+        wrong[2].value += 1;
+        assert!(!verify_inclusion_proofs(&head, &wrong, &proof));
+        let mut tampered = proof.clone();
+        tampered[1].value += 1;
+        assert!(!verify_inclusion_proofs(&head, &nodes, &tampered));
+        assert!(!verify_inclusion_proofs(&head, &nodes, &proof[1..]));
+        let older_head = skip_list.nodes[997].head_commitment();
+        assert!(!verify_inclusion_proofs(&older_head, &nodes[..4], &proof));
+        // A node can't be slipped in next to its proven twin
+        let mut duplicated = proof.clone();
+        duplicated.push(wrong[2].clone());
+        assert!(!verify_inclusion_proofs(&head, &nodes, &duplicated));
+    }
+
+    #[test]
+    pub fn test_hybrid_schedule() {
+        let schedule = FingerSchedule::Hybrid { recent_base: 2, recent_heights: 64, base: 10 };
+        assert_eq!(schedule.finger_indices(5346), vec![5345, 5344, 5340, 5312, 5300, 5000]);
+        assert_eq!(schedule.finger_indices(2), vec![1]);
+        assert_eq!(FingerSchedule::default().finger_indices(5346), calculate_finger_indices(5346, 10));
+
+        let mut decimal = SkipList::<u64>::new();
+        let mut hybrid = SkipList::<u64>::with_schedule(schedule);
+        for i in 1..5000 {
+            decimal.add(i);
+            hybrid.add(i);
+        }
+        let head = hybrid.head().unwrap();
+        let proof_len = |skip_list: &SkipList<u64>, heights: std::ops::RangeInclusive<u64>| -> usize {
+            heights.map(|h| skip_list.get_inclusion_proof(h).len()).sum()
+        };
+        // Recent nodes have shorter proofs, old ones about as short
+        assert!(proof_len(&hybrid, 4936..=4999) < proof_len(&decimal, 4936..=4999));
+        assert!(proof_len(&hybrid, 1..=1000) <= proof_len(&decimal, 1..=1000));
+        for h in [1, 17, 2500, 4990, 4998] {
+            let proof = hybrid.get_inclusion_proof(h);
+            assert!(verify_inclusion_proof(&head, &hybrid.nodes[h as usize - 1], &proof));
+        }
+        let fingers = |skip_list: &SkipList<u64>| -> usize { skip_list.nodes.iter().map(|node| node.fingers.len()).sum() };
+        assert!(fingers(&hybrid) < 3 * fingers(&decimal));
+
+        // The schedule carries over to stores
+        let mut store = ads_store::MemoryStore::new();
+        hybrid.write_to(&mut store).unwrap();
+        let mut restored = SkipList::<u64>::read_from_with_schedule(&store, schedule).unwrap();
+        restored.add(5000);
+        hybrid.add(5000);
+        assert_eq!(restored.head(), hybrid.head());
+        let mut stored = crate::store::StoredSkipList::<u64, _>::open(store).unwrap().with_schedule(schedule);
+        stored.add(5000).unwrap();
+        assert_eq!(stored.head(), hybrid.head());
+    }
+
+    #[test]
+    pub fn test_observers() {
+        use std::sync::{Arc, Mutex};
+
+        let mut skip_list = SkipList::<u64>::new();
+        skip_list.add(1);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        skip_list.add_observer(move |index, leaf_hash: &Digest, head: &HeadCommitment| {
+            log.lock().unwrap().push((index, *leaf_hash, *head));
+        });
+        for i in 2..=20 {
+            skip_list.add(i);
+        }
+        let seen = std::mem::take(&mut *seen.lock().unwrap());
+        assert_eq!(seen.len(), 19);
+        for (i, (index, leaf_hash, head)) in seen.into_iter().enumerate() {
+            let node = &skip_list.nodes[i + 1];
+            assert_eq!(index, i as u64 + 1);
+            assert_eq!(leaf_hash, node.value_commitment());
+            assert_eq!(head, node.head_commitment());
+        }
+        skip_list.clear_observers();
+        skip_list.add(21);
+        assert!(format!("{:?}", skip_list).contains("0 observers"));
+    }
+
+    #[test]
+    pub fn test_stored_skip_list() {
+        use crate::store::{StoreError, StoredSkipList};
+
+        let mut skip_list = SkipList::<u64>::new();
+        let mut stored = StoredSkipList::in_memory();
+        for i in 1..500 {
+            skip_list.add(i);
+            stored.add(i).unwrap();
+        }
+        assert_eq!(stored.len(), 499);
+        assert_eq!(stored.head(), skip_list.head());
+        for height in [1, 2, 10, 345, 498, 499] {
+            assert_eq!(stored.get_node(height).unwrap().digest(), skip_list.nodes[height as usize - 1].digest());
+            let proof = stored.get_inclusion_proof(height).unwrap();
+            let expected = skip_list.get_inclusion_proof(height);
+            assert_eq!(proof.iter().map(Node::digest).collect::<Vec<_>>(), expected.iter().map(Node::digest).collect::<Vec<_>>());
+        }
+
+        // Only the head is kept when moving a list to a store, and reopening finds it again
+        let moved = StoredSkipList::from_skip_list(skip_list, ads_store::MemoryStore::new()).unwrap();
+        let reopened = StoredSkipList::<u64, _>::open(moved.into_store()).unwrap();
+        assert_eq!(reopened.head(), stored.head());
+        assert_eq!(reopened.get_node(345).unwrap().value, 345);
+
+        // Altered or missing historical nodes are detected on the way down
+        let mut store = stored.into_store();
+        let mut node = store.get(450).unwrap().unwrap();
+        node.value += 1;
+        store.put(450, node).unwrap();
+        store.delete(300).unwrap();
+        let stored = StoredSkipList::<u64, _>::open(store).unwrap();
+        assert_eq!(stored.get_node(345).unwrap().value, 345);
+        assert_eq!(stored.get_node(450).unwrap_err(), StoreError::CorruptNode(450));
+        assert_eq!(stored.get_node(449).unwrap_err(), StoreError::CorruptNode(450));
+        assert_eq!(stored.get_node(300).unwrap_err(), StoreError::MissingNode(300));
+
+        let empty = StoredSkipList::<u64, _>::open(ads_store::MemoryStore::new()).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.head(), None);
+    }
+
+    #[test]
+    pub fn test_empty_commitment() {
+        let mut skip_list = SkipList::<u64>::new();
+        assert_eq!(skip_list.head(), None);
+        assert_eq!(skip_list.commitment(), HeadCommitment::EMPTY);
+        assert!(skip_list.commitment().is_empty());
+        assert_eq!(HeadCommitment::EMPTY.digest, EMPTY_ROOT);
+
+        // No node is included in the empty list
+        skip_list.add(1);
+        let node = skip_list.nodes[0].clone();
+        assert!(verify_inclusion_proof(&skip_list.commitment(), &node, &[]));
+        assert!(!verify_inclusion_proof(&HeadCommitment::EMPTY, &node, &[]));
+        assert!(!skip_list.commitment().is_empty());
+    }
+
+    #[test]
+    pub fn test_keyed_skip_list() {
+        let hasher = NodeHasher::keyed([3; 32]);
+        let mut plain = SkipList::<u64>::new();
+        let mut keyed = SkipList::<u64>::with_hasher(hasher);
+        let mut stored = store::StoredSkipList::in_memory().with_hasher(hasher);
+        for i in 1..150 {
+            plain.add(i);
+            keyed.add(i);
+            stored.add(i).unwrap();
+        }
+        assert_ne!(keyed.head(), plain.head());
+        assert_eq!(stored.head(), keyed.head());
+
+        let head = keyed.head().unwrap();
+        let node = &keyed.nodes[66];
+        let proof = keyed.get_inclusion_proof(67);
+        assert!(verify_inclusion_proof_with_hasher(&hasher, &head, node, &proof));
+        assert!(!verify_inclusion_proof(&head, node, &proof));
+        assert!(verify_inclusion_proofs_with_hasher(&hasher, &head, std::slice::from_ref(node), &keyed.get_inclusion_proofs(&[67])));
+        assert_eq!(stored.get_inclusion_proof(67).unwrap().len(), proof.len());
+
+        // Nodes fetched from the store are checked with the hasher they were written with
+        let unkeyed = store::StoredSkipList::<u64>::open(stored.into_store()).unwrap();
+        assert!(unkeyed.get_node(67).is_err());
+    }
+
+    #[test]
+    pub fn test_value_hiding() {
+        let mut plain = SkipList::<u64>::new();
+        let mut hiding = SkipList::<u64>::new_hiding();
+        let mut seeded = SkipList::<u64>::new_hiding();
+        for i in 1..200 {
+            plain.add(i);
+            hiding.add(i);
+            seeded.add_with_salt(i, [i as u8; SALT_LENGTH]);
+        }
+        // Salts are fresh for each node, and change every digest
+        assert!(hiding.nodes.iter().all(|node| node.salt.is_some()));
+        assert_ne!(hiding.nodes[0].salt, hiding.nodes[1].salt);
+        assert_ne!(hiding.head(), plain.head());
+        assert_ne!(hiding.nodes[0].value_commitment(), seeded.nodes[0].value_commitment());
+
+        for skip_list in [&hiding, &seeded] {
+            let head = skip_list.head().unwrap();
+            let node = &skip_list.nodes[44];
+            let proof = skip_list.get_hiding_inclusion_proof(45);
+            assert!(!proof.is_empty());
+            for (blinded, path_node) in proof.iter().zip(skip_list.get_inclusion_proof(45)) {
+                assert_eq!(blinded.digest(), path_node.digest());
+            }
+            assert!(verify_hiding_inclusion_proof(&head, node, &proof));
+
+            // The value and salt of the proven node must both be right
+            let mut wrong = node.clone();
+            wrong.value += 1;
+            assert!(!verify_hiding_inclusion_proof(&head, &wrong, &proof));
+            let mut wrong = node.clone();
+            wrong.salt.as_mut().unwrap()[0] ^= 1;
+            assert!(!verify_hiding_inclusion_proof(&head, &wrong, &proof));
+            let mut tampered = proof.clone();
+            tampered[0].value_commitment = skip_list.nodes[0].value_commitment();
+            assert!(!verify_hiding_inclusion_proof(&head, node, &tampered));
+        }
+
+        // Salted nodes round-trip through a store
+        let mut store = ads_store::MemoryStore::new();
+        seeded.write_to(&mut store).unwrap();
+        let mut restored = SkipList::<u64>::read_from(&store).unwrap();
+        assert!(restored.hiding);
+        restored.add_with_salt(200, [0; SALT_LENGTH]);
+        seeded.add_with_salt(200, [0; SALT_LENGTH]);
+        assert_eq!(restored.head(), seeded.head());
+    }
+
+    #[cfg(feature = "testing")]
+    proptest::proptest! {
+        #[test]
+        fn test_generated_proofs(
+            (skip_list, height, proof) in testing::inclusion_proof(300),
+            (tampered_list, tampered_height, tampered) in testing::tampered_inclusion_proof(300),
+        ) {
+            // Each node of a path is the head or pointed to by a finger of the node before it
+            let links = |skip_list: &SkipList<u64>, height: u64, proof: &[Node<u64>]| {
+                let mut expected = skip_list.nodes.last().unwrap().digest().0;
+                for node in proof {
+                    if node.digest().0 != expected {
+                        return false;
+                    }
+                    let finger = node.fingers.keys().filter(|&&f| f >= height).min().unwrap();
+                    expected = node.fingers[finger].0;
+                }
+                expected == skip_list.nodes[height as usize - 1].digest().0
+            };
+            proptest::prop_assert!(links(&skip_list, height, &proof));
+            proptest::prop_assert!(!links(&tampered_list, tampered_height, &tampered));
+            let head = skip_list.head().unwrap();
+            proptest::prop_assert!(verify_inclusion_proof(&head, &skip_list.nodes[height as usize - 1], &proof));
+            let head = tampered_list.head().unwrap();
+            let node = &tampered_list.nodes[tampered_height as usize - 1];
+            proptest::prop_assert!(!verify_inclusion_proof(&head, node, &tampered));
+        }
+    }
+}
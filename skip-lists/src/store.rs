@@ -0,0 +1,208 @@
+//! Skip lists kept in a `NodeStore`, with only the head in memory.
+//!
+//! Nodes are stored at their heights, as written by `SkipList::write_to`. Every other node is
+//! evicted once it's written, and reads fetch nodes back by following fingers down from the head:
+//! each fetched node is checked against the digest in the finger that references it, so a store
+//! can't substitute a historical node without being noticed.
+
+use std::fmt;
+
+use ads_core::NodeHasher;
+use ads_store::{BatchOp, MemoryStore, NodeStore};
+use serde::Serialize;
+
+use crate::{FingerSchedule, HeadCommitment, Node, SkipList, fresh_salt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreError<E> {
+    /// The backend failed
+    Backend(E),
+    /// A node below the head isn't in the store
+    MissingNode(u64),
+    /// A stored node doesn't match the finger referencing it
+    CorruptNode(u64),
+}
+
+impl<E: fmt::Display> fmt::Display for StoreError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Backend(e) => write!(f, "Storage backend error: {}", e),
+            StoreError::MissingNode(height) => write!(f, "Node at height {} is missing", height),
+            StoreError::CorruptNode(height) => write!(f, "Node at height {} is corrupt", height),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for StoreError<E> {}
+
+/// A skip list whose nodes live in a node store, except for the head.
+#[derive(Debug)]
+pub struct StoredSkipList<T, S = MemoryStore<Node<T>>> {
+    store: S,
+    head: Option<Node<T>>,
+    hiding: bool,
+    schedule: FingerSchedule,
+    hasher: NodeHasher,
+}
+
+impl<T: Copy + Serialize> StoredSkipList<T> {
+    /// An empty skip list on the heap.
+    pub fn in_memory() -> Self {
+        StoredSkipList::new(MemoryStore::new())
+    }
+
+    /// An empty value-hiding skip list on the heap.
+    pub fn in_memory_hiding() -> Self {
+        StoredSkipList::new_hiding(MemoryStore::new())
+    }
+}
+
+impl<T: Copy + Serialize, S: NodeStore<Node<T>>> StoredSkipList<T, S> {
+    /// An empty skip list on top of an empty store.
+    pub fn new(store: S) -> Self {
+        StoredSkipList { store, head: None, hiding: false, schedule: FingerSchedule::default(), hasher: NodeHasher::UNKEYED }
+    }
+
+    /// An empty value-hiding skip list on top of an empty store, see `SkipList::new_hiding`.
+    pub fn new_hiding(store: S) -> Self {
+        StoredSkipList { store, head: None, hiding: true, schedule: FingerSchedule::default(), hasher: NodeHasher::UNKEYED }
+    }
+
+    /// Open the skip list kept in `store`, loading its head only. The head is trusted, e.g. because
+    /// its digest is checked against a published one.
+    pub fn open(store: S) -> Result<Self, StoreError<S::Error>> {
+        let height = count_nodes(&store).map_err(StoreError::Backend)?;
+        let head = match height {
+            0 => None,
+            _ => Some(store.get(height).map_err(StoreError::Backend)?.ok_or(StoreError::MissingNode(height))?),
+        };
+        // Only value-hiding skip lists have salted nodes
+        let hiding = head.as_ref().is_some_and(|node| node.salt.is_some());
+        Ok(StoredSkipList { store, head, hiding, schedule: FingerSchedule::default(), hasher: NodeHasher::UNKEYED })
+    }
+
+    /// Move the nodes of an in-memory skip list to `store`, evicting all of them but the head.
+    pub fn from_skip_list(skip_list: SkipList<T>, mut store: S) -> Result<Self, StoreError<S::Error>> {
+        let batch = skip_list.nodes.iter().map(|node| BatchOp::Put(node.height, node.clone())).collect();
+        store.write_batch(batch).map_err(StoreError::Backend)?;
+        Ok(StoredSkipList {
+            store,
+            head: skip_list.nodes.last().cloned(),
+            hiding: skip_list.hiding,
+            schedule: skip_list.schedule,
+            hasher: skip_list.hasher,
+        })
+    }
+
+    /// Use `schedule` for the fingers of new nodes, which must be the schedule the existing nodes
+    /// were built with, as for `SkipList::read_from_with_schedule`.
+    pub fn with_schedule(mut self, schedule: FingerSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Hash nodes with `hasher`, which must be the hasher the existing nodes were hashed with, as
+    /// for `SkipList::with_hasher`. Nodes fetched from the store are checked with it too.
+    pub fn with_hasher(mut self, hasher: NodeHasher) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Add a new value, writing its node to the store.
+    pub fn add(&mut self, value: T) -> Result<(), StoreError<S::Error>> {
+        let mut node = match &self.head {
+            Some(head) => head.next_with_hasher(value, &self.schedule, &self.hasher),
+            None => Node::first(value),
+        };
+        if self.hiding {
+            node.salt = Some(fresh_salt());
+        }
+        self.store.put(node.height, node.clone()).map_err(StoreError::Backend)?;
+        self.head = Some(node);
+        Ok(())
+    }
+
+    /// Number of nodes, i.e., the height of the head.
+    pub fn len(&self) -> u64 {
+        self.head.as_ref().map_or(0, |head| head.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Commitment to the current state, see `SkipList::head`.
+    pub fn head(&self) -> Option<HeadCommitment> {
+        self.head.as_ref().map(|head| head.head_commitment_with_hasher(&self.hasher))
+    }
+
+    pub fn head_node(&self) -> Option<&Node<T>> {
+        self.head.as_ref()
+    }
+
+    /// Fetch the node at `height` from the store, checking every node fetched on the way down
+    /// from the head against the finger referencing it.
+    pub fn get_node(&self, height: u64) -> Result<Node<T>, StoreError<S::Error>> {
+        let mut nodes = self.walk(height)?;
+        Ok(nodes.pop().unwrap())
+    }
+
+    /// Same as `SkipList::get_inclusion_proof`, with every node fetched from the store checked.
+    pub fn get_inclusion_proof(&self, height: u64) -> Result<Vec<Node<T>>, StoreError<S::Error>> {
+        let mut nodes = self.walk(height)?;
+        nodes.pop();
+        Ok(nodes)
+    }
+
+    /// Make all previous writes durable.
+    pub fn flush(&mut self) -> Result<(), StoreError<S::Error>> {
+        self.store.flush().map_err(StoreError::Backend)
+    }
+
+    /// Consume the skip list, returning its store.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    // The nodes from the head down to the one at `height`, following the fingers chosen by
+    // `SkipList::get_inclusion_proof`
+    fn walk(&self, height: u64) -> Result<Vec<Node<T>>, StoreError<S::Error>> {
+        let head = self.head.as_ref().expect("One node must exist");
+        assert!(height >= 1 && height <= head.height, "No node at height {}", height);
+        let mut nodes = vec![head.clone()];
+        while let Some(current) = nodes.last().filter(|node| node.height > height) {
+            let finger = *current
+                .fingers
+                .keys()
+                .filter(|&&finger| finger >= height)
+                .min()
+                .expect("At least one finger must be found");
+            let next = self.store.get(finger).map_err(StoreError::Backend)?.ok_or(StoreError::MissingNode(finger))?;
+            if next.height != finger || next.digest_with_hasher(&self.hasher) != current.fingers[&finger] {
+                return Err(StoreError::CorruptNode(finger));
+            }
+            nodes.push(next);
+        }
+        Ok(nodes)
+    }
+}
+
+// Number of nodes in the store, which hold heights [1, n]
+fn count_nodes<T, S: NodeStore<Node<T>>>(store: &S) -> Result<u64, S::Error> {
+    let mut low = 0;
+    let mut high = 1;
+    while store.get(high)?.is_some() {
+        low = high;
+        high *= 2;
+    }
+    // Heights [1, low] exist, height high doesn't
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if store.get(mid)?.is_some() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
@@ -0,0 +1,46 @@
+//! Proptest strategies for skip lists and their inclusion proofs, so that crates built on top of
+//! this one can property-test their integrations without writing their own generators.
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use crate::{Node, SkipList};
+
+/// A skip list of up to `max_len` arbitrary values.
+pub fn skip_list(max_len: usize) -> impl Strategy<Value = SkipList<u64>> {
+    prop::collection::vec(any::<u64>(), 0..=max_len).prop_map(|values| build(&values))
+}
+
+/// A non-empty skip list, a height in it and the inclusion proof of that height.
+pub fn inclusion_proof(max_len: usize) -> impl Strategy<Value = (SkipList<u64>, u64, Vec<Node<u64>>)> {
+    (prop::collection::vec(any::<u64>(), 1..=max_len.max(1)), any::<Index>()).prop_map(|(values, index)| {
+        let skip_list = build(&values);
+        let height = index.index(values.len()) as u64 + 1;
+        let proof = skip_list.get_inclusion_proof(height);
+        (skip_list, height, proof)
+    })
+}
+
+/// Same as `inclusion_proof` for a height below the head, with the value of one node on the path
+/// altered, so that its digest matches neither the head nor the finger pointing to it.
+pub fn tampered_inclusion_proof(max_len: usize) -> impl Strategy<Value = (SkipList<u64>, u64, Vec<Node<u64>>)> {
+    (prop::collection::vec(any::<u64>(), 2..=max_len.max(2)), any::<Index>(), any::<Index>()).prop_map(
+        |(values, index, node)| {
+            let skip_list = build(&values);
+            // The head is excluded, its proof would be empty
+            let height = index.index(values.len() - 1) as u64 + 1;
+            let mut proof = skip_list.get_inclusion_proof(height);
+            let node = node.get_mut(&mut proof);
+            node.value = node.value.wrapping_add(1);
+            (skip_list, height, proof)
+        },
+    )
+}
+
+fn build(values: &[u64]) -> SkipList<u64> {
+    let mut skip_list = SkipList::new();
+    for &value in values {
+        skip_list.add(value);
+    }
+    skip_list
+}